@@ -0,0 +1,79 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/* The C API of the `ffi` feature (see src/ffi.rs). */
+
+#ifndef R_JSON_EVENT_PARSER_H
+#define R_JSON_EVENT_PARSER_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum {
+    JEP_BEGIN_FILE = 0,
+    JEP_END_FILE = 1,
+    JEP_BEGIN_OBJECT = 2,
+    JEP_END_OBJECT = 3,
+    JEP_BEGIN_ARRAY = 4,
+    JEP_END_ARRAY = 5,
+    JEP_KEY = 6,
+    JEP_BOOLEAN = 7,
+    JEP_NULL = 8,
+    JEP_STRING = 9,
+    JEP_BYTES = 10,
+    JEP_INT = 11,
+    JEP_FLOAT = 12,
+    JEP_STRING_START = 13,
+    JEP_STRING_CHUNK = 14,
+    JEP_STRING_END = 15
+} jep_token_type;
+
+typedef enum {
+    JEP_ERROR_NONE = 0,
+    JEP_ERROR_PARSE = 1,
+    JEP_ERROR_STOPPED = 2,
+    JEP_ERROR_NO_CALLBACK = 3
+} jep_error_kind;
+
+typedef struct jep_parser jep_parser;
+
+/* `data` is not NUL-terminated and is only valid during the call. Return non-zero to stop. */
+typedef int (*jep_callback)(void *user_data, jep_token_type token_type, const char *data, size_t len);
+
+jep_parser *jep_parser_new(int strict);
+void jep_parser_set_callback(jep_parser *parser, jep_callback callback, void *user_data);
+int jep_parser_feed(jep_parser *parser, const unsigned char *data, size_t len);
+int jep_parser_finish(jep_parser *parser);
+jep_error_kind jep_parser_error_kind(const jep_parser *parser);
+const char *jep_parser_error_message(const jep_parser *parser);
+size_t jep_parser_error_line(const jep_parser *parser);
+size_t jep_parser_error_column(const jep_parser *parser);
+size_t jep_parser_byte_offset(const jep_parser *parser);
+void jep_parser_free(jep_parser *parser);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,147 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Convert many files with a binary: find the files of a directory or of a glob pattern, name
+//! the outputs after the inputs and run the conversions on several threads.
+
+use std::{fs, io};
+use std::path::{Path, PathBuf};
+
+use crate::parallel::map_parallel;
+
+/// The extensions of the files of a directory that are converted: the JSON files, compressed
+/// or not.
+const JSON_EXTENSIONS: [&str; 3] = [".json", ".json.gz", ".json.zst"];
+
+/// true if the path contains a wildcard: `*` or `?`.
+pub fn is_glob(path: &str) -> bool {
+    path.contains(['*', '?'])
+}
+
+/// Match a name against a pattern where `*` matches any sequence of chars and `?` one char.
+fn match_component(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|i| match_component(rest, &name[i..])),
+        Some(('?', rest)) => !name.is_empty() && match_component(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && match_component(rest, &name[1..]),
+    }
+}
+
+fn match_components(pattern: &[&str], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((&"**", rest)) => (0..=path.len()).any(|i| match_components(rest, &path[i..])),
+        Some((p, rest)) => match path.split_first() {
+            Some((name, path_rest)) => {
+                let p: Vec<char> = p.chars().collect();
+                let name: Vec<char> = name.chars().collect();
+                match_component(&p, &name) && match_components(rest, path_rest)
+            }
+            None => false,
+        },
+    }
+}
+
+/// Match a `/` separated path against a glob pattern: `*` matches any sequence of chars but
+/// `/`, `?` one char but `/`, and a `**` component any number of components.
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<&str> = pattern.split('/').collect();
+    let path: Vec<&str> = path.split('/').collect();
+    match_components(&pattern, &path)
+}
+
+/// The files under `dir`, up to `max_depth` levels of directories (no limit if None). The paths
+/// start with `prefix`.
+fn walk(dir: &Path, prefix: &str, max_depth: Option<usize>, files: &mut Vec<String>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let path = format!("{}{}", prefix, name);
+        if entry.file_type()?.is_dir() {
+            if max_depth != Some(0) {
+                walk(&entry.path(), &format!("{}/", path), max_depth.map(|d| d - 1), files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// The files to convert, sorted. If `pattern` is a directory, the JSON files (`.json`,
+/// `.json.gz` or `.json.zst`) of the directory, and of its subdirectories if `recursive` is
+/// true. Otherwise, the files that match the glob pattern (see `glob_match`).
+pub fn find_files(pattern: &str, recursive: bool) -> io::Result<Vec<PathBuf>> {
+    let mut files = vec!();
+    if Path::new(pattern).is_dir() {
+        let prefix = format!("{}/", pattern.trim_end_matches('/'));
+        walk(Path::new(pattern), &prefix, if recursive { None } else { Some(0) }, &mut files)?;
+        files.retain(|f| JSON_EXTENSIONS.iter().any(|e| f.ends_with(e)));
+    } else {
+        let components: Vec<&str> = pattern.split('/').collect();
+        let literal = components.iter().take_while(|c| !is_glob(c)).count().min(components.len() - 1);
+        let (base, prefix) = match components[..literal].join("/") {
+            base if base.is_empty() && literal > 0 => ("/".to_string(), "/".to_string()),
+            base if base.is_empty() => (".".to_string(), String::new()),
+            base => (base.clone(), format!("{}/", base)),
+        };
+        let max_depth = if components.contains(&"**") { None } else { Some(components.len() - literal - 1) };
+        if Path::new(&base).is_dir() {
+            walk(Path::new(&base), &prefix, max_depth, &mut files)?;
+        }
+        files.retain(|f| glob_match(pattern, f));
+    }
+    files.sort();
+    Ok(files.into_iter().map(PathBuf::from).collect())
+}
+
+/// The path of the output of a conversion, next to the input: the extension of the input, and
+/// the `.gz` or `.zst` extension of a compressed input, are replaced by `extension`.
+pub fn sibling_path(input: &Path, extension: &str) -> PathBuf {
+    let input = match input.extension().and_then(|e| e.to_str()) {
+        Some("gz") | Some("zst") => input.with_extension(""),
+        _ => input.to_path_buf(),
+    };
+    input.with_extension(extension)
+}
+
+/// The result of a batch of conversions.
+#[derive(Debug, PartialEq, Default)]
+pub struct BatchSummary {
+    pub converted: usize,
+    /// The failed files and the error messages, in the order of the files.
+    pub failures: Vec<(PathBuf, String)>,
+}
+
+/// Run `convert` on every file, on `jobs` threads (at least one).
+pub fn convert_files<F>(files: &[PathBuf], jobs: usize, convert: F) -> BatchSummary
+    where F: Fn(&Path) -> Result<(), String> + Sync {
+    let results = map_parallel(files, jobs, |_, file| convert(file));
+    let mut summary = BatchSummary::default();
+    for (file, result) in files.iter().zip(results) {
+        match result {
+            Ok(()) => summary.converted += 1,
+            Err(msg) => summary.failures.push((file.clone(), msg)),
+        }
+    }
+    summary
+}
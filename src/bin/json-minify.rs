@@ -0,0 +1,130 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, io};
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::{ConsumeError, LexerOptions};
+use r_json_event_parser::input::open_input;
+use r_json_event_parser::json_parser::JSONParser;
+use r_json_event_parser::json_report::JSONStatsConsumer;
+use r_json_event_parser::output::replace_file;
+use r_json_event_parser::json_writer::{reformat, JSONWriteConsumer, WriteStyle};
+
+fn main() {
+    extern crate clap;
+    use clap::{Arg, App};
+    let matches = App::new("R-JsonMinify")
+        .version("0.0.1")
+        .author("Julien Férard <github.com/jferard>")
+        .about("Remove the whitespace of a JSON file, keeping the number and string lexemes")
+        .arg(Arg::with_name("infile")
+            .help("JSON file or, with the `http` feature, http(s) URL")
+            .index(1))
+        .arg(Arg::with_name("outfile")
+            .help("minified JSON file")
+            .index(2))
+        .arg(Arg::with_name("ascii")
+            .short("a")
+            .long("ascii")
+            .help("write the non ASCII chars as \\u escapes")
+            .takes_value(false))
+        .arg(Arg::with_name("indent")
+            .short("i")
+            .long("indent")
+            .value_name("N")
+            .help("pretty print with N spaces per level instead")
+            .takes_value(true))
+        .arg(Arg::with_name("write")
+            .short("w")
+            .long("write")
+            .help("replace the input file, only if it was minified without error")
+            .requires("infile")
+            .conflicts_with_all(&["outfile", "stats"])
+            .takes_value(false))
+        .arg(Arg::with_name("stats")
+            .short("s")
+            .long("stats")
+            .help("write the statistics of the document (tokens, depth, keys, numbers) instead")
+            .takes_value(false))
+        .get_matches();
+
+    let style = match matches.value_of("indent").map(|n| n.parse::<usize>()) {
+        None => WriteStyle::compact(),
+        Some(Ok(n)) => WriteStyle::pretty(n),
+        Some(Err(_)) => {
+            eprintln!("Err invalid indentation `{}`", matches.value_of("indent").unwrap());
+            std::process::exit(1);
+        }
+    };
+    let inpath = matches.value_of("infile").unwrap_or("-");
+    let outpath = matches.value_of("outfile").unwrap_or("-");
+    let infile = match open_input(inpath) {
+        Ok(infile) => infile,
+        Err(e) => {
+            eprintln!("Err {}", e);
+            std::process::exit(1);
+        }
+    };
+    let ascii = matches.is_present("ascii");
+    if matches.is_present("write") {
+        if let Err(e) = replace_file(Path::new(inpath), |outfile| minify(infile, outfile, style, ascii)) {
+            eprintln!("Err {:?}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    let mut outfile: Box<dyn io::Write> = if outpath == "-" {
+        Box::new(BufWriter::new(io::stdout()))
+    } else {
+        Box::new(BufWriter::new(fs::File::create(outpath).expect("no file found")))
+    };
+    if matches.is_present("stats") {
+        let mut consumer = JSONStatsConsumer::new();
+        let mut parser = JSONParser::new(ByteSource::new(infile), false);
+        let r = parser.parse(&mut consumer)
+            .and_then(|_| write!(outfile, "{}", consumer.report()).and_then(|_| outfile.flush()).map_err(ConsumeError::from_io));
+        if let Err(e) = r {
+            eprintln!("Err {:?}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Err(e) = minify(infile, &mut outfile, style, ascii) {
+        eprintln!("Err {:?}", e);
+        std::process::exit(1);
+    }
+}
+
+fn minify(infile: Box<dyn Read>, outfile: &mut dyn Write, style: WriteStyle, ascii: bool) -> Result<(), ConsumeError> {
+    if ascii {
+        let mut consumer = JSONWriteConsumer::with_style(outfile, style).ascii_only();
+        let mut parser = JSONParser::new(ByteSource::new(infile), false);
+        parser.parse(&mut consumer)?;
+        consumer.into_inner().flush()?;
+    } else {
+        // a `JSONMinifyConsumer` with the style: the lexemes are kept
+        reformat(ByteSource::new(infile), LexerOptions::default(), outfile, style)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, io};
+use std::io::BufWriter;
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::cbor_lexer::CBORLexer;
+use r_json_event_parser::input::open_input;
+use r_json_event_parser::json2cbor::json_to_cbor_write;
+use r_json_event_parser::json_writer::JSONWriteConsumer;
+
+fn main() {
+    extern crate clap;
+    use clap::{Arg, App};
+    let matches = App::new("R-Json2CBOR")
+        .version("0.0.1")
+        .author("Julien Férard <github.com/jferard>")
+        .about("Convert a JSON document to CBOR, or a CBOR item to JSON")
+        .arg(Arg::with_name("infile")
+            .help("JSON or CBOR file or, with the `http` feature, http(s) URL")
+            .index(1))
+        .arg(Arg::with_name("outfile")
+            .help("CBOR or JSON file")
+            .index(2))
+        .arg(Arg::with_name("decode")
+            .short("d")
+            .long("decode")
+            .help("convert a CBOR item to JSON")
+            .takes_value(false))
+        .get_matches();
+
+    let inpath = matches.value_of("infile").unwrap_or("-");
+    let outpath = matches.value_of("outfile").unwrap_or("-");
+    let infile = match open_input(inpath) {
+        Ok(infile) => infile,
+        Err(e) => {
+            eprintln!("Err {}", e);
+            std::process::exit(1);
+        }
+    };
+    let outfile: Box<dyn io::Write> = if outpath == "-" {
+        Box::new(BufWriter::new(io::stdout()))
+    } else {
+        Box::new(BufWriter::new(fs::File::create(outpath).expect("no file found")))
+    };
+    let result = if matches.is_present("decode") {
+        let mut consumer = JSONWriteConsumer::new(outfile);
+        CBORLexer::new(ByteSource::new(infile)).parse(&mut consumer)
+    } else {
+        json_to_cbor_write(infile, outfile)
+    };
+    if let Err(e) = result {
+        eprintln!("Err {:?}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,117 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, io};
+use std::io::BufWriter;
+
+use r_json_event_parser::input::open_input;
+use r_json_event_parser::json2csv::{json_to_csv_write, Json2CsvOptions};
+
+fn main() {
+    extern crate clap;
+    use clap::{Arg, App};
+    let matches = App::new("R-Json2CSV")
+        .version("0.0.1")
+        .author("Julien Férard <github.com/jferard>")
+        .about("Convert an array of JSON objects to CSV")
+        .arg(Arg::with_name("infile")
+            .help("JSON file or, with the `http` feature, http(s) URL")
+            .index(1))
+        .arg(Arg::with_name("outfile")
+            .help("CSV file")
+            .index(2))
+        .arg(Arg::with_name("delimiter")
+            .short("d")
+            .long("delimiter")
+            .value_name("CHAR")
+            .help("the delimiter of the fields (default: ,)")
+            .takes_value(true))
+        .arg(Arg::with_name("tsv")
+            .short("t")
+            .long("tsv")
+            .help("write tab separated values")
+            .takes_value(false))
+        .arg(Arg::with_name("columns")
+            .short("c")
+            .long("columns")
+            .value_name("COLUMNS")
+            .help("the comma separated list of the columns, e.g. 'id,name,geo.lat' (default: inferred)")
+            .takes_value(true))
+        .arg(Arg::with_name("infer")
+            .long("infer")
+            .value_name("N")
+            .help("infer the columns from the first N records (default: 100)")
+            .takes_value(true))
+        .arg(Arg::with_name("separator")
+            .short("s")
+            .long("key-separator")
+            .value_name("SEPARATOR")
+            .help("the text between the keys of a nested value (default: .)")
+            .takes_value(true))
+        .get_matches();
+
+    let inpath = matches.value_of("infile").unwrap_or("-");
+    let outpath = matches.value_of("outfile").unwrap_or("-");
+    let mut opts = Json2CsvOptions::default();
+    if matches.is_present("tsv") {
+        opts.delimiter = b'\t';
+    }
+    if let Some(delimiter) = matches.value_of("delimiter") {
+        match delimiter.as_bytes() {
+            [b] => opts.delimiter = *b,
+            _ => {
+                eprintln!("Err the delimiter must be a single byte");
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(columns) = matches.value_of("columns") {
+        opts.columns = Some(columns.split(',').map(|c| c.to_string()).collect());
+    }
+    if let Some(n) = matches.value_of("infer") {
+        match n.parse() {
+            Ok(n) => opts.infer_records = n,
+            Err(_) => {
+                eprintln!("Err invalid number of records `{}`", n);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(separator) = matches.value_of("separator") {
+        opts.key_separator = separator.into();
+    }
+    let infile = match open_input(inpath) {
+        Ok(infile) => infile,
+        Err(e) => {
+            eprintln!("Err {}", e);
+            std::process::exit(1);
+        }
+    };
+    let outfile: Box<dyn io::Write> = if outpath == "-" {
+        Box::new(BufWriter::new(io::stdout()))
+    } else {
+        Box::new(BufWriter::new(fs::File::create(outpath).expect("no file found")))
+    };
+    if let Err(e) = json_to_csv_write(infile, outfile, &opts) {
+        eprintln!("Err {:?}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,91 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, io};
+use std::io::BufWriter;
+
+use r_json_event_parser::input::open_input;
+use r_json_event_parser::json2html::{json_to_html_write, Json2HtmlOptions};
+
+fn main() {
+    extern crate clap;
+    use clap::{Arg, App};
+    let matches = App::new("R-Json2HTML")
+        .version("0.0.1")
+        .author("Julien Férard <github.com/jferard>")
+        .about("Render a JSON document as a collapsible HTML tree")
+        .arg(Arg::with_name("infile")
+            .help("JSON file or, with the `http` feature, http(s) URL")
+            .index(1))
+        .arg(Arg::with_name("outfile")
+            .help("HTML file")
+            .index(2))
+        .arg(Arg::with_name("standalone")
+            .short("s")
+            .long("standalone")
+            .help("write a whole page with a style sheet instead of a fragment")
+            .takes_value(false))
+        .arg(Arg::with_name("title")
+            .long("title")
+            .value_name("TITLE")
+            .help("the title of a standalone page (default: JSON)")
+            .takes_value(true))
+        .arg(Arg::with_name("collapse")
+            .short("c")
+            .long("collapse-depth")
+            .value_name("DEPTH")
+            .help("close the containers nested deeper than DEPTH (default: all open)")
+            .takes_value(true))
+        .get_matches();
+
+    let inpath = matches.value_of("infile").unwrap_or("-");
+    let outpath = matches.value_of("outfile").unwrap_or("-");
+    let mut opts = Json2HtmlOptions {
+        standalone: matches.is_present("standalone"),
+        title: matches.value_of("title").map(|t| t.to_string()),
+        ..Default::default()
+    };
+    if let Some(depth) = matches.value_of("collapse") {
+        match depth.parse() {
+            Ok(depth) => opts.collapse_depth = Some(depth),
+            Err(_) => {
+                eprintln!("Err invalid depth `{}`", depth);
+                std::process::exit(1);
+            }
+        }
+    }
+    let infile = match open_input(inpath) {
+        Ok(infile) => infile,
+        Err(e) => {
+            eprintln!("Err {}", e);
+            std::process::exit(1);
+        }
+    };
+    let outfile: Box<dyn io::Write> = if outpath == "-" {
+        Box::new(BufWriter::new(io::stdout()))
+    } else {
+        Box::new(BufWriter::new(fs::File::create(outpath).expect("no file found")))
+    };
+    if let Err(e) = json_to_html_write(infile, outfile, &opts) {
+        eprintln!("Err {:?}", e);
+        std::process::exit(1);
+    }
+}
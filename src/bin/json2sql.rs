@@ -0,0 +1,111 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, io};
+use std::io::BufWriter;
+
+use r_json_event_parser::input::open_input;
+use r_json_event_parser::json2sql::{json_to_sql_write, Json2SqlOptions};
+
+fn main() {
+    extern crate clap;
+    use clap::{Arg, App};
+    let matches = App::new("R-Json2SQL")
+        .version("0.0.1")
+        .author("Julien Férard <github.com/jferard>")
+        .about("Convert an array of JSON objects to SQL INSERT statements")
+        .arg(Arg::with_name("infile")
+            .help("JSON file or, with the `http` feature, http(s) URL")
+            .index(1))
+        .arg(Arg::with_name("outfile")
+            .help("SQL file")
+            .index(2))
+        .arg(Arg::with_name("table")
+            .short("t")
+            .long("table")
+            .value_name("TABLE")
+            .help("the name of the table (default: data)")
+            .takes_value(true))
+        .arg(Arg::with_name("batch-size")
+            .short("b")
+            .long("batch-size")
+            .value_name("N")
+            .help("the maximum number of records of a statement (default: 100)")
+            .takes_value(true))
+        .arg(Arg::with_name("columns")
+            .short("c")
+            .long("columns")
+            .value_name("COLUMNS")
+            .help("the comma separated list of the columns (default: the keys of the records)")
+            .takes_value(true))
+        .arg(Arg::with_name("quote")
+            .short("q")
+            .long("identifier-quote")
+            .value_name("CHAR")
+            .help("the quote of the column names (default: \"), e.g. ` for MySQL")
+            .takes_value(true))
+        .get_matches();
+
+    let inpath = matches.value_of("infile").unwrap_or("-");
+    let outpath = matches.value_of("outfile").unwrap_or("-");
+    let mut opts = Json2SqlOptions::default();
+    if let Some(table) = matches.value_of("table") {
+        opts.table = table.into();
+    }
+    if let Some(n) = matches.value_of("batch-size") {
+        match n.parse() {
+            Ok(n) => opts.batch_size = n,
+            Err(_) => {
+                eprintln!("Err invalid batch size `{}`", n);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(columns) = matches.value_of("columns") {
+        opts.columns = Some(columns.split(',').map(|c| c.to_string()).collect());
+    }
+    if let Some(quote) = matches.value_of("quote") {
+        let mut chars = quote.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => opts.identifier_quote = c,
+            _ => {
+                eprintln!("Err the quote must be a single char");
+                std::process::exit(1);
+            }
+        }
+    }
+    let infile = match open_input(inpath) {
+        Ok(infile) => infile,
+        Err(e) => {
+            eprintln!("Err {}", e);
+            std::process::exit(1);
+        }
+    };
+    let outfile: Box<dyn io::Write> = if outpath == "-" {
+        Box::new(BufWriter::new(io::stdout()))
+    } else {
+        Box::new(BufWriter::new(fs::File::create(outpath).expect("no file found")))
+    };
+    if let Err(e) = json_to_sql_write(infile, outfile, &opts) {
+        eprintln!("Err {:?}", e);
+        std::process::exit(1);
+    }
+}
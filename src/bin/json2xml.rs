@@ -19,12 +19,22 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::{fs, io};
-use std::io::{BufWriter, Write};
+use std::{io, thread};
+use std::path::Path;
 
+use r_json_event_parser::batch::{convert_files, find_files, is_glob, sibling_path};
+#[cfg(feature = "mmap")]
 use r_json_event_parser::byte_source::ByteSource;
-use r_json_event_parser::json2xml::JSON2XMLConsumer;
-use r_json_event_parser::json_parser::JSONParser;
+#[cfg(feature = "mmap")]
+use r_json_event_parser::input::map_input;
+use r_json_event_parser::input::{as_single_document, Compression, InputFormat, open_compressed_input, open_input, sniff_input};
+use r_json_event_parser::json_pointer::JSONPointer;
+use r_json_event_parser::json_redact::RedactPattern;
+use r_json_event_parser::json_transform::value_tokens;
+#[cfg(feature = "mmap")]
+use r_json_event_parser::json2xml::byte_source_to_xml_write;
+use r_json_event_parser::output::open_output;
+use r_json_event_parser::json2xml::{ControlChars, json_lines_to_xml_write, json_to_xml_write, Json2XmlOptions, TextEscaping, XmlNamePolicy};
 
 fn main() {
     extern crate clap;
@@ -34,11 +44,45 @@ fn main() {
         .author("Julien Férard <github.com/jferard>")
         .about("Convert JSON file to XML")
         .arg(Arg::with_name("infile")
-            .help("JSON file")
+            .help("JSON file, directory or glob pattern (e.g. 'data/**/*.json') or, with the `http` feature, \
+    http(s) URL; the files of a directory or a glob pattern are converted to sibling .xml files")
             .index(1))
         .arg(Arg::with_name("outfile")
             .help("XML file")
             .index(2))
+        .arg(Arg::with_name("input-format")
+            .short("i")
+            .long("input-format")
+            .value_name("FORMAT")
+            .help("json, ndjson (one document per line), json-seq (RS delimited documents) or auto; \
+    the documents of a stream become the elements of the root")
+            .possible_values(&["json", "ndjson", "json-seq", "auto"])
+            .default_value("json")
+            .takes_value(true))
+        .arg(Arg::with_name("recursive")
+            .short("r")
+            .long("recursive")
+            .help("convert the JSON files of the subdirectories of a directory too")
+            .takes_value(false))
+        .arg(Arg::with_name("jobs")
+            .long("jobs")
+            .value_name("N")
+            .help("the number of files of a directory or a glob pattern converted in parallel \
+    (default: the number of CPUs)")
+            .takes_value(true))
+        .arg(Arg::with_name("decompress")
+            .short("z")
+            .long("decompress")
+            .help("decompress a gzip or zstd input, detected by its magic bytes (requires the `gzip` \
+    or `zstd` feature)")
+            .takes_value(false))
+        .arg(Arg::with_name("compress")
+            .long("compress")
+            .value_name("FORMAT")
+            .help("compress the output with gzip or zstd (default: guessed from the extension of the \
+    output file, .gz or .zst; requires the `gzip` or `zstd` feature)")
+            .possible_values(&["gzip", "zstd", "none"])
+            .takes_value(true))
         .arg(Arg::with_name("formatted")
             .short("f")
             .long("formatted")
@@ -50,43 +94,320 @@ fn main() {
             .long("typed")
             .help("type tags")
             .takes_value(false))
+        .arg(Arg::with_name("compact")
+            .short("c")
+            .long("compact")
+            .help("with --formatted, write the elements that contain only scalars on a single line")
+            .takes_value(false))
+        .arg(Arg::with_name("join")
+            .short("j")
+            .long("join-scalar-arrays")
+            .value_name("DELIMITER")
+            .help("write the arrays of scalars as a single element, values joined by DELIMITER")
+            .takes_value(true))
+        .arg(Arg::with_name("comment")
+            .long("comment")
+            .value_name("TEXT")
+            .help("add a comment before the root (repeatable)")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true))
+        .arg(Arg::with_name("pi")
+            .long("processing-instruction")
+            .value_name("PI")
+            .help("add a processing instruction `target data` before the root (repeatable), \
+    e.g. 'xml-stylesheet type=\"text/xsl\" href=\"style.xsl\"'")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true))
+        .arg(Arg::with_name("doctype")
+            .long("doctype")
+            .value_name("DOCTYPE")
+            .help("add a DOCTYPE before the root, e.g. 'root SYSTEM \"root.dtd\"'")
+            .takes_value(true))
+        .arg(Arg::with_name("key-names")
+            .long("key-names")
+            .value_name("POLICY")
+            .help("how the keys become element names: as-is, mangle (invalid chars replaced by _), \
+    hex (invalid chars encoded as _xHHHH_) or entry (<entry key=\"...\">)")
+            .possible_values(&["as-is", "mangle", "hex", "entry"])
+            .default_value("as-is")
+            .takes_value(true))
+        .arg(Arg::with_name("attribute-prefix")
+            .long("attribute-prefix")
+            .value_name("PREFIX")
+            .help("write the scalar members whose key starts with PREFIX as attributes, e.g. '@'")
+            .takes_value(true))
+        .arg(Arg::with_name("root")
+            .long("root")
+            .value_name("NAME")
+            .help("the name of the root element (default: root)")
+            .takes_value(true))
+        .arg(Arg::with_name("namespace")
+            .long("namespace")
+            .value_name("URI")
+            .help("the default namespace, declared on the root")
+            .takes_value(true))
+        .arg(Arg::with_name("root-attr")
+            .long("root-attr")
+            .value_name("NAME=VALUE")
+            .help("add an attribute to the root (repeatable), e.g. 'version=1.0'")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true))
+        .arg(Arg::with_name("true-text")
+            .long("true-text")
+            .value_name("TEXT")
+            .help("the text of the true values (default: true)")
+            .takes_value(true))
+        .arg(Arg::with_name("false-text")
+            .long("false-text")
+            .value_name("TEXT")
+            .help("the text of the false values (default: false)")
+            .takes_value(true))
+        .arg(Arg::with_name("null-text")
+            .long("null-text")
+            .value_name("TEXT")
+            .help("the text of the null values (default: null, empty for an empty element)")
+            .takes_value(true))
+        .arg(Arg::with_name("set")
+            .long("set")
+            .value_name("POINTER=JSON")
+            .help("replace the value at the JSON Pointer by the JSON value (repeatable), a * matching any key or index, e.g. '/a/*/b=42'")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true))
+        .arg(Arg::with_name("redact")
+            .long("redact")
+            .value_name("PATTERN")
+            .help("replace the values of the matching members by a mask (repeatable): a key (e.g. 'password'), \
+    dotted path ends (e.g. '*.token') or a JSON Pointer (e.g. '/users/*/ssn'), `*` matching any key or index")
+            .multiple(true)
+            .number_of_values(1)
+            .takes_value(true))
+        .arg(Arg::with_name("mask")
+            .long("mask")
+            .value_name("TEXT")
+            .help("the mask of the redacted values (default: ***)")
+            .takes_value(true))
+        .arg(Arg::with_name("escape")
+            .long("escape")
+            .value_name("MODE")
+            .help("how the special chars of the texts are escaped: cdata (a CDATA section) or entities (&lt;, &amp;...)")
+            .possible_values(&["cdata", "entities"])
+            .default_value("cdata")
+            .takes_value(true))
+        .arg(Arg::with_name("control-chars")
+            .long("control-chars")
+            .value_name("POLICY")
+            .help("what to do with the control chars, not allowed in XML 1.0: replace (by U+FFFD), strip, \
+    reference (XML 1.1) or error")
+            .possible_values(&["replace", "strip", "reference", "error"])
+            .default_value("replace")
+            .takes_value(true))
+        .arg(Arg::with_name("ascii")
+            .short("a")
+            .long("ascii")
+            .help("write the non ASCII chars of the values as character references")
+            .takes_value(false))
         .get_matches();
 
     let inpath = matches.value_of("infile").unwrap_or("-");
     let outpath = matches.value_of("outfile").unwrap_or("-");
     let formatted = matches.is_present("formatted");
     let typed = matches.is_present("typed");
-    let infile: Box<dyn io::Read> = if inpath == "-" {
-        Box::new(io::stdin())
-    } else {
-        Box::new(fs::File::open(inpath).expect("no file found"))
+    let scalar_array_delimiter = matches.value_of("join").map(|d| d.to_string());
+    let compact = matches.is_present("compact");
+    let ascii_only = matches.is_present("ascii");
+    let control_chars = match matches.value_of("control-chars") {
+        Some("strip") => ControlChars::Strip,
+        Some("reference") => ControlChars::Reference,
+        Some("error") => ControlChars::Error,
+        _ => ControlChars::Replace,
+    };
+    let text_escaping = match matches.value_of("escape") {
+        Some("entities") => TextEscaping::Entities,
+        _ => TextEscaping::Cdata,
+    };
+    let comments = matches.values_of("comment").map(|v| v.map(|c| c.to_string()).collect()).unwrap_or_default();
+    let processing_instructions = matches.values_of("pi").map(|v| v.map(|pi| {
+        let mut parts = pi.splitn(2, ' ');
+        (parts.next().unwrap_or("").to_string(), parts.next().unwrap_or("").to_string())
+    }).collect()).unwrap_or_default();
+    let doctype = matches.value_of("doctype").map(|d| d.to_string());
+    let key_names = match matches.value_of("key-names") {
+        Some("mangle") => XmlNamePolicy::Mangle,
+        Some("hex") => XmlNamePolicy::HexEncode,
+        Some("entry") => XmlNamePolicy::Entry,
+        _ => XmlNamePolicy::AsIs,
+    };
+    let attribute_prefix = matches.value_of("attribute-prefix").map(|p| p.to_string());
+    let root = matches.value_of("root").map(|r| r.to_string());
+    let namespace = matches.value_of("namespace").map(|n| n.to_string());
+    let root_attributes = matches.values_of("root-attr").map(|v| v.map(|attr| {
+        let mut parts = attr.splitn(2, '=');
+        (parts.next().unwrap_or("").to_string(), parts.next().unwrap_or("").to_string())
+    }).collect()).unwrap_or_default();
+    let true_text = matches.value_of("true-text").map(|t| t.to_string());
+    let false_text = matches.value_of("false-text").map(|t| t.to_string());
+    let null_text = matches.value_of("null-text").map(|t| t.to_string());
+    let mut substitutions = vec!();
+    for set in matches.values_of("set").into_iter().flatten() {
+        let mut parts = set.splitn(2, '=');
+        let pointer = JSONPointer::parse(parts.next().unwrap_or(""));
+        let value = value_tokens(parts.next().unwrap_or(""));
+        match (pointer, value) {
+            (Ok(pointer), Ok(value)) => substitutions.push((pointer, value)),
+            _ => {
+                eprintln!("Err invalid substitution `{}`", set);
+                std::process::exit(1);
+            }
+        }
+    }
+    let mut redactions = vec!();
+    for pattern in matches.values_of("redact").into_iter().flatten() {
+        match RedactPattern::parse(pattern) {
+            Ok(pattern) => redactions.push(pattern),
+            Err(e) => {
+                eprintln!("Err invalid redaction `{}`: {}", pattern, e.msg);
+                std::process::exit(1);
+            }
+        }
+    }
+    let redaction_mask = matches.value_of("mask").map(|m| m.to_string());
+    let decompress = matches.is_present("decompress");
+    let format = match matches.value_of("input-format") {
+        Some("ndjson") => Some(InputFormat::JsonLines),
+        Some("json-seq") => Some(InputFormat::JsonSeq),
+        Some("auto") => None,
+        _ => Some(InputFormat::Json),
+    };
+    let compression = match matches.value_of("compress") {
+        Some("gzip") => Compression::Gzip,
+        Some("zstd") => Compression::Zstd,
+        Some("none") => Compression::None,
+        _ => Compression::Auto,
     };
-    let outfile: Box<dyn io::Write> = if outpath == "-" {
-        Box::new(BufWriter::new(io::stdout()))
-    } else {
-        Box::new(BufWriter::new(fs::File::create(outpath).expect("no file found")))
+    let opts = Json2XmlOptions {
+        formatted,
+        typed,
+        ignore_unicode_errs: true,
+        scalar_array_delimiter,
+        compact,
+        comments,
+        processing_instructions,
+        doctype,
+        key_names,
+        attribute_prefix,
+        root,
+        namespace,
+        root_attributes,
+        true_text,
+        false_text,
+        null_text,
+        substitutions,
+        redactions,
+        redaction_mask,
+        ascii_only,
+        text_escaping,
+        control_chars,
     };
-    let byte_source = ByteSource::new(infile);
-    let mut parser = JSONParser::new(byte_source, true);
-    let r = if formatted {
-        if typed {
-            let mut consumer = JSON2XMLConsumer::new_formatted_and_typed(outfile);
-            parser.parse(&mut consumer)
-        } else {
-            let mut consumer = JSON2XMLConsumer::new_formatted(outfile);
-            parser.parse(&mut consumer)
+    let conversion = Conversion { format, decompress, compression, opts };
+    if !Path::new(inpath).is_dir() && !is_glob(inpath) {
+        match conversion.convert(inpath, outpath) {
+            Ok(warnings) => {
+                for warning in warnings {
+                    eprintln!("{}", warning);
+                }
+            }
+            Err(e) => {
+                eprintln!("Err {}", e);
+                std::process::exit(1);
+            }
         }
-    } else {
-        if typed {
-            let mut consumer = JSON2XMLConsumer::new_typed(outfile);
-            parser.parse(&mut consumer)
-        } else {
-            let mut consumer = JSON2XMLConsumer::new(outfile);
-            parser.parse(&mut consumer)
+        return;
+    }
+
+    if matches.is_present("outfile") {
+        eprintln!("Err the output files of a directory or a glob are named after the input files");
+        std::process::exit(1);
+    }
+    let files = match find_files(inpath, matches.is_present("recursive")) {
+        Ok(files) => files,
+        Err(e) => {
+            eprintln!("Err {}", e);
+            std::process::exit(1);
         }
     };
-    match r {
-        Ok(_) => {}
-        Err(e) => { write!(io::stderr(), "Err {:?}", e).unwrap(); }
+    let jobs = match matches.value_of("jobs").map(|j| j.parse::<usize>()) {
+        Some(Ok(jobs)) => jobs,
+        Some(Err(_)) => {
+            eprintln!("Err invalid number of jobs `{}`", matches.value_of("jobs").unwrap_or(""));
+            std::process::exit(1);
+        }
+        None => thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+    };
+    let extension = match compression {
+        Compression::Gzip => "xml.gz",
+        Compression::Zstd => "xml.zst",
+        _ => "xml",
+    };
+    let summary = convert_files(&files, jobs, |file| {
+        let inpath = file.to_string_lossy();
+        let outpath = sibling_path(file, extension);
+        let warnings = conversion.convert(&inpath, &outpath.to_string_lossy())?;
+        for warning in warnings {
+            eprintln!("{}: {}", inpath, warning);
+        }
+        Ok(())
+    });
+    for (file, msg) in &summary.failures {
+        eprintln!("{}: Err {}", file.display(), msg);
+    }
+    eprintln!("{} file(s) converted, {} failed", summary.converted, summary.failures.len());
+    if !summary.failures.is_empty() {
+        std::process::exit(1);
+    }
+}
+
+/// The conversion of one file.
+struct Conversion {
+    /// None to guess the format
+    format: Option<InputFormat>,
+    decompress: bool,
+    compression: Compression,
+    opts: Json2XmlOptions,
+}
+
+impl Conversion {
+    /// Convert a file. Returns the errors of the lines of a JSON Lines input.
+    fn convert(&self, inpath: &str, outpath: &str) -> Result<Vec<String>, String> {
+        let opened = if self.decompress { open_compressed_input(inpath) } else { open_input(inpath) };
+        let infile = opened.map_err(|e| e.to_string())?;
+        let (format, infile) = match self.format {
+            Some(format) => (format, infile),
+            None => match sniff_input(infile) {
+                Ok((format, infile)) => (format, Box::new(infile) as Box<dyn io::Read>),
+                Err(e) => return Err(e.to_string()),
+            },
+        };
+        let outfile = open_output(outpath, self.compression).map_err(|e| e.to_string())?;
+        if format == InputFormat::JsonLines {
+            let summary = json_lines_to_xml_write(infile, outfile, &self.opts).map_err(|e| e.to_string())?;
+            let mut warnings: Vec<String> = summary.errors.iter().map(|e| format!("Line {}: {}", e.line + 1, e.msg)).collect();
+            if !summary.errors.is_empty() {
+                let lines: Vec<String> = summary.failed_lines().iter().map(|l| (l + 1).to_string()).collect();
+                warnings.push(format!("{} document(s) converted, {} line(s) failed: {}", summary.documents, lines.len(), lines.join(", ")));
+            }
+            return Ok(warnings);
+        }
+        #[cfg(feature = "mmap")]
+        let r = match map_input(inpath).filter(|_| format == InputFormat::Json && !self.decompress) {
+            Some(mmap) => byte_source_to_xml_write(ByteSource::from_buf_read(mmap), outfile, &self.opts),
+            None => json_to_xml_write(as_single_document(infile, format), outfile, &self.opts),
+        };
+        #[cfg(not(feature = "mmap"))]
+        let r = json_to_xml_write(as_single_document(infile, format), outfile, &self.opts);
+        r.map(|_| vec!()).map_err(|e| e.to_string())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,83 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, io};
+use std::io::BufWriter;
+
+use r_json_event_parser::input::open_input;
+use r_json_event_parser::json2yaml::{json_to_yaml_write, Json2YamlOptions};
+
+fn main() {
+    extern crate clap;
+    use clap::{Arg, App};
+    let matches = App::new("R-Json2YAML")
+        .version("0.0.1")
+        .author("Julien Férard <github.com/jferard>")
+        .about("Convert a JSON document to YAML")
+        .arg(Arg::with_name("infile")
+            .help("JSON file or, with the `http` feature, http(s) URL")
+            .index(1))
+        .arg(Arg::with_name("outfile")
+            .help("YAML file")
+            .index(2))
+        .arg(Arg::with_name("indent")
+            .short("i")
+            .long("indent")
+            .value_name("N")
+            .help("the indentation of the values of an object (default: 2)")
+            .takes_value(true))
+        .arg(Arg::with_name("document-start")
+            .short("s")
+            .long("document-start")
+            .help("begin the output with `---`")
+            .takes_value(false))
+        .get_matches();
+
+    let inpath = matches.value_of("infile").unwrap_or("-");
+    let outpath = matches.value_of("outfile").unwrap_or("-");
+    let mut opts = Json2YamlOptions::default();
+    if let Some(n) = matches.value_of("indent") {
+        match n.parse() {
+            Ok(n) => opts.indent = n,
+            Err(_) => {
+                eprintln!("Err invalid indentation `{}`", n);
+                std::process::exit(1);
+            }
+        }
+    }
+    opts.document_start = matches.is_present("document-start");
+    let infile = match open_input(inpath) {
+        Ok(infile) => infile,
+        Err(e) => {
+            eprintln!("Err {}", e);
+            std::process::exit(1);
+        }
+    };
+    let outfile: Box<dyn io::Write> = if outpath == "-" {
+        Box::new(BufWriter::new(io::stdout()))
+    } else {
+        Box::new(BufWriter::new(fs::File::create(outpath).expect("no file found")))
+    };
+    if let Err(e) = json_to_yaml_write(infile, outfile, &opts) {
+        eprintln!("Err {:?}", e);
+        std::process::exit(1);
+    }
+}
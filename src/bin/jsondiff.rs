@@ -0,0 +1,106 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io;
+use std::io::{BufWriter, Write};
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::input::open_input;
+use r_json_event_parser::json_diff::{diff, DiffConsumer, DiffEntry, HTMLDiffConsumer, HTMLDiffLayout};
+use r_json_event_parser::json_lexer::ConsumeError;
+
+/// Writes one line per difference.
+struct LineConsumer<W: Write> {
+    destination: W,
+    count: usize,
+}
+
+impl<W: Write> DiffConsumer for LineConsumer<W> {
+    fn consume(&mut self, entry: DiffEntry) -> Result<(), ConsumeError> {
+        self.count += 1;
+        writeln!(self.destination, "{}", entry).map_err(ConsumeError::from_io)
+    }
+}
+
+fn main() {
+    extern crate clap;
+    use clap::{Arg, App};
+    let matches = App::new("R-JsonDiff")
+        .version("0.0.1")
+        .author("Julien Férard <github.com/jferard>")
+        .about("Write the added (+), removed (-) and changed (~) values of two JSON files. \
+The exit status is 0 if the files are equal, 1 if they differ, 2 on error")
+        .arg(Arg::with_name("left")
+            .help("JSON file or, with the `http` feature, http(s) URL")
+            .required(true)
+            .index(1))
+        .arg(Arg::with_name("right")
+            .help("JSON file or, with the `http` feature, http(s) URL")
+            .required(true)
+            .index(2))
+        .arg(Arg::with_name("html")
+            .long("html")
+            .help("write a self-contained HTML report instead")
+            .takes_value(false))
+        .arg(Arg::with_name("side-by-side")
+            .long("side-by-side")
+            .help("write the old and the new values of the HTML report in two columns")
+            .requires("html")
+            .takes_value(false))
+        .get_matches();
+
+    let open = |path: &str| match open_input(path) {
+        Ok(infile) => infile,
+        Err(e) => {
+            eprintln!("Err {}", e);
+            std::process::exit(2);
+        }
+    };
+    let left_path = matches.value_of("left").unwrap();
+    let right_path = matches.value_of("right").unwrap();
+    let left = open(left_path);
+    let right = open(right_path);
+    if matches.is_present("html") {
+        let layout = if matches.is_present("side-by-side") { HTMLDiffLayout::SideBySide } else { HTMLDiffLayout::Inline };
+        let mut consumer = HTMLDiffConsumer::new(BufWriter::new(io::stdout()), layout)
+            .title(&format!("{} / {}", left_path, right_path));
+        let r = diff(ByteSource::new(left), ByteSource::new(right), &mut consumer);
+        let count = consumer.count();
+        if let Err(e) = r.and_then(|_| consumer.finish()) {
+            eprintln!("Err {:?}", e);
+            std::process::exit(2);
+        }
+        if count > 0 {
+            std::process::exit(1);
+        }
+        return;
+    }
+    let mut consumer = LineConsumer { destination: BufWriter::new(io::stdout()), count: 0 };
+    let r = diff(ByteSource::new(left), ByteSource::new(right), &mut consumer)
+        .and_then(|_| consumer.destination.flush().map_err(ConsumeError::from_io));
+    if let Err(e) = r {
+        eprintln!("Err {:?}", e);
+        std::process::exit(2);
+    }
+    if consumer.count > 0 {
+        std::process::exit(1);
+    }
+}
@@ -19,53 +19,340 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
-use std::io::Read;
+use std::collections::VecDeque;
+use std::io;
+use std::io::{BufRead, Read};
+
+/// The default size of the buffer.
+pub const DEFAULT_CAPACITY: usize = 32 * 1024;
+
+/// The number of bytes copied from the buffer of a `BufRead` source for the byte by byte reads.
+const WINDOW_SIZE: usize = 256;
+
+type FillBuf<R> = for<'a> fn(&'a mut R) -> io::Result<&'a [u8]>;
+type Consume<R> = fn(&mut R, usize);
 
 pub struct ByteSource<R: Read> {
     source: R,
-    unget_byte: Option<u8>,
-    buffer: [u8; 32768],
+    /// The bytes read from the source or, if the buffer of the source is used, a copy of the
+    /// bytes of that buffer from `window_start`, to avoid a `fill_buf` per byte.
+    buffer: Vec<u8>,
+    /// The `BufRead` methods of the source, if the buffer of the source is used.
+    buf_read: Option<(FillBuf<R>, Consume<R>)>,
+    window_start: usize,
     i: usize,
     limit: usize,
+    offset: usize,
+    /// The bytes to return before the bytes of the buffer: the pushed back and the peeked bytes.
+    lookahead: VecDeque<u8>,
+    /// The last byte returned by `get`
+    last: Option<u8>,
+    /// The bytes returned since `start_recording`
+    record: Option<Vec<u8>>,
 }
 
 impl<R: Read> ByteSource<R> {
     pub fn new(source: R) -> Self {
+        ByteSource::with_capacity(source, DEFAULT_CAPACITY)
+    }
+
+    /// A source with a buffer of `capacity` bytes (at least one byte).
+    pub fn with_capacity(source: R, capacity: usize) -> Self {
         ByteSource {
             source,
-            unget_byte: None,
-            buffer: [0u8; 32 * 1024],
+            buffer: vec![0u8; capacity.max(1)],
+            buf_read: None,
+            window_start: 0,
             i: 0,
             limit: 0,
+            offset: 0,
+            lookahead: VecDeque::new(),
+            last: None,
+            record: None,
         }
     }
 
-    pub(crate) fn get(&mut self) -> Option<u8> {
-        if let Some(b) = self.unget_byte {
-            self.unget_byte = None;
-            Some(b)
-        } else {
-            if self.i >= self.limit {
-                self.i = 0;
-                loop {
-                    match self.source.read(&mut self.buffer[..]) {
-                        Ok(0) => { return None; }
-                        Ok(n) => {
-                            self.limit = n;
-                            break;
-                        }
-                        Err(_) => {} // retry
-                    };
+    /// The bytes that were read from the source, at least `limit` bytes.
+    fn data(&mut self) -> io::Result<&[u8]> {
+        match self.buf_read {
+            // the bytes are not consumed yet: `fill_buf` returns the same bytes
+            Some((fill_buf, _)) => {
+                let limit = self.limit;
+                let data = fill_buf(&mut self.source)?;
+                if data.len() < limit {
+                    return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "the buffer of the source was consumed"));
+                }
+                Ok(data)
+            }
+            None => Ok(&self.buffer),
+        }
+    }
+
+    /// Read the next bytes. Returns false at the end of the source.
+    fn fill(&mut self) -> io::Result<bool> {
+        loop {
+            let read = match self.buf_read {
+                Some((fill_buf, consume)) => {
+                    consume(&mut self.source, self.limit);
+                    self.i = 0;
+                    self.limit = 0;
+                    self.buffer.clear();
+                    fill_buf(&mut self.source).map(|data| data.len())
+                }
+                None => self.source.read(&mut self.buffer[..]),
+            };
+            match read {
+                Ok(0) => { return Ok(false); }
+                Ok(n) => {
+                    self.i = 0;
+                    self.limit = n;
+                    return Ok(true);
                 }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {} // retry
+                Err(e) => { return Err(e); }
+            };
+        }
+    }
+
+    /// The next byte of the buffer, ignoring the lookahead.
+    fn next_buffered(&mut self) -> io::Result<Option<u8>> {
+        if self.i >= self.limit && !self.fill()? {
+            return Ok(None);
+        }
+        if self.buf_read.is_some() && !(self.window_start..self.window_start + self.buffer.len()).contains(&self.i) {
+            self.copy_window()?;
+        }
+        let byte = self.buffer[self.i - self.window_start];
+        self.i += 1;
+        Ok(Some(byte))
+    }
+
+    /// Copy the next bytes of the buffer of the source, from the current position.
+    fn copy_window(&mut self) -> io::Result<()> {
+        let (i, end) = (self.i, self.limit.min(self.i + WINDOW_SIZE));
+        let mut window = std::mem::take(&mut self.buffer);
+        window.clear();
+        let result = self.data().map(|data| window.extend_from_slice(&data[i..end]));
+        self.buffer = window;
+        self.window_start = i;
+        result
+    }
+
+    /// The next byte, or None at the end of the source.
+    pub(crate) fn get(&mut self) -> io::Result<Option<u8>> {
+        let byte = match self.lookahead.pop_front() {
+            Some(byte) => Some(byte),
+            None => self.next_buffered()?,
+        };
+        if let Some(byte) = byte {
+            self.offset += 1;
+            if let Some(record) = self.record.as_mut() {
+                record.push(byte);
             }
-            let j = self.i;
-            self.i += 1;
-            Some(self.buffer[j])
         }
+        self.last = byte;
+        Ok(byte)
     }
 
+    /// Put back the last byte returned by `get`: the offset goes back one byte.
     pub(crate) fn unget(&mut self) {
-        self.unget_byte = Some(self.buffer[self.i-1]);
+        if let Some(byte) = self.last.take() {
+            debug_assert!(self.offset > 0);
+            self.lookahead.push_front(byte);
+            self.offset -= 1;
+            if let Some(record) = self.record.as_mut() {
+                record.pop();
+            }
+        }
+    }
+
+    /// Insert a byte that was not returned by this source: it will be the next byte read, and
+    /// is counted by the offset when it is read. May be used before the parsing, e.g. to restore
+    /// a byte of a header.
+    pub fn unget_byte(&mut self, byte: u8) {
+        self.lookahead.push_front(byte);
+    }
+
+    /// The next byte, without consuming it.
+    pub fn peek(&mut self) -> io::Result<Option<u8>> {
+        Ok(self.peek_n(1)?.first().copied())
+    }
+
+    /// The next `n` bytes, or less at the end of the source, without consuming them, e.g. to sniff
+    /// the format of the input before the parsing.
+    pub fn peek_n(&mut self, n: usize) -> io::Result<&[u8]> {
+        while self.lookahead.len() < n {
+            match self.next_buffered()? {
+                Some(byte) => self.lookahead.push_back(byte),
+                None => break,
+            }
+        }
+        let n = n.min(self.lookahead.len());
+        Ok(&self.lookahead.make_contiguous()[..n])
+    }
+
+    /// The bytes of the buffer after the current position. The source is not read: the slice is
+    /// empty if the buffer is exhausted or if some bytes were pushed back or peeked.
+    pub(crate) fn peek_slice(&mut self) -> io::Result<&[u8]> {
+        if !self.lookahead.is_empty() || self.i >= self.limit {
+            return Ok(&[]);
+        }
+        let (i, limit) = (self.i, self.limit);
+        Ok(&self.data()?[i..limit])
+    }
+
+    /// Skip the next `n` bytes, that were returned by `peek_n` or `peek_slice`. `unget` is not
+    /// possible after a `consume`.
+    pub(crate) fn consume(&mut self, n: usize) -> io::Result<()> {
+        let from_lookahead = n.min(self.lookahead.len());
+        let drained = self.lookahead.drain(..from_lookahead);
+        match self.record.as_mut() {
+            Some(record) => record.extend(drained),
+            None => drop(drained),
+        }
+        let from_buffer = n - from_lookahead;
+        debug_assert!(self.i + from_buffer <= self.limit);
+        if from_buffer > 0 {
+            if let Some(mut record) = self.record.take() {
+                let (i, limit) = (self.i, self.i + from_buffer);
+                let result = self.data().map(|data| record.extend_from_slice(&data[i..limit]));
+                self.record = Some(record);
+                result?;
+            }
+        }
+        self.i += from_buffer;
+        self.offset += n;
+        self.last = None;
+        Ok(())
+    }
+
+    /// Keep a copy of the next bytes, e.g. to get the source text of a token.
+    pub(crate) fn start_recording(&mut self) {
+        self.record = Some(vec!());
+    }
+
+    /// The bytes returned since `start_recording`, minus the bytes that were pushed back.
+    pub(crate) fn stop_recording(&mut self) -> Option<Vec<u8>> {
+        self.record.take()
+    }
+
+    /// The number of bytes consumed so far.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The underlying source, e.g. to append the bytes of a `FeedBuffer`.
+    pub(crate) fn source_mut(&mut self) -> &mut R {
+        &mut self.source
+    }
+}
+
+impl<R: BufRead> ByteSource<R> {
+    /// A source that reads the bytes in the buffer of `source` instead of copying them to its own
+    /// buffer. The bytes of the last `fill_buf` are consumed when the next ones are needed.
+    pub fn from_buf_read(source: R) -> Self {
+        ByteSource {
+            source,
+            buffer: Vec::with_capacity(WINDOW_SIZE),
+            buf_read: Some((R::fill_buf, R::consume)),
+            window_start: 0,
+            i: 0,
+            limit: 0,
+            offset: 0,
+            lookahead: VecDeque::new(),
+            last: None,
+            record: None,
+        }
+    }
+}
+
+impl<'a> ByteSource<&'a [u8]> {
+    /// A source that reads an in-memory document without copying it to a buffer.
+    pub fn from_slice(bytes: &'a [u8]) -> Self {
+        ByteSource::from_buf_read(bytes)
+    }
+}
+
+/// The bytes that were fed to a lexer and not read yet (see `JSONLexer::new_feed`). A read
+/// returns 0 when the buffer is empty: this is the end of the current chunk, not of the document.
+#[derive(Debug, Default)]
+pub struct FeedBuffer {
+    bytes: VecDeque<u8>,
+}
+
+impl FeedBuffer {
+    pub fn push(&mut self, chunk: &[u8]) {
+        self.bytes.extend(chunk);
+    }
+}
+
+impl Read for FeedBuffer {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.bytes.read(buf)
+    }
+}
+
+#[cfg(feature = "gzip")]
+impl<R: Read> ByteSource<flate2::read::MultiGzDecoder<R>> {
+    /// A source that decompresses a gzip stream (requires the `gzip` feature). The members of a
+    /// multi-member file are read one after the other. The offset counts the decompressed bytes.
+    pub fn new_gzip(source: R) -> Self {
+        ByteSource::new(flate2::read::MultiGzDecoder::new(source))
+    }
+}
+
+#[cfg(feature = "zstd")]
+impl<R: Read> ByteSource<zstd::stream::read::Decoder<'static, io::BufReader<R>>> {
+    /// A source that decompresses a zstd stream (requires the `zstd` feature). The offset counts
+    /// the decompressed bytes.
+    pub fn new_zstd(source: R) -> io::Result<Self> {
+        Ok(ByteSource::new(zstd::stream::read::Decoder::new(source)?))
+    }
+}
+
+/// A file mapped in memory, read without `read` calls nor copies (requires the `mmap` feature).
+#[cfg(feature = "mmap")]
+pub struct MmapFile {
+    mmap: memmap2::Mmap,
+    pos: usize,
+}
+
+#[cfg(feature = "mmap")]
+impl MmapFile {
+    /// Map the file. The file must not be modified while it is mapped: the content of the map
+    /// would change under the parser.
+    pub fn open(file: &std::fs::File) -> io::Result<Self> {
+        // safety: see above
+        let mmap = unsafe { memmap2::Mmap::map(file)? };
+        Ok(MmapFile { mmap, pos: 0 })
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl Read for MmapFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = (&self.mmap[self.pos..]).read(buf)?;
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl BufRead for MmapFile {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(&self.mmap[self.pos..])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.mmap.len());
+    }
+}
+
+#[cfg(feature = "mmap")]
+impl ByteSource<MmapFile> {
+    /// A source that reads a memory map of the file (see `MmapFile::open`).
+    pub fn from_mmap(file: &std::fs::File) -> io::Result<Self> {
+        Ok(ByteSource::from_buf_read(MmapFile::open(file)?))
     }
 }
 
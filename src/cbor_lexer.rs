@@ -0,0 +1,400 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Read a CBOR (RFC 8949) data item and send the same `ParserToken`s as a `JSONParser`, hence
+//! every consumer of JSON documents also accepts CBOR. See `json2cbor` for the reverse
+//! conversion.
+
+use std::io;
+use std::io::Read;
+
+use crate::byte_source::ByteSource;
+use crate::json2cbor::{BREAK, INDEFINITE, MAJOR_ARRAY, MAJOR_BYTES, MAJOR_MAP, MAJOR_NEGATIVE, MAJOR_SIMPLE, MAJOR_TAG, MAJOR_TEXT, MAJOR_UNSIGNED, TAG_NEGATIVE_BIGNUM, TAG_POSITIVE_BIGNUM};
+use crate::json_lexer::{ConsumeError, LexErrorKind, Span};
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParseErrorKind, ParserToken};
+
+/// The reason why the lexing stopped.
+enum LexFailure {
+    /// The input is not a valid CBOR item, or can't be converted
+    Invalid(String),
+    Read(io::Error),
+    Consume(ConsumeError),
+}
+
+impl From<ConsumeError> for LexFailure {
+    fn from(e: ConsumeError) -> Self {
+        LexFailure::Consume(e)
+    }
+}
+
+/// An open array or map.
+struct Container {
+    map: bool,
+    /// The number of elements or pairs that were not read, None for an indefinite length
+    remaining: Option<u64>,
+    /// The next item of a map is a key
+    expect_key: bool,
+}
+
+/// The head of a data item: the major type, the additional information and the argument.
+struct Head {
+    major: u8,
+    info: u8,
+    arg: u64,
+}
+
+impl Head {
+    fn indefinite(&self) -> bool {
+        self.info == INDEFINITE
+    }
+}
+
+/// A lexer of CBOR data items. The items are converted to their JSON counterparts:
+/// * the integers and the bignums (tags 2 and 3) give an `IntValue`, the floats a `FloatValue`,
+///   except NaN and the infinites that give a `NullValue`;
+/// * the text strings give a `StringValue`, or a `StringStart`, `StringChunk`s and a `StringEnd`
+///   if the string has an indefinite length. The byte strings give a `BytesValue`;
+/// * the keys of a map must be text strings or integers;
+/// * `undefined` gives a `NullValue` and the other tags are ignored.
+///
+/// There is no line in a binary input: the column of an error is the offset of the item.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::cbor_lexer::CBORLexer;
+/// # use r_json_event_parser::json_writer::JSONWriteConsumer;
+/// let cbor = [0xa1, 0x61, b'a', 0x82, 0x01, 0xf9, 0x3e, 0x00];
+/// let mut consumer = JSONWriteConsumer::new(vec!());
+/// CBORLexer::new(ByteSource::new(&cbor[..])).parse(&mut consumer).unwrap();
+/// assert_eq!(br#"{"a":[1,1.5]}"#.to_vec(), consumer.into_inner());
+/// ```
+pub struct CBORLexer<R: Read> {
+    byte_source: ByteSource<R>,
+    multi_document: bool,
+    /// The offset of the current item
+    item_start: usize,
+}
+
+impl<R: Read> CBORLexer<R> {
+    pub fn new(byte_source: ByteSource<R>) -> Self {
+        CBORLexer {
+            byte_source,
+            multi_document: false,
+            item_start: 0,
+        }
+    }
+
+    /// Accept a CBOR sequence (RFC 8742). Each item is a document: the consumer receives an
+    /// `EndFile` and a `BeginFile` between two items.
+    pub fn multi_document(mut self, multi_document: bool) -> Self {
+        self.multi_document = multi_document;
+        self
+    }
+
+    /// The number of bytes consumed so far.
+    pub fn byte_offset(&self) -> usize {
+        self.byte_source.offset()
+    }
+
+    pub fn parse<C: JSONParseConsumer>(&mut self, consumer: &mut C) -> Result<(), ConsumeError> {
+        let e = match self.parse_items(consumer) {
+            Ok(()) => return Ok(()),
+            Err(e) => e,
+        };
+        let column = self.item_start;
+        let span = Span { start: column, end: self.byte_source.offset(), line: 0, column };
+        match e {
+            LexFailure::Consume(e) => Err(e),
+            LexFailure::Invalid(msg) => {
+                let error = JSONParseError { kind: ParseErrorKind::Other, msg: msg.clone(), line: 0, column };
+                consumer.consume_with_span(Err(error), span)?;
+                Err(ConsumeError { msg, line: 0, column, io_error: None })
+            }
+            LexFailure::Read(e) => {
+                let msg = format!("read error {:?}", e.kind());
+                let error = JSONParseError { kind: ParseErrorKind::Lex(LexErrorKind::Io(e.kind())), msg: msg.clone(), line: 0, column };
+                match consumer.consume_with_span(Err(error), span) {
+                    Err(consume_error) if consume_error.io_error.is_some() => Err(consume_error),
+                    _ => Err(ConsumeError { msg, line: 0, column, io_error: Some(e) }),
+                }
+            }
+        }
+    }
+
+    fn parse_items<C: JSONParseConsumer>(&mut self, consumer: &mut C) -> Result<(), LexFailure> {
+        self.emit(consumer, ParserToken::BeginFile)?;
+        let mut first = true;
+        while self.byte_source.peek().map_err(LexFailure::Read)?.is_some() {
+            if !first {
+                self.item_start = self.byte_source.offset();
+                if !self.multi_document {
+                    return Err(LexFailure::Invalid("Data after the root item".into()));
+                }
+                self.emit(consumer, ParserToken::EndFile)?;
+                self.emit(consumer, ParserToken::BeginFile)?;
+            }
+            first = false;
+            self.parse_item(consumer)?;
+        }
+        self.emit(consumer, ParserToken::EndFile)
+    }
+
+    /// Read a root item and its children.
+    fn parse_item<C: JSONParseConsumer>(&mut self, consumer: &mut C) -> Result<(), LexFailure> {
+        let mut stack: Vec<Container> = vec!();
+        loop {
+            self.item_start = self.byte_source.offset();
+            let mut head = self.read_head()?;
+            if head.major == MAJOR_SIMPLE && head.indefinite() {
+                match stack.last() {
+                    Some(c) if c.remaining.is_none() && (!c.map || c.expect_key) => {
+                        let token = if c.map { ParserToken::EndObject } else { ParserToken::EndArray };
+                        stack.pop();
+                        self.emit(consumer, token)?;
+                    }
+                    _ => return Err(LexFailure::Invalid("Unexpected break".into())),
+                }
+            } else {
+                let mut tag = None;
+                while head.major == MAJOR_TAG {
+                    tag = Some(head.arg);
+                    head = self.read_head()?;
+                }
+                let is_key = match stack.last_mut() {
+                    Some(c) if c.map && c.expect_key => {
+                        c.expect_key = false;
+                        true
+                    }
+                    Some(c) => {
+                        c.expect_key = c.map;
+                        c.remaining = c.remaining.map(|n| n - 1);
+                        false
+                    }
+                    None => false,
+                };
+                if let Some(container) = self.parse_value(consumer, head, tag, is_key)? {
+                    stack.push(container);
+                }
+            }
+            loop {
+                match stack.last() {
+                    None => return Ok(()),
+                    Some(c) if c.remaining == Some(0) => {
+                        let token = if c.map { ParserToken::EndObject } else { ParserToken::EndArray };
+                        stack.pop();
+                        self.emit(consumer, token)?;
+                    }
+                    Some(_) => break,
+                }
+            }
+        }
+    }
+
+    /// Read the rest of an item and send the tokens. Returns the container that was opened, if
+    /// any.
+    fn parse_value<C: JSONParseConsumer>(&mut self, consumer: &mut C, head: Head, tag: Option<u64>, is_key: bool) -> Result<Option<Container>, LexFailure> {
+        let token = match (head.major, tag) {
+            (_, Some(tag @ TAG_POSITIVE_BIGNUM)) | (_, Some(tag @ TAG_NEGATIVE_BIGNUM)) => {
+                if head.major != MAJOR_BYTES {
+                    return Err(LexFailure::Invalid("A bignum must be a byte string".into()));
+                }
+                let magnitude = self.read_string(&head)?;
+                let decimal = bytes_to_decimal(&magnitude, tag == TAG_NEGATIVE_BIGNUM);
+                if is_key { ParserToken::Key(decimal) } else { ParserToken::IntValue(decimal) }
+            }
+            (MAJOR_UNSIGNED, _) | (MAJOR_NEGATIVE, _) => {
+                let n = if head.major == MAJOR_UNSIGNED { head.arg as i128 } else { -1 - head.arg as i128 };
+                if is_key { ParserToken::Key(n.to_string()) } else { ParserToken::IntValue(n.to_string()) }
+            }
+            (MAJOR_TEXT, _) if is_key => ParserToken::Key(self.read_text(&head)?),
+            (MAJOR_TEXT, _) if head.indefinite() => {
+                self.emit(consumer, ParserToken::StringStart)?;
+                while let Some(chunk) = self.read_chunk(MAJOR_TEXT)? {
+                    let chunk = self.check_utf8(chunk)?.into_bytes();
+                    self.emit(consumer, ParserToken::StringChunk(chunk))?;
+                }
+                ParserToken::StringEnd
+            }
+            (MAJOR_TEXT, _) => ParserToken::StringValue(self.read_text(&head)?),
+            _ if is_key => return Err(LexFailure::Invalid("A key must be a text string or an integer".into())),
+            (MAJOR_BYTES, _) => ParserToken::BytesValue(self.read_string(&head)?),
+            (MAJOR_ARRAY, _) | (MAJOR_MAP, _) => {
+                let map = head.major == MAJOR_MAP;
+                let token = if map { ParserToken::BeginObject } else { ParserToken::BeginArray };
+                self.emit(consumer, token)?;
+                let remaining = if head.indefinite() { None } else { Some(head.arg) };
+                return Ok(Some(Container { map, remaining, expect_key: map }));
+            }
+            _ => match head.info {
+                20 => ParserToken::BooleanValue(false),
+                21 => ParserToken::BooleanValue(true),
+                22 | 23 => ParserToken::NullValue,
+                25 => float_token(f16_to_f32(head.arg as u16) as f64, format!("{:?}", f16_to_f32(head.arg as u16))),
+                26 => float_token(f32::from_bits(head.arg as u32) as f64, format!("{:?}", f32::from_bits(head.arg as u32))),
+                27 => float_token(f64::from_bits(head.arg), format!("{:?}", f64::from_bits(head.arg))),
+                _ => return Err(LexFailure::Invalid(format!("Unsupported simple value {}", head.arg))),
+            },
+        };
+        self.emit(consumer, token)?;
+        Ok(None)
+    }
+
+    fn emit<C: JSONParseConsumer>(&mut self, consumer: &mut C, token: ParserToken) -> Result<(), LexFailure> {
+        let span = Span { start: self.item_start, end: self.byte_source.offset(), line: 0, column: self.item_start };
+        consumer.consume_with_span(Ok(token), span)?;
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> Result<u8, LexFailure> {
+        match self.byte_source.get() {
+            Ok(Some(byte)) => Ok(byte),
+            Ok(None) => Err(LexFailure::Invalid("Unexpected end of the input".into())),
+            Err(e) => Err(LexFailure::Read(e)),
+        }
+    }
+
+    fn read_head(&mut self) -> Result<Head, LexFailure> {
+        let byte = self.read_byte()?;
+        let (major, info) = (byte >> 5, byte & 0x1f);
+        let arg = match info {
+            0..=23 => info as u64,
+            24..=27 => {
+                let mut arg = 0u64;
+                for _ in 0..1 << (info - 24) {
+                    arg = arg << 8 | self.read_byte()? as u64;
+                }
+                arg
+            }
+            INDEFINITE if major == MAJOR_BYTES || major == MAJOR_TEXT || major == MAJOR_ARRAY
+                || major == MAJOR_MAP || byte == BREAK => 0,
+            _ => return Err(LexFailure::Invalid(format!("Invalid initial byte 0x{:02x}", byte))),
+        };
+        Ok(Head { major, info, arg })
+    }
+
+    /// Read the next chunk of an indefinite length string, None after the last chunk.
+    fn read_chunk(&mut self, major: u8) -> Result<Option<Vec<u8>>, LexFailure> {
+        let head = self.read_head()?;
+        if head.major == MAJOR_SIMPLE && head.indefinite() {
+            Ok(None)
+        } else if head.major == major && !head.indefinite() {
+            self.read_bytes(head.arg).map(Some)
+        } else {
+            Err(LexFailure::Invalid("Invalid chunk of an indefinite length string".into()))
+        }
+    }
+
+    /// Read the bytes of a byte or text string of a definite or indefinite length.
+    fn read_string(&mut self, head: &Head) -> Result<Vec<u8>, LexFailure> {
+        if !head.indefinite() {
+            return self.read_bytes(head.arg);
+        }
+        let mut bytes = vec!();
+        while let Some(chunk) = self.read_chunk(head.major)? {
+            bytes.extend(chunk);
+        }
+        Ok(bytes)
+    }
+
+    fn read_text(&mut self, head: &Head) -> Result<String, LexFailure> {
+        let bytes = self.read_string(head)?;
+        self.check_utf8(bytes)
+    }
+
+    fn check_utf8(&self, bytes: Vec<u8>) -> Result<String, LexFailure> {
+        String::from_utf8(bytes).map_err(|_| LexFailure::Invalid("Invalid UTF-8 in a text string".into()))
+    }
+
+    fn read_bytes(&mut self, len: u64) -> Result<Vec<u8>, LexFailure> {
+        let mut bytes = vec!();
+        while (bytes.len() as u64) < len {
+            let slice = self.byte_source.peek_slice().map_err(LexFailure::Read)?;
+            if slice.is_empty() {
+                bytes.push(self.read_byte()?);
+            } else {
+                let n = slice.len().min((len - bytes.len() as u64) as usize);
+                bytes.extend_from_slice(&slice[..n]);
+                self.byte_source.consume(n).map_err(LexFailure::Read)?;
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+/// The JSON counterpart of a float: NaN and the infinites are not JSON numbers.
+fn float_token(v: f64, lexeme: String) -> ParserToken {
+    if v.is_finite() {
+        ParserToken::FloatValue(lexeme)
+    } else {
+        ParserToken::NullValue
+    }
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = (half >> 10 & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+    sign * match exponent {
+        0 => mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        e => (1.0 + mantissa / 1024.0) * 2f32.powi(e - 15),
+    }
+}
+
+/// The decimal representation of a bignum: `n` or `-1 - n` for a negative bignum.
+fn bytes_to_decimal(magnitude: &[u8], negative: bool) -> String {
+    let mut limbs: Vec<u32> = vec!(); // base 10^9, little endian
+    for &b in magnitude {
+        let mut carry = b as u64;
+        for limb in limbs.iter_mut() {
+            let v = *limb as u64 * 256 + carry;
+            *limb = (v % 1_000_000_000) as u32;
+            carry = v / 1_000_000_000;
+        }
+        while carry > 0 {
+            limbs.push((carry % 1_000_000_000) as u32);
+            carry /= 1_000_000_000;
+        }
+    }
+    if negative {
+        let mut carry = 1;
+        for limb in limbs.iter_mut() {
+            let v = *limb + carry;
+            *limb = v % 1_000_000_000;
+            carry = v / 1_000_000_000;
+        }
+        if carry > 0 {
+            limbs.push(carry);
+        }
+    }
+    let mut decimal = String::from(if negative { "-" } else { "" });
+    match limbs.split_last() {
+        None => decimal.push('0'),
+        Some((last, rest)) => {
+            decimal.push_str(&last.to_string());
+            for limb in rest.iter().rev() {
+                decimal.push_str(&format!("{:09}", limb));
+            }
+        }
+    }
+    decimal
+}
@@ -0,0 +1,269 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A C API over `JSONFeedParser` (see `include/r_json_event_parser.h`):
+//!
+//! ```c
+//! jep_parser *parser = jep_parser_new(0);
+//! jep_parser_set_callback(parser, on_token, &state);
+//! while ((n = fread(buf, 1, sizeof buf, f)) > 0 && jep_parser_feed(parser, buf, n) == 0) {}
+//! if (jep_parser_finish(parser) != 0) {
+//!     fprintf(stderr, "%s at %zu:%zu\n", jep_parser_error_message(parser),
+//!             jep_parser_error_line(parser), jep_parser_error_column(parser));
+//! }
+//! jep_parser_free(parser);
+//! ```
+//!
+//! The callback receives the type of the token and its text, which is not NUL-terminated: the
+//! key or the string, the number as written in the document, `true`, `false` or `null`. The text
+//! is only valid during the call. A non-zero return value stops the parser.
+//!
+//! The crate is a Rust library by default. Build the C library with
+//! `cargo rustc --release --lib --features ffi --crate-type cdylib` (or `staticlib`).
+
+use std::ffi::CString;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::slice;
+
+use crate::json_lexer::{ConsumeError, LexerOptions};
+use crate::json_parser::{JSONFeedParser, JSONParseConsumer, JSONParseError, ParserToken};
+
+/// The type of a token, the first argument of the callback.
+#[repr(C)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JepTokenType {
+    BeginFile = 0,
+    EndFile = 1,
+    BeginObject = 2,
+    EndObject = 3,
+    BeginArray = 4,
+    EndArray = 5,
+    Key = 6,
+    Boolean = 7,
+    Null = 8,
+    String = 9,
+    /// A string that is not valid UTF-8 (see `InvalidUtf8Policy::Bytes`)
+    Bytes = 10,
+    Int = 11,
+    Float = 12,
+    StringStart = 13,
+    StringChunk = 14,
+    StringEnd = 15,
+}
+
+/// `int callback(void *user_data, jep_token_type token_type, const char *data, size_t len)`
+pub type JepCallback = extern "C" fn(*mut c_void, JepTokenType, *const c_char, usize) -> c_int;
+
+/// The category of the last error.
+#[repr(C)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum JepErrorKind {
+    None = 0,
+    /// The document is not valid
+    Parse = 1,
+    /// The callback returned a non-zero value
+    Stopped = 2,
+    /// No callback was registered
+    NoCallback = 3,
+}
+
+struct JepError {
+    kind: JepErrorKind,
+    msg: CString,
+    line: usize,
+    column: usize,
+}
+
+impl JepError {
+    fn new(kind: JepErrorKind, msg: &str, line: usize, column: usize) -> Self {
+        let msg = CString::new(msg.replace('\0', "\\u0000")).unwrap_or_default();
+        JepError { kind, msg, line, column }
+    }
+}
+
+pub struct JepParser {
+    parser: JSONFeedParser,
+    callback: Option<(JepCallback, *mut c_void)>,
+    error: Option<JepError>,
+}
+
+struct CallbackConsumer<'a> {
+    callback: JepCallback,
+    user_data: *mut c_void,
+    error: &'a mut Option<JepError>,
+}
+
+impl JSONParseConsumer for CallbackConsumer<'_> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let error = match token {
+            Ok(token) => {
+                let (token_type, data): (JepTokenType, &[u8]) = match &token {
+                    ParserToken::BeginFile => (JepTokenType::BeginFile, &[]),
+                    ParserToken::EndFile => (JepTokenType::EndFile, &[]),
+                    ParserToken::BeginObject => (JepTokenType::BeginObject, &[]),
+                    ParserToken::EndObject => (JepTokenType::EndObject, &[]),
+                    ParserToken::BeginArray => (JepTokenType::BeginArray, &[]),
+                    ParserToken::EndArray => (JepTokenType::EndArray, &[]),
+                    ParserToken::Key(s) => (JepTokenType::Key, s.as_bytes()),
+                    ParserToken::BooleanValue(true) => (JepTokenType::Boolean, b"true"),
+                    ParserToken::BooleanValue(false) => (JepTokenType::Boolean, b"false"),
+                    ParserToken::NullValue => (JepTokenType::Null, b"null"),
+                    ParserToken::StringValue(s) => (JepTokenType::String, s.as_bytes()),
+                    ParserToken::BytesValue(bytes) => (JepTokenType::Bytes, bytes),
+                    ParserToken::IntValue(s) => (JepTokenType::Int, s.as_bytes()),
+                    ParserToken::FloatValue(s) => (JepTokenType::Float, s.as_bytes()),
+                    ParserToken::StringStart => (JepTokenType::StringStart, &[]),
+                    ParserToken::StringChunk(bytes) => (JepTokenType::StringChunk, bytes),
+                    ParserToken::StringEnd => (JepTokenType::StringEnd, &[]),
+                };
+                let data_ptr = if data.is_empty() { ptr::null() } else { data.as_ptr() as *const c_char };
+                if (self.callback)(self.user_data, token_type, data_ptr, data.len()) == 0 {
+                    return Ok(());
+                }
+                JepError::new(JepErrorKind::Stopped, "Stopped by the callback", 0, 0)
+            }
+            Err(e) => JepError::new(JepErrorKind::Parse, &e.msg, e.line, e.column),
+        };
+        *self.error = Some(error);
+        Err(ConsumeError { msg: "Parse stopped".into(), line: 0, column: 0, io_error: None })
+    }
+}
+
+impl JepParser {
+    fn run<F>(&mut self, f: F) -> c_int
+        where F: FnOnce(&mut JSONFeedParser, &mut CallbackConsumer) -> Result<(), ConsumeError> {
+        if self.error.is_some() {
+            return -1;
+        }
+        let (callback, user_data) = match self.callback {
+            Some(c) => c,
+            None => {
+                self.error = Some(JepError::new(JepErrorKind::NoCallback, "No callback", 0, 0));
+                return -1;
+            }
+        };
+        let mut consumer = CallbackConsumer { callback, user_data, error: &mut self.error };
+        if let Err(e) = f(&mut self.parser, &mut consumer) {
+            if self.error.is_none() {
+                self.error = Some(JepError::new(JepErrorKind::Parse, &e.msg, e.line, e.column));
+            }
+            return -1;
+        }
+        0
+    }
+}
+
+/// Create a parser. A non-zero `strict` sets `LexerOptions::strict`. The parser must be released
+/// with `jep_parser_free`.
+#[no_mangle]
+pub extern "C" fn jep_parser_new(strict: c_int) -> *mut JepParser {
+    let options = LexerOptions { strict: strict != 0, ..LexerOptions::default() };
+    Box::into_raw(Box::new(JepParser { parser: JSONFeedParser::new(options), callback: None, error: None }))
+}
+
+/// # Safety
+/// `parser` must come from `jep_parser_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jep_parser_set_callback(parser: *mut JepParser, callback: JepCallback, user_data: *mut c_void) {
+    if let Some(parser) = parser.as_mut() {
+        parser.callback = Some((callback, user_data));
+    }
+}
+
+/// Parse a chunk of the document. Return 0, or -1 on error (see `jep_parser_error_message`):
+/// the next calls are then ignored and return -1.
+///
+/// # Safety
+/// `parser` must come from `jep_parser_new` and not be freed yet; `data` must point to `len`
+/// readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn jep_parser_feed(parser: *mut JepParser, data: *const u8, len: usize) -> c_int {
+    let parser = match parser.as_mut() {
+        Some(parser) => parser,
+        None => return -1,
+    };
+    let chunk = if len == 0 { &[][..] } else { slice::from_raw_parts(data, len) };
+    parser.run(|p, c| p.feed(chunk, c))
+}
+
+/// Parse the end of the document. Return 0, or -1 on error.
+///
+/// # Safety
+/// `parser` must come from `jep_parser_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jep_parser_finish(parser: *mut JepParser) -> c_int {
+    match parser.as_mut() {
+        Some(parser) => parser.run(|p, c| p.finish(c)),
+        None => -1,
+    }
+}
+
+/// The kind of the last error, `JEP_ERROR_NONE` if there is no error.
+///
+/// # Safety
+/// `parser` must come from `jep_parser_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jep_parser_error_kind(parser: *const JepParser) -> JepErrorKind {
+    parser.as_ref().and_then(|p| p.error.as_ref()).map_or(JepErrorKind::None, |e| e.kind)
+}
+
+/// The NUL-terminated message of the last error, `NULL` if there is no error. The message is
+/// owned by the parser.
+///
+/// # Safety
+/// `parser` must come from `jep_parser_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jep_parser_error_message(parser: *const JepParser) -> *const c_char {
+    parser.as_ref().and_then(|p| p.error.as_ref()).map_or(ptr::null(), |e| e.msg.as_ptr())
+}
+
+/// # Safety
+/// `parser` must come from `jep_parser_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jep_parser_error_line(parser: *const JepParser) -> usize {
+    parser.as_ref().and_then(|p| p.error.as_ref()).map_or(0, |e| e.line)
+}
+
+/// # Safety
+/// `parser` must come from `jep_parser_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jep_parser_error_column(parser: *const JepParser) -> usize {
+    parser.as_ref().and_then(|p| p.error.as_ref()).map_or(0, |e| e.column)
+}
+
+/// The number of bytes consumed so far.
+///
+/// # Safety
+/// `parser` must come from `jep_parser_new` and not be freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn jep_parser_byte_offset(parser: *const JepParser) -> usize {
+    parser.as_ref().map_or(0, |p| p.parser.byte_offset())
+}
+
+/// # Safety
+/// `parser` must come from `jep_parser_new` or be `NULL`; it must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn jep_parser_free(parser: *mut JepParser) {
+    if !parser.is_null() {
+        drop(Box::from_raw(parser));
+    }
+}
@@ -0,0 +1,92 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{fmt, io, str};
+use std::io::ErrorKind;
+
+/// An `io::Write` backed by a `fmt::Write` (e.g. a `String` or a `&mut String`), to plug the
+/// writer consumers into in-memory buffers.
+///
+/// A multibyte char may be split between two calls to `write`: the incomplete bytes are kept
+/// until the next call.
+pub struct FmtWriter<F: fmt::Write> {
+    destination: F,
+    pending: Vec<u8>,
+}
+
+impl<F: fmt::Write> FmtWriter<F> {
+    pub fn new(destination: F) -> Self {
+        FmtWriter {
+            destination,
+            pending: vec!(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &F {
+        &self.destination
+    }
+
+    pub fn into_inner(self) -> F {
+        self.destination
+    }
+
+    fn write_str(&mut self, s: &str) -> io::Result<()> {
+        self.destination.write_str(s).map_err(|_| io::Error::other("formatter error"))
+    }
+}
+
+impl<F: fmt::Write> io::Write for FmtWriter<F> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let bytes: Vec<u8>;
+        let to_decode = if self.pending.is_empty() {
+            buf
+        } else {
+            bytes = [&self.pending[..], buf].concat();
+            &bytes[..]
+        };
+        // the pending bytes are kept until they are written
+        match str::from_utf8(to_decode) {
+            Ok(s) => {
+                self.write_str(s)?;
+                self.pending.clear();
+            }
+            Err(e) => {
+                let valid_up_to = e.valid_up_to();
+                if e.error_len().is_some() {
+                    return Err(io::Error::new(ErrorKind::InvalidData, e));
+                }
+                // Safe: the bytes are valid up to `valid_up_to`
+                let s = str::from_utf8(&to_decode[..valid_up_to]).unwrap();
+                self.write_str(s)?;
+                self.pending = to_decode[valid_up_to..].to_vec();
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.pending.is_empty() {
+            Ok(())
+        } else {
+            Err(io::Error::new(ErrorKind::InvalidData, "incomplete utf-8 byte sequence"))
+        }
+    }
+}
@@ -0,0 +1,515 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, io};
+use std::io::Read;
+
+/// true if the path is an http(s) URL.
+pub fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// Open the input of a binary: `-` is stdin, an http(s) URL is streamed from the response body
+/// (requires the `http` feature; gzip encoded bodies are decoded), anything else is a file.
+/// UTF-16 and UTF-32 inputs are decoded (see `DecodingReader`).
+pub fn open_input(path: &str) -> io::Result<Box<dyn Read>> {
+    Ok(Box::new(DecodingReader::new(open_raw_input(path)?)))
+}
+
+/// Like `open_input`, but a gzip or zstd compressed input is decompressed (see `decompress`).
+pub fn open_compressed_input(path: &str) -> io::Result<Box<dyn Read>> {
+    let read = decompress(open_raw_input(path)?, Compression::Auto)?;
+    Ok(Box::new(DecodingReader::new(read)))
+}
+
+fn open_raw_input(path: &str) -> io::Result<Box<dyn Read>> {
+    Ok(if path == "-" {
+        Box::new(io::stdin())
+    } else if is_url(path) {
+        open_url(path)?
+    } else {
+        Box::new(fs::File::open(path)?)
+    })
+}
+
+/// The compressions of an input.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Compression {
+    /// Guess the compression from the magic bytes (see `detect_compression`).
+    Auto,
+    None,
+    Gzip,
+    Zstd,
+}
+
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+/// Guess the compression from the first bytes of the input. Never returns `Compression::Auto`.
+pub fn detect_compression(prefix: &[u8]) -> Compression {
+    if prefix.starts_with(GZIP_MAGIC) {
+        Compression::Gzip
+    } else if prefix.starts_with(ZSTD_MAGIC) {
+        Compression::Zstd
+    } else {
+        Compression::None
+    }
+}
+
+/// Wrap the input in a decompressor. The gzip inputs require the `gzip` feature and the zstd
+/// inputs the `zstd` feature, otherwise this is an `Unsupported` error.
+pub fn decompress<'a, R: Read + 'a>(mut read: R, compression: Compression) -> io::Result<Box<dyn Read + 'a>> {
+    if compression != Compression::Auto {
+        return decoder(read, compression);
+    }
+    let mut prefix = vec!();
+    (&mut read).take(ZSTD_MAGIC.len() as u64).read_to_end(&mut prefix)?;
+    let compression = detect_compression(&prefix);
+    decoder(io::Cursor::new(prefix).chain(read), compression)
+}
+
+fn decoder<'a, R: Read + 'a>(read: R, compression: Compression) -> io::Result<Box<dyn Read + 'a>> {
+    match compression {
+        Compression::Auto | Compression::None => Ok(Box::new(read)),
+        Compression::Gzip => gzip_decoder(read),
+        Compression::Zstd => zstd_decoder(read),
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_decoder<'a, R: Read + 'a>(read: R) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(Box::new(flate2::read::MultiGzDecoder::new(read)))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_decoder<'a, R: Read + 'a>(_read: R) -> io::Result<Box<dyn Read + 'a>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "Gzip inputs require the `gzip` feature"))
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_decoder<'a, R: Read + 'a>(read: R) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(Box::new(zstd::stream::read::Decoder::new(read)?))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_decoder<'a, R: Read + 'a>(_read: R) -> io::Result<Box<dyn Read + 'a>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "Zstd inputs require the `zstd` feature"))
+}
+
+/// The memory map of the input if it is a regular UTF-8 file, without the BOM (requires the
+/// `mmap` feature).
+#[cfg(feature = "mmap")]
+pub fn map_input(path: &str) -> Option<crate::byte_source::MmapFile> {
+    use std::io::BufRead;
+    if path == "-" || is_url(path) {
+        return None;
+    }
+    let file = fs::File::open(path).ok()?;
+    if !file.metadata().ok()?.is_file() {
+        return None;
+    }
+    let mut mmap = crate::byte_source::MmapFile::open(&file).ok()?;
+    match detect_encoding(mmap.fill_buf().ok()?) {
+        (Encoding::Utf8, bom_len) => {
+            mmap.consume(bom_len);
+            Some(mmap)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(feature = "http")]
+fn open_url(url: &str) -> io::Result<Box<dyn Read>> {
+    match ureq::get(url).call() {
+        Ok(response) => Ok(Box::new(response.into_reader())),
+        Err(e) => Err(io::Error::other(format!("Can't read `{}`: {}", url, e))),
+    }
+}
+
+#[cfg(not(feature = "http"))]
+fn open_url(url: &str) -> io::Result<Box<dyn Read>> {
+    Err(io::Error::new(io::ErrorKind::Unsupported,
+                       format!("Can't read `{}`: URLs require the `http` feature", url)))
+}
+
+const RECORD_SEPARATOR: u8 = 0x1e;
+const SNIFF_SIZE: usize = 64 * 1024;
+
+/// The flavors of JSON input.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum InputFormat {
+    /// A single JSON document.
+    Json,
+    /// One document per line (NDJSON / JSON Lines).
+    JsonLines,
+    /// Documents preceded by a RS byte (RFC 7464).
+    JsonSeq,
+}
+
+/// The index after the first value of the prefix, or None if the value is not complete.
+fn end_of_first_value(prefix: &[u8], start: usize) -> Option<usize> {
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escape = false;
+    for (i, &b) in prefix.iter().enumerate().skip(start) {
+        if in_string {
+            if escape {
+                escape = false;
+            } else if b == b'\\' {
+                escape = true;
+            } else if b == b'"' {
+                in_string = false;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            continue;
+        }
+        match b {
+            b'"' => in_string = true,
+            b'{' | b'[' => depth += 1,
+            b'}' | b']' => {
+                depth = depth.checked_sub(1)?;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            b' ' | b'\t' | b'\r' | b'\n' | b',' | b':' if depth == 0 => return Some(i),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Guess the format from the first bytes of the input: a RS byte announces a JSON text
+/// sequence, a second value after a newline announces JSON Lines.
+pub fn sniff_format(prefix: &[u8]) -> InputFormat {
+    let is_ws = |b: &u8| b" \t\r\n".contains(b);
+    let start = match prefix.iter().position(|b| !is_ws(b)) {
+        Some(start) => start,
+        None => return InputFormat::Json,
+    };
+    if prefix[start] == RECORD_SEPARATOR {
+        return InputFormat::JsonSeq;
+    }
+    let end = match end_of_first_value(prefix, start) {
+        Some(end) => end,
+        None => return InputFormat::Json,
+    };
+    let rest = &prefix[end..];
+    match rest.iter().position(|b| !is_ws(b)) {
+        Some(i) if rest[..i].contains(&b'\n') => InputFormat::JsonLines,
+        _ => InputFormat::Json,
+    }
+}
+
+/// The sniffed bytes followed by the rest of the input.
+pub type SniffedInput<R> = io::Chain<io::Cursor<Vec<u8>>, R>;
+
+/// Read the first bytes of the input to guess its format. The returned reader yields the
+/// whole input.
+pub fn sniff_input<R: Read>(mut read: R) -> io::Result<(InputFormat, SniffedInput<R>)> {
+    let mut prefix = vec!();
+    (&mut read).take(SNIFF_SIZE as u64).read_to_end(&mut prefix)?;
+    let format = sniff_format(&prefix);
+    Ok((format, io::Cursor::new(prefix).chain(read)))
+}
+
+/// A reader that presents a stream of documents (JSON Lines or JSON text sequence) as a
+/// single JSON array, so that the parser can read it. The newlines are kept, hence the line
+/// numbers of the errors are still valid.
+pub struct DocumentsAsArray<R: Read> {
+    read: R,
+    separator: u8,
+    started: bool,
+    finished: bool,
+    in_document: bool,
+    documents: usize,
+    out: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> DocumentsAsArray<R> {
+    pub fn new(read: R, format: InputFormat) -> Self {
+        DocumentsAsArray {
+            read,
+            separator: if format == InputFormat::JsonSeq { RECORD_SEPARATOR } else { b'\n' },
+            started: false,
+            finished: false,
+            in_document: false,
+            documents: 0,
+            out: vec!(),
+            pos: 0,
+        }
+    }
+
+    fn fill(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; 8192];
+        self.out.clear();
+        self.pos = 0;
+        if !self.started {
+            self.started = true;
+            self.out.push(b'[');
+        }
+        let n = self.read.read(&mut chunk)?;
+        if n == 0 {
+            self.finished = true;
+            self.out.push(b']');
+            return Ok(());
+        }
+        for &b in &chunk[..n] {
+            if b == self.separator {
+                self.in_document = false;
+                self.out.push(if b == RECORD_SEPARATOR { b' ' } else { b });
+            } else if b" \t\r\n".contains(&b) {
+                self.out.push(b);
+            } else {
+                if !self.in_document {
+                    if self.documents > 0 {
+                        self.out.push(b',');
+                    }
+                    self.documents += 1;
+                    self.in_document = true;
+                }
+                self.out.push(b);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DocumentsAsArray<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.out.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+        let n = buf.len().min(self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Wrap the input so that the parser reads a single document: the documents of JSON Lines and
+/// JSON text sequences become the elements of an array.
+pub fn as_single_document<'a, R: Read + 'a>(read: R, format: InputFormat) -> Box<dyn Read + 'a> {
+    match format {
+        InputFormat::Json => Box::new(read),
+        _ => Box::new(DocumentsAsArray::new(read, format)),
+    }
+}
+
+/// The encodings of a JSON text (RFC 4627).
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+/// Guess the encoding from the first bytes of the input: a BOM, or the pattern of the null
+/// bytes of the first two chars, that are ASCII (RFC 4627, section 3). Returns the encoding and
+/// the length of the BOM.
+pub fn detect_encoding(prefix: &[u8]) -> (Encoding, usize) {
+    match prefix {
+        [0xEF, 0xBB, 0xBF, ..] => (Encoding::Utf8, 3),
+        [0x00, 0x00, 0xFE, 0xFF, ..] => (Encoding::Utf32Be, 4),
+        [0xFF, 0xFE, 0x00, 0x00, ..] => (Encoding::Utf32Le, 4),
+        [0xFE, 0xFF, ..] => (Encoding::Utf16Be, 2),
+        [0xFF, 0xFE, ..] => (Encoding::Utf16Le, 2),
+        [0x00, 0x00, 0x00, _, ..] => (Encoding::Utf32Be, 0),
+        [_, 0x00, 0x00, 0x00, ..] => (Encoding::Utf32Le, 0),
+        [0x00, _, 0x00, _, ..] | [0x00, _] => (Encoding::Utf16Be, 0),
+        [_, 0x00, _, 0x00, ..] | [_, 0x00] => (Encoding::Utf16Le, 0),
+        _ => (Encoding::Utf8, 0),
+    }
+}
+
+/// A reader that detects the encoding of the input (see `detect_encoding`) and decodes it to
+/// UTF-8, the encoding expected by the lexer. The BOM is removed. An invalid UTF-16 or UTF-32
+/// sequence is an `InvalidData` error.
+pub struct DecodingReader<R: Read> {
+    read: R,
+    /// None before the first read
+    encoding: Option<Encoding>,
+    /// The bytes that were read and not decoded yet
+    pending: Vec<u8>,
+    finished: bool,
+    out: Vec<u8>,
+    pos: usize,
+}
+
+impl<R: Read> DecodingReader<R> {
+    pub fn new(read: R) -> Self {
+        DecodingReader {
+            read,
+            encoding: None,
+            pending: vec!(),
+            finished: false,
+            out: vec!(),
+            pos: 0,
+        }
+    }
+
+    /// The encoding of the input, once the first bytes are read.
+    pub fn encoding(&self) -> Option<Encoding> {
+        self.encoding
+    }
+
+    /// Read some bytes to `pending`; set `finished` at the end of the input.
+    fn read_pending(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; 8192];
+        loop {
+            match self.read.read(&mut chunk) {
+                Ok(0) => {
+                    self.finished = true;
+                    return Ok(());
+                }
+                Ok(n) => {
+                    self.pending.extend_from_slice(&chunk[..n]);
+                    return Ok(());
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    fn detect(&mut self) -> io::Result<Encoding> {
+        while self.pending.len() < 4 && !self.finished {
+            self.read_pending()?;
+        }
+        let (encoding, bom_len) = detect_encoding(&self.pending);
+        self.pending.drain(..bom_len);
+        self.encoding = Some(encoding);
+        Ok(encoding)
+    }
+
+    fn fill(&mut self, encoding: Encoding) -> io::Result<()> {
+        self.out.clear();
+        self.pos = 0;
+        if self.pending.is_empty() || encoding != Encoding::Utf8 {
+            self.read_pending()?;
+        }
+        let decoded = match encoding {
+            Encoding::Utf8 => self.pending.len(),
+            Encoding::Utf16Le => self.decode_utf16(u16::from_le_bytes)?,
+            Encoding::Utf16Be => self.decode_utf16(u16::from_be_bytes)?,
+            Encoding::Utf32Le => self.decode_utf32(u32::from_le_bytes)?,
+            Encoding::Utf32Be => self.decode_utf32(u32::from_be_bytes)?,
+        };
+        if encoding == Encoding::Utf8 {
+            std::mem::swap(&mut self.out, &mut self.pending);
+        } else {
+            self.pending.drain(..decoded);
+        }
+        if self.finished && !self.pending.is_empty() {
+            return Err(invalid_data(format!("Truncated {:?} input", encoding)));
+        }
+        Ok(())
+    }
+
+    fn push_char(&mut self, c: char) {
+        let mut utf8 = [0u8; 4];
+        self.out.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+    }
+
+    /// Decode the complete units of `pending`. Returns the number of bytes decoded.
+    fn decode_utf16(&mut self, unit: fn([u8; 2]) -> u16) -> io::Result<usize> {
+        let pending = std::mem::take(&mut self.pending);
+        let mut i = 0;
+        let result = loop {
+            if i + 2 > pending.len() {
+                break Ok(i);
+            }
+            let high = unit([pending[i], pending[i + 1]]);
+            let c = match high {
+                0xD800..=0xDBFF => {
+                    if i + 4 > pending.len() {
+                        break Ok(i);
+                    }
+                    let low = unit([pending[i + 2], pending[i + 3]]);
+                    if !(0xDC00..=0xDFFF).contains(&low) {
+                        break Err(invalid_data(format!("Invalid UTF-16 surrogate pair `0x{:04x} 0x{:04x}`", high, low)));
+                    }
+                    i += 2;
+                    0x10000 + (((high as u32) - 0xD800) << 10) + ((low as u32) - 0xDC00)
+                }
+                0xDC00..=0xDFFF => break Err(invalid_data(format!("Invalid UTF-16 low surrogate `0x{:04x}`", high))),
+                _ => high as u32,
+            };
+            i += 2;
+            // the code point is valid
+            self.push_char(char::from_u32(c).unwrap_or(REPLACEMENT_CHARACTER));
+        };
+        self.pending = pending;
+        result
+    }
+
+    /// Decode the complete units of `pending`. Returns the number of bytes decoded.
+    fn decode_utf32(&mut self, unit: fn([u8; 4]) -> u32) -> io::Result<usize> {
+        let pending = std::mem::take(&mut self.pending);
+        let mut i = 0;
+        let result = loop {
+            if i + 4 > pending.len() {
+                break Ok(i);
+            }
+            let code_point = unit([pending[i], pending[i + 1], pending[i + 2], pending[i + 3]]);
+            match char::from_u32(code_point) {
+                Some(c) => self.push_char(c),
+                None => break Err(invalid_data(format!("Invalid UTF-32 code point `0x{:x}`", code_point))),
+            }
+            i += 4;
+        };
+        self.pending = pending;
+        result
+    }
+}
+
+const REPLACEMENT_CHARACTER: char = '\u{fffd}';
+
+fn invalid_data(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+impl<R: Read> Read for DecodingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let encoding = match self.encoding {
+            Some(encoding) => encoding,
+            None => self.detect()?,
+        };
+        while self.pos == self.out.len() {
+            if self.finished && self.pending.is_empty() {
+                return Ok(0);
+            }
+            self.fill(encoding)?;
+        }
+        let n = buf.len().min(self.out.len() - self.pos);
+        buf[..n].copy_from_slice(&self.out[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
@@ -0,0 +1,241 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Convert a JSON document to CBOR (RFC 8949), without loading the document. The containers
+//! and the chunked strings are written with an indefinite length, hence the conversion needs no
+//! lookahead. See `cbor_lexer` for the reverse conversion.
+
+use std::io::{Read, Write};
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+
+pub(crate) const MAJOR_UNSIGNED: u8 = 0;
+pub(crate) const MAJOR_NEGATIVE: u8 = 1;
+pub(crate) const MAJOR_BYTES: u8 = 2;
+pub(crate) const MAJOR_TEXT: u8 = 3;
+pub(crate) const MAJOR_ARRAY: u8 = 4;
+pub(crate) const MAJOR_MAP: u8 = 5;
+pub(crate) const MAJOR_TAG: u8 = 6;
+pub(crate) const MAJOR_SIMPLE: u8 = 7;
+/// The additional information of an indefinite length
+pub(crate) const INDEFINITE: u8 = 31;
+pub(crate) const BREAK: u8 = 0xff;
+pub(crate) const TAG_POSITIVE_BIGNUM: u64 = 2;
+pub(crate) const TAG_NEGATIVE_BIGNUM: u64 = 3;
+
+/// Append the head of a data item: the major type and the argument, in the shortest form.
+fn write_head(out: &mut Vec<u8>, major: u8, n: u64) {
+    let major = major << 5;
+    if n < 24 {
+        out.push(major | n as u8);
+    } else if n <= 0xff {
+        out.extend_from_slice(&[major | 24, n as u8]);
+    } else if n <= 0xffff {
+        out.push(major | 25);
+        out.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= 0xffff_ffff {
+        out.push(major | 26);
+        out.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn write_bytes(out: &mut Vec<u8>, major: u8, bytes: &[u8]) {
+    write_head(out, major, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+/// Append an integer lexeme: an integer or, outside of the 64 bits range, a bignum.
+fn write_int(out: &mut Vec<u8>, lexeme: &str) {
+    let (negative, digits) = match lexeme.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, lexeme),
+    };
+    match (negative, digits.parse::<u64>()) {
+        (false, Ok(n)) | (true, Ok(n @ 0)) => write_head(out, MAJOR_UNSIGNED, n),
+        (true, Ok(n)) => write_head(out, MAJOR_NEGATIVE, n - 1),
+        _ => {
+            // a negative bignum is -1 - n
+            let mut magnitude = decimal_to_bytes(digits);
+            let tag = if negative {
+                decrement(&mut magnitude);
+                TAG_NEGATIVE_BIGNUM
+            } else {
+                TAG_POSITIVE_BIGNUM
+            };
+            write_head(out, MAJOR_TAG, tag);
+            write_bytes(out, MAJOR_BYTES, &magnitude);
+        }
+    }
+}
+
+/// The big endian bytes of a decimal number, without the leading zeros.
+fn decimal_to_bytes(digits: &str) -> Vec<u8> {
+    let mut bytes: Vec<u8> = vec!(); // little endian
+    for d in digits.bytes() {
+        let mut carry = (d - b'0') as u32;
+        for b in bytes.iter_mut() {
+            let v = *b as u32 * 10 + carry;
+            *b = v as u8;
+            carry = v >> 8;
+        }
+        if carry > 0 {
+            bytes.push(carry as u8);
+        }
+    }
+    bytes.reverse();
+    bytes
+}
+
+/// Subtract one from a positive big endian number.
+fn decrement(bytes: &mut Vec<u8>) {
+    for b in bytes.iter_mut().rev() {
+        let (v, borrow) = b.overflowing_sub(1);
+        *b = v;
+        if !borrow {
+            break;
+        }
+    }
+    if bytes.first() == Some(&0) {
+        bytes.remove(0);
+    }
+}
+
+/// Append a float lexeme, as a single precision float if there is no loss.
+fn write_float(out: &mut Vec<u8>, lexeme: &str) {
+    let v: f64 = lexeme.parse().unwrap_or(f64::NAN);
+    if (v as f32) as f64 == v || v.is_nan() {
+        out.push(MAJOR_SIMPLE << 5 | 26);
+        out.extend_from_slice(&(v as f32).to_be_bytes());
+    } else {
+        out.push(MAJOR_SIMPLE << 5 | 27);
+        out.extend_from_slice(&v.to_be_bytes());
+    }
+}
+
+/// A consumer that writes a JSON document as a CBOR data item. The integers are written as CBOR
+/// integers or bignums and the floats as single or double precision floats. A string that is not
+/// valid UTF-8 (see `InvalidUtf8Policy::Bytes`) is written as a byte string.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_parser::JSONParser;
+/// # use r_json_event_parser::json2cbor::JSON2CBORConsumer;
+/// let mut consumer = JSON2CBORConsumer::new(vec!());
+/// let mut parser = JSONParser::new(ByteSource::new(r#"{"a": [1, -1, true]}"#.as_bytes()), false);
+/// parser.parse(&mut consumer).unwrap();
+/// assert_eq!(vec!(0xbf, 0x61, b'a', 0x9f, 0x01, 0x20, 0xf5, 0xff, 0xff), consumer.into_inner());
+/// ```
+pub struct JSON2CBORConsumer<W: Write> {
+    destination: W,
+    /// The bytes of an incomplete char at the end of the last chunk of a string
+    partial: Vec<u8>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> JSON2CBORConsumer<W> {
+    pub fn new(destination: W) -> Self {
+        JSON2CBORConsumer {
+            destination,
+            partial: vec!(),
+            buf: vec!(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.destination
+    }
+
+    pub fn into_inner(self) -> W {
+        self.destination
+    }
+
+    /// Append a chunk of a string as a text string. The chunks of an indefinite length string
+    /// must be valid UTF-8: an incomplete char is kept for the next chunk.
+    fn write_chunk(&mut self, chunk: Vec<u8>) {
+        let mut bytes = std::mem::take(&mut self.partial);
+        bytes.extend(chunk);
+        let valid_up_to = match std::str::from_utf8(&bytes) {
+            Ok(_) => bytes.len(),
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => {
+                let text = String::from_utf8_lossy(&bytes).into_owned();
+                write_bytes(&mut self.buf, MAJOR_TEXT, text.as_bytes());
+                return;
+            }
+        };
+        self.partial = bytes.split_off(valid_up_to);
+        if !bytes.is_empty() {
+            write_bytes(&mut self.buf, MAJOR_TEXT, &bytes);
+        }
+    }
+}
+
+impl<W: Write> JSONParseConsumer for JSON2CBORConsumer<W> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        let out = &mut self.buf;
+        match token {
+            ParserToken::BeginFile => {}
+            ParserToken::EndFile => {
+                return self.destination.flush().map_err(ConsumeError::from_io);
+            }
+            ParserToken::BeginObject => out.push(MAJOR_MAP << 5 | INDEFINITE),
+            ParserToken::BeginArray => out.push(MAJOR_ARRAY << 5 | INDEFINITE),
+            ParserToken::EndObject | ParserToken::EndArray => out.push(BREAK),
+            ParserToken::Key(s) | ParserToken::StringValue(s) => write_bytes(out, MAJOR_TEXT, s.as_bytes()),
+            ParserToken::BytesValue(b) => write_bytes(out, MAJOR_BYTES, &b),
+            ParserToken::IntValue(s) => write_int(out, &s),
+            ParserToken::FloatValue(s) => write_float(out, &s),
+            ParserToken::BooleanValue(b) => out.push(MAJOR_SIMPLE << 5 | if b { 21 } else { 20 }),
+            ParserToken::NullValue => out.push(MAJOR_SIMPLE << 5 | 22),
+            ParserToken::StringStart => out.push(MAJOR_TEXT << 5 | INDEFINITE),
+            ParserToken::StringChunk(chunk) => self.write_chunk(chunk),
+            ParserToken::StringEnd => {
+                if !self.partial.is_empty() {
+                    let text = String::from_utf8_lossy(&std::mem::take(&mut self.partial)).into_owned();
+                    write_bytes(&mut self.buf, MAJOR_TEXT, text.as_bytes());
+                }
+                self.buf.push(BREAK);
+            }
+        }
+        self.destination.write_all(&self.buf).map_err(ConsumeError::from_io)?;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+/// Convert a JSON document to CBOR.
+pub fn json_to_cbor(input: &str) -> Result<Vec<u8>, ConsumeError> {
+    let mut destination = vec!();
+    json_to_cbor_write(input.as_bytes(), &mut destination)?;
+    Ok(destination)
+}
+
+/// Convert a JSON document from `input` to CBOR written to `destination`.
+pub fn json_to_cbor_write<R: Read, W: Write>(input: R, destination: W) -> Result<(), ConsumeError> {
+    let mut consumer = JSON2CBORConsumer::new(destination);
+    JSONParser::new(ByteSource::new(input), false).parse(&mut consumer)
+}
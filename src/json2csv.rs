@@ -0,0 +1,301 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Convert an array of objects to CSV rows, without loading the document. The columns are the
+//! flat keys of the values (see `json_flatten::flat_key`), e.g. `{"a": {"b": 1, "c": [2, 3]}}`
+//! gives the columns `a.b`, `a.c[0]` and `a.c[1]`.
+
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+use crate::byte_source::ByteSource;
+use crate::fmt_write::FmtWriter;
+use crate::json_flatten::flat_key;
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::json_pointer::PathTracker;
+
+/// The column of a record that is a scalar, e.g. in `[1, 2]`.
+pub const VALUE_COLUMN: &str = "value";
+
+/// A consumer that writes the records of a JSON document as CSV rows. The records are the
+/// elements of the top-level array, or the top-level object itself. The columns are given or
+/// inferred from the first records: the values of the other columns are dropped.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_parser::JSONParser;
+/// # use r_json_event_parser::json2csv::JSON2CSVConsumer;
+/// let mut consumer = JSON2CSVConsumer::new(vec!());
+/// let mut parser = JSONParser::new(ByteSource::new(r#"[{"a": 1, "b": {"c": "x,y"}}, {"a": 2}]"#.as_bytes()), false);
+/// parser.parse(&mut consumer).unwrap();
+/// assert_eq!(b"a,b.c\n1,\"x,y\"\n2,\n".to_vec(), consumer.into_inner());
+/// ```
+pub struct JSON2CSVConsumer<W: Write> {
+    destination: W,
+    delimiter: u8,
+    key_separator: String,
+    infer_records: usize,
+    /// The given or inferred columns
+    columns: Option<Vec<String>>,
+    header_written: bool,
+    /// The records read before the header is known
+    pending_rows: Vec<Vec<(String, String)>>,
+    tracker: PathTracker,
+    /// true if the records are the elements of the top-level array, false if the record is the
+    /// top-level object
+    root_array: bool,
+    row: Vec<(String, String)>,
+    /// The chunks of a string
+    chunks: Vec<u8>,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> JSON2CSVConsumer<W> {
+    pub fn new(destination: W) -> Self {
+        JSON2CSVConsumer {
+            destination,
+            delimiter: b',',
+            key_separator: ".".into(),
+            infer_records: 100,
+            columns: None,
+            header_written: false,
+            pending_rows: vec!(),
+            tracker: PathTracker::new(),
+            root_array: false,
+            row: vec!(),
+            chunks: vec!(),
+            buf: vec!(),
+        }
+    }
+
+    pub fn with_options(mut self, opts: &Json2CsvOptions) -> Self {
+        self = self.delimiter(opts.delimiter).key_separator(&opts.key_separator).infer_records(opts.infer_records);
+        if let Some(columns) = &opts.columns {
+            self = self.columns(columns.clone());
+        }
+        self
+    }
+
+    /// The delimiter of the fields (default: `,`), e.g. `\t` for TSV.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        self.delimiter = delimiter;
+        self
+    }
+
+    /// The separator of the keys of a nested value (default: `.`).
+    pub fn key_separator(mut self, separator: &str) -> Self {
+        self.key_separator = separator.into();
+        self
+    }
+
+    /// The number of records that are read to infer the columns (default: 100). The columns are
+    /// in the order of their first occurrence.
+    pub fn infer_records(mut self, n: usize) -> Self {
+        self.infer_records = n.max(1);
+        self
+    }
+
+    /// Write these columns instead of inferring them.
+    pub fn columns(mut self, columns: Vec<String>) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.destination
+    }
+
+    pub fn into_inner(self) -> W {
+        self.destination
+    }
+
+    /// The column of the current value: the flat key of its path in the record.
+    fn column(&self) -> String {
+        let path = self.tracker.path();
+        flat_key(if self.root_array { &path[1..] } else { path }, &self.key_separator)
+    }
+
+    /// The end of a container, whose path was updated.
+    fn end_container(&mut self) -> Result<(), ConsumeError> {
+        let depth = self.tracker.depth();
+        // the end of an element of the top-level array or of the top-level object
+        if (self.root_array && depth == 1) || (!self.root_array && depth == 0) {
+            self.end_record()?;
+        }
+        Ok(())
+    }
+
+    /// A scalar value, whose path was updated.
+    fn scalar(&mut self, text: String) -> Result<(), ConsumeError> {
+        let depth = self.tracker.depth();
+        if depth == 0 || (self.root_array && depth == 1) {
+            self.row.push((VALUE_COLUMN.into(), text));
+            self.end_record()
+        } else {
+            let column = self.column();
+            self.row.push((column, text));
+            Ok(())
+        }
+    }
+
+    fn end_record(&mut self) -> Result<(), ConsumeError> {
+        let row = std::mem::take(&mut self.row);
+        if self.columns.is_some() && self.pending_rows.is_empty() {
+            self.write_header()?;
+            return self.write_row(row);
+        }
+        self.pending_rows.push(row);
+        if self.pending_rows.len() >= self.infer_records {
+            self.write_pending_rows()?;
+        }
+        Ok(())
+    }
+
+    /// Infer the columns if needed, and write the header and the pending rows.
+    fn write_pending_rows(&mut self) -> Result<(), ConsumeError> {
+        if self.columns.is_none() {
+            let mut columns: Vec<String> = vec!();
+            for row in &self.pending_rows {
+                for (column, _) in row {
+                    if !columns.contains(column) {
+                        columns.push(column.clone());
+                    }
+                }
+            }
+            self.columns = Some(columns);
+        }
+        self.write_header()?;
+        for row in std::mem::take(&mut self.pending_rows) {
+            self.write_row(row)?;
+        }
+        Ok(())
+    }
+
+    fn write_header(&mut self) -> Result<(), ConsumeError> {
+        if self.header_written {
+            return Ok(());
+        }
+        self.header_written = true;
+        let columns = self.columns.clone().unwrap_or_default();
+        self.write_fields(columns.iter().map(|c| c.as_str()))
+    }
+
+    fn write_row(&mut self, row: Vec<(String, String)>) -> Result<(), ConsumeError> {
+        let values: HashMap<String, String> = row.into_iter().collect();
+        let columns = self.columns.take().unwrap_or_default();
+        let result = self.write_fields(columns.iter().map(|c| values.get(c).map(|v| v.as_str()).unwrap_or("")));
+        self.columns = Some(columns);
+        result
+    }
+
+    fn write_fields<'a, I: Iterator<Item=&'a str>>(&mut self, fields: I) -> Result<(), ConsumeError> {
+        self.buf.clear();
+        for (i, field) in fields.enumerate() {
+            if i > 0 {
+                self.buf.push(self.delimiter);
+            }
+            escape_field(&mut self.buf, field, self.delimiter);
+        }
+        self.buf.push(b'\n');
+        self.destination.write_all(&self.buf).map_err(ConsumeError::from_io)
+    }
+}
+
+/// Quote a field that contains the delimiter, a quote or a newline.
+fn escape_field(out: &mut Vec<u8>, field: &str, delimiter: u8) {
+    if field.bytes().any(|b| b == delimiter || b == b'"' || b == b'\n' || b == b'\r') {
+        out.push(b'"');
+        out.extend_from_slice(field.replace('"', "\"\"").as_bytes());
+        out.push(b'"');
+    } else {
+        out.extend_from_slice(field.as_bytes());
+    }
+}
+
+impl<W: Write> JSONParseConsumer for JSON2CSVConsumer<W> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        if self.tracker.depth() == 0 && matches!(token, ParserToken::BeginObject | ParserToken::BeginArray) {
+            self.root_array = token == ParserToken::BeginArray;
+        }
+        self.tracker.update(&token);
+        match token {
+            ParserToken::BeginFile | ParserToken::BeginObject | ParserToken::BeginArray | ParserToken::Key(_) | ParserToken::StringStart => Ok(()),
+            ParserToken::EndFile => {
+                if !self.pending_rows.is_empty() || (self.columns.is_some() && !self.header_written) {
+                    self.write_pending_rows()?;
+                }
+                self.destination.flush().map_err(ConsumeError::from_io)
+            }
+            ParserToken::EndObject | ParserToken::EndArray => self.end_container(),
+            ParserToken::StringChunk(chunk) => {
+                self.chunks.extend(chunk);
+                Ok(())
+            }
+            ParserToken::StringEnd => {
+                let s = String::from_utf8_lossy(&std::mem::take(&mut self.chunks)).into_owned();
+                self.scalar(s)
+            }
+            ParserToken::StringValue(s) | ParserToken::IntValue(s) | ParserToken::FloatValue(s) => self.scalar(s),
+            ParserToken::BytesValue(b) => self.scalar(String::from_utf8_lossy(&b).into_owned()),
+            ParserToken::BooleanValue(b) => self.scalar(b.to_string()),
+            ParserToken::NullValue => self.scalar(String::new()),
+        }
+    }
+}
+
+/// Options of the one-call conversion functions.
+#[derive(Debug, Clone)]
+pub struct Json2CsvOptions {
+    /// See `JSON2CSVConsumer::delimiter`
+    pub delimiter: u8,
+    /// See `JSON2CSVConsumer::key_separator`
+    pub key_separator: String,
+    /// See `JSON2CSVConsumer::infer_records`
+    pub infer_records: usize,
+    /// See `JSON2CSVConsumer::columns`
+    pub columns: Option<Vec<String>>,
+}
+
+impl Default for Json2CsvOptions {
+    fn default() -> Self {
+        Json2CsvOptions {
+            delimiter: b',',
+            key_separator: ".".into(),
+            infer_records: 100,
+            columns: None,
+        }
+    }
+}
+
+/// Convert a JSON document to CSV.
+pub fn json_to_csv_string(input: &str, opts: &Json2CsvOptions) -> Result<String, ConsumeError> {
+    let mut destination = String::new();
+    json_to_csv_write(input.as_bytes(), FmtWriter::new(&mut destination), opts)?;
+    Ok(destination)
+}
+
+/// Convert a JSON document from `input` to CSV written to `destination`.
+pub fn json_to_csv_write<R: Read, W: Write>(input: R, destination: W, opts: &Json2CsvOptions) -> Result<(), ConsumeError> {
+    let mut consumer = JSON2CSVConsumer::new(destination).with_options(opts);
+    JSONParser::new(ByteSource::new(input), false).parse(&mut consumer)
+}
@@ -0,0 +1,291 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Render a JSON document as a collapsible HTML tree, without loading the document. Every value
+//! is an item of a `<ul>` list and every non empty container is a `<details>` element whose
+//! `<summary>` is the key and the opening bracket.
+
+use std::io::{Read, Write};
+
+use crate::byte_source::ByteSource;
+use crate::fmt_write::FmtWriter;
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::json_writer::escape_string;
+
+/// The style sheet of a standalone page. The classes of the values are `json-key`,
+/// `json-string`, `json-number`, `json-boolean`, `json-null` and `json-punct`.
+pub const DEFAULT_STYLE: &str = ".json-tree, .json-tree ul { list-style: none; font-family: monospace; margin: 0; }
+.json-tree ul { padding-left: 1.5em; }
+.json-tree summary { cursor: pointer; }
+.json-key { color: #881391; }
+.json-string { color: #c41a16; }
+.json-number { color: #1c00cf; }
+.json-boolean { color: #0d22aa; }
+.json-null { color: #808080; }
+.json-punct { color: #444444; }";
+
+/// An open container.
+struct Frame {
+    array: bool,
+    /// No child was written: the element is written at the first child, or as an empty container
+    empty: bool,
+    /// The key of the container in its parent
+    label: String,
+}
+
+/// A consumer that writes a JSON document as an HTML tree. The output is a fragment, a
+/// `<ul class="json-tree">` element, unless the consumer is `standalone`.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_parser::JSONParser;
+/// # use r_json_event_parser::json2html::JSON2HTMLConsumer;
+/// let mut consumer = JSON2HTMLConsumer::new(vec!());
+/// let mut parser = JSONParser::new(ByteSource::new(r#"{"a": [1, "<b>"], "c": {}}"#.as_bytes()), false);
+/// parser.parse(&mut consumer).unwrap();
+/// assert_eq!(r#"<ul class="json-tree">
+///   <li><details open><summary><span class="json-punct">{</span></summary>
+///     <ul>
+///       <li><details open><summary><span class="json-key">"a"</span>: <span class="json-punct">[</span></summary>
+///         <ul>
+///           <li><span class="json-number">1</span></li>
+///           <li><span class="json-string">"&lt;b&gt;"</span></li>
+///         </ul>
+///         <span class="json-punct">]</span></details></li>
+///       <li><span class="json-key">"c"</span>: <span class="json-punct">{}</span></li>
+///     </ul>
+///     <span class="json-punct">}</span></details></li>
+/// </ul>
+/// "#, String::from_utf8(consumer.into_inner()).unwrap());
+/// ```
+pub struct JSON2HTMLConsumer<W: Write> {
+    destination: W,
+    standalone: bool,
+    title: String,
+    collapse_depth: Option<usize>,
+    frames: Vec<Frame>,
+    /// The key of the next value
+    label: String,
+    /// The chunks of a string
+    chunks: Vec<u8>,
+}
+
+impl<W: Write> JSON2HTMLConsumer<W> {
+    pub fn new(destination: W) -> Self {
+        JSON2HTMLConsumer {
+            destination,
+            standalone: false,
+            title: "JSON".into(),
+            collapse_depth: None,
+            frames: vec!(),
+            label: String::new(),
+            chunks: vec!(),
+        }
+    }
+
+    pub fn with_options(mut self, opts: &Json2HtmlOptions) -> Self {
+        if opts.standalone {
+            self = self.standalone();
+        }
+        if let Some(title) = &opts.title {
+            self = self.title(title);
+        }
+        if let Some(depth) = opts.collapse_depth {
+            self = self.collapse_depth(depth);
+        }
+        self
+    }
+
+    /// Write a whole page, with the `DEFAULT_STYLE`, instead of a fragment.
+    pub fn standalone(mut self) -> Self {
+        self.standalone = true;
+        self
+    }
+
+    /// The title of a standalone page (default: `JSON`).
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Close the containers that are nested deeper than `depth`, e.g. `1` to show only the
+    /// members of the root or `0` to close the root. By default, all the containers are open.
+    pub fn collapse_depth(mut self, depth: usize) -> Self {
+        self.collapse_depth = Some(depth);
+        self
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.destination
+    }
+
+    pub fn into_inner(self) -> W {
+        self.destination
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), ConsumeError> {
+        self.destination.write_all(text.as_bytes()).map_err(ConsumeError::from_io)
+    }
+
+    /// The indentation of an item of the innermost container.
+    fn item_indent(&self) -> String {
+        "  ".repeat(2 * self.frames.len() + 1)
+    }
+
+    /// Write the start of the innermost container if this is its first child.
+    fn begin_child(&mut self) -> Result<(), ConsumeError> {
+        let depth = self.frames.len();
+        let frame = match self.frames.last_mut() {
+            Some(frame) if frame.empty => frame,
+            _ => return Ok(()),
+        };
+        frame.empty = false;
+        let bracket = if frame.array { "[" } else { "{" };
+        let open = if self.collapse_depth.is_some_and(|d| depth > d) { "" } else { " open" };
+        let indent = "  ".repeat(2 * depth - 1);
+        let text = format!("{}<li><details{}><summary>{}<span class=\"json-punct\">{}</span></summary>\n{}  <ul>\n",
+                           indent, open, frame.label, bracket, indent);
+        self.write(&text)
+    }
+
+    /// Write the key and the value of an item.
+    fn item(&mut self, markup: &str) -> Result<(), ConsumeError> {
+        self.begin_child()?;
+        let text = format!("{}<li>{}{}</li>\n", self.item_indent(), std::mem::take(&mut self.label), markup);
+        self.write(&text)
+    }
+
+    fn scalar(&mut self, class: &str, text: &str) -> Result<(), ConsumeError> {
+        self.item(&format!("<span class=\"json-{}\">{}</span>", class, escape_html(text)))
+    }
+
+    fn string(&mut self, s: &str) -> Result<(), ConsumeError> {
+        let mut quoted = vec!();
+        escape_string(&mut quoted, s);
+        self.scalar("string", &String::from_utf8_lossy(&quoted))
+    }
+
+    fn begin_container(&mut self, array: bool) -> Result<(), ConsumeError> {
+        self.begin_child()?;
+        let label = std::mem::take(&mut self.label);
+        self.frames.push(Frame { array, empty: true, label });
+        Ok(())
+    }
+
+    fn end_container(&mut self) -> Result<(), ConsumeError> {
+        let frame = match self.frames.pop() {
+            Some(frame) => frame,
+            None => return Ok(()),
+        };
+        let brackets = if frame.array { "[]" } else { "{}" };
+        if frame.empty {
+            self.label = frame.label;
+            return self.item(&format!("<span class=\"json-punct\">{}</span>", brackets));
+        }
+        let indent = self.item_indent();
+        let text = format!("{}  </ul>\n{}  <span class=\"json-punct\">{}</span></details></li>\n",
+                           indent, indent, &brackets[1..]);
+        self.write(&text)
+    }
+
+    fn key(&mut self, key: &str) -> Result<(), ConsumeError> {
+        self.begin_child()?;
+        let mut quoted = vec!();
+        escape_string(&mut quoted, key);
+        self.label = format!("<span class=\"json-key\">{}</span>: ", escape_html(&String::from_utf8_lossy(&quoted)));
+        Ok(())
+    }
+
+    fn begin_file(&mut self) -> Result<(), ConsumeError> {
+        if self.standalone {
+            let text = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n",
+                               escape_html(&self.title), DEFAULT_STYLE);
+            self.write(&text)?;
+        }
+        self.write("<ul class=\"json-tree\">\n")
+    }
+
+    fn end_file(&mut self) -> Result<(), ConsumeError> {
+        self.write("</ul>\n")?;
+        if self.standalone {
+            self.write("</body>\n</html>\n")?;
+        }
+        self.destination.flush().map_err(ConsumeError::from_io)
+    }
+}
+
+/// Escape the special chars of a text of an HTML element.
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+impl<W: Write> JSONParseConsumer for JSON2HTMLConsumer<W> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        match token {
+            ParserToken::BeginFile => self.begin_file(),
+            ParserToken::EndFile => self.end_file(),
+            ParserToken::BeginObject => self.begin_container(false),
+            ParserToken::BeginArray => self.begin_container(true),
+            ParserToken::EndObject | ParserToken::EndArray => self.end_container(),
+            ParserToken::Key(k) => self.key(&k),
+            ParserToken::StringStart => Ok(()),
+            ParserToken::StringChunk(chunk) => {
+                self.chunks.extend(chunk);
+                Ok(())
+            }
+            ParserToken::StringEnd => {
+                let s = String::from_utf8_lossy(&std::mem::take(&mut self.chunks)).into_owned();
+                self.string(&s)
+            }
+            ParserToken::StringValue(s) => self.string(&s),
+            ParserToken::BytesValue(b) => self.string(&String::from_utf8_lossy(&b)),
+            ParserToken::IntValue(s) | ParserToken::FloatValue(s) => self.scalar("number", &s),
+            ParserToken::BooleanValue(b) => self.scalar("boolean", if b { "true" } else { "false" }),
+            ParserToken::NullValue => self.scalar("null", "null"),
+        }
+    }
+}
+
+/// Options of the one-call conversion functions.
+#[derive(Debug, Clone, Default)]
+pub struct Json2HtmlOptions {
+    /// See `JSON2HTMLConsumer::standalone`
+    pub standalone: bool,
+    /// See `JSON2HTMLConsumer::title`
+    pub title: Option<String>,
+    /// See `JSON2HTMLConsumer::collapse_depth`
+    pub collapse_depth: Option<usize>,
+}
+
+/// Convert a JSON document to an HTML tree.
+pub fn json_to_html_string(input: &str, opts: &Json2HtmlOptions) -> Result<String, ConsumeError> {
+    let mut destination = String::new();
+    json_to_html_write(input.as_bytes(), FmtWriter::new(&mut destination), opts)?;
+    Ok(destination)
+}
+
+/// Convert a JSON document from `input` to an HTML tree written to `destination`.
+pub fn json_to_html_write<R: Read, W: Write>(input: R, destination: W, opts: &Json2HtmlOptions) -> Result<(), ConsumeError> {
+    let mut consumer = JSON2HTMLConsumer::new(destination).with_options(opts);
+    JSONParser::new(ByteSource::new(input), false).parse(&mut consumer)
+}
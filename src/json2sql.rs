@@ -0,0 +1,325 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Convert an array of objects to SQL `INSERT` statements, without loading the document. The
+//! records are written by batches: one statement per batch.
+
+use std::io::{Read, Write};
+
+use crate::byte_source::ByteSource;
+use crate::fmt_write::FmtWriter;
+use crate::json_lexer::{ConsumeError, Span};
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::json_writer::JSONWriteConsumer;
+
+/// A record: the columns and the SQL literals of the values.
+type Row = Vec<(String, String)>;
+
+/// A consumer that writes the records of a JSON document as SQL `INSERT` statements. The records
+/// are the elements of the top-level array, or the top-level object itself. The columns of a
+/// statement are given, or are the keys of the records of the batch, and the missing values are
+/// `NULL`.
+///
+/// The strings are quoted by `'` and the nested containers are written as JSON strings. The
+/// booleans are `TRUE` and `FALSE`.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_parser::JSONParser;
+/// # use r_json_event_parser::json2sql::JSON2SQLConsumer;
+/// let mut consumer = JSON2SQLConsumer::new(vec!()).table("users");
+/// let mut parser = JSONParser::new(ByteSource::new(r#"[{"id": 1, "name": "O'Hara"}, {"id": 2, "tags": [1]}]"#.as_bytes()), false);
+/// parser.parse(&mut consumer).unwrap();
+/// assert_eq!(r#"INSERT INTO users ("id", "name", "tags") VALUES
+/// (1, 'O''Hara', NULL),
+/// (2, NULL, '[1]');
+/// "#, String::from_utf8(consumer.into_inner()).unwrap());
+/// ```
+pub struct JSON2SQLConsumer<W: Write> {
+    destination: W,
+    table: String,
+    batch_size: usize,
+    identifier_quote: char,
+    columns: Option<Vec<String>>,
+    /// The number of open containers, outside of the nested values
+    depth: usize,
+    root_array: bool,
+    key: String,
+    row: Row,
+    /// The writer of a nested container, and its depth
+    nested: Option<(JSONWriteConsumer<Vec<u8>>, usize)>,
+    batch: Vec<Row>,
+    /// The chunks of a string
+    chunks: Vec<u8>,
+    /// The position of the last token
+    line: usize,
+    column: usize,
+}
+
+impl<W: Write> JSON2SQLConsumer<W> {
+    pub fn new(destination: W) -> Self {
+        JSON2SQLConsumer {
+            destination,
+            table: "data".into(),
+            batch_size: 100,
+            identifier_quote: '"',
+            columns: None,
+            depth: 0,
+            root_array: false,
+            key: String::new(),
+            row: vec!(),
+            nested: None,
+            batch: vec!(),
+            chunks: vec!(),
+            line: 0,
+            column: 0,
+        }
+    }
+
+    pub fn with_options(mut self, opts: &Json2SqlOptions) -> Self {
+        self = self.table(&opts.table).batch_size(opts.batch_size).identifier_quote(opts.identifier_quote);
+        if let Some(columns) = &opts.columns {
+            self = self.columns(columns.clone());
+        }
+        self
+    }
+
+    /// The name of the table (default: `data`). The name is written as is, e.g. `public.users`.
+    pub fn table(mut self, table: &str) -> Self {
+        self.table = table.into();
+        self
+    }
+
+    /// The maximum number of records of a statement (default: 100).
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// The quote of the column names (default: `"`), e.g. `` ` `` for MySQL. A quote in a name is
+    /// doubled.
+    pub fn identifier_quote(mut self, quote: char) -> Self {
+        self.identifier_quote = quote;
+        self
+    }
+
+    /// Write these columns instead of the keys of the records: the other values are dropped.
+    pub fn columns(mut self, columns: Vec<String>) -> Self {
+        self.columns = Some(columns);
+        self
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.destination
+    }
+
+    pub fn into_inner(self) -> W {
+        self.destination
+    }
+
+    fn error(&self, msg: &str) -> ConsumeError {
+        ConsumeError { msg: msg.into(), line: self.line, column: self.column, io_error: None }
+    }
+
+    /// The depth of the members of a record.
+    fn record_depth(&self) -> usize {
+        if self.root_array { 2 } else { 1 }
+    }
+
+    fn value(&mut self, literal: String) -> Result<(), ConsumeError> {
+        if self.depth != self.record_depth() {
+            return Err(self.error("A record must be an object"));
+        }
+        self.row.push((std::mem::take(&mut self.key), literal));
+        Ok(())
+    }
+
+    fn begin_container(&mut self, token: ParserToken) -> Result<(), ConsumeError> {
+        match (self.depth, &token) {
+            (0, ParserToken::BeginArray) => self.root_array = true,
+            (0, _) => {}
+            (1, ParserToken::BeginObject) if self.root_array => {}
+            (d, _) if d == self.record_depth() => {
+                let mut writer = JSONWriteConsumer::new(vec!());
+                writer.consume(Ok(token))?;
+                self.nested = Some((writer, 1));
+                return Ok(());
+            }
+            _ => return Err(self.error("A record must be an object")),
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn end_container(&mut self) -> Result<(), ConsumeError> {
+        if self.depth == self.record_depth() {
+            let row = std::mem::take(&mut self.row);
+            self.batch.push(row);
+            if self.batch.len() >= self.batch_size {
+                self.write_batch()?;
+            }
+        }
+        self.depth -= 1;
+        Ok(())
+    }
+
+    /// Send a token to the writer of a nested container, and write the container at its end.
+    fn consume_nested(&mut self, token: ParserToken) -> Result<(), ConsumeError> {
+        let (writer, depth) = match self.nested.as_mut() {
+            Some(nested) => nested,
+            None => return Ok(()),
+        };
+        match token {
+            ParserToken::BeginObject | ParserToken::BeginArray => *depth += 1,
+            ParserToken::EndObject | ParserToken::EndArray => *depth -= 1,
+            _ => {}
+        }
+        let done = *depth == 0;
+        writer.consume(Ok(token))?;
+        if done {
+            if let Some((writer, _)) = self.nested.take() {
+                let json = String::from_utf8_lossy(&writer.into_inner()).into_owned();
+                self.value(quote_string(&json))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn quote_identifier(&self, name: &str) -> String {
+        let q = self.identifier_quote;
+        let doubled: String = [q, q].iter().collect();
+        format!("{}{}{}", q, name.replace(q, &doubled), q)
+    }
+
+    fn write_batch(&mut self) -> Result<(), ConsumeError> {
+        let batch = std::mem::take(&mut self.batch);
+        if batch.is_empty() {
+            return Ok(());
+        }
+        let columns = match &self.columns {
+            Some(columns) => columns.clone(),
+            None => {
+                let mut columns: Vec<String> = vec!();
+                for (column, _) in batch.iter().flatten() {
+                    if !columns.contains(column) {
+                        columns.push(column.clone());
+                    }
+                }
+                columns
+            }
+        };
+        let names: Vec<String> = columns.iter().map(|c| self.quote_identifier(c)).collect();
+        let mut statement = format!("INSERT INTO {} ({}) VALUES\n", self.table, names.join(", "));
+        for (i, row) in batch.iter().enumerate() {
+            let values: Vec<&str> = columns.iter().map(|c| {
+                row.iter().rev().find(|(column, _)| column == c).map_or("NULL", |(_, v)| v.as_str())
+            }).collect();
+            statement.push('(');
+            statement.push_str(&values.join(", "));
+            statement.push_str(if i + 1 == batch.len() { ");\n" } else { "),\n" });
+        }
+        self.destination.write_all(statement.as_bytes()).map_err(ConsumeError::from_io)
+    }
+}
+
+/// A SQL string literal.
+fn quote_string(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+impl<W: Write> JSONParseConsumer for JSON2SQLConsumer<W> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        if self.nested.is_some() {
+            return self.consume_nested(token);
+        }
+        match token {
+            ParserToken::BeginFile => Ok(()),
+            ParserToken::EndFile => {
+                self.write_batch()?;
+                self.destination.flush().map_err(ConsumeError::from_io)
+            }
+            ParserToken::BeginObject | ParserToken::BeginArray => self.begin_container(token),
+            ParserToken::EndObject | ParserToken::EndArray => self.end_container(),
+            ParserToken::Key(k) => {
+                self.key = k;
+                Ok(())
+            }
+            ParserToken::StringStart => Ok(()),
+            ParserToken::StringChunk(chunk) => {
+                self.chunks.extend(chunk);
+                Ok(())
+            }
+            ParserToken::StringEnd => {
+                let s = String::from_utf8_lossy(&std::mem::take(&mut self.chunks)).into_owned();
+                self.value(quote_string(&s))
+            }
+            ParserToken::StringValue(s) => self.value(quote_string(&s)),
+            ParserToken::BytesValue(b) => self.value(quote_string(&String::from_utf8_lossy(&b))),
+            ParserToken::IntValue(s) | ParserToken::FloatValue(s) => self.value(s),
+            ParserToken::BooleanValue(b) => self.value(if b { "TRUE" } else { "FALSE" }.into()),
+            ParserToken::NullValue => self.value("NULL".into()),
+        }
+    }
+
+    fn consume_with_span(&mut self, token: Result<ParserToken, JSONParseError>, span: Span) -> Result<(), ConsumeError> {
+        self.line = span.line;
+        self.column = span.column;
+        self.consume(token)
+    }
+}
+
+/// Options of the one-call conversion functions.
+#[derive(Debug, Clone)]
+pub struct Json2SqlOptions {
+    /// See `JSON2SQLConsumer::table`
+    pub table: String,
+    /// See `JSON2SQLConsumer::batch_size`
+    pub batch_size: usize,
+    /// See `JSON2SQLConsumer::identifier_quote`
+    pub identifier_quote: char,
+    /// See `JSON2SQLConsumer::columns`
+    pub columns: Option<Vec<String>>,
+}
+
+impl Default for Json2SqlOptions {
+    fn default() -> Self {
+        Json2SqlOptions {
+            table: "data".into(),
+            batch_size: 100,
+            identifier_quote: '"',
+            columns: None,
+        }
+    }
+}
+
+/// Convert a JSON document to SQL statements.
+pub fn json_to_sql_string(input: &str, opts: &Json2SqlOptions) -> Result<String, ConsumeError> {
+    let mut destination = String::new();
+    json_to_sql_write(input.as_bytes(), FmtWriter::new(&mut destination), opts)?;
+    Ok(destination)
+}
+
+/// Convert a JSON document from `input` to SQL statements written to `destination`.
+pub fn json_to_sql_write<R: Read, W: Write>(input: R, destination: W, opts: &Json2SqlOptions) -> Result<(), ConsumeError> {
+    let mut consumer = JSON2SQLConsumer::new(destination).with_options(opts);
+    JSONParser::new(ByteSource::new(input), false).parse(&mut consumer)
+}
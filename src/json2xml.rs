@@ -19,27 +19,64 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::fmt;
 use std::io;
-use std::io::Write;
+use std::io::{Read, Write};
 use std::marker::PhantomData;
 
+use crate::byte_source::ByteSource;
+use crate::fmt_write::FmtWriter;
 use crate::json_lexer::ConsumeError;
-use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
-use crate::json_parser::ParserToken::{BeginArray, BeginFile, BeginObject, BooleanValue, EndArray, EndFile, EndObject, FloatValue, IntValue, Key, NullValue, StringValue};
+use crate::json_lines::{JSONLinesParser, JSONLinesSummary};
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::json_parser::ParserToken::{BeginArray, BeginFile, BeginObject, BooleanValue, BytesValue, EndArray, EndFile, EndObject, FloatValue, IntValue, Key, NullValue, StringChunk, StringEnd, StringStart, StringValue};
+use crate::json_pointer::JSONPointer;
+use crate::json_redact::{RedactConsumer, RedactPattern};
+use crate::json_transform::SubstituteConsumer;
+use crate::ndjson::RecordErrorPolicy;
 
 pub trait XMLWrite<W: Write> {
     fn write_value(&mut self, size: usize, cur_key: String, value_type: &str, value: String) -> io::Result<()>;
 
     fn write_string_value(&mut self, size: usize, cur_key: String, value: String) -> io::Result<()>;
 
-    fn write_open(&mut self) -> io::Result<()>;
+    /// Write the XML declaration, the prolog items (one per line) and the root start tag. The
+    /// start tag is the name of the root followed by its attributes, e.g. `root a="b"`.
+    fn write_open(&mut self, prolog: &[String], root_start_tag: &str) -> io::Result<()>;
 
-    fn write_close(&mut self) -> io::Result<()>;
+    fn write_close(&mut self, root: &str) -> io::Result<()>;
 
     fn write_begin(&mut self, size: usize, cur_key: &str) -> io::Result<()>;
 
     fn write_end(&mut self, size: usize, cur_key: &str) -> io::Result<()>;
 
+    /// Write an element that contains only scalars: (key, value type, value).
+    fn write_compact(&mut self, size: usize, cur_key: &str, values: Vec<(String, &str, String)>) -> io::Result<()> {
+        self.write_begin(size, cur_key)?;
+        for (key, value_type, value) in values {
+            if value_type == "string" {
+                self.write_string_value(size + 4, key, value)?;
+            } else {
+                self.write_value(size + 4, key, value_type, value)?;
+            }
+        }
+        self.write_end(size, cur_key)
+    }
+
+    /// How to write the string values (see `JSON2XMLConsumer::ascii_only` and
+    /// `JSON2XMLConsumer::text_escaping`).
+    fn set_text_options(&mut self, _text: TextOptions) {}
+
+    /// Escape a text as required by the options. Fails on a control char if the policy is
+    /// `ControlChars::Error`.
+    fn escape_text(s: String, text: TextOptions) -> io::Result<String> {
+        let escaped = match text.escaping {
+            TextEscaping::Cdata => Self::escape_value(s),
+            TextEscaping::Entities => escape_entities(&s),
+        };
+        finish_text(escaped, text)
+    }
+
     fn escape_value(s: String) -> String {
         if s.find(&['<', '>', '&', '"', '\''][..]).is_some() {
             if s.find("]]>").is_some() {
@@ -53,122 +90,345 @@ pub trait XMLWrite<W: Write> {
     }
 }
 
+/// How the special chars of a text are escaped.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum TextEscaping {
+    /// Wrap a text that contains `<`, `>`, `&`, `"` or `'` in a CDATA section.
+    #[default]
+    Cdata,
+    /// Replace `<`, `>`, `&`, `"` and `'` with the predefined entities, e.g. `&lt;`. Some tools,
+    /// e.g. the canonicalizers, do not handle the CDATA sections well.
+    Entities,
+}
+
+/// What to do with the control chars other than tab, LF and CR, that are not allowed in XML 1.0,
+/// even in a CDATA section.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum ControlChars {
+    /// Replace the control chars with U+FFFD.
+    #[default]
+    Replace,
+    Strip,
+    /// Write the control chars as character references and declare an XML 1.1 document.
+    /// U+0000 is not allowed in XML 1.1 and is replaced with U+FFFD.
+    Reference,
+    /// Fail with an `InvalidData` error.
+    Error,
+}
+
+/// How the string values are written.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct TextOptions {
+    pub ascii_only: bool,
+    pub escaping: TextEscaping,
+    pub control_chars: ControlChars,
+}
+
+impl TextOptions {
+    fn xml_version(&self) -> &'static str {
+        match self.control_chars {
+            ControlChars::Reference => "1.1",
+            _ => "1.0",
+        }
+    }
+}
+
+/// Replace the special chars of a text with the predefined entities.
+fn escape_entities(s: &str) -> String {
+    if !s.contains(&['<', '>', '&', '"', '\''][..]) {
+        return s.into();
+    }
+    let mut escaped = String::with_capacity(s.len() + 16);
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn is_control_char(c: char) -> bool {
+    c < ' ' && !matches!(c, '\t' | '\n' | '\r')
+}
+
+/// Apply the control char policy to an escaped value, and replace the non ASCII chars with
+/// character references if `ascii_only`. A reference is not recognized in a CDATA section: the
+/// section is closed before the reference and reopened after.
+fn finish_text(escaped: String, text: TextOptions) -> io::Result<String> {
+    if !escaped.contains(is_control_char) && (!text.ascii_only || escaped.is_ascii()) {
+        return Ok(escaped);
+    }
+    let cdata = escaped.starts_with("<![CDATA[");
+    let mut finished = String::with_capacity(escaped.len() + 16);
+    for c in escaped.chars() {
+        let c = match c {
+            '\0' if text.control_chars == ControlChars::Reference => char::REPLACEMENT_CHARACTER,
+            c if is_control_char(c) => match text.control_chars {
+                ControlChars::Replace => char::REPLACEMENT_CHARACTER,
+                ControlChars::Strip => continue,
+                ControlChars::Reference => c,
+                ControlChars::Error => {
+                    return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Control char `0x{:02x}` in a text", c as u32)));
+                }
+            },
+            c => c,
+        };
+        if (c.is_ascii() || !text.ascii_only) && !is_control_char(c) {
+            finished.push(c);
+        } else if cdata {
+            finished.push_str(&format!("]]>&#x{:X};<![CDATA[", c as u32));
+        } else {
+            finished.push_str(&format!("&#x{:X};", c as u32));
+        }
+    }
+    Ok(finished.replace("<![CDATA[]]>", ""))
+}
+
+/// How a key becomes an element name. A key is not always a valid XML name, e.g. `1abc`,
+/// `foo bar` or an empty key, and a colon is read as a namespace prefix.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum XmlNamePolicy {
+    /// Use the key as it is: the XML may be malformed.
+    #[default]
+    AsIs,
+    /// Replace the invalid chars and the colons with `_`, and prefix the names that do not start
+    /// with a letter or `_`, or that start with `xml`, with `_`. Two keys may give the same name.
+    Mangle,
+    /// Encode the invalid chars and the colons as `_xHHHH_`, as .NET's `XmlConvert.EncodeName`
+    /// (the `%` of the percent-encoding is not allowed in a name). The key can be decoded. An
+    /// empty key is written `_x_`.
+    HexEncode,
+    /// Write an `<entry key="...">` element: the key is the value of an attribute.
+    Entry,
+}
+
+/// NameStartChar of XML 1.0, without the colon.
+fn is_name_start_char(c: char) -> bool {
+    matches!(c, 'A'..='Z' | '_' | 'a'..='z' | '\u{C0}'..='\u{D6}' | '\u{D8}'..='\u{F6}'
+        | '\u{F8}'..='\u{2FF}' | '\u{370}'..='\u{37D}' | '\u{37F}'..='\u{1FFF}' | '\u{200C}'..='\u{200D}'
+        | '\u{2070}'..='\u{218F}' | '\u{2C00}'..='\u{2FEF}' | '\u{3001}'..='\u{D7FF}' | '\u{F900}'..='\u{FDCF}'
+        | '\u{FDF0}'..='\u{FFFD}' | '\u{10000}'..='\u{EFFFF}')
+}
+
+/// NameChar of XML 1.0, without the colon.
+fn is_name_char(c: char) -> bool {
+    is_name_start_char(c) || matches!(c, '-' | '.' | '0'..='9' | '\u{B7}' | '\u{300}'..='\u{36F}' | '\u{203F}'..='\u{2040}')
+}
+
+/// The start tag of the element of a key: the name, and the attribute of an `Entry`.
+fn key_start_tag(key: String, policy: XmlNamePolicy) -> String {
+    match policy {
+        XmlNamePolicy::AsIs => key,
+        XmlNamePolicy::Mangle => {
+            let mut name: String = key.chars().map(|c| if is_name_char(c) { c } else { '_' }).collect();
+            if !name.starts_with(is_name_start_char) || name.to_lowercase().starts_with("xml") {
+                name.insert(0, '_');
+            }
+            name
+        }
+        XmlNamePolicy::HexEncode => {
+            if key.is_empty() {
+                return "_x_".into();
+            }
+            let mut name = String::with_capacity(key.len());
+            let mut chars = key.chars().peekable();
+            let mut first = true;
+            while let Some(c) = chars.next() {
+                let valid = if first { is_name_start_char(c) && !key.to_lowercase().starts_with("xml") } else { is_name_char(c) };
+                // a `_` that could be read as the start of an encoded char is encoded
+                if !valid || (c == '_' && chars.peek() == Some(&'x')) {
+                    name.push_str(&format!("_x{:04X}_", c as u32));
+                } else {
+                    name.push(c);
+                }
+                first = false;
+            }
+            name
+        }
+        XmlNamePolicy::Entry => format!("entry key=\"{}\"", escape_attribute(&key)),
+    }
+}
+
+/// The name of an element, without the attributes of the start tag.
+fn tag_name(start_tag: &str) -> &str {
+    start_tag.split(' ').next().unwrap_or(start_tag)
+}
+
+/// Escape an attribute value written between double quotes. The tabs and the newlines are written
+/// as references, since they would be normalized to spaces.
+fn escape_attribute(value: &str) -> String {
+    value.replace('&', "&amp;").replace('<', "&lt;").replace('"', "&quot;")
+        .replace('\t', "&#x9;").replace('\n', "&#xA;").replace('\r', "&#xD;")
+}
+
+/// Write the XML declaration followed by the prolog items, one per line.
+fn write_declaration<W: Write>(destination: &mut W, prolog: &[String], text: TextOptions) -> io::Result<()> {
+    writeln!(destination, "<?xml version=\"{}\" encoding=\"utf-8\"?>", text.xml_version())?;
+    for item in prolog {
+        writeln!(destination, "{}", item)?;
+    }
+    Ok(())
+}
+
 pub struct FormattedTypedXMLWrite<W: Write> {
     destination: W,
+    text: TextOptions,
 }
 
 impl<W: Write> XMLWrite<W> for FormattedTypedXMLWrite<W> {
+    fn set_text_options(&mut self, text: TextOptions) {
+        self.text = text;
+    }
+
     fn write_value(&mut self, size: usize, cur_key: String, value_type: &str, value: String) -> io::Result<()> {
-        write!(self.destination, "{0: >1$}<{2} type=\"{3}\">{4}</{2}>\n", "", size, cur_key, value_type, value)
+        writeln!(self.destination, "{0: >1$}<{2} type=\"{3}\">{4}</{5}>", "", size, cur_key, value_type, value, tag_name(&cur_key))
     }
 
     fn write_string_value(&mut self, size: usize, cur_key: String, value: String) -> io::Result<()> {
         if value.is_empty() {
-            write!(self.destination, "{0: >1$}<{2} type=\"string\"/>\n", "", size, cur_key)
+            writeln!(self.destination, "{0: >1$}<{2} type=\"string\"/>", "", size, cur_key)
         } else {
-            let e_value = FormattedTypedXMLWrite::<W>::escape_value(value);
-            write!(self.destination, "{0: >1$}<{2} type=\"string\">{3}</{2}>\n", "", size, cur_key, e_value)
+            let e_value = FormattedTypedXMLWrite::<W>::escape_text(value, self.text)?;
+            writeln!(self.destination, "{0: >1$}<{2} type=\"string\">{3}</{4}>", "", size, cur_key, e_value, tag_name(&cur_key))
         }
     }
 
-    fn write_open(&mut self) -> io::Result<()> {
-        write!(self.destination, "{}\n<{}>\n", "<?xml version=\"1.0\" encoding=\"utf-8\"?>", "root")
+    fn write_open(&mut self, prolog: &[String], root_start_tag: &str) -> io::Result<()> {
+        write_declaration(&mut self.destination, prolog, self.text)?;
+        writeln!(self.destination, "<{}>", root_start_tag)
     }
 
-    fn write_close(&mut self) -> io::Result<()> {
-        write!(self.destination, "</{}>\n", "root")
+    fn write_close(&mut self, root: &str) -> io::Result<()> {
+        writeln!(self.destination, "</{}>", root)
     }
 
     fn write_begin(&mut self, size: usize, cur_key: &str) -> io::Result<()> {
-        write!(self.destination, "{0: >1$}<{2}>\n", "", size, cur_key)
+        writeln!(self.destination, "{0: >1$}<{2}>", "", size, cur_key)
     }
 
     fn write_end(&mut self, size: usize, cur_key: &str) -> io::Result<()> {
-        write!(self.destination, "{0: >1$}</{2}>\n", "", size, cur_key)
+        writeln!(self.destination, "{0: >1$}</{2}>", "", size, tag_name(cur_key))
+    }
+
+    fn write_compact(&mut self, size: usize, cur_key: &str, values: Vec<(String, &str, String)>) -> io::Result<()> {
+        write!(self.destination, "{0: >1$}<{2}>", "", size, cur_key)?;
+        for (key, value_type, value) in values {
+            if value_type == "string" && value.is_empty() {
+                write!(self.destination, "<{0} type=\"string\"/>", key)?;
+            } else if value_type == "string" {
+                let e_value = FormattedTypedXMLWrite::<W>::escape_text(value, self.text)?;
+                write!(self.destination, "<{0} type=\"string\">{1}</{2}>", key, e_value, tag_name(&key))?;
+            } else {
+                write!(self.destination, "<{0} type=\"{1}\">{2}</{3}>", key, value_type, value, tag_name(&key))?;
+            }
+        }
+        writeln!(self.destination, "</{}>", tag_name(cur_key))
     }
 }
 
 impl<W: Write> JSON2XMLConsumer<W, FormattedTypedXMLWrite<W>> {
     pub fn new_formatted_and_typed(destination: W) -> JSON2XMLConsumer<W, FormattedTypedXMLWrite<W>> {
-        JSON2XMLConsumer {
-            xml_write: FormattedTypedXMLWrite { destination },
-            states_stack: vec!(),
-            keys_stack: vec!(),
-            phantom: PhantomData,
-        }
+        JSON2XMLConsumer::from_xml_write(FormattedTypedXMLWrite { destination, text: TextOptions::default() })
     }
 }
 
 pub struct FormattedXMLWrite<W: Write> {
     destination: W,
+    text: TextOptions,
 }
 
 impl<W: Write> XMLWrite<W> for FormattedXMLWrite<W> {
+    fn set_text_options(&mut self, text: TextOptions) {
+        self.text = text;
+    }
+
     fn write_value(&mut self, size: usize, cur_key: String, _value_type: &str, value: String) -> io::Result<()> {
-        write!(self.destination, "{0: >1$}<{2}>{3}</{2}>\n", "", size, cur_key, value)
+        writeln!(self.destination, "{0: >1$}<{2}>{3}</{4}>", "", size, cur_key, value, tag_name(&cur_key))
     }
 
     fn write_string_value(&mut self, size: usize, cur_key: String, value: String) -> io::Result<()> {
         if value.is_empty() {
-            write!(self.destination, "{0: >1$}<{2}/>\n", "", size, cur_key)
+            writeln!(self.destination, "{0: >1$}<{2}/>", "", size, cur_key)
         } else {
-            let e_value = FormattedXMLWrite::<W>::escape_value(value);
-            write!(self.destination, "{0: >1$}<{2}>{3}</{2}>\n", "", size, cur_key, e_value)
+            let e_value = FormattedXMLWrite::<W>::escape_text(value, self.text)?;
+            writeln!(self.destination, "{0: >1$}<{2}>{3}</{4}>", "", size, cur_key, e_value, tag_name(&cur_key))
         }
     }
 
-    fn write_open(&mut self) -> io::Result<()> {
-        write!(self.destination, "{}\n<{}>\n", "<?xml version=\"1.0\" encoding=\"utf-8\"?>", "root")
+    fn write_open(&mut self, prolog: &[String], root_start_tag: &str) -> io::Result<()> {
+        write_declaration(&mut self.destination, prolog, self.text)?;
+        writeln!(self.destination, "<{}>", root_start_tag)
     }
 
-    fn write_close(&mut self) -> io::Result<()> {
-        write!(self.destination, "</{}>\n", "root")
+    fn write_close(&mut self, root: &str) -> io::Result<()> {
+        writeln!(self.destination, "</{}>", root)
     }
 
     fn write_begin(&mut self, size: usize, cur_key: &str) -> io::Result<()> {
-        write!(self.destination, "{0: >1$}<{2}>\n", "", size, cur_key)
+        writeln!(self.destination, "{0: >1$}<{2}>", "", size, cur_key)
     }
 
     fn write_end(&mut self, size: usize, cur_key: &str) -> io::Result<()> {
-        write!(self.destination, "{0: >1$}</{2}>\n", "", size, cur_key)
+        writeln!(self.destination, "{0: >1$}</{2}>", "", size, tag_name(cur_key))
+    }
+
+    fn write_compact(&mut self, size: usize, cur_key: &str, values: Vec<(String, &str, String)>) -> io::Result<()> {
+        write!(self.destination, "{0: >1$}<{2}>", "", size, cur_key)?;
+        for (key, value_type, value) in values {
+            if value_type == "string" && value.is_empty() {
+                write!(self.destination, "<{0}/>", key)?;
+            } else if value_type == "string" {
+                let e_value = FormattedXMLWrite::<W>::escape_text(value, self.text)?;
+                write!(self.destination, "<{0}>{1}</{2}>", key, e_value, tag_name(&key))?;
+            } else {
+                write!(self.destination, "<{0}>{1}</{2}>", key, value, tag_name(&key))?;
+            }
+        }
+        writeln!(self.destination, "</{}>", tag_name(cur_key))
     }
 }
 
 impl<W: Write> JSON2XMLConsumer<W, FormattedXMLWrite<W>> {
     pub fn new_formatted(destination: W) -> JSON2XMLConsumer<W, FormattedXMLWrite<W>> {
-        JSON2XMLConsumer {
-            xml_write: FormattedXMLWrite { destination },
-            states_stack: vec!(),
-            keys_stack: vec!(),
-            phantom: PhantomData,
-        }
+        JSON2XMLConsumer::from_xml_write(FormattedXMLWrite { destination, text: TextOptions::default() })
     }
 }
 
 pub struct TypedXMLWrite<W: Write> {
     destination: W,
+    text: TextOptions,
 }
 
 impl<W: Write> XMLWrite<W> for TypedXMLWrite<W> {
+    fn set_text_options(&mut self, text: TextOptions) {
+        self.text = text;
+    }
+
     fn write_value(&mut self, _size: usize, cur_key: String, value_type: &str, value: String) -> io::Result<()> {
-        write!(self.destination, "<{0} type=\"{1}\">{2}</{0}>", cur_key, value_type, value)
+        write!(self.destination, "<{0} type=\"{1}\">{2}</{3}>", cur_key, value_type, value, tag_name(&cur_key))
     }
 
     fn write_string_value(&mut self, _size: usize, cur_key: String, value: String) -> io::Result<()> {
         if value.is_empty() {
             write!(self.destination, "<{0} type=\"string\"/>", cur_key)
         } else {
-            let e_value = TypedXMLWrite::<W>::escape_value(value);
-            write!(self.destination, "<{0} type=\"string\">{1}</{0}>", cur_key, e_value)
+            let e_value = TypedXMLWrite::<W>::escape_text(value, self.text)?;
+            write!(self.destination, "<{0} type=\"string\">{1}</{2}>", cur_key, e_value, tag_name(&cur_key))
         }
     }
 
-    fn write_open(&mut self) -> io::Result<()> {
-        write!(self.destination, "{}\n<{}>", "<?xml version=\"1.0\" encoding=\"utf-8\"?>", "root")
+    fn write_open(&mut self, prolog: &[String], root_start_tag: &str) -> io::Result<()> {
+        write_declaration(&mut self.destination, prolog, self.text)?;
+        write!(self.destination, "<{}>", root_start_tag)
     }
 
-    fn write_close(&mut self) -> io::Result<()> {
-        write!(self.destination, "</{}>", "root")
+    fn write_close(&mut self, root: &str) -> io::Result<()> {
+        write!(self.destination, "</{}>", root)
     }
 
     fn write_begin(&mut self, _size: usize, cur_key: &str) -> io::Result<()> {
@@ -176,45 +436,46 @@ impl<W: Write> XMLWrite<W> for TypedXMLWrite<W> {
     }
 
     fn write_end(&mut self, _size: usize, cur_key: &str) -> io::Result<()> {
-        write!(self.destination, "</{}>", cur_key)
+        write!(self.destination, "</{}>", tag_name(cur_key))
     }
 }
 
 impl<W: Write> JSON2XMLConsumer<W, TypedXMLWrite<W>> {
     pub fn new_typed(destination: W) -> JSON2XMLConsumer<W, TypedXMLWrite<W>> {
-        JSON2XMLConsumer {
-            xml_write: TypedXMLWrite { destination },
-            states_stack: vec!(),
-            keys_stack: vec!(),
-            phantom: PhantomData,
-        }
+        JSON2XMLConsumer::from_xml_write(TypedXMLWrite { destination, text: TextOptions::default() })
     }
 }
 
 pub struct RawXMLWrite<W: Write> {
     destination: W,
+    text: TextOptions,
 }
 
 impl<W: Write> XMLWrite<W> for RawXMLWrite<W> {
+    fn set_text_options(&mut self, text: TextOptions) {
+        self.text = text;
+    }
+
     fn write_value(&mut self, _size: usize, cur_key: String, _value_type: &str, value: String) -> io::Result<()> {
-        write!(self.destination, "<{0}>{1}</{0}>", cur_key, value)
+        write!(self.destination, "<{0}>{1}</{2}>", cur_key, value, tag_name(&cur_key))
     }
 
     fn write_string_value(&mut self, _size: usize, cur_key: String, value: String) -> io::Result<()> {
         if value.is_empty() {
             write!(self.destination, "<{0}/>", cur_key)
         } else {
-            let e_value = RawXMLWrite::<W>::escape_value(value);
-            write!(self.destination, "<{0}>{1}</{0}>", cur_key, e_value)
+            let e_value = RawXMLWrite::<W>::escape_text(value, self.text)?;
+            write!(self.destination, "<{0}>{1}</{2}>", cur_key, e_value, tag_name(&cur_key))
         }
     }
 
-    fn write_open(&mut self) -> io::Result<()> {
-        write!(self.destination, "{}\n<{}>", "<?xml version=\"1.0\" encoding=\"utf-8\"?>", "root")
+    fn write_open(&mut self, prolog: &[String], root_start_tag: &str) -> io::Result<()> {
+        write_declaration(&mut self.destination, prolog, self.text)?;
+        write!(self.destination, "<{}>", root_start_tag)
     }
 
-    fn write_close(&mut self) -> io::Result<()> {
-        write!(self.destination, "</{}>", "root")
+    fn write_close(&mut self, root: &str) -> io::Result<()> {
+        write!(self.destination, "</{}>", root)
     }
 
     fn write_begin(&mut self, _size: usize, cur_key: &str) -> io::Result<()> {
@@ -222,18 +483,13 @@ impl<W: Write> XMLWrite<W> for RawXMLWrite<W> {
     }
 
     fn write_end(&mut self, _size: usize, cur_key: &str) -> io::Result<()> {
-        write!(self.destination, "</{}>", cur_key)
+        write!(self.destination, "</{}>", tag_name(cur_key))
     }
 }
 
 impl<W: Write> JSON2XMLConsumer<W, RawXMLWrite<W>> {
     pub fn new(destination: W) -> JSON2XMLConsumer<W, RawXMLWrite<W>> {
-        JSON2XMLConsumer {
-            xml_write: RawXMLWrite { destination },
-            states_stack: vec!(),
-            keys_stack: vec!(),
-            phantom: PhantomData,
-        }
+        JSON2XMLConsumer::from_xml_write(RawXMLWrite { destination, text: TextOptions::default() })
     }
 }
 
@@ -241,39 +497,135 @@ pub struct JSON2XMLConsumer<W: Write, T: XMLWrite<W>> {
     pub states_stack: Vec<ParserToken>,
     pub keys_stack: Vec<String>,
     pub xml_write: T,
+    scalar_array_delimiter: Option<String>,
+    compact: bool,
+    prolog: Vec<String>,
+    key_names: XmlNamePolicy,
+    root: String,
+    /// The attributes of the root, namespace declarations included
+    root_attributes: Vec<(String, String)>,
+    true_text: String,
+    false_text: String,
+    null_text: String,
+    text: TextOptions,
+    pending_values: Option<Vec<(String, &'static str, String)>>,
+    pending_key: Option<String>,
+    /// The chunks of a string: XML has no use of a partial text.
+    chunks: Vec<u8>,
+    /// The name of the attribute of a key, if the scalar members are written as attributes.
+    attribute_keys: Option<Box<AttributeName>>,
+    /// The name of the attribute and the key, before the value.
+    pending_attribute: Option<(String, String)>,
+    /// The attributes of the start tag that is not written yet, with the escaped values.
+    attributes: Vec<(String, String)>,
+    open_tag: Option<OpenTag>,
+    /// The unescaped texts of `true`, `false` and `null`
+    literal_texts: [String; 3],
     phantom: PhantomData<W>,
 }
 
+/// Returns the name of the attribute of a key, see `JSON2XMLConsumer::attribute_keys`.
+type AttributeName = dyn Fn(&str) -> Option<String>;
+
+/// A start tag that waits for the attributes.
+#[derive(Debug, PartialEq)]
+enum OpenTag {
+    Root,
+    /// The indentation and the start tag
+    Element(usize, String),
+}
+
 impl<W: Write, T: XMLWrite<W>> JSONParseConsumer for JSON2XMLConsumer<W, T> {
     fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = match token {
+            Ok(StringStart) => return Ok(()),
+            Ok(StringChunk(chunk)) => {
+                self.chunks.extend(chunk);
+                return Ok(());
+            }
+            Ok(StringEnd) => Ok(StringValue(String::from_utf8_lossy(&std::mem::take(&mut self.chunks)).into_owned())),
+            t => t,
+        };
+        if self.attribute_keys.is_some() {
+            return self.consume_with_attributes(token);
+        }
+        self.consume_token(token)
+    }
+}
+
+
+impl<W: Write, T: XMLWrite<W>> JSON2XMLConsumer<W, T> {
+    fn consume_token(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        if let Some(values) = self.pending_values.as_mut() {
+            let value = match token {
+                Ok(Key(s)) => {
+                    self.pending_key = Some(key_start_tag(s, self.key_names));
+                    return Ok(());
+                }
+                Ok(BooleanValue(b)) => Some(("boolean", if b { self.true_text.clone() } else { self.false_text.clone() })),
+                Ok(NullValue) => Some(("null", self.null_text.clone())),
+                Ok(StringValue(ref s)) => Some(("string", s.clone())),
+                Ok(BytesValue(ref b)) => Some(("string", String::from_utf8_lossy(b).into_owned())),
+                Ok(IntValue(ref s)) => Some(("int", s.clone())),
+                Ok(FloatValue(ref s)) => Some(("float", s.clone())),
+                Ok(EndObject) | Ok(EndArray) => {
+                    return self.write_pending_container().map_err(xml_error);
+                }
+                _ => None,
+            };
+            match value {
+                Some((value_type, value)) => {
+                    let cur_key = self.pending_key.take().unwrap_or_else(|| "li".into());
+                    values.push((cur_key, value_type, value));
+                    return Ok(());
+                }
+                None => {
+                    self.write_pending_values().map_err(xml_error)?;
+                }
+            }
+        }
         let result = match token {
             Ok(BeginFile) => {
-                self.xml_write.write_open()
+                let root_start_tag = self.root_start_tag();
+                self.xml_write.write_open(&self.prolog, &root_start_tag)
             }
             Ok(EndFile) => {
-                self.xml_write.write_close()
+                self.xml_write.write_close(&self.root)
+            }
+            Ok(BeginObject) | Ok(BeginArray) if !self.states_stack.is_empty() &&
+                (self.compact || (self.scalar_array_delimiter.is_some() && matches!(token, Ok(BeginArray)))) => {
+                // wait for the end of the container or for a child container
+                if let Some(BeginArray) = self.states_stack.last() {
+                    self.keys_stack.push("li".into());
+                }
+                self.states_stack.push(token.unwrap());
+                self.pending_values = Some(vec!());
+                Ok(())
             }
             Ok(BeginObject) | Ok(BeginArray) => {
                 let r = match self.states_stack.last() {
+                    Some(BeginArray) if self.attribute_keys.is_some() && matches!(token, Ok(BeginObject)) => {
+                        self.keys_stack.push("li".into());
+                        self.open_tag = Some(OpenTag::Element(self.states_stack.len() * 4, "li".into()));
+                        Ok(())
+                    }
                     Some(BeginArray) => {
                         let cur_key = "li";
                         self.keys_stack.push(cur_key.into());
                         self.xml_write.write_begin(self.states_stack.len() * 4, cur_key)
                     }
+                    Some(_) if self.attribute_keys.is_some() && matches!(token, Ok(BeginObject)) => {
+                        let cur_key = self.keys_stack.last().unwrap();
+                        self.open_tag = Some(OpenTag::Element(self.states_stack.len() * 4, cur_key.clone()));
+                        Ok(())
+                    }
                     Some(_) => {
                         let cur_key = self.keys_stack.last().unwrap();
                         self.xml_write.write_begin(self.states_stack.len() * 4, cur_key)
                     }
                     None => { Ok(()) }
                 };
-                match r {
-                    Err(e) => { return Err(ConsumeError {
-                        msg: format!("write error {:?}", e.kind()),
-                        line: 0,
-                        column: 0,
-                    }); }
-                    _ => {}
-                }
+                r.map_err(xml_error)?;
                 self.states_stack.push(token.unwrap());
                 Ok(())
             }
@@ -290,22 +642,27 @@ impl<W: Write, T: XMLWrite<W>> JSONParseConsumer for JSON2XMLConsumer<W, T> {
                 }
             }
             Ok(Key(s)) => {
-                self.keys_stack.push(s);
+                self.keys_stack.push(key_start_tag(s, self.key_names));
                 Ok(())
             }
             Ok(BooleanValue(b)) => {
                 let cur_key = self.get_cur_key();
-                let value = if b { "true".into() } else { "false".into() };
+                let value = if b { self.true_text.clone() } else { self.false_text.clone() };
                 self.xml_write.write_value(self.states_stack.len() * 4, cur_key, "boolean", value)
             }
             Ok(NullValue) => {
                 let cur_key = self.get_cur_key();
-                self.xml_write.write_value(self.states_stack.len() * 4, cur_key, "null", String::from("null"))
+                self.xml_write.write_value(self.states_stack.len() * 4, cur_key, "null", self.null_text.clone())
             }
             Ok(StringValue(s)) => {
                 let cur_key = self.get_cur_key();
                 self.xml_write.write_string_value(self.states_stack.len() * 4, cur_key, s)
             }
+            Ok(BytesValue(b)) => {
+                let cur_key = self.get_cur_key();
+                let s = String::from_utf8_lossy(&b).into_owned();
+                self.xml_write.write_string_value(self.states_stack.len() * 4, cur_key, s)
+            }
             Ok(IntValue(s)) => {
                 let cur_key = self.get_cur_key();
                 self.xml_write.write_value(self.states_stack.len() * 4, cur_key, "int", s)
@@ -314,29 +671,358 @@ impl<W: Write, T: XMLWrite<W>> JSONParseConsumer for JSON2XMLConsumer<W, T> {
                 let cur_key = self.get_cur_key();
                 self.xml_write.write_value(self.states_stack.len() * 4, cur_key, "float", s)
             }
-            Err(e) => {
-                return Err(ConsumeError {
-                    msg: e.msg,
-                    line: e.line,
-                    column: e.column,
-                });
+            // rebuilt above
+            Ok(StringStart) | Ok(StringChunk(_)) | Ok(StringEnd) => Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        result.map_err(xml_error)
+    }
+
+    /// Capture the attributes and delay the start tags of the objects until their first child
+    /// element.
+    fn consume_with_attributes(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let tag_open = self.open_tag.is_some() || self.pending_values.is_some();
+        match (self.pending_attribute.take(), &token) {
+            (None, Ok(Key(key))) if tag_open => {
+                if let Some(name) = self.attribute_keys.as_ref().and_then(|f| f(key)) {
+                    self.pending_attribute = Some((name, key.clone()));
+                    return Ok(());
+                }
+            }
+            (Some((name, key)), Ok(value)) => {
+                match self.attribute_text(value) {
+                    Some(text) => {
+                        let text = text.map_err(xml_error)?;
+                        self.attributes.push((name, text));
+                        return Ok(());
+                    }
+                    // a container: the member is an element
+                    None => {
+                        self.flush_open_tag().map_err(xml_error)?;
+                        self.consume_token(Ok(Key(key)))?;
+                    }
+                }
+            }
+            _ => {}
+        }
+        match token {
+            Ok(BeginFile) => {
+                self.open_tag = Some(OpenTag::Root);
+                Ok(())
             }
+            Ok(BeginObject) if self.states_stack.is_empty() && self.open_tag == Some(OpenTag::Root) => {
+                self.states_stack.push(BeginObject);
+                Ok(())
+            }
+            token => {
+                self.flush_open_tag().map_err(xml_error)?;
+                self.consume_token(token)
+            }
+        }
+    }
+
+    /// The escaped text of a scalar written as an attribute value.
+    fn attribute_text(&self, value: &ParserToken) -> Option<io::Result<String>> {
+        let text = match value {
+            BooleanValue(true) => self.literal_texts[0].clone(),
+            BooleanValue(false) => self.literal_texts[1].clone(),
+            NullValue => self.literal_texts[2].clone(),
+            StringValue(s) | IntValue(s) | FloatValue(s) => s.clone(),
+            BytesValue(b) => String::from_utf8_lossy(b).into_owned(),
+            _ => return None,
         };
-        match result {
-            Ok(_) => { Ok(()) }
-            Err(e) => {
-                return Err(ConsumeError {
-                    msg: format!("write error {:?}", e.kind()),
-                    line: 0,
-                    column: 0,
-                });
+        Some(finish_text(escape_attribute(&text), self.text))
+    }
+
+    /// Append the captured attributes to a start tag.
+    fn with_attributes(&mut self, mut start_tag: String) -> String {
+        for (name, value) in self.attributes.drain(..) {
+            start_tag.push_str(&format!(" {}=\"{}\"", name, value));
+        }
+        start_tag
+    }
+
+    /// Write the start tag that waits for the attributes.
+    fn flush_open_tag(&mut self) -> io::Result<()> {
+        match self.open_tag.take() {
+            Some(OpenTag::Root) => {
+                let root_start_tag = self.root_start_tag();
+                let root_start_tag = self.with_attributes(root_start_tag);
+                self.xml_write.write_open(&self.prolog, &root_start_tag)
+            }
+            Some(OpenTag::Element(size, start_tag)) => {
+                let start_tag = self.with_attributes(start_tag);
+                self.xml_write.write_begin(size, &start_tag)
             }
+            None => Ok(()),
         }
     }
-}
 
+    fn root_start_tag(&self) -> String {
+        let mut root_start_tag = self.root.clone();
+        for (name, value) in &self.root_attributes {
+            root_start_tag.push_str(&format!(" {}=\"{}\"", name, escape_attribute(value)));
+        }
+        root_start_tag
+    }
+
+    fn from_xml_write(xml_write: T) -> Self {
+        JSON2XMLConsumer {
+            xml_write,
+            states_stack: vec!(),
+            keys_stack: vec!(),
+            scalar_array_delimiter: None,
+            compact: false,
+            prolog: vec!(),
+            key_names: XmlNamePolicy::AsIs,
+            root: "root".into(),
+            root_attributes: vec!(),
+            true_text: "true".into(),
+            false_text: "false".into(),
+            null_text: "null".into(),
+            text: TextOptions::default(),
+            pending_values: None,
+            pending_key: None,
+            chunks: vec!(),
+            attribute_keys: None,
+            pending_attribute: None,
+            attributes: vec!(),
+            open_tag: None,
+            literal_texts: ["true".into(), "false".into(), "null".into()],
+            phantom: PhantomData,
+        }
+    }
+
+    /// Apply the options, except `formatted` and `typed` that are given by the constructor.
+    pub fn with_options(mut self, opts: &Json2XmlOptions) -> Self {
+        if opts.ascii_only {
+            self = self.ascii_only();
+        }
+        self = self.text_escaping(opts.text_escaping);
+        self = self.control_chars(opts.control_chars);
+        if let Some(delimiter) = &opts.scalar_array_delimiter {
+            self = self.join_scalar_arrays(delimiter);
+        }
+        if opts.compact {
+            self = self.compact_scalar_containers();
+        }
+        for comment in &opts.comments {
+            self = self.comment(comment);
+        }
+        for (target, data) in &opts.processing_instructions {
+            self = self.processing_instruction(target, data);
+        }
+        if let Some(doctype) = &opts.doctype {
+            self = self.doctype(doctype);
+        }
+        self = self.key_names(opts.key_names);
+        if let Some(prefix) = &opts.attribute_prefix {
+            self = self.attribute_prefix(prefix);
+        }
+        if let Some(root) = &opts.root {
+            self = self.root(root);
+        }
+        if let Some(namespace) = &opts.namespace {
+            self = self.namespace(namespace);
+        }
+        for (name, value) in &opts.root_attributes {
+            self = self.root_attribute(name, value);
+        }
+        if let Some(text) = &opts.true_text {
+            self = self.true_text(text);
+        }
+        if let Some(text) = &opts.false_text {
+            self = self.false_text(text);
+        }
+        if let Some(text) = &opts.null_text {
+            self = self.null_text(text);
+        }
+        self
+    }
+
+    /// The text of the `true` values (default: `true`), e.g. `1` or `yes`.
+    pub fn true_text(mut self, text: &str) -> Self {
+        self.literal_texts[0] = text.into();
+        self.escape_literal_texts();
+        self
+    }
+
+    /// The text of the `false` values (default: `false`), e.g. `0` or `no`.
+    pub fn false_text(mut self, text: &str) -> Self {
+        self.literal_texts[1] = text.into();
+        self.escape_literal_texts();
+        self
+    }
+
+    /// The text of the `null` values (default: `null`). An empty text gives an empty element.
+    pub fn null_text(mut self, text: &str) -> Self {
+        self.literal_texts[2] = text.into();
+        self.escape_literal_texts();
+        self
+    }
+
+    /// Add a processing instruction before the root, e.g.
+    /// `processing_instruction("xml-stylesheet", "type=\"text/xsl\" href=\"style.xsl\"")`.
+    /// A `?>` in the data is written `? >`.
+    pub fn processing_instruction(mut self, target: &str, data: &str) -> Self {
+        if data.is_empty() {
+            self.prolog.push(format!("<?{}?>", target));
+        } else {
+            self.prolog.push(format!("<?{} {}?>", target, data.replace("?>", "? >")));
+        }
+        self
+    }
+
+    /// Add a DOCTYPE before the root, e.g. `doctype("root SYSTEM \"root.dtd\"")`.
+    pub fn doctype(mut self, doctype: &str) -> Self {
+        self.prolog.push(format!("<!DOCTYPE {}>", doctype));
+        self
+    }
+
+    /// How the keys become element names (default: `XmlNamePolicy::AsIs`).
+    pub fn key_names(mut self, policy: XmlNamePolicy) -> Self {
+        self.key_names = policy;
+        self
+    }
+
+    /// Write the scalar members whose key starts with `prefix` as attributes of the element of
+    /// their object, e.g. with `@`, `{"a": {"@id": 1, "b": 2}}` gives `<a id="1"><b>2</b></a>`
+    /// (BadgerFish convention). The attributes of the top-level object are written on the root.
+    pub fn attribute_prefix(self, prefix: &str) -> Self {
+        let prefix = prefix.to_string();
+        self.attribute_keys(move |key| key.strip_prefix(prefix.as_str()).filter(|name| !name.is_empty()).map(|name| name.into()))
+    }
+
+    /// Write a scalar member as an attribute if `f` returns a name for its key. The start tag of
+    /// an object is written before its first child element: a member that comes after (see
+    /// `compact_scalar_containers`), or whose value is a container, is written as an element.
+    /// The attribute names are written as they are.
+    pub fn attribute_keys<F: Fn(&str) -> Option<String> + 'static>(mut self, f: F) -> Self {
+        self.attribute_keys = Some(Box::new(f));
+        self
+    }
+
+    /// The name of the root element (default: `root`), e.g. `payload`.
+    pub fn root(mut self, name: &str) -> Self {
+        self.root = name.into();
+        self
+    }
+
+    /// Declare the default namespace on the root, e.g. `namespace("http://example.com/ns")`
+    /// gives `<root xmlns="http://example.com/ns">`.
+    pub fn namespace(self, uri: &str) -> Self {
+        self.root_attribute("xmlns", uri)
+    }
+
+    /// Add an attribute to the root, e.g. `root_attribute("version", "1.0")` or
+    /// `root_attribute("xmlns:xsi", "http://www.w3.org/2001/XMLSchema-instance")`. The value is
+    /// escaped.
+    pub fn root_attribute(mut self, name: &str, value: &str) -> Self {
+        self.root_attributes.push((name.into(), value.into()));
+        self
+    }
+
+    /// Add a comment before the root. A `--` in the text is written `- -`.
+    pub fn comment(mut self, text: &str) -> Self {
+        let mut text = text.to_string();
+        while text.contains("--") {
+            text = text.replace("--", "- -");
+        }
+        if text.ends_with('-') {
+            text.push(' ');
+        }
+        self.prolog.push(format!("<!--{}-->", text));
+        self
+    }
+
+    /// Write the non ASCII chars of the values as character references, e.g. `&#xE9;`, for the
+    /// readers that choke on UTF-8. The keys are element names: they are written as they are.
+    pub fn ascii_only(mut self) -> Self {
+        self.text.ascii_only = true;
+        self.xml_write.set_text_options(self.text);
+        self.escape_literal_texts();
+        self
+    }
+
+    /// How the special chars of the texts are escaped (default: `TextEscaping::Cdata`).
+    pub fn text_escaping(mut self, escaping: TextEscaping) -> Self {
+        self.text.escaping = escaping;
+        self.xml_write.set_text_options(self.text);
+        self.escape_literal_texts();
+        self
+    }
+
+    /// What to do with the control chars of the texts (default: `ControlChars::Replace`).
+    pub fn control_chars(mut self, policy: ControlChars) -> Self {
+        self.text.control_chars = policy;
+        self.xml_write.set_text_options(self.text);
+        self.escape_literal_texts();
+        self
+    }
+
+    fn escape_literal_texts(&mut self) {
+        // the texts are replaced, not checked
+        let text = match self.text.control_chars {
+            ControlChars::Error => TextOptions { control_chars: ControlChars::Replace, ..self.text },
+            _ => self.text,
+        };
+        let [true_text, false_text, null_text] = self.literal_texts.clone().map(|s| T::escape_text(s, text).unwrap_or_default());
+        self.true_text = true_text;
+        self.false_text = false_text;
+        self.null_text = null_text;
+    }
+
+    /// Write the objects and arrays that contain only scalars on a single line.
+    pub fn compact_scalar_containers(mut self) -> Self {
+        self.compact = true;
+        self
+    }
+
+    /// Write the arrays of scalars as a single element whose text is the values joined by
+    /// `delimiter` (e.g. `<GlossSeeAlso>GML XML</GlossSeeAlso>`) instead of `<li>` children.
+    pub fn join_scalar_arrays(mut self, delimiter: &str) -> Self {
+        self.scalar_array_delimiter = Some(delimiter.into());
+        self
+    }
+
+    /// The container contains a container: write the values as usual.
+    fn write_pending_values(&mut self) -> io::Result<()> {
+        let values = self.pending_values.take().unwrap();
+        let size = self.states_stack.len() * 4;
+        let start_tag = self.keys_stack.last().unwrap().clone();
+        let start_tag = self.with_attributes(start_tag);
+        self.xml_write.write_begin(size - 4, &start_tag)?;
+        for (cur_key, value_type, value) in values {
+            if value_type == "string" {
+                self.xml_write.write_string_value(size, cur_key, value)?;
+            } else {
+                self.xml_write.write_value(size, cur_key, value_type, value)?;
+            }
+        }
+        if let Some(cur_key) = self.pending_key.take() {
+            self.keys_stack.push(cur_key);
+        }
+        Ok(())
+    }
+
+    /// The container contains only scalars.
+    fn write_pending_container(&mut self) -> io::Result<()> {
+        let values = self.pending_values.take().unwrap();
+        let container = self.states_stack.pop().unwrap();
+        let cur_key = self.keys_stack.pop().unwrap();
+        let size = self.states_stack.len() * 4;
+        match &self.scalar_array_delimiter {
+            Some(delimiter) if container == BeginArray => {
+                let joined = values.into_iter().map(|(_, _, v)| v).collect::<Vec<String>>().join(delimiter);
+                self.xml_write.write_value(size, cur_key, "list", T::escape_text(joined, self.text)?)
+            }
+            _ => {
+                let start_tag = self.with_attributes(cur_key);
+                self.xml_write.write_compact(size, &start_tag, values)
+            }
+        }
+    }
 
-impl<W: Write, T: XMLWrite<W>> JSON2XMLConsumer<W, T> {
     fn get_cur_key(&mut self) -> String {
         match self.states_stack.last() {
             Some(BeginArray) => { "li".into() }
@@ -346,3 +1032,163 @@ impl<W: Write, T: XMLWrite<W>> JSON2XMLConsumer<W, T> {
     }
 }
 
+
+/// A write error, or a text that can't be written (see `ControlChars::Error`).
+fn xml_error(e: io::Error) -> ConsumeError {
+    if e.kind() == io::ErrorKind::InvalidData {
+        ConsumeError { msg: e.to_string(), line: 0, column: 0, io_error: Some(e) }
+    } else {
+        ConsumeError::from_io(e)
+    }
+}
+
+/// Options of the one-call conversion functions.
+#[derive(Debug, Clone, Default)]
+pub struct Json2XmlOptions {
+    pub formatted: bool,
+    pub typed: bool,
+    pub ignore_unicode_errs: bool,
+    /// See `JSON2XMLConsumer::join_scalar_arrays`
+    pub scalar_array_delimiter: Option<String>,
+    /// See `JSON2XMLConsumer::compact_scalar_containers`
+    pub compact: bool,
+    /// Comments written before the root, see `JSON2XMLConsumer::comment`
+    pub comments: Vec<String>,
+    /// (target, data) of the processing instructions written after the comments
+    pub processing_instructions: Vec<(String, String)>,
+    /// The DOCTYPE written after the processing instructions
+    pub doctype: Option<String>,
+    /// See `JSON2XMLConsumer::key_names`
+    pub key_names: XmlNamePolicy,
+    /// See `JSON2XMLConsumer::attribute_prefix`
+    pub attribute_prefix: Option<String>,
+    /// See `JSON2XMLConsumer::root`
+    pub root: Option<String>,
+    /// See `JSON2XMLConsumer::namespace`
+    pub namespace: Option<String>,
+    /// See `JSON2XMLConsumer::root_attribute`
+    pub root_attributes: Vec<(String, String)>,
+    /// See `JSON2XMLConsumer::true_text`
+    pub true_text: Option<String>,
+    /// See `JSON2XMLConsumer::false_text`
+    pub false_text: Option<String>,
+    /// See `JSON2XMLConsumer::null_text`
+    pub null_text: Option<String>,
+    /// The values to write instead of the values at the pointers (see `SubstituteConsumer`)
+    pub substitutions: Vec<(JSONPointer, Vec<ParserToken>)>,
+    /// The values replaced by `redaction_mask` (see `RedactConsumer`). The documents of a JSON
+    /// Lines input are the elements of a root array, e.g. `/*/users/*/ssn`.
+    pub redactions: Vec<RedactPattern>,
+    /// The mask of the redacted values (default: `***`)
+    pub redaction_mask: Option<String>,
+    /// See `JSON2XMLConsumer::ascii_only`
+    pub ascii_only: bool,
+    /// See `JSON2XMLConsumer::text_escaping`
+    pub text_escaping: TextEscaping,
+    /// See `JSON2XMLConsumer::control_chars`
+    pub control_chars: ControlChars,
+}
+
+#[derive(Debug)]
+pub struct ConvertError {
+    pub msg: String,
+    pub line: usize,
+    pub column: usize,
+    /// The error of the destination (see `ConsumeError::io_error`)
+    pub io_error: Option<io::Error>,
+}
+
+/// The `io::Error`s are compared by kind.
+impl PartialEq for ConvertError {
+    fn eq(&self, other: &Self) -> bool {
+        self.msg == other.msg && self.line == other.line && self.column == other.column &&
+            self.io_error.as_ref().map(|e| e.kind()) == other.io_error.as_ref().map(|e| e.kind())
+    }
+}
+
+impl fmt::Display for ConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.msg, self.line, self.column)
+    }
+}
+
+/// The source of an error of the destination is the `io::Error`.
+impl std::error::Error for ConvertError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.io_error.as_ref().map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl From<ConsumeError> for ConvertError {
+    fn from(e: ConsumeError) -> Self {
+        ConvertError {
+            msg: e.msg,
+            line: e.line,
+            column: e.column,
+            io_error: e.io_error,
+        }
+    }
+}
+
+/// Convert a JSON document to an XML document.
+pub fn json_to_xml_string(input: &str, opts: &Json2XmlOptions) -> Result<String, ConvertError> {
+    let mut destination = String::new();
+    byte_source_to_xml_write(ByteSource::from_slice(input.as_bytes()), FmtWriter::new(&mut destination), opts)?;
+    Ok(destination)
+}
+
+/// Convert a JSON document from `input` to an XML document written to `destination`.
+pub fn json_to_xml_write<R: Read, W: Write>(input: R, destination: W, opts: &Json2XmlOptions) -> Result<(), ConvertError> {
+    byte_source_to_xml_write(ByteSource::new(input), destination, opts)
+}
+
+/// Like `json_to_xml_write`, for any byte source, e.g. a memory map (see `ByteSource::from_mmap`).
+pub fn byte_source_to_xml_write<R: Read, W: Write>(byte_source: ByteSource<R>, destination: W, opts: &Json2XmlOptions) -> Result<(), ConvertError> {
+    let mut parser = JSONParser::new(byte_source, opts.ignore_unicode_errs);
+    with_consumer(destination, opts, |mut consumer| {
+        if opts.substitutions.is_empty() {
+            parser.parse(&mut consumer)
+        } else {
+            let pointers = opts.substitutions.iter().map(|(p, _)| p.clone()).collect();
+            let mut substitute = SubstituteConsumer::new(&mut consumer, pointers, |path, _| {
+                opts.substitutions.iter().find(|(p, _)| p.matches_pattern(path)).map(|(_, v)| v.clone()).unwrap_or_default()
+            });
+            parser.parse(&mut substitute)
+        }
+    })?;
+    Ok(())
+}
+
+/// Convert a JSON Lines input to an XML document written to `destination`: the documents are
+/// the children of the root and the malformed lines are skipped (see `JSONLinesParser`).
+pub fn json_lines_to_xml_write<R: Read, W: Write>(input: R, destination: W, opts: &Json2XmlOptions) -> Result<JSONLinesSummary, ConvertError> {
+    let mut parser = JSONLinesParser::new(input, opts.ignore_unicode_errs).error_policy(RecordErrorPolicy::Skip);
+    let summary = with_consumer(destination, opts, |mut consumer| parser.parse(&mut consumer))?;
+    Ok(summary)
+}
+
+/// Run `f` with the consumer that matches the options.
+fn with_consumer<W: Write, T, F>(destination: W, opts: &Json2XmlOptions, f: F) -> Result<T, ConsumeError>
+    where F: FnOnce(&mut dyn JSONParseConsumer) -> Result<T, ConsumeError> {
+    let f = |mut consumer: &mut dyn JSONParseConsumer| {
+        if opts.redactions.is_empty() {
+            return f(consumer);
+        }
+        let mut redact = RedactConsumer::new(&mut consumer, opts.redactions.clone());
+        if let Some(mask) = &opts.redaction_mask {
+            redact = redact.mask(mask);
+        }
+        f(&mut redact)
+    };
+    if opts.formatted {
+        if opts.typed {
+            f(&mut JSON2XMLConsumer::new_formatted_and_typed(destination).with_options(opts))
+        } else {
+            f(&mut JSON2XMLConsumer::new_formatted(destination).with_options(opts))
+        }
+    } else if opts.typed {
+        f(&mut JSON2XMLConsumer::new_typed(destination).with_options(opts))
+    } else {
+        f(&mut JSON2XMLConsumer::new(destination).with_options(opts))
+    }
+}
@@ -0,0 +1,290 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Convert a JSON document to YAML 1.2, without loading the document. Since YAML is a superset
+//! of JSON, the conversion only changes the layout: the containers are written in block style
+//! and the strings are quoted only when a YAML parser could read them as something else.
+
+use std::io::{Read, Write};
+
+use crate::byte_source::ByteSource;
+use crate::fmt_write::FmtWriter;
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::json_writer::escape_bytes;
+
+/// An open container.
+struct Frame {
+    array: bool,
+    /// The column of the entries
+    indent: usize,
+    /// No entry was written
+    empty: bool,
+    /// The container is the value of a key: its entries start on the next line
+    after_key: bool,
+}
+
+/// A consumer that writes a JSON document as a YAML document. The containers are written in block
+/// style, except the empty ones (`[]` and `{}`). The elements of an array that are containers
+/// begin on the line of the dash.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_parser::JSONParser;
+/// # use r_json_event_parser::json2yaml::JSON2YAMLConsumer;
+/// let mut consumer = JSON2YAMLConsumer::new(vec!());
+/// let mut parser = JSONParser::new(ByteSource::new(r#"{"a": [{"b": "x", "c": "1"}, []]}"#.as_bytes()), false);
+/// parser.parse(&mut consumer).unwrap();
+/// assert_eq!(b"a:\n  - b: x\n    c: \"1\"\n  - []\n".to_vec(), consumer.into_inner());
+/// ```
+pub struct JSON2YAMLConsumer<W: Write> {
+    destination: W,
+    indent: usize,
+    document_start: bool,
+    frames: Vec<Frame>,
+    /// A key was written, but not its value
+    after_key: bool,
+    /// The chunks of a string
+    chunks: Vec<u8>,
+}
+
+impl<W: Write> JSON2YAMLConsumer<W> {
+    pub fn new(destination: W) -> Self {
+        JSON2YAMLConsumer {
+            destination,
+            indent: 2,
+            document_start: false,
+            frames: vec!(),
+            after_key: false,
+            chunks: vec!(),
+        }
+    }
+
+    pub fn with_options(mut self, opts: &Json2YamlOptions) -> Self {
+        self = self.indent(opts.indent);
+        if opts.document_start {
+            self = self.document_start();
+        }
+        self
+    }
+
+    /// The indentation of the values of an object (default: 2). The entries of an array that is
+    /// an array element are always indented by the width of the dash, i.e. two spaces.
+    pub fn indent(mut self, indent: usize) -> Self {
+        self.indent = indent.max(1);
+        self
+    }
+
+    /// Begin the output with the document start marker `---`.
+    pub fn document_start(mut self) -> Self {
+        self.document_start = true;
+        self
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.destination
+    }
+
+    pub fn into_inner(self) -> W {
+        self.destination
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), ConsumeError> {
+        self.destination.write_all(bytes).map_err(ConsumeError::from_io)
+    }
+
+    /// Write the beginning of the line of an entry of the current container.
+    fn begin_entry(&mut self) -> Result<(), ConsumeError> {
+        let frame = match self.frames.last_mut() {
+            Some(frame) => frame,
+            None => return Ok(()),
+        };
+        let line = if !std::mem::replace(&mut frame.empty, false) {
+            " ".repeat(frame.indent)
+        } else if frame.after_key {
+            format!("\n{}", " ".repeat(frame.indent))
+        } else {
+            String::new() // on the line of the dash, or at the beginning of the document
+        };
+        self.write(line.as_bytes())
+    }
+
+    /// Write what precedes a value: the dash of an array element, or the space after a key.
+    fn begin_value(&mut self) -> Result<(), ConsumeError> {
+        if std::mem::take(&mut self.after_key) {
+            self.write(b" ")
+        } else if self.frames.last().is_some_and(|f| f.array) {
+            self.begin_entry()?;
+            self.write(b"- ")
+        } else {
+            Ok(())
+        }
+    }
+
+    fn begin_container(&mut self, array: bool) -> Result<(), ConsumeError> {
+        let after_key = std::mem::take(&mut self.after_key);
+        if !after_key {
+            self.begin_value()?;
+        }
+        let indent = match self.frames.last() {
+            None => 0,
+            Some(parent) if parent.array => parent.indent + 2,
+            Some(parent) => parent.indent + self.indent,
+        };
+        self.frames.push(Frame { array, indent, empty: true, after_key });
+        Ok(())
+    }
+
+    fn end_container(&mut self) -> Result<(), ConsumeError> {
+        match self.frames.pop() {
+            Some(frame) if frame.empty => {
+                let prefix = if frame.after_key { " " } else { "" };
+                let brackets = if frame.array { "[]" } else { "{}" };
+                self.write(format!("{}{}\n", prefix, brackets).as_bytes())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn key(&mut self, key: &str) -> Result<(), ConsumeError> {
+        self.begin_entry()?;
+        let mut line = vec!();
+        write_scalar_string(&mut line, key);
+        line.push(b':');
+        self.write(&line)?;
+        self.after_key = true;
+        Ok(())
+    }
+
+    fn scalar(&mut self, text: &[u8]) -> Result<(), ConsumeError> {
+        self.begin_value()?;
+        self.write(text)?;
+        self.write(b"\n")
+    }
+
+    fn string(&mut self, s: &str) -> Result<(), ConsumeError> {
+        let mut text = vec!();
+        write_scalar_string(&mut text, s);
+        self.scalar(&text)
+    }
+}
+
+/// The plain scalars that a YAML parser would read as a null, a boolean or a number. The YAML 1.1
+/// booleans are included, because many parsers still use them.
+const RESERVED_WORDS: [&str; 9] = ["null", "true", "false", "yes", "no", "on", "off", "y", "n"];
+
+/// True if the string can be written as a plain scalar. This is a conservative test: the string
+/// begins with a letter, an underscore or a slash and contains only letters, digits, spaces and
+/// the chars `_-./@+()`.
+fn is_plain(s: &str) -> bool {
+    match s.chars().next() {
+        Some(c) if c.is_alphabetic() || c == '_' || c == '/' => {}
+        _ => return false,
+    }
+    !s.ends_with(' ')
+        && s.chars().all(|c| c.is_alphanumeric() || " _-./@+()".contains(c))
+        && !RESERVED_WORDS.iter().any(|w| w.eq_ignore_ascii_case(s))
+}
+
+/// Append a string as a plain or a double quoted scalar to `out`.
+fn write_scalar_string(out: &mut Vec<u8>, s: &str) {
+    if is_plain(s) {
+        out.extend_from_slice(s.as_bytes());
+        return;
+    }
+    out.push(b'"');
+    let mut buf = [0u8; 4];
+    for c in s.chars() {
+        match c {
+            // not printable or line breaks in YAML
+            '\u{7f}'..='\u{9f}' | '\u{2028}' | '\u{2029}' | '\u{feff}' => {
+                out.extend_from_slice(format!("\\u{:04x}", c as u32).as_bytes())
+            }
+            c => escape_bytes(out, c.encode_utf8(&mut buf).as_bytes()),
+        }
+    }
+    out.push(b'"');
+}
+
+impl<W: Write> JSONParseConsumer for JSON2YAMLConsumer<W> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        match token {
+            ParserToken::BeginFile => {
+                if self.document_start {
+                    self.write(b"---\n")
+                } else {
+                    Ok(())
+                }
+            }
+            ParserToken::EndFile => self.destination.flush().map_err(ConsumeError::from_io),
+            ParserToken::BeginObject => self.begin_container(false),
+            ParserToken::BeginArray => self.begin_container(true),
+            ParserToken::EndObject | ParserToken::EndArray => self.end_container(),
+            ParserToken::Key(k) => self.key(&k),
+            ParserToken::StringStart => Ok(()),
+            ParserToken::StringChunk(chunk) => {
+                self.chunks.extend(chunk);
+                Ok(())
+            }
+            ParserToken::StringEnd => {
+                let s = String::from_utf8_lossy(&std::mem::take(&mut self.chunks)).into_owned();
+                self.string(&s)
+            }
+            ParserToken::StringValue(s) => self.string(&s),
+            ParserToken::BytesValue(b) => self.string(&String::from_utf8_lossy(&b)),
+            ParserToken::IntValue(s) | ParserToken::FloatValue(s) => self.scalar(s.as_bytes()),
+            ParserToken::BooleanValue(b) => self.scalar(if b { b"true" } else { b"false" }),
+            ParserToken::NullValue => self.scalar(b"null"),
+        }
+    }
+}
+
+/// Options of the one-call conversion functions.
+#[derive(Debug, Clone)]
+pub struct Json2YamlOptions {
+    /// See `JSON2YAMLConsumer::indent`
+    pub indent: usize,
+    /// See `JSON2YAMLConsumer::document_start`
+    pub document_start: bool,
+}
+
+impl Default for Json2YamlOptions {
+    fn default() -> Self {
+        Json2YamlOptions {
+            indent: 2,
+            document_start: false,
+        }
+    }
+}
+
+/// Convert a JSON document to YAML.
+pub fn json_to_yaml_string(input: &str, opts: &Json2YamlOptions) -> Result<String, ConsumeError> {
+    let mut destination = String::new();
+    json_to_yaml_write(input.as_bytes(), FmtWriter::new(&mut destination), opts)?;
+    Ok(destination)
+}
+
+/// Convert a JSON document from `input` to YAML written to `destination`.
+pub fn json_to_yaml_write<R: Read, W: Write>(input: R, destination: W, opts: &Json2YamlOptions) -> Result<(), ConsumeError> {
+    let mut consumer = JSON2YAMLConsumer::new(destination).with_options(opts);
+    JSONParser::new(ByteSource::new(input), false).parse(&mut consumer)
+}
@@ -0,0 +1,231 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[cfg(feature = "digest")]
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "digest")]
+use crate::json_lexer::ConsumeError;
+#[cfg(feature = "digest")]
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
+#[cfg(feature = "digest")]
+use crate::json_writer::{escape_bytes, escape_string};
+
+/// The RFC 8785 serialization of a number lexeme, that is the ECMAScript
+/// `Number.prototype.toString` of the closest `f64`. None if the number is not finite.
+pub fn canonical_number(lexeme: &str) -> Option<String> {
+    let f = lexeme.parse::<f64>().ok()?;
+    if !f.is_finite() {
+        return None;
+    }
+    if f == 0.0 {
+        return Some("0".into());
+    }
+    // Rust writes the shortest digits that round trip, e.g. `-1.5e-7`
+    let repr = format!("{:e}", f.abs());
+    let i = repr.find('e')?;
+    let digits = repr[..i].replace('.', "");
+    let k = digits.len() as i64;
+    let n = repr[i + 1..].parse::<i64>().ok()? + 1;
+    let mut ret = String::new();
+    if f < 0.0 {
+        ret.push('-');
+    }
+    if k <= n && n <= 21 {
+        ret.push_str(&digits);
+        ret.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        ret.push_str(&digits[..n as usize]);
+        ret.push('.');
+        ret.push_str(&digits[n as usize..]);
+    } else if -6 < n && n <= 0 {
+        ret.push_str("0.");
+        ret.push_str(&"0".repeat(-n as usize));
+        ret.push_str(&digits);
+    } else {
+        ret.push_str(&digits[..1]);
+        if k > 1 {
+            ret.push('.');
+            ret.push_str(&digits[1..]);
+        }
+        ret.push_str(&format!("e{}{}", if n > 0 { '+' } else { '-' }, (n - 1).abs()));
+    }
+    Some(ret)
+}
+
+#[cfg(feature = "digest")]
+enum Frame {
+    /// The members are buffered because the keys must be sorted.
+    Object(Vec<(String, Vec<u8>)>),
+    /// The elements are written as they come: the flag is true before the first element.
+    Array(bool),
+}
+
+#[cfg(feature = "digest")]
+/// A consumer that computes the SHA-256 digest of the RFC 8785 (JCS) canonical serialization
+/// of the document without writing it. Only the members of the objects being read are
+/// buffered, since they have to be sorted; arrays and scalars are streamed to the hasher.
+pub struct CanonicalDigestConsumer {
+    hasher: Sha256,
+    frames: Vec<Frame>,
+    digest: Option<[u8; 32]>,
+}
+
+#[cfg(feature = "digest")]
+impl CanonicalDigestConsumer {
+    pub fn new() -> Self {
+        CanonicalDigestConsumer {
+            hasher: Sha256::new(),
+            frames: vec!(),
+            digest: None,
+        }
+    }
+
+    /// The digest, once the end of the file was consumed.
+    pub fn digest(&self) -> Option<[u8; 32]> {
+        self.digest
+    }
+
+    /// The digest as lowercase hex, once the end of the file was consumed.
+    pub fn hex_digest(&self) -> Option<String> {
+        self.digest.map(|d| d.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    /// Write the bytes to the innermost object member, or to the hasher if there is none.
+    fn emit(&mut self, bytes: &[u8]) {
+        let member = self.frames.iter_mut().rev().find_map(|frame| match frame {
+            Frame::Object(members) => members.last_mut(),
+            Frame::Array(_) => None,
+        });
+        match member {
+            Some((_, value)) => value.extend_from_slice(bytes),
+            None => self.hasher.update(bytes),
+        }
+    }
+
+    /// Write the separator before an array element.
+    fn begin_value(&mut self) {
+        if let Some(Frame::Array(first)) = self.frames.last_mut() {
+            if *first {
+                *first = false;
+            } else {
+                self.emit(b",");
+            }
+        }
+    }
+
+    fn emit_scalar(&mut self, bytes: &[u8]) {
+        self.begin_value();
+        self.emit(bytes);
+    }
+
+    fn end_object(&mut self, mut members: Vec<(String, Vec<u8>)>) {
+        members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+        let mut out = vec!(b'{');
+        for (i, (key, value)) in members.iter().enumerate() {
+            if i > 0 {
+                out.push(b',');
+            }
+            escape_string(&mut out, key);
+            out.push(b':');
+            out.extend_from_slice(value);
+        }
+        out.push(b'}');
+        self.emit(&out);
+    }
+}
+
+#[cfg(feature = "digest")]
+impl Default for CanonicalDigestConsumer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "digest")]
+impl JSONParseConsumer for CanonicalDigestConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        match token {
+            ParserToken::BeginFile => {}
+            ParserToken::EndFile => {
+                let hasher = std::mem::take(&mut self.hasher);
+                self.digest = Some(hasher.finalize().into());
+            }
+            ParserToken::BeginObject => {
+                self.begin_value();
+                self.frames.push(Frame::Object(vec!()));
+            }
+            ParserToken::EndObject => {
+                if let Some(Frame::Object(members)) = self.frames.pop() {
+                    self.end_object(members);
+                }
+            }
+            ParserToken::BeginArray => {
+                self.begin_value();
+                self.emit(b"[");
+                self.frames.push(Frame::Array(true));
+            }
+            ParserToken::EndArray => {
+                self.frames.pop();
+                self.emit(b"]");
+            }
+            ParserToken::Key(k) => {
+                if let Some(Frame::Object(members)) = self.frames.last_mut() {
+                    members.push((k, vec!()));
+                }
+            }
+            ParserToken::StringValue(s) => {
+                let mut out = vec!();
+                escape_string(&mut out, &s);
+                self.emit_scalar(&out);
+            }
+            ParserToken::BytesValue(b) => {
+                let mut out = vec!();
+                escape_string(&mut out, &String::from_utf8_lossy(&b));
+                self.emit_scalar(&out);
+            }
+            ParserToken::StringStart => self.emit_scalar(b"\""),
+            ParserToken::StringChunk(chunk) => {
+                let mut out = vec!();
+                escape_bytes(&mut out, &chunk);
+                self.emit(&out);
+            }
+            ParserToken::StringEnd => self.emit(b"\""),
+            ParserToken::IntValue(s) | ParserToken::FloatValue(s) => {
+                match canonical_number(&s) {
+                    Some(n) => self.emit_scalar(n.as_bytes()),
+                    None => {
+                        return Err(ConsumeError {
+                            msg: format!("Number `{}` has no canonical form", s),
+                            line: 0,
+                            column: 0,
+                            io_error: None,
+                        });
+                    }
+                }
+            }
+            ParserToken::BooleanValue(b) => self.emit_scalar(if b { b"true" } else { b"false" }),
+            ParserToken::NullValue => self.emit_scalar(b"null"),
+        }
+        Ok(())
+    }
+}
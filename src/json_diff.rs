@@ -0,0 +1,443 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A structural diff of two documents. The documents are read in lockstep by two pull parsers:
+//! as long as the members of the objects come in the same order, nothing is buffered. When the
+//! keys of an object differ, the rest of both objects is read into `JsonValue`s and compared by
+//! key. The elements of the arrays are compared by index.
+
+use std::fmt;
+use std::io::{Read, Write};
+
+use crate::byte_source::ByteSource;
+use crate::json2html::escape_html;
+use crate::json_canonical::canonical_number;
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONPullParser, ParserToken};
+use crate::json_pointer::{to_pointer_string, PathElement};
+use crate::json_value::{JsonValue, ValueBuilderConsumer};
+use crate::json_writer::JSONWriteConsumer;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A difference between the documents. The path is a JSON Pointer. `old` is the value in the
+/// left document, `new` the value in the right document.
+#[derive(Debug, PartialEq, Clone)]
+pub struct DiffEntry {
+    pub kind: DiffKind,
+    pub path: String,
+    pub old: Option<JsonValue>,
+    pub new: Option<JsonValue>,
+}
+
+/// The compact JSON text of a value.
+fn to_json(value: &JsonValue) -> String {
+    let mut consumer = JSONWriteConsumer::new(vec!());
+    for token in value.to_tokens() {
+        // a Vec never fails
+        let _ = consumer.consume(Ok(token));
+    }
+    String::from_utf8_lossy(&consumer.into_inner()).into_owned()
+}
+
+/// `+ /path: value`, `- /path: value` or `~ /path: old -> new`.
+impl fmt::Display for DiffEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let path = if self.path.is_empty() { "(root)" } else { &self.path };
+        match (self.kind, &self.old, &self.new) {
+            (DiffKind::Added, _, Some(new)) => write!(f, "+ {}: {}", path, to_json(new)),
+            (DiffKind::Removed, Some(old), _) => write!(f, "- {}: {}", path, to_json(old)),
+            (_, Some(old), Some(new)) => write!(f, "~ {}: {} -> {}", path, to_json(old), to_json(new)),
+            _ => write!(f, "? {}", path),
+        }
+    }
+}
+
+/// A consumer of the differences.
+pub trait DiffConsumer {
+    fn consume(&mut self, entry: DiffEntry) -> Result<(), ConsumeError>;
+}
+
+impl DiffConsumer for Vec<DiffEntry> {
+    fn consume(&mut self, entry: DiffEntry) -> Result<(), ConsumeError> {
+        self.push(entry);
+        Ok(())
+    }
+}
+
+/// The layout of the rows of an HTML report.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum HTMLDiffLayout {
+    /// One column for the values: the old value is struck out and followed by the new value.
+    Inline,
+    /// One column for the old values and one for the new values.
+    SideBySide,
+}
+
+/// The style sheet of the report. The classes of the rows are `added`, `removed` and `changed`.
+pub const DIFF_STYLE: &str = "table.json-diff { border-collapse: collapse; font-family: monospace; }
+.json-diff th, .json-diff td { border: 1px solid #cccccc; padding: 0.2em 0.5em; text-align: left; vertical-align: top; }
+.json-diff td { white-space: pre-wrap; }
+.json-diff del { background: #ffd7d5; }
+.json-diff ins { background: #ccffd8; text-decoration: none; }
+.json-diff tr.added td:first-child { border-left: 4px solid #2da44e; }
+.json-diff tr.removed td:first-child { border-left: 4px solid #cf222e; }
+.json-diff tr.changed td:first-child { border-left: 4px solid #bf8700; }";
+
+/// A consumer that writes the differences as a self-contained HTML page: a table with one row
+/// per difference, keyed by the JSON Pointer of the value. Call `finish` after the diff to
+/// close the page.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_diff::{diff, HTMLDiffConsumer, HTMLDiffLayout};
+/// let mut consumer = HTMLDiffConsumer::new(vec!(), HTMLDiffLayout::Inline);
+/// diff(ByteSource::new(r#"{"a": 1}"#.as_bytes()), ByteSource::new(r#"{"a": "<2>"}"#.as_bytes()), &mut consumer).unwrap();
+/// let html = String::from_utf8(consumer.finish().unwrap()).unwrap();
+/// assert!(html.contains(r#"<tr class="changed"><td>/a</td><td><del>1</del> <ins>"&lt;2&gt;"</ins></td></tr>"#));
+/// ```
+pub struct HTMLDiffConsumer<W: Write> {
+    destination: W,
+    layout: HTMLDiffLayout,
+    title: String,
+    started: bool,
+    count: usize,
+}
+
+impl<W: Write> HTMLDiffConsumer<W> {
+    pub fn new(destination: W, layout: HTMLDiffLayout) -> Self {
+        HTMLDiffConsumer {
+            destination,
+            layout,
+            title: "JSON diff".into(),
+            started: false,
+            count: 0,
+        }
+    }
+
+    /// The title of the page (default: `JSON diff`), e.g. the names of the files.
+    pub fn title(mut self, title: &str) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// The number of differences written.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    fn write(&mut self, text: &str) -> Result<(), ConsumeError> {
+        self.destination.write_all(text.as_bytes()).map_err(ConsumeError::from_io)
+    }
+
+    fn start(&mut self) -> Result<(), ConsumeError> {
+        if self.started {
+            return Ok(());
+        }
+        self.started = true;
+        let header = match self.layout {
+            HTMLDiffLayout::Inline => "<tr><th>Path</th><th>Value</th></tr>",
+            HTMLDiffLayout::SideBySide => "<tr><th>Path</th><th>Old</th><th>New</th></tr>",
+        };
+        let text = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{}\n</style>\n</head>\n<body>\n<h1>{}</h1>\n<table class=\"json-diff\">\n{}\n",
+                           escape_html(&self.title), DIFF_STYLE, escape_html(&self.title), header);
+        self.write(&text)
+    }
+
+    /// Close the page and return the destination. An empty diff is written as an empty table.
+    pub fn finish(mut self) -> Result<W, ConsumeError> {
+        self.start()?;
+        let summary = match self.count {
+            0 => "No difference".to_string(),
+            1 => "1 difference".to_string(),
+            n => format!("{} differences", n),
+        };
+        self.write(&format!("</table>\n<p>{}</p>\n</body>\n</html>\n", summary))?;
+        self.destination.flush().map_err(ConsumeError::from_io)?;
+        Ok(self.destination)
+    }
+}
+
+impl<W: Write> DiffConsumer for HTMLDiffConsumer<W> {
+    fn consume(&mut self, entry: DiffEntry) -> Result<(), ConsumeError> {
+        self.start()?;
+        self.count += 1;
+        let (class, old, new) = match entry.kind {
+            DiffKind::Added => ("added", None, entry.new.as_ref()),
+            DiffKind::Removed => ("removed", entry.old.as_ref(), None),
+            DiffKind::Changed => ("changed", entry.old.as_ref(), entry.new.as_ref()),
+        };
+        let old = old.map(|v| format!("<del>{}</del>", escape_html(&to_json(v))));
+        let new = new.map(|v| format!("<ins>{}</ins>", escape_html(&to_json(v))));
+        let cells = match self.layout {
+            HTMLDiffLayout::Inline => {
+                let value: Vec<String> = old.into_iter().chain(new).collect();
+                format!("<td>{}</td>", value.join(" "))
+            }
+            HTMLDiffLayout::SideBySide => format!("<td>{}</td><td>{}</td>", old.unwrap_or_default(), new.unwrap_or_default()),
+        };
+        let path = if entry.path.is_empty() { "(root)" } else { &entry.path };
+        let row = format!("<tr class=\"{}\"><td>{}</td>{}</tr>\n", class, escape_html(path), cells);
+        self.write(&row)
+    }
+}
+
+/// One of the documents. The strings sent in chunks are joined.
+struct Side<R: Read> {
+    parser: JSONPullParser<R>,
+    name: &'static str,
+}
+
+impl<R: Read> Side<R> {
+    fn next(&mut self) -> Result<ParserToken, ConsumeError> {
+        let token = self.next_token()?;
+        if token != ParserToken::StringStart {
+            return Ok(token);
+        }
+        let mut buf = vec!();
+        loop {
+            match self.next_token()? {
+                ParserToken::StringChunk(chunk) => buf.extend(chunk),
+                _ => return Ok(ParserToken::StringValue(String::from_utf8_lossy(&buf).into_owned())),
+            }
+        }
+    }
+
+    fn next_token(&mut self) -> Result<ParserToken, ConsumeError> {
+        match self.parser.next_event() {
+            Some(Ok(token)) => Ok(token),
+            Some(Err(e)) => Err(ConsumeError { msg: format!("{} document: {}", self.name, e.msg), line: e.line, column: e.column, io_error: None }),
+            None => Err(ConsumeError { msg: format!("{} document: unexpected end", self.name), line: 0, column: 0, io_error: None }),
+        }
+    }
+
+    /// Read the rest of a value, given its first token.
+    fn read_value(&mut self, first: ParserToken) -> Result<JsonValue, ConsumeError> {
+        let mut builder = ValueBuilderConsumer::new();
+        let mut depth = 0usize;
+        let mut token = first;
+        loop {
+            match token {
+                ParserToken::BeginObject | ParserToken::BeginArray => depth += 1,
+                ParserToken::EndObject | ParserToken::EndArray => depth -= 1,
+                _ => {}
+            }
+            builder.consume(Ok(token))?;
+            if depth == 0 {
+                return Ok(builder.into_value().unwrap_or(JsonValue::Null));
+            }
+            token = self.next()?;
+        }
+    }
+
+    /// Read the members of an object up to its end, given the token after the common members.
+    fn read_members(&mut self, mut token: ParserToken) -> Result<Vec<(String, JsonValue)>, ConsumeError> {
+        let mut members = vec!();
+        while let ParserToken::Key(key) = token {
+            let first = self.next()?;
+            members.push((key, self.read_value(first)?));
+            token = self.next()?;
+        }
+        Ok(members)
+    }
+}
+
+fn scalar(token: &ParserToken) -> Option<JsonValue> {
+    Some(match token {
+        ParserToken::StringValue(s) => JsonValue::String(s.clone()),
+        ParserToken::BytesValue(b) => JsonValue::String(String::from_utf8_lossy(b).into_owned()),
+        ParserToken::IntValue(s) => JsonValue::Int(s.clone()),
+        ParserToken::FloatValue(s) => JsonValue::Float(s.clone()),
+        ParserToken::BooleanValue(b) => JsonValue::Bool(*b),
+        ParserToken::NullValue => JsonValue::Null,
+        _ => return None,
+    })
+}
+
+/// The numbers are equal if they have the same value as `f64`, e.g. `1`, `1.0` and `1e0`.
+fn values_equal(a: &JsonValue, b: &JsonValue) -> bool {
+    match (a, b) {
+        (JsonValue::Int(x), JsonValue::Int(y)) | (JsonValue::Int(x), JsonValue::Float(y))
+        | (JsonValue::Float(x), JsonValue::Int(y)) | (JsonValue::Float(x), JsonValue::Float(y)) => {
+            x == y || canonical_number(x).is_some_and(|x| Some(x) == canonical_number(y))
+        }
+        _ => a == b,
+    }
+}
+
+struct Differ<'a, R1: Read, R2: Read, C: DiffConsumer> {
+    left: Side<R1>,
+    right: Side<R2>,
+    consumer: &'a mut C,
+    path: Vec<PathElement>,
+}
+
+impl<'a, R1: Read, R2: Read, C: DiffConsumer> Differ<'a, R1, R2, C> {
+    fn emit(&mut self, kind: DiffKind, old: Option<JsonValue>, new: Option<JsonValue>) -> Result<(), ConsumeError> {
+        self.consumer.consume(DiffEntry { kind, path: to_pointer_string(&self.path), old, new })
+    }
+
+    fn diff_values(&mut self, a: ParserToken, b: ParserToken) -> Result<(), ConsumeError> {
+        match (a, b) {
+            (ParserToken::BeginObject, ParserToken::BeginObject) => self.diff_objects(),
+            (ParserToken::BeginArray, ParserToken::BeginArray) => self.diff_arrays(),
+            (a, b) => {
+                let old = match scalar(&a) {
+                    Some(v) => v,
+                    None => self.left.read_value(a)?,
+                };
+                let new = match scalar(&b) {
+                    Some(v) => v,
+                    None => self.right.read_value(b)?,
+                };
+                if !values_equal(&old, &new) {
+                    self.emit(DiffKind::Changed, Some(old), Some(new))?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn diff_objects(&mut self) -> Result<(), ConsumeError> {
+        loop {
+            match (self.left.next()?, self.right.next()?) {
+                (ParserToken::EndObject, ParserToken::EndObject) => return Ok(()),
+                (ParserToken::Key(x), ParserToken::Key(y)) if x == y => {
+                    let (a, b) = (self.left.next()?, self.right.next()?);
+                    self.path.push(PathElement::Key(x));
+                    self.diff_values(a, b)?;
+                    self.path.pop();
+                }
+                (a, b) => {
+                    let old = self.left.read_members(a)?;
+                    let new = self.right.read_members(b)?;
+                    return self.diff_members(&old, &new);
+                }
+            }
+        }
+    }
+
+    fn diff_arrays(&mut self) -> Result<(), ConsumeError> {
+        let mut index = 0;
+        loop {
+            self.path.push(PathElement::Index(index));
+            match (self.left.next()?, self.right.next()?) {
+                (ParserToken::EndArray, ParserToken::EndArray) => {
+                    self.path.pop();
+                    return Ok(());
+                }
+                (ParserToken::EndArray, mut b) => {
+                    while b != ParserToken::EndArray {
+                        let new = self.right.read_value(b)?;
+                        self.emit(DiffKind::Added, None, Some(new))?;
+                        index += 1;
+                        *self.path.last_mut().expect("an index") = PathElement::Index(index);
+                        b = self.right.next()?;
+                    }
+                    self.path.pop();
+                    return Ok(());
+                }
+                (mut a, ParserToken::EndArray) => {
+                    while a != ParserToken::EndArray {
+                        let old = self.left.read_value(a)?;
+                        self.emit(DiffKind::Removed, Some(old), None)?;
+                        index += 1;
+                        *self.path.last_mut().expect("an index") = PathElement::Index(index);
+                        a = self.left.next()?;
+                    }
+                    self.path.pop();
+                    return Ok(());
+                }
+                (a, b) => self.diff_values(a, b)?,
+            }
+            self.path.pop();
+            index += 1;
+        }
+    }
+
+    /// Compare the buffered members of two objects: the last value of a duplicate key wins.
+    fn diff_members(&mut self, old: &[(String, JsonValue)], new: &[(String, JsonValue)]) -> Result<(), ConsumeError> {
+        let is_last = |members: &[(String, JsonValue)], i: usize| members[i + 1..].iter().all(|(k, _)| *k != members[i].0);
+        for (i, (key, a)) in old.iter().enumerate() {
+            if !is_last(old, i) {
+                continue;
+            }
+            self.path.push(PathElement::Key(key.clone()));
+            match new.iter().rev().find(|(k, _)| k == key) {
+                Some((_, b)) => self.diff_buffered(a, b)?,
+                None => self.emit(DiffKind::Removed, Some(a.clone()), None)?,
+            }
+            self.path.pop();
+        }
+        for (i, (key, b)) in new.iter().enumerate() {
+            if is_last(new, i) && !old.iter().any(|(k, _)| k == key) {
+                self.path.push(PathElement::Key(key.clone()));
+                self.emit(DiffKind::Added, None, Some(b.clone()))?;
+                self.path.pop();
+            }
+        }
+        Ok(())
+    }
+
+    fn diff_buffered(&mut self, a: &JsonValue, b: &JsonValue) -> Result<(), ConsumeError> {
+        match (a, b) {
+            (JsonValue::Object(old), JsonValue::Object(new)) => self.diff_members(old, new),
+            (JsonValue::Array(old), JsonValue::Array(new)) => {
+                for i in 0..old.len().max(new.len()) {
+                    self.path.push(PathElement::Index(i));
+                    match (old.get(i), new.get(i)) {
+                        (Some(a), Some(b)) => self.diff_buffered(a, b)?,
+                        (Some(a), None) => self.emit(DiffKind::Removed, Some(a.clone()), None)?,
+                        (None, Some(b)) => self.emit(DiffKind::Added, None, Some(b.clone()))?,
+                        (None, None) => {}
+                    }
+                    self.path.pop();
+                }
+                Ok(())
+            }
+            (a, b) if values_equal(a, b) => Ok(()),
+            (a, b) => self.emit(DiffKind::Changed, Some(a.clone()), Some(b.clone())),
+        }
+    }
+}
+
+/// Compare two documents and send the differences to the consumer, in the order of the left
+/// document. A parse error of either document stops the diff.
+pub fn diff<R1: Read, R2: Read, C: DiffConsumer>(left: ByteSource<R1>, right: ByteSource<R2>, consumer: &mut C) -> Result<(), ConsumeError> {
+    let mut differ = Differ {
+        left: Side { parser: JSONPullParser::new(left, false), name: "left" },
+        right: Side { parser: JSONPullParser::new(right, false), name: "right" },
+        consumer,
+        path: vec!(),
+    };
+    differ.left.next()?;
+    differ.right.next()?;
+    let (a, b) = (differ.left.next()?, differ.right.next()?);
+    differ.diff_values(a, b)?;
+    differ.left.next()?;
+    differ.right.next()?;
+    Ok(())
+}
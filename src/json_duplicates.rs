@@ -0,0 +1,209 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{HashMap, HashSet};
+
+use crate::json_lexer::{ConsumeError, Span};
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParseErrorKind, ParserToken};
+
+/// What to do with the members of an object that repeat a key.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DuplicateKeyResolution {
+    /// Keep the first member, suppress the events of the following ones.
+    FirstWins,
+    /// Keep the value of the last member, at the position of the first one. Requires to buffer
+    /// each object until its end.
+    LastWins,
+    /// Stop on the first duplicate key.
+    Error,
+}
+
+struct BufferedObject {
+    members: Vec<(String, Vec<ParserToken>)>,
+    index_by_key: HashMap<String, usize>,
+    current: usize,
+}
+
+/// A consumer that resolves the duplicate keys before forwarding the tokens to another consumer.
+pub struct DuplicateKeyConsumer<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    resolution: DuplicateKeyResolution,
+    keys_stack: Vec<Option<HashSet<String>>>,
+    skip_depth: Option<usize>,
+    objects: Vec<BufferedObject>,
+}
+
+impl<'a, C: JSONParseConsumer> DuplicateKeyConsumer<'a, C> {
+    pub fn new(consumer: &'a mut C, resolution: DuplicateKeyResolution) -> Self {
+        DuplicateKeyConsumer {
+            consumer,
+            resolution,
+            keys_stack: vec!(),
+            skip_depth: None,
+            objects: vec!(),
+        }
+    }
+
+    /// Forward the token with its span, if any.
+    fn forward(&mut self, token: ParserToken, span: Option<Span>) -> Result<(), ConsumeError> {
+        match span {
+            Some(span) => self.consumer.consume_with_span(Ok(token), span),
+            None => self.consumer.consume(Ok(token)),
+        }
+    }
+
+    fn consume_first_wins(&mut self, token: ParserToken, span: Option<Span>) -> Result<(), ConsumeError> {
+        if let Some(depth) = self.skip_depth {
+            // a chunked string is skipped up to its end
+            let depth = match token {
+                ParserToken::BeginObject | ParserToken::BeginArray | ParserToken::StringStart => depth + 1,
+                ParserToken::EndObject | ParserToken::EndArray | ParserToken::StringEnd => depth - 1,
+                _ => depth,
+            };
+            self.skip_depth = if depth == 0 { None } else { Some(depth) };
+            return Ok(());
+        }
+        match token {
+            ParserToken::Key(ref k) => {
+                if let Some(Some(keys)) = self.keys_stack.last_mut() {
+                    if !keys.insert(k.clone()) {
+                        self.skip_depth = Some(0);
+                        return Ok(());
+                    }
+                }
+            }
+            _ => self.update_keys_stack(&token),
+        }
+        self.forward(token, span)
+    }
+
+    /// The error is reported at the position of the duplicate key.
+    fn consume_error(&mut self, token: ParserToken, span: Option<Span>) -> Result<(), ConsumeError> {
+        if let ParserToken::Key(ref k) = token {
+            if let Some(Some(keys)) = self.keys_stack.last_mut() {
+                if !keys.insert(k.clone()) {
+                    let msg = format!("Duplicate key `{}`", k);
+                    let (line, column) = span.map_or((0, 0), |s| (s.line, s.column));
+                    let error = JSONParseError { kind: ParseErrorKind::DuplicateKey(k.clone()), msg: msg.clone(), line, column };
+                    match span {
+                        Some(span) => self.consumer.consume_with_span(Err(error), span)?,
+                        None => self.consumer.consume(Err(error))?,
+                    }
+                    return Err(ConsumeError { msg, line, column, io_error: None });
+                }
+            }
+        } else {
+            self.update_keys_stack(&token);
+        }
+        self.forward(token, span)
+    }
+
+    fn update_keys_stack(&mut self, token: &ParserToken) {
+        match token {
+            ParserToken::BeginObject => { self.keys_stack.push(Some(HashSet::new())); }
+            ParserToken::BeginArray => { self.keys_stack.push(None); }
+            ParserToken::EndObject | ParserToken::EndArray => { self.keys_stack.pop(); }
+            _ => {}
+        }
+    }
+
+    fn consume_last_wins(&mut self, token: ParserToken) -> Result<(), ConsumeError> {
+        match token {
+            ParserToken::BeginObject => {
+                self.objects.push(BufferedObject {
+                    members: vec!(),
+                    index_by_key: HashMap::new(),
+                    current: 0,
+                });
+                Ok(())
+            }
+            ParserToken::Key(k) => {
+                let object = self.objects.last_mut().unwrap();
+                match object.index_by_key.get(&k) {
+                    Some(&i) => {
+                        object.members[i].1.clear();
+                        object.current = i;
+                    }
+                    None => {
+                        object.current = object.members.len();
+                        object.index_by_key.insert(k.clone(), object.current);
+                        object.members.push((k, vec!()));
+                    }
+                }
+                Ok(())
+            }
+            ParserToken::EndObject => {
+                let object = self.objects.pop().unwrap();
+                self.emit(ParserToken::BeginObject)?;
+                for (k, tokens) in object.members {
+                    self.emit(ParserToken::Key(k))?;
+                    for t in tokens {
+                        self.emit(t)?;
+                    }
+                }
+                self.emit(ParserToken::EndObject)
+            }
+            _ => self.emit(token)
+        }
+    }
+
+    fn emit(&mut self, token: ParserToken) -> Result<(), ConsumeError> {
+        match self.objects.last_mut() {
+            Some(object) => {
+                object.members[object.current].1.push(token);
+                Ok(())
+            }
+            None => self.consumer.consume(Ok(token))
+        }
+    }
+}
+
+impl<'a, C: JSONParseConsumer> DuplicateKeyConsumer<'a, C> {
+    fn dispatch(&mut self, token: Result<ParserToken, JSONParseError>, span: Option<Span>) -> Result<(), ConsumeError> {
+        match token {
+            Ok(token) => {
+                match self.resolution {
+                    DuplicateKeyResolution::FirstWins => self.consume_first_wins(token, span),
+                    DuplicateKeyResolution::LastWins => self.consume_last_wins(token),
+                    DuplicateKeyResolution::Error => self.consume_error(token, span),
+                }
+            }
+            Err(e) => match span {
+                Some(span) => self.consumer.consume_with_span(Err(e), span),
+                None => self.consumer.consume(Err(e)),
+            },
+        }
+    }
+}
+
+impl<'a, C: JSONParseConsumer> JSONParseConsumer for DuplicateKeyConsumer<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.dispatch(token, None)
+    }
+
+    fn consume_with_span(&mut self, token: Result<ParserToken, JSONParseError>, span: Span) -> Result<(), ConsumeError> {
+        self.dispatch(token, Some(span))
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
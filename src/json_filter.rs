@@ -0,0 +1,117 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::RefCell;
+
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
+use crate::json_pointer::{JSONPointer, PathElement, PathTracker};
+
+/// A consumer that forwards only the values found at some JSON Pointers (RFC 6901), and
+/// `BeginFile`/`EndFile`. A `*` token matches any key or index, e.g. `/results/*/id`. The
+/// other values are skipped by the lexer when possible.
+///
+/// The inner consumer receives a sequence of values: the key of a matched member is dropped.
+pub struct PointerFilterConsumer<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    pointers: Vec<JSONPointer>,
+    tracker: PathTracker,
+}
+
+impl<'a, C: JSONParseConsumer> PointerFilterConsumer<'a, C> {
+    pub fn new(consumer: &'a mut C, pointers: Vec<JSONPointer>) -> Self {
+        PointerFilterConsumer {
+            consumer,
+            pointers,
+            tracker: PathTracker::new(),
+        }
+    }
+
+    fn is_selected(&self, path: &[PathElement], key: bool) -> bool {
+        self.pointers.iter().any(|p| p.is_pattern_prefix_of(path) && !(key && p.tokens().len() == path.len()))
+    }
+}
+
+impl<'a, C: JSONParseConsumer> JSONParseConsumer for PointerFilterConsumer<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let t = match token {
+            Ok(t) => t,
+            Err(e) => return self.consumer.consume(Err(e)),
+        };
+        self.tracker.update(&t);
+        let selected = match t {
+            ParserToken::BeginFile | ParserToken::EndFile => true,
+            ParserToken::Key(_) => self.is_selected(self.tracker.path(), true),
+            _ => self.is_selected(self.tracker.path(), false),
+        };
+        if selected {
+            self.consumer.consume(Ok(t))
+        } else {
+            Ok(())
+        }
+    }
+
+    fn skip_value(&self) -> bool {
+        !self.is_selected(&self.tracker.next_path(), false) || self.consumer.skip_value()
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
+
+/// A consumer that declares the strings and numbers whose path matches the predicate
+/// uninteresting: the lexer scans them without unescaping or buffering them (see
+/// `JSONParseConsumer::skip_value`) and the inner consumer receives them with an empty text. The
+/// keys are always decoded.
+pub struct SkipValuesConsumer<'a, C: JSONParseConsumer, P: FnMut(&[PathElement]) -> bool> {
+    consumer: &'a mut C,
+    // `skip_value` takes `&self`
+    predicate: RefCell<P>,
+    tracker: PathTracker,
+}
+
+impl<'a, C: JSONParseConsumer, P: FnMut(&[PathElement]) -> bool> SkipValuesConsumer<'a, C, P> {
+    pub fn new(consumer: &'a mut C, predicate: P) -> Self {
+        SkipValuesConsumer {
+            consumer,
+            predicate: RefCell::new(predicate),
+            tracker: PathTracker::new(),
+        }
+    }
+}
+
+impl<'a, C: JSONParseConsumer, P: FnMut(&[PathElement]) -> bool> JSONParseConsumer for SkipValuesConsumer<'a, C, P> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        if let Ok(t) = &token {
+            self.tracker.update(t);
+        }
+        self.consumer.consume(token)
+    }
+
+    fn skip_value(&self) -> bool {
+        (self.predicate.borrow_mut())(&self.tracker.next_path()) || self.consumer.skip_value()
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
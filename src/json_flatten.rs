@@ -0,0 +1,364 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Flatten the nested containers of a document into a single object whose keys are paths, e.g.
+//! `{"a": {"b": [1, {"c": 2}]}}` becomes `{"a.b[0]": 1, "a.b[1].c": 2}`, the usual input of the
+//! columnar systems, and rebuild them (see `UnflattenConsumer`).
+//!
+//! In a flat key, the keys are joined by the separator and the indices are written between
+//! brackets. A `\`, a `[` or the separator inside a key is escaped by a `\`.
+
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
+use crate::json_pointer::{PathElement, PathTracker};
+use crate::json_transform::{depth_delta, is_value_start};
+use crate::json_value::{JsonValue, ValueBuilderConsumer};
+
+/// A key in a flat key.
+pub fn escape_flat_key(key: &str, separator: &str) -> String {
+    let mut escaped = String::with_capacity(key.len());
+    let mut rest = key;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' || c == '[' || (!separator.is_empty() && rest.starts_with(separator)) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+        rest = &rest[c.len_utf8()..];
+    }
+    escaped
+}
+
+/// The flat key of a path, e.g. `a.b[0].c` (see `parse_flat_key`). The flat keys of the
+/// crate, e.g. the CSV columns, are written by this function.
+pub fn flat_key(path: &[PathElement], separator: &str) -> String {
+    let mut key = String::new();
+    for (i, element) in path.iter().enumerate() {
+        match element {
+            PathElement::Key(k) => {
+                if i > 0 {
+                    key.push_str(separator);
+                }
+                key.push_str(&escape_flat_key(k, separator));
+            }
+            PathElement::Index(index) => key.push_str(&format!("[{}]", index)),
+        }
+    }
+    key
+}
+
+/// The path of a flat key, None if a bracket is not closed or holds something else than an
+/// index.
+pub fn parse_flat_key(key: &str, separator: &str) -> Option<Vec<PathElement>> {
+    let mut path = vec!();
+    let mut name = String::new();
+    // true if a key starts at this place: at the beginning and after a separator
+    let mut in_key = true;
+    let mut rest = key;
+    while let Some(c) = rest.chars().next() {
+        if c == '\\' {
+            rest = &rest[1..];
+            let escaped = rest.chars().next()?;
+            name.push(escaped);
+            rest = &rest[escaped.len_utf8()..];
+            in_key = true;
+        } else if !separator.is_empty() && rest.starts_with(separator) {
+            if in_key {
+                path.push(PathElement::Key(std::mem::take(&mut name)));
+            }
+            in_key = true;
+            rest = &rest[separator.len()..];
+        } else if c == '[' {
+            // a root array has no key before its first index
+            if in_key && (!name.is_empty() || rest.len() < key.len()) {
+                path.push(PathElement::Key(std::mem::take(&mut name)));
+            }
+            let end = rest.find(']')?;
+            path.push(PathElement::Index(rest[1..end].parse().ok()?));
+            in_key = false;
+            rest = &rest[end + 1..];
+        } else {
+            name.push(c);
+            in_key = true;
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    if in_key {
+        path.push(PathElement::Key(name));
+    }
+    Some(path)
+}
+
+/// A consumer that flattens the document before forwarding it: the root object or array
+/// becomes an object whose members are the scalars and the empty containers of the document,
+/// in the order of the document. A scalar document is forwarded as it is.
+pub struct FlattenConsumer<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    separator: String,
+    tracker: PathTracker,
+    /// For each open container: true while it is empty
+    empties: Vec<bool>,
+}
+
+impl<'a, C: JSONParseConsumer> FlattenConsumer<'a, C> {
+    pub fn new(consumer: &'a mut C) -> Self {
+        FlattenConsumer {
+            consumer,
+            separator: ".".into(),
+            tracker: PathTracker::new(),
+            empties: vec!(),
+        }
+    }
+
+    /// The separator of the keys (default: `.`).
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    /// Update the path with the first token of a value.
+    fn begin_value(&mut self, token: &ParserToken) {
+        if let Some(empty) = self.empties.last_mut() {
+            *empty = false;
+        }
+        self.tracker.update(token);
+    }
+}
+
+impl<'a, C: JSONParseConsumer> JSONParseConsumer for FlattenConsumer<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = match token {
+            Ok(t) => t,
+            Err(e) => return self.consumer.consume(Err(e)),
+        };
+        match token {
+            ParserToken::Key(_) => {
+                self.tracker.update(&token);
+                Ok(())
+            }
+            ParserToken::BeginObject | ParserToken::BeginArray => {
+                if self.empties.is_empty() {
+                    self.consumer.consume(Ok(ParserToken::BeginObject))?;
+                }
+                self.begin_value(&token);
+                self.empties.push(true);
+                Ok(())
+            }
+            ParserToken::EndObject | ParserToken::EndArray => {
+                let empty = self.empties.pop().expect("an open container");
+                // the path of the container
+                let key = flat_key(&self.tracker.path()[..self.tracker.depth() - 1], &self.separator);
+                self.tracker.update(&token);
+                if self.empties.is_empty() {
+                    self.consumer.consume(Ok(ParserToken::EndObject))
+                } else if empty {
+                    let (begin, end) = if token == ParserToken::EndArray {
+                        (ParserToken::BeginArray, ParserToken::EndArray)
+                    } else {
+                        (ParserToken::BeginObject, ParserToken::EndObject)
+                    };
+                    self.consumer.consume(Ok(ParserToken::Key(key)))?;
+                    self.consumer.consume(Ok(begin))?;
+                    self.consumer.consume(Ok(end))
+                } else {
+                    Ok(())
+                }
+            }
+            _ if is_value_start(&token) && !self.empties.is_empty() => {
+                self.begin_value(&token);
+                self.consumer.consume(Ok(ParserToken::Key(flat_key(self.tracker.path(), &self.separator))))?;
+                self.consumer.consume(Ok(token))
+            }
+            _ => self.consumer.consume(Ok(token)),
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
+
+/// A consumer of a flat document (see `FlattenConsumer`) that calls `callback` with the key and
+/// the value of each member. The key of a scalar document is empty.
+pub struct FlatPairsConsumer<F: FnMut(&str, JsonValue)> {
+    callback: F,
+    key: String,
+    /// The depth in the document: the members of the flat object are at depth 1
+    depth: isize,
+    builder: Option<ValueBuilderConsumer>,
+    /// The depth in the current value
+    value_depth: isize,
+}
+
+impl<F: FnMut(&str, JsonValue)> FlatPairsConsumer<F> {
+    pub fn new(callback: F) -> Self {
+        FlatPairsConsumer { callback, key: String::new(), depth: 0, builder: None, value_depth: 0 }
+    }
+}
+
+impl<F: FnMut(&str, JsonValue)> JSONParseConsumer for FlatPairsConsumer<F> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        if let Some(builder) = self.builder.as_mut() {
+            self.value_depth += depth_delta(&token);
+            builder.consume(Ok(token))?;
+            if self.value_depth == 0 {
+                let value = self.builder.take().and_then(|b| b.into_value()).unwrap_or(JsonValue::Null);
+                (self.callback)(&std::mem::take(&mut self.key), value);
+            }
+            return Ok(());
+        }
+        match token {
+            ParserToken::BeginObject if self.depth == 0 => self.depth = 1,
+            ParserToken::EndObject if self.depth == 1 => self.depth = 0,
+            ParserToken::Key(k) => self.key = k,
+            _ if is_value_start(&token) => {
+                let mut builder = ValueBuilderConsumer::new();
+                self.value_depth = depth_delta(&token);
+                builder.consume(Ok(token))?;
+                if self.value_depth == 0 {
+                    let value = builder.into_value().unwrap_or(JsonValue::Null);
+                    (self.callback)(&std::mem::take(&mut self.key), value);
+                } else {
+                    self.builder = Some(builder);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// A consumer that rebuilds the nested containers of a flat object (see `FlattenConsumer`)
+/// before forwarding it. The members of a container must be consecutive: if the keys `a.x`,
+/// `b`, `a.y` follow each other, the object `a` is written twice. The indices only delimit the
+/// elements of the arrays: `[0]`, `[5]` gives two elements.
+///
+/// A document that is not an object is forwarded as it is.
+pub struct UnflattenConsumer<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    separator: String,
+    /// The depth in the document: the members of the flat object are at depth 1
+    depth: isize,
+    /// true if the document is an object
+    flat: bool,
+    /// The path of the innermost open container
+    open: Vec<PathElement>,
+    /// true for an array, for the root and the open containers
+    arrays: Vec<bool>,
+}
+
+impl<'a, C: JSONParseConsumer> UnflattenConsumer<'a, C> {
+    pub fn new(consumer: &'a mut C) -> Self {
+        UnflattenConsumer {
+            consumer,
+            separator: ".".into(),
+            depth: 0,
+            flat: false,
+            open: vec!(),
+            arrays: vec!(),
+        }
+    }
+
+    /// The separator of the keys (default: `.`).
+    pub fn separator(mut self, separator: &str) -> Self {
+        self.separator = separator.into();
+        self
+    }
+
+    fn end(&mut self, array: bool) -> Result<(), ConsumeError> {
+        self.consumer.consume(Ok(if array { ParserToken::EndArray } else { ParserToken::EndObject }))
+    }
+
+    /// Close and open the containers before the value of the flat key.
+    fn move_to(&mut self, key: &str) -> Result<(), ConsumeError> {
+        let path = parse_flat_key(key, &self.separator).ok_or_else(|| ConsumeError {
+            msg: format!("Invalid flat key `{}`", key),
+            line: 0,
+            column: 0,
+            io_error: None,
+        })?;
+        if self.arrays.is_empty() {
+            let array = matches!(path.first(), Some(PathElement::Index(_)));
+            self.consumer.consume(Ok(if array { ParserToken::BeginArray } else { ParserToken::BeginObject }))?;
+            self.arrays.push(array);
+        }
+        let (last, containers) = path.split_last().expect("a path is never empty");
+        let common = self.open.iter().zip(containers).take_while(|(a, b)| a == b).count();
+        while self.open.len() > common {
+            self.open.pop();
+            let array = self.arrays.pop().expect("an open container");
+            self.end(array)?;
+        }
+        for (i, element) in containers.iter().enumerate().skip(common) {
+            if let PathElement::Key(k) = element {
+                self.consumer.consume(Ok(ParserToken::Key(k.clone())))?;
+            }
+            let next = containers.get(i + 1).unwrap_or(last);
+            let array = matches!(next, PathElement::Index(_));
+            self.consumer.consume(Ok(if array { ParserToken::BeginArray } else { ParserToken::BeginObject }))?;
+            self.open.push(element.clone());
+            self.arrays.push(array);
+        }
+        if let PathElement::Key(k) = last {
+            self.consumer.consume(Ok(ParserToken::Key(k.clone())))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, C: JSONParseConsumer> JSONParseConsumer for UnflattenConsumer<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = match token {
+            Ok(t) => t,
+            Err(e) => return self.consumer.consume(Err(e)),
+        };
+        match token {
+            ParserToken::BeginObject if self.depth == 0 => {
+                self.flat = true;
+                self.depth = 1;
+                Ok(())
+            }
+            ParserToken::EndObject if self.flat && self.depth == 1 => {
+                self.flat = false;
+                self.depth = 0;
+                self.open.clear();
+                if self.arrays.is_empty() {
+                    // an empty flat object
+                    self.consumer.consume(Ok(ParserToken::BeginObject))?;
+                    return self.end(false);
+                }
+                while let Some(array) = self.arrays.pop() {
+                    self.end(array)?;
+                }
+                Ok(())
+            }
+            ParserToken::Key(k) if self.flat && self.depth == 1 => self.move_to(&k),
+            _ => {
+                self.depth += depth_delta(&token);
+                self.consumer.consume(Ok(token))
+            }
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
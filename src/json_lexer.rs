@@ -20,13 +20,19 @@
  */
 #![allow(unused_variables)]
 
+use std::collections::VecDeque;
+use std::fmt;
+use std::io;
 use std::io::Read;
 use std::str;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
-use crate::byte_source::ByteSource;
+use crate::byte_source::{ByteSource, FeedBuffer};
 use crate::json_lexer::LexerToken::{BeginFile, EndFile};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum LexerToken {
     BeginObject,
     EndObject,
@@ -37,28 +43,163 @@ pub enum LexerToken {
     BooleanValue(bool),
     NullValue,
     String(String),
+    /// A string that is not valid UTF-8 (see `InvalidUtf8Policy::Bytes`)
+    Bytes(Vec<u8>),
+    /// A string and its source text, quotes and escapes included (see
+    /// `LexerOptions::raw_strings`). The invalid UTF-8 bytes of `raw` are replaced with U+FFFD.
+    RawString { decoded: String, raw: String },
     IntValue(String),
     FloatValue(String),
+    /// The text of a `//` or `/* */` comment, without the delimiters, or with them if
+    /// `LexerOptions::raw_strings` is set (see `LexerOptions::emit_comments`). The parser ignores
+    /// the comments.
+    Comment(String),
+    /// A string longer than `LexerOptions::string_chunk_size` is sent as a `StringStart`, some
+    /// `StringChunk`s and a `StringEnd`.
+    StringStart,
+    /// A part of the unescaped string. The chunks end on a char boundary if the string is
+    /// valid UTF-8. With `InvalidUtf8Policy::Error`, an invalid string ends with an error instead
+    /// of a `StringEnd`.
+    StringChunk(Vec<u8>),
+    StringEnd,
+    /// A string that was scanned but not decoded because the consumer did not need it (see
+    /// `JSONLexConsumer::skip_value`).
+    SkippedString,
     BeginFile,
     EndFile,
 }
 
+/// The category of a `JSONLexError`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum LexErrorKind {
+    UnexpectedChar(char),
+    /// `true`, `false` or `null` was expected
+    ExpectedWord(String),
+    /// A digit was expected after a minus sign
+    ExpectedDigit(char),
+    LeadingZero,
+    MissingDigits,
+    MissingDecimals,
+    MissingExponent,
+    UnfinishedString,
+    UnfinishedComment,
+    InvalidUtf8,
+    UnknownEscape(char),
+    InvalidHexDigit(char),
+    InvalidCodePoint(u32),
+    /// A high surrogate not followed by a low surrogate
+    LoneSurrogate,
+    /// An unescaped control char in a string (strict mode)
+    ControlChar(u8),
+    /// See `LexerLimits`
+    LimitExceeded,
+    /// See `Interrupt`
+    Interrupted(InterruptReason),
+    /// The source could not be read
+    Io(io::ErrorKind),
+    /// An inconsistent state of the lexer
+    Internal,
+}
+
 #[derive(Debug, PartialEq)]
 pub struct JSONLexError {
+    pub kind: LexErrorKind,
     pub msg: String,
     pub line: usize,
     pub column: usize,
 }
 
-#[derive(Debug, PartialEq)]
+impl fmt::Display for JSONLexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.msg, self.line, self.column)
+    }
+}
+
+impl std::error::Error for JSONLexError {}
+
+#[derive(Debug)]
 pub struct ConsumeError {
     pub msg: String,
     pub line: usize,
     pub column: usize,
+    /// The error of the output of the consumer, e.g. a full disk, or of the input. The parser
+    /// sets the position of an output error to the position of the token that was written.
+    pub io_error: Option<io::Error>,
+}
+
+impl ConsumeError {
+    /// An error of the output of a consumer. The position is set by the parser.
+    pub fn from_io(e: io::Error) -> Self {
+        ConsumeError {
+            msg: format!("write error {:?}", e.kind()),
+            line: 0,
+            column: 0,
+            io_error: Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for ConsumeError {
+    fn from(e: io::Error) -> Self {
+        ConsumeError::from_io(e)
+    }
+}
+
+/// The `io::Error`s are compared by kind.
+impl PartialEq for ConsumeError {
+    fn eq(&self, other: &Self) -> bool {
+        self.msg == other.msg && self.line == other.line && self.column == other.column &&
+            self.io_error.as_ref().map(|e| e.kind()) == other.io_error.as_ref().map(|e| e.kind())
+    }
+}
+
+impl fmt::Display for ConsumeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.msg, self.line, self.column)
+    }
+}
+
+impl std::error::Error for ConsumeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.io_error.as_ref().map(|e| e as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// The position of a token in the input: `start..end` is the range of the bytes of the token
+/// (for an error, the bytes read since the start of the token).
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
 }
 
 pub trait JSONLexConsumer {
     fn consume(&mut self, token: Result<LexerToken, JSONLexError>, line: usize, column: usize) -> Result<(), ConsumeError>;
+
+    /// Called by the lexer instead of `consume`. Override this method to get the byte range
+    /// of the tokens.
+    fn consume_with_span(&mut self, token: Result<LexerToken, JSONLexError>, span: Span) -> Result<(), ConsumeError> {
+        self.consume(token, span.line, span.column)
+    }
+
+    /// Asked by the lexer at the start of a string or a number: if true, the content is not
+    /// needed. The lexer scans the value without unescaping or buffering it and emits a
+    /// `SkippedString` token, or a number token with an empty text.
+    fn skip_value(&self) -> bool {
+        false
+    }
 }
 
 enum LexerState {
@@ -66,6 +207,15 @@ enum LexerState {
     Expect(LexerToken),
     Number,
     String,
+    /// A string whose content is not needed: the bool is true after a backslash.
+    SkippedString(bool),
+    /// An unquoted word of JSON5: a key, `Infinity`, `NaN` or a literal.
+    Identifier,
+    /// A `/` was seen
+    CommentStart,
+    LineComment,
+    /// The bool is true after a `*`.
+    BlockComment(bool),
 }
 
 enum LexerNumberSubState {
@@ -79,6 +229,11 @@ enum LexerNumberSubState {
     NumberFracExp,
     NumberFracExpMinusStart,
     NumberFracExpMinus,
+    /// JSON5: `.` without an integer part
+    NumberDotStart,
+    /// JSON5: `0x`
+    HexStart,
+    Hex,
 }
 
 #[derive(Debug)]
@@ -86,31 +241,535 @@ enum LexerStringSubState {
     None,
     Escape,
     Unicode,
+    /// JSON5: a backslash and a `\r`, maybe followed by a `\n`
+    LineContinuation,
 }
 
 const REPLACEMENT_CHARACTER: char = '\u{fffd}';
 
+/// What to do with an escaped surrogate that is not part of a pair, e.g. `"\ud83d-"`.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum SurrogatePolicy {
+    /// Emit an error and drop the surrogate. A lone low surrogate is replaced with U+FFFD if
+    /// `LexerOptions::ignore_unicode_errs` is set.
+    #[default]
+    Error,
+    /// Replace the surrogate with U+FFFD.
+    Replace,
+    /// Encode the surrogate as WTF-8, hence a string with a lone surrogate is a
+    /// `LexerToken::Bytes` token. The strings of JavaScript can be written back as they were.
+    PreserveWtf8,
+}
+
+/// What to do with a string that is not valid UTF-8.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum InvalidUtf8Policy {
+    /// Emit an error with the offset of the first invalid byte in the string. The position of
+    /// the error is the position of this byte.
+    #[default]
+    Error,
+    /// Replace the invalid sequences with U+FFFD, as soon as they are read.
+    Replace,
+    /// Emit a `LexerToken::Bytes` token holding the raw bytes.
+    Bytes,
+}
+
+/// The flavor of JSON accepted by the lexer.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum Dialect {
+    /// RFC 8259
+    #[default]
+    Json,
+    /// <https://json5.org/>: unquoted keys, single-quoted strings, hex numbers, `+` and leading or
+    /// trailing dots in numbers, `Infinity`, `NaN`, line continuations in strings and comments
+    /// (see `LexerOptions::allow_comments`). The
+    /// numbers are normalized: `+.5` is `0.5`, `0x1F` is `31` and `Infinity` is a `FloatValue`.
+    /// The parser accepts the trailing commas of any dialect.
+    Json5,
+}
+
+/// Why the lexer was interrupted.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum InterruptReason {
+    DeadlineExceeded,
+    Cancelled,
+}
+
+impl fmt::Display for InterruptReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterruptReason::DeadlineExceeded => write!(f, "deadline exceeded"),
+            InterruptReason::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+/// Abort the lexing when a deadline is passed or a flag is set, e.g. when the client of a server
+/// disconnects. The conditions are checked every `every` tokens (every token if `every` is 0):
+/// the consumer receives a `JSONLexError` of kind `LexErrorKind::Interrupted` instead of the
+/// current token, and the lexer returns a `ConsumeError` "Parsing interrupted: <reason>".
+#[derive(Debug, Clone, Default)]
+pub struct Interrupt {
+    pub every: usize,
+    pub deadline: Option<Instant>,
+    pub cancelled: Option<Arc<AtomicBool>>,
+}
+
+impl Interrupt {
+    /// The reason to stop now, if any.
+    pub fn check(&self) -> Option<InterruptReason> {
+        if let Some(cancelled) = &self.cancelled {
+            if cancelled.load(Ordering::Relaxed) {
+                return Some(InterruptReason::Cancelled);
+            }
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => Some(InterruptReason::DeadlineExceeded),
+            _ => None,
+        }
+    }
+}
+
+impl PartialEq for Interrupt {
+    fn eq(&self, other: &Self) -> bool {
+        self.every == other.every && self.deadline == other.deadline && match (&self.cancelled, &other.cancelled) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+}
+
+/// The progress of the lexing, sent to the `Progress` callback.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct ProgressInfo {
+    /// The number of bytes consumed.
+    pub bytes: usize,
+    /// The number of tokens emitted, `BeginFile` and `EndFile` included.
+    pub tokens: usize,
+}
+
+/// Report the progress of the lexing, e.g. to render a progress bar. The callback is called at
+/// the first token that ends after each step of `every_bytes` bytes (a long string is a single
+/// token, hence a single call), and at the `EndFile`. The pull mode does not report.
+#[derive(Clone)]
+pub struct Progress {
+    pub every_bytes: usize,
+    pub callback: Arc<dyn Fn(ProgressInfo) + Send + Sync>,
+}
+
+impl Progress {
+    pub fn new<F: Fn(ProgressInfo) + Send + Sync + 'static>(every_bytes: usize, callback: F) -> Self {
+        Progress { every_bytes, callback: Arc::new(callback) }
+    }
+}
+
+impl fmt::Debug for Progress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Progress").field("every_bytes", &self.every_bytes).finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for Progress {
+    fn eq(&self, other: &Self) -> bool {
+        self.every_bytes == other.every_bytes && Arc::ptr_eq(&self.callback, &other.callback)
+    }
+}
+
+/// The progress state, kept by the lexer between two chunks in feed mode.
+#[derive(Default, Clone, Copy)]
+struct ProgressState {
+    info: ProgressInfo,
+    /// The offset of the next report.
+    next: usize,
+}
+
+/// A lex consumer that checks the interrupt and reports the progress before forwarding the
+/// tokens.
+struct MonitorConsumer<'a, C: JSONLexConsumer> {
+    consumer: &'a mut C,
+    interrupt: Option<Interrupt>,
+    count: usize,
+    progress: Option<Progress>,
+    state: ProgressState,
+}
+
+impl<'a, C: JSONLexConsumer> MonitorConsumer<'a, C> {
+    fn report(&mut self, token: &Result<LexerToken, JSONLexError>, span: &Span) {
+        if let Some(progress) = &self.progress {
+            self.state.info.tokens += 1;
+            if span.end >= self.state.next || matches!(token, Ok(EndFile)) {
+                self.state.info.bytes = span.end;
+                self.state.next = match progress.every_bytes {
+                    0 => span.end,
+                    every => (span.end / every + 1) * every,
+                };
+                (progress.callback)(self.state.info);
+            }
+        }
+    }
+}
+
+impl<'a, C: JSONLexConsumer> JSONLexConsumer for MonitorConsumer<'a, C> {
+    fn consume(&mut self, token: Result<LexerToken, JSONLexError>, line: usize, column: usize) -> Result<(), ConsumeError> {
+        self.consume_with_span(token, Span { line, column, ..Default::default() })
+    }
+
+    fn consume_with_span(&mut self, token: Result<LexerToken, JSONLexError>, span: Span) -> Result<(), ConsumeError> {
+        if let Some(interrupt) = &self.interrupt {
+            self.count += 1;
+            if self.count >= interrupt.every {
+                self.count = 0;
+                if let Some(reason) = interrupt.check() {
+                    let msg = format!("Parsing interrupted: {}", reason);
+                    let error = JSONLexError { kind: LexErrorKind::Interrupted(reason), msg: msg.clone(), line: span.line, column: span.column };
+                    self.consumer.consume_with_span(Err(error), span)?;
+                    return Err(ConsumeError { msg, line: span.line, column: span.column, io_error: None });
+                }
+            }
+        }
+        self.report(&token, &span);
+        self.consumer.consume_with_span(token, span)
+    }
+
+    fn skip_value(&self) -> bool {
+        self.consumer.skip_value()
+    }
+}
+
+/// Limits for untrusted inputs. A violation is a `JSONLexError` that stops the lexing. The
+/// skipped values (see `JSONLexConsumer::skip_value`) are not buffered, hence not checked.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LexerLimits {
+    /// The max number of bytes of a string, after unescaping.
+    pub max_string_bytes: Option<usize>,
+    /// The max number of bytes of a number, sign, dot and exponent included.
+    pub max_number_bytes: Option<usize>,
+    /// The max number of tokens, `BeginFile` and `EndFile` excluded.
+    pub max_tokens: Option<usize>,
+    pub max_document_bytes: Option<usize>,
+}
+
+/// A lex consumer that counts the tokens before forwarding them.
+struct TokenCounter<'a, C: JSONLexConsumer> {
+    consumer: &'a mut C,
+    max_tokens: usize,
+    count: usize,
+}
+
+impl<'a, C: JSONLexConsumer> JSONLexConsumer for TokenCounter<'a, C> {
+    fn consume(&mut self, token: Result<LexerToken, JSONLexError>, line: usize, column: usize) -> Result<(), ConsumeError> {
+        self.consume_with_span(token, Span { line, column, ..Default::default() })
+    }
+
+    fn consume_with_span(&mut self, token: Result<LexerToken, JSONLexError>, span: Span) -> Result<(), ConsumeError> {
+        if matches!(token, Ok(EndFile)) || token.is_err() {
+            return self.consumer.consume_with_span(token, span);
+        }
+        self.count += 1;
+        if self.count > self.max_tokens {
+            let msg = format!("More than {} tokens", self.max_tokens);
+            let error = JSONLexError { kind: LexErrorKind::LimitExceeded, msg: msg.clone(), line: span.line, column: span.column };
+            self.consumer.consume_with_span(Err(error), span)?;
+            return Err(ConsumeError { msg, line: span.line, column: span.column, io_error: None });
+        }
+        self.consumer.consume_with_span(token, span)
+    }
+
+    fn skip_value(&self) -> bool {
+        self.consumer.skip_value()
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct LexerOptions {
+    /// Replace the invalid escaped code points with U+FFFD instead of emitting an error.
+    pub ignore_unicode_errs: bool,
+    pub invalid_utf8: InvalidUtf8Policy,
+    pub surrogates: SurrogatePolicy,
+    pub interrupt: Option<Interrupt>,
+    pub progress: Option<Progress>,
+    pub limits: LexerLimits,
+    /// Reject what RFC 8259 forbids and the lexer tolerates by default: the leading zeros
+    /// (`01` is read as `0` and `1`) and the unescaped control chars in strings. The parser
+    /// also rejects a second value at the top level.
+    pub strict: bool,
+    pub dialect: Dialect,
+    /// Skip the `//` line comments and the `/* */` block comments (JSONC).
+    pub allow_comments: bool,
+    /// Emit a `LexerToken::Comment` for each comment, if the comments are allowed.
+    pub emit_comments: bool,
+    /// Accept the `'single quoted'` strings (and the `\'` escape), as written by Python's
+    /// `repr`. Always on in JSON5.
+    pub single_quotes: bool,
+    /// Send the strings longer than this number of bytes in chunks instead of buffering them
+    /// (see `LexerToken::StringStart`). `LexerLimits::max_string_bytes` is ignored.
+    pub string_chunk_size: Option<usize>,
+    /// Emit a `LexerToken::RawString` instead of a `LexerToken::String`, to write the strings
+    /// back exactly as they were, e.g. `"\u00e9"` and not `"é"`. Ignored if `string_chunk_size`
+    /// is set. The parser uses the decoded string. The comments keep their delimiters and `-0`
+    /// is not read as `0`.
+    pub raw_strings: bool,
+}
+
+/// The state of the lexer between two bytes.
+struct LexCursor {
+    state: LexerState,
+    expect: &'static [u8; 4],
+    expected_index: usize,
+    number_sub_state: LexerNumberSubState,
+    string_sub_state: LexerStringSubState,
+    buf: Vec<u8>,
+    code_point: u32,
+    unicode_index: usize,
+    high: u32,
+    skip_number: bool,
+    token_start: usize,
+    /// The quote of the current string: `"`, or `'` in JSON5.
+    quote: u8,
+    /// true if a `StringStart` was sent for the current string
+    chunked: bool,
+    /// The length of the prefix of `buf` that was checked for UTF-8
+    utf8_checked: usize,
+    /// The position and the value of the first invalid UTF-8 byte of the current string
+    utf8_error: Option<(usize, usize, u8)>,
+    /// true if a lone surrogate of the current string was encoded as WTF-8
+    wtf8: bool,
+}
+
+impl Default for LexCursor {
+    fn default() -> Self {
+        LexCursor {
+            state: LexerState::None,
+            expect: &[1u8, 2u8, 3u8, 4u8],
+            expected_index: 0,
+            number_sub_state: LexerNumberSubState::None,
+            string_sub_state: LexerStringSubState::None,
+            buf: vec!(),
+            code_point: 0,
+            unicode_index: 0,
+            high: 0,
+            skip_number: false,
+            token_start: 0,
+            quote: b'"',
+            chunked: false,
+            utf8_checked: 0,
+            utf8_error: None,
+            wtf8: false,
+        }
+    }
+}
+
+type QueuedToken = (Result<LexerToken, JSONLexError>, Span);
+
+impl JSONLexConsumer for VecDeque<QueuedToken> {
+    fn consume(&mut self, token: Result<LexerToken, JSONLexError>, line: usize, column: usize) -> Result<(), ConsumeError> {
+        self.push_back((token, Span { line, column, ..Default::default() }));
+        Ok(())
+    }
+
+    fn consume_with_span(&mut self, token: Result<LexerToken, JSONLexError>, span: Span) -> Result<(), ConsumeError> {
+        self.push_back((token, span));
+        Ok(())
+    }
+}
+
+/// The queue of the pull mode, that may skip the values.
+struct PullQueue {
+    queue: VecDeque<QueuedToken>,
+    skip: bool,
+}
+
+impl JSONLexConsumer for PullQueue {
+    fn consume(&mut self, token: Result<LexerToken, JSONLexError>, line: usize, column: usize) -> Result<(), ConsumeError> {
+        self.queue.consume(token, line, column)
+    }
+
+    fn consume_with_span(&mut self, token: Result<LexerToken, JSONLexError>, span: Span) -> Result<(), ConsumeError> {
+        self.queue.consume_with_span(token, span)
+    }
+
+    fn skip_value(&self) -> bool {
+        self.skip
+    }
+}
+
 pub struct JSONLexer<R: Read> {
     byte_source: ByteSource<R>,
     line: usize,
     column: usize,
     ignore_unicode_errs: bool,
+    options: LexerOptions,
+    cursor: LexCursor,
+    // pull mode
+    queue: VecDeque<QueuedToken>,
+    started: bool,
+    finished: bool,
+    token_count: usize,
+    progress: ProgressState,
 }
 
 impl<R: Read> JSONLexer<R> {
     pub fn new(byte_source: ByteSource<R>, ignore_unicode_errs: bool) -> Self {
+        JSONLexer::new_with_options(byte_source, LexerOptions { ignore_unicode_errs, ..Default::default() })
+    }
+
+    pub fn new_with_options(byte_source: ByteSource<R>, options: LexerOptions) -> Self {
         JSONLexer {
             byte_source,
             line: 0,
             column: 0,
-            ignore_unicode_errs,
+            ignore_unicode_errs: options.ignore_unicode_errs,
+            options,
+            cursor: LexCursor::default(),
+            queue: VecDeque::new(),
+            started: false,
+            finished: false,
+            token_count: 0,
+            progress: ProgressState::default(),
         }
     }
 
+    /// The number of bytes consumed so far.
+    pub fn byte_offset(&self) -> usize {
+        self.byte_source.offset()
+    }
+
+    pub fn options(&self) -> &LexerOptions {
+        &self.options
+    }
+
     pub fn lex<C: JSONLexConsumer>(&mut self, consumer: &mut C) -> Result<(), ConsumeError> {
+        if self.options.interrupt.is_none() && self.options.progress.is_none() {
+            return self.lex_tokens(consumer);
+        }
+        let mut monitor = self.monitor(consumer);
+        let result = self.lex_tokens(&mut monitor);
+        self.progress = monitor.state;
+        result
+    }
+
+    fn monitor<'a, C: JSONLexConsumer>(&self, consumer: &'a mut C) -> MonitorConsumer<'a, C> {
+        MonitorConsumer {
+            consumer,
+            interrupt: self.options.interrupt.clone(),
+            count: 0,
+            progress: self.options.progress.clone(),
+            state: self.progress,
+        }
+    }
+
+    fn lex_tokens<C: JSONLexConsumer>(&mut self, consumer: &mut C) -> Result<(), ConsumeError> {
+        consumer.consume_with_span(Ok(BeginFile), self.begin_file_span())?;
+        loop {
+            match self.byte_source.get() {
+                Ok(Some(byte)) => self.lex_byte(Some(byte), consumer)?,
+                Ok(None) => return self.lex_byte(None, consumer),
+                Err(e) => return self.read_failed(consumer, e),
+            }
+        }
+    }
+
+    fn begin_file_span(&self) -> Span {
+        Span { start: 0, end: 0, line: self.line, column: self.column }
+    }
+
+    /// The next token, in pull mode. The tokens are the ones that `lex` would have sent to the
+    /// consumer, with their span.
+    pub fn next_token(&mut self) -> Option<(Result<LexerToken, JSONLexError>, Span)> {
+        self.next_token_skipping(false)
+    }
+
+    /// Like `next_token`, but if `skip` is true and the next token is a string or a number, the
+    /// text is not needed (see `JSONLexConsumer::skip_value`): the token has an empty text.
+    pub fn next_token_skipping(&mut self, skip: bool) -> Option<(Result<LexerToken, JSONLexError>, Span)> {
+        let mut queue = PullQueue { queue: std::mem::take(&mut self.queue), skip };
+        if !self.started {
+            self.started = true;
+            queue.queue.push_back((Ok(BeginFile), self.begin_file_span()));
+        }
+        while queue.queue.is_empty() && !self.finished {
+            // a queue never refuses a token
+            match self.byte_source.get() {
+                Ok(byte) => {
+                    if byte.is_none() {
+                        self.finished = true;
+                    }
+                    let _ = self.lex_byte(byte, &mut queue);
+                }
+                Err(e) => {
+                    let _ = self.read_failed(&mut queue, e);
+                }
+            }
+        }
+        let token = queue.queue.pop_front();
+        self.queue = queue.queue;
+        token
+    }
+
+    /// Lex one byte, or finish the lexing if `byte` is None.
+    fn lex_byte<C: JSONLexConsumer>(&mut self, byte: Option<u8>, consumer: &mut C) -> Result<(), ConsumeError> {
+        match self.options.limits.max_tokens {
+            Some(max_tokens) => {
+                let mut counter = TokenCounter { consumer, max_tokens, count: self.token_count };
+                let result = self.lex_byte_uncounted(byte, &mut counter);
+                self.token_count = counter.count;
+                result
+            }
+            None => self.lex_byte_uncounted(byte, consumer),
+        }
+    }
+
+    /// Emit a limit violation and stop the lexing.
+    fn limit_exceeded<C: JSONLexConsumer>(&mut self, consumer: &mut C, msg: String) -> Result<(), ConsumeError> {
+        self.finished = true;
+        let offset = self.byte_source.offset();
+        let span = Span { start: offset, end: offset, line: self.line, column: self.column };
+        let error = JSONLexError { kind: LexErrorKind::LimitExceeded, msg: msg.clone(), line: self.line, column: self.column };
+        consumer.consume_with_span(Err(error), span)?;
+        Err(ConsumeError { msg, line: self.line, column: self.column, io_error: None })
+    }
+
+    /// true if the rest of the literal that starts with `byte` follows. The literal is then
+    /// lexed at once; otherwise the `Expect` state reports the error.
+    fn peek_literal(&mut self, byte: u8) -> bool {
+        let (rest, _) = literal_rest(byte);
+        let within_limit = self.options.limits.max_document_bytes
+            .is_none_or(|max| self.byte_source.offset() + rest.len() <= max);
+        within_limit && matches!(self.byte_source.peek_n(rest.len()), Ok(next) if next == rest)
+    }
+
+    /// Emit a read error and stop the lexing.
+    fn read_failed<C: JSONLexConsumer>(&mut self, consumer: &mut C, e: io::Error) -> Result<(), ConsumeError> {
+        self.finished = true;
+        let offset = self.byte_source.offset();
+        let span = Span { start: offset, end: offset, line: self.line, column: self.column };
+        let msg = format!("read error {:?}", e.kind());
+        let error = JSONLexError { kind: LexErrorKind::Io(e.kind()), msg: msg.clone(), line: self.line, column: self.column };
+        match consumer.consume_with_span(Err(error), span) {
+            Err(consume_error) if consume_error.io_error.is_some() => Err(consume_error),
+            _ => Err(ConsumeError { msg, line: self.line, column: self.column, io_error: Some(e) }),
+        }
+    }
+
+    /// The number of bytes that may be added at once to a string of `len` bytes without missing
+    /// a chunk or a limit.
+    fn max_string_run(&self, len: usize) -> usize {
+        let string_max = match self.options.string_chunk_size {
+            Some(chunk_size) => Some(chunk_size.max(4)),
+            None => self.options.limits.max_string_bytes,
+        };
+        let string_run = string_max.map_or(usize::MAX, |max| (max + 1).saturating_sub(len));
+        let document_run = self.options.limits.max_document_bytes
+            .map_or(usize::MAX, |max| max.saturating_sub(self.byte_source.offset()));
+        string_run.min(document_run)
+    }
+
+    fn lex_byte_uncounted<C: JSONLexConsumer>(&mut self, byte: Option<u8>, consumer: &mut C) -> Result<(), ConsumeError> {
         macro_rules! lex_error {
-            ($($arg:tt)*) => {{
+            ($kind:expr, $($arg:tt)*) => {{
                 Err(JSONLexError {
+                    kind: $kind,
                     msg: format!($($arg)*),
                     line: self.line,
                     column: self.column,
@@ -119,15 +778,25 @@ impl<R: Read> JSONLexer<R> {
         }
 
         macro_rules! consume_lex_error {
-            ($($arg:tt)*) => {{
-                consumer.consume(lex_error!($($arg)*), self.line, self.column)?;
+            ($kind:expr, $($arg:tt)*) => {{
+                consumer.consume_with_span(lex_error!($kind, $($arg)*), span!())?;
             }}
         }
 
+        // the byte will be read again
+        macro_rules! unget {
+            () => {{
+                self.byte_source.unget();
+                if byte == Some(b'\n') {
+                    self.line -= 1;
+                }
+            }};
+        }
+
         macro_rules! end_of_number {
             ($buf:ident, $number_sub_state: ident, $state: ident) => {{
                 $buf = vec!();
-                self.byte_source.unget();
+                unget!();
                 $number_sub_state = LexerNumberSubState::None;
                 $state = LexerState::None;
             }};
@@ -153,15 +822,66 @@ impl<R: Read> JSONLexer<R> {
             ($buf:ident, $token_variant: ident) => {{
                 match String::from_utf8($buf) {
                     Ok(s) => {
-                        consumer.consume(Ok(LexerToken::$token_variant(s)), self.line, self.column)?;
+                        consumer.consume_with_span(Ok(LexerToken::$token_variant(s)), number_span!())?;
                     }
                     Err(e) => {
-                        consume_lex_error!("Can't decode string `{}`", e);
+                        consume_lex_error!(LexErrorKind::InvalidUtf8, "Can't decode string `{}`", e);
                     }
                 }
             }};
         }
 
+        // the position of the first invalid byte, if it is known
+        macro_rules! consume_utf8_error {
+            ($bytes:expr, $utf8_error:ident) => {{
+                let bytes: &[u8] = $bytes;
+                let valid_up_to = str::from_utf8(bytes).err().map_or(bytes.len(), |e| e.valid_up_to());
+                let valid = str::from_utf8(&bytes[..valid_up_to]).unwrap();
+                let (line, column) = $utf8_error.map_or((self.line, self.column), |(line, column, _)| (line, column));
+                let error = JSONLexError {
+                    kind: LexErrorKind::InvalidUtf8,
+                    msg: format!("Invalid UTF-8 byte `0x{:02x}` at offset {} in string `{}`", bytes.get(valid_up_to).copied().unwrap_or_default(), valid_up_to, valid),
+                    line,
+                    column,
+                };
+                consumer.consume_with_span(Err(error), span!())?;
+            }};
+        }
+
+        macro_rules! consume_string {
+            ($buf:ident, $utf8_error:ident, $wtf8:ident, $quote:ident) => {{
+                let string_token = |decoded: String, raw: Option<Vec<u8>>| match raw {
+                    Some(mut raw) => {
+                        raw.insert(0, $quote);
+                        LexerToken::RawString { decoded, raw: String::from_utf8_lossy(&raw).into_owned() }
+                    }
+                    None => LexerToken::String(decoded),
+                };
+                let raw = self.byte_source.stop_recording();
+                if $wtf8 {
+                    consumer.consume_with_span(Ok(LexerToken::Bytes($buf)), span!())?;
+                } else { match String::from_utf8($buf) {
+                    Ok(s) => {
+                        consumer.consume_with_span(Ok(string_token(s, raw)), span!())?;
+                    }
+                    Err(e) => {
+                        match self.options.invalid_utf8 {
+                            InvalidUtf8Policy::Error => {
+                                consume_utf8_error!(e.as_bytes(), $utf8_error);
+                            }
+                            InvalidUtf8Policy::Replace => {
+                                let s = String::from_utf8_lossy(e.as_bytes()).into_owned();
+                                consumer.consume_with_span(Ok(string_token(s, raw)), span!())?;
+                            }
+                            InvalidUtf8Policy::Bytes => {
+                                consumer.consume_with_span(Ok(LexerToken::Bytes(e.into_bytes())), span!())?;
+                            }
+                        }
+                    }
+                } }
+            }};
+        }
+
         let mut bytes = [0u8; 4];
 
         macro_rules! try_to_append_code_point {
@@ -176,7 +896,7 @@ impl<R: Read> JSONLexer<R> {
                             let utf8_bytes = REPLACEMENT_CHARACTER.encode_utf8(&mut bytes);
                             $buf.append(&mut utf8_bytes.as_bytes().to_vec());
                         } else {
-                            consume_lex_error!("This is not a code point `{}`", $code_point);
+                            consume_lex_error!(LexErrorKind::InvalidCodePoint($code_point), "This is not a code point `{}`", $code_point);
                         }
                     }
                 }
@@ -189,439 +909,888 @@ impl<R: Read> JSONLexer<R> {
                     let utf8_bytes = REPLACEMENT_CHARACTER.encode_utf8(&mut bytes);
                     $buf.append(&mut utf8_bytes.as_bytes().to_vec());
                 } else {
-                    consume_lex_error!("This is not a code point `{}`", $code_point)
+                    consume_lex_error!(LexErrorKind::InvalidCodePoint($code_point), "This is not a code point `{}`", $code_point)
+                }
+            }};
+        }
+
+
+        let LexCursor {
+            mut state,
+            mut expect,
+            mut expected_index,
+            mut number_sub_state,
+            mut string_sub_state,
+            mut buf,
+            mut code_point,
+            mut unicode_index,
+            mut high,
+            mut skip_number,
+            mut token_start,
+            mut quote,
+            mut chunked,
+            mut utf8_checked,
+            mut utf8_error,
+            mut wtf8,
+        } = std::mem::take(&mut self.cursor);
+        let json5 = self.options.dialect == Dialect::Json5;
+        let comments = json5 || self.options.allow_comments;
+
+        // a surrogate that is not part of a pair, if the policy is not `Error`
+        macro_rules! push_lone_surrogate {
+            ($surrogate:expr) => {{
+                if self.options.surrogates == SurrogatePolicy::PreserveWtf8 {
+                    let surrogate: u32 = $surrogate;
+                    buf.extend_from_slice(&[0xE0 | (surrogate >> 12) as u8, 0x80 | ((surrogate >> 6) & 0x3F) as u8, 0x80 | (surrogate & 0x3F) as u8]);
+                    // not UTF-8
+                    utf8_checked = buf.len();
+                    wtf8 = true;
+                } else {
+                    buf.extend_from_slice(REPLACEMENT_CHARACTER.encode_utf8(&mut bytes).as_bytes());
+                }
+            }};
+        }
+        let single_quotes = json5 || self.options.single_quotes;
+
+        macro_rules! span {
+            () => {
+                Span { start: token_start, end: self.byte_source.offset(), line: self.line, column: self.column }
+            };
+        }
+
+        // a number ends before the byte that follows it
+        macro_rules! number_span {
+            () => {
+                Span { end: self.byte_source.offset() - byte.is_some() as usize, ..span!() }
+            };
+        }
+
+        macro_rules! push_number_byte {
+            ($byte: expr) => {{
+                if !skip_number {
+                    buf.push($byte);
+                }
+            }};
+        }
+
+        macro_rules! consume_number {
+            ($token_variant: ident) => {{
+                if skip_number {
+                    consumer.consume_with_span(Ok(LexerToken::$token_variant(String::new())), number_span!())?;
+                } else {
+                    consume_buf!(buf, $token_variant);
                 }
             }};
         }
 
+        // the buffer holds the sign and the hex digits
+        macro_rules! consume_hex {
+            () => {{
+                if skip_number {
+                    consumer.consume_with_span(Ok(LexerToken::IntValue(String::new())), number_span!())?;
+                } else {
+                    let s = String::from_utf8(buf).unwrap();
+                    let (sign, digits) = s.split_at(s.starts_with('-') as usize);
+                    let n = match u128::from_str_radix(digits, 16) {
+                        Ok(n) => format!("{}{}", sign, n),
+                        Err(_) => format!("{}0x{}", sign, digits), // too large
+                    };
+                    consumer.consume_with_span(Ok(LexerToken::IntValue(n)), number_span!())?;
+                }
+            }};
+        }
 
-        consumer.consume(Ok(BeginFile), self.line, self.column)?;
+        macro_rules! consume_comment {
+            () => {{
+                if self.options.emit_comments {
+                    let mut text = String::from_utf8_lossy(&buf).into_owned();
+                    if matches!(state, LexerState::LineComment) && text.ends_with('\r') {
+                        text.pop();
+                    }
+                    if self.options.raw_strings {
+                        text = if matches!(state, LexerState::LineComment) { format!("//{}", text) } else { format!("/*{}*/", text) };
+                    }
+                    consumer.consume_with_span(Ok(LexerToken::Comment(text)), span!())?;
+                }
+            }};
+        }
 
-        let mut state: LexerState = LexerState::None;
-        let mut expect: &[u8; 4] = &[1u8, 2u8, 3u8, 4u8];
-        let mut expected_index: usize = 0;
-        let mut number_sub_state: LexerNumberSubState = LexerNumberSubState::None;
-        let mut string_sub_state: LexerStringSubState = LexerStringSubState::None;
-        let mut buf: Vec<u8> = vec!();
-        let mut code_point: u32 = 0;
-        let mut unicode_index: usize = 0;
-        let mut high: u32 = 0;
+        macro_rules! consume_identifier {
+            () => {{
+                let word = String::from_utf8(buf).unwrap();
+                let token = match word.as_str() {
+                    "true" => Ok(LexerToken::BooleanValue(true)),
+                    "false" => Ok(LexerToken::BooleanValue(false)),
+                    "null" => Ok(LexerToken::NullValue),
+                    "Infinity" | "-Infinity" | "NaN" | "-NaN" => Ok(LexerToken::FloatValue(word)),
+                    _ if word.starts_with('-') => lex_error!(LexErrorKind::ExpectedWord("Infinity".into()), "Expected word `Infinity` or `NaN`, got `{}`", word),
+                    _ => Ok(LexerToken::String(word)),
+                };
+                consumer.consume_with_span(token, number_span!())?;
+            }};
+        }
 
-        while let Some(byte) = self.byte_source.get() {
+        if let Some(byte) = byte {
+            if let Some(max) = self.options.limits.max_document_bytes {
+                if self.byte_source.offset() > max {
+                    return self.limit_exceeded(consumer, format!("Document longer than {} bytes", max));
+                }
+            }
             self.column += 1;
             if byte == b'\n' {
                 self.line += 1;
-            } else {
-                match state {
-                    LexerState::None => {
-                        match byte {
-                            b' ' | b'\t' | b'\r' => {} // pass
-                            b'f' => {
-                                expect = &[b'a', b'l', b's', b'e'];
-                                state = LexerState::Expect(LexerToken::BooleanValue(false));
-                                expected_index = 0;
-                            }
-                            b't' => {
-                                expect = &[0u8, b'r', b'u', b'e'];
-                                state = LexerState::Expect(LexerToken::BooleanValue(true));
-                                expected_index = 1;
+            }
+            match state {
+                LexerState::None => {
+                    token_start = self.byte_source.offset() - 1;
+                    match byte {
+                        b' ' | b'\t' | b'\r' | b'\n' => {} // pass
+                        b'f' | b't' | b'n' if !json5 && self.peek_literal(byte) => {
+                            let (rest, token) = literal_rest(byte);
+                            // the bytes were peeked
+                            if let Err(e) = self.byte_source.consume(rest.len()) {
+                                return self.read_failed(consumer, e);
                             }
-                            b'n' => {
-                                expect = &[0u8, b'u', b'l', b'l'];
-                                state = LexerState::Expect(LexerToken::NullValue);
-                                expected_index = 1;
+                            self.column += rest.len();
+                            consumer.consume_with_span(Ok(token), span!())?;
+                        }
+                        b'f' if !json5 => {
+                            expect = &[b'a', b'l', b's', b'e'];
+                            state = LexerState::Expect(LexerToken::BooleanValue(false));
+                            expected_index = 0;
+                        }
+                        b't' if !json5 => {
+                            expect = &[0u8, b'r', b'u', b'e'];
+                            state = LexerState::Expect(LexerToken::BooleanValue(true));
+                            expected_index = 1;
+                        }
+                        b'n' if !json5 => {
+                            expect = &[0u8, b'u', b'l', b'l'];
+                            state = LexerState::Expect(LexerToken::NullValue);
+                            expected_index = 1;
+                        }
+                        b'{' => {
+                            consumer.consume_with_span(Ok(LexerToken::BeginObject), span!())?;
+                        }
+                        b'}' => {
+                            consumer.consume_with_span(Ok(LexerToken::EndObject), span!())?;
+                        }
+                        b'[' => {
+                            consumer.consume_with_span(Ok(LexerToken::BeginArray), span!())?;
+                        }
+                        b']' => {
+                            consumer.consume_with_span(Ok(LexerToken::EndArray), span!())?;
+                        }
+                        b':' => {
+                            consumer.consume_with_span(Ok(LexerToken::NameSeparator), span!())?;
+                        }
+                        b',' => {
+                            consumer.consume_with_span(Ok(LexerToken::ValueSeparator), span!())?;
+                        }
+                        b'-' => {
+                            skip_number = consumer.skip_value();
+                            state = LexerState::Number;
+                            number_sub_state = LexerNumberSubState::NegNumberStart;
+                            buf = vec!(b'-');
+                        }
+                        b'+' if json5 => {
+                            skip_number = consumer.skip_value();
+                            state = LexerState::Number;
+                            number_sub_state = LexerNumberSubState::NegNumberStart;
+                            buf = vec!();
+                        }
+                        b'.' if json5 => {
+                            skip_number = consumer.skip_value();
+                            state = LexerState::Number;
+                            number_sub_state = LexerNumberSubState::NumberDotStart;
+                            buf = b"0.".to_vec();
+                        }
+                        b'0' => {
+                            skip_number = consumer.skip_value();
+                            state = LexerState::Number;
+                            number_sub_state = LexerNumberSubState::ZeroNumberStart;
+                            buf = vec!(b'0');
+                        }
+                        b'"' | b'\'' if (byte == b'"' || single_quotes) && consumer.skip_value() => {
+                            quote = byte;
+                            state = LexerState::SkippedString(false);
+                        }
+                        b'"' | b'\'' if byte == b'"' || single_quotes => {
+                            quote = byte;
+                            state = LexerState::String;
+                            string_sub_state = LexerStringSubState::None;
+                            buf = vec!();
+                            if self.options.raw_strings && self.options.string_chunk_size.is_none() {
+                                self.byte_source.start_recording();
                             }
-                            b'{' => {
-                                consumer.consume(Ok(LexerToken::BeginObject), self.line, self.column)?;
+                        }
+                        _ if b'1' <= byte && byte <= b'9' => {
+                            skip_number = consumer.skip_value();
+                            state = LexerState::Number;
+                            number_sub_state = LexerNumberSubState::OtherNumber;
+                            buf = vec!(byte);
+                        }
+                        b'/' if comments => {
+                            state = LexerState::CommentStart;
+                            buf = vec!();
+                        }
+                        _ if json5 && is_identifier_start(byte) => {
+                            state = LexerState::Identifier;
+                            buf = vec!(byte);
+                        }
+                        _ => {
+                            consume_lex_error!(LexErrorKind::UnexpectedChar(byte as char), "Unexpected char `{}`", byte as char);
+                        }
+                    }
+                }
+                LexerState::Expect(ref token) if expected_index < expect.len() => {
+                    if expect[expected_index] == byte {
+                        expected_index += 1;
+                    } else {
+                        consume_lex_error!(LexErrorKind::ExpectedWord(str::from_utf8(expect).unwrap().into()), "Expected word `{}`", str::from_utf8(expect).unwrap());
+                        state = LexerState::None
+                    }
+                }
+                LexerState::Expect(token) if expected_index == expect.len() => {
+                    unget!();
+                    expected_index = 0;
+                    consumer.consume_with_span(Ok(token), span!())?;
+                    state = LexerState::None;
+                }
+                LexerState::Number => {  // 6. Numbers
+                    match number_sub_state {
+                        LexerNumberSubState::NegNumberStart => { // -...
+                            match byte {
+                                b'0' => {
+                                    push_number_byte!(b'0');
+                                    number_sub_state = LexerNumberSubState::ZeroNumberStart;
+                                }
+                                _ if b'1' <= byte && byte <= b'9' => {
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::OtherNumber;
+                                }
+                                b'.' if json5 => {
+                                    push_number_byte!(b'0');
+                                    push_number_byte!(b'.');
+                                    number_sub_state = LexerNumberSubState::NumberDotStart;
+                                }
+                                b'I' | b'N' if json5 => {
+                                    buf.push(byte);
+                                    number_sub_state = LexerNumberSubState::None;
+                                    state = LexerState::Identifier;
+                                }
+                                _ => {
+                                    consume_lex_error!(LexErrorKind::ExpectedDigit(byte as char), "Expected a digit `{}`", byte as char);
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
                             }
-                            b'}' => {
-                                consumer.consume(Ok(LexerToken::EndObject), self.line, self.column)?;
+                        }
+                        LexerNumberSubState::ZeroNumberStart => { // -?0
+                            match byte {
+                                b'.' => {
+                                    push_number_byte!(b'.');
+                                    number_sub_state = LexerNumberSubState::NumberFracStart;
+                                }
+                                b'e' | b'E' => {
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::NumberFracExpStart;
+                                }
+                                b'x' | b'X' if json5 => {
+                                    buf.pop();
+                                    number_sub_state = LexerNumberSubState::HexStart;
+                                }
+                                _ if self.options.strict && byte.is_ascii_digit() => {
+                                    consume_lex_error!(LexErrorKind::LeadingZero, "Leading zero in `0{}`", byte as char);
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
+                                _ if self.options.raw_strings => {
+                                    consume_number!(IntValue);
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
+                                _ => {
+                                    consumer.consume_with_span(Ok(LexerToken::IntValue("0".into())), number_span!())?;
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
                             }
-                            b'[' => {
-                                consumer.consume(Ok(LexerToken::BeginArray), self.line, self.column)?;
+                        }
+                        LexerNumberSubState::OtherNumber => { // -?[1-9]
+                            match byte {
+                                b'.' => {
+                                    push_number_byte!(b'.');
+                                    number_sub_state = LexerNumberSubState::NumberFracStart;
+                                }
+                                b'e' | b'E' => {
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::NumberFracExpStart;
+                                }
+                                _ if b'0' <= byte && byte <= b'9' => {
+                                    push_number_byte!(byte);
+                                }
+                                _ => {
+                                    consume_number!(IntValue);
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
                             }
-                            b']' => {
-                                consumer.consume(Ok(LexerToken::EndArray), self.line, self.column)?;
+                        }
+                        LexerNumberSubState::NumberFracStart => { // -?[0-9][1-9]*\.
+                            match byte {
+                                _ if byte.is_ascii_digit() => {
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::NumberFrac;
+                                }
+                                // JSON5: `1.` is `1.0`
+                                b'e' | b'E' if json5 => {
+                                    push_number_byte!(b'0');
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::NumberFracExpStart;
+                                }
+                                _ if json5 => {
+                                    push_number_byte!(b'0');
+                                    consume_number!(FloatValue);
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
+                                _ => {
+                                    consume_lex_error!(LexErrorKind::MissingDecimals, "Missing decimals `{}`", String::from_utf8(buf).unwrap());
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
                             }
-                            b':' => {
-                                consumer.consume(Ok(LexerToken::NameSeparator), self.line, self.column)?;
+                        }
+                        LexerNumberSubState::NumberDotStart => { // [+-]?\.
+                            match byte {
+                                _ if b'0' <= byte && byte <= b'9' => {
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::NumberFrac;
+                                }
+                                _ => {
+                                    consume_lex_error!(LexErrorKind::MissingDecimals, "Missing decimals `{}`", String::from_utf8(buf).unwrap());
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
                             }
-                            b',' => {
-                                consumer.consume(Ok(LexerToken::ValueSeparator), self.line, self.column)?;
+                        }
+                        LexerNumberSubState::HexStart => { // [+-]?0x
+                            match byte {
+                                _ if byte.is_ascii_hexdigit() => {
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::Hex;
+                                }
+                                _ => {
+                                    consume_lex_error!(LexErrorKind::MissingDigits, "Missing digits `{}0x`", String::from_utf8(buf).unwrap());
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
                             }
-                            b'-' => {
-                                state = LexerState::Number;
-                                number_sub_state = LexerNumberSubState::NegNumberStart;
-                                buf = vec!(b'-');
+                        }
+                        LexerNumberSubState::Hex => { // [+-]?0x[0-9a-fA-F]+
+                            match byte {
+                                _ if byte.is_ascii_hexdigit() => {
+                                    push_number_byte!(byte);
+                                }
+                                _ => {
+                                    consume_hex!();
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
                             }
-                            b'0' => {
-                                state = LexerState::Number;
-                                number_sub_state = LexerNumberSubState::ZeroNumberStart;
-                                buf = vec!(b'0');
+                        }
+                        LexerNumberSubState::NumberFrac => { // -?[0-9][1-9]*\.[0-9]+
+                            match byte {
+                                b'e' | b'E' => {
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::NumberFracExpStart;
+                                }
+                                _ if b'0' <= byte && byte <= b'9' => {
+                                    push_number_byte!(byte);
+                                }
+                                _ => {
+                                    consume_number!(FloatValue);
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
                             }
-                            b'"' => {
-                                state = LexerState::String;
-                                string_sub_state = LexerStringSubState::None;
-                                buf = vec!();
+                        }
+                        LexerNumberSubState::NumberFracExpStart => { // -?[0-9][1-9](*\.[0-9]+)?e
+                            match byte {
+                                b'-' | b'+' => {
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::NumberFracExpMinusStart;
+                                }
+                                _ if b'0' <= byte && byte <= b'9' => {
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::NumberFracExp;
+                                }
+                                _ => {
+                                    consume_lex_error!(LexErrorKind::MissingExponent, "Missing exp `{}`", String::from_utf8(buf).unwrap());
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
                             }
-                            _ if b'1' <= byte && byte <= b'9' => {
-                                state = LexerState::Number;
-                                number_sub_state = LexerNumberSubState::OtherNumber;
-                                buf = vec!(byte);
+                        }
+                        LexerNumberSubState::NumberFracExp => { // -?[0-9][1-9](*\.[0-9]+)?e[0-9]+
+                            match byte {
+                                _ if b'0' <= byte && byte <= b'9' => {
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::NumberFracExp;
+                                }
+                                _ => {
+                                    consume_number!(FloatValue);
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
                             }
-                            _ => {
-                                consume_lex_error!("Unexpected char `{}`", byte as char);
+                        }
+                        LexerNumberSubState::NumberFracExpMinusStart => { // -?[0-9][1-9](*\.[0-9]+)?e[+-]
+                            match byte {
+                                _ if b'0' <= byte && byte <= b'9' => {
+                                    push_number_byte!(byte);
+                                    number_sub_state = LexerNumberSubState::NumberFracExpMinus;
+                                }
+                                _ => {
+                                    consume_lex_error!(LexErrorKind::MissingExponent, "Missing exp `{}`", String::from_utf8(buf).unwrap());
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
                             }
                         }
-                    }
-                    LexerState::Expect(ref token) if expected_index < expect.len() => {
-                        if expect[expected_index] == byte {
-                            expected_index += 1;
-                        } else {
-                            consume_lex_error!("Expected word `{}`", str::from_utf8(expect).unwrap());
-                            state = LexerState::None
+                        LexerNumberSubState::NumberFracExpMinus => { // -?[0-9][1-9](*\.[0-9]+)?e[+-][0-9]+
+                            match byte {
+                                _ if b'0' <= byte && byte <= b'9' => {
+                                    push_number_byte!(byte);
+                                }
+                                _ => {
+                                    consume_number!(FloatValue);
+                                    end_of_number!(buf, number_sub_state, state);
+                                }
+                            }
                         }
+                        LexerNumberSubState::None => { panic!() }
                     }
-                    LexerState::Expect(token) if expected_index == expect.len() => {
-                        self.byte_source.unget();
-                        expected_index = 0;
-                        consumer.consume(Ok(token), self.line, self.column)?;
-                        state = LexerState::None;
-                    }
-                    LexerState::Number => {  // 6. Numbers
-                        match number_sub_state {
-                            LexerNumberSubState::NegNumberStart => { // -...
+                }
+                LexerState::String => { //  7. Strings
+                    if high == 0 {
+                        match string_sub_state {
+                            LexerStringSubState::Escape => {
                                 match byte {
-                                    b'0' => {
-                                        buf.push(b'0');
-                                        number_sub_state = LexerNumberSubState::ZeroNumberStart;
+                                    b'"' | b'\\' | b'/' => {
+                                        buf.push(byte);
+                                        string_sub_state = LexerStringSubState::None;
                                     }
-                                    _ if b'1' <= byte && byte <= b'9' => {
+                                    b'\'' if single_quotes => {
                                         buf.push(byte);
-                                        number_sub_state = LexerNumberSubState::OtherNumber;
+                                        string_sub_state = LexerStringSubState::None;
                                     }
-                                    _ => {
-                                        consume_lex_error!("Expected a digit `{}`", byte as char);
-                                        end_of_number!(buf, number_sub_state, state);
+                                    b'\n' if json5 => {
+                                        string_sub_state = LexerStringSubState::None;
                                     }
-                                }
-                            }
-                            LexerNumberSubState::ZeroNumberStart => { // -?0
-                                match byte {
-                                    b'.' => {
-                                        buf.push(b'.');
-                                        number_sub_state = LexerNumberSubState::NumberFracStart;
+                                    b'\r' if json5 => {
+                                        string_sub_state = LexerStringSubState::LineContinuation;
                                     }
-                                    b'e' | b'E' => {
-                                        buf.push(b'e');
-                                        number_sub_state = LexerNumberSubState::NumberFracExpStart;
+                                    b'b' => {
+                                        buf.push(0x08);
+                                        string_sub_state = LexerStringSubState::None;
                                     }
-                                    _ => {
-                                        consumer.consume(Ok(LexerToken::IntValue("0".into())), self.line, self.column)?;
-                                        end_of_number!(buf, number_sub_state, state);
+                                    b'f' => {
+                                        buf.push(0x0C);
+                                        string_sub_state = LexerStringSubState::None;
                                     }
-                                }
-                            }
-                            LexerNumberSubState::OtherNumber => { // -?[1-9]
-                                match byte {
-                                    b'.' => {
-                                        buf.push(b'.');
-                                        number_sub_state = LexerNumberSubState::NumberFracStart;
+                                    b'n' => {
+                                        buf.push(b'\n');
+                                        string_sub_state = LexerStringSubState::None;
                                     }
-                                    b'e' | b'E' => {
-                                        buf.push(b'e');
-                                        number_sub_state = LexerNumberSubState::NumberFracExpStart;
+                                    b'r' => {
+                                        buf.push(b'\r');
+                                        string_sub_state = LexerStringSubState::None;
                                     }
-                                    _ if b'0' <= byte && byte <= b'9' => {
-                                        buf.push(byte);
+                                    b't' => {
+                                        buf.push(b'\t');
+                                        string_sub_state = LexerStringSubState::None;
+                                    }
+                                    b'u' => {
+                                        string_sub_state = LexerStringSubState::Unicode;
+                                        code_point = 0u32;
+                                        unicode_index = 0;
                                     }
                                     _ => {
-                                        consume_buf!(buf, IntValue);
-                                        end_of_number!(buf, number_sub_state, state);
+                                        consume_lex_error!(LexErrorKind::UnknownEscape(byte as char), "Unknown escaped char `{}`", byte as char);
                                     }
                                 }
                             }
-                            LexerNumberSubState::NumberFracStart => { // -?[0-9][1-9]*\.
-                                match byte {
-                                    _ if b'0' <= byte && byte <= b'9' => {
-                                        buf.push(byte);
-                                        number_sub_state = LexerNumberSubState::NumberFrac;
+                            LexerStringSubState::Unicode => { // \u was seen
+                                if unicode_index <= 3 {
+                                    let n = self.parse_hex(byte);
+                                    match n {
+                                        Ok(i) => {
+                                            code_point = code_point * 16 + i;
+                                            unicode_index += 1;
+                                        }
+                                        Err(e) => {
+                                            end_of_unicode!(code_point, unicode_index, string_sub_state);
+                                            consumer.consume_with_span(Err(e), span!())?;
+                                        }
                                     }
-                                    _ => {
-                                        consume_lex_error!("Missing decimals `{}`", String::from_utf8(buf).unwrap());
-                                        end_of_number!(buf, number_sub_state, state);
+                                }
+                                if unicode_index == 4 {
+                                    // high surrogate
+                                    if 0xd800 <= code_point && code_point <= 0xdbff {
+                                        high = code_point;
+                                    // low surrogate
+                                    } else if 0xdc00 <= code_point && code_point <= 0xdfff {
+                                        if self.options.surrogates == SurrogatePolicy::Error {
+                                            replacement_char_or_err!(buf, code_point);
+                                        } else {
+                                            push_lone_surrogate!(code_point);
+                                        }
+                                    } else {
+                                        try_to_append_code_point!(buf, code_point);
                                     }
+                                    end_of_unicode!(code_point, unicode_index, string_sub_state);
                                 }
                             }
-                            LexerNumberSubState::NumberFrac => { // -?[0-9][1-9]*\.[0-9]+
-                                match byte {
-                                    b'e' | b'E' => {
-                                        buf.push(b'e');
-                                        number_sub_state = LexerNumberSubState::NumberFracExpStart;
-                                    }
-                                    _ if b'0' <= byte && byte <= b'9' => {
-                                        buf.push(byte);
-                                    }
-                                    _ => {
-                                        consume_buf!(buf, FloatValue);
-                                        end_of_number!(buf, number_sub_state, state);
-                                    }
+                            LexerStringSubState::LineContinuation => {
+                                if byte != b'\n' {
+                                    unget!();
                                 }
+                                string_sub_state = LexerStringSubState::None;
                             }
-                            LexerNumberSubState::NumberFracExpStart => { // -?[0-9][1-9](*\.[0-9]+)?e
+                            LexerStringSubState::None => {
                                 match byte {
-                                    b'-' => {
-                                        buf.push(b'-');
-                                        number_sub_state = LexerNumberSubState::NumberFracExpMinusStart;
+                                    b'\\' => { string_sub_state = LexerStringSubState::Escape }
+                                    _ if byte == quote && chunked && utf8_error.is_some() => {
+                                        // the chunks that were sent are not valid
+                                        if let Some((line, column, invalid)) = utf8_error {
+                                            let error = JSONLexError { kind: LexErrorKind::InvalidUtf8, msg: format!("Invalid UTF-8 byte `0x{:02x}` in string", invalid), line, column };
+                                            consumer.consume_with_span(Err(error), span!())?;
+                                        }
+                                        chunked = false;
+                                        end_of_string!(buf, string_sub_state, state);
                                     }
-                                    _ if b'0' <= byte && byte <= b'9' => {
-                                        buf.push(byte);
-                                        number_sub_state = LexerNumberSubState::NumberFracExp;
+                                    _ if byte == quote && chunked => {
+                                        if !buf.is_empty() {
+                                            consumer.consume_with_span(Ok(LexerToken::StringChunk(buf)), span!())?;
+                                        }
+                                        consumer.consume_with_span(Ok(LexerToken::StringEnd), span!())?;
+                                        chunked = false;
+                                        end_of_string!(buf, string_sub_state, state);
                                     }
-                                    _ => {
-                                        consume_lex_error!("Missing exp `{}`", String::from_utf8(buf).unwrap());
-                                        end_of_number!(buf, number_sub_state, state);
+                                    _ if byte == quote => {
+                                        consume_string!(buf, utf8_error, wtf8, quote);
+                                        end_of_string!(buf, string_sub_state, state);
                                     }
-                                }
-                            }
-                            LexerNumberSubState::NumberFracExp => { // -?[0-9][1-9](*\.[0-9]+)?e[0-9]+
-                                match byte {
-                                    _ if b'0' <= byte && byte <= b'9' => {
-                                        buf.push(byte);
-                                        number_sub_state = LexerNumberSubState::NumberFracExp;
+                                    _ if self.options.strict && byte < b' ' => {
+                                        consume_lex_error!(LexErrorKind::ControlChar(byte), "Unescaped control char `0x{:02x}` in string", byte);
                                     }
                                     _ => {
-                                        consume_buf!(buf, FloatValue);
-                                        end_of_number!(buf, number_sub_state, state);
-                                    }
-                                }
-                            }
-                            LexerNumberSubState::NumberFracExpMinusStart => { // -?[0-9][1-9](*\.[0-9]+)?e-
-                                match byte {
-                                    _ if b'0' <= byte && byte <= b'9' => {
                                         buf.push(byte);
-                                        number_sub_state = LexerNumberSubState::NumberFracExpMinus;
-                                    }
-                                    _ => {
-                                        consume_lex_error!("Missing exp `{}`", String::from_utf8(buf).unwrap());
-                                        end_of_number!(buf, number_sub_state, state);
+                                        // copy the following plain bytes at once
+                                        let max_run = self.max_string_run(buf.len());
+                                        let run = match self.byte_source.peek_slice() {
+                                            Ok(run) => run,
+                                            Err(e) => return self.read_failed(consumer, e),
+                                        };
+                                        let n = run.iter().take(max_run)
+                                            .position(|&b| b == quote || b == b'\\' || b < b' ')
+                                            .unwrap_or_else(|| run.len().min(max_run));
+                                        buf.extend_from_slice(&run[..n]);
+                                        if let Err(e) = self.byte_source.consume(n) {
+                                            return self.read_failed(consumer, e);
+                                        }
+                                        self.column += n;
                                     }
                                 }
                             }
-                            LexerNumberSubState::NumberFracExpMinus => { // -?[0-9][1-9](*\.[0-9]+)?e-[0-9]+
+                        }
+                    } else {
+                        match string_sub_state {
+                            LexerStringSubState::Escape => {
                                 match byte {
-                                    _ if b'0' <= byte && byte <= b'9' => {
-                                        buf.push(byte);
+                                    b'u' => {
+                                        string_sub_state = LexerStringSubState::Unicode;
+                                        code_point = 0u32;
+                                        unicode_index = 0;
                                     }
                                     _ => {
-                                        consume_buf!(buf, FloatValue);
-                                        end_of_number!(buf, number_sub_state, state);
+                                        if self.options.surrogates == SurrogatePolicy::Error {
+                                            consume_lex_error!(LexErrorKind::LoneSurrogate, "Waiting for low surrogate: needs \\u, got `\\{}`", byte as char);
+                                        } else {
+                                            push_lone_surrogate!(high);
+                                        }
+                                        unget!();
+                                        high = 0;
                                     }
                                 }
                             }
-                            LexerNumberSubState::None => { panic!() }
-                        }
-                    }
-                    LexerState::String => { //  7. Strings
-                        if high == 0 {
-                            match string_sub_state {
-                                LexerStringSubState::Escape => {
-                                    match byte {
-                                        b'"' | b'\\' => {
-                                            buf.push(byte);
-                                            string_sub_state = LexerStringSubState::None;
-                                        }
-                                        b'b' => {
-                                            buf.push(0x08);
-                                            string_sub_state = LexerStringSubState::None;
-                                        }
-                                        b'f' => {
-                                            buf.push(0x0C);
-                                            string_sub_state = LexerStringSubState::None;
+                            LexerStringSubState::Unicode => { // \u was seen
+                                if unicode_index <= 3 {
+                                    let n = self.parse_hex(byte);
+                                    match n {
+                                        Ok(i) => {
+                                            code_point = code_point * 16 + i;
+                                            unicode_index += 1;
                                         }
-                                        b'n' => {
-                                            buf.push(b'\n');
-                                            string_sub_state = LexerStringSubState::None;
-                                        }
-                                        b'r' => {
-                                            buf.push(b'\r');
-                                            string_sub_state = LexerStringSubState::None;
-                                        }
-                                        b't' => {
-                                            buf.push(b'\t');
-                                            string_sub_state = LexerStringSubState::None;
-                                        }
-                                        b'u' => {
-                                            string_sub_state = LexerStringSubState::Unicode;
-                                            code_point = 0u32;
-                                            unicode_index = 0;
-                                        }
-                                        _ => {
-                                            consume_lex_error!("Unknown escaped char `{}`", byte as char);
+                                        Err(e) => {
+                                            end_of_unicode!(code_point, unicode_index, string_sub_state);
+                                            consumer.consume_with_span(Err(e), span!())?;
                                         }
                                     }
                                 }
-                                LexerStringSubState::Unicode => { // \u was seen
-                                    if unicode_index <= 3 {
-                                        let n = self.parse_hex(byte);
-                                        match n {
-                                            Ok(i) => {
-                                                code_point = code_point * 16 + i;
-                                                unicode_index += 1;
-                                            }
-                                            Err(e) => {
-                                                end_of_unicode!(code_point, unicode_index, string_sub_state);
-                                                consumer.consume(Err(e), self.line, self.column)?;
-                                            }
+                                if unicode_index == 4 {
+                                    // low surrogate
+                                    if 0xdc00 <= code_point && code_point <= 0xdfff {
+                                        code_point = 0x10000 + (high - 0xd800) * 0x400 + code_point - 0xdc00;
+                                        try_to_append_code_point!(buf, code_point);
+                                        high = 0;
+                                    } else {
+                                        if self.options.surrogates == SurrogatePolicy::Error {
+                                            consume_lex_error!(LexErrorKind::LoneSurrogate, "Waiting for low surrogate, got `{}`", code_point);
+                                        } else {
+                                            push_lone_surrogate!(high);
                                         }
-                                    }
-                                    if unicode_index == 4 {
-                                        // high surrogate
-                                        if 0xd800 <= code_point && code_point <= 0xdbff {
+                                        // the second escape is not lost
+                                        if (0xd800..=0xdbff).contains(&code_point) {
                                             high = code_point;
-                                        // low surrogate
-                                        } else if 0xdc00 <= code_point && code_point <= 0xdfff {
-                                            replacement_char_or_err!(buf, code_point);
                                         } else {
+                                            high = 0;
                                             try_to_append_code_point!(buf, code_point);
                                         }
-                                        end_of_unicode!(code_point, unicode_index, string_sub_state);
-                                    }
-                                }
-                                LexerStringSubState::None => {
-                                    match byte {
-                                        b'\\' => { string_sub_state = LexerStringSubState::Escape }
-                                        b'"' => {
-                                            consume_buf!(buf, String);
-                                            end_of_string!(buf, string_sub_state, state);
-                                        }
-                                        _ => {
-                                            buf.push(byte);
-                                        }
                                     }
+                                    end_of_unicode!(code_point, unicode_index, string_sub_state);
                                 }
                             }
-                        } else {
-                            match string_sub_state {
-                                LexerStringSubState::Escape => {
-                                    match byte {
-                                        b'u' => {
-                                            string_sub_state = LexerStringSubState::Unicode;
-                                            code_point = 0u32;
-                                            unicode_index = 0;
-                                        }
-                                        _ => {
-                                            consume_lex_error!("Waiting for low surrogate: needs \\u, got `\\{}`", byte as char);
-                                            self.byte_source.unget();
-                                            high = 0;
-                                        }
-                                    }
-                                }
-                                LexerStringSubState::Unicode => { // \u was seen
-                                    if unicode_index <= 3 {
-                                        let n = self.parse_hex(byte);
-                                        match n {
-                                            Ok(i) => {
-                                                code_point = code_point * 16 + i;
-                                                unicode_index += 1;
-                                            }
-                                            Err(e) => {
-                                                end_of_unicode!(code_point, unicode_index, string_sub_state);
-                                                consumer.consume(Err(e), self.line, self.column)?;
-                                            }
-                                        }
-                                    }
-                                    if unicode_index == 4 {
-                                        // low surrogate
-                                        if 0xdc00 <= code_point && code_point <= 0xdfff {
-                                            code_point = 0x10000 + (high - 0xd800) * 0x400 + code_point - 0xdc00;
-                                            try_to_append_code_point!(buf, code_point);
+                            LexerStringSubState::None => {
+                                match byte {
+                                    b'\\' => { string_sub_state = LexerStringSubState::Escape }
+                                    _ => {
+                                        if self.options.surrogates == SurrogatePolicy::Error {
+                                            consume_lex_error!(LexErrorKind::LoneSurrogate, "Waiting for low surrogate: needs backslash, got `{}`", byte as char);
                                         } else {
-                                            consume_lex_error!("Waiting for low surrogate, got `{}`", code_point);
-                                            let utf8_bytes = REPLACEMENT_CHARACTER.encode_utf8(&mut bytes);
-                                            buf.append(&mut utf8_bytes.as_bytes().to_vec());
+                                            push_lone_surrogate!(high);
                                         }
+                                        unget!();
                                         high = 0;
-                                        end_of_unicode!(code_point, unicode_index, string_sub_state);
-                                    }
-                                }
-                                LexerStringSubState::None => {
-                                    match byte {
-                                        b'\\' => { string_sub_state = LexerStringSubState::Escape }
-                                        _ => {
-                                            consume_lex_error!("Waiting for low surrogate: needs backslash, got `{}`", byte as char);
-                                            self.byte_source.unget();
-                                            high = 0;
-                                        }
                                     }
                                 }
                             }
+                            LexerStringSubState::LineContinuation => {
+                                // a line continuation resets `high`
+                                unget!();
+                                string_sub_state = LexerStringSubState::None;
+                            }
                         }
                     }
-                    _ => {}
                 }
+                LexerState::SkippedString(true) => {
+                    state = LexerState::SkippedString(false);
+                }
+                LexerState::SkippedString(false) => {
+                    match byte {
+                        b'\\' => { state = LexerState::SkippedString(true) }
+                        _ if byte == quote => {
+                            consumer.consume_with_span(Ok(LexerToken::SkippedString), span!())?;
+                            state = LexerState::None;
+                        }
+                        _ if self.options.strict && byte < b' ' => {
+                            consume_lex_error!(LexErrorKind::ControlChar(byte), "Unescaped control char `0x{:02x}` in string", byte);
+                        }
+                        _ => {}
+                    }
+                }
+                LexerState::CommentStart => {
+                    match byte {
+                        b'/' => { state = LexerState::LineComment }
+                        b'*' => { state = LexerState::BlockComment(false) }
+                        _ => {
+                            consume_lex_error!(LexErrorKind::UnexpectedChar('/'), "Unexpected char `/`");
+                            unget!();
+                            state = LexerState::None;
+                        }
+                    }
+                }
+                LexerState::LineComment => {
+                    if byte == b'\n' {
+                        consume_comment!();
+                        state = LexerState::None;
+                    } else if self.options.emit_comments {
+                        buf.push(byte);
+                    }
+                }
+                LexerState::BlockComment(star) => {
+                    if star && byte == b'/' {
+                        consume_comment!();
+                        state = LexerState::None;
+                    } else {
+                        if star && self.options.emit_comments {
+                            buf.push(b'*');
+                        }
+                        if byte == b'*' {
+                            state = LexerState::BlockComment(true);
+                        } else {
+                            if self.options.emit_comments {
+                                buf.push(byte);
+                            }
+                            state = LexerState::BlockComment(false);
+                        }
+                    }
+                }
+                LexerState::Identifier => {
+                    if is_identifier_part(byte) {
+                        buf.push(byte);
+                    } else {
+                        consume_identifier!();
+                        buf = vec!();
+                        unget!();
+                        state = LexerState::None;
+                    }
+                }
+                _ => {}
             }
+            match state {
+                LexerState::String if utf8_error.is_none() && self.options.invalid_utf8 != InvalidUtf8Policy::Bytes => {
+                    if let Some(invalid) = check_utf8(&mut buf, &mut utf8_checked, self.options.invalid_utf8) {
+                        // the bytes after the invalid byte were read from the input
+                        let after = buf.len() - invalid - 1;
+                        utf8_error = Some((self.line, self.column.saturating_sub(after), buf[invalid]));
+                    }
+                }
+                LexerState::String => {}
+                _ => {
+                    utf8_checked = 0;
+                    utf8_error = None;
+                    wtf8 = false;
+                    if self.options.raw_strings {
+                        // e.g. an unfinished string
+                        self.byte_source.stop_recording();
+                    }
+                }
+            }
+            if let (LexerState::String, Some(chunk_size)) = (&state, self.options.string_chunk_size.map(|n| n.max(4))) {
+                if buf.len() > chunk_size {
+                    if !chunked {
+                        consumer.consume_with_span(Ok(LexerToken::StringStart), span!())?;
+                        chunked = true;
+                    }
+                    let end = match utf8_chunk_end(&buf[..chunk_size]) {
+                        0 => chunk_size,
+                        end => end,
+                    };
+                    let rest = buf.split_off(end);
+                    utf8_checked = utf8_checked.saturating_sub(end);
+                    consumer.consume_with_span(Ok(LexerToken::StringChunk(std::mem::replace(&mut buf, rest))), span!())?;
+                }
+            }
+            self.cursor = LexCursor {
+                state,
+                expect,
+                expected_index,
+                number_sub_state,
+                string_sub_state,
+                buf,
+                code_point,
+                unicode_index,
+                high,
+                skip_number,
+                token_start,
+                quote,
+                chunked,
+                utf8_checked,
+                utf8_error,
+                wtf8,
+            };
+            let too_long = match self.cursor.state {
+                LexerState::String if self.options.string_chunk_size.is_some() => None,
+                LexerState::String => self.options.limits.max_string_bytes
+                    .filter(|max| self.cursor.buf.len() > *max).map(|max| format!("String longer than {} bytes", max)),
+                LexerState::Number => self.options.limits.max_number_bytes
+                    .filter(|max| self.cursor.buf.len() > *max).map(|max| format!("Number longer than {} bytes", max)),
+                _ => None,
+            };
+            if let Some(msg) = too_long {
+                return self.limit_exceeded(consumer, msg);
+            }
+            return Ok(());
         }
-        // end of loop
+        // end of input
         match state {
             LexerState::Number => {  // finish our number if possible
                 match number_sub_state {
+                    LexerNumberSubState::ZeroNumberStart if self.options.raw_strings => { // -?0
+                        consume_number!(IntValue);
+                    }
                     LexerNumberSubState::ZeroNumberStart => { // 0
-                        consumer.consume(Ok(LexerToken::IntValue("0".into())), self.line, self.column)?;
+                        consumer.consume_with_span(Ok(LexerToken::IntValue("0".into())), number_span!())?;
                     }
                     LexerNumberSubState::NegNumberStart => {
                         // -
-                        consume_lex_error!("Missing digits `{}`", String::from_utf8(buf).unwrap());
+                        consume_lex_error!(LexErrorKind::MissingDigits, "Missing digits `{}`", String::from_utf8(buf).unwrap());
                     }
                     LexerNumberSubState::OtherNumber => {
                         // [1-9]
-                        consume_buf!(buf, IntValue);
+                        consume_number!(IntValue);
+                    }
+                    LexerNumberSubState::NumberFracStart if json5 => {
+                        buf.push(b'0');
+                        consume_number!(FloatValue);
+                    }
+                    LexerNumberSubState::Hex => {
+                        consume_hex!();
                     }
-                    LexerNumberSubState::NumberFracStart => {
+                    LexerNumberSubState::HexStart => {
+                        consume_lex_error!(LexErrorKind::MissingDigits, "Missing digits `{}0x`", String::from_utf8(buf).unwrap());
+                    }
+                    LexerNumberSubState::NumberFracStart | LexerNumberSubState::NumberDotStart => {
                         //  [0-9]\.
-                        consume_lex_error!("Missing decimals `{}`", String::from_utf8(buf).unwrap());
+                        consume_lex_error!(LexErrorKind::MissingDecimals, "Missing decimals `{}`", String::from_utf8(buf).unwrap());
                     }
                     LexerNumberSubState::NumberFrac => {
                         // [0-9]\.[0-9]
-                        consume_buf!(buf, FloatValue);
+                        consume_number!(FloatValue);
                     }
                     LexerNumberSubState::NumberFracExpStart => {
-                        consume_lex_error!("Missing exp `{}`", String::from_utf8(buf).unwrap());
+                        consume_lex_error!(LexErrorKind::MissingExponent, "Missing exp `{}`", String::from_utf8(buf).unwrap());
                     }
                     LexerNumberSubState::NumberFracExp => {
-                        consume_buf!(buf, FloatValue);
+                        consume_number!(FloatValue);
                     }
                     LexerNumberSubState::NumberFracExpMinusStart => {
-                        consume_lex_error!("Missing exp `{}`", String::from_utf8(buf).unwrap());
+                        consume_lex_error!(LexErrorKind::MissingExponent, "Missing exp `{}`", String::from_utf8(buf).unwrap());
                     }
                     LexerNumberSubState::NumberFracExpMinus => {
-                        consume_buf!(buf, FloatValue);
+                        consume_number!(FloatValue);
                     }
                     _ => {
-                        consume_lex_error!("Unexpected sub_state");
+                        consume_lex_error!(LexErrorKind::Internal, "Unexpected sub_state");
                     }
                 }
             }
             LexerState::String => {
                 match String::from_utf8(buf) {
-                    Ok(s) => { consume_lex_error!("Unfinished string `{}`", s); }
-                    Err(e) => { consume_lex_error!("Can't decode string `{}`", e); }
+                    Ok(s) => { consume_lex_error!(LexErrorKind::UnfinishedString, "Unfinished string `{}`", s); }
+                    Err(e) => { consume_lex_error!(LexErrorKind::InvalidUtf8, "Can't decode string `{}`", e); }
                 }
             }
+            LexerState::SkippedString(_) => {
+                consume_lex_error!(LexErrorKind::UnfinishedString, "Unfinished string");
+            }
+            LexerState::Identifier => {
+                consume_identifier!();
+            }
+            LexerState::LineComment => {
+                consume_comment!();
+            }
+            LexerState::BlockComment(_) => {
+                consume_lex_error!(LexErrorKind::UnfinishedComment, "Unfinished comment");
+            }
+            LexerState::CommentStart => {
+                consume_lex_error!(LexErrorKind::UnexpectedChar('/'), "Unexpected char `/`");
+            }
             LexerState::None => {
                 // pass
             }
-            _ => { consume_lex_error!("Unexpected sub_state"); }
+            _ => { consume_lex_error!(LexErrorKind::Internal, "Unexpected sub_state"); }
         }
-        consumer.consume(Ok(EndFile), self.line, self.column)?;
+        let offset = self.byte_source.offset();
+        consumer.consume_with_span(Ok(EndFile), Span { start: offset, end: offset, line: self.line, column: self.column })?;
         Ok(())
     }
 
     #[inline]
     fn parse_hex(&self, byte: u8) -> Result<u32, JSONLexError> {
         macro_rules! lex_error {
-            ($($arg:tt)*) => {{
+            ($kind:expr, $($arg:tt)*) => {{
                 Err(JSONLexError {
+                    kind: $kind,
                     msg: format!($($arg)*),
                     line: self.line,
                     column: self.column,
@@ -634,8 +1803,161 @@ impl<R: Read> JSONLexer<R> {
             _ if b'a' <= byte && byte <= b'f' => { Ok((byte - b'a') as u32 + 10) }
             _ if b'A' <= byte && byte <= b'F' => { Ok((byte - b'A') as u32 + 10) }
             _ => {
-                lex_error!("Unknown hex digit `{}`", byte as char)
+                lex_error!(LexErrorKind::InvalidHexDigit(byte as char), "Unknown hex digit `{}`", byte as char)
+            }
+        }
+    }
+}
+
+impl JSONLexer<FeedBuffer> {
+    /// A lexer that receives the input by chunks, e.g. from a network protocol: call `feed` for
+    /// every chunk, then `finish`. The chunks may end anywhere, even in the middle of a token
+    /// or of a char.
+    ///
+    /// ```
+    /// # use std::collections::VecDeque;
+    /// # use r_json_event_parser::json_lexer::{JSONLexer, LexerOptions, LexerToken};
+    /// let mut lexer = JSONLexer::new_feed(LexerOptions::default());
+    /// let mut tokens = VecDeque::new();
+    /// lexer.feed(b"[tr", &mut tokens).unwrap();
+    /// lexer.feed(b"ue, 1", &mut tokens).unwrap();
+    /// assert_eq!(4, tokens.len()); // BeginFile, BeginArray, true and the comma
+    /// lexer.finish(&mut tokens).unwrap();
+    /// assert_eq!(Ok(LexerToken::IntValue("1".into())), tokens[4].0);
+    /// assert_eq!(Ok(LexerToken::EndFile), tokens[5].0);
+    /// ```
+    pub fn new_feed(options: LexerOptions) -> Self {
+        JSONLexer::new_with_options(ByteSource::new(FeedBuffer::default()), options)
+    }
+
+    /// Lex a chunk of the input. The last token of the chunk is sent when the next chunk or the
+    /// end of the input shows its end. After an error or `finish`, the chunks are ignored.
+    pub fn feed<C: JSONLexConsumer>(&mut self, chunk: &[u8], consumer: &mut C) -> Result<(), ConsumeError> {
+        if self.finished {
+            return Ok(());
+        }
+        self.byte_source.source_mut().push(chunk);
+        let result = if self.options.interrupt.is_none() && self.options.progress.is_none() {
+            self.feed_tokens(consumer)
+        } else {
+            let mut monitor = self.monitor(consumer);
+            let result = self.feed_tokens(&mut monitor);
+            self.progress = monitor.state;
+            result
+        };
+        if result.is_err() {
+            self.finished = true;
+        }
+        result
+    }
+
+    /// Lex the end of the input: send the last token and the `EndFile`.
+    pub fn finish<C: JSONLexConsumer>(&mut self, consumer: &mut C) -> Result<(), ConsumeError> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        if self.options.interrupt.is_none() && self.options.progress.is_none() {
+            return self.finish_tokens(consumer);
+        }
+        let mut monitor = self.monitor(consumer);
+        let result = self.finish_tokens(&mut monitor);
+        self.progress = monitor.state;
+        result
+    }
+
+    fn finish_tokens<C: JSONLexConsumer>(&mut self, consumer: &mut C) -> Result<(), ConsumeError> {
+        if !self.started {
+            self.started = true;
+            consumer.consume_with_span(Ok(BeginFile), self.begin_file_span())?;
+        }
+        self.lex_byte(None, consumer)
+    }
+
+    fn feed_tokens<C: JSONLexConsumer>(&mut self, consumer: &mut C) -> Result<(), ConsumeError> {
+        if !self.started {
+            self.started = true;
+            consumer.consume_with_span(Ok(BeginFile), self.begin_file_span())?;
+        }
+        loop {
+            match self.byte_source.get() {
+                Ok(Some(byte)) => self.lex_byte(Some(byte), consumer)?,
+                Ok(None) => return Ok(()),
+                Err(e) => return self.read_failed(consumer, e),
             }
         }
     }
 }
+
+
+/// The bytes that follow the first byte of `false`, `true` or `null`, and the token.
+fn literal_rest(byte: u8) -> (&'static [u8], LexerToken) {
+    match byte {
+        b'f' => (b"alse", LexerToken::BooleanValue(false)),
+        b't' => (b"rue", LexerToken::BooleanValue(true)),
+        _ => (b"ull", LexerToken::NullValue),
+    }
+}
+
+/// JSON5 accepts the ECMAScript identifiers as keys. Only the ASCII ones are supported.
+fn is_identifier_start(byte: u8) -> bool {
+    byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$'
+}
+
+fn is_identifier_part(byte: u8) -> bool {
+    is_identifier_start(byte) || byte.is_ascii_digit()
+}
+
+/// Check the bytes of a string that were added since the last call: `checked` is the length of
+/// the prefix of `buf` already checked. An incomplete sequence at the end is checked on the next
+/// call. Returns the index of the first invalid byte, or, with the `Replace` policy, replaces the
+/// invalid sequences with U+FFFD.
+fn check_utf8(buf: &mut Vec<u8>, checked: &mut usize, policy: InvalidUtf8Policy) -> Option<usize> {
+    loop {
+        let e = match str::from_utf8(&buf[*checked..]) {
+            Ok(_) => {
+                *checked = buf.len();
+                return None;
+            }
+            Err(e) => e,
+        };
+        let invalid = *checked + e.valid_up_to();
+        match (e.error_len(), policy) {
+            (None, _) => {
+                *checked = invalid;
+                return None;
+            }
+            (Some(len), InvalidUtf8Policy::Replace) => {
+                let mut replacement = [0u8; 3];
+                buf.splice(invalid..invalid + len, REPLACEMENT_CHARACTER.encode_utf8(&mut replacement).bytes());
+                *checked = invalid + replacement.len();
+            }
+            (Some(_), _) => {
+                *checked = buf.len();
+                return Some(invalid);
+            }
+        }
+    }
+}
+
+/// The end of the longest prefix of `buf` that does not end in the middle of a UTF-8 sequence.
+fn utf8_chunk_end(buf: &[u8]) -> usize {
+    let mut i = buf.len();
+    // skip the continuation bytes
+    while i > 0 && buf.len() - i < 3 && buf[i - 1] & 0xC0 == 0x80 {
+        i -= 1;
+    }
+    if i > 0 {
+        let lead = buf[i - 1];
+        let width = match lead {
+            0xF0..=0xFF => 4,
+            0xE0..=0xEF => 3,
+            0xC0..=0xDF => 2,
+            _ => 1,
+        };
+        if buf.len() - (i - 1) < width {
+            return i - 1;
+        }
+    }
+    buf.len()
+}
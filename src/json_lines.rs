@@ -0,0 +1,141 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io::Read;
+
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
+use crate::ndjson::{NDJSONConsumer, NDJSONParser, RecordErrorPolicy};
+
+/// The outcome of the parsing of a JSON Lines input.
+#[derive(Debug, PartialEq, Default)]
+pub struct JSONLinesSummary {
+    /// The number of documents that were forwarded.
+    pub documents: usize,
+    /// One error per malformed line, with the line of the input.
+    pub errors: Vec<JSONParseError>,
+}
+
+impl JSONLinesSummary {
+    pub fn failed_lines(&self) -> Vec<usize> {
+        self.errors.iter().map(|e| e.line).collect()
+    }
+}
+
+/// Keeps the tokens of a record until the record is known to be valid, then forwards them as an
+/// element of the array.
+struct LinesConsumer<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    tokens: Vec<ParserToken>,
+    emit_errors: bool,
+    summary: JSONLinesSummary,
+    /// true if the consumer is done
+    stopped: bool,
+}
+
+impl<'a, C: JSONParseConsumer> LinesConsumer<'a, C> {
+    fn forward(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.consumer.consume(token)?;
+        if self.consumer.is_done() {
+            // unwind the NDJSON parser
+            self.stopped = true;
+            return Err(ConsumeError { msg: "Parsing stopped".into(), line: 0, column: 0, io_error: None });
+        }
+        Ok(())
+    }
+}
+
+impl<'a, C: JSONParseConsumer> NDJSONConsumer for LinesConsumer<'a, C> {
+    fn consume(&mut self, _record: usize, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        match token {
+            Ok(ParserToken::BeginFile) => Ok(()),
+            Ok(ParserToken::EndFile) => {
+                for token in std::mem::take(&mut self.tokens) {
+                    self.forward(Ok(token))?;
+                }
+                self.summary.documents += 1;
+                Ok(())
+            }
+            Ok(t) => {
+                self.tokens.push(t);
+                Ok(())
+            }
+            Err(e) => {
+                self.tokens.clear();
+                self.summary.errors.push(e.clone());
+                if self.emit_errors {
+                    self.forward(Err(e))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A parser for JSON Lines (one document per line), on top of the `NDJSONParser`. The consumer
+/// sees the documents as the elements of an array. A malformed line does not stop the parsing:
+/// its tokens are dropped, the error, with the line of the input, is sent to the consumer in
+/// place of the document (see `JSONLinesParser::error_policy`) and recorded in the summary. The
+/// parsing stops when the consumer is done (see `JSONParseConsumer::is_done`).
+pub struct JSONLinesParser<R: Read> {
+    parser: NDJSONParser<R>,
+    error_policy: RecordErrorPolicy,
+}
+
+impl<R: Read> JSONLinesParser<R> {
+    pub fn new(read: R, ignore_unicode_errs: bool) -> Self {
+        JSONLinesParser {
+            parser: NDJSONParser::new(read, ignore_unicode_errs).error_policy(RecordErrorPolicy::Emit),
+            error_policy: RecordErrorPolicy::Emit,
+        }
+    }
+
+    /// `Emit` (the default) sends the error of a malformed line to the consumer, `Skip` only
+    /// records it in the summary and `Abort` sends it and stops.
+    pub fn error_policy(mut self, error_policy: RecordErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        // the errors are needed for the summary
+        let ndjson_policy = if error_policy == RecordErrorPolicy::Abort { RecordErrorPolicy::Abort } else { RecordErrorPolicy::Emit };
+        self.parser = self.parser.error_policy(ndjson_policy);
+        self
+    }
+
+    pub fn parse<C: JSONParseConsumer>(&mut self, consumer: &mut C) -> Result<JSONLinesSummary, ConsumeError> {
+        consumer.consume(Ok(ParserToken::BeginFile))?;
+        consumer.consume(Ok(ParserToken::BeginArray))?;
+        let mut lines_consumer = LinesConsumer {
+            consumer,
+            tokens: vec!(),
+            emit_errors: self.error_policy != RecordErrorPolicy::Skip,
+            summary: JSONLinesSummary::default(),
+            stopped: false,
+        };
+        match self.parser.parse(&mut lines_consumer) {
+            Ok(_) => {}
+            Err(_) if lines_consumer.stopped => return Ok(lines_consumer.summary),
+            Err(e) => return Err(e),
+        }
+        let summary = lines_consumer.summary;
+        consumer.consume(Ok(ParserToken::EndArray))?;
+        consumer.consume(Ok(ParserToken::EndFile))?;
+        Ok(summary)
+    }
+}
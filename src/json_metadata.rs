@@ -0,0 +1,147 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
+use crate::json_pointer::{PathElement, PathTracker};
+
+/// The position of a token in its container.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct TokenMetadata {
+    /// The number of open containers around the token (the `Begin*`/`End*` tokens of a container
+    /// are at the depth of the container's parent).
+    pub depth: usize,
+    /// The index of the element in its array, or the ordinal of the member in its object (a key
+    /// and its value share the ordinal). None outside of any container.
+    pub index: Option<usize>,
+    /// true if the token belongs to an element of an array, false in an object or outside of
+    /// any container.
+    pub in_array: bool,
+}
+
+impl TokenMetadata {
+    /// The index of the element in its array ("row number"), None in an object or outside of any
+    /// container.
+    pub fn array_index(&self) -> Option<usize> {
+        if self.in_array { self.index } else { None }
+    }
+}
+
+/// A consumer of parser tokens annotated with their position.
+pub trait JSONMetadataConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>, metadata: TokenMetadata) -> Result<(), ConsumeError>;
+}
+
+struct Container {
+    is_array: bool,
+    count: usize,
+    index: Option<usize>,
+    /// true if the container is an element of an array
+    in_array: bool,
+}
+
+/// A parse consumer that computes the metadata of the tokens and forwards them to a
+/// `JSONMetadataConsumer`.
+pub struct MetadataAdapter<'a, C: JSONMetadataConsumer> {
+    consumer: &'a mut C,
+    stack: Vec<Container>,
+}
+
+impl<'a, C: JSONMetadataConsumer> MetadataAdapter<'a, C> {
+    pub fn new(consumer: &'a mut C) -> Self {
+        MetadataAdapter {
+            consumer,
+            stack: vec!(),
+        }
+    }
+
+    /// The index of a new value (or key) in the current container.
+    fn next_index(&mut self, is_key: bool) -> Option<usize> {
+        let container = self.stack.last_mut()?;
+        if container.is_array || is_key {
+            container.count += 1;
+        }
+        Some(container.count - 1)
+    }
+
+    /// The index of the current value, e.g. a chunked string after its `StringStart`.
+    fn current_index(&self) -> Option<usize> {
+        self.stack.last().map(|c| c.count - 1)
+    }
+
+    fn in_array(&self) -> bool {
+        self.stack.last().map(|c| c.is_array).unwrap_or(false)
+    }
+}
+
+impl<'a, C: JSONMetadataConsumer> JSONParseConsumer for MetadataAdapter<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let depth = self.stack.len();
+        let in_array = self.in_array();
+        let metadata = match &token {
+            Ok(ParserToken::BeginFile) | Ok(ParserToken::EndFile) | Err(_) => TokenMetadata { depth, index: None, in_array: false },
+            Ok(ParserToken::Key(_)) => TokenMetadata { depth, index: self.next_index(true), in_array },
+            Ok(ParserToken::BeginObject) | Ok(ParserToken::BeginArray) => {
+                let index = self.next_index(false);
+                let is_array = matches!(token, Ok(ParserToken::BeginArray));
+                self.stack.push(Container { is_array, count: 0, index, in_array });
+                TokenMetadata { depth, index, in_array }
+            }
+            Ok(ParserToken::EndObject) | Ok(ParserToken::EndArray) => {
+                let (index, in_array) = self.stack.pop().map(|c| (c.index, c.in_array)).unwrap_or((None, false));
+                TokenMetadata { depth: self.stack.len(), index, in_array }
+            }
+            Ok(ParserToken::StringChunk(_)) | Ok(ParserToken::StringEnd) => TokenMetadata { depth, index: self.current_index(), in_array },
+            Ok(_) => TokenMetadata { depth, index: self.next_index(false), in_array },
+        };
+        self.consumer.consume(token, metadata)
+    }
+}
+
+/// A consumer of parser tokens annotated with the path of their value (see `PathTracker`).
+pub trait JSONPathAwareConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>, path: &[PathElement]) -> Result<(), ConsumeError>;
+}
+
+/// A parse consumer that tracks the path of the tokens and forwards them to a
+/// `JSONPathAwareConsumer`.
+pub struct PathAdapter<'a, C: JSONPathAwareConsumer> {
+    consumer: &'a mut C,
+    tracker: PathTracker,
+}
+
+impl<'a, C: JSONPathAwareConsumer> PathAdapter<'a, C> {
+    pub fn new(consumer: &'a mut C) -> Self {
+        PathAdapter {
+            consumer,
+            tracker: PathTracker::new(),
+        }
+    }
+}
+
+impl<'a, C: JSONPathAwareConsumer> JSONParseConsumer for PathAdapter<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        if let Ok(t) = &token {
+            self.tracker.update(t);
+        }
+        self.consumer.consume(token, self.tracker.path())
+    }
+}
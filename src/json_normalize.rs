@@ -0,0 +1,78 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
+
+/// The tokens to normalize.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum NormalizationScope {
+    Keys,
+    KeysAndStrings,
+}
+
+/// A consumer that NFC-normalizes the keys (and optionally the string values) before forwarding
+/// the tokens to another consumer, so that keys differing only by their normalization form are
+/// equal downstream.
+pub struct NFCConsumer<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    scope: NormalizationScope,
+}
+
+impl<'a, C: JSONParseConsumer> NFCConsumer<'a, C> {
+    pub fn new(consumer: &'a mut C, scope: NormalizationScope) -> Self {
+        NFCConsumer {
+            consumer,
+            scope,
+        }
+    }
+}
+
+/// The NFC form of a string.
+pub fn to_nfc(s: String) -> String {
+    match is_nfc_quick(s.chars()) {
+        IsNormalized::Yes => s,
+        _ => s.nfc().collect(),
+    }
+}
+
+impl<'a, C: JSONParseConsumer> JSONParseConsumer for NFCConsumer<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = match token {
+            Ok(ParserToken::Key(s)) => Ok(ParserToken::Key(to_nfc(s))),
+            Ok(ParserToken::StringValue(s)) if self.scope == NormalizationScope::KeysAndStrings => {
+                Ok(ParserToken::StringValue(to_nfc(s)))
+            }
+            t => t,
+        };
+        self.consumer.consume(token)
+    }
+
+    fn skip_value(&self) -> bool {
+        self.consumer.skip_value()
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
@@ -0,0 +1,164 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
+use crate::json_pointer::{PathTracker, to_pointer_string};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PrecisionLossKind {
+    /// The integer does not fit in an `i64` nor in an `u64`.
+    IntegerOverflow,
+    /// The decimal can't be parsed as a `f64` and written back without a change of value.
+    InexactFloat,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct PrecisionWarning {
+    pub kind: PrecisionLossKind,
+    pub path: String,
+    pub lexeme: String,
+}
+
+/// The sign, the significant digits and the exponent of a decimal lexeme: `-12.50e1` is
+/// `(true, "125", 0)`. Zero is `(false, "", 0)`.
+fn to_decimal(lexeme: &str) -> Option<(bool, String, i64)> {
+    let (negative, rest) = match lexeme.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, lexeme),
+    };
+    let (mantissa, exp) = match rest.find(['e', 'E']) {
+        Some(i) => (&rest[..i], rest[i + 1..].trim_start_matches('+').parse::<i64>().ok()?),
+        None => (rest, 0),
+    };
+    let (int_part, frac_part) = match mantissa.find('.') {
+        Some(i) => (&mantissa[..i], &mantissa[i + 1..]),
+        None => (mantissa, ""),
+    };
+    let digits = format!("{}{}", int_part, frac_part);
+    let exp = exp.checked_sub(frac_part.len() as i64)?;
+    let trimmed = digits.trim_start_matches('0');
+    let without_trailing = trimmed.trim_end_matches('0');
+    if without_trailing.is_empty() {
+        return Some((false, String::new(), 0));
+    }
+    let exp = exp.checked_add((trimmed.len() - without_trailing.len()) as i64)?;
+    Some((negative, without_trailing.into(), exp))
+}
+
+/// true if the lexeme of an int fits in an `i64` or an `u64`.
+pub fn is_exact_int(lexeme: &str) -> bool {
+    lexeme.parse::<i64>().is_ok() || lexeme.parse::<u64>().is_ok()
+}
+
+/// true if the lexeme of a float has the same value after a round trip through a `f64`.
+pub fn is_exact_float(lexeme: &str) -> bool {
+    match lexeme.parse::<f64>() {
+        Ok(f) if f.is_finite() => {
+            let decimal = to_decimal(lexeme);
+            decimal.is_some() && decimal == to_decimal(&format!("{:e}", f))
+        }
+        _ => false
+    }
+}
+
+/// The kind of precision loss of a number token, if any.
+pub fn precision_loss(token: &ParserToken) -> Option<PrecisionLossKind> {
+    match token {
+        // the empty values are skipped values (see `JSONParseConsumer::skip_value`)
+        ParserToken::IntValue(s) if !s.is_empty() && !is_exact_int(s) => Some(PrecisionLossKind::IntegerOverflow),
+        ParserToken::FloatValue(s) if !s.is_empty() && !is_exact_float(s) => Some(PrecisionLossKind::InexactFloat),
+        _ => None,
+    }
+}
+
+/// Replace the lexeme of a number token with the text of the nearest `i64`, `u64` or `f64`, that
+/// `str::parse` reads back without loss. An integer that fits in none of the integer types
+/// becomes a `FloatValue`. The empty values (see `JSONParseConsumer::skip_value`) are unchanged.
+pub fn to_native(token: ParserToken) -> ParserToken {
+    match token {
+        ParserToken::IntValue(s) if !s.is_empty() => {
+            if let Ok(i) = s.parse::<i64>() {
+                ParserToken::IntValue(i.to_string())
+            } else if let Ok(u) = s.parse::<u64>() {
+                ParserToken::IntValue(u.to_string())
+            } else {
+                match s.parse::<f64>() {
+                    Ok(f) => ParserToken::FloatValue(format!("{:?}", f)),
+                    Err(_) => ParserToken::IntValue(s),
+                }
+            }
+        }
+        ParserToken::FloatValue(s) if !s.is_empty() => {
+            match s.parse::<f64>() {
+                Ok(f) => ParserToken::FloatValue(format!("{:?}", f)),
+                Err(_) => ParserToken::FloatValue(s),
+            }
+        }
+        t => t,
+    }
+}
+
+/// A consumer that records a warning for every number that would lose precision as an `i64`,
+/// `u64` or `f64`, and forwards the tokens unchanged to another consumer.
+pub struct PrecisionCheckConsumer<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    tracker: PathTracker,
+    warnings: Vec<PrecisionWarning>,
+}
+
+impl<'a, C: JSONParseConsumer> PrecisionCheckConsumer<'a, C> {
+    pub fn new(consumer: &'a mut C) -> Self {
+        PrecisionCheckConsumer {
+            consumer,
+            tracker: PathTracker::new(),
+            warnings: vec!(),
+        }
+    }
+
+    pub fn warnings(&self) -> &[PrecisionWarning] {
+        &self.warnings
+    }
+
+    fn warn(&mut self, kind: PrecisionLossKind, lexeme: &str) {
+        self.warnings.push(PrecisionWarning {
+            kind,
+            path: to_pointer_string(self.tracker.path()),
+            lexeme: lexeme.into(),
+        });
+    }
+}
+
+impl<'a, C: JSONParseConsumer> JSONParseConsumer for PrecisionCheckConsumer<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        if let Ok(ref t) = token {
+            self.tracker.update(t);
+            if let (Some(kind), ParserToken::IntValue(s) | ParserToken::FloatValue(s)) = (precision_loss(t), t) {
+                self.warn(kind, s);
+            }
+        }
+        self.consumer.consume(token)
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
@@ -19,13 +19,17 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
 use std::io::Read;
+use std::sync::Arc;
 
-use crate::byte_source::ByteSource;
-use crate::json_lexer::{ConsumeError, JSONLexConsumer, JSONLexer, JSONLexError, LexerToken};
+use crate::byte_source::{ByteSource, FeedBuffer};
+use crate::json_lexer::{ConsumeError, JSONLexConsumer, JSONLexer, JSONLexError, LexErrorKind, LexerOptions, LexerToken, Span};
 use crate::json_lexer::LexerToken::BeginFile;
+use crate::json_numbers::{precision_loss, PrecisionLossKind, to_native};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum ParserToken {
     BeginFile,
     EndFile,
@@ -37,20 +41,119 @@ pub enum ParserToken {
     BooleanValue(bool),
     NullValue,
     StringValue(String),
+    /// A string that is not valid UTF-8 (see `InvalidUtf8Policy::Bytes`)
+    BytesValue(Vec<u8>),
     IntValue(String),
     FloatValue(String),
+    /// A long string value sent in chunks (see `LexerOptions::string_chunk_size`). The keys are
+    /// always sent as `Key`.
+    StringStart,
+    StringChunk(Vec<u8>),
+    StringEnd,
 }
 
-#[derive(Debug, PartialEq)]
+/// The category of a `JSONParseError`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseErrorKind {
+    /// A token that is not allowed at this place
+    UnexpectedToken(LexerToken),
+    /// The end of the file was reached before the end of a container
+    UnclosedContainer,
+    /// A second value after the root value (strict mode)
+    ValueAfterRoot,
+    /// See `DuplicateKeyPolicy`
+    DuplicateKey(String),
+    /// An error of the lexer
+    Lex(LexErrorKind),
+    /// A number that can't be read without a loss (see `NumberMode::ParseStrictOrError`)
+    PrecisionLoss(PrecisionLossKind),
+    Other,
+}
+
+impl ParseErrorKind {
+    fn unexpected(token: &Result<LexerToken, JSONLexError>) -> Self {
+        match token {
+            Ok(t) => ParseErrorKind::UnexpectedToken(t.clone()),
+            Err(e) => ParseErrorKind::Lex(e.kind.clone()),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
 pub struct JSONParseError {
+    pub kind: ParseErrorKind,
     pub msg: String,
     pub line: usize,
     pub column: usize,
 }
 
+impl fmt::Display for JSONParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.msg, self.line, self.column)
+    }
+}
+
+impl std::error::Error for JSONParseError {}
+
+/// A parse error that stops a consumer, e.g. `let token = token?;`.
+impl From<JSONParseError> for ConsumeError {
+    fn from(e: JSONParseError) -> Self {
+        ConsumeError { msg: e.msg, line: e.line, column: e.column, io_error: None }
+    }
+}
+
 
 pub trait JSONParseConsumer {
     fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError>;
+
+    /// Called by the parser instead of `consume`, with the span of the lexer token that produced
+    /// the parser token. Override this method to get the byte range of the tokens.
+    fn consume_with_span(&mut self, token: Result<ParserToken, JSONParseError>, _span: Span) -> Result<(), ConsumeError> {
+        self.consume(token)
+    }
+
+    /// Asked before a scalar value: if true, the text of the next string or number is not needed
+    /// and the consumer will receive an empty `StringValue`, `IntValue` or `FloatValue`. The
+    /// lexer then skips the value without unescaping or buffering it. Keys are always decoded.
+    fn skip_value(&self) -> bool {
+        false
+    }
+
+    /// Called by the parser instead of `consume_with_span` for the keys if the keys are interned
+    /// (see `JSONParser::intern_keys`): equal keys share the same `Arc`. Override this method to
+    /// keep the keys without a copy. By default, the key is sent as a `Key` token.
+    fn consume_interned_key(&mut self, key: Arc<str>, span: Span) -> Result<(), ConsumeError> {
+        self.consume_with_span(Ok(ParserToken::Key(key.to_string())), span)
+    }
+
+    /// Asked after each token: if true, the parser stops and returns `Ok`, e.g. when the
+    /// consumer found what it was looking for. Use this method rather than an error to stop
+    /// early (see `JSONParser::stopped`).
+    fn is_done(&self) -> bool {
+        false
+    }
+}
+
+impl<C: JSONParseConsumer + ?Sized> JSONParseConsumer for &mut C {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        (**self).consume(token)
+    }
+
+    fn consume_with_span(&mut self, token: Result<ParserToken, JSONParseError>, span: Span) -> Result<(), ConsumeError> {
+        (**self).consume_with_span(token, span)
+    }
+
+    fn consume_interned_key(&mut self, key: Arc<str>, span: Span) -> Result<(), ConsumeError> {
+        (**self).consume_interned_key(key, span)
+    }
+
+    fn skip_value(&self) -> bool {
+        (**self).skip_value()
+    }
+
+    fn is_done(&self) -> bool {
+        (**self).is_done()
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -65,21 +168,274 @@ enum ParserState {
     InArraySep,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ContainerKind {
+    Object,
+    Array,
+}
+
+/// A container that was opened at a given position and is not closed yet.
+#[derive(Debug, PartialEq, Clone)]
+pub struct OpenContainer {
+    pub kind: ContainerKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for OpenContainer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self.kind {
+            ContainerKind::Object => "object",
+            ContainerKind::Array => "array",
+        };
+        write!(f, "{} opened at {}:{} not closed", kind, self.line, self.column)
+    }
+}
+
+/// What the parser does when a key repeats in an object (RFC 8259 says the names "SHOULD be
+/// unique").
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum DuplicateKeyPolicy {
+    #[default]
+    Allow,
+    /// Emit a `JSONParseError` instead of the `Key` token.
+    Error,
+    /// Keep the `Key` token and record a warning (see `JSONParser::duplicate_keys`).
+    Warn,
+}
+
+/// What the parser does with the lexemes of the numbers.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum NumberMode {
+    /// Keep the lexemes: `1.10` and `1e400` are sent as they are.
+    #[default]
+    PreserveLexeme,
+    /// Send the text of the nearest `i64`, `u64` or `f64`, that `str::parse` reads back (see
+    /// `json_numbers::to_native`). An integer too large for the integer types becomes a
+    /// `FloatValue`.
+    ParseLossy,
+    /// As `ParseLossy`, but emit a `PrecisionLoss` error instead of a number that has no exact
+    /// `i64`, `u64` or `f64` value.
+    ParseStrictOrError,
+}
+
 pub struct JSONParser<R: Read> {
     json_lexer: JSONLexer<R>,
+    open_containers: Vec<OpenContainer>,
+    duplicate_key_policy: DuplicateKeyPolicy,
+    number_mode: NumberMode,
+    duplicate_keys: Vec<JSONParseError>,
+    error_recovery: bool,
+    multi_document: bool,
+    intern_keys: bool,
+    stopped: bool,
 }
 
-pub struct JSONLexerToParser<'a, C: JSONParseConsumer> {
+/// Sends the tokens to the consumer with the span of the current lexer token.
+struct SpanConsumer<'a, C: JSONParseConsumer> {
     consumer: &'a mut C,
+    span: Span,
+    number_mode: NumberMode,
+    /// Send a `StringEnd` instead of the `StringValue`.
+    string_end: bool,
+    /// true if an error was sent
+    error: bool,
+}
+
+impl<'a, C: JSONParseConsumer> JSONParseConsumer for SpanConsumer<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = match (self.number_mode, token) {
+            (NumberMode::PreserveLexeme, token) => token,
+            (NumberMode::ParseStrictOrError, Ok(t)) => match precision_loss(&t) {
+                Some(kind) => Err(JSONParseError {
+                    kind: ParseErrorKind::PrecisionLoss(kind),
+                    msg: format!("Precision loss in `{:?}`", t),
+                    line: self.span.line,
+                    column: self.span.column,
+                }),
+                None => Ok(to_native(t)),
+            },
+            (_, token) => token.map(to_native),
+        };
+        let token = match token {
+            Ok(ParserToken::StringValue(_)) if self.string_end => Ok(ParserToken::StringEnd),
+            t => t,
+        };
+        self.error |= token.is_err();
+        let result = self.consumer.consume_with_span(token, self.span);
+        self.fill_position(result)
+    }
+
+    fn consume_interned_key(&mut self, key: Arc<str>, _span: Span) -> Result<(), ConsumeError> {
+        let result = self.consumer.consume_interned_key(key, self.span);
+        self.fill_position(result)
+    }
+}
+
+impl<'a, C: JSONParseConsumer> SpanConsumer<'a, C> {
+    /// Set the position of a write error to the position of the token.
+    fn fill_position(&self, result: Result<(), ConsumeError>) -> Result<(), ConsumeError> {
+        result.map_err(|mut e| {
+            if e.io_error.is_some() {
+                e.line = self.span.line;
+                e.column = self.span.column;
+            }
+            e
+        })
+    }
+}
+
+/// The state machine of the parser, shared by the push and the pull parsers.
+pub(crate) struct ParserMachine {
     state: ParserState,
     states: Vec<ParserState>,
+    open_containers: Vec<OpenContainer>,
+    duplicate_key_policy: DuplicateKeyPolicy,
+    number_mode: NumberMode,
+    /// The keys of the open objects, if the policy is not `Allow`.
+    keys: Vec<HashSet<String>>,
+    duplicate_keys: Vec<JSONParseError>,
+    /// Reject a second value at the top level.
+    strict: bool,
+    root_done: bool,
+    /// Start a new document after the root value.
+    multi_document: bool,
+    error_recovery: bool,
+    /// true if the tokens are skipped after an error
+    recovering: bool,
+    /// The chunks of a long key
+    chunked_key: Option<Vec<u8>>,
+    /// true if the `StringStart` of the current string was rejected: its chunks are dropped
+    rejected_string: bool,
+    /// The interned keys, if the keys are interned
+    interned_keys: Option<HashSet<Arc<str>>>,
 }
 
-impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
-    fn consume(&mut self, token: Result<LexerToken, JSONLexError>, line: usize, column: usize) -> Result<(), ConsumeError> {
+impl ParserMachine {
+    pub(crate) fn new() -> Self {
+        ParserMachine {
+            state: ParserState::Undefined,
+            states: vec!(),
+            open_containers: vec!(),
+            duplicate_key_policy: DuplicateKeyPolicy::Allow,
+            number_mode: NumberMode::PreserveLexeme,
+            keys: vec!(),
+            duplicate_keys: vec!(),
+            strict: false,
+            root_done: false,
+            multi_document: false,
+            error_recovery: false,
+            recovering: false,
+            chunked_key: None,
+            rejected_string: false,
+            interned_keys: None,
+        }
+    }
+
+    /// On the first `,`, `]` or `}` after an error, set the state to go on after the element
+    /// of the innermost container. Returns false if the token must be skipped.
+    fn resync(&mut self, token: &Result<LexerToken, JSONLexError>) -> bool {
+        let kind = self.open_containers.last().map(|c| c.kind);
+        let state = match (token, kind) {
+            (Ok(LexerToken::EndFile), _) => ParserState::None,
+            (Ok(LexerToken::ValueSeparator), Some(ContainerKind::Object)) | (Ok(LexerToken::EndObject), Some(ContainerKind::Object)) => {
+                ParserState::InObjectSep
+            }
+            (Ok(LexerToken::ValueSeparator), Some(ContainerKind::Array)) | (Ok(LexerToken::EndArray), Some(ContainerKind::Array)) => {
+                ParserState::InArraySep
+            }
+            _ => return false,
+        };
+        self.state = state;
+        self.recovering = false;
+        true
+    }
+
+    /// Apply the duplicate key policy to a key token.
+    fn check_key(&mut self, token: Result<ParserToken, JSONParseError>, line: usize, column: usize) -> Result<ParserToken, JSONParseError> {
+        let key = match (&token, self.keys.last_mut()) {
+            (Ok(ParserToken::Key(k)), Some(keys)) => {
+                if keys.insert(k.clone()) {
+                    return token;
+                }
+                k
+            }
+            _ => return token,
+        };
+        let error = JSONParseError { kind: ParseErrorKind::DuplicateKey(key.clone()), msg: format!("Duplicate key `{}`", key), line, column };
+        match self.duplicate_key_policy {
+            DuplicateKeyPolicy::Error => Err(error),
+            _ => {
+                self.duplicate_keys.push(error);
+                token
+            }
+        }
+    }
+
+    pub(crate) fn consume<C: JSONParseConsumer>(&mut self, consumer: &mut C, token: Result<LexerToken, JSONLexError>, span: Span) -> Result<(), ConsumeError> {
+        let Span { line, column, .. } = span;
+        if let Ok(LexerToken::Comment(_)) = token {
+            return Ok(());
+        }
+        if self.recovering && !self.resync(&token) {
+            return Ok(());
+        }
+        let mut span_consumer = SpanConsumer { consumer, span, number_mode: self.number_mode, string_end: false, error: false };
+        let token = match token {
+            // a key is rebuilt from its chunks, a value is forwarded and checked as an empty
+            // string on `StringEnd`.
+            Ok(LexerToken::StringStart) if self.state == ParserState::InObject => {
+                self.chunked_key = Some(vec!());
+                return Ok(());
+            }
+            // a value at a wrong place: the state machine reports the error on the `StringStart`
+            Ok(LexerToken::StringStart) if !self.expects_value() || (self.state == ParserState::None && self.strict && self.root_done) => {
+                self.rejected_string = true;
+                Ok(LexerToken::StringStart)
+            }
+            Ok(LexerToken::StringStart) => {
+                self.rejected_string = false;
+                if self.state == ParserState::None && self.multi_document && self.root_done {
+                    self.root_done = false;
+                    span_consumer.consume(Ok(ParserToken::EndFile))?;
+                    span_consumer.consume(Ok(ParserToken::BeginFile))?;
+                }
+                return span_consumer.consume(Ok(ParserToken::StringStart));
+            }
+            Ok(LexerToken::StringChunk(_)) | Ok(LexerToken::StringEnd) if self.rejected_string => {
+                if let Ok(LexerToken::StringEnd) = token {
+                    self.rejected_string = false;
+                }
+                return Ok(());
+            }
+            Ok(LexerToken::StringChunk(chunk)) => {
+                match self.chunked_key.as_mut() {
+                    Some(key) => {
+                        key.extend(chunk);
+                        return Ok(());
+                    }
+                    None => return span_consumer.consume(Ok(ParserToken::StringChunk(chunk))),
+                }
+            }
+            Ok(LexerToken::RawString { decoded, .. }) => Ok(LexerToken::String(decoded)),
+            // the consumer receives an empty `StringValue`
+            Ok(LexerToken::SkippedString) => Ok(LexerToken::String(String::new())),
+            Ok(LexerToken::StringEnd) => {
+                match self.chunked_key.take() {
+                    Some(key) => Ok(LexerToken::String(String::from_utf8_lossy(&key).into_owned())),
+                    None => {
+                        span_consumer.string_end = true;
+                        Ok(LexerToken::String(String::new()))
+                    }
+                }
+            }
+            t => t,
+        };
+        let consumer = &mut span_consumer;
         macro_rules! parse_error {
-            ($($arg:tt)*) => {{
+            ($kind:expr, $($arg:tt)*) => {{
                 Err(JSONParseError {
+                    kind: $kind,
                     msg: format!($($arg)*),
                     line,
                     column,
@@ -88,41 +444,61 @@ impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
         }
 
         macro_rules! consume_parse_error {
-            ($($arg:tt)*) => {{
-                self.consumer.consume(parse_error!($($arg)*))?;
+            ($kind:expr, $($arg:tt)*) => {{
+                consumer.consume(parse_error!($kind, $($arg)*))?;
             }};
         }
 
         if let Err(e) = token {
-            self.consumer.consume(Err(JSONParseError {
+            consumer.consume(Err(JSONParseError {
+                kind: ParseErrorKind::Lex(e.kind.clone()),
                 msg: e.msg.clone(),
                 line: e.line,
                 column: e.column,
             }))?;
+            if self.error_recovery {
+                self.recovering = true;
+                return Ok(());
+            }
             return Err(ConsumeError {
                 msg: e.msg,
                 line: e.line,
                 column: e.column,
+                io_error: None,
             });
         }
+        if let (Ok(LexerToken::EndFile), false) = (&token, self.open_containers.is_empty()) {
+            let open: Vec<String> = self.open_containers.iter().rev().map(|c| c.to_string()).collect();
+            consume_parse_error!(ParseErrorKind::UnclosedContainer, "Unexpected token `{:?}`: {}", token, open.join(", "));
+            return Ok(());
+        }
+        let depth = self.states.len();
         match self.state {
             ParserState::Undefined => {
-                self.consumer.consume(match token {
+                consumer.consume(match token {
                     Ok(BeginFile) => {
                         self.state = ParserState::None;
                         Ok(ParserToken::BeginFile)
                     }
-                    _ => parse_error!("Unexpected state")
+                    _ => parse_error!(ParseErrorKind::unexpected(&token), "Unexpected state")
                 })?
             }
             ParserState::None => {
+                if self.multi_document && self.root_done && !matches!(token, Ok(LexerToken::EndFile)) {
+                    self.root_done = false;
+                    consumer.consume(Ok(ParserToken::EndFile))?;
+                    consumer.consume(Ok(ParserToken::BeginFile))?;
+                }
                 let token = match token {
                     Ok(LexerToken::EndFile) => {
                         match self.states.last() {
-                            Some(t) => parse_error!("Should be closed: {:?}", t),
+                            Some(t) => parse_error!(ParseErrorKind::UnclosedContainer, "Should be closed: {:?}", t),
                             _ => Ok(ParserToken::EndFile)
                         }
                     }
+                    t if self.strict && self.root_done => {
+                        parse_error!(ParseErrorKind::ValueAfterRoot, "Unexpected token after the root value `{:?}`", t)
+                    }
                     Ok(LexerToken::BeginObject) => {
                         self.states.push(ParserState::None);
                         self.state = ParserState::InObject;
@@ -148,11 +524,19 @@ impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
                     Ok(LexerToken::String(s)) => {
                         Ok(ParserToken::StringValue(s))
                     }
+                    Ok(LexerToken::Bytes(b)) => {
+                        Ok(ParserToken::BytesValue(b))
+                    }
                     t => {
-                        parse_error!("Unexpected token `{:?}`", t)
+                        parse_error!(ParseErrorKind::unexpected(&t), "Unexpected token `{:?}`", t)
                     }
                 };
-                self.consumer.consume(token)?;
+                if self.states.is_empty() && matches!(token, Ok(ParserToken::BooleanValue(_)) | Ok(ParserToken::NullValue)
+                    | Ok(ParserToken::IntValue(_)) | Ok(ParserToken::FloatValue(_)) | Ok(ParserToken::StringValue(_))
+                    | Ok(ParserToken::BytesValue(_))) {
+                    self.root_done = true;
+                }
+                consumer.consume(token)?;
             }
             ParserState::InObject => {
                 let token = match token {
@@ -164,11 +548,30 @@ impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
                         self.state = ParserState::InObjectMember;
                         Ok(ParserToken::Key(s))
                     }
+                    Ok(LexerToken::Bytes(b)) => {
+                        // a key is always a string
+                        self.state = ParserState::InObjectMember;
+                        Ok(ParserToken::Key(String::from_utf8_lossy(&b).into_owned()))
+                    }
                     t => {
-                        parse_error!("Unexpected token `{:?}`", t)
+                        parse_error!(ParseErrorKind::unexpected(&t), "Unexpected token `{:?}`", t)
                     }
                 };
-                self.consumer.consume(token)?;
+                let token = self.check_key(token, line, column);
+                match (token, self.interned_keys.as_mut()) {
+                    (Ok(ParserToken::Key(k)), Some(interned_keys)) => {
+                        let key = match interned_keys.get(k.as_str()) {
+                            Some(key) => key.clone(),
+                            None => {
+                                let key: Arc<str> = Arc::from(k);
+                                interned_keys.insert(key.clone());
+                                key
+                            }
+                        };
+                        consumer.consume_interned_key(key, span)?;
+                    }
+                    (token, _) => consumer.consume(token)?,
+                }
             }
             ParserState::InObjectMember => {
                 match token {
@@ -176,7 +579,7 @@ impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
                         self.state = ParserState::InObjectMemberValue
                     }
                     t => {
-                        consume_parse_error!("Unexpected token `{:?}`", t);
+                        consume_parse_error!(ParseErrorKind::unexpected(&t), "Unexpected token `{:?}`", t);
                     }
                 }
             }
@@ -202,6 +605,10 @@ impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
                         self.state = ParserState::InObjectSep;
                         Ok(ParserToken::StringValue(s))
                     }
+                    Ok(LexerToken::Bytes(b)) => {
+                        self.state = ParserState::InObjectSep;
+                        Ok(ParserToken::BytesValue(b))
+                    }
                     Ok(LexerToken::BeginObject) => {
                         self.states.push(ParserState::InObjectSep);
                         self.state = ParserState::InObject;
@@ -213,10 +620,10 @@ impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
                         Ok(ParserToken::BeginArray)
                     }
                     t => {
-                        parse_error!("Unexpected token `{:?}`", t)
+                        parse_error!(ParseErrorKind::unexpected(&t), "Unexpected token `{:?}`", t)
                     }
                 };
-                self.consumer.consume(token)?;
+                consumer.consume(token)?;
             }
             ParserState::InObjectSep => {
                 match token {
@@ -225,10 +632,10 @@ impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
                     }
                     Ok(LexerToken::EndObject) => {
                         self.state = self.states.pop().unwrap();
-                        self.consumer.consume(Ok(ParserToken::EndObject))?;
+                        consumer.consume(Ok(ParserToken::EndObject))?;
                     }
                     t => {
-                        consume_parse_error!("Unexpected token `{:?}`", t);
+                        consume_parse_error!(ParseErrorKind::unexpected(&t), "Unexpected token `{:?}`", t);
                     }
                 }
             }
@@ -258,6 +665,10 @@ impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
                         self.state = ParserState::InArraySep;
                         Ok(ParserToken::StringValue(s))
                     }
+                    Ok(LexerToken::Bytes(b)) => {
+                        self.state = ParserState::InArraySep;
+                        Ok(ParserToken::BytesValue(b))
+                    }
                     Ok(LexerToken::BeginObject) => {
                         self.states.push(ParserState::InArraySep);
                         self.state = ParserState::InObject;
@@ -269,10 +680,10 @@ impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
                         Ok(ParserToken::BeginArray)
                     }
                     t => {
-                        parse_error!("Unexpected token `{:?}`", t)
+                        parse_error!(ParseErrorKind::unexpected(&t), "Unexpected token `{:?}`", t)
                     }
                 };
-                self.consumer.consume(token)?;
+                consumer.consume(token)?;
             }
             ParserState::InArraySep => {
                 match token {
@@ -281,37 +692,584 @@ impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
                     }
                     Ok(LexerToken::EndArray) => {
                         self.state = self.states.pop().unwrap();
-                        self.consumer.consume(Ok(ParserToken::EndArray))?;
+                        consumer.consume(Ok(ParserToken::EndArray))?;
                     }
                     t => {
-                        consume_parse_error!("Unexpected token `{:?}`", t);
+                        consume_parse_error!(ParseErrorKind::unexpected(&t), "Unexpected token `{:?}`", t);
                     }
                 }
             }
         }
+        if self.states.len() > depth {
+            let kind = if self.state == ParserState::InObject { ContainerKind::Object } else { ContainerKind::Array };
+            if kind == ContainerKind::Object && self.duplicate_key_policy != DuplicateKeyPolicy::Allow {
+                self.keys.push(HashSet::new());
+            }
+            self.open_containers.push(OpenContainer { kind, line, column });
+        } else if self.states.len() < depth {
+            if self.states.is_empty() {
+                self.root_done = true;
+            }
+            let container = self.open_containers.pop();
+            if let Some(OpenContainer { kind: ContainerKind::Object, .. }) = container {
+                self.keys.pop();
+            }
+        }
+        if span_consumer.error && self.error_recovery {
+            self.recovering = true;
+        }
         Ok(())
     }
+
+    /// true if the next token must be a value (not a key).
+    fn expects_value(&self) -> bool {
+        matches!(self.state, ParserState::None | ParserState::InObjectMemberValue | ParserState::InArray)
+    }
+}
+
+/// A consumer that stops on the first error.
+/// Keeps the first error to return it with its kind.
+struct ValidateConsumer {
+    error: Option<JSONParseError>,
+}
+
+impl JSONParseConsumer for ValidateConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        match token {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                let error = ConsumeError::from(e.clone());
+                self.error = Some(e);
+                Err(error)
+            }
+        }
+    }
+}
+
+pub struct JSONLexerToParser<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    machine: ParserMachine,
+    /// true if the consumer is done (see `JSONParseConsumer::is_done`)
+    stopped: bool,
+}
+
+impl<'a, C: JSONParseConsumer> JSONLexConsumer for JSONLexerToParser<'a, C> {
+    fn consume(&mut self, token: Result<LexerToken, JSONLexError>, line: usize, column: usize) -> Result<(), ConsumeError> {
+        self.consume_with_span(token, Span { line, column, ..Default::default() })
+    }
+
+    fn consume_with_span(&mut self, token: Result<LexerToken, JSONLexError>, span: Span) -> Result<(), ConsumeError> {
+        self.machine.consume(self.consumer, token, span)?;
+        if self.consumer.is_done() {
+            // unwind the lexer, the error is dropped by the parser
+            self.stopped = true;
+            return Err(ConsumeError { msg: "Parsing stopped".into(), line: span.line, column: span.column, io_error: None });
+        }
+        Ok(())
+    }
+
+    fn skip_value(&self) -> bool {
+        self.machine.expects_value() && self.consumer.skip_value()
+    }
 }
 
 impl<'a, C: JSONParseConsumer> JSONLexerToParser<'a, C> {
     pub fn new(consumer: &'a mut C) -> Self {
         JSONLexerToParser {
             consumer,
-            state: ParserState::Undefined,
-            states: vec!(),
+            machine: ParserMachine::new(),
+            stopped: false,
         }
     }
 }
 
 impl<R: Read> JSONParser<R> {
     pub fn new(byte_source: ByteSource<R>, ignore_unicode_errs: bool) -> Self {
+        JSONParser::new_with_options(byte_source, LexerOptions { ignore_unicode_errs, ..Default::default() })
+    }
+
+    pub fn new_with_options(byte_source: ByteSource<R>, options: LexerOptions) -> Self {
         JSONParser {
-            json_lexer: JSONLexer::new(byte_source, ignore_unicode_errs),
+            json_lexer: JSONLexer::new_with_options(byte_source, options),
+            open_containers: vec!(),
+            duplicate_key_policy: DuplicateKeyPolicy::Allow,
+            number_mode: NumberMode::PreserveLexeme,
+            duplicate_keys: vec!(),
+            error_recovery: false,
+            multi_document: false,
+            intern_keys: false,
+            stopped: false,
         }
     }
 
+    pub fn duplicate_key_policy(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.duplicate_key_policy = policy;
+        self
+    }
+
+    pub fn number_mode(mut self, number_mode: NumberMode) -> Self {
+        self.number_mode = number_mode;
+        self
+    }
+
+    /// After an error, skip the tokens up to the next `,`, `]` or `}` of the innermost container
+    /// and go on. The consumer receives the errors and may still stop the parsing by returning
+    /// an error.
+    pub fn error_recovery(mut self, error_recovery: bool) -> Self {
+        self.error_recovery = error_recovery;
+        self
+    }
+
+    /// Accept a stream of concatenated values, e.g. `{"a": 1} {"a": 2}`. Each value is a
+    /// document: the consumer receives an `EndFile` and a `BeginFile` between two values.
+    pub fn multi_document(mut self, multi_document: bool) -> Self {
+        self.multi_document = multi_document;
+        self
+    }
+
+    /// Send the keys to `JSONParseConsumer::consume_interned_key`: the parser keeps one copy of
+    /// every distinct key, and the consumer receives a shared `Arc<str>` for the repeated keys.
+    pub fn intern_keys(mut self, intern_keys: bool) -> Self {
+        self.intern_keys = intern_keys;
+        self
+    }
+
     pub fn parse<C: JSONParseConsumer>(&mut self, consumer: &mut C) -> Result<(), ConsumeError> {
         let mut parser = JSONLexerToParser::new(consumer);
-        self.json_lexer.lex(&mut parser)
+        if self.intern_keys {
+            parser.machine.interned_keys = Some(HashSet::new());
+        }
+        parser.machine.duplicate_key_policy = self.duplicate_key_policy;
+        parser.machine.number_mode = self.number_mode;
+        parser.machine.strict = self.json_lexer.options().strict;
+        parser.machine.error_recovery = self.error_recovery;
+        parser.machine.multi_document = self.multi_document;
+        let result = self.json_lexer.lex(&mut parser);
+        self.open_containers = parser.machine.open_containers;
+        self.duplicate_keys = parser.machine.duplicate_keys;
+        self.stopped = parser.stopped;
+        if self.stopped { Ok(()) } else { result }
+    }
+
+    /// true if the last parsing was stopped by the consumer before the end of the input (see
+    /// `JSONParseConsumer::is_done`).
+    pub fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Check that the input is a single JSON document, in strict mode (see
+    /// `LexerOptions::strict`). The error is the first one, with its position.
+    pub fn validate(byte_source: ByteSource<R>) -> Result<(), JSONParseError> {
+        let options = LexerOptions { strict: true, ..Default::default() };
+        let mut parser = JSONParser::new_with_options(byte_source, options);
+        let mut consumer = ValidateConsumer { error: None };
+        parser.parse(&mut consumer).map_err(|e| consumer.error.take().unwrap_or(JSONParseError {
+            kind: ParseErrorKind::Other,
+            msg: e.msg,
+            line: e.line,
+            column: e.column,
+        }))
+    }
+
+    /// The duplicate keys found by the last parsing with the `Warn` policy.
+    pub fn duplicate_keys(&self) -> &[JSONParseError] {
+        &self.duplicate_keys
+    }
+
+    /// The containers that were not closed when the last parsing stopped, outermost first.
+    pub fn open_containers(&self) -> &[OpenContainer] {
+        &self.open_containers
+    }
+
+    /// The number of bytes consumed so far.
+    pub fn byte_offset(&self) -> usize {
+        self.json_lexer.byte_offset()
+    }
+}
+impl JSONParseConsumer for VecDeque<Result<ParserToken, JSONParseError>> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.push_back(token);
+        Ok(())
+    }
+}
+
+type QueuedEvent = (Result<ParserToken, JSONParseError>, Span);
+
+impl JSONParseConsumer for VecDeque<QueuedEvent> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.push_back((token, Span::default()));
+        Ok(())
+    }
+
+    fn consume_with_span(&mut self, token: Result<ParserToken, JSONParseError>, span: Span) -> Result<(), ConsumeError> {
+        self.push_back((token, span));
+        Ok(())
+    }
+}
+
+/// A parser that returns the tokens one at a time: the parsing may be suspended between two
+/// and the parsing stops after the first error, unless the error recovery is on.
+pub struct JSONPullParser<R: Read> {
+    json_lexer: JSONLexer<R>,
+    machine: ParserMachine,
+    queue: VecDeque<QueuedEvent>,
+    done: bool,
+    /// The number of containers opened and not closed in the returned tokens
+    depth: usize,
+}
+
+impl<R: Read> JSONPullParser<R> {
+    pub fn new(byte_source: ByteSource<R>, ignore_unicode_errs: bool) -> Self {
+        JSONPullParser::new_with_options(byte_source, LexerOptions { ignore_unicode_errs, ..Default::default() })
+    }
+
+    pub fn new_with_options(byte_source: ByteSource<R>, options: LexerOptions) -> Self {
+        let mut machine = ParserMachine::new();
+        machine.strict = options.strict;
+        JSONPullParser {
+            json_lexer: JSONLexer::new_with_options(byte_source, options),
+            machine,
+            queue: VecDeque::new(),
+            done: false,
+            depth: 0,
+        }
     }
-}
\ No newline at end of file
+
+    pub fn duplicate_key_policy(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.machine.duplicate_key_policy = policy;
+        self
+    }
+
+    pub fn number_mode(mut self, number_mode: NumberMode) -> Self {
+        self.machine.number_mode = number_mode;
+        self
+    }
+
+    /// See `JSONParser::error_recovery`. With the recovery, the parsing goes on after an error.
+    pub fn error_recovery(mut self, error_recovery: bool) -> Self {
+        self.machine.error_recovery = error_recovery;
+        self
+    }
+
+    /// See `JSONParser::multi_document`.
+    pub fn multi_document(mut self, multi_document: bool) -> Self {
+        self.machine.multi_document = multi_document;
+        self
+    }
+
+    /// The duplicate keys found so far with the `Warn` policy.
+    pub fn duplicate_keys(&self) -> &[JSONParseError] {
+        &self.machine.duplicate_keys
+    }
+
+    pub fn next_event(&mut self) -> Option<Result<ParserToken, JSONParseError>> {
+        self.next_event_with_span().map(|(token, _)| token)
+    }
+
+    /// The next token and the span of the lexer token that produced it.
+    pub fn next_event_with_span(&mut self) -> Option<(Result<ParserToken, JSONParseError>, Span)> {
+        self.next_event_skipping(false)
+    }
+
+    /// Skip the rest of the current container, that is the innermost container whose
+    /// `BeginObject`/`BeginArray` was returned and whose end was not, up to and including its
+    /// `EndObject`/`EndArray`. The strings and the numbers of the container are scanned without
+    /// being unescaped or buffered. Returns the first error; does nothing outside of a container.
+    ///
+    /// ```
+    /// # use r_json_event_parser::byte_source::ByteSource;
+    /// # use r_json_event_parser::json_parser::{JSONPullParser, ParserToken};
+    /// let mut parser = JSONPullParser::new(ByteSource::new(r#"[{"a": ["b", 1]}, 2]"#.as_bytes()), false);
+    /// parser.next_event(); // BeginFile
+    /// parser.next_event(); // BeginArray
+    /// parser.next_event(); // BeginObject
+    /// parser.skip_value().unwrap();
+    /// assert_eq!(Some(Ok(ParserToken::IntValue("2".into()))), parser.next_event());
+    /// ```
+    pub fn skip_value(&mut self) -> Result<(), JSONParseError> {
+        let target = match self.depth.checked_sub(1) {
+            Some(target) => target,
+            None => return Ok(()),
+        };
+        while self.depth > target {
+            match self.next_event_skipping(true) {
+                Some((Ok(_), _)) => {}
+                Some((Err(e), _)) => return Err(e),
+                None => break,
+            }
+        }
+        Ok(())
+    }
+
+    fn next_event_skipping(&mut self, skip: bool) -> Option<(Result<ParserToken, JSONParseError>, Span)> {
+        loop {
+            if let Some((token, span)) = self.queue.pop_front() {
+                if token.is_err() && !self.machine.error_recovery {
+                    self.done = true;
+                    self.queue.clear();
+                }
+                match token {
+                    Ok(ParserToken::BeginObject) | Ok(ParserToken::BeginArray) => self.depth += 1,
+                    Ok(ParserToken::EndObject) | Ok(ParserToken::EndArray) => self.depth = self.depth.saturating_sub(1),
+                    _ => {}
+                }
+                return Some((token, span));
+            }
+            if self.done {
+                return None;
+            }
+            // the keys are always decoded
+            match self.json_lexer.next_token_skipping(skip && self.machine.expects_value()) {
+                Some((token, span)) => {
+                    // a queue never refuses a token
+                    let _ = self.machine.consume(&mut self.queue, token, span);
+                }
+                None => self.done = true,
+            }
+        }
+    }
+
+    /// The containers that are open at this point, outermost first.
+    pub fn open_containers(&self) -> &[OpenContainer] {
+        &self.machine.open_containers
+    }
+
+    /// The number of bytes consumed so far.
+    pub fn byte_offset(&self) -> usize {
+        self.json_lexer.byte_offset()
+    }
+}
+
+impl<R: Read> Iterator for JSONPullParser<R> {
+    type Item = Result<ParserToken, JSONParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event()
+    }
+}
+
+/// A parser that receives the input by chunks (see `JSONLexer::new_feed`): call `feed` for every
+/// chunk, then `finish`. The consumer receives the tokens as soon as they are complete.
+///
+/// ```
+/// # use std::collections::VecDeque;
+/// # use r_json_event_parser::json_lexer::LexerOptions;
+/// # use r_json_event_parser::json_parser::{JSONFeedParser, ParserToken};
+/// let mut parser = JSONFeedParser::new(LexerOptions::default());
+/// let mut tokens = VecDeque::new();
+/// parser.feed(br#"{"a": "b"#, &mut tokens).unwrap();
+/// parser.feed(br#"c"}"#, &mut tokens).unwrap();
+/// parser.finish(&mut tokens).unwrap();
+/// assert_eq!(Some(Ok(ParserToken::StringValue("bc".into()))), tokens.into_iter().nth(3));
+/// ```
+pub struct JSONFeedParser {
+    json_lexer: JSONLexer<FeedBuffer>,
+    machine: ParserMachine,
+    stopped: bool,
+}
+
+impl JSONFeedParser {
+    pub fn new(options: LexerOptions) -> Self {
+        let mut machine = ParserMachine::new();
+        machine.strict = options.strict;
+        JSONFeedParser {
+            json_lexer: JSONLexer::new_feed(options),
+            machine,
+            stopped: false,
+        }
+    }
+
+    pub fn duplicate_key_policy(mut self, policy: DuplicateKeyPolicy) -> Self {
+        self.machine.duplicate_key_policy = policy;
+        self
+    }
+
+    pub fn number_mode(mut self, number_mode: NumberMode) -> Self {
+        self.machine.number_mode = number_mode;
+        self
+    }
+
+    /// See `JSONParser::error_recovery`.
+    pub fn error_recovery(mut self, error_recovery: bool) -> Self {
+        self.machine.error_recovery = error_recovery;
+        self
+    }
+
+    /// See `JSONParser::multi_document`.
+    pub fn multi_document(mut self, multi_document: bool) -> Self {
+        self.machine.multi_document = multi_document;
+        self
+    }
+
+    /// Parse a chunk of the input. See `JSONLexer::feed`. Once the consumer is done (see
+    /// `JSONParseConsumer::is_done`), the chunks are ignored.
+    pub fn feed<C: JSONParseConsumer>(&mut self, chunk: &[u8], consumer: &mut C) -> Result<(), ConsumeError> {
+        if self.stopped {
+            return Ok(());
+        }
+        let mut parser = JSONLexerToParser { consumer, machine: std::mem::replace(&mut self.machine, ParserMachine::new()), stopped: false };
+        let result = self.json_lexer.feed(chunk, &mut parser);
+        self.machine = parser.machine;
+        self.stopped = parser.stopped;
+        if self.stopped { Ok(()) } else { result }
+    }
+
+    /// Parse the end of the input. See `JSONLexer::finish`.
+    pub fn finish<C: JSONParseConsumer>(&mut self, consumer: &mut C) -> Result<(), ConsumeError> {
+        if self.stopped {
+            return Ok(());
+        }
+        let mut parser = JSONLexerToParser { consumer, machine: std::mem::replace(&mut self.machine, ParserMachine::new()), stopped: false };
+        let result = self.json_lexer.finish(&mut parser);
+        self.machine = parser.machine;
+        self.stopped = parser.stopped;
+        if self.stopped { Ok(()) } else { result }
+    }
+
+    /// true if the consumer stopped the parsing (see `JSONParseConsumer::is_done`).
+    pub fn stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// The duplicate keys found so far with the `Warn` policy.
+    pub fn duplicate_keys(&self) -> &[JSONParseError] {
+        &self.machine.duplicate_keys
+    }
+
+    /// The containers that are open at this point, outermost first.
+    pub fn open_containers(&self) -> &[OpenContainer] {
+        &self.machine.open_containers
+    }
+
+    /// The number of bytes consumed so far.
+    pub fn byte_offset(&self) -> usize {
+        self.json_lexer.byte_offset()
+    }
+}
+
+/// Statistics of a `Pipeline` run.
+#[derive(Debug, PartialEq, Default)]
+pub struct ParseStats {
+    pub tokens: usize,
+    pub max_depth: usize,
+}
+
+struct StatsConsumer<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    stats: ParseStats,
+    depth: usize,
+}
+
+impl<'a, C: JSONParseConsumer> StatsConsumer<'a, C> {
+    fn count(&mut self, token: &Result<ParserToken, JSONParseError>) {
+        match token {
+            Ok(ParserToken::BeginObject) | Ok(ParserToken::BeginArray) => {
+                self.depth += 1;
+                if self.depth > self.stats.max_depth {
+                    self.stats.max_depth = self.depth;
+                }
+            }
+            Ok(ParserToken::EndObject) | Ok(ParserToken::EndArray) => {
+                self.depth -= 1;
+            }
+            _ => {}
+        }
+        if token.is_ok() {
+            self.stats.tokens += 1;
+        }
+    }
+}
+
+impl<'a, C: JSONParseConsumer> JSONParseConsumer for StatsConsumer<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.count(&token);
+        self.consumer.consume(token)
+    }
+
+    fn consume_with_span(&mut self, token: Result<ParserToken, JSONParseError>, span: Span) -> Result<(), ConsumeError> {
+        self.count(&token);
+        self.consumer.consume_with_span(token, span)
+    }
+
+    fn consume_interned_key(&mut self, key: Arc<str>, span: Span) -> Result<(), ConsumeError> {
+        self.stats.tokens += 1;
+        self.consumer.consume_interned_key(key, span)
+    }
+
+    fn skip_value(&self) -> bool {
+        self.consumer.skip_value()
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
+
+/// The error of a `Pipeline` run, with the statistics of the tokens read before the error and
+/// the consumer.
+pub struct PipelineError<C> {
+    pub error: ConsumeError,
+    pub stats: ParseStats,
+    pub consumer: C,
+}
+
+impl<C> fmt::Debug for PipelineError<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PipelineError").field("error", &self.error).field("stats", &self.stats).finish_non_exhaustive()
+    }
+}
+
+/// A parser that owns its consumer and gives it back after the run:
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParseError, ParserToken, Pipeline};
+/// # use r_json_event_parser::json_lexer::ConsumeError;
+/// struct Count(usize);
+///
+/// impl JSONParseConsumer for Count {
+///     fn consume(&mut self, _token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+///         self.0 += 1;
+///         Ok(())
+///     }
+/// }
+///
+/// let pipeline = Pipeline::new(ByteSource::new("[1, 2]".as_bytes()), false, Count(0));
+/// let (stats, consumer) = pipeline.run().unwrap();
+/// assert_eq!(6, consumer.0);
+/// assert_eq!(1, stats.max_depth);
+/// ```
+pub struct Pipeline<R: Read, C: JSONParseConsumer> {
+    parser: JSONParser<R>,
+    consumer: C,
+}
+
+impl<R: Read, C: JSONParseConsumer> Pipeline<R, C> {
+    pub fn new(byte_source: ByteSource<R>, ignore_unicode_errs: bool, consumer: C) -> Self {
+        Pipeline::from_parser(JSONParser::new(byte_source, ignore_unicode_errs), consumer)
+    }
+
+    pub fn new_with_options(byte_source: ByteSource<R>, options: LexerOptions, consumer: C) -> Self {
+        Pipeline::from_parser(JSONParser::new_with_options(byte_source, options), consumer)
+    }
+
+    /// A pipeline on a configured parser, e.g. with a `NumberMode` or a `DuplicateKeyPolicy`.
+    pub fn from_parser(parser: JSONParser<R>, consumer: C) -> Self {
+        Pipeline { parser, consumer }
+    }
+
+    /// Parse the document. On error, the consumer and the statistics are in the `PipelineError`.
+    pub fn run(mut self) -> Result<(ParseStats, C), PipelineError<C>> {
+        let mut stats_consumer = StatsConsumer {
+            consumer: &mut self.consumer,
+            stats: ParseStats::default(),
+            depth: 0,
+        };
+        let result = self.parser.parse(&mut stats_consumer);
+        let stats = stats_consumer.stats;
+        match result {
+            Ok(()) => Ok((stats, self.consumer)),
+            Err(error) => Err(PipelineError { error, stats, consumer: self.consumer }),
+        }
+    }
+}
@@ -0,0 +1,545 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Apply a JSON Patch (RFC 6902) or a JSON Merge Patch (RFC 7386) to a stream of tokens.
+//!
+//! Each operation of a JSON Patch is a stage that transforms the tokens of the previous stage,
+//! hence the operations see the document as modified by the previous operations. Only the
+//! values that are tested, moved or copied are buffered; a `move` or a `copy` to a place that
+//! comes before its source in the document also holds the tokens between the two places.
+//!
+//! If an operation fails (path not found, failed `test`), the parsing stops with an error, but
+//! the consumer may have received a part of the document.
+
+use std::fmt;
+use std::io::Read;
+
+use crate::byte_source::ByteSource;
+use crate::json_canonical::canonical_number;
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::json_pointer::{JSONPointer, PathElement, PathTracker};
+use crate::json_transform::{depth_delta, is_value_start};
+use crate::json_value::{JsonValue, ValueBuilderConsumer};
+
+/// A patch that can't be loaded.
+#[derive(Debug, PartialEq, Clone)]
+pub struct PatchError {
+    pub msg: String,
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+/// An operation of a JSON Patch. The paths are JSON Pointers.
+#[derive(Debug, PartialEq, Clone)]
+pub enum PatchOperation {
+    Add { path: String, value: JsonValue },
+    Remove { path: String },
+    Replace { path: String, value: JsonValue },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: JsonValue },
+}
+
+impl PatchOperation {
+    fn from_value(value: &JsonValue) -> Result<PatchOperation, PatchError> {
+        let string = |name: &str| value.get(name).and_then(|v| v.as_str()).map(|s| s.to_string())
+            .ok_or_else(|| PatchError { msg: format!("Missing `{}` string", name) });
+        let operand = || value.get("value").cloned().ok_or_else(|| PatchError { msg: "Missing `value`".into() });
+        Ok(match string("op")?.as_str() {
+            "add" => PatchOperation::Add { path: string("path")?, value: operand()? },
+            "remove" => PatchOperation::Remove { path: string("path")? },
+            "replace" => PatchOperation::Replace { path: string("path")?, value: operand()? },
+            "move" => PatchOperation::Move { from: string("from")?, path: string("path")? },
+            "copy" => PatchOperation::Copy { from: string("from")?, path: string("path")? },
+            "test" => PatchOperation::Test { path: string("path")?, value: operand()? },
+            op => return Err(PatchError { msg: format!("Unknown operation `{}`", op) }),
+        })
+    }
+}
+
+/// A RFC 6902 JSON Patch: a list of operations.
+#[derive(Debug, PartialEq, Clone)]
+pub struct JSONPatch {
+    pub operations: Vec<PatchOperation>,
+}
+
+impl JSONPatch {
+    /// Load a patch from its JSON text.
+    pub fn parse(patch: &str) -> Result<JSONPatch, PatchError> {
+        let value = JsonValue::parse(patch).map_err(|e| PatchError { msg: format!("Invalid JSON: {}", e) })?;
+        JSONPatch::from_value(&value)
+    }
+
+    pub fn from_value(patch: &JsonValue) -> Result<JSONPatch, PatchError> {
+        match patch {
+            JsonValue::Array(operations) => Ok(JSONPatch {
+                operations: operations.iter().map(PatchOperation::from_value).collect::<Result<_, _>>()?,
+            }),
+            _ => Err(PatchError { msg: "A patch must be an array".into() }),
+        }
+    }
+}
+
+/// RFC 6902 equality: the members of the objects are not ordered, the numbers are compared by
+/// value.
+fn json_equal(a: &JsonValue, b: &JsonValue) -> bool {
+    match (a, b) {
+        (JsonValue::Object(x), JsonValue::Object(y)) => {
+            let keys = |members: &[(String, JsonValue)]| {
+                let mut keys: Vec<String> = members.iter().map(|(k, _)| k.clone()).collect();
+                keys.sort();
+                keys.dedup();
+                keys
+            };
+            let keys_a = keys(x);
+            keys_a == keys(y) && keys_a.iter().all(|k| match (a.get(k), b.get(k)) {
+                (Some(v), Some(w)) => json_equal(v, w),
+                _ => false,
+            })
+        }
+        (JsonValue::Array(x), JsonValue::Array(y)) => x.len() == y.len() && x.iter().zip(y).all(|(v, w)| json_equal(v, w)),
+        (JsonValue::Int(x), JsonValue::Int(y)) | (JsonValue::Int(x), JsonValue::Float(y))
+        | (JsonValue::Float(x), JsonValue::Int(y)) | (JsonValue::Float(x), JsonValue::Float(y)) => {
+            x == y || canonical_number(x).is_some_and(|x| Some(x) == canonical_number(y))
+        }
+        _ => a == b,
+    }
+}
+
+fn to_value(tokens: Vec<ParserToken>) -> JsonValue {
+    let mut builder = ValueBuilderConsumer::new();
+    for token in tokens {
+        // a builder never fails
+        let _ = builder.consume(Ok(token));
+    }
+    builder.into_value().unwrap_or(JsonValue::Null)
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Action {
+    /// Add, or replace a member of an object
+    Add,
+    Remove,
+    Replace,
+    Test,
+    /// Capture the value for the next stage (`move` or `copy`)
+    Capture { remove: bool },
+}
+
+/// An operation applied to the tokens.
+struct Stage {
+    name: String,
+    action: Action,
+    pointer: JSONPointer,
+    parent: Option<JSONPointer>,
+    /// The tokens of the value to add; None until the value was captured by the previous stage
+    value: Option<Vec<ParserToken>>,
+    expected: Option<JsonValue>,
+    tracker: PathTracker,
+    done: bool,
+    /// The depth in the value being skipped or captured
+    depth: isize,
+    skip: bool,
+    capture: Option<Vec<ParserToken>>,
+    /// Some(true) if the parent is an open array, Some(false) if it's an open object
+    parent_is_array: Option<bool>,
+    parent_len: usize,
+    /// The tokens held until the value is known, and the place of the value in those tokens
+    held: Vec<ParserToken>,
+    hole: Option<usize>,
+    /// The value captured for the next stage
+    captured: Option<Vec<ParserToken>>,
+}
+
+impl Stage {
+    fn new(name: String, action: Action, path: &str, value: Option<&JsonValue>) -> Result<Stage, PatchError> {
+        let pointer = JSONPointer::parse(path).map_err(|e| PatchError { msg: e.msg })?;
+        if pointer.tokens().is_empty() && matches!(action, Action::Remove | Action::Capture { remove: true }) {
+            return Err(PatchError { msg: format!("{}: can't remove the root", name) });
+        }
+        Ok(Stage {
+            name,
+            action,
+            parent: pointer.parent(),
+            pointer,
+            value: value.map(|v| v.to_tokens()),
+            expected: if action == Action::Test { value.cloned() } else { None },
+            tracker: PathTracker::new(),
+            done: false,
+            depth: 0,
+            skip: false,
+            capture: None,
+            parent_is_array: None,
+            parent_len: 0,
+            held: vec!(),
+            hole: None,
+            captured: None,
+        })
+    }
+
+    fn error(&self, msg: &str) -> ConsumeError {
+        ConsumeError { msg: format!("{}: {}", self.name, msg), line: 0, column: 0, io_error: None }
+    }
+
+    fn emit(&mut self, token: ParserToken, out: &mut Vec<ParserToken>) {
+        if self.hole.is_some() {
+            self.held.push(token);
+        } else {
+            out.push(token);
+        }
+    }
+
+    /// Emit the value to add, or leave a hole if it is not known yet.
+    fn emit_value(&mut self, out: &mut Vec<ParserToken>) {
+        match self.value.clone() {
+            Some(tokens) => for token in tokens {
+                self.emit(token, out);
+            },
+            None => self.hole = Some(self.held.len()),
+        }
+    }
+
+    /// The value captured by the previous stage.
+    fn fill(&mut self, value: Vec<ParserToken>, out: &mut Vec<ParserToken>) {
+        if let Some(hole) = self.hole.take() {
+            let rest = self.held.split_off(hole);
+            out.append(&mut self.held);
+            out.extend(value.iter().cloned());
+            out.extend(rest);
+        }
+        self.value = Some(value);
+    }
+
+    fn finish_value(&mut self) -> Result<(), ConsumeError> {
+        self.done = true;
+        self.skip = false;
+        if let Some(tokens) = self.capture.take() {
+            match self.action {
+                Action::Test => {
+                    let expected = self.expected.as_ref().expect("a test has a value");
+                    if !json_equal(&to_value(tokens), expected) {
+                        return Err(self.error("test failed"));
+                    }
+                }
+                _ => self.captured = Some(tokens),
+            }
+        }
+        Ok(())
+    }
+
+    fn process(&mut self, token: ParserToken, out: &mut Vec<ParserToken>) -> Result<(), ConsumeError> {
+        self.tracker.update(&token);
+        if self.depth > 0 {
+            // in the target value
+            self.depth += depth_delta(&token);
+            if let Some(capture) = self.capture.as_mut() {
+                capture.push(token.clone());
+            }
+            if !self.skip {
+                self.emit(token, out);
+            }
+            if self.depth == 0 {
+                self.finish_value()?;
+            }
+            return Ok(());
+        }
+        if self.done {
+            if token == ParserToken::EndFile && self.hole.is_some() {
+                return Err(self.error("`from` path not found"));
+            }
+            self.emit(token, out);
+            return Ok(());
+        }
+        let path = self.tracker.path().to_vec();
+        let is_parent = |parent: &Option<JSONPointer>, path: &[PathElement]| parent.as_ref().is_some_and(|p| p.matches(path));
+        match &token {
+            ParserToken::EndFile => return Err(self.error("path not found")),
+            ParserToken::Key(_) if self.parent_is_array == Some(false) && self.pointer.matches(&path) => {
+                if !matches!(self.action, Action::Remove | Action::Capture { remove: true }) {
+                    self.emit(token, out);
+                }
+                return Ok(());
+            }
+            ParserToken::EndObject | ParserToken::EndArray if is_parent(&self.parent, &path) => {
+                if self.action == Action::Add {
+                    let last = &self.pointer.tokens()[self.pointer.tokens().len() - 1];
+                    if token == ParserToken::EndObject {
+                        self.emit(ParserToken::Key(last.clone()), out);
+                        self.emit_value(out);
+                    } else if last == "-" || *last == self.parent_len.to_string() {
+                        self.emit_value(out);
+                    } else {
+                        return Err(self.error("index out of bounds"));
+                    }
+                    self.done = true;
+                    self.emit(token, out);
+                    return Ok(());
+                }
+                self.parent_is_array = None;
+            }
+            _ if is_value_start(&token) => {
+                if self.pointer.matches(&path) {
+                    return self.start_target(token, out);
+                }
+                if self.parent_is_array == Some(true) && path.len() == self.pointer.tokens().len()
+                    && is_parent(&self.parent, &path[..path.len() - 1]) {
+                    self.parent_len += 1;
+                }
+                if is_parent(&self.parent, &path) {
+                    match token {
+                        ParserToken::BeginArray => self.parent_is_array = Some(true),
+                        ParserToken::BeginObject => self.parent_is_array = Some(false),
+                        _ => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+        self.emit(token, out);
+        Ok(())
+    }
+
+    /// The first token of the target value.
+    fn start_target(&mut self, token: ParserToken, out: &mut Vec<ParserToken>) -> Result<(), ConsumeError> {
+        if self.action == Action::Add && self.parent_is_array == Some(true) {
+            // insert before the element
+            self.emit_value(out);
+            self.done = true;
+            self.emit(token, out);
+            return Ok(());
+        }
+        self.skip = matches!(self.action, Action::Add | Action::Remove | Action::Replace | Action::Capture { remove: true });
+        if matches!(self.action, Action::Add | Action::Replace) {
+            self.emit_value(out);
+        }
+        if matches!(self.action, Action::Test | Action::Capture { .. }) {
+            self.capture = Some(vec!(token.clone()));
+        }
+        self.depth = depth_delta(&token);
+        if !self.skip {
+            self.emit(token, out);
+        }
+        if self.depth == 0 {
+            self.finish_value()?;
+        }
+        Ok(())
+    }
+}
+
+/// A consumer that applies a `JSONPatch` to the tokens and forwards the result.
+pub struct JSONPatchConsumer<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    stages: Vec<Stage>,
+}
+
+impl<'a, C: JSONParseConsumer> JSONPatchConsumer<'a, C> {
+    pub fn new(consumer: &'a mut C, patch: &JSONPatch) -> Result<Self, PatchError> {
+        let mut stages = vec!();
+        for (i, operation) in patch.operations.iter().enumerate() {
+            let name = |op: &str, path: &str| format!("Operation {} ({} `{}`)", i, op, path);
+            match operation {
+                PatchOperation::Add { path, value } => stages.push(Stage::new(name("add", path), Action::Add, path, Some(value))?),
+                PatchOperation::Remove { path } => stages.push(Stage::new(name("remove", path), Action::Remove, path, None)?),
+                PatchOperation::Replace { path, value } => stages.push(Stage::new(name("replace", path), Action::Replace, path, Some(value))?),
+                PatchOperation::Test { path, value } => stages.push(Stage::new(name("test", path), Action::Test, path, Some(value))?),
+                PatchOperation::Move { from, path } | PatchOperation::Copy { from, path } => {
+                    let remove = matches!(operation, PatchOperation::Move { .. });
+                    let op = if remove { "move" } else { "copy" };
+                    if remove && path.starts_with(&format!("{}/", from)) {
+                        return Err(PatchError { msg: format!("{}: can't move a value into itself", name(op, path)) });
+                    }
+                    stages.push(Stage::new(name(op, from), Action::Capture { remove }, from, None)?);
+                    stages.push(Stage::new(name(op, path), Action::Add, path, None)?);
+                }
+            }
+        }
+        Ok(JSONPatchConsumer { consumer, stages })
+    }
+}
+
+impl<C: JSONParseConsumer> JSONParseConsumer for JSONPatchConsumer<'_, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let mut tokens = match token {
+            Ok(t) => vec!(t),
+            Err(e) => return self.consumer.consume(Err(e)),
+        };
+        let mut captured = None;
+        for stage in self.stages.iter_mut() {
+            let mut out = vec!();
+            if let Some(value) = captured.take() {
+                stage.fill(value, &mut out);
+            }
+            for token in tokens {
+                stage.process(token, &mut out)?;
+            }
+            captured = stage.captured.take();
+            tokens = out;
+        }
+        for token in tokens {
+            self.consumer.consume(Ok(token))?;
+        }
+        Ok(())
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
+
+/// Parse a document, apply the patch and send the tokens to the consumer.
+pub fn apply_patch<R: Read, C: JSONParseConsumer>(byte_source: ByteSource<R>, patch: &JSONPatch, consumer: &mut C) -> Result<(), ConsumeError> {
+    let mut patch_consumer = JSONPatchConsumer::new(consumer, patch)
+        .map_err(|e| ConsumeError { msg: e.msg, line: 0, column: 0, io_error: None })?;
+    JSONParser::new(byte_source, false).parse(&mut patch_consumer)
+}
+
+/// The value without its null members, recursively: the result of the merge of a value with
+/// an empty object.
+fn without_nulls(value: &JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(members) => JsonValue::Object(members.iter()
+            .filter(|(_, v)| !v.is_null())
+            .map(|(k, v)| (k.clone(), without_nulls(v)))
+            .collect()),
+        v => v.clone(),
+    }
+}
+
+/// An open object of the target and the members of the patch that apply to it.
+struct MergeFrame<'p> {
+    patch: Option<&'p JsonValue>,
+    seen: Vec<String>,
+}
+
+/// A consumer that applies a RFC 7386 JSON Merge Patch to the tokens and forwards the result.
+pub struct MergePatchConsumer<'a, 'p, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    patch: &'p JsonValue,
+    /// One frame per open container, None for an array
+    frames: Vec<Option<MergeFrame<'p>>>,
+    /// The patch of the value after the current key
+    next_patch: Option<&'p JsonValue>,
+    /// true if the value after the current key is removed
+    remove: bool,
+    /// The depth in a value that is replaced or removed
+    skip: usize,
+}
+
+impl<'a, 'p, C: JSONParseConsumer> MergePatchConsumer<'a, 'p, C> {
+    pub fn new(consumer: &'a mut C, patch: &'p JsonValue) -> Self {
+        MergePatchConsumer { consumer, patch, frames: vec!(), next_patch: None, remove: false, skip: 0 }
+    }
+
+    fn emit_value(&mut self, value: &JsonValue) -> Result<(), ConsumeError> {
+        for token in without_nulls(value).to_tokens() {
+            self.consumer.consume(Ok(token))?;
+        }
+        Ok(())
+    }
+}
+
+impl<C: JSONParseConsumer> JSONParseConsumer for MergePatchConsumer<'_, '_, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = match token {
+            Ok(t) => t,
+            Err(e) => return self.consumer.consume(Err(e)),
+        };
+        if self.skip > 0 {
+            self.skip = (self.skip as isize + depth_delta(&token)) as usize;
+            return Ok(());
+        }
+        match token {
+            ParserToken::BeginFile => {
+                self.frames.clear();
+                self.next_patch = Some(self.patch);
+            }
+            ParserToken::EndFile => {}
+            ParserToken::Key(ref k) => {
+                let frame = self.frames.last_mut().and_then(|f| f.as_mut()).expect("a key in an object");
+                frame.seen.push(k.clone());
+                match frame.patch.and_then(|p| p.get(k)) {
+                    Some(JsonValue::Null) => {
+                        // remove the member
+                        self.next_patch = None;
+                        self.remove = true;
+                        return Ok(());
+                    }
+                    patch => self.next_patch = patch,
+                }
+            }
+            ParserToken::EndObject => {
+                if let Some(Some(MergeFrame { patch: Some(JsonValue::Object(members)), seen })) = self.frames.pop() {
+                    for (i, (k, v)) in members.iter().enumerate() {
+                        let is_last = members[i + 1..].iter().all(|(l, _)| l != k);
+                        if is_last && !v.is_null() && !seen.contains(k) {
+                            self.consumer.consume(Ok(ParserToken::Key(k.clone())))?;
+                            self.emit_value(v)?;
+                        }
+                    }
+                }
+            }
+            ParserToken::EndArray => {
+                self.frames.pop();
+            }
+            _ => {}
+        }
+        if is_value_start(&token) {
+            if self.remove {
+                self.remove = false;
+                self.skip = depth_delta(&token) as usize;
+                return Ok(());
+            }
+            match self.next_patch.take() {
+                Some(patch @ JsonValue::Object(_)) if token == ParserToken::BeginObject => {
+                    self.frames.push(Some(MergeFrame { patch: Some(patch), seen: vec!() }));
+                }
+                Some(patch) => {
+                    // replace the value
+                    self.emit_value(patch)?;
+                    self.skip = depth_delta(&token) as usize;
+                    return Ok(());
+                }
+                None => match token {
+                    ParserToken::BeginObject => self.frames.push(Some(MergeFrame { patch: None, seen: vec!() })),
+                    ParserToken::BeginArray => self.frames.push(None),
+                    _ => {}
+                },
+            }
+        }
+        self.consumer.consume(Ok(token))
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
+
+/// Parse a document, apply the merge patch and send the tokens to the consumer.
+pub fn apply_merge_patch<R: Read, C: JSONParseConsumer>(byte_source: ByteSource<R>, patch: &JsonValue, consumer: &mut C) -> Result<(), ConsumeError> {
+    JSONParser::new(byte_source, false).parse(&mut MergePatchConsumer::new(consumer, patch))
+}
@@ -0,0 +1,210 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io::Read;
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::json_pointer::{PathElement, PathTracker};
+use crate::json_transform::is_value_start;
+use crate::json_value::{JsonValue, ValueBuilderConsumer};
+
+#[derive(Debug, PartialEq)]
+pub struct JSONPathError {
+    pub msg: String,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Selector {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+impl Selector {
+    fn matches(&self, element: &PathElement) -> bool {
+        match (self, element) {
+            (Selector::Wildcard, _) => true,
+            (Selector::Key(k), PathElement::Key(e)) => k == e,
+            (Selector::Index(i), PathElement::Index(e)) => i == e,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Segment {
+    /// `.name`, `.*`, `[n]`, `['name']`, `[*]`
+    Child(Selector),
+    /// `..name`, `..*`, `..[n]`
+    Descendant(Selector),
+}
+
+/// A compiled JSONPath expression. The supported subset is: the root `$`, the child
+/// selectors `.name`, `['name']`, `["name"]`, `[n]`, the wildcards `.*` and `[*]`, and the
+/// recursive descent `..` followed by a selector, e.g. `$.store.book[*].author` or `$..price`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct JSONPath {
+    segments: Vec<Segment>,
+}
+
+impl JSONPath {
+    pub fn compile(expression: &str) -> Result<Self, JSONPathError> {
+        macro_rules! path_error {
+            ($($arg:tt)*) => {{
+                Err(JSONPathError { msg: format!($($arg)*) })
+            }};
+        }
+
+        let chars: Vec<char> = expression.chars().collect();
+        if chars.first() != Some(&'$') {
+            return path_error!("Path should start with a `$`: `{}`", expression);
+        }
+        let mut segments = vec!();
+        let mut i = 1;
+        while i < chars.len() {
+            let descendant = chars[i] == '.' && chars.get(i + 1) == Some(&'.');
+            let selector = match chars[i] {
+                '.' => {
+                    i += if descendant { 2 } else { 1 };
+                    if chars.get(i) == Some(&'[') && descendant {
+                        continue_bracket(&chars, &mut i, expression)?
+                    } else if chars.get(i) == Some(&'*') {
+                        i += 1;
+                        Selector::Wildcard
+                    } else {
+                        let start = i;
+                        while i < chars.len() && chars[i] != '.' && chars[i] != '[' {
+                            i += 1;
+                        }
+                        if start == i {
+                            return path_error!("Missing name at {} in `{}`", start, expression);
+                        }
+                        Selector::Key(chars[start..i].iter().collect())
+                    }
+                }
+                '[' => continue_bracket(&chars, &mut i, expression)?,
+                c => return path_error!("Unexpected char `{}` at {} in `{}`", c, i, expression),
+            };
+            segments.push(if descendant { Segment::Descendant(selector) } else { Segment::Child(selector) });
+        }
+        Ok(JSONPath { segments })
+    }
+
+    /// true if the value at `path` is selected by the expression.
+    pub fn matches(&self, path: &[PathElement]) -> bool {
+        segments_match(&self.segments, path)
+    }
+}
+
+/// Parse a bracket selector starting at `chars[*i] == '['` and move `i` after the `]`.
+fn continue_bracket(chars: &[char], i: &mut usize, expression: &str) -> Result<Selector, JSONPathError> {
+    let start = *i + 1;
+    let end = match chars[start..].iter().position(|c| *c == ']') {
+        Some(p) => start + p,
+        None => return Err(JSONPathError { msg: format!("Missing `]` in `{}`", expression) }),
+    };
+    let content: String = chars[start..end].iter().collect();
+    *i = end + 1;
+    if content == "*" {
+        return Ok(Selector::Wildcard);
+    }
+    let quoted = content.len() >= 2 && ((content.starts_with('\'') && content.ends_with('\''))
+        || (content.starts_with('"') && content.ends_with('"')));
+    if quoted {
+        return Ok(Selector::Key(content[1..content.len() - 1].to_string()));
+    }
+    match content.parse::<usize>() {
+        Ok(n) => Ok(Selector::Index(n)),
+        Err(_) => Err(JSONPathError { msg: format!("Wrong selector `[{}]` in `{}`", content, expression) }),
+    }
+}
+
+fn segments_match(segments: &[Segment], path: &[PathElement]) -> bool {
+    match segments.split_first() {
+        None => path.is_empty(),
+        Some((Segment::Child(selector), rest)) => {
+            !path.is_empty() && selector.matches(&path[0]) && segments_match(rest, &path[1..])
+        }
+        Some((Segment::Descendant(selector), rest)) => {
+            (0..path.len()).any(|i| selector.matches(&path[i]) && segments_match(rest, &path[i + 1..]))
+        }
+    }
+}
+
+/// A consumer that builds the values selected by a JSONPath expression and gives them to
+/// a callback with their path. When a selected value contains another selected value (e.g.
+/// `$..a` on `{"a": {"a": 1}}`), both are built, the inner value first: the values are given
+/// in the order of their end. The scalars that are not selected are skipped by the lexer.
+pub struct JSONPathConsumer<F: FnMut(&[PathElement], JsonValue)> {
+    path: JSONPath,
+    callback: F,
+    tracker: PathTracker,
+    builders: Vec<(Vec<PathElement>, ValueBuilderConsumer)>,
+}
+
+impl<F: FnMut(&[PathElement], JsonValue)> JSONPathConsumer<F> {
+    pub fn new(path: JSONPath, callback: F) -> Self {
+        JSONPathConsumer {
+            path,
+            callback,
+            tracker: PathTracker::new(),
+            builders: vec!(),
+        }
+    }
+}
+
+impl<F: FnMut(&[PathElement], JsonValue)> JSONParseConsumer for JSONPathConsumer<F> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        self.tracker.update(&token);
+        if is_value_start(&token) && self.path.matches(self.tracker.path()) {
+            self.builders.push((self.tracker.path().to_vec(), ValueBuilderConsumer::new()));
+        }
+        for (_, builder) in self.builders.iter_mut() {
+            builder.consume(Ok(token.clone()))?;
+        }
+        while let Some((_, builder)) = self.builders.last() {
+            if builder.value().is_none() {
+                break;
+            }
+            if let Some((path, builder)) = self.builders.pop() {
+                if let Some(value) = builder.into_value() {
+                    (self.callback)(&path, value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn skip_value(&self) -> bool {
+        self.builders.is_empty() && !self.path.matches(&self.tracker.next_path())
+    }
+}
+
+/// The values selected by a JSONPath expression, with their paths, in the order of their end.
+pub fn select<R: Read>(byte_source: ByteSource<R>, path: &JSONPath) -> Result<Vec<(Vec<PathElement>, JsonValue)>, ConsumeError> {
+    let mut selected = vec!();
+    let mut consumer = JSONPathConsumer::new(path.clone(), |path, value| selected.push((path.to_vec(), value)));
+    JSONParser::new(byte_source, false).parse(&mut consumer)?;
+    Ok(selected)
+}
@@ -0,0 +1,211 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::json_parser::ParserToken;
+
+/// An element of the path of a value: a key in an object or an index in an array.
+#[derive(Debug, PartialEq, Clone)]
+pub enum PathElement {
+    Key(String),
+    Index(usize),
+}
+
+#[derive(Debug, PartialEq)]
+pub struct JSONPointerError {
+    pub msg: String,
+}
+
+/// A RFC 6901 JSON Pointer, e.g. `/items/3/config`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct JSONPointer {
+    tokens: Vec<String>,
+}
+
+impl JSONPointer {
+    pub fn parse(pointer: &str) -> Result<Self, JSONPointerError> {
+        if pointer.is_empty() {
+            return Ok(JSONPointer { tokens: vec!() });
+        }
+        if !pointer.starts_with('/') {
+            return Err(JSONPointerError { msg: format!("Pointer should start with a `/`: `{}`", pointer) });
+        }
+        let mut tokens = vec!();
+        for token in pointer[1..].split('/') {
+            let mut chars = token.chars();
+            let mut unescaped = String::with_capacity(token.len());
+            while let Some(c) = chars.next() {
+                if c == '~' {
+                    match chars.next() {
+                        Some('0') => unescaped.push('~'),
+                        Some('1') => unescaped.push('/'),
+                        _ => {
+                            return Err(JSONPointerError { msg: format!("Wrong escape sequence in `{}`", token) });
+                        }
+                    }
+                } else {
+                    unescaped.push(c);
+                }
+            }
+            tokens.push(unescaped);
+        }
+        Ok(JSONPointer { tokens })
+    }
+
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    /// The pointer of the container of the value, None for the root.
+    pub fn parent(&self) -> Option<JSONPointer> {
+        self.tokens.split_last().map(|(_, parent)| JSONPointer { tokens: parent.to_vec() })
+    }
+
+    /// true if the pointer points to the value at `path`.
+    pub fn matches(&self, path: &[PathElement]) -> bool {
+        self.tokens.len() == path.len() && self.is_prefix_of(path)
+    }
+
+    /// true if the pointer points to the value at `path` or to one of its ancestors.
+    pub fn is_prefix_of(&self, path: &[PathElement]) -> bool {
+        self.tokens.len() <= path.len() &&
+            self.tokens.iter().zip(path.iter()).all(|(t, e)| token_equals(t, e))
+    }
+
+    /// Like `matches`, a `*` token matching any key or index, e.g. `/results/*/id`.
+    pub fn matches_pattern(&self, path: &[PathElement]) -> bool {
+        self.tokens.len() == path.len() && self.is_pattern_prefix_of(path)
+    }
+
+    /// Like `is_prefix_of`, a `*` token matching any key or index.
+    pub fn is_pattern_prefix_of(&self, path: &[PathElement]) -> bool {
+        self.tokens.len() <= path.len() &&
+            self.tokens.iter().zip(path.iter()).all(|(t, e)| token_matches(t, e))
+    }
+}
+
+/// The JSON Pointer of a path, e.g. `/items/3/config`.
+pub fn to_pointer_string(path: &[PathElement]) -> String {
+    let mut pointer = String::new();
+    for element in path {
+        pointer.push('/');
+        match element {
+            PathElement::Key(k) => { pointer.push_str(&k.replace('~', "~0").replace('/', "~1")); }
+            PathElement::Index(i) => { pointer.push_str(&i.to_string()); }
+        }
+    }
+    pointer
+}
+
+fn token_equals(token: &str, element: &PathElement) -> bool {
+    match element {
+        PathElement::Key(k) => k == token,
+        PathElement::Index(i) => token == i.to_string(),
+    }
+}
+
+/// true if the token of a pattern matches the path element: `*` matches any key or index.
+pub fn token_matches(token: &str, element: &PathElement) -> bool {
+    token == "*" || token_equals(token, element)
+}
+
+#[derive(Debug, PartialEq)]
+enum Container {
+    Object,
+    Array,
+}
+
+/// Tracks the path of the current value from the parser tokens.
+///
+/// After `update(token)`, `path()` is the path of the value the token belongs to: the member
+/// for a `Key`, the scalar for a value, the container for `Begin*`/`End*`.
+#[derive(Debug, Default)]
+pub struct PathTracker {
+    path: Vec<PathElement>,
+    containers: Vec<Container>,
+}
+
+impl PathTracker {
+    pub fn new() -> Self {
+        PathTracker {
+            path: vec!(),
+            containers: vec!(),
+        }
+    }
+
+    pub fn path(&self) -> &[PathElement] {
+        &self.path
+    }
+
+    pub fn depth(&self) -> usize {
+        self.containers.len()
+    }
+
+    /// The path of the next value, when the tokens before it (e.g. its key) were seen.
+    pub fn next_path(&self) -> Vec<PathElement> {
+        let mut path = self.path.clone();
+        if self.containers.last() == Some(&Container::Array) {
+            if path.len() == self.containers.len() {
+                if let Some(PathElement::Index(i)) = path.last_mut() {
+                    *i += 1;
+                }
+            } else {
+                path.push(PathElement::Index(0));
+            }
+        }
+        path
+    }
+
+    /// Update the path with the next token. A chunked string starts at its `StringStart`: the
+    /// `StringChunk`s and the `StringEnd` don't change the path.
+    pub fn update(&mut self, token: &ParserToken) {
+        match token {
+            ParserToken::BeginFile | ParserToken::EndFile | ParserToken::StringChunk(_) | ParserToken::StringEnd => {}
+            ParserToken::Key(k) => {
+                if self.path.len() == self.containers.len() {
+                    self.path.pop();
+                }
+                self.path.push(PathElement::Key(k.clone()));
+            }
+            ParserToken::EndObject | ParserToken::EndArray => {
+                if self.path.len() == self.containers.len() {
+                    self.path.pop();
+                }
+                self.containers.pop();
+            }
+            _ => {
+                if self.containers.last() == Some(&Container::Array) {
+                    if self.path.len() == self.containers.len() {
+                        if let Some(PathElement::Index(i)) = self.path.last_mut() {
+                            *i += 1;
+                        }
+                    } else {
+                        self.path.push(PathElement::Index(0));
+                    }
+                }
+                match token {
+                    ParserToken::BeginObject => { self.containers.push(Container::Object) }
+                    ParserToken::BeginArray => { self.containers.push(Container::Array) }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
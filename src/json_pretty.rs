@@ -0,0 +1,92 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io::Write;
+
+use crate::json_lexer::{ConsumeError, Span};
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
+use crate::json_writer::{JSONWriteConsumer, WriteStyle};
+
+/// A consumer that reformats the document, one element per line. By default, the indent is
+/// two spaces, the key is followed by `": "` and the lines end with `"\n"`.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_parser::JSONParser;
+/// # use r_json_event_parser::json_pretty::JSONPrettyPrintConsumer;
+/// let mut consumer = JSONPrettyPrintConsumer::new(vec!()).indent("\t").newline("\r\n");
+/// let mut parser = JSONParser::new(ByteSource::new(r#"{"a":[1]}"#.as_bytes()), false);
+/// parser.parse(&mut consumer).unwrap();
+/// assert_eq!(b"{\r\n\t\"a\": [\r\n\t\t1\r\n\t]\r\n}\r\n".to_vec(), consumer.into_inner());
+/// ```
+pub struct JSONPrettyPrintConsumer<W: Write> {
+    writer: JSONWriteConsumer<W>,
+}
+
+impl<W: Write> JSONPrettyPrintConsumer<W> {
+    pub fn new(destination: W) -> Self {
+        JSONPrettyPrintConsumer {
+            writer: JSONWriteConsumer::with_style(destination, WriteStyle::pretty(2)),
+        }
+    }
+
+    /// The indentation of one level, e.g. `"\t"`.
+    pub fn indent(mut self, indent: &str) -> Self {
+        self.writer.style.indent = indent.into();
+        self
+    }
+
+    /// The text between a key and its value, e.g. `" : "`.
+    pub fn key_value_separator(mut self, separator: &str) -> Self {
+        self.writer.style.key_value_separator = separator.into();
+        self
+    }
+
+    /// The line separator, e.g. `"\r\n"`.
+    pub fn newline(mut self, newline: &str) -> Self {
+        self.writer.style.newline = newline.into();
+        self
+    }
+
+    /// Write the non ASCII chars as `\u` escapes (see `JSONWriteConsumer::ascii_only`).
+    pub fn ascii_only(mut self) -> Self {
+        self.writer = self.writer.ascii_only();
+        self
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.writer.get_ref()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl<W: Write> JSONParseConsumer for JSONPrettyPrintConsumer<W> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.writer.consume(token)
+    }
+
+    fn consume_with_span(&mut self, token: Result<ParserToken, JSONParseError>, span: Span) -> Result<(), ConsumeError> {
+        self.writer.consume_with_span(token, span)
+    }
+}
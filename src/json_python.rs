@@ -0,0 +1,228 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Python bindings: the module `r_json_event_parser` has two iterator classes, `JSONLexer` and
+//! `JSONParser`, that read a binary file-like object (or `bytes`) by blocks and yield
+//! `(event, value)` tuples:
+//!
+//! ```python
+//! from r_json_event_parser import JSONParser
+//!
+//! with open("huge.json", "rb") as f:
+//!     for event, value in JSONParser(f):
+//!         if event == "key":
+//!             ...
+//! ```
+//!
+//! The integers are converted to `int` and the floats to `float`. An error raises a
+//! `JSONParseError(msg, line, column)`, a subclass of `ValueError`. Build the extension with
+//! `maturin build --features python-extension`: maturin adds the `cdylib` crate type.
+
+use std::io::{self, Cursor, Read};
+
+use pyo3::create_exception;
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyFloat, PyInt, PyString};
+use pyo3::IntoPyObjectExt;
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::{JSONLexer, LexerOptions, LexerToken};
+use crate::json_parser::{JSONPullParser, ParserToken};
+
+create_exception!(r_json_event_parser, JSONParseError, PyValueError);
+
+const READ_SIZE: usize = 64 * 1024;
+
+/// The input: `bytes`, `str` or an object with a `read(size)` method that returns `bytes`.
+pub enum PySource {
+    Bytes(Cursor<Vec<u8>>),
+    File(Py<PyAny>),
+}
+
+impl PySource {
+    fn new(source: &Bound<'_, PyAny>) -> PyResult<Self> {
+        if let Ok(bytes) = source.downcast::<PyBytes>() {
+            Ok(PySource::Bytes(Cursor::new(bytes.as_bytes().to_vec())))
+        } else if let Ok(text) = source.downcast::<PyString>() {
+            Ok(PySource::Bytes(Cursor::new(text.to_str()?.as_bytes().to_vec())))
+        } else if source.hasattr("read")? {
+            Ok(PySource::File(source.clone().unbind()))
+        } else {
+            Err(pyo3::exceptions::PyTypeError::new_err("Expected bytes, str or a binary file"))
+        }
+    }
+}
+
+impl Read for PySource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            PySource::Bytes(cursor) => cursor.read(buf),
+            PySource::File(file) => Python::with_gil(|py| {
+                let block = file.call_method1(py, "read", (buf.len().min(READ_SIZE),))
+                    .map_err(|e| io::Error::other(e.to_string()))?;
+                let block = block.downcast_bound::<PyBytes>(py)
+                    .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "read() must return bytes"))?
+                    .as_bytes();
+                let n = block.len().min(buf.len());
+                buf[..n].copy_from_slice(&block[..n]);
+                Ok(n)
+            }),
+        }
+    }
+}
+
+#[pyclass(name = "JSONLexer", module = "r_json_event_parser")]
+pub struct PyJSONLexer {
+    lexer: JSONLexer<PySource>,
+    done: bool,
+}
+
+#[pymethods]
+impl PyJSONLexer {
+    #[new]
+    #[pyo3(signature = (source, strict = false, allow_comments = false))]
+    fn new(source: &Bound<'_, PyAny>, strict: bool, allow_comments: bool) -> PyResult<Self> {
+        let options = LexerOptions { strict, allow_comments, ..LexerOptions::default() };
+        Ok(PyJSONLexer { lexer: JSONLexer::new_with_options(ByteSource::new(PySource::new(source)?), options), done: false })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<(&'static str, PyObject)>> {
+        if self.done {
+            return Ok(None);
+        }
+        match self.lexer.next_token() {
+            None => Ok(None),
+            Some((Ok(token), _)) => Ok(Some(lexer_event(py, token)?)),
+            Some((Err(e), _)) => {
+                self.done = true;
+                Err(JSONParseError::new_err((e.msg, e.line, e.column)))
+            }
+        }
+    }
+}
+
+#[pyclass(name = "JSONParser", module = "r_json_event_parser")]
+pub struct PyJSONParser {
+    parser: JSONPullParser<PySource>,
+    done: bool,
+}
+
+#[pymethods]
+impl PyJSONParser {
+    #[new]
+    #[pyo3(signature = (source, strict = false, allow_comments = false))]
+    fn new(source: &Bound<'_, PyAny>, strict: bool, allow_comments: bool) -> PyResult<Self> {
+        let options = LexerOptions { strict, allow_comments, ..LexerOptions::default() };
+        Ok(PyJSONParser { parser: JSONPullParser::new_with_options(ByteSource::new(PySource::new(source)?), options), done: false })
+    }
+
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<(&'static str, PyObject)>> {
+        if self.done {
+            return Ok(None);
+        }
+        match self.parser.next_event() {
+            None => Ok(None),
+            Some(Ok(token)) => Ok(Some(parser_event(py, token)?)),
+            Some(Err(e)) => {
+                self.done = true;
+                Err(JSONParseError::new_err((e.msg, e.line, e.column)))
+            }
+        }
+    }
+}
+
+fn parser_event(py: Python<'_>, token: ParserToken) -> PyResult<(&'static str, PyObject)> {
+    Ok(match token {
+        ParserToken::BeginFile => ("begin_file", py.None()),
+        ParserToken::EndFile => ("end_file", py.None()),
+        ParserToken::BeginObject => ("begin_object", py.None()),
+        ParserToken::EndObject => ("end_object", py.None()),
+        ParserToken::BeginArray => ("begin_array", py.None()),
+        ParserToken::EndArray => ("end_array", py.None()),
+        ParserToken::Key(s) => ("key", s.into_py_any(py)?),
+        ParserToken::BooleanValue(b) => ("boolean", b.into_py_any(py)?),
+        ParserToken::NullValue => ("null", py.None()),
+        ParserToken::StringValue(s) => ("string", s.into_py_any(py)?),
+        ParserToken::BytesValue(bytes) => ("bytes", PyBytes::new(py, &bytes).into_any().unbind()),
+        ParserToken::IntValue(s) => ("int", int_value(py, &s)?),
+        ParserToken::FloatValue(s) => ("float", float_value(py, &s)?),
+        ParserToken::StringStart => ("string_start", py.None()),
+        ParserToken::StringChunk(bytes) => ("string_chunk", PyBytes::new(py, &bytes).into_any().unbind()),
+        ParserToken::StringEnd => ("string_end", py.None()),
+    })
+}
+
+fn lexer_event(py: Python<'_>, token: LexerToken) -> PyResult<(&'static str, PyObject)> {
+    Ok(match token {
+        LexerToken::BeginFile => ("begin_file", py.None()),
+        LexerToken::EndFile => ("end_file", py.None()),
+        LexerToken::BeginObject => ("begin_object", py.None()),
+        LexerToken::EndObject => ("end_object", py.None()),
+        LexerToken::BeginArray => ("begin_array", py.None()),
+        LexerToken::EndArray => ("end_array", py.None()),
+        LexerToken::NameSeparator => ("name_separator", py.None()),
+        LexerToken::ValueSeparator => ("value_separator", py.None()),
+        LexerToken::BooleanValue(b) => ("boolean", b.into_py_any(py)?),
+        LexerToken::NullValue => ("null", py.None()),
+        LexerToken::String(s) => ("string", s.into_py_any(py)?),
+        LexerToken::Bytes(bytes) => ("bytes", PyBytes::new(py, &bytes).into_any().unbind()),
+        LexerToken::RawString { decoded, raw } => ("raw_string", (decoded, raw).into_py_any(py)?),
+        LexerToken::IntValue(s) => ("int", int_value(py, &s)?),
+        LexerToken::FloatValue(s) => ("float", float_value(py, &s)?),
+        LexerToken::Comment(s) => ("comment", s.into_py_any(py)?),
+        LexerToken::StringStart => ("string_start", py.None()),
+        LexerToken::StringChunk(bytes) => ("string_chunk", PyBytes::new(py, &bytes).into_any().unbind()),
+        LexerToken::StringEnd => ("string_end", py.None()),
+        LexerToken::SkippedString => ("skipped_string", py.None()),
+    })
+}
+
+/// A Python `int`, without a loss for the big integers.
+fn int_value(py: Python<'_>, text: &str) -> PyResult<PyObject> {
+    match text.parse::<i64>() {
+        Ok(n) => n.into_py_any(py),
+        Err(_) => Ok(py.get_type::<PyInt>().call1((text,))?.unbind()),
+    }
+}
+
+fn float_value(py: Python<'_>, text: &str) -> PyResult<PyObject> {
+    match text.parse::<f64>() {
+        Ok(x) => x.into_py_any(py),
+        Err(_) => Ok(py.get_type::<PyFloat>().call1((text,))?.unbind()),
+    }
+}
+
+#[pymodule]
+fn r_json_event_parser(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyJSONLexer>()?;
+    m.add_class::<PyJSONParser>()?;
+    m.add("JSONParseError", m.py().get_type::<JSONParseError>())?;
+    Ok(())
+}
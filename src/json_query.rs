@@ -0,0 +1,180 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cell::RefCell;
+use std::io::Read;
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::json_pointer::{JSONPointer, PathElement, PathTracker};
+
+/// A value found by `find_first`: the tokens of the value and the number of bytes read. Since
+/// the end of a number is detected on the next byte, this byte is included in the offset.
+#[derive(Debug, PartialEq)]
+pub struct Found {
+    pub tokens: Vec<ParserToken>,
+    pub offset: usize,
+}
+
+struct FindConsumer<P: FnMut(&[PathElement]) -> bool> {
+    // `skip_value` takes `&self`
+    predicate: RefCell<P>,
+    tracker: PathTracker,
+    tokens: Vec<ParserToken>,
+    depth: usize,
+    found: bool,
+}
+
+impl<P: FnMut(&[PathElement]) -> bool> JSONParseConsumer for FindConsumer<P> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        self.tracker.update(&token);
+        match token {
+            ParserToken::BeginFile | ParserToken::EndFile | ParserToken::Key(_) | ParserToken::StringChunk(_) => {
+                if !self.tokens.is_empty() {
+                    self.tokens.push(token);
+                }
+                Ok(())
+            }
+            // a chunked string is read up to its end
+            ParserToken::BeginObject | ParserToken::BeginArray | ParserToken::StringStart => {
+                if self.tokens.is_empty() && !(self.predicate.borrow_mut())(self.tracker.path()) {
+                    return Ok(());
+                }
+                self.depth += 1;
+                self.tokens.push(token);
+                Ok(())
+            }
+            ParserToken::EndObject | ParserToken::EndArray | ParserToken::StringEnd => {
+                if self.tokens.is_empty() {
+                    return Ok(());
+                }
+                self.depth -= 1;
+                self.tokens.push(token);
+                self.found = self.depth == 0;
+                Ok(())
+            }
+            _ => {
+                if self.tokens.is_empty() {
+                    if !(self.predicate.borrow_mut())(self.tracker.path()) {
+                        return Ok(());
+                    }
+                    self.tokens.push(token);
+                    self.found = true;
+                    Ok(())
+                } else {
+                    self.tokens.push(token);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    /// Outside of the value, a scalar that does not match is not needed.
+    fn skip_value(&self) -> bool {
+        self.tokens.is_empty() && !(self.predicate.borrow_mut())(&self.tracker.next_path())
+    }
+
+    fn is_done(&self) -> bool {
+        self.found
+    }
+}
+
+/// Find the first value whose path matches the predicate. The parsing stops as soon as the value
+/// is complete.
+pub fn find_first<R: Read, P: FnMut(&[PathElement]) -> bool>(byte_source: ByteSource<R>, predicate: P) -> Result<Option<Found>, ConsumeError> {
+    let mut consumer = FindConsumer {
+        predicate: RefCell::new(predicate),
+        tracker: PathTracker::new(),
+        tokens: vec!(),
+        depth: 0,
+        found: false,
+    };
+    let mut parser = JSONParser::new(byte_source, false);
+    parser.parse(&mut consumer)?;
+    if !consumer.found {
+        return Ok(None);
+    }
+    Ok(Some(Found {
+        tokens: consumer.tokens,
+        offset: parser.byte_offset(),
+    }))
+}
+
+/// Find the first value at a given JSON Pointer.
+pub fn find_first_at<R: Read>(byte_source: ByteSource<R>, pointer: &JSONPointer) -> Result<Option<Found>, ConsumeError> {
+    find_first(byte_source, |path| pointer.matches(path))
+}
+
+/// A scalar value found by `first_value`. The numbers are lexemes.
+#[derive(Debug, PartialEq, Clone)]
+pub enum JsonScalar {
+    String(String),
+    Number(String),
+    Bool(bool),
+    Null,
+}
+
+impl JsonScalar {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonScalar::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonScalar::Number(n) => n.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+/// The scalar at a JSON Pointer, e.g. `/a/b/0`, None if there is no value or if the value is a
+/// container. The parsing stops as soon as the value is read.
+pub fn first_value<R: Read>(reader: R, pointer: &str) -> Result<Option<JsonScalar>, ConsumeError> {
+    let pointer = JSONPointer::parse(pointer)
+        .map_err(|e| ConsumeError { msg: e.msg, line: 0, column: 0, io_error: None })?;
+    let found = find_first_at(ByteSource::new(reader), &pointer)?;
+    Ok(found.and_then(|found| match found.tokens.into_iter().next() {
+        Some(ParserToken::StringValue(s)) => Some(JsonScalar::String(s)),
+        Some(ParserToken::BytesValue(b)) => Some(JsonScalar::String(String::from_utf8_lossy(&b).into_owned())),
+        Some(ParserToken::IntValue(n)) | Some(ParserToken::FloatValue(n)) => Some(JsonScalar::Number(n)),
+        Some(ParserToken::BooleanValue(b)) => Some(JsonScalar::Bool(b)),
+        Some(ParserToken::NullValue) => Some(JsonScalar::Null),
+        _ => None,
+    }))
+}
+
+/// The string at a JSON Pointer, None if there is no value or if the value is not a string.
+pub fn get_string_at<R: Read>(reader: R, pointer: &str) -> Result<Option<String>, ConsumeError> {
+    Ok(match first_value(reader, pointer)? {
+        Some(JsonScalar::String(s)) => Some(s),
+        _ => None,
+    })
+}
+
+/// The number at a JSON Pointer, None if there is no value or if the value is not a number.
+pub fn get_number_at<R: Read>(reader: R, pointer: &str) -> Result<Option<f64>, ConsumeError> {
+    Ok(first_value(reader, pointer)?.and_then(|v| v.as_f64()))
+}
@@ -0,0 +1,128 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Mask the values of some members before they reach a consumer, e.g. to scrub personal data
+//! while converting logs.
+
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
+use crate::json_pointer::{JSONPointer, JSONPointerError, PathElement, PathTracker, token_matches};
+use crate::json_transform::{depth_delta, is_value_start};
+
+/// The values to redact.
+#[derive(Debug, PartialEq, Clone)]
+pub enum RedactPattern {
+    /// A JSON Pointer from the root, a `*` token matching any key or index, e.g.
+    /// `/users/*/ssn`.
+    Pointer(JSONPointer),
+    /// The last elements of the path, separated by dots, a `*` matching any key or index, e.g.
+    /// `password` (any `password` member) or `*.token` (any `token` member that is not at the
+    /// root).
+    Suffix(Vec<String>),
+}
+
+impl RedactPattern {
+    /// A pattern that starts with a `/` is a `Pointer`, any other pattern is a `Suffix`.
+    pub fn parse(pattern: &str) -> Result<RedactPattern, JSONPointerError> {
+        if pattern.starts_with('/') {
+            Ok(RedactPattern::Pointer(JSONPointer::parse(pattern)?))
+        } else if pattern.is_empty() {
+            Err(JSONPointerError { msg: "Empty pattern".into() })
+        } else {
+            Ok(RedactPattern::Suffix(pattern.split('.').map(|t| t.to_string()).collect()))
+        }
+    }
+
+    pub fn matches(&self, path: &[PathElement]) -> bool {
+        let tokens = match self {
+            RedactPattern::Pointer(pointer) => {
+                if pointer.tokens().len() != path.len() {
+                    return false;
+                }
+                pointer.tokens()
+            }
+            RedactPattern::Suffix(tokens) => tokens,
+        };
+        tokens.len() <= path.len() &&
+            tokens.iter().rev().zip(path.iter().rev()).all(|(t, e)| token_matches(t, e))
+    }
+}
+
+/// A consumer that replaces the values matching some patterns, scalars or containers, with a
+/// mask string, and forwards everything else. The lexer skips the strings and numbers of the
+/// redacted values without decoding them.
+pub struct RedactConsumer<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+    patterns: Vec<RedactPattern>,
+    mask: String,
+    tracker: PathTracker,
+    /// The depth in a redacted container
+    depth: isize,
+}
+
+impl<'a, C: JSONParseConsumer> RedactConsumer<'a, C> {
+    pub fn new(consumer: &'a mut C, patterns: Vec<RedactPattern>) -> Self {
+        RedactConsumer {
+            consumer,
+            patterns,
+            mask: "***".into(),
+            tracker: PathTracker::new(),
+            depth: 0,
+        }
+    }
+
+    /// The string that replaces the values (default: `***`).
+    pub fn mask(mut self, mask: &str) -> Self {
+        self.mask = mask.into();
+        self
+    }
+
+    fn is_redacted(&self, path: &[PathElement]) -> bool {
+        self.patterns.iter().any(|p| p.matches(path))
+    }
+}
+
+impl<'a, C: JSONParseConsumer> JSONParseConsumer for RedactConsumer<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = match token {
+            Ok(t) => t,
+            Err(e) => return self.consumer.consume(Err(e)),
+        };
+        self.tracker.update(&token);
+        if self.depth > 0 {
+            self.depth += depth_delta(&token);
+            return Ok(());
+        }
+        if is_value_start(&token) && self.is_redacted(self.tracker.path()) {
+            self.depth = depth_delta(&token);
+            return self.consumer.consume(Ok(ParserToken::StringValue(self.mask.clone())));
+        }
+        self.consumer.consume(Ok(token))
+    }
+
+    fn skip_value(&self) -> bool {
+        self.depth > 0 || self.is_redacted(&self.tracker.next_path()) || self.consumer.skip_value()
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
@@ -0,0 +1,355 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
+use crate::json_pointer::{JSONPointer, PathTracker, to_pointer_string};
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum ValueType {
+    Object,
+    Array,
+    String,
+    Int,
+    Float,
+    Boolean,
+    Null,
+}
+
+impl ValueType {
+    /// The type of the value that begins with this token, if any.
+    pub fn of(token: &ParserToken) -> Option<ValueType> {
+        match token {
+            ParserToken::BeginObject => Some(ValueType::Object),
+            ParserToken::BeginArray => Some(ValueType::Array),
+            ParserToken::StringValue(_) | ParserToken::BytesValue(_) | ParserToken::StringStart => Some(ValueType::String),
+            ParserToken::IntValue(_) => Some(ValueType::Int),
+            ParserToken::FloatValue(_) => Some(ValueType::Float),
+            ParserToken::BooleanValue(_) => Some(ValueType::Boolean),
+            ParserToken::NullValue => Some(ValueType::Null),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValueType::Object => "object",
+            ValueType::Array => "array",
+            ValueType::String => "string",
+            ValueType::Int => "int",
+            ValueType::Float => "float",
+            ValueType::Boolean => "boolean",
+            ValueType::Null => "null",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Default)]
+pub struct KeyUsage {
+    pub count: usize,
+    pub types: BTreeSet<ValueType>,
+    pub example_paths: Vec<String>,
+}
+
+/// The usage of every distinct key, sorted by key.
+#[derive(Debug, PartialEq, Default)]
+pub struct KeyUsageReport {
+    pub keys: BTreeMap<String, KeyUsage>,
+}
+
+impl fmt::Display for KeyUsageReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (key, usage) in self.keys.iter() {
+            let types: Vec<&str> = usage.types.iter().map(|t| t.name()).collect();
+            writeln!(f, "{}\t{}\t{}\t{}", key, usage.count, types.join("|"), usage.example_paths.join(" "))?;
+        }
+        Ok(())
+    }
+}
+
+/// A consumer that counts the keys and collects the types of their values. The same consumer may
+/// be used for several documents (e.g. the records of a NDJSON file) to get a global report.
+pub struct KeyUsageConsumer {
+    tracker: PathTracker,
+    max_example_paths: usize,
+    report: KeyUsageReport,
+    cur_key: Option<String>,
+}
+
+impl KeyUsageConsumer {
+    pub fn new(max_example_paths: usize) -> Self {
+        KeyUsageConsumer {
+            tracker: PathTracker::new(),
+            max_example_paths,
+            report: KeyUsageReport::default(),
+            cur_key: None,
+        }
+    }
+
+    pub fn report(&self) -> &KeyUsageReport {
+        &self.report
+    }
+
+    pub fn into_report(self) -> KeyUsageReport {
+        self.report
+    }
+}
+
+impl JSONParseConsumer for KeyUsageConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        self.tracker.update(&token);
+        if let ParserToken::Key(k) = token {
+            self.cur_key = Some(k);
+        } else if let Some(value_type) = ValueType::of(&token) {
+            if let Some(key) = self.cur_key.take() {
+                let usage = self.report.keys.entry(key).or_default();
+                usage.count += 1;
+                usage.types.insert(value_type);
+                if usage.example_paths.len() < self.max_example_paths {
+                    let path = to_pointer_string(self.tracker.path());
+                    if !usage.example_paths.contains(&path) {
+                        usage.example_paths.push(path);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The most frequent values, by decreasing count.
+#[derive(Debug, PartialEq, Default)]
+pub struct ValueFrequencyReport {
+    pub values: Vec<(ValueType, String, usize)>,
+    /// The number of occurrences of the values that were truncated.
+    pub others: usize,
+    pub total: usize,
+}
+
+impl fmt::Display for ValueFrequencyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (value_type, value, count) in self.values.iter() {
+            match value_type {
+                ValueType::Object => writeln!(f, "{}\t{{...}}", count)?,
+                ValueType::Array => writeln!(f, "{}\t[...]", count)?,
+                _ => writeln!(f, "{}\t{}", count, value)?,
+            }
+        }
+        if self.others > 0 {
+            writeln!(f, "{}\t(others)", self.others)?;
+        }
+        Ok(())
+    }
+}
+
+/// A consumer that counts the distinct values found at a pointer. The same consumer may
+/// be used for several documents (e.g. the records of a NDJSON file).
+pub struct ValueFrequencyConsumer {
+    pointer: JSONPointer,
+    tracker: PathTracker,
+    counts: HashMap<(ValueType, String), usize>,
+    /// The chunks of a string found at the pointer
+    chunks: Option<Vec<u8>>,
+}
+
+impl ValueFrequencyConsumer {
+    pub fn new(pointer: JSONPointer) -> Self {
+        ValueFrequencyConsumer {
+            pointer,
+            tracker: PathTracker::new(),
+            counts: HashMap::new(),
+            chunks: None,
+        }
+    }
+
+    /// The `top_n` most frequent values. Ties are sorted by value.
+    pub fn report(&self, top_n: usize) -> ValueFrequencyReport {
+        let mut values: Vec<(ValueType, String, usize)> = self.counts.iter()
+            .map(|((t, v), c)| (*t, v.clone(), *c)).collect();
+        values.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| (a.0, &a.1).cmp(&(b.0, &b.1))));
+        let total = values.iter().map(|v| v.2).sum();
+        let others = values.iter().skip(top_n).map(|v| v.2).sum();
+        values.truncate(top_n);
+        ValueFrequencyReport { values, others, total }
+    }
+}
+
+impl JSONParseConsumer for ValueFrequencyConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        self.tracker.update(&token);
+        if let Some(chunks) = self.chunks.as_mut() {
+            match token {
+                ParserToken::StringChunk(chunk) => chunks.extend(chunk),
+                _ => {
+                    let value = String::from_utf8_lossy(chunks).into_owned();
+                    self.chunks = None;
+                    *self.counts.entry((ValueType::String, value)).or_insert(0) += 1;
+                }
+            }
+            return Ok(());
+        }
+        if let Some(value_type) = ValueType::of(&token) {
+            if self.pointer.matches(self.tracker.path()) {
+                let value = match token {
+                    ParserToken::StringStart => {
+                        self.chunks = Some(vec!());
+                        return Ok(());
+                    }
+                    ParserToken::StringValue(s) | ParserToken::IntValue(s) | ParserToken::FloatValue(s) => s,
+                    ParserToken::BytesValue(b) => String::from_utf8_lossy(&b).into_owned(),
+                    ParserToken::BooleanValue(b) => b.to_string(),
+                    ParserToken::NullValue => "null".into(),
+                    _ => String::new(),
+                };
+                *self.counts.entry((value_type, value)).or_insert(0) += 1;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The statistics of a document (see `JSONStatsConsumer`).
+#[derive(Debug, PartialEq, Default)]
+pub struct JSONStatsReport {
+    /// The number of tokens of each type, by token name (`BeginObject`, `Key`, ...)
+    pub token_counts: BTreeMap<&'static str, usize>,
+    pub max_depth: usize,
+    pub objects: usize,
+    pub arrays: usize,
+    /// The number of occurrences of every distinct key
+    pub keys: BTreeMap<String, usize>,
+    /// The total length of the string values, in bytes
+    pub string_bytes: usize,
+    /// The lexeme of the smallest number
+    pub min_number: Option<String>,
+    /// The lexeme of the largest number
+    pub max_number: Option<String>,
+}
+
+impl fmt::Display for JSONStatsReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "objects\t{}", self.objects)?;
+        writeln!(f, "arrays\t{}", self.arrays)?;
+        writeln!(f, "max depth\t{}", self.max_depth)?;
+        writeln!(f, "string bytes\t{}", self.string_bytes)?;
+        if let (Some(min), Some(max)) = (&self.min_number, &self.max_number) {
+            writeln!(f, "min number\t{}", min)?;
+            writeln!(f, "max number\t{}", max)?;
+        }
+        for (name, count) in self.token_counts.iter() {
+            writeln!(f, "token {}\t{}", name, count)?;
+        }
+        for (key, count) in self.keys.iter() {
+            writeln!(f, "key {}\t{}", key, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// A consumer that computes the statistics of the documents: counts of tokens, depth, keys,
+/// size of the strings and range of the numbers. Useful to explore an unknown dump.
+#[derive(Default)]
+pub struct JSONStatsConsumer {
+    report: JSONStatsReport,
+    depth: usize,
+    min: Option<f64>,
+    max: Option<f64>,
+}
+
+impl JSONStatsConsumer {
+    pub fn new() -> Self {
+        JSONStatsConsumer::default()
+    }
+
+    pub fn report(&self) -> &JSONStatsReport {
+        &self.report
+    }
+
+    pub fn into_report(self) -> JSONStatsReport {
+        self.report
+    }
+
+    fn number(&mut self, lexeme: &str) {
+        let x = match lexeme.parse::<f64>() {
+            Ok(x) if !x.is_nan() => x,
+            _ => return,
+        };
+        if self.min.is_none_or(|min| x < min) {
+            self.min = Some(x);
+            self.report.min_number = Some(lexeme.to_string());
+        }
+        if self.max.is_none_or(|max| x > max) {
+            self.max = Some(x);
+            self.report.max_number = Some(lexeme.to_string());
+        }
+    }
+}
+
+fn token_name(token: &ParserToken) -> &'static str {
+    match token {
+        ParserToken::BeginFile => "BeginFile",
+        ParserToken::EndFile => "EndFile",
+        ParserToken::BeginObject => "BeginObject",
+        ParserToken::EndObject => "EndObject",
+        ParserToken::BeginArray => "BeginArray",
+        ParserToken::EndArray => "EndArray",
+        ParserToken::Key(_) => "Key",
+        ParserToken::BooleanValue(_) => "BooleanValue",
+        ParserToken::NullValue => "NullValue",
+        ParserToken::StringValue(_) => "StringValue",
+        ParserToken::BytesValue(_) => "BytesValue",
+        ParserToken::IntValue(_) => "IntValue",
+        ParserToken::FloatValue(_) => "FloatValue",
+        ParserToken::StringStart => "StringStart",
+        ParserToken::StringChunk(_) => "StringChunk",
+        ParserToken::StringEnd => "StringEnd",
+    }
+}
+
+impl JSONParseConsumer for JSONStatsConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        *self.report.token_counts.entry(token_name(&token)).or_insert(0) += 1;
+        match token {
+            ParserToken::BeginObject | ParserToken::BeginArray => {
+                if token == ParserToken::BeginObject {
+                    self.report.objects += 1;
+                } else {
+                    self.report.arrays += 1;
+                }
+                self.depth += 1;
+                self.report.max_depth = self.report.max_depth.max(self.depth);
+            }
+            ParserToken::EndObject | ParserToken::EndArray => self.depth -= 1,
+            ParserToken::Key(k) => *self.report.keys.entry(k).or_insert(0) += 1,
+            ParserToken::StringValue(s) => self.report.string_bytes += s.len(),
+            ParserToken::BytesValue(b) | ParserToken::StringChunk(b) => self.report.string_bytes += b.len(),
+            ParserToken::IntValue(s) | ParserToken::FloatValue(s) => self.number(&s),
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,490 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A streaming validation of the documents against a JSON Schema: the schema is loaded once, and
+//! a `SchemaValidatorConsumer` checks every token as it comes, without building the document.
+//!
+//! The supported keywords are `type`, `enum`, `const`, `properties`, `required`,
+//! `additionalProperties`, `items`, `minItems`, `maxItems`, `minLength`, `maxLength`,
+//! `pattern`, `minimum`, `maximum`, `exclusiveMinimum` and `exclusiveMaximum`, and the boolean
+//! schemas. The other keywords are ignored. Since the values are not built, an `enum` or a
+//! `const` with an object or an array only checks the type of the value.
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io::Read;
+
+use regex::Regex;
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::{ConsumeError, Span};
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::json_pointer::{to_pointer_string, PathTracker};
+use crate::json_value::JsonValue;
+
+/// A schema that can't be loaded.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SchemaError {
+    pub msg: String,
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.msg)
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum SchemaType {
+    Object,
+    Array,
+    String,
+    Integer,
+    Number,
+    Boolean,
+    Null,
+}
+
+impl SchemaType {
+    fn parse(name: &str) -> Result<SchemaType, SchemaError> {
+        Ok(match name {
+            "object" => SchemaType::Object,
+            "array" => SchemaType::Array,
+            "string" => SchemaType::String,
+            "integer" => SchemaType::Integer,
+            "number" => SchemaType::Number,
+            "boolean" => SchemaType::Boolean,
+            "null" => SchemaType::Null,
+            _ => return Err(SchemaError { msg: format!("Unknown type `{}`", name) }),
+        })
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            SchemaType::Object => "object",
+            SchemaType::Array => "array",
+            SchemaType::String => "string",
+            SchemaType::Integer => "integer",
+            SchemaType::Number => "number",
+            SchemaType::Boolean => "boolean",
+            SchemaType::Null => "null",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Schema {
+    /// The `false` schema
+    reject: bool,
+    types: Option<Vec<SchemaType>>,
+    enum_values: Option<Vec<JsonValue>>,
+    properties: Vec<(String, Schema)>,
+    required: Vec<String>,
+    /// None: any property
+    additional_properties: Option<Box<Schema>>,
+    items: Option<Box<Schema>>,
+    min_items: Option<usize>,
+    max_items: Option<usize>,
+    min_length: Option<usize>,
+    max_length: Option<usize>,
+    pattern: Option<Regex>,
+    minimum: Option<f64>,
+    maximum: Option<f64>,
+    exclusive_minimum: Option<f64>,
+    exclusive_maximum: Option<f64>,
+}
+
+fn schema_error(keyword: &str, expected: &str) -> SchemaError {
+    SchemaError { msg: format!("`{}` must be {}", keyword, expected) }
+}
+
+fn size(value: &JsonValue, keyword: &str) -> Result<usize, SchemaError> {
+    value.as_i64().filter(|n| *n >= 0).map(|n| n as usize).ok_or_else(|| schema_error(keyword, "a non-negative integer"))
+}
+
+fn number(value: &JsonValue, keyword: &str) -> Result<f64, SchemaError> {
+    value.as_f64().ok_or_else(|| schema_error(keyword, "a number"))
+}
+
+impl Schema {
+    fn from_value(value: &JsonValue) -> Result<Schema, SchemaError> {
+        let members = match value {
+            JsonValue::Bool(b) => return Ok(Schema { reject: !b, ..Default::default() }),
+            JsonValue::Object(members) => members,
+            _ => return Err(SchemaError { msg: "A schema must be an object or a boolean".into() }),
+        };
+        let mut schema = Schema::default();
+        for (keyword, value) in members {
+            match keyword.as_str() {
+                "type" => schema.types = Some(match value {
+                    JsonValue::String(name) => vec!(SchemaType::parse(name)?),
+                    JsonValue::Array(names) => names.iter()
+                        .map(|name| name.as_str().ok_or_else(|| schema_error("type", "a string or an array of strings")).and_then(SchemaType::parse))
+                        .collect::<Result<_, _>>()?,
+                    _ => return Err(schema_error("type", "a string or an array of strings")),
+                }),
+                "enum" => match value {
+                    JsonValue::Array(values) => schema.enum_values = Some(values.clone()),
+                    _ => return Err(schema_error("enum", "an array")),
+                },
+                "const" => schema.enum_values = Some(vec!(value.clone())),
+                "properties" => match value {
+                    JsonValue::Object(properties) => for (name, property) in properties {
+                        schema.properties.push((name.clone(), Schema::from_value(property)?));
+                    },
+                    _ => return Err(schema_error("properties", "an object")),
+                },
+                "required" => match value {
+                    JsonValue::Array(names) => schema.required = names.iter()
+                        .map(|name| name.as_str().map(|s| s.to_string()).ok_or_else(|| schema_error("required", "an array of strings")))
+                        .collect::<Result<_, _>>()?,
+                    _ => return Err(schema_error("required", "an array of strings")),
+                },
+                "additionalProperties" => schema.additional_properties = Some(Box::new(Schema::from_value(value)?)),
+                "items" => schema.items = Some(Box::new(Schema::from_value(value)?)),
+                "minItems" => schema.min_items = Some(size(value, keyword)?),
+                "maxItems" => schema.max_items = Some(size(value, keyword)?),
+                "minLength" => schema.min_length = Some(size(value, keyword)?),
+                "maxLength" => schema.max_length = Some(size(value, keyword)?),
+                "pattern" => {
+                    let pattern = value.as_str().ok_or_else(|| schema_error("pattern", "a string"))?;
+                    schema.pattern = Some(Regex::new(pattern).map_err(|e| SchemaError { msg: format!("Invalid pattern `{}`: {}", pattern, e) })?);
+                }
+                "minimum" => schema.minimum = Some(number(value, keyword)?),
+                "maximum" => schema.maximum = Some(number(value, keyword)?),
+                "exclusiveMinimum" => schema.exclusive_minimum = Some(number(value, keyword)?),
+                "exclusiveMaximum" => schema.exclusive_maximum = Some(number(value, keyword)?),
+                _ => {}
+            }
+        }
+        Ok(schema)
+    }
+
+    /// The schema of the value of a property.
+    fn property(&self, name: &str) -> Option<&Schema> {
+        match self.properties.iter().find(|(n, _)| n == name) {
+            Some((_, schema)) => Some(schema),
+            None => self.additional_properties.as_deref(),
+        }
+    }
+
+    /// Check a value, given its first token. The content of a string sent in chunks is checked
+    /// later, by `check_content`.
+    fn check_start(&self, token: &ParserToken, violations: &mut Vec<String>) {
+        if self.reject {
+            violations.push("No value is allowed here".into());
+            return;
+        }
+        if let Some(types) = &self.types {
+            if !types.iter().any(|t| type_matches(*t, token)) {
+                let names: Vec<&str> = types.iter().map(|t| t.name()).collect();
+                violations.push(format!("Expected {}", names.join(" or ")));
+            }
+        }
+        if *token != ParserToken::StringStart {
+            self.check_content(token, violations);
+        }
+    }
+
+    /// Check the enum and the bounds of a value, given its first token.
+    fn check_content(&self, token: &ParserToken, violations: &mut Vec<String>) {
+        if let Some(values) = &self.enum_values {
+            if !values.iter().any(|v| enum_matches(v, token)) {
+                violations.push("Value not in the enum".into());
+            }
+        }
+        match token {
+            ParserToken::IntValue(s) | ParserToken::FloatValue(s) => {
+                if let Ok(x) = s.parse::<f64>() {
+                    self.check_number(x, violations);
+                }
+            }
+            ParserToken::StringValue(s) => self.check_string(s, violations),
+            ParserToken::BytesValue(b) => self.check_string(&String::from_utf8_lossy(b), violations),
+            _ => {}
+        }
+    }
+
+    fn check_number(&self, x: f64, violations: &mut Vec<String>) {
+        if let Some(min) = self.minimum.filter(|min| x < *min) {
+            violations.push(format!("Less than the minimum {}", min));
+        }
+        if let Some(max) = self.maximum.filter(|max| x > *max) {
+            violations.push(format!("Greater than the maximum {}", max));
+        }
+        if let Some(min) = self.exclusive_minimum.filter(|min| x <= *min) {
+            violations.push(format!("Not greater than the exclusive minimum {}", min));
+        }
+        if let Some(max) = self.exclusive_maximum.filter(|max| x >= *max) {
+            violations.push(format!("Not less than the exclusive maximum {}", max));
+        }
+    }
+
+    fn check_string(&self, s: &str, violations: &mut Vec<String>) {
+        if self.min_length.is_some() || self.max_length.is_some() {
+            let len = s.chars().count();
+            if let Some(min) = self.min_length.filter(|min| len < *min) {
+                violations.push(format!("Shorter than {} chars", min));
+            }
+            if let Some(max) = self.max_length.filter(|max| len > *max) {
+                violations.push(format!("Longer than {} chars", max));
+            }
+        }
+        if let Some(pattern) = self.pattern.as_ref().filter(|p| !p.is_match(s)) {
+            violations.push(format!("Does not match the pattern `{}`", pattern));
+        }
+    }
+
+    /// true if the string values must be read to check this schema.
+    fn needs_string(&self) -> bool {
+        !self.reject && (self.min_length.is_some() || self.max_length.is_some() || self.pattern.is_some()
+            || self.enum_values.is_some())
+    }
+}
+
+fn type_matches(schema_type: SchemaType, token: &ParserToken) -> bool {
+    match (schema_type, token) {
+        (SchemaType::Object, ParserToken::BeginObject) => true,
+        (SchemaType::Array, ParserToken::BeginArray) => true,
+        (SchemaType::String, ParserToken::StringValue(_)) | (SchemaType::String, ParserToken::BytesValue(_))
+        | (SchemaType::String, ParserToken::StringStart) => true,
+        (SchemaType::Integer, ParserToken::IntValue(_)) => true,
+        // 1.0 is an integer
+        (SchemaType::Integer, ParserToken::FloatValue(s)) => s.parse::<f64>().is_ok_and(|x| x.fract() == 0.0),
+        (SchemaType::Number, ParserToken::IntValue(_)) | (SchemaType::Number, ParserToken::FloatValue(_)) => true,
+        (SchemaType::Boolean, ParserToken::BooleanValue(_)) => true,
+        (SchemaType::Null, ParserToken::NullValue) => true,
+        _ => false,
+    }
+}
+
+fn enum_matches(value: &JsonValue, token: &ParserToken) -> bool {
+    match (value, token) {
+        (JsonValue::Object(_), ParserToken::BeginObject) | (JsonValue::Array(_), ParserToken::BeginArray) => true,
+        (JsonValue::String(a), ParserToken::StringValue(b)) => a == b,
+        (JsonValue::String(a), ParserToken::BytesValue(b)) => a.as_bytes() == &b[..],
+        (JsonValue::Int(_), ParserToken::IntValue(b)) | (JsonValue::Int(_), ParserToken::FloatValue(b))
+        | (JsonValue::Float(_), ParserToken::IntValue(b)) | (JsonValue::Float(_), ParserToken::FloatValue(b)) => {
+            value.as_f64().is_some_and(|a| b.parse::<f64>().is_ok_and(|b| a == b))
+        }
+        (JsonValue::Bool(a), ParserToken::BooleanValue(b)) => a == b,
+        (JsonValue::Null, ParserToken::NullValue) => true,
+        _ => false,
+    }
+}
+
+/// A compiled JSON Schema. See the module documentation for the supported keywords.
+#[derive(Debug)]
+pub struct JSONSchema {
+    root: Schema,
+}
+
+impl JSONSchema {
+    /// Load a schema from its JSON text.
+    pub fn parse(schema: &str) -> Result<JSONSchema, SchemaError> {
+        let value = JsonValue::parse(schema).map_err(|e| SchemaError { msg: format!("Invalid JSON: {}", e) })?;
+        JSONSchema::from_value(&value)
+    }
+
+    pub fn from_value(schema: &JsonValue) -> Result<JSONSchema, SchemaError> {
+        Ok(JSONSchema { root: Schema::from_value(schema)? })
+    }
+
+    /// Validate a document and return the violations. A parse error is returned as an error.
+    pub fn validate<R: Read>(&self, byte_source: ByteSource<R>) -> Result<Vec<SchemaViolation>, ConsumeError> {
+        let mut consumer = SchemaValidatorConsumer::new(self);
+        JSONParser::new(byte_source, false).parse(&mut consumer)?;
+        Ok(consumer.into_violations())
+    }
+}
+
+/// A value that does not match the schema. The path is a JSON Pointer and the position is the
+/// position of the token where the violation was found: the value, or the end of the container
+/// for `required`, `minItems` and `maxItems`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub msg: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for SchemaViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {} at {}:{}", self.path, self.msg, self.line, self.column)
+    }
+}
+
+/// An open container and its schema.
+struct Frame<'a> {
+    array: bool,
+    schema: Option<&'a Schema>,
+    /// The keys seen so far, if the object has required properties
+    keys: Option<HashSet<String>>,
+    /// The schema of the value of the current key
+    value_schema: Option<&'a Schema>,
+    items: usize,
+}
+
+/// A consumer that checks the tokens against a `JSONSchema`. The violations are collected (see
+/// `violations`), or, with `fail_fast`, the first violation stops the parsing.
+pub struct SchemaValidatorConsumer<'a> {
+    schema: &'a JSONSchema,
+    fail_fast: bool,
+    tracker: PathTracker,
+    frames: Vec<Frame<'a>>,
+    /// A string sent in chunks that must be checked, with its schema
+    chunked: Option<(&'a Schema, Vec<u8>)>,
+    violations: Vec<SchemaViolation>,
+}
+
+impl<'a> SchemaValidatorConsumer<'a> {
+    pub fn new(schema: &'a JSONSchema) -> Self {
+        SchemaValidatorConsumer {
+            schema,
+            fail_fast: false,
+            tracker: PathTracker::new(),
+            frames: vec!(),
+            chunked: None,
+            violations: vec!(),
+        }
+    }
+
+    /// Stop the parsing at the first violation.
+    pub fn fail_fast(mut self) -> Self {
+        self.fail_fast = true;
+        self
+    }
+
+    pub fn violations(&self) -> &[SchemaViolation] {
+        &self.violations
+    }
+
+    pub fn into_violations(self) -> Vec<SchemaViolation> {
+        self.violations
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// The schema of the value that begins now.
+    fn next_schema(&mut self) -> Option<&'a Schema> {
+        match self.frames.last_mut() {
+            None => Some(&self.schema.root),
+            Some(frame) if frame.array => frame.schema.and_then(|s| s.items.as_deref()),
+            Some(frame) => frame.value_schema.take(),
+        }
+    }
+
+    fn report(&mut self, msgs: Vec<String>, span: &Span) -> Result<(), ConsumeError> {
+        if msgs.is_empty() {
+            return Ok(());
+        }
+        let path = to_pointer_string(self.tracker.path());
+        for msg in msgs {
+            self.violations.push(SchemaViolation { path: path.clone(), msg, line: span.line, column: span.column });
+        }
+        if self.fail_fast {
+            let v = &self.violations[0];
+            return Err(ConsumeError { msg: format!("{}: {}", v.path, v.msg), line: v.line, column: v.column, io_error: None });
+        }
+        Ok(())
+    }
+}
+
+impl JSONParseConsumer for SchemaValidatorConsumer<'_> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.consume_with_span(token, Span::default())
+    }
+
+    fn consume_with_span(&mut self, token: Result<ParserToken, JSONParseError>, span: Span) -> Result<(), ConsumeError> {
+        let token = token?;
+        self.tracker.update(&token);
+        let mut msgs = vec!();
+        match &token {
+            ParserToken::BeginFile => self.frames.clear(),
+            ParserToken::EndFile => {}
+            ParserToken::Key(k) => {
+                if let Some(frame) = self.frames.last_mut() {
+                    frame.value_schema = frame.schema.and_then(|s| s.property(k));
+                    if let Some(keys) = frame.keys.as_mut() {
+                        keys.insert(k.clone());
+                    }
+                }
+            }
+            ParserToken::EndObject | ParserToken::EndArray => {
+                if let Some(Frame { schema: Some(schema), keys, items, .. }) = self.frames.pop() {
+                    if let Some(keys) = keys {
+                        for name in schema.required.iter().filter(|name| !keys.contains(*name)) {
+                            msgs.push(format!("Missing required property `{}`", name));
+                        }
+                    }
+                    if token == ParserToken::EndArray {
+                        if let Some(min) = schema.min_items.filter(|min| items < *min) {
+                            msgs.push(format!("Less than {} items", min));
+                        }
+                        if let Some(max) = schema.max_items.filter(|max| items > *max) {
+                            msgs.push(format!("More than {} items", max));
+                        }
+                    }
+                }
+            }
+            ParserToken::StringChunk(chunk) => {
+                if let Some((_, buf)) = self.chunked.as_mut() {
+                    buf.extend_from_slice(chunk);
+                }
+            }
+            ParserToken::StringEnd => {
+                if let Some((schema, buf)) = self.chunked.take() {
+                    schema.check_content(&ParserToken::StringValue(String::from_utf8_lossy(&buf).into_owned()), &mut msgs);
+                }
+            }
+            _ => {
+                // the first token of a value
+                if let Some(Frame { items, .. }) = self.frames.last_mut() {
+                    *items += 1;
+                }
+                let schema = self.next_schema();
+                if let Some(schema) = schema {
+                    schema.check_start(&token, &mut msgs);
+                    if token == ParserToken::StringStart && schema.needs_string() {
+                        self.chunked = Some((schema, vec!()));
+                    }
+                }
+                match token {
+                    ParserToken::BeginObject => self.frames.push(Frame {
+                        array: false,
+                        schema,
+                        keys: schema.filter(|s| !s.required.is_empty()).map(|_| HashSet::new()),
+                        value_schema: None,
+                        items: 0,
+                    }),
+                    ParserToken::BeginArray => self.frames.push(Frame { array: true, schema, keys: None, value_schema: None, items: 0 }),
+                    _ => {}
+                }
+            }
+        }
+        self.report(msgs, &span)
+    }
+}
@@ -0,0 +1,921 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A `serde::Deserializer` over the tokens of a `JSONPullParser`: the values are built while the
+//! document is read, and the elements of a huge top-level array may be deserialized one at a
+//! time (see `Deserializer::array_elements`). A `Serializer` does the reverse: it sends the
+//! tokens of a value to a `JSONParseConsumer`.
+
+use std::fmt;
+use std::io::Read;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, Visitor};
+use serde::ser::{self, Serialize};
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::{ConsumeError, Span};
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONPullParser, ParseErrorKind, ParserToken};
+
+/// The category of a `DeError`.
+#[derive(Debug, PartialEq, Clone)]
+pub enum DeErrorKind {
+    /// An error of the parser
+    Parse(ParseErrorKind),
+    /// The document ended before the value
+    UnexpectedEnd,
+    /// A token that does not match the expected value, e.g. a string for a number
+    UnexpectedToken,
+    /// An error of the `Deserialize` implementation, e.g. a missing field
+    Custom,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct DeError {
+    pub kind: DeErrorKind,
+    pub msg: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for DeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {}:{}", self.msg, self.line, self.column)
+    }
+}
+
+impl std::error::Error for DeError {}
+
+impl de::Error for DeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        // the position is set by the deserializer
+        DeError { kind: DeErrorKind::Custom, msg: msg.to_string(), line: 0, column: 0 }
+    }
+}
+
+impl From<JSONParseError> for DeError {
+    fn from(e: JSONParseError) -> Self {
+        DeError { kind: DeErrorKind::Parse(e.kind), msg: e.msg, line: e.line, column: e.column }
+    }
+}
+
+/// A deserializer that reads the tokens of a JSON document. The numbers are converted to the
+/// type of the field; a `ParserToken::BytesValue` is a byte buffer.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_serde::Deserializer;
+/// # use serde::Deserialize;
+/// let mut deserializer = Deserializer::new(ByteSource::new(r#"{"a": [1, 2], "b": null}"#.as_bytes()));
+/// let value: std::collections::BTreeMap<String, Option<Vec<u8>>> = Deserialize::deserialize(&mut deserializer).unwrap();
+/// deserializer.end().unwrap();
+/// assert_eq!(Some(&Some(vec!(1, 2))), value.get("a"));
+/// assert_eq!(Some(&None), value.get("b"));
+/// ```
+pub struct Deserializer<R: Read> {
+    parser: JSONPullParser<R>,
+    peeked: Option<ParserToken>,
+    /// The span of the last token
+    span: Span,
+}
+
+impl<R: Read> Deserializer<R> {
+    pub fn new(byte_source: ByteSource<R>) -> Self {
+        Deserializer::from_pull_parser(JSONPullParser::new(byte_source, false))
+    }
+
+    /// A deserializer over a parser with its own options, e.g. a `DuplicateKeyPolicy`.
+    pub fn from_pull_parser(parser: JSONPullParser<R>) -> Self {
+        Deserializer {
+            parser,
+            peeked: None,
+            span: Span::default(),
+        }
+    }
+
+    /// Check that the document has no other value.
+    pub fn end(&mut self) -> Result<(), DeError> {
+        match self.next()? {
+            ParserToken::EndFile => Ok(()),
+            _ => Err(self.error(DeErrorKind::UnexpectedToken, "Value after the root value")),
+        }
+    }
+
+    /// An iterator over the elements of the top-level array, deserialized one at a time.
+    pub fn array_elements<T: DeserializeOwned>(self) -> ArrayElements<R, T> {
+        ArrayElements { deserializer: self, state: ArrayState::Start, element: PhantomData }
+    }
+
+    fn error(&self, kind: DeErrorKind, msg: &str) -> DeError {
+        DeError { kind, msg: msg.into(), line: self.span.line, column: self.span.column }
+    }
+
+    /// Set the position of the errors of the `Deserialize` implementations.
+    fn locate(&self, mut e: DeError) -> DeError {
+        if e.kind == DeErrorKind::Custom && e.line == 0 && e.column == 0 {
+            e.line = self.span.line;
+            e.column = self.span.column;
+        }
+        e
+    }
+
+    fn next(&mut self) -> Result<ParserToken, DeError> {
+        if let Some(token) = self.peeked.take() {
+            return Ok(token);
+        }
+        loop {
+            match self.parser.next_event_with_span() {
+                None => return Err(self.error(DeErrorKind::UnexpectedEnd, "Unexpected end of the document")),
+                Some((token, span)) => {
+                    self.span = span;
+                    match token? {
+                        ParserToken::BeginFile => {}
+                        token => return Ok(token),
+                    }
+                }
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Result<&ParserToken, DeError> {
+        if self.peeked.is_none() {
+            self.peeked = Some(self.next()?);
+        }
+        Ok(self.peeked.as_ref().expect("a token was peeked"))
+    }
+
+    /// The chunks of a string, up to the `StringEnd`.
+    fn string_chunks(&mut self) -> Result<Vec<u8>, DeError> {
+        let mut bytes = vec!();
+        loop {
+            match self.next()? {
+                ParserToken::StringChunk(chunk) => bytes.extend(chunk),
+                ParserToken::StringEnd => return Ok(bytes),
+                _ => return Err(self.error(DeErrorKind::UnexpectedToken, "Unfinished string")),
+            }
+        }
+    }
+
+    /// Check that the container was read up to its end.
+    fn end_container(&mut self, done: bool) -> Result<(), DeError> {
+        if done {
+            return Ok(());
+        }
+        match self.next()? {
+            ParserToken::EndArray | ParserToken::EndObject => Ok(()),
+            _ => Err(self.error(DeErrorKind::UnexpectedToken, "Too many elements")),
+        }
+    }
+
+    /// Read a value without building it.
+    fn skip_value(&mut self) -> Result<(), DeError> {
+        let mut depth = 0usize;
+        loop {
+            match self.next()? {
+                ParserToken::BeginArray | ParserToken::BeginObject | ParserToken::StringStart => depth += 1,
+                ParserToken::EndArray | ParserToken::EndObject | ParserToken::StringEnd => depth -= 1,
+                ParserToken::EndFile => return Err(self.error(DeErrorKind::UnexpectedEnd, "Unexpected end of the document")),
+                _ => {}
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+}
+
+/// Visit an integer lexeme with the narrowest type. The integers outside of the 128 bits range
+/// are visited as floats.
+fn visit_int<'de, V: Visitor<'de>>(lexeme: &str, visitor: V) -> Result<V::Value, DeError> {
+    if let Ok(n) = lexeme.parse::<i64>() {
+        visitor.visit_i64(n)
+    } else if let Ok(n) = lexeme.parse::<u64>() {
+        visitor.visit_u64(n)
+    } else if let Ok(n) = lexeme.parse::<i128>() {
+        visitor.visit_i128(n)
+    } else if let Ok(n) = lexeme.parse::<u128>() {
+        visitor.visit_u128(n)
+    } else {
+        visit_float(lexeme, visitor)
+    }
+}
+
+fn visit_float<'de, V: Visitor<'de>>(lexeme: &str, visitor: V) -> Result<V::Value, DeError> {
+    match lexeme.parse::<f64>() {
+        Ok(v) => visitor.visit_f64(v),
+        Err(_) => Err(de::Error::custom(format!("Invalid number `{}`", lexeme))),
+    }
+}
+
+impl<'de, R: Read> de::Deserializer<'de> for &mut Deserializer<R> {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        let result = match self.next()? {
+            ParserToken::BeginObject => {
+                let mut access = Access { de: self, done: false };
+                let value = visitor.visit_map(&mut access)?;
+                let done = access.done;
+                self.end_container(done).map(|_| value)
+            }
+            ParserToken::BeginArray => {
+                let mut access = Access { de: self, done: false };
+                let value = visitor.visit_seq(&mut access)?;
+                let done = access.done;
+                self.end_container(done).map(|_| value)
+            }
+            ParserToken::Key(s) | ParserToken::StringValue(s) => visitor.visit_string(s),
+            ParserToken::StringStart => match String::from_utf8(self.string_chunks()?) {
+                Ok(s) => visitor.visit_string(s),
+                Err(e) => visitor.visit_byte_buf(e.into_bytes()),
+            },
+            ParserToken::BytesValue(b) => visitor.visit_byte_buf(b),
+            ParserToken::IntValue(s) => visit_int(&s, visitor),
+            ParserToken::FloatValue(s) => visit_float(&s, visitor),
+            ParserToken::BooleanValue(b) => visitor.visit_bool(b),
+            ParserToken::NullValue => visitor.visit_unit(),
+            ParserToken::EndFile => Err(self.error(DeErrorKind::UnexpectedEnd, "Unexpected end of the document")),
+            _ => Err(self.error(DeErrorKind::UnexpectedToken, "A value was expected")),
+        };
+        result.map_err(|e| self.locate(e))
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        if let ParserToken::NullValue = self.peek()? {
+            self.next()?;
+            visitor.visit_none()
+        } else {
+            visitor.visit_some(self)
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value, DeError> {
+        let result = match self.next()? {
+            ParserToken::StringValue(s) => visitor.visit_enum(s.into_deserializer()),
+            ParserToken::BeginObject => {
+                let value = visitor.visit_enum(&mut *self)?;
+                match self.next()? {
+                    ParserToken::EndObject => Ok(value),
+                    _ => Err(self.error(DeErrorKind::UnexpectedToken, "An enum must be an object with a single key")),
+                }
+            }
+            _ => Err(self.error(DeErrorKind::UnexpectedToken, "An enum must be a string or an object")),
+        };
+        result.map_err(|e| self.locate(e))
+    }
+
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        self.skip_value()?;
+        visitor.visit_unit()
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+    }
+}
+
+/// The elements of an array or the members of an object.
+struct Access<'a, R: Read> {
+    de: &'a mut Deserializer<R>,
+    /// The end of the container was read
+    done: bool,
+}
+
+impl<'de, 'a, 'b, R: Read> de::SeqAccess<'de> for &'b mut Access<'a, R> {
+    type Error = DeError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(&mut self, seed: T) -> Result<Option<T::Value>, DeError> {
+        if self.done {
+            return Ok(None);
+        }
+        if let ParserToken::EndArray = self.de.peek()? {
+            self.de.next()?;
+            self.done = true;
+            return Ok(None);
+        }
+        seed.deserialize(&mut *self.de).map(Some)
+    }
+}
+
+impl<'de, 'a, 'b, R: Read> de::MapAccess<'de> for &'b mut Access<'a, R> {
+    type Error = DeError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(&mut self, seed: K) -> Result<Option<K::Value>, DeError> {
+        if self.done {
+            return Ok(None);
+        }
+        match self.de.next()? {
+            ParserToken::EndObject => {
+                self.done = true;
+                Ok(None)
+            }
+            ParserToken::Key(key) => seed.deserialize(KeyDeserializer { key }).map(Some).map_err(|e| self.de.locate(e)),
+            _ => Err(self.de.error(DeErrorKind::UnexpectedToken, "A key was expected")),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(&mut self, seed: V) -> Result<V::Value, DeError> {
+        seed.deserialize(&mut *self.de)
+    }
+}
+
+impl<'de, R: Read> de::EnumAccess<'de> for &mut Deserializer<R> {
+    type Error = DeError;
+    type Variant = Self;
+
+    fn variant_seed<V: DeserializeSeed<'de>>(self, seed: V) -> Result<(V::Value, Self), DeError> {
+        match self.next()? {
+            ParserToken::Key(key) => {
+                let variant = seed.deserialize(KeyDeserializer { key })?;
+                Ok((variant, self))
+            }
+            _ => Err(self.error(DeErrorKind::UnexpectedToken, "An enum must be an object with a single key")),
+        }
+    }
+}
+
+impl<'de, R: Read> de::VariantAccess<'de> for &mut Deserializer<R> {
+    type Error = DeError;
+
+    fn unit_variant(self) -> Result<(), DeError> {
+        de::Deserialize::deserialize(self)
+    }
+
+    fn newtype_variant_seed<T: DeserializeSeed<'de>>(self, seed: T) -> Result<T::Value, DeError> {
+        seed.deserialize(self)
+    }
+
+    fn tuple_variant<V: Visitor<'de>>(self, _len: usize, visitor: V) -> Result<V::Value, DeError> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+
+    fn struct_variant<V: Visitor<'de>>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value, DeError> {
+        de::Deserializer::deserialize_any(self, visitor)
+    }
+}
+
+/// A deserializer of a key: a string, or a number written as a string, e.g. the key of a
+/// `HashMap<u32, _>`.
+struct KeyDeserializer {
+    key: String,
+}
+
+macro_rules! deserialize_parsed_key {
+    ($($method:ident => $visit:ident,)*) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+                match self.key.parse() {
+                    Ok(n) => visitor.$visit(n),
+                    Err(_) => Err(de::Error::custom(format!("Invalid number `{}` in a key", self.key))),
+                }
+            }
+        )*
+    }
+}
+
+impl<'de> de::Deserializer<'de> for KeyDeserializer {
+    type Error = DeError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_string(self.key)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value, DeError> {
+        self.key.into_deserializer().deserialize_enum(name, variants, visitor)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(self, _name: &'static str, visitor: V) -> Result<V::Value, DeError> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    deserialize_parsed_key! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+    }
+
+    serde::forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf option unit unit_struct seq tuple tuple_struct
+        map struct identifier ignored_any
+    }
+}
+
+enum ArrayState {
+    Start,
+    Elements,
+    Done,
+}
+
+/// The elements of the top-level array (see `Deserializer::array_elements`). The iterator stops
+/// after the first error.
+pub struct ArrayElements<R: Read, T: DeserializeOwned> {
+    deserializer: Deserializer<R>,
+    state: ArrayState,
+    element: PhantomData<T>,
+}
+
+impl<R: Read, T: DeserializeOwned> ArrayElements<R, T> {
+    fn next_element(&mut self) -> Result<Option<T>, DeError> {
+        let de = &mut self.deserializer;
+        if let ArrayState::Start = self.state {
+            match de.next()? {
+                ParserToken::BeginArray => self.state = ArrayState::Elements,
+                _ => return Err(de.error(DeErrorKind::UnexpectedToken, "The root value must be an array")),
+            }
+        }
+        if let ParserToken::EndArray = de.peek()? {
+            de.next()?;
+            de.end()?;
+            return Ok(None);
+        }
+        let element = T::deserialize(&mut *de).map_err(|e| de.locate(e))?;
+        Ok(Some(element))
+    }
+}
+
+impl<R: Read, T: DeserializeOwned> Iterator for ArrayElements<R, T> {
+    type Item = Result<T, DeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let ArrayState::Done = self.state {
+            return None;
+        }
+        let result = self.next_element();
+        if !matches!(result, Ok(Some(_))) {
+            self.state = ArrayState::Done;
+        }
+        result.transpose()
+    }
+}
+
+/// Deserialize a JSON document from `input`.
+pub fn from_reader<R: Read, T: DeserializeOwned>(input: R) -> Result<T, DeError> {
+    let mut deserializer = Deserializer::new(ByteSource::new(input));
+    let value = T::deserialize(&mut deserializer).map_err(|e| deserializer.locate(e))?;
+    deserializer.end()?;
+    Ok(value)
+}
+
+/// Deserialize a JSON document.
+pub fn from_str<T: DeserializeOwned>(input: &str) -> Result<T, DeError> {
+    from_reader(input.as_bytes())
+}
+
+impl ser::Error for ConsumeError {
+    fn custom<T: fmt::Display>(msg: T) -> Self {
+        ConsumeError { msg: msg.to_string(), line: 0, column: 0, io_error: None }
+    }
+}
+
+/// A serializer that sends the tokens of a value to a consumer, as if the value was parsed. The
+/// values are mapped as by `serde_json`: the enum variants with data are objects with a single
+/// key, the byte buffers are arrays of numbers and the NaN and infinite floats are nulls. The
+/// errors are the errors of the consumer.
+///
+/// ```
+/// # use r_json_event_parser::json_serde::Serializer;
+/// # use r_json_event_parser::json_writer::JSONWriteConsumer;
+/// # use serde::Serialize;
+/// let mut consumer = JSONWriteConsumer::new(vec!());
+/// let value = vec!((1, Some("a")), (2, None));
+/// Serializer::new(&mut consumer).serialize_document(&value).unwrap();
+/// assert_eq!(br#"[[1,"a"],[2,null]]"#.to_vec(), consumer.into_inner());
+/// ```
+pub struct Serializer<'a, C: JSONParseConsumer> {
+    consumer: &'a mut C,
+}
+
+impl<'a, C: JSONParseConsumer> Serializer<'a, C> {
+    pub fn new(consumer: &'a mut C) -> Self {
+        Serializer { consumer }
+    }
+
+    /// Send the tokens of a whole document: `BeginFile`, the tokens of the value and `EndFile`.
+    pub fn serialize_document<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::BeginFile)?;
+        value.serialize(&mut *self)?;
+        self.emit(ParserToken::EndFile)
+    }
+
+    fn emit(&mut self, token: ParserToken) -> Result<(), ConsumeError> {
+        self.consumer.consume(Ok(token))
+    }
+
+    fn float(&mut self, v: f64, lexeme: String) -> Result<(), ConsumeError> {
+        if v.is_finite() {
+            self.emit(ParserToken::FloatValue(lexeme))
+        } else {
+            self.emit(ParserToken::NullValue)
+        }
+    }
+
+    /// Open the object of an enum variant with data.
+    fn begin_variant(&mut self, variant: &str) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::BeginObject)?;
+        self.emit(ParserToken::Key(variant.into()))
+    }
+}
+
+/// Send the tokens of a value to a consumer, as a whole document.
+pub fn to_consumer<T: Serialize + ?Sized, C: JSONParseConsumer>(value: &T, consumer: &mut C) -> Result<(), ConsumeError> {
+    Serializer::new(consumer).serialize_document(value)
+}
+
+impl<'b, 'a, C: JSONParseConsumer> ser::Serializer for &'b mut Serializer<'a, C> {
+    type Ok = ();
+    type Error = ConsumeError;
+    type SerializeSeq = Compound<'b, 'a, C>;
+    type SerializeTuple = Compound<'b, 'a, C>;
+    type SerializeTupleStruct = Compound<'b, 'a, C>;
+    type SerializeTupleVariant = Compound<'b, 'a, C>;
+    type SerializeMap = Compound<'b, 'a, C>;
+    type SerializeStruct = Compound<'b, 'a, C>;
+    type SerializeStructVariant = Compound<'b, 'a, C>;
+
+    fn serialize_bool(self, v: bool) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::BooleanValue(v))
+    }
+
+    fn serialize_i8(self, v: i8) -> Result<(), ConsumeError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i16(self, v: i16) -> Result<(), ConsumeError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i32(self, v: i32) -> Result<(), ConsumeError> {
+        self.serialize_i64(v as i64)
+    }
+
+    fn serialize_i64(self, v: i64) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::IntValue(v.to_string()))
+    }
+
+    fn serialize_i128(self, v: i128) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::IntValue(v.to_string()))
+    }
+
+    fn serialize_u8(self, v: u8) -> Result<(), ConsumeError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u16(self, v: u16) -> Result<(), ConsumeError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u32(self, v: u32) -> Result<(), ConsumeError> {
+        self.serialize_u64(v as u64)
+    }
+
+    fn serialize_u64(self, v: u64) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::IntValue(v.to_string()))
+    }
+
+    fn serialize_u128(self, v: u128) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::IntValue(v.to_string()))
+    }
+
+    fn serialize_f32(self, v: f32) -> Result<(), ConsumeError> {
+        self.float(v as f64, format!("{:?}", v))
+    }
+
+    fn serialize_f64(self, v: f64) -> Result<(), ConsumeError> {
+        self.float(v, format!("{:?}", v))
+    }
+
+    fn serialize_char(self, v: char) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::StringValue(v.to_string()))
+    }
+
+    fn serialize_str(self, v: &str) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::StringValue(v.into()))
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::BeginArray)?;
+        for b in v {
+            self.emit(ParserToken::IntValue(b.to_string()))?;
+        }
+        self.emit(ParserToken::EndArray)
+    }
+
+    fn serialize_none(self) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::NullValue)
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<(), ConsumeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::NullValue)
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::NullValue)
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<(), ConsumeError> {
+        self.emit(ParserToken::StringValue(variant.into()))
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<(), ConsumeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant: &'static str, value: &T) -> Result<(), ConsumeError> {
+        self.begin_variant(variant)?;
+        value.serialize(&mut *self)?;
+        self.emit(ParserToken::EndObject)
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Compound<'b, 'a, C>, ConsumeError> {
+        self.emit(ParserToken::BeginArray)?;
+        Ok(Compound { ser: self, variant: false })
+    }
+
+    fn serialize_tuple(self, len: usize) -> Result<Compound<'b, 'a, C>, ConsumeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<Compound<'b, 'a, C>, ConsumeError> {
+        self.serialize_seq(Some(len))
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<Compound<'b, 'a, C>, ConsumeError> {
+        self.begin_variant(variant)?;
+        self.emit(ParserToken::BeginArray)?;
+        Ok(Compound { ser: self, variant: true })
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Compound<'b, 'a, C>, ConsumeError> {
+        self.emit(ParserToken::BeginObject)?;
+        Ok(Compound { ser: self, variant: false })
+    }
+
+    fn serialize_struct(self, _name: &'static str, len: usize) -> Result<Compound<'b, 'a, C>, ConsumeError> {
+        self.serialize_map(Some(len))
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<Compound<'b, 'a, C>, ConsumeError> {
+        self.begin_variant(variant)?;
+        self.emit(ParserToken::BeginObject)?;
+        Ok(Compound { ser: self, variant: true })
+    }
+}
+
+/// An array or an object being serialized.
+pub struct Compound<'b, 'a, C: JSONParseConsumer> {
+    ser: &'b mut Serializer<'a, C>,
+    /// The container is the value of an enum variant: the object of the variant is closed at the
+    /// end
+    variant: bool,
+}
+
+impl<'b, 'a, C: JSONParseConsumer> Compound<'b, 'a, C> {
+    fn end_container(self, token: ParserToken) -> Result<(), ConsumeError> {
+        self.ser.emit(token)?;
+        if self.variant {
+            self.ser.emit(ParserToken::EndObject)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'b, 'a, C: JSONParseConsumer> ser::SerializeSeq for Compound<'b, 'a, C> {
+    type Ok = ();
+    type Error = ConsumeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConsumeError> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), ConsumeError> {
+        self.end_container(ParserToken::EndArray)
+    }
+}
+
+impl<'b, 'a, C: JSONParseConsumer> ser::SerializeTuple for Compound<'b, 'a, C> {
+    type Ok = ();
+    type Error = ConsumeError;
+
+    fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConsumeError> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), ConsumeError> {
+        self.end_container(ParserToken::EndArray)
+    }
+}
+
+impl<'b, 'a, C: JSONParseConsumer> ser::SerializeTupleStruct for Compound<'b, 'a, C> {
+    type Ok = ();
+    type Error = ConsumeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConsumeError> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), ConsumeError> {
+        self.end_container(ParserToken::EndArray)
+    }
+}
+
+impl<'b, 'a, C: JSONParseConsumer> ser::SerializeTupleVariant for Compound<'b, 'a, C> {
+    type Ok = ();
+    type Error = ConsumeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConsumeError> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), ConsumeError> {
+        self.end_container(ParserToken::EndArray)
+    }
+}
+
+impl<'b, 'a, C: JSONParseConsumer> ser::SerializeMap for Compound<'b, 'a, C> {
+    type Ok = ();
+    type Error = ConsumeError;
+
+    fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<(), ConsumeError> {
+        let key = key.serialize(KeySerializer)?;
+        self.ser.emit(ParserToken::Key(key))
+    }
+
+    fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<(), ConsumeError> {
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), ConsumeError> {
+        self.end_container(ParserToken::EndObject)
+    }
+}
+
+impl<'b, 'a, C: JSONParseConsumer> ser::SerializeStruct for Compound<'b, 'a, C> {
+    type Ok = ();
+    type Error = ConsumeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), ConsumeError> {
+        self.ser.emit(ParserToken::Key(key.into()))?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), ConsumeError> {
+        self.end_container(ParserToken::EndObject)
+    }
+}
+
+impl<'b, 'a, C: JSONParseConsumer> ser::SerializeStructVariant for Compound<'b, 'a, C> {
+    type Ok = ();
+    type Error = ConsumeError;
+
+    fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<(), ConsumeError> {
+        self.ser.emit(ParserToken::Key(key.into()))?;
+        value.serialize(&mut *self.ser)
+    }
+
+    fn end(self) -> Result<(), ConsumeError> {
+        self.end_container(ParserToken::EndObject)
+    }
+}
+
+/// A serializer of the keys of a map: the strings, the chars, the numbers, the booleans and the
+/// unit variants become strings.
+struct KeySerializer;
+
+fn key_error() -> ConsumeError {
+    ser::Error::custom("A key must be a string, a number, a boolean or a unit variant")
+}
+
+macro_rules! serialize_key_to_string {
+    ($($method:ident($type:ty),)*) => {
+        $(
+            fn $method(self, v: $type) -> Result<String, ConsumeError> {
+                Ok(v.to_string())
+            }
+        )*
+    }
+}
+
+impl ser::Serializer for KeySerializer {
+    type Ok = String;
+    type Error = ConsumeError;
+    type SerializeSeq = ser::Impossible<String, ConsumeError>;
+    type SerializeTuple = ser::Impossible<String, ConsumeError>;
+    type SerializeTupleStruct = ser::Impossible<String, ConsumeError>;
+    type SerializeTupleVariant = ser::Impossible<String, ConsumeError>;
+    type SerializeMap = ser::Impossible<String, ConsumeError>;
+    type SerializeStruct = ser::Impossible<String, ConsumeError>;
+    type SerializeStructVariant = ser::Impossible<String, ConsumeError>;
+
+    serialize_key_to_string! {
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_i128(i128),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_u128(u128),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+    }
+
+    fn serialize_bytes(self, _v: &[u8]) -> Result<String, ConsumeError> {
+        Err(key_error())
+    }
+
+    fn serialize_none(self) -> Result<String, ConsumeError> {
+        Err(key_error())
+    }
+
+    fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String, ConsumeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_unit(self) -> Result<String, ConsumeError> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<String, ConsumeError> {
+        Err(key_error())
+    }
+
+    fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<String, ConsumeError> {
+        Ok(variant.into())
+    }
+
+    fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<String, ConsumeError> {
+        value.serialize(self)
+    }
+
+    fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, _variant: &'static str, _value: &T) -> Result<String, ConsumeError> {
+        Err(key_error())
+    }
+
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, ConsumeError> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, ConsumeError> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct, ConsumeError> {
+        Err(key_error())
+    }
+
+    fn serialize_tuple_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeTupleVariant, ConsumeError> {
+        Err(key_error())
+    }
+
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, ConsumeError> {
+        Err(key_error())
+    }
+
+    fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct, ConsumeError> {
+        Err(key_error())
+    }
+
+    fn serialize_struct_variant(self, _name: &'static str, _index: u32, _variant: &'static str, _len: usize) -> Result<Self::SerializeStructVariant, ConsumeError> {
+        Err(key_error())
+    }
+}
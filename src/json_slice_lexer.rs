@@ -0,0 +1,340 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A lexer for an in-memory document that borrows the text of the tokens from the input.
+//!
+//! The strings without escapes and the numbers are slices of the input; only the strings that
+//! need to be unescaped are allocated. The dialect is RFC 8259 and the lexing stops at the
+//! first error.
+
+use std::borrow::Cow;
+use std::str;
+
+use crate::json_lexer::{JSONLexError, LexErrorKind, LexerToken, Span};
+
+/// A `LexerToken` that borrows its text from the input.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SliceToken<'a> {
+    BeginObject,
+    EndObject,
+    BeginArray,
+    EndArray,
+    NameSeparator,
+    ValueSeparator,
+    BooleanValue(bool),
+    NullValue,
+    /// The unescaped string: borrowed unless the string has escapes.
+    String(Cow<'a, str>),
+    IntValue(&'a str),
+    FloatValue(&'a str),
+    BeginFile,
+    EndFile,
+}
+
+impl<'a> SliceToken<'a> {
+    pub fn into_owned(self) -> LexerToken {
+        match self {
+            SliceToken::BeginObject => LexerToken::BeginObject,
+            SliceToken::EndObject => LexerToken::EndObject,
+            SliceToken::BeginArray => LexerToken::BeginArray,
+            SliceToken::EndArray => LexerToken::EndArray,
+            SliceToken::NameSeparator => LexerToken::NameSeparator,
+            SliceToken::ValueSeparator => LexerToken::ValueSeparator,
+            SliceToken::BooleanValue(b) => LexerToken::BooleanValue(b),
+            SliceToken::NullValue => LexerToken::NullValue,
+            SliceToken::String(s) => LexerToken::String(s.into_owned()),
+            SliceToken::IntValue(s) => LexerToken::IntValue(s.into()),
+            SliceToken::FloatValue(s) => LexerToken::FloatValue(s.into()),
+            SliceToken::BeginFile => LexerToken::BeginFile,
+            SliceToken::EndFile => LexerToken::EndFile,
+        }
+    }
+}
+
+/// A lexer over a byte slice, in pull mode: the iterator yields the tokens with their span,
+/// from `BeginFile` to `EndFile`. After an error, the iterator yields `EndFile` and stops.
+/// The line is 0-based and the column is the number of bytes of the line read so far.
+pub struct JSONSliceLexer<'a> {
+    input: &'a [u8],
+    pos: usize,
+    line: usize,
+    line_start: usize,
+    started: bool,
+    finished: bool,
+}
+
+type SliceResult<'a> = Result<SliceToken<'a>, JSONLexError>;
+
+impl<'a> JSONSliceLexer<'a> {
+    pub fn new(input: &'a [u8]) -> Self {
+        JSONSliceLexer {
+            input,
+            pos: 0,
+            line: 0,
+            line_start: 0,
+            started: false,
+            finished: false,
+        }
+    }
+
+    /// The number of bytes consumed so far.
+    pub fn byte_offset(&self) -> usize {
+        self.pos
+    }
+
+    fn error(&self, kind: LexErrorKind, msg: String) -> JSONLexError {
+        JSONLexError { kind, msg, line: self.line, column: self.pos - self.line_start }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(byte) = self.peek() {
+            match byte {
+                b' ' | b'\t' | b'\r' => {}
+                b'\n' => {
+                    self.line += 1;
+                    self.line_start = self.pos + 1;
+                }
+                _ => return,
+            }
+            self.pos += 1;
+        }
+    }
+
+    /// The text of a number: ASCII only.
+    fn lexeme(&self, start: usize) -> &'a str {
+        let input: &'a [u8] = self.input;
+        str::from_utf8(&input[start..self.pos]).unwrap_or_default()
+    }
+
+    fn lex_word(&mut self, word: &'static str, token: SliceToken<'a>) -> SliceResult<'a> {
+        if self.input[self.pos..].starts_with(word.as_bytes()) {
+            self.pos += word.len();
+            Ok(token)
+        } else {
+            self.pos += 1;
+            Err(self.error(LexErrorKind::ExpectedWord(word.into()), format!("Expected word `{}`", word)))
+        }
+    }
+
+    fn skip_digits(&mut self) -> usize {
+        let start = self.pos;
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.pos += 1;
+        }
+        self.pos - start
+    }
+
+    fn lex_number(&mut self) -> SliceResult<'a> {
+        let start = self.pos;
+        if self.peek() == Some(b'-') {
+            self.pos += 1;
+            match self.peek() {
+                Some(b'0'..=b'9') => {}
+                Some(byte) => {
+                    self.pos += 1;
+                    return Err(self.error(LexErrorKind::ExpectedDigit(byte as char), format!("Expected a digit `{}`", byte as char)));
+                }
+                None => return Err(self.error(LexErrorKind::MissingDigits, format!("Missing digits `{}`", self.lexeme(start)))),
+            }
+        }
+        if self.peek() == Some(b'0') {
+            self.pos += 1;
+            if let Some(byte @ b'0'..=b'9') = self.peek() {
+                self.pos += 1;
+                return Err(self.error(LexErrorKind::LeadingZero, format!("Leading zero in `0{}`", byte as char)));
+            }
+        } else {
+            self.skip_digits();
+        }
+        let mut float = false;
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            float = true;
+            if self.skip_digits() == 0 {
+                return Err(self.error(LexErrorKind::MissingDecimals, format!("Missing decimals `{}`", self.lexeme(start))));
+            }
+        }
+        if let Some(b'e' | b'E') = self.peek() {
+            self.pos += 1;
+            float = true;
+            if let Some(b'+' | b'-') = self.peek() {
+                self.pos += 1;
+            }
+            if self.skip_digits() == 0 {
+                return Err(self.error(LexErrorKind::MissingExponent, format!("Missing exp `{}`", self.lexeme(start))));
+            }
+        }
+        let lexeme = self.lexeme(start);
+        Ok(if float { SliceToken::FloatValue(lexeme) } else { SliceToken::IntValue(lexeme) })
+    }
+
+    fn lex_string(&mut self) -> SliceResult<'a> {
+        let input: &'a [u8] = self.input;
+        self.pos += 1;
+        let start = self.pos;
+        // the unescaped string, once an escape is found
+        let mut owned: Option<Vec<u8>> = None;
+        loop {
+            let byte = match self.peek() {
+                Some(byte) => byte,
+                None => return Err(self.error(LexErrorKind::UnfinishedString, "Unfinished string".into())),
+            };
+            self.pos += 1;
+            match byte {
+                b'"' => break,
+                b'\\' => {
+                    let buf = owned.get_or_insert_with(|| input[start..self.pos - 1].to_vec());
+                    let escaped = match input.get(self.pos) {
+                        Some(escaped) => *escaped,
+                        None => return Err(self.error(LexErrorKind::UnfinishedString, "Unfinished string".into())),
+                    };
+                    self.pos += 1;
+                    let c = match escaped {
+                        b'"' => '"',
+                        b'\\' => '\\',
+                        b'/' => '/',
+                        b'b' => '\u{8}',
+                        b'f' => '\u{c}',
+                        b'n' => '\n',
+                        b'r' => '\r',
+                        b't' => '\t',
+                        b'u' => self.lex_unicode_escape()?,
+                        _ => return Err(self.error(LexErrorKind::UnknownEscape(escaped as char), format!("Unknown escaped char `{}`", escaped as char))),
+                    };
+                    let mut utf8 = [0u8; 4];
+                    buf.extend_from_slice(c.encode_utf8(&mut utf8).as_bytes());
+                }
+                0..=0x1f => return Err(self.error(LexErrorKind::ControlChar(byte), format!("Unescaped control char `0x{:02x}` in string", byte))),
+                _ => {
+                    if let Some(buf) = owned.as_mut() {
+                        buf.push(byte);
+                    }
+                }
+            }
+        }
+        match owned {
+            None => match str::from_utf8(&input[start..self.pos - 1]) {
+                Ok(s) => Ok(SliceToken::String(Cow::Borrowed(s))),
+                Err(e) => Err(self.error(LexErrorKind::InvalidUtf8, format!("Can't decode string `{}`", e))),
+            },
+            Some(buf) => match String::from_utf8(buf) {
+                Ok(s) => Ok(SliceToken::String(Cow::Owned(s))),
+                Err(e) => Err(self.error(LexErrorKind::InvalidUtf8, format!("Can't decode string `{}`", e))),
+            },
+        }
+    }
+
+    /// The four hex digits after `\u`.
+    fn lex_hex4(&mut self) -> Result<u32, JSONLexError> {
+        let mut code_point = 0;
+        for _ in 0..4 {
+            let byte = match self.peek() {
+                Some(byte) => byte,
+                None => return Err(self.error(LexErrorKind::UnfinishedString, "Unfinished string".into())),
+            };
+            self.pos += 1;
+            let digit = match (byte as char).to_digit(16) {
+                Some(digit) => digit,
+                None => return Err(self.error(LexErrorKind::InvalidHexDigit(byte as char), format!("Unknown hex digit `{}`", byte as char))),
+            };
+            code_point = code_point * 16 + digit;
+        }
+        Ok(code_point)
+    }
+
+    /// The char of a `\uXXXX` escape, or of a pair of surrogates.
+    fn lex_unicode_escape(&mut self) -> Result<char, JSONLexError> {
+        let code_point = self.lex_hex4()?;
+        let code_point = if (0xD800..0xDC00).contains(&code_point) {
+            if !self.input[self.pos..].starts_with(b"\\u") {
+                return Err(self.error(LexErrorKind::LoneSurrogate, "Waiting for low surrogate: needs \\u".into()));
+            }
+            self.pos += 2;
+            let low = self.lex_hex4()?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(self.error(LexErrorKind::LoneSurrogate, format!("Waiting for low surrogate, got `{}`", low)));
+            }
+            0x10000 + ((code_point - 0xD800) << 10) + (low - 0xDC00)
+        } else {
+            code_point
+        };
+        char::from_u32(code_point)
+            .ok_or_else(|| self.error(LexErrorKind::InvalidCodePoint(code_point), format!("This is not a code point `{}`", code_point)))
+    }
+
+    fn lex_token(&mut self) -> SliceResult<'a> {
+        let byte = self.input[self.pos];
+        let token = match byte {
+            b'{' => SliceToken::BeginObject,
+            b'}' => SliceToken::EndObject,
+            b'[' => SliceToken::BeginArray,
+            b']' => SliceToken::EndArray,
+            b':' => SliceToken::NameSeparator,
+            b',' => SliceToken::ValueSeparator,
+            b't' => return self.lex_word("true", SliceToken::BooleanValue(true)),
+            b'f' => return self.lex_word("false", SliceToken::BooleanValue(false)),
+            b'n' => return self.lex_word("null", SliceToken::NullValue),
+            b'"' => return self.lex_string(),
+            b'-' | b'0'..=b'9' => return self.lex_number(),
+            _ => {
+                self.pos += 1;
+                return Err(self.error(LexErrorKind::UnexpectedChar(byte as char), format!("Unexpected char `{}`", byte as char)));
+            }
+        };
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn span(&self, start: usize) -> Span {
+        Span { start, end: self.pos, line: self.line, column: self.pos - self.line_start }
+    }
+}
+
+impl<'a> Iterator for JSONSliceLexer<'a> {
+    type Item = (SliceResult<'a>, Span);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.started {
+            self.started = true;
+            return Some((Ok(SliceToken::BeginFile), Span::default()));
+        }
+        if self.finished {
+            return None;
+        }
+        self.skip_whitespace();
+        let start = self.pos;
+        if start == self.input.len() {
+            self.finished = true;
+            return Some((Ok(SliceToken::EndFile), self.span(start)));
+        }
+        let token = self.lex_token();
+        let span = self.span(start);
+        if token.is_err() {
+            // the EndFile follows the error
+            self.pos = self.input.len();
+        }
+        Some((token, span))
+    }
+}
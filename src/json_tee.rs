@@ -0,0 +1,128 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Send the tokens of one parse to several consumers, e.g. write the XML and collect the
+//! statistics without reading the input twice.
+
+use crate::json_lexer::{ConsumeError, Span};
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserToken};
+
+/// What a `TeeConsumer` does when a branch returns an error.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum TeeErrorPolicy {
+    /// Stop the parsing with the error of the branch.
+    #[default]
+    StopAll,
+    /// Detach the branch, record its error (see `TeeConsumer::errors`) and go on with the other
+    /// branches. The parsing stops when every branch failed.
+    ContinueOthers,
+}
+
+/// A consumer that forwards each token to every branch, in the order of the branches. A branch
+/// that is done (see `JSONParseConsumer::is_done`) does not receive the next tokens.
+pub struct TeeConsumer<'a> {
+    branches: Vec<Option<&'a mut dyn JSONParseConsumer>>,
+    error_policy: TeeErrorPolicy,
+    errors: Vec<(usize, ConsumeError)>,
+}
+
+impl<'a> TeeConsumer<'a> {
+    pub fn new() -> Self {
+        TeeConsumer {
+            branches: vec!(),
+            error_policy: TeeErrorPolicy::StopAll,
+            errors: vec!(),
+        }
+    }
+
+    /// Add a branch.
+    pub fn branch(mut self, consumer: &'a mut dyn JSONParseConsumer) -> Self {
+        self.branches.push(Some(consumer));
+        self
+    }
+
+    pub fn error_policy(mut self, error_policy: TeeErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// The errors of the detached branches, with the index of the branch.
+    pub fn errors(&self) -> &[(usize, ConsumeError)] {
+        &self.errors
+    }
+
+    fn forward<F>(&mut self, mut consume: F) -> Result<(), ConsumeError>
+        where F: FnMut(&mut dyn JSONParseConsumer) -> Result<(), ConsumeError> {
+        for (i, branch) in self.branches.iter_mut().enumerate() {
+            let consumer = match branch {
+                Some(consumer) if !consumer.is_done() => consumer,
+                _ => continue,
+            };
+            if let Err(e) = consume(&mut **consumer) {
+                match self.error_policy {
+                    TeeErrorPolicy::StopAll => return Err(e),
+                    TeeErrorPolicy::ContinueOthers => {
+                        *branch = None;
+                        self.errors.push((i, e));
+                    }
+                }
+            }
+        }
+        if !self.branches.is_empty() && self.branches.iter().all(|b| b.is_none()) {
+            let (_, e) = &self.errors[0];
+            return Err(ConsumeError {
+                msg: format!("Every branch failed: {}", e.msg),
+                line: e.line,
+                column: e.column,
+                io_error: None,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for TeeConsumer<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JSONParseConsumer for TeeConsumer<'_> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.forward(|consumer| consumer.consume(token.clone()))
+    }
+
+    fn consume_with_span(&mut self, token: Result<ParserToken, JSONParseError>, span: Span) -> Result<(), ConsumeError> {
+        self.forward(|consumer| consumer.consume_with_span(token.clone(), span))
+    }
+
+    /// A value is skipped only if every branch skips it.
+    fn skip_value(&self) -> bool {
+        let mut live = self.branches.iter().flatten().peekable();
+        live.peek().is_some() && live.all(|consumer| consumer.skip_value() || consumer.is_done())
+    }
+
+    /// The parsing stops when every branch is done.
+    fn is_done(&self) -> bool {
+        let mut live = self.branches.iter().flatten().peekable();
+        live.peek().is_some() && live.all(|consumer| consumer.is_done())
+    }
+}
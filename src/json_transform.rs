@@ -0,0 +1,412 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::cmp::Ordering;
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::json_pointer::{JSONPointer, PathElement, PathTracker};
+
+struct TokensConsumer {
+    tokens: Vec<ParserToken>,
+}
+
+impl JSONParseConsumer for TokensConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        match token? {
+            ParserToken::BeginFile | ParserToken::EndFile => {}
+            t => self.tokens.push(t),
+        }
+        Ok(())
+    }
+}
+
+/// The tokens of a JSON value, without `BeginFile`/`EndFile`.
+pub fn value_tokens(json: &str) -> Result<Vec<ParserToken>, ConsumeError> {
+    let mut consumer = TokensConsumer { tokens: vec!() };
+    JSONParser::new(ByteSource::new(json.as_bytes()), false).parse(&mut consumer)?;
+    Ok(consumer.tokens)
+}
+
+/// A consumer that replaces the values at some JSON Pointers and forwards everything else
+/// unchanged. A `*` token matches any key or index, e.g. `/users/*/id`. The `replace` function receives the path and the tokens of the original value and
+/// returns the tokens of the new value. Only the matching values are buffered.
+pub struct SubstituteConsumer<'a, C: JSONParseConsumer, F: FnMut(&[PathElement], Vec<ParserToken>) -> Vec<ParserToken>> {
+    consumer: &'a mut C,
+    pointers: Vec<JSONPointer>,
+    replace: F,
+    tracker: PathTracker,
+    captured: Vec<ParserToken>,
+    captured_path: Vec<PathElement>,
+    depth: isize,
+}
+
+impl<'a, C: JSONParseConsumer, F: FnMut(&[PathElement], Vec<ParserToken>) -> Vec<ParserToken>> SubstituteConsumer<'a, C, F> {
+    pub fn new(consumer: &'a mut C, pointers: Vec<JSONPointer>, replace: F) -> Self {
+        SubstituteConsumer {
+            consumer,
+            pointers,
+            replace,
+            tracker: PathTracker::new(),
+            captured: vec!(),
+            captured_path: vec!(),
+            depth: 0,
+        }
+    }
+
+    fn emit_replacement(&mut self) -> Result<(), ConsumeError> {
+        let captured = std::mem::take(&mut self.captured);
+        for token in (self.replace)(&self.captured_path, captured) {
+            self.consumer.consume(Ok(token))?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a, C: JSONParseConsumer, F: FnMut(&[PathElement], Vec<ParserToken>) -> Vec<ParserToken>> JSONParseConsumer for SubstituteConsumer<'a, C, F> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = match token {
+            Ok(t) => t,
+            Err(e) => return self.consumer.consume(Err(e)),
+        };
+        self.tracker.update(&token);
+        if self.depth > 0 {
+            self.depth += depth_delta(&token);
+            self.captured.push(token);
+            if self.depth == 0 {
+                self.emit_replacement()?;
+            }
+            return Ok(());
+        }
+        if is_value_start(&token) && self.pointers.iter().any(|p| p.matches_pattern(self.tracker.path())) {
+            self.captured_path = self.tracker.path().to_vec();
+            // a container or a chunked string
+            let depth = depth_delta(&token);
+            self.captured.push(token);
+            if depth > 0 {
+                self.depth = depth;
+                return Ok(());
+            }
+            return self.emit_replacement();
+        }
+        self.consumer.consume(Ok(token))
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
+
+/// The members of an open object, or None for an array.
+type SortFrame = Option<Vec<(String, Vec<ParserToken>)>>;
+
+/// A consumer that forwards the tokens with the members of every object sorted by key. The
+/// members of the open objects are buffered until the end of the object; arrays outside of any
+/// object are streamed. The sort is stable: the duplicate keys keep their order.
+pub struct SortKeysConsumer<'a, C: JSONParseConsumer, F: FnMut(&str, &str) -> Ordering> {
+    consumer: &'a mut C,
+    compare: F,
+    frames: Vec<SortFrame>,
+}
+
+impl<'a, C: JSONParseConsumer> SortKeysConsumer<'a, C, fn(&str, &str) -> Ordering> {
+    /// Sort the keys by code point.
+    pub fn new(consumer: &'a mut C) -> Self {
+        SortKeysConsumer::with_comparator(consumer, |a, b| a.cmp(b))
+    }
+}
+
+impl<'a, C: JSONParseConsumer, F: FnMut(&str, &str) -> Ordering> SortKeysConsumer<'a, C, F> {
+    pub fn with_comparator(consumer: &'a mut C, compare: F) -> Self {
+        SortKeysConsumer {
+            consumer,
+            compare,
+            frames: vec!(),
+        }
+    }
+
+    /// Add the token to the innermost object member, or forward it if there is none.
+    fn emit(&mut self, token: ParserToken) -> Result<(), ConsumeError> {
+        let member = self.frames.iter_mut().rev().find_map(|frame| frame.as_mut().and_then(|m| m.last_mut()));
+        match member {
+            Some((_, tokens)) => {
+                tokens.push(token);
+                Ok(())
+            }
+            None => self.consumer.consume(Ok(token)),
+        }
+    }
+}
+
+impl<'a, C: JSONParseConsumer, F: FnMut(&str, &str) -> Ordering> JSONParseConsumer for SortKeysConsumer<'a, C, F> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = match token {
+            Ok(t) => t,
+            Err(e) => return self.consumer.consume(Err(e)),
+        };
+        match token {
+            ParserToken::BeginObject => {
+                self.emit(token)?;
+                self.frames.push(Some(vec!()));
+            }
+            ParserToken::BeginArray => {
+                self.emit(token)?;
+                self.frames.push(None);
+            }
+            ParserToken::Key(k) => {
+                if let Some(Some(members)) = self.frames.last_mut() {
+                    members.push((k, vec!()));
+                }
+            }
+            ParserToken::EndObject => {
+                if let Some(Some(mut members)) = self.frames.pop() {
+                    let compare = &mut self.compare;
+                    members.sort_by(|(a, _), (b, _)| compare(a, b));
+                    for (key, tokens) in members {
+                        self.emit(ParserToken::Key(key))?;
+                        for token in tokens {
+                            self.emit(token)?;
+                        }
+                    }
+                }
+                self.emit(ParserToken::EndObject)?;
+            }
+            ParserToken::EndArray => {
+                self.frames.pop();
+                self.emit(token)?;
+            }
+            _ => self.emit(token)?,
+        }
+        Ok(())
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
+
+/// A stage of a pipeline of tokens: receives a token and pushes zero or more tokens to `out`.
+/// The errors of the parser don't go through the transformers.
+///
+/// ```
+/// use r_json_event_parser::byte_source::ByteSource;
+/// use r_json_event_parser::json_parser::JSONParser;
+/// use r_json_event_parser::json_transform::{drop_nulls, rename_key, TokenTransformer, TransformConsumer};
+/// use r_json_event_parser::json_writer::JSONWriteConsumer;
+///
+/// let mut writer = JSONWriteConsumer::new(vec!());
+/// let mut consumer = TransformConsumer::new(&mut writer, rename_key("a", "b").then(drop_nulls()));
+/// JSONParser::new(ByteSource::new(r#"{"a": 1, "c": null}"#.as_bytes()), false).parse(&mut consumer).unwrap();
+/// assert_eq!(br#"{"b":1}"#.to_vec(), writer.into_inner());
+/// ```
+pub trait TokenTransformer {
+    fn transform(&mut self, token: ParserToken, out: &mut Vec<ParserToken>) -> Result<(), ConsumeError>;
+
+    /// This transformer followed by `next`.
+    fn then<T: TokenTransformer>(self, next: T) -> Chain<Self, T> where Self: Sized {
+        chain(self, next)
+    }
+}
+
+impl<T: TokenTransformer + ?Sized> TokenTransformer for Box<T> {
+    fn transform(&mut self, token: ParserToken, out: &mut Vec<ParserToken>) -> Result<(), ConsumeError> {
+        (**self).transform(token, out)
+    }
+}
+
+/// A consumer that sends the tokens through a transformer to another consumer.
+pub struct TransformConsumer<'a, C: JSONParseConsumer, T: TokenTransformer> {
+    consumer: &'a mut C,
+    transformer: T,
+    out: Vec<ParserToken>,
+}
+
+impl<'a, C: JSONParseConsumer, T: TokenTransformer> TransformConsumer<'a, C, T> {
+    pub fn new(consumer: &'a mut C, transformer: T) -> Self {
+        TransformConsumer {
+            consumer,
+            transformer,
+            out: vec!(),
+        }
+    }
+}
+
+impl<'a, C: JSONParseConsumer, T: TokenTransformer> JSONParseConsumer for TransformConsumer<'a, C, T> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = match token {
+            Ok(t) => t,
+            Err(e) => return self.consumer.consume(Err(e)),
+        };
+        self.transformer.transform(token, &mut self.out)?;
+        for token in self.out.drain(..) {
+            self.consumer.consume(Ok(token))?;
+        }
+        Ok(())
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
+
+/// See `chain`.
+pub struct Chain<A: TokenTransformer, B: TokenTransformer> {
+    first: A,
+    second: B,
+    buffer: Vec<ParserToken>,
+}
+
+/// The tokens go through `first`, then through `second`.
+pub fn chain<A: TokenTransformer, B: TokenTransformer>(first: A, second: B) -> Chain<A, B> {
+    Chain { first, second, buffer: vec!() }
+}
+
+impl<A: TokenTransformer, B: TokenTransformer> TokenTransformer for Chain<A, B> {
+    fn transform(&mut self, token: ParserToken, out: &mut Vec<ParserToken>) -> Result<(), ConsumeError> {
+        self.first.transform(token, &mut self.buffer)?;
+        for token in std::mem::take(&mut self.buffer) {
+            self.second.transform(token, out)?;
+        }
+        Ok(())
+    }
+}
+
+/// See `map_keys`.
+pub struct MapKeys<F: FnMut(&str) -> String> {
+    map: F,
+}
+
+/// Replace every key `k` by `map(k)`.
+pub fn map_keys<F: FnMut(&str) -> String>(map: F) -> MapKeys<F> {
+    MapKeys { map }
+}
+
+impl<F: FnMut(&str) -> String> TokenTransformer for MapKeys<F> {
+    fn transform(&mut self, token: ParserToken, out: &mut Vec<ParserToken>) -> Result<(), ConsumeError> {
+        match token {
+            ParserToken::Key(k) => out.push(ParserToken::Key((self.map)(&k))),
+            t => out.push(t),
+        }
+        Ok(())
+    }
+}
+
+/// Rename the keys `from` to `to`, at any depth.
+pub fn rename_key(from: &str, to: &str) -> MapKeys<impl FnMut(&str) -> String> {
+    let (from, to) = (from.to_string(), to.to_string());
+    map_keys(move |k: &str| if k == from { to.clone() } else { k.to_string() })
+}
+
+/// +1 for a token that opens a value, -1 for a token that closes it.
+pub(crate) fn depth_delta(token: &ParserToken) -> isize {
+    match token {
+        ParserToken::BeginObject | ParserToken::BeginArray | ParserToken::StringStart => 1,
+        ParserToken::EndObject | ParserToken::EndArray | ParserToken::StringEnd => -1,
+        _ => 0,
+    }
+}
+
+/// true if the token is the first token of a value.
+pub(crate) fn is_value_start(token: &ParserToken) -> bool {
+    !matches!(token, ParserToken::BeginFile | ParserToken::EndFile | ParserToken::Key(_) | ParserToken::EndObject
+        | ParserToken::EndArray | ParserToken::StringChunk(_) | ParserToken::StringEnd)
+}
+
+/// See `filter_paths`.
+pub struct FilterPaths<F: FnMut(&[PathElement]) -> bool> {
+    keep: F,
+    tracker: PathTracker,
+    /// The depth in the value being dropped
+    depth: isize,
+    /// true if the value after the current key is dropped
+    drop_value: bool,
+}
+
+/// Drop the values whose path doesn't satisfy `keep` (the root is always kept). A dropped
+/// member loses its key; the elements after a dropped element of an array are shifted.
+pub fn filter_paths<F: FnMut(&[PathElement]) -> bool>(keep: F) -> FilterPaths<F> {
+    FilterPaths { keep, tracker: PathTracker::new(), depth: 0, drop_value: false }
+}
+
+impl<F: FnMut(&[PathElement]) -> bool> TokenTransformer for FilterPaths<F> {
+    fn transform(&mut self, token: ParserToken, out: &mut Vec<ParserToken>) -> Result<(), ConsumeError> {
+        self.tracker.update(&token);
+        if self.depth > 0 {
+            self.depth += depth_delta(&token);
+            return Ok(());
+        }
+        if let ParserToken::Key(_) = token {
+            self.drop_value = !(self.keep)(self.tracker.path());
+            if !self.drop_value {
+                out.push(token);
+            }
+            return Ok(());
+        }
+        if is_value_start(&token) {
+            let path = self.tracker.path();
+            let drop_value = std::mem::take(&mut self.drop_value)
+                || (matches!(path.last(), Some(PathElement::Index(_))) && !(self.keep)(path));
+            if drop_value {
+                self.depth = depth_delta(&token);
+                return Ok(());
+            }
+        }
+        out.push(token);
+        Ok(())
+    }
+}
+
+/// See `drop_nulls`.
+pub struct DropNulls {
+    /// The key of the current member, held until its value is known
+    key: Option<String>,
+    depth: usize,
+}
+
+/// Drop the `null` members of the objects and the `null` elements of the arrays. A `null`
+/// document is kept.
+pub fn drop_nulls() -> DropNulls {
+    DropNulls { key: None, depth: 0 }
+}
+
+impl TokenTransformer for DropNulls {
+    fn transform(&mut self, token: ParserToken, out: &mut Vec<ParserToken>) -> Result<(), ConsumeError> {
+        match token {
+            ParserToken::Key(k) => {
+                self.key = Some(k);
+                return Ok(());
+            }
+            ParserToken::NullValue if self.depth > 0 => {
+                self.key = None;
+                return Ok(());
+            }
+            ParserToken::BeginObject | ParserToken::BeginArray => self.depth += 1,
+            ParserToken::EndObject | ParserToken::EndArray => self.depth -= 1,
+            _ => {}
+        }
+        if let Some(k) = self.key.take() {
+            out.push(ParserToken::Key(k));
+        }
+        out.push(token);
+        Ok(())
+    }
+}
@@ -0,0 +1,270 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::json_pointer::{JSONPointer, JSONPointerError, PathTracker};
+use crate::json_transform::is_value_start;
+
+/// An owned JSON value. The members of an object are kept in the document order, duplicate
+/// keys included, and the numbers are kept as lexemes.
+#[derive(Debug, PartialEq, Clone)]
+pub enum JsonValue {
+    Object(Vec<(String, JsonValue)>),
+    Array(Vec<JsonValue>),
+    String(String),
+    Int(String),
+    Float(String),
+    Bool(bool),
+    Null,
+}
+
+impl JsonValue {
+    /// Parse a whole document.
+    pub fn parse(json: &str) -> Result<JsonValue, ConsumeError> {
+        let mut consumer = ValueBuilderConsumer::new();
+        JSONParser::new(ByteSource::new(json.as_bytes()), false).parse(&mut consumer)?;
+        consumer.into_value().ok_or(ConsumeError { msg: "No value".into(), line: 0, column: 0, io_error: None })
+    }
+
+    /// The value of the last member with this key, if this is an object.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Object(members) => members.iter().rev().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// The element at this index, if this is an array.
+    pub fn at(&self, index: usize) -> Option<&JsonValue> {
+        match self {
+            JsonValue::Array(elements) => elements.get(index),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            JsonValue::Int(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Int(s) | JsonValue::Float(s) => s.parse().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == JsonValue::Null
+    }
+
+    /// The tokens of this value, without `BeginFile`/`EndFile`.
+    pub fn to_tokens(&self) -> Vec<ParserToken> {
+        let mut tokens = vec!();
+        self.push_tokens(&mut tokens);
+        tokens
+    }
+
+    fn push_tokens(&self, tokens: &mut Vec<ParserToken>) {
+        match self {
+            JsonValue::Object(members) => {
+                tokens.push(ParserToken::BeginObject);
+                for (k, v) in members {
+                    tokens.push(ParserToken::Key(k.clone()));
+                    v.push_tokens(tokens);
+                }
+                tokens.push(ParserToken::EndObject);
+            }
+            JsonValue::Array(elements) => {
+                tokens.push(ParserToken::BeginArray);
+                for v in elements {
+                    v.push_tokens(tokens);
+                }
+                tokens.push(ParserToken::EndArray);
+            }
+            JsonValue::String(s) => tokens.push(ParserToken::StringValue(s.clone())),
+            JsonValue::Int(s) => tokens.push(ParserToken::IntValue(s.clone())),
+            JsonValue::Float(s) => tokens.push(ParserToken::FloatValue(s.clone())),
+            JsonValue::Bool(b) => tokens.push(ParserToken::BooleanValue(*b)),
+            JsonValue::Null => tokens.push(ParserToken::NullValue),
+        }
+    }
+}
+
+/// A consumer that builds a `JsonValue` from the tokens. `BeginFile` and `EndFile` are ignored,
+/// hence the tokens of a single value may be fed to the consumer.
+pub struct ValueBuilderConsumer {
+    /// The open containers, with the key of the container in its parent object.
+    stack: Vec<(Option<String>, JsonValue)>,
+    key: Option<String>,
+    value: Option<JsonValue>,
+    /// The chunks of a string
+    chunks: Vec<u8>,
+}
+
+impl ValueBuilderConsumer {
+    pub fn new() -> Self {
+        ValueBuilderConsumer {
+            stack: vec!(),
+            key: None,
+            value: None,
+            chunks: vec!(),
+        }
+    }
+
+    /// The value, once it is complete.
+    pub fn value(&self) -> Option<&JsonValue> {
+        self.value.as_ref()
+    }
+
+    pub fn into_value(self) -> Option<JsonValue> {
+        self.value
+    }
+
+    /// Add a complete value to the innermost container, or make it the result.
+    fn add(&mut self, key: Option<String>, value: JsonValue) {
+        match self.stack.last_mut() {
+            Some((_, JsonValue::Object(members))) => members.push((key.unwrap_or_default(), value)),
+            Some((_, JsonValue::Array(elements))) => elements.push(value),
+            _ => self.value = Some(value),
+        }
+    }
+}
+
+impl Default for ValueBuilderConsumer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl JSONParseConsumer for ValueBuilderConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        let value = match token {
+            ParserToken::BeginFile | ParserToken::EndFile => return Ok(()),
+            ParserToken::Key(k) => {
+                self.key = Some(k);
+                return Ok(());
+            }
+            ParserToken::BeginObject => {
+                self.stack.push((self.key.take(), JsonValue::Object(vec!())));
+                return Ok(());
+            }
+            ParserToken::BeginArray => {
+                self.stack.push((self.key.take(), JsonValue::Array(vec!())));
+                return Ok(());
+            }
+            ParserToken::EndObject | ParserToken::EndArray => {
+                if let Some((key, container)) = self.stack.pop() {
+                    self.add(key, container);
+                }
+                return Ok(());
+            }
+            ParserToken::StringValue(s) => JsonValue::String(s),
+            ParserToken::BytesValue(b) => JsonValue::String(String::from_utf8_lossy(&b).into_owned()),
+            ParserToken::StringStart => return Ok(()),
+            ParserToken::StringChunk(chunk) => {
+                self.chunks.extend(chunk);
+                return Ok(());
+            }
+            ParserToken::StringEnd => {
+                let chunks = std::mem::take(&mut self.chunks);
+                JsonValue::String(String::from_utf8_lossy(&chunks).into_owned())
+            }
+            ParserToken::IntValue(s) => JsonValue::Int(s),
+            ParserToken::FloatValue(s) => JsonValue::Float(s),
+            ParserToken::BooleanValue(b) => JsonValue::Bool(b),
+            ParserToken::NullValue => JsonValue::Null,
+        };
+        let key = self.key.take();
+        self.add(key, value);
+        Ok(())
+    }
+}
+
+/// A consumer that forwards every token to another consumer and builds the value found at a
+/// JSON Pointer, e.g. `/items/3/config`. The `callback` receives the value once it is complete.
+/// Only this value is materialized.
+pub struct SubtreeCaptureConsumer<'a, C: JSONParseConsumer, F: FnMut(JsonValue)> {
+    consumer: &'a mut C,
+    pointer: JSONPointer,
+    callback: F,
+    tracker: PathTracker,
+    builder: Option<ValueBuilderConsumer>,
+}
+
+impl<'a, C: JSONParseConsumer, F: FnMut(JsonValue)> SubtreeCaptureConsumer<'a, C, F> {
+    pub fn new(consumer: &'a mut C, path: &str, callback: F) -> Result<Self, JSONPointerError> {
+        Ok(SubtreeCaptureConsumer {
+            consumer,
+            pointer: JSONPointer::parse(path)?,
+            callback,
+            tracker: PathTracker::new(),
+            builder: None,
+        })
+    }
+}
+
+impl<'a, C: JSONParseConsumer, F: FnMut(JsonValue)> JSONParseConsumer for SubtreeCaptureConsumer<'a, C, F> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        if let Ok(t) = &token {
+            self.tracker.update(t);
+            if self.builder.is_none() && is_value_start(t) && self.pointer.matches(self.tracker.path()) {
+                self.builder = Some(ValueBuilderConsumer::new());
+            }
+            if let Some(builder) = self.builder.as_mut() {
+                builder.consume(Ok(t.clone()))?;
+                if builder.value().is_some() {
+                    if let Some(value) = self.builder.take().and_then(|b| b.into_value()) {
+                        (self.callback)(value);
+                    }
+                }
+            }
+        }
+        self.consumer.consume(token)
+    }
+
+    fn skip_value(&self) -> bool {
+        self.builder.is_none() && !self.pointer.is_prefix_of(&self.tracker.next_path()) && self.consumer.skip_value()
+    }
+
+    fn is_done(&self) -> bool {
+        self.consumer.is_done()
+    }
+}
@@ -0,0 +1,163 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A thin WebAssembly wrapper around the parser: the input is a `Uint8Array` and the events are
+//! sent to a JavaScript callback `(event, value) => void`, e.g. `("key", "a")`, `("int", "10")` or
+//! `("beginObject", undefined)`. The numbers are sent as their text, to keep the precision; the
+//! caller decides whether to use `Number`, `BigInt` or a decimal library.
+//!
+//! An error is thrown as an object `{kind, message, line, column}`. An exception thrown by the
+//! callback stops the parser and is rethrown as is.
+//!
+//! Build the module with
+//! `cargo rustc --release --lib --target wasm32-unknown-unknown --features wasm --crate-type cdylib`,
+//! then generate the JavaScript glue with `wasm-bindgen`.
+
+use js_sys::{Function, Object, Reflect, Uint8Array};
+use wasm_bindgen::prelude::*;
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::{ConsumeError, LexerOptions};
+use crate::json_parser::{JSONFeedParser, JSONParseConsumer, JSONParseError, JSONParser, ParseErrorKind, ParserToken};
+
+/// Parse a whole document. See `LexerOptions::strict`.
+#[wasm_bindgen]
+pub fn parse(input: &[u8], callback: &Function, strict: bool) -> Result<(), JsValue> {
+    let mut consumer = CallbackConsumer::new(callback);
+    let result = JSONParser::new_with_options(ByteSource::new(input), LexerOptions { strict, ..LexerOptions::default() }).parse(&mut consumer);
+    consumer.into_result(result)
+}
+
+/// A parser that receives the document by chunks, e.g. from a `ReadableStream`: call `feed` for
+/// every chunk and then `finish`. See `JSONFeedParser`.
+#[wasm_bindgen]
+pub struct WasmParser {
+    parser: JSONFeedParser,
+}
+
+#[wasm_bindgen]
+impl WasmParser {
+    #[wasm_bindgen(constructor)]
+    pub fn new(strict: bool) -> WasmParser {
+        WasmParser { parser: JSONFeedParser::new(LexerOptions { strict, ..LexerOptions::default() }) }
+    }
+
+    pub fn feed(&mut self, chunk: &[u8], callback: &Function) -> Result<(), JsValue> {
+        let mut consumer = CallbackConsumer::new(callback);
+        let result = self.parser.feed(chunk, &mut consumer);
+        consumer.into_result(result)
+    }
+
+    pub fn finish(&mut self, callback: &Function) -> Result<(), JsValue> {
+        let mut consumer = CallbackConsumer::new(callback);
+        let result = self.parser.finish(&mut consumer);
+        consumer.into_result(result)
+    }
+
+    /// The number of bytes consumed so far.
+    #[wasm_bindgen(js_name = byteOffset)]
+    pub fn byte_offset(&self) -> usize {
+        self.parser.byte_offset()
+    }
+}
+
+struct CallbackConsumer<'a> {
+    callback: &'a Function,
+    /// The error to throw: the exception of the callback or a parse error
+    error: Option<JsValue>,
+}
+
+impl<'a> CallbackConsumer<'a> {
+    fn new(callback: &'a Function) -> Self {
+        CallbackConsumer { callback, error: None }
+    }
+
+    fn into_result(self, result: Result<(), ConsumeError>) -> Result<(), JsValue> {
+        match (self.error, result) {
+            (Some(error), _) => Err(error),
+            (None, Ok(())) => Ok(()),
+            (None, Err(e)) => Err(error_object("Consume", &e.msg, e.line, e.column)),
+        }
+    }
+}
+
+impl JSONParseConsumer for CallbackConsumer<'_> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let error = match token {
+            Ok(token) => {
+                let (event, value) = event(token);
+                match self.callback.call2(&JsValue::NULL, &JsValue::from_str(event), &value) {
+                    Ok(_) => return Ok(()),
+                    Err(e) => e,
+                }
+            }
+            Err(e) => error_object(kind_name(&e.kind), &e.msg, e.line, e.column),
+        };
+        self.error = Some(error);
+        Err(ConsumeError { msg: "Parse stopped".into(), line: 0, column: 0, io_error: None })
+    }
+}
+
+fn event(token: ParserToken) -> (&'static str, JsValue) {
+    match token {
+        ParserToken::BeginFile => ("beginFile", JsValue::UNDEFINED),
+        ParserToken::EndFile => ("endFile", JsValue::UNDEFINED),
+        ParserToken::BeginObject => ("beginObject", JsValue::UNDEFINED),
+        ParserToken::EndObject => ("endObject", JsValue::UNDEFINED),
+        ParserToken::BeginArray => ("beginArray", JsValue::UNDEFINED),
+        ParserToken::EndArray => ("endArray", JsValue::UNDEFINED),
+        ParserToken::Key(s) => ("key", JsValue::from_str(&s)),
+        ParserToken::BooleanValue(b) => ("boolean", JsValue::from_bool(b)),
+        ParserToken::NullValue => ("null", JsValue::NULL),
+        ParserToken::StringValue(s) => ("string", JsValue::from_str(&s)),
+        ParserToken::BytesValue(bytes) => ("bytes", Uint8Array::from(&bytes[..]).into()),
+        ParserToken::IntValue(s) => ("int", JsValue::from_str(&s)),
+        ParserToken::FloatValue(s) => ("float", JsValue::from_str(&s)),
+        ParserToken::StringStart => ("stringStart", JsValue::UNDEFINED),
+        ParserToken::StringChunk(bytes) => ("stringChunk", Uint8Array::from(&bytes[..]).into()),
+        ParserToken::StringEnd => ("stringEnd", JsValue::UNDEFINED),
+    }
+}
+
+fn kind_name(kind: &ParseErrorKind) -> &'static str {
+    match kind {
+        ParseErrorKind::UnexpectedToken(_) => "UnexpectedToken",
+        ParseErrorKind::UnclosedContainer => "UnclosedContainer",
+        ParseErrorKind::ValueAfterRoot => "ValueAfterRoot",
+        ParseErrorKind::DuplicateKey(_) => "DuplicateKey",
+        ParseErrorKind::Lex(_) => "Lex",
+        ParseErrorKind::PrecisionLoss(_) => "PrecisionLoss",
+        ParseErrorKind::Other => "Other",
+    }
+}
+
+fn error_object(kind: &str, message: &str, line: usize, column: usize) -> JsValue {
+    let object = Object::new();
+    for (name, value) in [
+        ("kind", JsValue::from_str(kind)),
+        ("message", JsValue::from_str(message)),
+        ("line", JsValue::from_f64(line as f64)),
+        ("column", JsValue::from_f64(column as f64)),
+    ] {
+        let _ = Reflect::set(&object, &JsValue::from_str(name), &value);
+    }
+    object.into()
+}
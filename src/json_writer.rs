@@ -0,0 +1,448 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io::{Read, Write};
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::{ConsumeError, JSONLexConsumer, JSONLexError, JSONLexer, LexerOptions, LexerToken, Span};
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParserMachine, ParserToken};
+
+/// Append the JSON serialization of a string to `out`: only the quote, the backslash and the
+/// control chars are escaped, as required by RFC 8785.
+pub(crate) fn escape_string(out: &mut Vec<u8>, s: &str) {
+    out.push(b'"');
+    escape_bytes(out, s.as_bytes());
+    out.push(b'"');
+}
+
+/// Append the escaped bytes of a string, without the quotes. The non ASCII bytes are copied:
+/// a chunk of a string may end in the middle of a char.
+pub(crate) fn escape_bytes(out: &mut Vec<u8>, bytes: &[u8]) {
+    for &b in bytes {
+        match b {
+            b'"' => out.extend_from_slice(b"\\\""),
+            b'\\' => out.extend_from_slice(b"\\\\"),
+            0x08 => out.extend_from_slice(b"\\b"),
+            b'\t' => out.extend_from_slice(b"\\t"),
+            b'\n' => out.extend_from_slice(b"\\n"),
+            0x0c => out.extend_from_slice(b"\\f"),
+            b'\r' => out.extend_from_slice(b"\\r"),
+            b if b < b' ' => out.extend_from_slice(format!("\\u{:04x}", b).as_bytes()),
+            b => out.push(b),
+        }
+    }
+}
+
+/// Append the escaped chars of a string, without the quotes. The non ASCII chars are written as
+/// `\u` escapes, as surrogate pairs outside of the BMP.
+pub(crate) fn escape_ascii(out: &mut Vec<u8>, s: &str) {
+    let mut units = [0u16; 2];
+    for c in s.chars() {
+        if c.is_ascii() {
+            escape_bytes(out, &[c as u8]);
+        } else {
+            for unit in c.encode_utf16(&mut units) {
+                out.extend_from_slice(format!("\\u{:04x}", unit).as_bytes());
+            }
+        }
+    }
+}
+
+/// The whitespace of the output.
+#[derive(Debug, PartialEq, Clone)]
+pub struct WriteStyle {
+    /// The indentation of one level.
+    pub indent: String,
+    /// The text between a key and its value, e.g. `": "`.
+    pub key_value_separator: String,
+    /// The line separator, e.g. `"\n"` or `"\r\n"`. An empty newline writes everything on
+    /// a single line.
+    pub newline: String,
+}
+
+impl WriteStyle {
+    /// No whitespace at all.
+    pub fn compact() -> Self {
+        WriteStyle {
+            indent: String::new(),
+            key_value_separator: ":".into(),
+            newline: String::new(),
+        }
+    }
+
+    /// One element per line, indented by `indent` spaces per level.
+    pub fn pretty(indent: usize) -> Self {
+        WriteStyle {
+            indent: " ".repeat(indent),
+            key_value_separator: ": ".into(),
+            newline: "\n".into(),
+        }
+    }
+}
+
+/// A consumer that writes the tokens back as JSON, compact or pretty printed. The numbers are
+/// written as they were read.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_parser::JSONParser;
+/// # use r_json_event_parser::json_writer::JSONWriteConsumer;
+/// let mut consumer = JSONWriteConsumer::new(vec!());
+/// let mut parser = JSONParser::new(ByteSource::new(r#"{ "a" : [1, 2.50] }"#.as_bytes()), false);
+/// parser.parse(&mut consumer).unwrap();
+/// assert_eq!(br#"{"a":[1,2.50]}"#.to_vec(), consumer.into_inner());
+/// ```
+pub struct JSONWriteConsumer<W: Write> {
+    destination: W,
+    pub(crate) style: WriteStyle,
+    /// For each open container: true before the first element.
+    firsts: Vec<bool>,
+    after_key: bool,
+    ascii_only: bool,
+    buf: Vec<u8>,
+}
+
+impl<W: Write> JSONWriteConsumer<W> {
+    /// A consumer that writes a compact JSON document.
+    pub fn new(destination: W) -> Self {
+        JSONWriteConsumer::with_style(destination, WriteStyle::compact())
+    }
+
+    /// A consumer that writes every element on its own line, indented by `indent` spaces per
+    /// level. Empty containers are written as `{}` and `[]`.
+    pub fn pretty(destination: W, indent: usize) -> Self {
+        JSONWriteConsumer::with_style(destination, WriteStyle::pretty(indent))
+    }
+
+    pub fn with_style(destination: W, style: WriteStyle) -> Self {
+        JSONWriteConsumer {
+            destination,
+            style,
+            firsts: vec!(),
+            after_key: false,
+            ascii_only: false,
+            buf: vec!(),
+        }
+    }
+
+    /// Write the non ASCII chars as `\u` escapes, e.g. `"\u00e9"`, for the readers that choke
+    /// on UTF-8.
+    pub fn ascii_only(mut self) -> Self {
+        self.ascii_only = true;
+        self
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.destination
+    }
+
+    pub fn into_inner(self) -> W {
+        self.destination
+    }
+
+    fn write_string(&mut self, s: &str) {
+        if self.ascii_only {
+            self.buf.push(b'"');
+            escape_ascii(&mut self.buf, s);
+            self.buf.push(b'"');
+        } else {
+            escape_string(&mut self.buf, s);
+        }
+    }
+
+    fn new_line(&mut self, depth: usize) {
+        if !self.style.newline.is_empty() {
+            self.buf.extend_from_slice(self.style.newline.as_bytes());
+            for _ in 0..depth {
+                self.buf.extend_from_slice(self.style.indent.as_bytes());
+            }
+        }
+    }
+
+    /// Write the separator before a value or a key.
+    fn begin_element(&mut self) {
+        if self.after_key {
+            self.after_key = false;
+            return;
+        }
+        let depth = self.firsts.len();
+        if let Some(first) = self.firsts.last_mut() {
+            if *first {
+                *first = false;
+            } else {
+                self.buf.push(b',');
+            }
+            self.new_line(depth);
+        }
+    }
+
+    fn end_container(&mut self, end: u8) {
+        if let Some(first) = self.firsts.pop() {
+            if !first {
+                let depth = self.firsts.len();
+                self.new_line(depth);
+            }
+        }
+        self.buf.push(end);
+    }
+}
+
+impl<W: Write> JSONParseConsumer for JSONWriteConsumer<W> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token?;
+        match token {
+            ParserToken::BeginFile => {}
+            ParserToken::EndFile => self.buf.extend_from_slice(self.style.newline.as_bytes()),
+            ParserToken::BeginObject => {
+                self.begin_element();
+                self.buf.push(b'{');
+                self.firsts.push(true);
+            }
+            ParserToken::EndObject => self.end_container(b'}'),
+            ParserToken::BeginArray => {
+                self.begin_element();
+                self.buf.push(b'[');
+                self.firsts.push(true);
+            }
+            ParserToken::EndArray => self.end_container(b']'),
+            ParserToken::Key(k) => {
+                self.begin_element();
+                self.write_string(&k);
+                self.buf.extend_from_slice(self.style.key_value_separator.as_bytes());
+                self.after_key = true;
+            }
+            ParserToken::StringValue(s) => {
+                self.begin_element();
+                self.write_string(&s);
+            }
+            ParserToken::BytesValue(b) => {
+                self.begin_element();
+                self.write_string(&String::from_utf8_lossy(&b));
+            }
+            ParserToken::StringStart => {
+                self.begin_element();
+                self.buf.push(b'"');
+            }
+            // the chunks end on a char boundary
+            ParserToken::StringChunk(chunk) if self.ascii_only => escape_ascii(&mut self.buf, &String::from_utf8_lossy(&chunk)),
+            ParserToken::StringChunk(chunk) => escape_bytes(&mut self.buf, &chunk),
+            ParserToken::StringEnd => self.buf.push(b'"'),
+            ParserToken::IntValue(s) | ParserToken::FloatValue(s) => {
+                self.begin_element();
+                self.buf.extend_from_slice(s.as_bytes());
+            }
+            ParserToken::BooleanValue(b) => {
+                self.begin_element();
+                self.buf.extend_from_slice(if b { b"true" } else { b"false" });
+            }
+            ParserToken::NullValue => {
+                self.begin_element();
+                self.buf.extend_from_slice(b"null");
+            }
+        }
+        self.destination.write_all(&self.buf).map_err(ConsumeError::from_io)?;
+        self.buf.clear();
+        Ok(())
+    }
+}
+
+/// Rewrite a document with the whitespace of `style` and nothing else changed: the number
+/// lexemes, the strings with their quotes and escapes and the order of the keys are kept as they
+/// were read, e.g. to reformat a file under code review. The comments are kept if `options`
+/// allows them.
+///
+/// `options` sets the dialect of the document; `raw_strings` and `emit_comments` are forced and
+/// `string_chunk_size` is ignored. The document is checked by the parser before being written.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_lexer::LexerOptions;
+/// # use r_json_event_parser::json_writer::{reformat, WriteStyle};
+/// let source = ByteSource::new(r#"{ "a" : [1.0E2, "\u00e9"] }"#.as_bytes());
+/// let destination = reformat(source, LexerOptions::default(), vec!(), WriteStyle::compact()).unwrap();
+/// assert_eq!(br#"{"a":[1.0E2,"\u00e9"]}"#.to_vec(), destination);
+/// ```
+pub fn reformat<R: Read, W: Write>(byte_source: ByteSource<R>, options: LexerOptions, destination: W, style: WriteStyle) -> Result<W, ConsumeError> {
+    let options = LexerOptions { raw_strings: true, emit_comments: true, string_chunk_size: None, ..options };
+    let mut lexer = JSONLexer::new_with_options(byte_source, options);
+    let mut consumer = JSONMinifyConsumer::with_style(destination, style);
+    lexer.lex(&mut consumer)?;
+    consumer.writer.destination.flush().map_err(ConsumeError::from_io)?;
+    Ok(consumer.into_inner())
+}
+
+/// Stop on the first parse error.
+struct CheckConsumer;
+
+impl JSONParseConsumer for CheckConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        token?;
+        Ok(())
+    }
+}
+
+/// A lexer consumer that writes the most compact JSON: no whitespace, and the number and string
+/// lexemes as they were read. The lexer must be created with `raw_strings`, else the strings
+/// are escaped again and the comments lose their delimiters. A line comment is followed by a
+/// newline. Each token is checked by the parser before being written: the first
+/// parse error is returned.
+///
+/// ```
+/// # use r_json_event_parser::byte_source::ByteSource;
+/// # use r_json_event_parser::json_lexer::{JSONLexer, LexerOptions};
+/// # use r_json_event_parser::json_writer::JSONMinifyConsumer;
+/// let options = LexerOptions { raw_strings: true, ..Default::default() };
+/// let mut lexer = JSONLexer::new_with_options(ByteSource::new(r#"{ "a" : [1.0E2, "\/"] }"#.as_bytes()), options);
+/// let mut consumer = JSONMinifyConsumer::new(vec!());
+/// lexer.lex(&mut consumer).unwrap();
+/// assert_eq!(br#"{"a":[1.0E2,"\/"]}"#.to_vec(), consumer.into_inner());
+/// ```
+pub struct JSONMinifyConsumer<W: Write> {
+    writer: JSONWriteConsumer<W>,
+    machine: ParserMachine,
+    /// true after a `,`: the comma is written before a comment
+    after_separator: bool,
+    /// true after a `//` comment: the next token starts on a new line
+    after_line_comment: bool,
+}
+
+impl<W: Write> JSONMinifyConsumer<W> {
+    pub fn new(destination: W) -> Self {
+        JSONMinifyConsumer::with_style(destination, WriteStyle::compact())
+    }
+
+    /// The consumer of `reformat`.
+    fn with_style(destination: W, style: WriteStyle) -> Self {
+        JSONMinifyConsumer {
+            writer: JSONWriteConsumer::with_style(destination, style),
+            machine: ParserMachine::new(),
+            after_separator: false,
+            after_line_comment: false,
+        }
+    }
+
+    pub fn get_ref(&self) -> &W {
+        self.writer.get_ref()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl<W: Write> JSONLexConsumer for JSONMinifyConsumer<W> {
+    fn consume(&mut self, token: Result<LexerToken, JSONLexError>, line: usize, column: usize) -> Result<(), ConsumeError> {
+        self.consume_with_span(token, Span { line, column, ..Default::default() })
+    }
+
+    fn consume_with_span(&mut self, token: Result<LexerToken, JSONLexError>, span: Span) -> Result<(), ConsumeError> {
+        let token = match token {
+            Ok(t) => t,
+            // the parser returns the error
+            Err(e) => return self.machine.consume(&mut CheckConsumer, Err(e), span),
+        };
+        self.machine.consume(&mut CheckConsumer, Ok(token.clone()), span)?;
+        let writer = &mut self.writer;
+        let after_separator = matches!(token, LexerToken::ValueSeparator);
+        let line_comment = matches!(&token, LexerToken::Comment(text) if text.starts_with("//"));
+        let depth = writer.firsts.len();
+        match token {
+            LexerToken::BeginFile | LexerToken::ValueSeparator | LexerToken::SkippedString => {}
+            LexerToken::EndFile => writer.buf.extend_from_slice(writer.style.newline.as_bytes()),
+            LexerToken::Comment(text) => {
+                if self.after_separator {
+                    // the next element is written without a comma
+                    writer.buf.push(b',');
+                    if let Some(first) = writer.firsts.last_mut() {
+                        *first = true;
+                    }
+                }
+                let spaced = self.after_line_comment || (writer.after_key && writer.style.key_value_separator.ends_with(' '));
+                if !writer.style.newline.is_empty() && !writer.firsts.is_empty() && !spaced {
+                    writer.buf.push(b' ');
+                }
+                writer.buf.extend_from_slice(text.as_bytes());
+            }
+            LexerToken::BeginObject => {
+                writer.begin_element();
+                writer.buf.push(b'{');
+                writer.firsts.push(true);
+            }
+            LexerToken::EndObject => writer.end_container(b'}'),
+            LexerToken::BeginArray => {
+                writer.begin_element();
+                writer.buf.push(b'[');
+                writer.firsts.push(true);
+            }
+            LexerToken::EndArray => writer.end_container(b']'),
+            LexerToken::NameSeparator => {
+                writer.buf.extend_from_slice(writer.style.key_value_separator.as_bytes());
+                writer.after_key = true;
+            }
+            LexerToken::RawString { raw, .. } => {
+                writer.begin_element();
+                writer.buf.extend_from_slice(raw.as_bytes());
+            }
+            LexerToken::String(s) => {
+                writer.begin_element();
+                writer.write_string(&s);
+            }
+            LexerToken::Bytes(b) => {
+                writer.begin_element();
+                writer.write_string(&String::from_utf8_lossy(&b));
+            }
+            LexerToken::StringStart => {
+                writer.begin_element();
+                writer.buf.push(b'"');
+            }
+            LexerToken::StringChunk(chunk) => escape_bytes(&mut writer.buf, &chunk),
+            LexerToken::StringEnd => writer.buf.push(b'"'),
+            LexerToken::IntValue(s) | LexerToken::FloatValue(s) => {
+                writer.begin_element();
+                writer.buf.extend_from_slice(s.as_bytes());
+            }
+            LexerToken::BooleanValue(b) => {
+                writer.begin_element();
+                writer.buf.extend_from_slice(if b { b"true" } else { b"false" });
+            }
+            LexerToken::NullValue => {
+                writer.begin_element();
+                writer.buf.extend_from_slice(b"null");
+            }
+        }
+        if self.after_line_comment && !writer.buf.is_empty() {
+            let newline: &[u8] = if writer.style.newline.is_empty() { b"\n" } else { writer.style.newline.as_bytes() };
+            if !writer.buf.starts_with(newline) {
+                writer.destination.write_all(newline).map_err(ConsumeError::from_io)?;
+                // the depth before a `{` and after a `}`
+                for _ in 0..depth.min(writer.firsts.len()) {
+                    writer.destination.write_all(writer.style.indent.as_bytes()).map_err(ConsumeError::from_io)?;
+                }
+            }
+            self.after_line_comment = false;
+        }
+        writer.destination.write_all(&writer.buf).map_err(ConsumeError::from_io)?;
+        writer.buf.clear();
+        self.after_separator = after_separator;
+        self.after_line_comment |= line_comment;
+        Ok(())
+    }
+}
@@ -19,7 +19,52 @@
  * along with this program.  If not, see <http://www.gnu.org/licenses/>.
  */
 
+pub mod batch;
 pub mod byte_source;
+pub mod cbor_lexer;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+pub mod fmt_write;
+pub mod input;
+pub mod json_canonical;
+pub mod json_diff;
+pub mod json_duplicates;
+pub mod json_filter;
+pub mod json_flatten;
 pub mod json_lexer;
+pub mod json_lines;
+pub mod json_metadata;
+#[cfg(feature = "unicode-normalization")]
+pub mod json_normalize;
+pub mod json_numbers;
 pub mod json_parser;
+pub mod json_patch;
+pub mod json_path;
+pub mod json_pointer;
+pub mod json_pretty;
+#[cfg(feature = "python")]
+pub mod json_python;
+pub mod json_query;
+pub mod json_redact;
+pub mod json_report;
+#[cfg(feature = "schema")]
+pub mod json_schema;
+#[cfg(feature = "serde")]
+pub mod json_serde;
+pub mod json_slice_lexer;
+pub mod json_tee;
+pub mod json_transform;
+pub mod json_value;
+#[cfg(feature = "wasm")]
+pub mod json_wasm;
+pub mod json_writer;
+pub mod json2cbor;
+pub mod json2csv;
+pub mod json2html;
+pub mod json2sql;
 pub mod json2xml;
+pub mod json2yaml;
+pub mod ndjson;
+pub mod output;
+pub mod parallel;
+pub mod token_tape;
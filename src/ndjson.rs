@@ -0,0 +1,177 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io::{BufRead, BufReader, Read};
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::{ConsumeError, LexerOptions};
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+
+/// What to do with a malformed record.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum RecordErrorPolicy {
+    /// Drop the tokens of the record and go on. The tokens of a record are kept until the record
+    /// is known to be valid.
+    #[default]
+    Skip,
+    /// Send the error to the consumer and stop.
+    Abort,
+    /// Send the error to the consumer and go on with the next record.
+    Emit,
+}
+
+/// A consumer of the tokens of a NDJSON input. Each record is a document, from `BeginFile` to
+/// `EndFile`, and `record` is the index of the record (the blank lines are not records).
+pub trait NDJSONConsumer {
+    fn consume(&mut self, record: usize, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError>;
+}
+
+/// A malformed record. The line of the error is the line of the input.
+#[derive(Debug, PartialEq)]
+pub struct RecordError {
+    pub record: usize,
+    pub error: JSONParseError,
+}
+
+/// The outcome of the parsing of a NDJSON input.
+#[derive(Debug, PartialEq, Default)]
+pub struct NDJSONSummary {
+    /// The number of records, valid or not.
+    pub records: usize,
+    pub errors: Vec<RecordError>,
+}
+
+/// Forwards the tokens of a record, with the record number and the line of the input.
+struct RecordConsumer<'a, C: NDJSONConsumer> {
+    consumer: &'a mut C,
+    record: usize,
+    line: usize,
+    /// Keep the tokens until the end of the record (`Skip` policy).
+    buffer: Option<Vec<ParserToken>>,
+    error: Option<JSONParseError>,
+}
+
+impl<'a, C: NDJSONConsumer> JSONParseConsumer for RecordConsumer<'a, C> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        match token {
+            Ok(t) => match self.buffer.as_mut() {
+                Some(buffer) => {
+                    buffer.push(t);
+                    Ok(())
+                }
+                None => self.consumer.consume(self.record, Ok(t)),
+            },
+            Err(e) => {
+                let error = JSONParseError { kind: e.kind, msg: e.msg, line: self.line, column: e.column };
+                let err = ConsumeError::from(error.clone());
+                if self.buffer.is_none() {
+                    self.consumer.consume(self.record, Err(error.clone()))?;
+                }
+                self.error = Some(error);
+                Err(err)
+            }
+        }
+    }
+}
+
+/// A parser for NDJSON (newline delimited JSON): each non blank line is a record, parsed as a
+/// document. Unlike `JSONLinesParser`, the records are not wrapped in an array and the consumer
+/// knows the record of each token.
+pub struct NDJSONParser<R: Read> {
+    read: BufReader<R>,
+    options: LexerOptions,
+    error_policy: RecordErrorPolicy,
+}
+
+impl<R: Read> NDJSONParser<R> {
+    pub fn new(read: R, ignore_unicode_errs: bool) -> Self {
+        NDJSONParser::new_with_options(read, LexerOptions { ignore_unicode_errs, ..Default::default() })
+    }
+
+    pub fn new_with_options(read: R, options: LexerOptions) -> Self {
+        NDJSONParser {
+            read: BufReader::new(read),
+            options,
+            error_policy: RecordErrorPolicy::Skip,
+        }
+    }
+
+    pub fn error_policy(mut self, error_policy: RecordErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Parse the records. With the `Abort` policy, the first error is returned, after it was sent
+    /// to the consumer.
+    pub fn parse<C: NDJSONConsumer>(&mut self, consumer: &mut C) -> Result<NDJSONSummary, ConsumeError> {
+        let mut summary = NDJSONSummary::default();
+        let mut line = vec!();
+        let mut line_number = 0;
+        loop {
+            line.clear();
+            match self.read.read_until(b'\n', &mut line) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(e) => return Err(ConsumeError { msg: format!("Can't read line: {}", e), line: line_number, column: 0, io_error: Some(e) }),
+            }
+            if !line.iter().all(|b| b" \t\r\n".contains(b)) {
+                let mut record_consumer = RecordConsumer {
+                    consumer,
+                    record: summary.records,
+                    line: line_number,
+                    buffer: if self.error_policy == RecordErrorPolicy::Skip { Some(vec!()) } else { None },
+                    error: None,
+                };
+                let mut parser = JSONParser::new_with_options(ByteSource::new(&line[..]), self.options.clone());
+                let result = parser.parse(&mut record_consumer);
+                match (result, record_consumer.error, record_consumer.buffer) {
+                    (Err(e), None, _) => {
+                        // the consumer failed
+                        return Err(e);
+                    }
+                    (Err(e), Some(error), _) => {
+                        summary.errors.push(RecordError { record: summary.records, error });
+                        if self.error_policy == RecordErrorPolicy::Abort {
+                            return Err(e);
+                        }
+                    }
+                    (Ok(()), _, Some(buffer)) => {
+                        for token in buffer {
+                            consumer.consume(summary.records, Ok(token))?;
+                        }
+                    }
+                    (Ok(()), _, None) => {}
+                }
+                summary.records += 1;
+            }
+            line_number += 1;
+        }
+        Ok(summary)
+    }
+}
+
+/// Collects the tokens with their records.
+impl NDJSONConsumer for Vec<(usize, Result<ParserToken, JSONParseError>)> {
+    fn consume(&mut self, record: usize, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.push((record, token));
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::{fs, io};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::input::Compression;
+
+/// The compression of an output file, guessed from its extension: `.gz` or `.zst`.
+pub fn compression_from_extension(path: &str) -> Compression {
+    match Path::new(path).extension().and_then(|e| e.to_str()) {
+        Some("gz") => Compression::Gzip,
+        Some("zst") => Compression::Zstd,
+        _ => Compression::None,
+    }
+}
+
+/// Open the output of a binary: `-` is stdout, anything else is a file. The output is buffered
+/// and compressed with `compression` (`Compression::Auto`: see `compression_from_extension`).
+/// The gzip outputs require the `gzip` feature and the zstd outputs the `zstd` feature,
+/// otherwise this is an `Unsupported` error. The compressed stream is finished when the writer
+/// is dropped.
+pub fn open_output(path: &str, compression: Compression) -> io::Result<Box<dyn Write>> {
+    let compression = match compression {
+        Compression::Auto => compression_from_extension(path),
+        c => c,
+    };
+    // don't create the file if it can't be written
+    match compression {
+        Compression::Gzip if !cfg!(feature = "gzip") => return Err(missing_feature(compression)),
+        Compression::Zstd if !cfg!(feature = "zstd") => return Err(missing_feature(compression)),
+        _ => {}
+    }
+    let write: Box<dyn Write> = if path == "-" {
+        Box::new(io::stdout())
+    } else {
+        Box::new(fs::File::create(path)?)
+    };
+    Ok(Box::new(BufWriter::new(compress(write, compression)?)))
+}
+
+/// Replace the file at `path` with the output of `f`, e.g. to format a file in place. The output
+/// is written to a temporary file in the directory of `path`, which gets the permissions of the
+/// original file and is renamed over it only if `f` succeeds: on error, the temporary file is
+/// removed and the original file is left untouched.
+pub fn replace_file<T, E, F>(path: &Path, f: F) -> Result<T, E>
+    where E: From<io::Error>, F: FnOnce(&mut dyn Write) -> Result<T, E> {
+    let permissions = fs::metadata(path)?.permissions();
+    let (temp_path, file) = create_temp_file(path)?;
+    let result = (|| {
+        let mut write = BufWriter::new(file);
+        let t = f(&mut write)?;
+        let file = write.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()?;
+        fs::set_permissions(&temp_path, permissions)?;
+        fs::rename(&temp_path, path)?;
+        Ok(t)
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// A new file `.<name>.<pid>.<n>.tmp` next to `path`: the rename is atomic on the same file
+/// system.
+fn create_temp_file(path: &Path) -> io::Result<(PathBuf, fs::File)> {
+    let name = path.file_name().ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, format!("Not a file: {}", path.display())))?;
+    let dir = path.parent().filter(|d| !d.as_os_str().is_empty()).unwrap_or_else(|| Path::new("."));
+    let mut n = 0;
+    loop {
+        let temp_path = dir.join(format!(".{}.{}.{}.tmp", name.to_string_lossy(), std::process::id(), n));
+        match fs::OpenOptions::new().write(true).create_new(true).open(&temp_path) {
+            Ok(file) => return Ok((temp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => n += 1,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Wrap the output in a compressor, finished when it is dropped (see `open_output` for the
+/// features).
+pub fn compress<'a, W: Write + 'a>(write: W, compression: Compression) -> io::Result<Box<dyn Write + 'a>> {
+    match compression {
+        Compression::Auto | Compression::None => Ok(Box::new(write)),
+        Compression::Gzip => gzip_encoder(write),
+        Compression::Zstd => zstd_encoder(write),
+    }
+}
+
+fn missing_feature(compression: Compression) -> io::Error {
+    let msg = match compression {
+        Compression::Gzip => "Gzip outputs require the `gzip` feature",
+        _ => "Zstd outputs require the `zstd` feature",
+    };
+    io::Error::new(io::ErrorKind::Unsupported, msg)
+}
+
+#[cfg(feature = "gzip")]
+fn gzip_encoder<'a, W: Write + 'a>(write: W) -> io::Result<Box<dyn Write + 'a>> {
+    Ok(Box::new(flate2::write::GzEncoder::new(write, flate2::Compression::default())))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip_encoder<'a, W: Write + 'a>(_write: W) -> io::Result<Box<dyn Write + 'a>> {
+    Err(missing_feature(Compression::Gzip))
+}
+
+#[cfg(feature = "zstd")]
+fn zstd_encoder<'a, W: Write + 'a>(write: W) -> io::Result<Box<dyn Write + 'a>> {
+    Ok(Box::new(zstd::stream::write::Encoder::new(write, 0)?.auto_finish()))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd_encoder<'a, W: Write + 'a>(_write: W) -> io::Result<Box<dyn Write + 'a>> {
+    Err(missing_feature(Compression::Zstd))
+}
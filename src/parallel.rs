@@ -0,0 +1,306 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Parse a NDJSON input or a large top-level array on several threads. The input is split into
+//! chunks at record (or element) boundaries, each chunk is parsed by its own parser and the
+//! tokens are merged in the order of the input, so that the consumer sees the same tokens as with
+//! a single parser.
+//!
+//! The chunks are parsed by waves of `threads` chunks: the tokens of a wave are kept in memory
+//! until they are sent to the consumer.
+
+use std::io::Read;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+use crate::byte_source::ByteSource;
+use crate::json_lexer::{ConsumeError, Dialect, LexerOptions};
+use crate::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use crate::ndjson::{NDJSONConsumer, NDJSONParser, NDJSONSummary, RecordError, RecordErrorPolicy};
+
+const DEFAULT_CHUNK_SIZE: usize = 1 << 20;
+
+/// Split a NDJSON input into chunks of at least `chunk_size` bytes (except the last one) that
+/// end with a newline.
+pub fn split_lines(input: &[u8], chunk_size: usize) -> Vec<&[u8]> {
+    let mut chunks = vec!();
+    let mut start = 0;
+    while start < input.len() {
+        let min_end = (start + chunk_size.max(1)).min(input.len());
+        let end = match input[min_end - 1..].iter().position(|&b| b == b'\n') {
+            Some(i) => min_end + i,
+            None => input.len(),
+        };
+        chunks.push(&input[start..end]);
+        start = end;
+    }
+    chunks
+}
+
+/// Split the content of a top-level array into groups of elements of at least `chunk_size`
+/// bytes. The groups are separated by a comma that is not part of any group. Return `None` if
+/// the input is not a well delimited array: the caller must use a single parser.
+fn split_array(input: &[u8], chunk_size: usize) -> Option<Vec<&[u8]>> {
+    let start = input.iter().position(|b| !b" \t\r\n".contains(b))?;
+    if input[start] != b'[' {
+        return None;
+    }
+    let mut chunks = vec!();
+    let mut chunk_start = start + 1;
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut escape = false;
+    for (i, &byte) in input.iter().enumerate().skip(start + 1) {
+        if in_string {
+            if escape {
+                escape = false;
+            } else if byte == b'\\' {
+                escape = true;
+            } else if byte == b'"' {
+                in_string = false;
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => depth += 1,
+            b']' if depth == 0 => {
+                if !input[i + 1..].iter().all(|b| b" \t\r\n".contains(b)) {
+                    return None;
+                }
+                chunks.push(&input[chunk_start..i]);
+                // an empty group is a trailing comma or an error
+                if chunks.len() > 1 && chunks.iter().any(|c| c.iter().all(|b| b" \t\r\n".contains(b))) {
+                    return None;
+                }
+                return Some(chunks);
+            }
+            b'}' if depth == 0 => return None,
+            b']' | b'}' => depth -= 1,
+            b',' if depth == 0 && i - chunk_start >= chunk_size => {
+                chunks.push(&input[chunk_start..i]);
+                chunk_start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Keeps the tokens of a chunk of a top-level array, without the tokens of the array, until the
+/// first error.
+struct ArrayChunkConsumer {
+    tokens: Vec<Result<ParserToken, JSONParseError>>,
+    depth: usize,
+}
+
+impl JSONParseConsumer for ArrayChunkConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        match token {
+            Ok(ParserToken::BeginFile) | Ok(ParserToken::EndFile) => {}
+            Ok(t @ ParserToken::BeginArray) | Ok(t @ ParserToken::BeginObject) => {
+                self.depth += 1;
+                if self.depth > 1 {
+                    self.tokens.push(Ok(t));
+                }
+            }
+            Ok(t @ ParserToken::EndArray) | Ok(t @ ParserToken::EndObject) => {
+                self.depth -= 1;
+                if self.depth > 0 {
+                    self.tokens.push(Ok(t));
+                }
+            }
+            Ok(t) => self.tokens.push(Ok(t)),
+            Err(e) => {
+                let err = ConsumeError::from(e.clone());
+                self.tokens.push(Err(e));
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Stops at the first error.
+struct FirstErrorConsumer {
+    error: Option<JSONParseError>,
+}
+
+impl JSONParseConsumer for FirstErrorConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        match token {
+            Ok(_) => Ok(()),
+            Err(e) => {
+                let err = ConsumeError::from(e.clone());
+                self.error = Some(e);
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Run `f` on every item, with the index of the item, on `threads` threads (at least one), and
+/// return the results in the order of the items. A free thread takes the next item.
+pub(crate) fn map_parallel<I, T, F>(items: &[I], threads: usize, f: F) -> Vec<T>
+    where I: Sync, T: Send, F: Fn(usize, &I) -> T + Sync {
+    let next = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<T>>> = Mutex::new((0..items.len()).map(|_| None).collect());
+    thread::scope(|scope| {
+        for _ in 0..threads.max(1).min(items.len()) {
+            scope.spawn(|| loop {
+                let i = next.fetch_add(1, Ordering::Relaxed);
+                if i >= items.len() {
+                    break;
+                }
+                let result = f(i, &items[i]);
+                results.lock().unwrap()[i] = Some(result);
+            });
+        }
+    });
+    results.into_inner().unwrap().into_iter().map(|r| r.expect("every item is mapped")).collect()
+}
+
+/// A parser that splits its input across threads. See the module documentation.
+pub struct ParallelParser {
+    options: LexerOptions,
+    threads: usize,
+    chunk_size: usize,
+    error_policy: RecordErrorPolicy,
+}
+
+impl ParallelParser {
+    pub fn new(options: LexerOptions) -> Self {
+        ParallelParser {
+            options,
+            threads: thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            error_policy: RecordErrorPolicy::Skip,
+        }
+    }
+
+    /// The number of threads, by default the available parallelism.
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    /// The minimal size of a chunk, 1 MiB by default.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// See `NDJSONParser::error_policy`.
+    pub fn error_policy(mut self, error_policy: RecordErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    /// Run `f` on every chunk, with the index of the chunk, and return the results in the order
+    /// of the chunks.
+    pub fn map_chunks<'a, T, F>(&self, chunks: &[&'a [u8]], f: F) -> Vec<T>
+        where T: Send, F: Fn(usize, &'a [u8]) -> T + Sync {
+        map_parallel(chunks, self.threads, |i, chunk| f(i, chunk))
+    }
+
+    /// Parse a NDJSON input: the consumer receives the same tokens, in the same order, as from
+    /// `NDJSONParser::parse`.
+    pub fn parse_ndjson<C: NDJSONConsumer>(&self, input: &[u8], consumer: &mut C) -> Result<NDJSONSummary, ConsumeError> {
+        let chunks = split_lines(input, self.chunk_size);
+        let mut summary = NDJSONSummary::default();
+        let mut line = 0;
+        for wave in chunks.chunks(self.threads) {
+            let results = self.map_chunks(wave, |_, chunk| {
+                let mut tokens: Vec<(usize, Result<ParserToken, JSONParseError>)> = vec!();
+                let result = NDJSONParser::new_with_options(chunk, self.options.clone())
+                    .error_policy(self.error_policy)
+                    .parse(&mut tokens);
+                let lines = chunk.iter().filter(|&&b| b == b'\n').count();
+                (tokens, result, lines)
+            });
+            for (tokens, result, lines) in results {
+                let shift = |e: JSONParseError| JSONParseError { line: e.line + line, ..e };
+                for (record, token) in tokens {
+                    consumer.consume(summary.records + record, token.map_err(shift))?;
+                }
+                match result {
+                    Ok(chunk_summary) => {
+                        let records = summary.records;
+                        summary.errors.extend(chunk_summary.errors.into_iter().map(|e| RecordError {
+                            record: records + e.record,
+                            error: shift(e.error),
+                        }));
+                        summary.records += chunk_summary.records;
+                    }
+                    Err(e) => return Err(ConsumeError { line: e.line + line, ..e }),
+                }
+                line += lines;
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Parse a document: if the root value is an array, its elements are split across the
+    /// threads. The consumer receives the same tokens as from `JSONParser::parse`, but the
+    /// parsing stops at the first error, which is also returned. If the input can't be split (not
+    /// an array, JSON5, comments), a single parser is used.
+    pub fn parse_array<C: JSONParseConsumer>(&self, input: &[u8], consumer: &mut C) -> Result<(), ConsumeError> {
+        let chunks = if self.options.dialect == Dialect::Json && !self.options.allow_comments && !self.options.single_quotes {
+            split_array(input, self.chunk_size)
+        } else {
+            None
+        };
+        let chunks = match chunks {
+            Some(chunks) if chunks.len() > 1 => chunks,
+            _ => return JSONParser::new_with_options(ByteSource::new(input), self.options.clone()).parse(consumer),
+        };
+        consumer.consume(Ok(ParserToken::BeginFile))?;
+        consumer.consume(Ok(ParserToken::BeginArray))?;
+        for wave in chunks.chunks(self.threads) {
+            let results = self.map_chunks(wave, |_, chunk| {
+                let mut chunk_consumer = ArrayChunkConsumer { tokens: vec!(), depth: 0 };
+                let source = (&b"["[..]).chain(chunk).chain(&b"]"[..]);
+                let _ = JSONParser::new_with_options(ByteSource::new(source), self.options.clone()).parse(&mut chunk_consumer);
+                chunk_consumer.tokens
+            });
+            for tokens in results {
+                for token in tokens {
+                    match token {
+                        Ok(t) => consumer.consume(Ok(t))?,
+                        Err(e) => {
+                            // the position of the error in the chunk is not the position in the
+                            // input: find the error with a single parser
+                            let mut first_error = FirstErrorConsumer { error: None };
+                            let _ = JSONParser::new_with_options(ByteSource::new(input), self.options.clone()).parse(&mut first_error);
+                            let e = first_error.error.unwrap_or(e);
+                            let err = ConsumeError::from(e.clone());
+                            consumer.consume(Err(e))?;
+                            return Err(err);
+                        }
+                    }
+                }
+            }
+        }
+        consumer.consume(Ok(ParserToken::EndArray))?;
+        consumer.consume(Ok(ParserToken::EndFile))
+    }
+}
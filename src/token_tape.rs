@@ -0,0 +1,285 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! A compact binary serialization of the `ParserToken` stream.
+//!
+//! The tape starts with the magic bytes `RJT1`. Every token is a tag byte, followed, for the
+//! keys and the values, by a LEB128 varint length and the UTF-8 bytes. The first occurrence of a
+//! key is stored with its bytes and gets the next id; the following occurrences only store the id.
+//! An error is stored with its message and its position, but not its kind: the replayed errors
+//! have the kind `ParseErrorKind::Other`.
+
+use std::collections::HashMap;
+use std::io;
+use std::io::{Read, Write};
+
+use crate::json_lexer::ConsumeError;
+use crate::json_parser::{JSONParseConsumer, JSONParseError, ParseErrorKind, ParserToken};
+
+const MAGIC: &[u8; 4] = b"RJT1";
+
+const TAG_BEGIN_FILE: u8 = 0;
+const TAG_END_FILE: u8 = 1;
+const TAG_BEGIN_OBJECT: u8 = 2;
+const TAG_END_OBJECT: u8 = 3;
+const TAG_BEGIN_ARRAY: u8 = 4;
+const TAG_END_ARRAY: u8 = 5;
+const TAG_NEW_KEY: u8 = 6;
+const TAG_KEY: u8 = 7;
+const TAG_FALSE: u8 = 8;
+const TAG_TRUE: u8 = 9;
+const TAG_NULL: u8 = 10;
+const TAG_STRING: u8 = 11;
+const TAG_INT: u8 = 12;
+const TAG_FLOAT: u8 = 13;
+const TAG_ERROR: u8 = 14;
+const TAG_BYTES: u8 = 15;
+const TAG_STRING_START: u8 = 16;
+const TAG_STRING_CHUNK: u8 = 17;
+const TAG_STRING_END: u8 = 18;
+
+fn read_error(msg: String) -> ConsumeError {
+    ConsumeError {
+        msg,
+        line: 0,
+        column: 0,
+        io_error: None,
+    }
+}
+
+/// A consumer that records the tokens on a tape.
+pub struct TokenTapeWriter<W: Write> {
+    destination: W,
+    key_ids: HashMap<String, usize>,
+    started: bool,
+}
+
+impl<W: Write> TokenTapeWriter<W> {
+    pub fn new(destination: W) -> Self {
+        TokenTapeWriter {
+            destination,
+            key_ids: HashMap::new(),
+            started: false,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.destination
+    }
+
+    fn write_varint(&mut self, mut n: usize) -> io::Result<()> {
+        let mut bytes = [0u8; 10];
+        let mut i = 0;
+        loop {
+            let b = (n & 0x7f) as u8;
+            n >>= 7;
+            if n == 0 {
+                bytes[i] = b;
+                i += 1;
+                break;
+            }
+            bytes[i] = b | 0x80;
+            i += 1;
+        }
+        self.destination.write_all(&bytes[..i])
+    }
+
+    fn write_str(&mut self, tag: u8, s: &str) -> io::Result<()> {
+        self.write_bytes(tag, s.as_bytes())
+    }
+
+    fn write_bytes(&mut self, tag: u8, b: &[u8]) -> io::Result<()> {
+        self.destination.write_all(&[tag])?;
+        self.write_varint(b.len())?;
+        self.destination.write_all(b)
+    }
+
+    fn write_token(&mut self, token: Result<ParserToken, JSONParseError>) -> io::Result<()> {
+        if !self.started {
+            self.destination.write_all(MAGIC)?;
+            self.started = true;
+        }
+        match token {
+            Ok(ParserToken::BeginFile) => self.destination.write_all(&[TAG_BEGIN_FILE]),
+            Ok(ParserToken::EndFile) => self.destination.write_all(&[TAG_END_FILE]),
+            Ok(ParserToken::BeginObject) => self.destination.write_all(&[TAG_BEGIN_OBJECT]),
+            Ok(ParserToken::EndObject) => self.destination.write_all(&[TAG_END_OBJECT]),
+            Ok(ParserToken::BeginArray) => self.destination.write_all(&[TAG_BEGIN_ARRAY]),
+            Ok(ParserToken::EndArray) => self.destination.write_all(&[TAG_END_ARRAY]),
+            Ok(ParserToken::Key(k)) => {
+                match self.key_ids.get(&k) {
+                    Some(&id) => {
+                        self.destination.write_all(&[TAG_KEY])?;
+                        self.write_varint(id)
+                    }
+                    None => {
+                        self.write_str(TAG_NEW_KEY, &k)?;
+                        let id = self.key_ids.len();
+                        self.key_ids.insert(k, id);
+                        Ok(())
+                    }
+                }
+            }
+            Ok(ParserToken::BooleanValue(false)) => self.destination.write_all(&[TAG_FALSE]),
+            Ok(ParserToken::BooleanValue(true)) => self.destination.write_all(&[TAG_TRUE]),
+            Ok(ParserToken::NullValue) => self.destination.write_all(&[TAG_NULL]),
+            Ok(ParserToken::StringValue(s)) => self.write_str(TAG_STRING, &s),
+            Ok(ParserToken::BytesValue(b)) => self.write_bytes(TAG_BYTES, &b),
+            Ok(ParserToken::StringStart) => self.destination.write_all(&[TAG_STRING_START]),
+            Ok(ParserToken::StringChunk(b)) => self.write_bytes(TAG_STRING_CHUNK, &b),
+            Ok(ParserToken::StringEnd) => self.destination.write_all(&[TAG_STRING_END]),
+            Ok(ParserToken::IntValue(s)) => self.write_str(TAG_INT, &s),
+            Ok(ParserToken::FloatValue(s)) => self.write_str(TAG_FLOAT, &s),
+            Err(e) => {
+                self.write_str(TAG_ERROR, &e.msg)?;
+                self.write_varint(e.line)?;
+                self.write_varint(e.column)
+            }
+        }
+    }
+}
+
+impl<W: Write> JSONParseConsumer for TokenTapeWriter<W> {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let is_end = matches!(token, Ok(ParserToken::EndFile));
+        self.write_token(token).map_err(ConsumeError::from_io)?;
+        if is_end {
+            self.destination.flush().map_err(ConsumeError::from_io)?;
+        }
+        Ok(())
+    }
+}
+
+/// A reader that replays a tape into any consumer.
+pub struct TokenTapeReader<R: Read> {
+    source: R,
+    keys: Vec<String>,
+}
+
+impl<R: Read> TokenTapeReader<R> {
+    pub fn new(source: R) -> Self {
+        TokenTapeReader {
+            source,
+            keys: vec!(),
+        }
+    }
+
+    /// Feed the tokens of the tape to the consumer.
+    pub fn replay<C: JSONParseConsumer>(&mut self, consumer: &mut C) -> Result<(), ConsumeError> {
+        let mut magic = [0u8; 4];
+        self.read_exact(&mut magic)?;
+        if &magic != MAGIC {
+            return Err(read_error("Not a token tape".into()));
+        }
+        self.keys.clear();
+        loop {
+            let mut tag = [0u8; 1];
+            match self.source.read(&mut tag) {
+                Ok(0) => { return Ok(()); }
+                Ok(_) => {}
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => { continue; }
+                Err(e) => { return Err(read_error(format!("read error {:?}", e.kind()))); }
+            }
+            let token = match tag[0] {
+                TAG_BEGIN_FILE => Ok(ParserToken::BeginFile),
+                TAG_END_FILE => Ok(ParserToken::EndFile),
+                TAG_BEGIN_OBJECT => Ok(ParserToken::BeginObject),
+                TAG_END_OBJECT => Ok(ParserToken::EndObject),
+                TAG_BEGIN_ARRAY => Ok(ParserToken::BeginArray),
+                TAG_END_ARRAY => Ok(ParserToken::EndArray),
+                TAG_NEW_KEY => {
+                    let k = self.read_string()?;
+                    self.keys.push(k.clone());
+                    Ok(ParserToken::Key(k))
+                }
+                TAG_KEY => {
+                    let id = self.read_varint()?;
+                    match self.keys.get(id) {
+                        Some(k) => Ok(ParserToken::Key(k.clone())),
+                        None => { return Err(read_error(format!("Unknown key id `{}`", id))); }
+                    }
+                }
+                TAG_FALSE => Ok(ParserToken::BooleanValue(false)),
+                TAG_TRUE => Ok(ParserToken::BooleanValue(true)),
+                TAG_NULL => Ok(ParserToken::NullValue),
+                TAG_STRING => Ok(ParserToken::StringValue(self.read_string()?)),
+                TAG_BYTES => Ok(ParserToken::BytesValue(self.read_bytes()?)),
+                TAG_STRING_START => Ok(ParserToken::StringStart),
+                TAG_STRING_CHUNK => Ok(ParserToken::StringChunk(self.read_bytes()?)),
+                TAG_STRING_END => Ok(ParserToken::StringEnd),
+                TAG_INT => Ok(ParserToken::IntValue(self.read_string()?)),
+                TAG_FLOAT => Ok(ParserToken::FloatValue(self.read_string()?)),
+                TAG_ERROR => {
+                    let msg = self.read_string()?;
+                    let line = self.read_varint()?;
+                    let column = self.read_varint()?;
+                    // the kind is not stored in the tape
+                    Err(JSONParseError { kind: ParseErrorKind::Other, msg, line, column })
+                }
+                t => { return Err(read_error(format!("Unknown tag `{}`", t))); }
+            };
+            if let Err(ref e) = token {
+                let e = ConsumeError::from(e.clone());
+                consumer.consume(token)?;
+                return Err(e);
+            }
+            consumer.consume(token)?;
+        }
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), ConsumeError> {
+        self.source.read_exact(buf).map_err(|e| read_error(format!("read error {:?}", e.kind())))
+    }
+
+    fn read_varint(&mut self) -> Result<usize, ConsumeError> {
+        let mut n: usize = 0;
+        let mut shift = 0;
+        loop {
+            let mut byte = [0u8; 1];
+            self.read_exact(&mut byte)?;
+            if shift >= usize::BITS {
+                return Err(read_error("Varint overflow".into()));
+            }
+            n |= ((byte[0] & 0x7f) as usize) << shift;
+            if byte[0] & 0x80 == 0 {
+                return Ok(n);
+            }
+            shift += 7;
+        }
+    }
+
+    fn read_bytes(&mut self) -> Result<Vec<u8>, ConsumeError> {
+        let len = self.read_varint()?;
+        // don't trust the length of a corrupted tape to allocate the buffer
+        let mut bytes = vec!();
+        (&mut self.source).take(len as u64).read_to_end(&mut bytes)
+            .map_err(|e| read_error(format!("read error {:?}", e.kind())))?;
+        if bytes.len() < len {
+            return Err(read_error(format!("Truncated tape: expected {} bytes, got {}", len, bytes.len())));
+        }
+        Ok(bytes)
+    }
+
+    fn read_string(&mut self) -> Result<String, ConsumeError> {
+        let bytes = self.read_bytes()?;
+        String::from_utf8(bytes).map_err(|e| read_error(format!("Can't decode string `{}`", e)))
+    }
+}
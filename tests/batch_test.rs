@@ -0,0 +1,75 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use r_json_event_parser::batch::{BatchSummary, convert_files, find_files, glob_match, is_glob, sibling_path};
+
+#[test]
+fn test_glob_match() {
+    assert!(glob_match("data/*.json", "data/a.json"));
+    assert!(!glob_match("data/*.json", "data/sub/a.json"));
+    assert!(glob_match("data/**/*.json", "data/a.json"));
+    assert!(glob_match("data/**/*.json", "data/sub/deeper/a.json"));
+    assert!(glob_match("data/?.json", "data/a.json"));
+    assert!(!glob_match("data/?.json", "data/ab.json"));
+    assert!(!glob_match("data/*.json", "data/a.json.gz"));
+    assert!(is_glob("data/**/*.json"));
+    assert!(!is_glob("data/a.json"));
+}
+
+#[test]
+fn test_sibling_path() {
+    assert_eq!(PathBuf::from("data/a.xml"), sibling_path(Path::new("data/a.json"), "xml"));
+    assert_eq!(PathBuf::from("data/a.xml"), sibling_path(Path::new("data/a.json.gz"), "xml"));
+    assert_eq!(PathBuf::from("a.xml.zst"), sibling_path(Path::new("a.json.zst"), "xml.zst"));
+}
+
+#[test]
+fn test_find_files() {
+    let dir = std::env::temp_dir().join(format!("r_json_batch_test_{}", std::process::id()));
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    for name in ["a.json", "b.json.gz", "c.txt", "sub/d.json"] {
+        fs::write(dir.join(name), b"{}").unwrap();
+    }
+    let root = dir.to_string_lossy();
+    let found = |pattern: &str, recursive: bool| -> Vec<String> {
+        find_files(pattern, recursive).unwrap().iter()
+            .map(|p| p.strip_prefix(&dir).unwrap().to_string_lossy().into_owned()).collect()
+    };
+    assert_eq!(vec!("a.json", "b.json.gz"), found(&root, false));
+    assert_eq!(vec!("a.json", "b.json.gz", "sub/d.json"), found(&root, true));
+    assert_eq!(vec!("a.json"), found(&format!("{}/*.json", root), false));
+    assert_eq!(vec!("a.json", "sub/d.json"), found(&format!("{}/**/*.json", root), false));
+    assert_eq!(vec!("sub/d.json"), found(&format!("{}/*/*.json", root), false));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_convert_files() {
+    let files: Vec<PathBuf> = ["a.json", "b.json", "c.json"].iter().map(PathBuf::from).collect();
+    let summary = convert_files(&files, 2, |file| {
+        if file == Path::new("b.json") { Err("bad".into()) } else { Ok(()) }
+    });
+    assert_eq!(BatchSummary { converted: 2, failures: vec!((PathBuf::from("b.json"), "bad".into())) }, summary);
+    assert_eq!(BatchSummary::default(), convert_files(&[], 4, |_| Ok(())));
+}
@@ -0,0 +1,69 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#[cfg(feature = "digest")]
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_canonical::canonical_number;
+#[cfg(feature = "digest")]
+use r_json_event_parser::json_canonical::CanonicalDigestConsumer;
+#[cfg(feature = "digest")]
+use r_json_event_parser::json_parser::JSONParser;
+
+#[cfg(feature = "digest")]
+fn digest(text: &str) -> Option<String> {
+    let mut consumer = CanonicalDigestConsumer::new();
+    let mut parser = JSONParser::new(ByteSource::new(text.as_bytes()), false);
+    parser.parse(&mut consumer).unwrap();
+    consumer.hex_digest()
+}
+
+#[test]
+fn test_canonical_number() {
+    assert_eq!(Some("1e+30".into()), canonical_number("1E30"));
+    assert_eq!(Some("4.5".into()), canonical_number("4.50"));
+    assert_eq!(Some("0.002".into()), canonical_number("2e-3"));
+    assert_eq!(Some("0.000001".into()), canonical_number("0.000001"));
+    assert_eq!(Some("1e-7".into()), canonical_number("1e-7"));
+    assert_eq!(Some("333333333.3333333".into()), canonical_number("333333333.33333329"));
+    assert_eq!(Some("0".into()), canonical_number("-0"));
+    assert_eq!(Some("-1.5e-7".into()), canonical_number("-0.00000015"));
+    assert_eq!(Some("100000000000000000000".into()), canonical_number("1e20"));
+    assert_eq!(Some("1e+21".into()), canonical_number("1e21"));
+    assert_eq!(None, canonical_number("1e400"));
+}
+
+#[test]
+#[cfg(feature = "digest")]
+fn test_digest() {
+    // sha256 of `{"a":[true,null,"x\n\u001fé"],"b":1e+21,"€":{},"😀":0.000001}`
+    let expected = Some("cdddaeeaf697551132945753a7a2e2aa64162d1d78ea8d5c8a29b4090f91b12d".into());
+    assert_eq!(expected, digest("{\"\\ud83d\\ude00\": 1E-6, \"b\": 1000000000000000000000, \"€\": { },
+        \"a\": [true, null, \"x\\n\\u001F\\u00e9\"]}"));
+}
+
+#[test]
+#[cfg(feature = "digest")]
+fn test_digest_error() {
+    let mut consumer = CanonicalDigestConsumer::new();
+    let mut parser = JSONParser::new(ByteSource::new(r#"[1e400]"#.as_bytes()), false);
+    assert!(parser.parse(&mut consumer).is_err());
+    assert_eq!(None, consumer.hex_digest());
+}
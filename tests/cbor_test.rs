@@ -0,0 +1,118 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::VecDeque;
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::cbor_lexer::CBORLexer;
+use r_json_event_parser::json2cbor::{json_to_cbor, JSON2CBORConsumer};
+use r_json_event_parser::json_lexer::LexerOptions;
+use r_json_event_parser::json_parser::{JSONParseError, JSONParser, ParserToken};
+use r_json_event_parser::json_parser::ParserToken::*;
+use r_json_event_parser::json_writer::JSONWriteConsumer;
+
+fn cbor_to_json(cbor: &[u8]) -> Result<String, String> {
+    let mut consumer = JSONWriteConsumer::new(vec!());
+    CBORLexer::new(ByteSource::new(cbor)).parse(&mut consumer)
+        .map(|_| String::from_utf8(consumer.into_inner()).unwrap())
+        .map_err(|e| format!("{} at {}", e.msg, e.column))
+}
+
+fn cbor_tokens(cbor: &[u8]) -> Vec<Result<ParserToken, JSONParseError>> {
+    let mut tokens = VecDeque::new();
+    let _ = CBORLexer::new(ByteSource::new(cbor)).parse(&mut tokens);
+    tokens.into_iter().collect()
+}
+
+#[test]
+fn json_to_cbor_items() {
+    assert_eq!(Ok(vec!(0x9f, 0x00, 0x17, 0x18, 0x18, 0x19, 0x01, 0x00, 0x20, 0x38, 0x63, 0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff)),
+               json_to_cbor("[0, 23, 24, 256, -1, -100, 18446744073709551615]"));
+    assert_eq!(Ok(vec!(0x9f, 0xc2, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0xc3, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0xff)),
+               json_to_cbor("[18446744073709551616, -18446744073709551617]"));
+    assert_eq!(Ok(vec!(0x9f, 0xfa, 0x3f, 0xc0, 0, 0, 0xfb, 0x3f, 0xb9, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a, 0xf4, 0xf6, 0xff)),
+               json_to_cbor("[1.5, 0.1, false, null]"));
+    assert_eq!(Ok(vec!(0xbf, 0x60, 0x62, 0xc3, 0xa9, 0x60, 0xbf, 0xff, 0xff)), json_to_cbor(r#"{"": "é", "": {}}"#));
+    assert_eq!(Err((0, 6)), json_to_cbor("[1, }").map_err(|e| (e.line, e.column)));
+}
+
+#[test]
+fn json_to_cbor_chunks() {
+    let options = LexerOptions { string_chunk_size: Some(4), ..Default::default() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new(r#""abcé€😀""#.as_bytes()), options);
+    let mut consumer = JSON2CBORConsumer::new(vec!());
+    parser.parse(&mut consumer).unwrap();
+    let cbor = consumer.into_inner();
+    assert_eq!(0x7f, cbor[0]);
+    assert_eq!(Some(&0xff), cbor.last());
+    assert_eq!(Ok(r#""abcé€😀""#.into()), cbor_to_json(&cbor));
+}
+
+#[test]
+fn cbor_to_json_items() {
+    assert_eq!(Ok(r#"[1,-1,-1000,18446744073709551615,-18446744073709551616]"#.into()),
+               cbor_to_json(&[0x85, 0x01, 0x20, 0x39, 0x03, 0xe7, 0x1b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+                   0x3b, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff]));
+    assert_eq!(Ok(r#"[18446744073709551616,-18446744073709551617,0]"#.into()),
+               cbor_to_json(&[0x83, 0xc2, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0xc3, 0x49, 0x01, 0, 0, 0, 0, 0, 0, 0, 0, 0xc2, 0x40]));
+    assert_eq!(Ok(r#"[1.0,-2.5,0.1,null,null,true,false,null]"#.into()),
+               cbor_to_json(&[0x88, 0xf9, 0x3c, 0x00, 0xf9, 0xc1, 0x00, 0xfb, 0x3f, 0xb9, 0x99, 0x99, 0x99, 0x99, 0x99, 0x9a,
+                   0xf9, 0x7e, 0x00, 0xfa, 0x7f, 0x80, 0, 0, 0xf5, 0xf4, 0xf7]));
+    assert_eq!(Ok(r#"{"a":{},"1":[],"-2":"x","t":"2013-03-21"}"#.into()),
+               cbor_to_json(&[0xbf, 0x61, b'a', 0xa0, 0x01, 0x80, 0x21, 0x61, b'x', 0x61, b't',
+                   0xc0, 0x6a, b'2', b'0', b'1', b'3', b'-', b'0', b'3', b'-', b'2', b'1', 0xff]));
+    assert_eq!(Ok(r#"[[],[[1]]]"#.into()), cbor_to_json(&[0x82, 0x9f, 0xff, 0x9f, 0x81, 0x01, 0xff]));
+    assert_eq!(Ok("".into()), cbor_to_json(&[]));
+}
+
+#[test]
+fn cbor_strings() {
+    assert_eq!(vec!(Ok(BeginFile), Ok(BeginObject), Ok(Key("ab".into())), Ok(StringStart),
+                    Ok(StringChunk(b"x".to_vec())), Ok(StringChunk(b"yz".to_vec())), Ok(StringEnd),
+                    Ok(Key("c".into())), Ok(BytesValue(vec!(1, 2, 3))), Ok(EndObject), Ok(EndFile)),
+               cbor_tokens(&[0xa2, 0x7f, 0x61, b'a', 0x61, b'b', 0xff, 0x7f, 0x61, b'x', 0x62, b'y', b'z', 0xff,
+                   0x61, b'c', 0x5f, 0x41, 0x01, 0x42, 0x02, 0x03, 0xff]));
+}
+
+#[test]
+fn cbor_round_trip() {
+    let json = r#"{"a":[1,-2,3.25,"é\n",null,true,{"b":[]}],"c":123456789012345678901234567890,"d":-1e300}"#;
+    let cbor = json_to_cbor(json).unwrap();
+    assert_eq!(Ok(json.into()), cbor_to_json(&cbor));
+}
+
+#[test]
+fn cbor_errors() {
+    assert_eq!(Err("Unexpected end of the input at 3".into()), cbor_to_json(&[0x82, 0x01, 0x81]));
+    assert_eq!(Err("Unexpected break at 2".into()), cbor_to_json(&[0xbf, 0x01, 0xff]));
+    assert_eq!(Err("Unexpected break at 0".into()), cbor_to_json(&[0xff]));
+    assert_eq!(Err("A key must be a text string or an integer at 1".into()), cbor_to_json(&[0xa1, 0x80, 0x01]));
+    assert_eq!(Err("Invalid initial byte 0x1f at 1".into()), cbor_to_json(&[0x81, 0x1f]));
+    assert_eq!(Err("Invalid initial byte 0x1c at 0".into()), cbor_to_json(&[0x1c]));
+    assert_eq!(Err("Invalid UTF-8 in a text string at 0".into()), cbor_to_json(&[0x61, 0xff]));
+    assert_eq!(Err("A bignum must be a byte string at 0".into()), cbor_to_json(&[0xc2, 0x01]));
+    assert_eq!(Err("Unsupported simple value 16 at 0".into()), cbor_to_json(&[0xf0]));
+    assert_eq!(Err("Data after the root item at 1".into()), cbor_to_json(&[0x01, 0x02]));
+    let mut tokens = VecDeque::new();
+    CBORLexer::new(ByteSource::new(&[0x01, 0x02][..])).multi_document(true).parse(&mut tokens).unwrap();
+    assert_eq!(vec!(Ok(BeginFile), Ok(IntValue("1".into())), Ok(EndFile), Ok(BeginFile), Ok(IntValue("2".into())), Ok(EndFile)),
+               tokens.into_iter().collect::<Vec<_>>());
+}
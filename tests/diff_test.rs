@@ -0,0 +1,113 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_diff::{diff, DiffEntry, DiffKind, HTMLDiffConsumer, HTMLDiffLayout};
+use r_json_event_parser::json_value::JsonValue;
+
+fn diff_lines(left: &str, right: &str) -> Vec<String> {
+    let mut entries: Vec<DiffEntry> = vec!();
+    diff(ByteSource::new(left.as_bytes()), ByteSource::new(right.as_bytes()), &mut entries).unwrap();
+    entries.iter().map(|e| e.to_string()).collect()
+}
+
+#[test]
+fn test_equal() {
+    assert!(diff_lines(r#"{"a": [1, {"b": null}], "c": 1e2}"#, r#"{"a":[1,{"b":null}],"c":100.0}"#).is_empty());
+}
+
+#[test]
+fn test_same_order() {
+    assert_eq!(vec!(
+        "~ /a: 1 -> 2",
+        "~ /b/1/c: \"x\" -> true",
+        "- /b/2: 3",
+        "~ /d: [1] -> {\"e\":1}",
+    ), diff_lines(r#"{"a": 1, "b": [0, {"c": "x"}, 3], "d": [1]}"#, r#"{"a": 2, "b": [0, {"c": true}], "d": {"e": 1}}"#));
+}
+
+#[test]
+fn test_added_elements() {
+    assert_eq!(vec!("+ /1: [2]", "+ /2: 3"), diff_lines("[1]", "[1, [2], 3]"));
+}
+
+#[test]
+fn test_other_order() {
+    assert_eq!(vec!(
+        "~ /b/x: 1 -> 2",
+        "- /c: null",
+        "+ /d: {\"z\":[]}",
+    ), diff_lines(r#"{"a": 0, "b": {"x": 1}, "c": null}"#, r#"{"a": 0, "d": {"z": []}, "b": {"x": 2}}"#));
+}
+
+#[test]
+fn test_root() {
+    let mut entries: Vec<DiffEntry> = vec!();
+    diff(ByteSource::new("1".as_bytes()), ByteSource::new("\"1\"".as_bytes()), &mut entries).unwrap();
+    assert_eq!(vec!(DiffEntry {
+        kind: DiffKind::Changed,
+        path: "".into(),
+        old: Some(JsonValue::Int("1".into())),
+        new: Some(JsonValue::String("1".into())),
+    }), entries);
+    assert_eq!("~ (root): 1 -> \"1\"", entries[0].to_string());
+}
+
+#[test]
+fn test_error() {
+    let mut entries: Vec<DiffEntry> = vec!();
+    let e = diff(ByteSource::new("[1, 2]".as_bytes()), ByteSource::new("[1, }".as_bytes()), &mut entries).unwrap_err();
+    assert!(e.msg.starts_with("right document: "));
+}
+
+fn diff_html(left: &str, right: &str, layout: HTMLDiffLayout) -> String {
+    let mut consumer = HTMLDiffConsumer::new(vec!(), layout).title("a.json & b.json");
+    diff(ByteSource::new(left.as_bytes()), ByteSource::new(right.as_bytes()), &mut consumer).unwrap();
+    assert_eq!(2, consumer.count());
+    String::from_utf8(consumer.finish().unwrap()).unwrap()
+}
+
+#[test]
+fn test_html_inline() {
+    let html = diff_html(r#"{"a": 1, "b": [true]}"#, r#"{"a": "<2>", "b": [true, null]}"#, HTMLDiffLayout::Inline);
+    assert!(html.starts_with("<!DOCTYPE html>\n"));
+    assert!(html.contains("<title>a.json &amp; b.json</title>"));
+    assert!(html.contains("<tr><th>Path</th><th>Value</th></tr>\n\
+<tr class=\"changed\"><td>/a</td><td><del>1</del> <ins>\"&lt;2&gt;\"</ins></td></tr>\n\
+<tr class=\"added\"><td>/b/1</td><td><ins>null</ins></td></tr>\n\
+</table>\n<p>2 differences</p>\n</body>\n</html>\n"));
+}
+
+#[test]
+fn test_html_side_by_side() {
+    let html = diff_html(r#"{"a": 1, "b": [true]}"#, r#"{"a": 2}"#, HTMLDiffLayout::SideBySide);
+    assert!(html.contains("<tr><th>Path</th><th>Old</th><th>New</th></tr>\n\
+<tr class=\"changed\"><td>/a</td><td><del>1</del></td><td><ins>2</ins></td></tr>\n\
+<tr class=\"removed\"><td>/b</td><td><del>[true]</del></td><td></td></tr>\n"));
+}
+
+#[test]
+fn test_html_equal() {
+    let mut consumer = HTMLDiffConsumer::new(vec!(), HTMLDiffLayout::Inline);
+    diff(ByteSource::new("[1]".as_bytes()), ByteSource::new("[1]".as_bytes()), &mut consumer).unwrap();
+    let html = String::from_utf8(consumer.finish().unwrap()).unwrap();
+    assert!(html.contains("<table class=\"json-diff\">\n<tr><th>Path</th><th>Value</th></tr>\n</table>\n<p>No difference</p>\n"));
+}
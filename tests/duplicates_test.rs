@@ -0,0 +1,134 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_duplicates::{DuplicateKeyConsumer, DuplicateKeyResolution};
+use r_json_event_parser::json_lexer::{ConsumeError, LexerOptions};
+use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParseErrorKind, ParserToken};
+use r_json_event_parser::json_parser::ParserToken::{BeginArray, BeginFile, BeginObject, EndArray, EndFile, EndObject, IntValue, Key, StringValue};
+
+struct AssertEqualsConsumer {
+    tokens: Vec<Result<ParserToken, JSONParseError>>,
+}
+
+impl AssertEqualsConsumer {
+    fn new() -> Self {
+        AssertEqualsConsumer { tokens: vec!() }
+    }
+}
+
+impl JSONParseConsumer for AssertEqualsConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.tokens.push(token);
+        Ok(())
+    }
+}
+
+const JSON: &str = r#"{"a": 1, "b": {"c": 2, "c": [3]}, "a": {"d": 4}}"#;
+
+fn test_resolution(resolution: DuplicateKeyResolution, expected_tokens: Vec<Result<ParserToken, JSONParseError>>) {
+    let mut consumer = AssertEqualsConsumer::new();
+    let mut parser = JSONParser::new(ByteSource::new(JSON.as_bytes()), false);
+    let _ = parser.parse(&mut DuplicateKeyConsumer::new(&mut consumer, resolution));
+    assert_eq!(expected_tokens, consumer.tokens);
+}
+
+#[test]
+fn test_first_wins() {
+    test_resolution(DuplicateKeyResolution::FirstWins, vec!(
+        Ok(BeginFile),
+        Ok(BeginObject),
+        Ok(Key("a".into())),
+        Ok(IntValue("1".into())),
+        Ok(Key("b".into())),
+        Ok(BeginObject),
+        Ok(Key("c".into())),
+        Ok(IntValue("2".into())),
+        Ok(EndObject),
+        Ok(EndObject),
+        Ok(EndFile),
+    ));
+}
+
+#[test]
+fn test_last_wins() {
+    test_resolution(DuplicateKeyResolution::LastWins, vec!(
+        Ok(BeginFile),
+        Ok(BeginObject),
+        Ok(Key("a".into())),
+        Ok(BeginObject),
+        Ok(Key("d".into())),
+        Ok(IntValue("4".into())),
+        Ok(EndObject),
+        Ok(Key("b".into())),
+        Ok(BeginObject),
+        Ok(Key("c".into())),
+        Ok(BeginArray),
+        Ok(IntValue("3".into())),
+        Ok(EndArray),
+        Ok(EndObject),
+        Ok(EndObject),
+        Ok(EndFile),
+    ));
+}
+
+#[test]
+fn test_error() {
+    test_resolution(DuplicateKeyResolution::Error, vec!(
+        Ok(BeginFile),
+        Ok(BeginObject),
+        Ok(Key("a".into())),
+        Ok(IntValue("1".into())),
+        Ok(Key("b".into())),
+        Ok(BeginObject),
+        Ok(Key("c".into())),
+        Ok(IntValue("2".into())),
+        Err(JSONParseError { kind: ParseErrorKind::DuplicateKey("c".into()), msg: "Duplicate key `c`".into(), line: 0, column: 28 }),
+    ));
+}
+
+#[test]
+fn test_error_position() {
+    let mut consumer = AssertEqualsConsumer::new();
+    let mut parser = JSONParser::new(ByteSource::new("{\"a\": 1,\n\"a\": 2}".as_bytes()), false);
+    let result = parser.parse(&mut DuplicateKeyConsumer::new(&mut consumer, DuplicateKeyResolution::Error));
+    let error = result.unwrap_err();
+    assert_eq!((1, 13), (error.line, error.column));
+    assert_eq!(Some(&Err(JSONParseError { kind: ParseErrorKind::DuplicateKey("a".into()), msg: "Duplicate key `a`".into(), line: 1, column: 13 })), consumer.tokens.last());
+}
+
+#[test]
+fn test_first_wins_chunked_value() {
+    let mut consumer = AssertEqualsConsumer::new();
+    let options = LexerOptions { string_chunk_size: Some(4), ..Default::default() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new(r#"{"a": "x", "a": "abcdefghij", "b": "y"}"#.as_bytes()), options);
+    parser.parse(&mut DuplicateKeyConsumer::new(&mut consumer, DuplicateKeyResolution::FirstWins)).unwrap();
+    assert_eq!(vec!(
+        Ok(BeginFile),
+        Ok(BeginObject),
+        Ok(Key("a".into())),
+        Ok(StringValue("x".into())),
+        Ok(Key("b".into())),
+        Ok(StringValue("y".into())),
+        Ok(EndObject),
+        Ok(EndFile),
+    ), consumer.tokens);
+}
@@ -0,0 +1,109 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#![cfg(feature = "ffi")]
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+use std::slice;
+
+use r_json_event_parser::ffi::{jep_parser_byte_offset, jep_parser_error_column, jep_parser_error_kind, jep_parser_error_line, jep_parser_error_message, jep_parser_feed, jep_parser_finish, jep_parser_free, jep_parser_new, jep_parser_set_callback, JepErrorKind, JepTokenType};
+
+extern "C" fn collect(user_data: *mut c_void, token_type: JepTokenType, data: *const c_char, len: usize) -> c_int {
+    let tokens = unsafe { &mut *(user_data as *mut Vec<(JepTokenType, String)>) };
+    let text = if data.is_null() {
+        String::new()
+    } else {
+        String::from_utf8_lossy(unsafe { slice::from_raw_parts(data as *const u8, len) }).into_owned()
+    };
+    tokens.push((token_type, text));
+    0
+}
+
+extern "C" fn stop_at_key(_user_data: *mut c_void, token_type: JepTokenType, _data: *const c_char, _len: usize) -> c_int {
+    (token_type == JepTokenType::Key) as c_int
+}
+
+fn parse_chunks(chunks: &[&[u8]], tokens: &mut Vec<(JepTokenType, String)>) -> (c_int, JepErrorKind, Option<String>, usize, usize) {
+    unsafe {
+        let parser = jep_parser_new(0);
+        jep_parser_set_callback(parser, collect, tokens as *mut Vec<(JepTokenType, String)> as *mut c_void);
+        let mut ret = 0;
+        for chunk in chunks {
+            ret = jep_parser_feed(parser, chunk.as_ptr(), chunk.len());
+            if ret != 0 {
+                break;
+            }
+        }
+        if ret == 0 {
+            ret = jep_parser_finish(parser);
+        }
+        let msg = jep_parser_error_message(parser);
+        let msg = if msg.is_null() { None } else { Some(CStr::from_ptr(msg).to_string_lossy().into_owned()) };
+        let result = (ret, jep_parser_error_kind(parser), msg, jep_parser_error_line(parser), jep_parser_error_column(parser));
+        jep_parser_free(parser);
+        result
+    }
+}
+
+#[test]
+fn test_ffi_tokens() {
+    let mut tokens = vec!();
+    let result = parse_chunks(&[br#"{"a": [tr"#, b"ue, null, 1.5, \"\xc3", b"\xa9x\"]}"], &mut tokens);
+    assert_eq!((0, JepErrorKind::None, None, 0, 0), result);
+    assert_eq!(vec!(
+        (JepTokenType::BeginFile, "".into()), (JepTokenType::BeginObject, "".into()),
+        (JepTokenType::Key, "a".into()), (JepTokenType::BeginArray, "".into()),
+        (JepTokenType::Boolean, "true".into()), (JepTokenType::Null, "null".into()),
+        (JepTokenType::Float, "1.5".into()), (JepTokenType::String, "éx".into()),
+        (JepTokenType::EndArray, "".into()), (JepTokenType::EndObject, "".into()),
+        (JepTokenType::EndFile, "".into()),
+    ), tokens);
+}
+
+#[test]
+fn test_ffi_parse_error() {
+    let mut tokens = vec!();
+    let (ret, kind, msg, _, _) = parse_chunks(&[b"[1, }"], &mut tokens);
+    assert_eq!(-1, ret);
+    assert_eq!(JepErrorKind::Parse, kind);
+    assert!(msg.is_some());
+    assert_eq!(3, tokens.len());
+}
+
+#[test]
+fn test_ffi_stop_and_no_callback() {
+    unsafe {
+        let parser = jep_parser_new(1);
+        assert_eq!(-1, jep_parser_feed(parser, b"1".as_ptr(), 1));
+        assert_eq!(JepErrorKind::NoCallback, jep_parser_error_kind(parser));
+        jep_parser_free(parser);
+
+        let parser = jep_parser_new(1);
+        jep_parser_set_callback(parser, stop_at_key, std::ptr::null_mut());
+        let json = br#"{"a": 1}"#;
+        assert_eq!(-1, jep_parser_feed(parser, json.as_ptr(), json.len()));
+        assert_eq!(JepErrorKind::Stopped, jep_parser_error_kind(parser));
+        assert_eq!(-1, jep_parser_finish(parser));
+        assert!(jep_parser_byte_offset(parser) <= json.len());
+        jep_parser_free(parser);
+    }
+}
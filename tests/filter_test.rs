@@ -0,0 +1,63 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_filter::{PointerFilterConsumer, SkipValuesConsumer};
+use r_json_event_parser::json_parser::JSONParser;
+use r_json_event_parser::json_pointer::{JSONPointer, PathElement};
+use r_json_event_parser::json_writer::JSONWriteConsumer;
+
+fn filter(json: &str, pointers: &[&str]) -> String {
+    let pointers = pointers.iter().map(|p| JSONPointer::parse(p).unwrap()).collect();
+    let mut writer = JSONWriteConsumer::new(vec!());
+    {
+        let mut consumer = PointerFilterConsumer::new(&mut writer, pointers);
+        let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+        parser.parse(&mut consumer).unwrap();
+    }
+    String::from_utf8(writer.into_inner()).unwrap()
+}
+
+#[test]
+fn test_wildcard() {
+    let json = r#"{"count": 3, "results": [{"id": 1, "name": "a"}, {"name": "b"}, {"id": {"x": "y"}}]}"#;
+    // the writer separates the values of the stream with nothing
+    assert_eq!(r#"1{"x":"y"}"#, filter(json, &["/results/*/id"]));
+}
+
+#[test]
+fn test_several_pointers() {
+    let json = r#"{"a": [1, 2, 3], "b": {"c": "d"}, "e": null}"#;
+    assert_eq!(r#"2{"c":"d"}"#, filter(json, &["/a/1", "/b"]));
+    assert_eq!(json.replace(' ', ""), filter(json, &[""]));
+    assert_eq!("", filter(json, &["/f"]));
+}
+
+#[test]
+fn test_skip_values() {
+    let mut consumer = JSONWriteConsumer::new(vec!());
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"id": 1, "body": "a\"b", "tags": ["x", 2], "n": {"body": 3}}"#.as_bytes()), false);
+    let mut skip = SkipValuesConsumer::new(&mut consumer, |path: &[PathElement]| {
+        path.len() == 1 && path[0] != PathElement::Key("id".into())
+    });
+    parser.parse(&mut skip).unwrap();
+    assert_eq!(r#"{"id":1,"body":"","tags":["x",2],"n":{"body":3}}"#, String::from_utf8(consumer.into_inner()).unwrap());
+}
@@ -0,0 +1,102 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_flatten::{escape_flat_key, FlatPairsConsumer, FlattenConsumer, parse_flat_key, UnflattenConsumer};
+use r_json_event_parser::json_parser::JSONParser;
+use r_json_event_parser::json_pointer::PathElement;
+use r_json_event_parser::json_value::JsonValue;
+use r_json_event_parser::json_writer::JSONWriteConsumer;
+
+fn flatten(json: &str, separator: &str) -> String {
+    let mut writer = JSONWriteConsumer::new(vec!());
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+    parser.parse(&mut FlattenConsumer::new(&mut writer).separator(separator)).unwrap();
+    String::from_utf8(writer.into_inner()).unwrap()
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(r"a\.b\[0]\\", escape_flat_key(r"a.b[0]\", "."));
+    assert_eq!(r"a.b\::c", escape_flat_key("a.b::c", "::"));
+}
+
+#[test]
+fn test_flatten() {
+    assert_eq!(r#"{"a.b[0]":1,"a.b[1].c":"x","a.d":{},"e":[],"f":null}"#,
+               flatten(r#"{"a": {"b": [1, {"c": "x"}], "d": {}}, "e": [], "f": null}"#, "."));
+    assert_eq!(r#"{"[0]":true,"[1][0]":2,"[2]/x\\/y":3}"#, flatten(r#"[true, [2], {"x/y": 3}]"#, "/"));
+    assert_eq!("{}", flatten("{}", "."));
+    assert_eq!("{}", flatten("[]", "."));
+    assert_eq!("42", flatten("42", "."));
+}
+
+#[test]
+fn test_flat_pairs() {
+    let mut pairs = vec!();
+    let mut callback = FlatPairsConsumer::new(|key: &str, value| pairs.push((key.to_string(), value)));
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a": [1, {"b": "s"}], "c": {}}"#.as_bytes()), false);
+    parser.parse(&mut FlattenConsumer::new(&mut callback)).unwrap();
+    assert_eq!(vec!(
+        ("a[0]".to_string(), JsonValue::Int("1".into())),
+        ("a[1].b".to_string(), JsonValue::String("s".into())),
+        ("c".to_string(), JsonValue::Object(vec!())),
+    ), pairs);
+}
+
+fn unflatten(json: &str, separator: &str) -> String {
+    let mut writer = JSONWriteConsumer::new(vec!());
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+    parser.parse(&mut UnflattenConsumer::new(&mut writer).separator(separator)).unwrap();
+    String::from_utf8(writer.into_inner()).unwrap()
+}
+
+#[test]
+fn test_parse_flat_key() {
+    use PathElement::{Index, Key};
+    assert_eq!(Some(vec!(Key("a".into()), Key("b".into()), Index(3), Key("c".into()))), parse_flat_key("a.b[3].c", "."));
+    assert_eq!(Some(vec!(Index(0), Index(1), Key("x.y[".into()))), parse_flat_key(r"[0][1].x\.y\[", "."));
+    assert_eq!(Some(vec!(Key("".into()))), parse_flat_key("", "."));
+    assert_eq!(Some(vec!(Key("a".into()), Key("b".into()))), parse_flat_key("a::b", "::"));
+    assert_eq!(None, parse_flat_key("a[x]", "."));
+    assert_eq!(None, parse_flat_key("a[1", "."));
+}
+
+#[test]
+fn test_unflatten() {
+    assert_eq!(r#"{"a":{"b":[1,{"c":"x"}],"d":{}},"e":[],"f":null}"#,
+               unflatten(r#"{"a.b[0]": 1, "a.b[1].c": "x", "a.d": {}, "e": [], "f": null}"#, "."));
+    assert_eq!(r#"[[1,2],{"a":true}]"#, unflatten(r#"{"[0][0]": 1, "[0][1]": 2, "[1].a": true}"#, "."));
+    assert_eq!(r#"{"a":{"x":1},"b":2,"a":{"y":3}}"#, unflatten(r#"{"a.x": 1, "b": 2, "a.y": 3}"#, "."));
+    assert_eq!("{}", unflatten("{}", "."));
+    assert_eq!(r#"[{"a.b":1}]"#, unflatten(r#"[{"a.b": 1}]"#, "."));
+}
+
+#[test]
+fn test_round_trip() {
+    let json = r#"{"a":{"b.c":[1,[2,{"d[0]":"x"}]],"e":{}},"f\\":[[]],"g":1.5}"#;
+    let mut writer = JSONWriteConsumer::new(vec!());
+    let mut unflatten_consumer = UnflattenConsumer::new(&mut writer).separator("/");
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+    parser.parse(&mut FlattenConsumer::new(&mut unflatten_consumer).separator("/")).unwrap();
+    assert_eq!(json.as_bytes().to_vec(), writer.into_inner());
+    assert_eq!(json, unflatten(&flatten(json, "."), "."));
+}
@@ -0,0 +1,61 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io::Write;
+
+use r_json_event_parser::fmt_write::FmtWriter;
+
+#[test]
+fn write_string() {
+    let mut s = String::new();
+    let mut destination = FmtWriter::new(&mut s);
+    let tag = "a";
+    write!(destination, "<{0}>é</{0}>", tag).unwrap();
+    assert_eq!("<a>é</a>", s);
+}
+
+#[test]
+fn write_split_char() {
+    let mut destination = FmtWriter::new(String::new());
+    let bytes = "é".as_bytes();
+    destination.write_all(&bytes[..1]).unwrap();
+    assert!(destination.flush().is_err());
+    destination.write_all(&bytes[1..]).unwrap();
+    destination.flush().unwrap();
+    assert_eq!("é", destination.into_inner());
+}
+
+#[test]
+fn write_invalid_utf8() {
+    let mut destination = FmtWriter::new(String::new());
+    assert!(destination.write_all(&[b'a', 0xff]).is_err());
+}
+
+#[test]
+fn write_invalid_utf8_keeps_pending() {
+    let mut destination = FmtWriter::new(String::new());
+    let bytes = "é".as_bytes();
+    destination.write_all(&bytes[..1]).unwrap();
+    assert!(destination.write(&[0xff]).is_err());
+    destination.write_all(&bytes[1..]).unwrap();
+    destination.flush().unwrap();
+    assert_eq!("é", destination.into_inner());
+}
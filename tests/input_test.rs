@@ -0,0 +1,203 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::io::Read;
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::input::{as_single_document, Compression, decompress, DecodingReader, detect_compression, detect_encoding, Encoding, InputFormat, is_url, open_input, sniff_format, sniff_input};
+use r_json_event_parser::json_value::JsonValue;
+use r_json_event_parser::json_value::ValueBuilderConsumer;
+use r_json_event_parser::json_parser::JSONParser;
+
+#[test]
+fn test_is_url() {
+    assert!(is_url("http://example.com/a.json"));
+    assert!(is_url("https://example.com/a.json"));
+    assert!(!is_url("tests/files/example1.json"));
+    assert!(!is_url("-"));
+}
+
+#[test]
+fn test_open_file() {
+    let mut s = String::new();
+    open_input("tests/files/example1.json").unwrap().read_to_string(&mut s).unwrap();
+    assert!(s.contains("glossary"));
+    assert!(open_input("tests/files/missing.json").is_err());
+}
+
+#[cfg(not(feature = "http"))]
+#[test]
+fn test_open_url_without_feature() {
+    let err = open_input("http://localhost/a.json").err().unwrap();
+    assert_eq!(std::io::ErrorKind::Unsupported, err.kind());
+}
+
+#[test]
+fn test_sniff_format() {
+    assert_eq!(InputFormat::Json, sniff_format(b"  {\"a\": [1, \"}\\\"\"]}\n  "));
+    assert_eq!(InputFormat::Json, sniff_format(b"{\"a\": "));
+    assert_eq!(InputFormat::Json, sniff_format(b""));
+    assert_eq!(InputFormat::Json, sniff_format(b"{} {}"));
+    assert_eq!(InputFormat::JsonLines, sniff_format(b"{\"a\": 1}\n{\"a\": 2}\n"));
+    assert_eq!(InputFormat::JsonLines, sniff_format(b"1\r\n2"));
+    assert_eq!(InputFormat::JsonSeq, sniff_format(b"\x1e{\"a\": 1}\n\x1e{\"a\": 2}\n"));
+}
+
+#[test]
+fn test_documents_as_array() {
+    let read = |bytes: &[u8], format| {
+        let mut s = String::new();
+        as_single_document(bytes, format).read_to_string(&mut s).unwrap();
+        s
+    };
+    assert_eq!("[{\"a\": 1}\n,{\"a\": 2}\n\n]", read(b"{\"a\": 1}\n{\"a\": 2}\n\n", InputFormat::JsonLines));
+    assert_eq!("[ {\"a\": 1}\n ,[2]\n]", read(b"\x1e{\"a\": 1}\n\x1e[2]\n", InputFormat::JsonSeq));
+    assert_eq!("[]", read(b"", InputFormat::JsonLines));
+    assert_eq!("{}", read(b"{}", InputFormat::Json));
+}
+
+#[test]
+fn test_sniff_input() {
+    let (format, read) = sniff_input(&b"1\n2\n"[..]).unwrap();
+    assert_eq!(InputFormat::JsonLines, format);
+    let mut s = String::new();
+    as_single_document(read, format).read_to_string(&mut s).unwrap();
+    assert_eq!("[1\n,2\n]", s);
+}
+
+/// Returns one byte per read.
+struct ByteByByte<'a>(&'a [u8]);
+
+impl<'a> Read for ByteByByte<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(1).min(self.0.len());
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+fn encode(json: &str, encoding: Encoding, bom: bool) -> Vec<u8> {
+    let text = if bom { format!("\u{feff}{}", json) } else { json.to_string() };
+    match encoding {
+        Encoding::Utf8 => text.into_bytes(),
+        Encoding::Utf16Le => text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect(),
+        Encoding::Utf16Be => text.encode_utf16().flat_map(|u| u.to_be_bytes()).collect(),
+        Encoding::Utf32Le => text.chars().flat_map(|c| (c as u32).to_le_bytes()).collect(),
+        Encoding::Utf32Be => text.chars().flat_map(|c| (c as u32).to_be_bytes()).collect(),
+    }
+}
+
+fn decode<R: Read>(read: R) -> std::io::Result<String> {
+    let mut s = String::new();
+    DecodingReader::new(read).read_to_string(&mut s)?;
+    Ok(s)
+}
+
+#[test]
+fn test_detect_encoding() {
+    assert_eq!((Encoding::Utf8, 3), detect_encoding(b"\xEF\xBB\xBF{}"));
+    assert_eq!((Encoding::Utf16Le, 2), detect_encoding(b"\xFF\xFE{\x00"));
+    assert_eq!((Encoding::Utf32Le, 4), detect_encoding(b"\xFF\xFE\x00\x00"));
+    assert_eq!((Encoding::Utf16Be, 0), detect_encoding(b"\x001"));
+    assert_eq!((Encoding::Utf32Be, 0), detect_encoding(b"\x00\x00\x00["));
+    assert_eq!((Encoding::Utf8, 0), detect_encoding(b"1"));
+    assert_eq!((Encoding::Utf8, 0), detect_encoding(b""));
+}
+
+#[test]
+fn test_decoding_reader() {
+    let json = r#"{"a": ["é€😀", 1]}"#;
+    for encoding in [Encoding::Utf8, Encoding::Utf16Le, Encoding::Utf16Be, Encoding::Utf32Le, Encoding::Utf32Be] {
+        for bom in [false, true] {
+            let bytes = encode(json, encoding, bom);
+            assert_eq!(json, decode(bytes.as_slice()).unwrap());
+            assert_eq!(json, decode(ByteByByte(&bytes)).unwrap());
+            let mut reader = DecodingReader::new(bytes.as_slice());
+            let mut consumer = ValueBuilderConsumer::new();
+            JSONParser::new(ByteSource::new(&mut reader), false).parse(&mut consumer).unwrap();
+            assert_eq!(Some(JsonValue::parse(json).unwrap()), consumer.into_value());
+            assert_eq!(Some(encoding), reader.encoding());
+        }
+    }
+    assert_eq!("7", decode(encode("7", Encoding::Utf16Le, false).as_slice()).unwrap());
+}
+
+#[test]
+fn test_decoding_errors() {
+    let e = decode(b"[\x00 \x00\x00\xDC]\x00".as_slice()).unwrap_err();
+    assert_eq!(std::io::ErrorKind::InvalidData, e.kind());
+    assert_eq!("Invalid UTF-16 low surrogate `0xdc00`", e.to_string());
+    let e = decode(b"[\x00 \x00\x00".as_slice()).unwrap_err();
+    assert_eq!("Truncated Utf16Le input", e.to_string());
+    let e = decode(b"\x00\x00\x00[\x00\x11\x00\x00".as_slice()).unwrap_err();
+    assert_eq!("Invalid UTF-32 code point `0x110000`", e.to_string());
+}
+
+
+#[test]
+fn test_detect_compression() {
+    assert_eq!(Compression::Gzip, detect_compression(b"\x1f\x8b\x08\x00"));
+    assert_eq!(Compression::Zstd, detect_compression(b"\x28\xb5\x2f\xfd\x00"));
+    assert_eq!(Compression::None, detect_compression(b"\x28\xb5"));
+    assert_eq!(Compression::None, detect_compression(b"{}"));
+}
+
+#[test]
+fn test_decompress_plain() {
+    let mut s = String::new();
+    decompress(b"[1]".as_slice(), Compression::Auto).unwrap().read_to_string(&mut s).unwrap();
+    assert_eq!("[1]", s);
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn test_decompress_gzip_without_feature() {
+    let err = decompress(b"\x1f\x8b\x08\x00".as_slice(), Compression::Auto).err().unwrap();
+    assert_eq!(std::io::ErrorKind::Unsupported, err.kind());
+}
+
+#[cfg(feature = "gzip")]
+#[test]
+fn test_decompress_gzip() {
+    use std::io::Write;
+    let mut encoder = flate2::write::GzEncoder::new(vec!(), flate2::Compression::default());
+    encoder.write_all(br#"{"a": [1, 2]}"#).unwrap();
+    let bytes = encoder.finish().unwrap();
+    let mut s = String::new();
+    decompress(bytes.as_slice(), Compression::Auto).unwrap().read_to_string(&mut s).unwrap();
+    assert_eq!(r#"{"a": [1, 2]}"#, s);
+    let mut consumer = ValueBuilderConsumer::new();
+    JSONParser::new(ByteSource::new_gzip(bytes.as_slice()), false).parse(&mut consumer).unwrap();
+    assert_eq!(JsonValue::parse(r#"{"a": [1, 2]}"#).ok(), consumer.into_value());
+}
+
+#[cfg(feature = "zstd")]
+#[test]
+fn test_decompress_zstd() {
+    let bytes = zstd::encode_all(br#"{"a": [1, 2]}"#.as_slice(), 0).unwrap();
+    let mut s = String::new();
+    decompress(bytes.as_slice(), Compression::Auto).unwrap().read_to_string(&mut s).unwrap();
+    assert_eq!(r#"{"a": [1, 2]}"#, s);
+    let mut consumer = ValueBuilderConsumer::new();
+    JSONParser::new(ByteSource::new_zstd(bytes.as_slice()).unwrap(), false).parse(&mut consumer).unwrap();
+    assert_eq!(JsonValue::parse(r#"{"a": [1, 2]}"#).ok(), consumer.into_value());
+}
@@ -0,0 +1,65 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json2csv::{json_to_csv_string, Json2CsvOptions, JSON2CSVConsumer};
+use r_json_event_parser::json_lexer::LexerOptions;
+use r_json_event_parser::json_parser::JSONParser;
+
+#[test]
+fn convert_records() {
+    let json = r#"[{"id": 1, "name": "a \"b\", c", "tags": ["x", "y"], "geo": {"lat": 1.5, "lon": -2}},
+                   {"id": 2, "name": "é\nf", "ok": true, "none": null}]"#;
+    assert_eq!(Ok("id,name,tags[0],tags[1],geo.lat,geo.lon,ok,none
+1,\"a \"\"b\"\", c\",x,y,1.5,-2,,
+2,\"é\nf\",,,,,true,
+".into()), json_to_csv_string(json, &Json2CsvOptions::default()));
+}
+
+#[test]
+fn convert_inference() {
+    let json = r#"[{"a": 1}, {"a": 2, "b": 3}, {"c": 4}]"#;
+    let opts = Json2CsvOptions { infer_records: 2, ..Default::default() };
+    assert_eq!(Ok("a,b\n1,\n2,3\n,\n".into()), json_to_csv_string(json, &opts));
+    let opts = Json2CsvOptions { columns: Some(vec!("c".into(), "a".into())), delimiter: b'\t', ..Default::default() };
+    assert_eq!(Ok("c\ta\n\t1\n\t2\n4\t\n".into()), json_to_csv_string(json, &opts));
+}
+
+#[test]
+fn convert_other_documents() {
+    let opts = Json2CsvOptions { key_separator: "/".into(), ..Default::default() };
+    assert_eq!(Ok("a/b\n1\n".into()), json_to_csv_string(r#"{"a": {"b": 1}}"#, &opts));
+    assert_eq!(Ok("value,[0],[1]\n1,,\n,2,3\n,,\n".into()), json_to_csv_string(r#"[1, [2, 3], []]"#, &opts));
+    assert_eq!(Ok("".into()), json_to_csv_string("[]", &opts));
+    let opts = Json2CsvOptions { columns: Some(vec!("a".into())), ..Default::default() };
+    assert_eq!(Ok("a\n".into()), json_to_csv_string("[]", &opts));
+    let err = json_to_csv_string(r#"[{"a": 1}, {"a": }]"#, &opts).unwrap_err();
+    assert_eq!((0, 19), (err.line, err.column));
+}
+
+#[test]
+fn convert_chunked_strings() {
+    let json = r#"[{"a": "a long string", "b": {"c": "another long string"}}, "a long record"]"#;
+    let options = LexerOptions { string_chunk_size: Some(4), ..Default::default() };
+    let mut consumer = JSON2CSVConsumer::new(vec!());
+    JSONParser::new_with_options(ByteSource::new(json.as_bytes()), options).parse(&mut consumer).unwrap();
+    assert_eq!("a,b.c,value\na long string,another long string,\n,,a long record\n", String::from_utf8(consumer.into_inner()).unwrap());
+}
@@ -0,0 +1,67 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::json2html::{DEFAULT_STYLE, json_to_html_string, Json2HtmlOptions};
+
+#[test]
+fn convert_values() {
+    let json = r#"[true, null, -1.5e3, "a\"&\n", [], [{"<k>": "v"}]]"#;
+    assert_eq!(Ok(r#"<ul class="json-tree">
+  <li><details open><summary><span class="json-punct">[</span></summary>
+    <ul>
+      <li><span class="json-boolean">true</span></li>
+      <li><span class="json-null">null</span></li>
+      <li><span class="json-number">-1.5e3</span></li>
+      <li><span class="json-string">"a\"&amp;\n"</span></li>
+      <li><span class="json-punct">[]</span></li>
+      <li><details open><summary><span class="json-punct">[</span></summary>
+        <ul>
+          <li><details open><summary><span class="json-punct">{</span></summary>
+            <ul>
+              <li><span class="json-key">"&lt;k&gt;"</span>: <span class="json-string">"v"</span></li>
+            </ul>
+            <span class="json-punct">}</span></details></li>
+        </ul>
+        <span class="json-punct">]</span></details></li>
+    </ul>
+    <span class="json-punct">]</span></details></li>
+</ul>
+"#.into()), json_to_html_string(json, &Json2HtmlOptions::default()));
+    assert_eq!(Ok("<ul class=\"json-tree\">\n  <li><span class=\"json-string\">\"x\"</span></li>\n</ul>\n".into()),
+               json_to_html_string(r#""x""#, &Json2HtmlOptions::default()));
+    assert_eq!(Ok("<ul class=\"json-tree\">\n  <li><span class=\"json-punct\">{}</span></li>\n</ul>\n".into()),
+               json_to_html_string("{}", &Json2HtmlOptions::default()));
+}
+
+#[test]
+fn convert_options() {
+    let opts = Json2HtmlOptions { collapse_depth: Some(1), ..Default::default() };
+    let html = json_to_html_string(r#"{"a": {"b": [1]}}"#, &opts).unwrap();
+    assert_eq!(1, html.matches("<details open>").count());
+    assert_eq!(2, html.matches("<details>").count());
+    let opts = Json2HtmlOptions { standalone: true, title: Some("A & B".into()), ..Default::default() };
+    let html = json_to_html_string("1", &opts).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>A &amp; B</title>\n"));
+    assert!(html.contains(DEFAULT_STYLE));
+    assert!(html.ends_with("<ul class=\"json-tree\">\n  <li><span class=\"json-number\">1</span></li>\n</ul>\n</body>\n</html>\n"));
+    let err = json_to_html_string(r#"[1, }"#, &opts).unwrap_err();
+    assert_eq!((0, 6), (err.line, err.column));
+}
@@ -0,0 +1,54 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::json2sql::{json_to_sql_string, Json2SqlOptions};
+
+#[test]
+fn convert_batches() {
+    let json = r#"[{"id": 1, "name": "a'b", "ok": true}, {"id": 2.5, "none": null, "geo": {"lat": 1, "tags": ["x"]}},
+                   {"id": 3, "ok": false}]"#;
+    let opts = Json2SqlOptions { table: "t".into(), batch_size: 2, ..Default::default() };
+    assert_eq!(Ok(r#"INSERT INTO t ("id", "name", "ok", "none", "geo") VALUES
+(1, 'a''b', TRUE, NULL, NULL),
+(2.5, NULL, NULL, NULL, '{"lat":1,"tags":["x"]}');
+INSERT INTO t ("id", "ok") VALUES
+(3, FALSE);
+"#.into()), json_to_sql_string(json, &opts));
+    assert_eq!(Ok("INSERT INTO data (\"a\") VALUES\n(1);\n".into()), json_to_sql_string(r#"{"a": 1}"#, &Json2SqlOptions::default()));
+    assert_eq!(Ok("".into()), json_to_sql_string("[]", &Json2SqlOptions::default()));
+}
+
+#[test]
+fn convert_options() {
+    let json = r#"[{"a": 1, "b`c": 2, "d": 3}, {"a": 4}]"#;
+    let opts = Json2SqlOptions { identifier_quote: '`', columns: Some(vec!("b`c".into(), "a".into())), ..Default::default() };
+    assert_eq!(Ok("INSERT INTO data (`b``c`, `a`) VALUES\n(2, 1),\n(NULL, 4);\n".into()), json_to_sql_string(json, &opts));
+}
+
+#[test]
+fn convert_errors() {
+    let err = json_to_sql_string(r#"[{"a": 1}, 2]"#, &Json2SqlOptions::default()).unwrap_err();
+    assert_eq!(("A record must be an object".into(), 0, 14), (err.msg, err.line, err.column));
+    let err = json_to_sql_string(r#""a""#, &Json2SqlOptions::default()).unwrap_err();
+    assert_eq!(("A record must be an object".into(), 0, 3), (err.msg, err.line, err.column));
+    let err = json_to_sql_string("[[1]]", &Json2SqlOptions::default()).unwrap_err();
+    assert_eq!("A record must be an object", err.msg);
+}
@@ -23,8 +23,12 @@ use std::{fs, io};
 use std::io::{Write, ErrorKind};
 
 use r_json_event_parser::byte_source::ByteSource;
-use r_json_event_parser::json2xml::{JSON2XMLConsumer};
+use r_json_event_parser::fmt_write::FmtWriter;
+use r_json_event_parser::json2xml::{ControlChars, ConvertError, json_lines_to_xml_write, json_to_xml_string, json_to_xml_write, Json2XmlOptions, JSON2XMLConsumer, TextEscaping, XmlNamePolicy};
 use r_json_event_parser::json_parser::JSONParser;
+use r_json_event_parser::json_pointer::JSONPointer;
+use r_json_event_parser::json_redact::RedactPattern;
+use r_json_event_parser::json_transform::value_tokens;
 
 #[test]
 fn lex_example1() {
@@ -178,6 +182,336 @@ fn lex_example5() {
     assert_eq!(expected, destination.to_str());
 }
 
+#[test]
+fn lex_example1_fmt_write() {
+    let expected = fs::read_to_string("tests/files/example1.xml").unwrap();
+    let f = fs::File::open("tests/files/example1.json").expect("no file found");
+    let mut destination = String::new();
+    let mut consumer = JSON2XMLConsumer::new_formatted_and_typed(FmtWriter::new(&mut destination));
+    let mut parser = JSONParser::new(ByteSource::new(f), false);
+    let _ = parser.parse(&mut consumer);
+    assert_eq!(expected, destination);
+}
+
+#[test]
+fn lex_example1_no_format_no_type_fmt_write() {
+    let f = fs::File::open("tests/files/example1.json").expect("no file found");
+    let mut destination = String::new();
+    let mut consumer = JSON2XMLConsumer::new(FmtWriter::new(&mut destination));
+    let mut parser = JSONParser::new(ByteSource::new(f), false);
+    let _ = parser.parse(&mut consumer);
+    assert_eq!(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><glossary><title>example glossary</title><GlossDiv><title>S</title><GlossList><GlossEntry><ID>SGML</ID><SortAs>SGML</SortAs><GlossTerm>Standard Generalized Markup Language</GlossTerm><Acronym>SGML</Acronym><Abbrev>ISO 8879:1986</Abbrev><GlossDef><para>A meta-markup language, used to create markup languages such as DocBook.</para><GlossSeeAlso><li>GML</li><li>XML</li></GlossSeeAlso></GlossDef><GlossSee>markup</GlossSee></GlossEntry></GlossList></GlossDiv></glossary></root>"#, destination);
+}
+
+#[test]
+fn convert_string() {
+    let opts = Json2XmlOptions::default();
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><a>1</a><b><li>x</li><li>true</li></b></root>"#.into()), json_to_xml_string(r#"{"a": 1, "b": ["x", true]}"#, &opts));
+}
+
+#[test]
+fn convert_string_formatted_and_typed() {
+    let opts = Json2XmlOptions { formatted: true, typed: true, ..Default::default() };
+    let expected = fs::read_to_string("tests/files/example2.xml").unwrap();
+    let input = fs::read_to_string("tests/files/example2.json").unwrap();
+    assert_eq!(Ok(expected), json_to_xml_string(&input, &opts));
+}
+
+#[test]
+fn convert_string_error() {
+    let opts = Json2XmlOptions::default();
+    assert_eq!(Err(ConvertError { msg: "Unexpected token `Ok(EndFile)`: object opened at 0:1 not closed".into(), line: 0, column: 8, io_error: None }),
+               json_to_xml_string(r#"{"foo":1"#, &opts));
+    let err = json_to_xml_string(r#"{"foo":1"#, &opts).unwrap_err();
+    assert_eq!("Unexpected token `Ok(EndFile)`: object opened at 0:1 not closed at 0:8", err.to_string());
+    assert!(std::error::Error::source(&err).is_none());
+}
+
+#[test]
+fn convert_write() {
+    let opts = Json2XmlOptions { typed: true, ..Default::default() };
+    let mut destination: Vec<u8> = vec!();
+    json_to_xml_write(fs::File::open("tests/files/example1.json").unwrap(), &mut destination, &opts).unwrap();
+    assert_eq!(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><glossary><title type="string">example glossary</title><GlossDiv><title type="string">S</title><GlossList><GlossEntry><ID type="string">SGML</ID><SortAs type="string">SGML</SortAs><GlossTerm type="string">Standard Generalized Markup Language</GlossTerm><Acronym type="string">SGML</Acronym><Abbrev type="string">ISO 8879:1986</Abbrev><GlossDef><para type="string">A meta-markup language, used to create markup languages such as DocBook.</para><GlossSeeAlso><li type="string">GML</li><li type="string">XML</li></GlossSeeAlso></GlossDef><GlossSee type="string">markup</GlossSee></GlossEntry></GlossList></GlossDiv></glossary></root>"#, String::from_utf8(destination).unwrap());
+}
+
+#[test]
+fn convert_joined_scalar_arrays() {
+    let opts = Json2XmlOptions { formatted: true, scalar_array_delimiter: Some(" ".into()), ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root>
+    <GlossSeeAlso>GML XML</GlossSeeAlso>
+    <n>1 2.5 true null</n>
+    <e></e>
+    <esc><![CDATA[a<b c]]></esc>
+    <m>
+        <li>1</li>
+        <li>
+            <a>2</a>
+        </li>
+        <li>3</li>
+    </m>
+    <nested>
+        <li>1 2</li>
+        <li>3</li>
+    </nested>
+</root>
+"#.into()), json_to_xml_string(r#"{"GlossSeeAlso": ["GML", "XML"], "n": [1, 2.5, true, null], "e": [], "esc": ["a<b", "c"],
+    "m": [1, {"a": 2}, 3], "nested": [[1, 2], [3]]}"#, &opts));
+}
+
+#[test]
+fn convert_compact() {
+    let opts = Json2XmlOptions { formatted: true, typed: true, compact: true, ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root>
+    <a>
+        <b><c type="int">1</c><d type="string"/><e type="string"><![CDATA[<]]></e></b>
+        <f><li type="null">null</li><li type="boolean">true</li></f>
+        <g>
+            <li type="int">1</li>
+            <li><h type="int">2</h></li>
+        </g>
+        <i></i>
+    </a>
+</root>
+"#.into()), json_to_xml_string(r#"{"a": {"b": {"c": 1, "d": "", "e": "<"}, "f": [null, true], "g": [1, {"h": 2}], "i": {}}}"#, &opts));
+}
+
+#[test]
+fn convert_compact_and_joined() {
+    let opts = Json2XmlOptions { formatted: true, compact: true, scalar_array_delimiter: Some(",".into()), ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root>
+    <a>
+        <b>1</b>
+        <f>x,y</f>
+    </a>
+    <c><d>2</d></c>
+</root>
+"#.into()), json_to_xml_string(r#"{"a": {"b": 1, "f": ["x", "y"]}, "c": {"d": 2}}"#, &opts));
+}
+
+#[test]
+fn convert_json_lines() {
+    let opts = Json2XmlOptions::default();
+    let mut destination: Vec<u8> = vec!();
+    let summary = json_lines_to_xml_write("{\"a\": 1}\n{\"a\": }\n[true]\n".as_bytes(), &mut destination, &opts).unwrap();
+    assert_eq!(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><li><a>1</a></li><li><li>true</li></li></root>"#, String::from_utf8(destination).unwrap());
+    assert_eq!(vec!(1), summary.failed_lines());
+}
+
+#[test]
+fn convert_prolog() {
+    let mut destination = String::new();
+    let mut consumer = JSON2XMLConsumer::new_formatted(FmtWriter::new(&mut destination))
+        .comment("generated -- do not edit-")
+        .processing_instruction("xml-stylesheet", r#"type="text/xsl" href="style.xsl""#)
+        .doctype(r#"root SYSTEM "root.dtd""#);
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a": 1}"#.as_bytes()), false);
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(r#"<?xml version="1.0" encoding="utf-8"?>
+<!--generated - - do not edit- -->
+<?xml-stylesheet type="text/xsl" href="style.xsl"?>
+<!DOCTYPE root SYSTEM "root.dtd">
+<root>
+    <a>1</a>
+</root>
+"#, destination);
+}
+
+#[test]
+fn convert_prolog_options() {
+    let opts = Json2XmlOptions {
+        processing_instructions: vec!(("pi".into(), "".into())),
+        doctype: Some("root".into()),
+        ..Default::default()
+    };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<?pi?>
+<!DOCTYPE root>
+<root><a>1</a></root>"#.into()), json_to_xml_string(r#"{"a": 1}"#, &opts));
+}
+
+#[test]
+fn convert_root_options() {
+    let opts = Json2XmlOptions {
+        root: Some("payload".into()),
+        namespace: Some("http://example.com/ns".into()),
+        root_attributes: vec!(("version".into(), "1 \"<&>\"".into())),
+        ..Default::default()
+    };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<payload xmlns="http://example.com/ns" version="1 &quot;&lt;&amp;>&quot;"><a>1</a></payload>"#.into()), json_to_xml_string(r#"{"a": 1}"#, &opts));
+    let opts = Json2XmlOptions { root: Some("payload".into()), formatted: true, ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<payload>
+    <a>1</a>
+</payload>
+"#.into()), json_to_xml_string(r#"{"a": 1}"#, &opts));
+}
+
+#[test]
+fn convert_key_names() {
+    let json = r#"{"a:b": 1, "foo bar": [true], "1abc": {"": "x"}, "_x": null, "xml": 2}"#;
+    let opts = |key_names| Json2XmlOptions { key_names, ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><a_b>1</a_b><foo_bar><li>true</li></foo_bar><_1abc><_>x</_></_1abc><_x>null</_x><_xml>2</_xml></root>"#.into()),
+               json_to_xml_string(json, &opts(XmlNamePolicy::Mangle)));
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><a_x003A_b>1</a_x003A_b><foo_x0020_bar><li>true</li></foo_x0020_bar><_x0031_abc><_x_>x</_x_></_x0031_abc><_x005F_x>null</_x005F_x><_x0078_ml>2</_x0078_ml></root>"#.into()),
+               json_to_xml_string(json, &opts(XmlNamePolicy::HexEncode)));
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><entry key="a:b">1</entry><entry key="foo bar"><li>true</li></entry><entry key="1abc"><entry key="">x</entry></entry><entry key="_x">null</entry><entry key="xml">2</entry></root>"#.into()),
+               json_to_xml_string(json, &opts(XmlNamePolicy::Entry)));
+    let opts = Json2XmlOptions { key_names: XmlNamePolicy::Entry, typed: true, formatted: true, compact: true, ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root>
+    <entry key="a&lt;b"><entry key="c d" type="int">1</entry></entry>
+</root>
+"#.into()), json_to_xml_string(r#"{"a<b": {"c d": 1}}"#, &opts));
+}
+
+#[test]
+fn convert_attributes() {
+    let json = r#"{"@version": 2, "a": {"@id": "x&\"y", "@ok": true, "b": 1, "@late": 3, "@obj": {"c": null}}, "d": [{"@n": 1.5}]}"#;
+    let opts = Json2XmlOptions { attribute_prefix: Some("@".into()), ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root version="2"><a id="x&amp;&quot;y" ok="true"><b>1</b><@late>3</@late><@obj><c>null</c></@obj></a><d><li n="1.5"></li></d></root>"#.into()),
+               json_to_xml_string(json, &opts));
+    let opts = Json2XmlOptions { attribute_prefix: Some("@".into()), formatted: true, compact: true, true_text: Some("<yes>".into()), ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root version="2">
+    <a id="x&amp;&quot;y" ok="&lt;yes>" late="3"><b>1</b></a>
+    <d>
+        <li n="1.5"></li>
+    </d>
+</root>
+"#.into()), json_to_xml_string(r#"{"@version": 2, "a": {"@id": "x&\"y", "@ok": true, "b": 1, "@late": 3}, "d": [{"@n": 1.5}]}"#, &opts));
+}
+
+#[test]
+fn convert_attribute_keys() {
+    let mut destination = String::new();
+    let mut consumer = JSON2XMLConsumer::new(FmtWriter::new(&mut destination))
+        .attribute_keys(|key| key.strip_suffix("_attr").map(|name| name.to_uppercase()));
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a": {"id_attr": 7, "b": "c"}}"#.as_bytes()), false);
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><a ID="7"><b>c</b></a></root>"#, destination);
+}
+
+#[test]
+fn convert_text_escaping() {
+    let json = r#"{"a": "x < y & 'z' ]]>", "b": ["<", "é"], "c": true}"#;
+    let opts = Json2XmlOptions { text_escaping: TextEscaping::Entities, true_text: Some("<true>".into()), ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><a>x &lt; y &amp; &apos;z&apos; ]]&gt;</a><b><li>&lt;</li><li>é</li></b><c>&lt;true&gt;</c></root>"#.into()),
+               json_to_xml_string(json, &opts));
+    let opts = Json2XmlOptions { text_escaping: TextEscaping::Entities, ascii_only: true, scalar_array_delimiter: Some(" ".into()), ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><b>&lt; &#xE9;</b></root>"#.into()), json_to_xml_string(r#"{"b": ["<", "é"]}"#, &opts));
+}
+
+#[test]
+fn convert_control_chars() {
+    let json = r#"{"@c": "\n\u0002", "a": "x\u0001y\u0000\tz", "b": "<\u001f>"}"#;
+    let opts = |control_chars| Json2XmlOptions { control_chars, attribute_prefix: Some("@".into()), ..Default::default() };
+    assert_eq!(Ok("<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<root c=\"&#xA;\u{fffd}\"><a>x\u{fffd}y\u{fffd}\tz</a><b><![CDATA[<\u{fffd}>]]></b></root>".into()),
+               json_to_xml_string(json, &opts(ControlChars::Replace)));
+    assert_eq!(Ok("<?xml version=\"1.0\" encoding=\"utf-8\"?>
+<root c=\"&#xA;\"><a>xy\tz</a><b><![CDATA[<>]]></b></root>".into()),
+               json_to_xml_string(json, &opts(ControlChars::Strip)));
+    assert_eq!(Ok("<?xml version=\"1.1\" encoding=\"utf-8\"?>
+<root c=\"&#xA;&#x2;\"><a>x&#x1;y\u{fffd}\tz</a><b><![CDATA[<]]>&#x1F;<![CDATA[>]]></b></root>".into()),
+               json_to_xml_string(json, &opts(ControlChars::Reference)));
+    let err = json_to_xml_string(r#"{"a": "x", "b": "\u0001"}"#, &opts(ControlChars::Error)).unwrap_err();
+    assert_eq!(Some(io::ErrorKind::InvalidData), err.io_error.as_ref().map(|e| e.kind()));
+    assert_eq!(("Control char `0x01` in a text".into(), 0, 24), (err.msg, err.line, err.column));
+}
+
+#[test]
+fn convert_literals() {
+    let opts = Json2XmlOptions {
+        true_text: Some("1".into()),
+        false_text: Some("0".into()),
+        null_text: Some("".into()),
+        ..Default::default()
+    };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><a>1</a><b>0</b><c></c><d><li>1</li><li></li></d></root>"#.into()), json_to_xml_string(r#"{"a": true, "b": false, "c": null, "d": [true, null]}"#, &opts));
+    let opts = Json2XmlOptions { null_text: Some("".into()), scalar_array_delimiter: Some(",".into()), ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><d>true,,false</d></root>"#.into()), json_to_xml_string(r#"{"d": [true, null, false]}"#, &opts));
+}
+
+#[test]
+fn convert_ascii_only() {
+    let opts = Json2XmlOptions { ascii_only: true, true_text: Some("oui ✓".into()), ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><a>&#xE9;t&#xE9; &#x1F600;</a><b><![CDATA[<]]>&#xE9;<![CDATA[>]]></b><c>oui &#x2713;</c></root>"#.into()),
+               json_to_xml_string(r#"{"a": "été 😀", "b": "<é>", "c": true}"#, &opts));
+    let opts = Json2XmlOptions { ascii_only: true, typed: true, scalar_array_delimiter: Some(" ".into()), ..Default::default() };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><a type="list">&#xE9;<![CDATA[ &]]></a></root>"#.into()), json_to_xml_string(r#"{"a": ["é", "&"]}"#, &opts));
+}
+
+#[test]
+fn convert_substitutions() {
+    let opts = Json2XmlOptions {
+        substitutions: vec!((JSONPointer::parse("/a").unwrap(), value_tokens(r#"{"b": null}"#).unwrap())),
+        ..Default::default()
+    };
+    assert_eq!(Ok(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><a><b>null</b></a><c>2</c></root>"#.into()), json_to_xml_string(r#"{"a": [1], "c": 2}"#, &opts));
+}
+
+#[test]
+fn convert_redactions() {
+    let opts = Json2XmlOptions {
+        redactions: vec!(RedactPattern::parse("password").unwrap(), RedactPattern::parse("/*/users/*/ssn").unwrap()),
+        redaction_mask: Some("X".into()),
+        ..Default::default()
+    };
+    let mut destination: Vec<u8> = vec!();
+    json_lines_to_xml_write("{\"password\": \"p\"}\n{\"users\": [{\"ssn\": 1, \"name\": \"n\"}]}\n".as_bytes(), &mut destination, &opts).unwrap();
+    assert_eq!(r#"<?xml version="1.0" encoding="utf-8"?>
+<root><li><password>X</password></li><li><users><li><ssn>X</ssn><name>n</name></li></users></li></root>"#, String::from_utf8(destination).unwrap());
+}
+
+/// A destination that accepts `capacity` bytes.
+struct FullWrite {
+    capacity: usize,
+}
+
+impl io::Write for FullWrite {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.len() > self.capacity {
+            return Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"));
+        }
+        self.capacity -= buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn convert_write_error() {
+    let err = json_to_xml_write(r#"{"a": 1, "b": "a long string"}"#.as_bytes(), FullWrite { capacity: 60 }, &Json2XmlOptions::default()).unwrap_err();
+    assert_eq!(Some(io::ErrorKind::StorageFull), err.io_error.as_ref().map(|e| e.kind()));
+    assert_eq!("write error StorageFull", err.msg);
+    assert_eq!((0, 30), (err.line, err.column));
+    assert_eq!("disk full", std::error::Error::source(&err).unwrap().to_string());
+}
+
 struct BufWrite<'a> {
     index: usize,
     buf: &'a mut [u8]
@@ -0,0 +1,80 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::json2yaml::{json_to_yaml_string, Json2YamlOptions};
+
+#[test]
+fn convert_containers() {
+    let json = r#"{"a": 1, "b": [true, null, [1.5, -2], [], {}], "c": {"d": {"e": []}, "f": [{"g": 1, "h": [2]}]}, "i": {}}"#;
+    assert_eq!(Ok("a: 1
+b:
+  - true
+  - null
+  - - 1.5
+    - -2
+  - []
+  - {}
+c:
+  d:
+    e: []
+  f:
+    - g: 1
+      h:
+        - 2
+i: {}
+".into()), json_to_yaml_string(json, &Json2YamlOptions::default()));
+    assert_eq!(Ok("[]\n".into()), json_to_yaml_string("[]", &Json2YamlOptions::default()));
+    assert_eq!(Ok("- - - 1\n".into()), json_to_yaml_string("[[[1]]]", &Json2YamlOptions::default()));
+}
+
+#[test]
+fn convert_scalars() {
+    let json = r#"["text", "two words", "", "true", "No", "12", "1e3", ".inf", "-x", "a: b", "a #b", "x ", "line\nbreak", "tab\t\"q\"", "é\u0085", "a/b.c_d@e"]"#;
+    assert_eq!(Ok(r#"- text
+- two words
+- ""
+- "true"
+- "No"
+- "12"
+- "1e3"
+- ".inf"
+- "-x"
+- "a: b"
+- "a #b"
+- "x "
+- "line\nbreak"
+- "tab\t\"q\""
+- "é\u0085"
+- a/b.c_d@e
+"#.into()), json_to_yaml_string(json, &Json2YamlOptions::default()));
+    assert_eq!(Ok("\"null\": null\n\"\": 1\nk: v\n".into()), json_to_yaml_string(r#"{"null": null, "": 1, "k": "v"}"#, &Json2YamlOptions::default()));
+    assert_eq!(Ok("\"1\"\n".into()), json_to_yaml_string(r#""1""#, &Json2YamlOptions::default()));
+}
+
+#[test]
+fn convert_options() {
+    let opts = Json2YamlOptions { indent: 4, document_start: true };
+    assert_eq!(Ok("---\na:\n    b:\n        - 1\n        - c: 2\n          d: 3\n".into()),
+               json_to_yaml_string(r#"{"a": {"b": [1, {"c": 2, "d": 3}]}}"#, &opts));
+    assert_eq!(Ok("---\n1\n".into()), json_to_yaml_string("1", &opts));
+    let err = json_to_yaml_string(r#"{"a": ]"#, &opts).unwrap_err();
+    assert_eq!((0, 7), (err.line, err.column));
+}
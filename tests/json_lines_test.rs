@@ -0,0 +1,114 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::VecDeque;
+
+use r_json_event_parser::json_lexer::{ConsumeError, LexerToken};
+use r_json_event_parser::json_lines::JSONLinesParser;
+use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParseError, ParseErrorKind, ParserToken};
+use r_json_event_parser::json_parser::ParserToken::{BeginArray, BeginFile, BeginObject, EndArray, EndFile, EndObject, IntValue, Key};
+use r_json_event_parser::ndjson::RecordErrorPolicy;
+
+struct TokensConsumer {
+    tokens: Vec<ParserToken>,
+    errors: Vec<JSONParseError>,
+}
+
+impl JSONParseConsumer for TokensConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        match token {
+            Ok(t) => self.tokens.push(t),
+            Err(e) => self.errors.push(e),
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_json_lines() {
+    let input = "{\"a\": 1}\n\n{\"a\": \n[2]\r\n{\"a\" 3}\n";
+    let mut consumer = TokensConsumer { tokens: vec!(), errors: vec!() };
+    let summary = JSONLinesParser::new(input.as_bytes(), false).parse(&mut consumer).unwrap();
+    assert_eq!(vec!(
+        BeginFile, BeginArray,
+        BeginObject, Key("a".into()), IntValue("1".into()), EndObject,
+        BeginArray, IntValue("2".into()), EndArray,
+        EndArray, EndFile,
+    ), consumer.tokens);
+    assert_eq!(2, summary.documents);
+    assert_eq!(vec!(2, 4), summary.failed_lines());
+    assert_eq!(summary.errors, consumer.errors);
+}
+
+#[test]
+fn test_json_lines_errors() {
+    let input = "{\"a\": 1}\n{\"a\" 2}\n[3]\n";
+    let mut tokens: VecDeque<Result<ParserToken, JSONParseError>> = VecDeque::new();
+    let summary = JSONLinesParser::new(input.as_bytes(), false).parse(&mut tokens).unwrap();
+    let error = JSONParseError { kind: ParseErrorKind::UnexpectedToken(LexerToken::IntValue("2".into())), msg: "Unexpected token `Ok(IntValue(\"2\"))`".into(), line: 1, column: 7 };
+    assert_eq!(vec!(
+        Ok(BeginFile), Ok(BeginArray),
+        Ok(BeginObject), Ok(Key("a".into())), Ok(IntValue("1".into())), Ok(EndObject),
+        Err(error.clone()),
+        Ok(BeginArray), Ok(IntValue("3".into())), Ok(EndArray),
+        Ok(EndArray), Ok(EndFile),
+    ), tokens.into_iter().collect::<Vec<_>>());
+    assert_eq!(2, summary.documents);
+    assert_eq!(vec!(error), summary.errors);
+}
+
+#[test]
+fn test_json_lines_error_policy() {
+    let input = "[1]\n{\"a\" 2}\n[3]\n";
+    let mut consumer = TokensConsumer { tokens: vec!(), errors: vec!() };
+    let summary = JSONLinesParser::new(input.as_bytes(), false).error_policy(RecordErrorPolicy::Skip).parse(&mut consumer).unwrap();
+    assert!(consumer.errors.is_empty());
+    assert_eq!(vec!(1), summary.failed_lines());
+
+    let mut consumer = TokensConsumer { tokens: vec!(), errors: vec!() };
+    assert!(JSONLinesParser::new(input.as_bytes(), false).error_policy(RecordErrorPolicy::Abort).parse(&mut consumer).is_err());
+    assert_eq!(vec!(BeginFile, BeginArray, BeginArray, IntValue("1".into()), EndArray), consumer.tokens);
+    assert_eq!(vec!(1), consumer.errors.iter().map(|e| e.line).collect::<Vec<_>>());
+}
+
+/// Done after the first document.
+struct FirstConsumer {
+    tokens: Vec<ParserToken>,
+}
+
+impl JSONParseConsumer for FirstConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.tokens.push(token.unwrap());
+        Ok(())
+    }
+
+    fn is_done(&self) -> bool {
+        self.tokens.last() == Some(&EndArray)
+    }
+}
+
+#[test]
+fn test_json_lines_stop() {
+    let mut consumer = FirstConsumer { tokens: vec!() };
+    let summary = JSONLinesParser::new("[1]\n[2]\n".as_bytes(), false).parse(&mut consumer).unwrap();
+    assert_eq!(vec!(BeginFile, BeginArray, BeginArray, IntValue("1".into()), EndArray), consumer.tokens);
+    assert_eq!(0, summary.documents);
+}
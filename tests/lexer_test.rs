@@ -23,9 +23,10 @@ use std::fs;
 use std::io::Read;
 
 use r_json_event_parser::byte_source::ByteSource;
-use r_json_event_parser::json_lexer::{ConsumeError, JSONLexConsumer, JSONLexer, JSONLexError, LexerToken};
+use r_json_event_parser::json_lexer::{ConsumeError, Dialect, InvalidUtf8Policy, JSONLexConsumer, JSONLexer, JSONLexError, LexErrorKind, LexerLimits, LexerOptions, LexerToken, Span, SurrogatePolicy};
 use r_json_event_parser::json_lexer::LexerToken::{BeginArray, BeginFile, EndArray, EndFile, FloatValue, IntValue};
 
+#[allow(dead_code)]
 struct PrintConsumer;
 
 impl JSONLexConsumer for PrintConsumer {
@@ -1010,7 +1011,7 @@ fn test_wrong_number() {
               vec!(
                   Ok(BeginFile),
                   Ok(BeginArray),
-                  Err(JSONLexError { msg: "Missing decimals `1.`".into(), line: 0, column: 4 }),
+                  Err(JSONLexError { kind: LexErrorKind::MissingDecimals, msg: "Missing decimals `1.`".into(), line: 0, column: 4 }),
                   Ok(EndArray), Ok(EndFile),
               ),
     );
@@ -1018,7 +1019,7 @@ fn test_wrong_number() {
               vec!(
                   Ok(BeginFile),
                   Ok(BeginArray),
-                  Err(JSONLexError { msg: "Expected a digit `]`".into(), line: 0, column: 3 }),
+                  Err(JSONLexError { kind: LexErrorKind::ExpectedDigit(']'), msg: "Expected a digit `]`".into(), line: 0, column: 3 }),
                   Ok(EndArray), Ok(EndFile),
               ),
     );
@@ -1026,7 +1027,7 @@ fn test_wrong_number() {
               vec!(
                   Ok(BeginFile),
                   Ok(BeginArray),
-                  Err(JSONLexError { msg: "Missing exp `1.5e`".into(), line: 0, column: 6 }),
+                  Err(JSONLexError { kind: LexErrorKind::MissingExponent, msg: "Missing exp `1.5e`".into(), line: 0, column: 6 }),
                   Ok(EndArray), Ok(EndFile),
               ),
     );
@@ -1034,7 +1035,7 @@ fn test_wrong_number() {
               vec!(
                   Ok(BeginFile),
                   Ok(BeginArray),
-                  Err(JSONLexError { msg: "Missing exp `1e-`".into(), line: 0, column: 5 }),
+                  Err(JSONLexError { kind: LexErrorKind::MissingExponent, msg: "Missing exp `1e-`".into(), line: 0, column: 5 }),
                   Ok(EndArray), Ok(EndFile),
               ),
     );
@@ -1045,35 +1046,35 @@ fn test_lonely_incomplete_number() {
     test_read("-".as_bytes(),
               vec!(
                   Ok(BeginFile),
-                  Err(JSONLexError { msg: "Missing digits `-`".into(), line: 0, column: 1 }),
+                  Err(JSONLexError { kind: LexErrorKind::MissingDigits, msg: "Missing digits `-`".into(), line: 0, column: 1 }),
                   Ok(EndFile),
               ),
     );
     test_read("0.".as_bytes(),
               vec!(
                   Ok(BeginFile),
-                  Err(JSONLexError { msg: "Missing decimals `0.`".into(), line: 0, column: 2 }),
+                  Err(JSONLexError { kind: LexErrorKind::MissingDecimals, msg: "Missing decimals `0.`".into(), line: 0, column: 2 }),
                   Ok(EndFile),
               ),
     );
     test_read("1.5e".as_bytes(),
               vec!(
                   Ok(BeginFile),
-                  Err(JSONLexError { msg: "Missing exp `1.5e`".into(), line: 0, column: 4 }),
+                  Err(JSONLexError { kind: LexErrorKind::MissingExponent, msg: "Missing exp `1.5e`".into(), line: 0, column: 4 }),
                   Ok(EndFile),
               ),
     );
     test_read("1.5e-".as_bytes(),
               vec!(
                   Ok(BeginFile),
-                  Err(JSONLexError { msg: "Missing exp `1.5e-`".into(), line: 0, column: 5 }),
+                  Err(JSONLexError { kind: LexErrorKind::MissingExponent, msg: "Missing exp `1.5e-`".into(), line: 0, column: 5 }),
                   Ok(EndFile),
               ),
     );
     test_read("\"foo".as_bytes(),
               vec!(
                   Ok(BeginFile),
-                  Err(JSONLexError { msg: "Unfinished string `foo`".into(), line: 0, column: 4 }),
+                  Err(JSONLexError { kind: LexErrorKind::UnfinishedString, msg: "Unfinished string `foo`".into(), line: 0, column: 4 }),
                   Ok(EndFile),
               ),
     );
@@ -1138,7 +1139,7 @@ fn test_wrong_unicode() {
               vec!(
                   Ok(BeginFile),
                   Ok(BeginArray),
-                  Err(JSONLexError { msg: "Unknown hex digit `Z`".into(), line: 0, column: 6 }),
+                  Err(JSONLexError { kind: LexErrorKind::InvalidHexDigit('Z'), msg: "Unknown hex digit `Z`".into(), line: 0, column: 6 }),
                   Ok(LexerToken::String("-9D0-".into())),
                   Ok(EndArray),
                   Ok(EndFile),
@@ -1157,7 +1158,7 @@ fn test_wrong_unicode() {
               vec!(
                   Ok(BeginFile),
                   Ok(BeginArray),
-                  Err(JSONLexError { msg: "Waiting for low surrogate: needs backslash, got `-`".into(), line: 0, column: 10 }),
+                  Err(JSONLexError { kind: LexErrorKind::LoneSurrogate, msg: "Waiting for low surrogate: needs backslash, got `-`".into(), line: 0, column: 10 }),
                   Ok(LexerToken::String("--".into())),
                   Ok(EndArray),
                   Ok(EndFile),
@@ -1178,21 +1179,456 @@ fn test_escape() {
     );
 }
 
+#[test]
+fn test_newlines() {
+    test_read("[1\n,\n2]".as_bytes(), vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(IntValue("1".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(IntValue("2".into())),
+        Ok(EndArray),
+        Ok(EndFile),
+    ));
+    // a newline ends a number and is kept in a string
+    test_read("[1\n2, \"a\nb\"]".as_bytes(), vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(IntValue("1".into())),
+        Ok(IntValue("2".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(LexerToken::String("a\nb".into())),
+        Ok(EndArray),
+        Ok(EndFile),
+    ));
+}
+
 #[test]
 fn test_unexpected_char() {
     test_read("*".as_bytes(), vec!(
         Ok(BeginFile),
-        Err(JSONLexError { msg: "Unexpected char `*`".into(), line: 0, column: 1 }),
+        Err(JSONLexError { kind: LexErrorKind::UnexpectedChar('*'), msg: "Unexpected char `*`".into(), line: 0, column: 1 }),
         Ok(EndFile),
     ));
     test_read("foo".as_bytes(), vec!(
         Ok(BeginFile),
-        Err(JSONLexError { msg: "Expected word `alse`".into(), line: 0, column: 2 }),
-        Err(JSONLexError { msg: "Unexpected char `o`".into(), line: 0, column: 3 }),
+        Err(JSONLexError { kind: LexErrorKind::ExpectedWord("alse".into()), msg: "Expected word `alse`".into(), line: 0, column: 2 }),
+        Err(JSONLexError { kind: LexErrorKind::UnexpectedChar('o'), msg: "Unexpected char `o`".into(), line: 0, column: 3 }),
         Ok(EndFile),
     ));
 }
 
+#[test]
+fn test_invalid_utf8() {
+    let json: &[u8] = b"[\"a\xffb\"]";
+    test_read(json, vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Err(JSONLexError { kind: LexErrorKind::InvalidUtf8, msg: "Invalid UTF-8 byte `0xff` at offset 1 in string `a`".into(), line: 0, column: 4 }),
+        Ok(EndArray),
+        Ok(EndFile),
+    ));
+    test_read_with_options(json, LexerOptions { invalid_utf8: InvalidUtf8Policy::Replace, ..Default::default() }, vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(LexerToken::String("a\u{fffd}b".into())),
+        Ok(EndArray),
+        Ok(EndFile),
+    ));
+    test_read_with_options(json, LexerOptions { invalid_utf8: InvalidUtf8Policy::Bytes, ..Default::default() }, vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(LexerToken::Bytes(b"a\xffb".to_vec())),
+        Ok(EndArray),
+        Ok(EndFile),
+    ));
+}
+
+#[test]
+fn test_invalid_utf8_position() {
+    let json: &[u8] = b"[1,\n \"\xc3\xa9t\xc3\xa9 \xe9t\xe9\", \"\xc3\"]";
+    test_read(json, vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(IntValue("1".into())),
+        Ok(LexerToken::ValueSeparator),
+        Err(JSONLexError { kind: LexErrorKind::InvalidUtf8, msg: "Invalid UTF-8 byte `0xe9` at offset 6 in string `été `".into(), line: 1, column: 14 }),
+        Ok(LexerToken::ValueSeparator),
+        Err(JSONLexError { kind: LexErrorKind::InvalidUtf8, msg: "Invalid UTF-8 byte `0xc3` at offset 0 in string ``".into(), line: 1, column: 22 }),
+        Ok(EndArray),
+        Ok(EndFile),
+    ));
+    let options = |invalid_utf8| LexerOptions { invalid_utf8, string_chunk_size: Some(4), ..Default::default() };
+    test_read_with_options(b"\"ab\xffcd\xfeef\"".as_slice(), options(InvalidUtf8Policy::Replace), vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::StringStart),
+        Ok(LexerToken::StringChunk(b"ab".to_vec())),
+        Ok(LexerToken::StringChunk("\u{fffd}c".as_bytes().to_vec())),
+        Ok(LexerToken::StringChunk("d\u{fffd}".as_bytes().to_vec())),
+        Ok(LexerToken::StringChunk(b"ef".to_vec())),
+        Ok(LexerToken::StringEnd),
+        Ok(EndFile),
+    ));
+    test_read_with_options(b"\"abcd\xffefgh\"".as_slice(), options(InvalidUtf8Policy::Error), vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::StringStart),
+        Ok(LexerToken::StringChunk(b"abcd".to_vec())),
+        Ok(LexerToken::StringChunk(b"\xffefg".to_vec())),
+        Err(JSONLexError { kind: LexErrorKind::InvalidUtf8, msg: "Invalid UTF-8 byte `0xff` in string".into(), line: 0, column: 6 }),
+        Ok(EndFile),
+    ));
+}
+
+#[test]
+fn test_surrogate_policy() {
+    let options = |surrogates| LexerOptions { surrogates, ..Default::default() };
+    test_read_with_options("\"\\ud83d\\u0041\"".as_bytes(), options(SurrogatePolicy::Error), vec!(
+        Ok(BeginFile),
+        Err(JSONLexError { kind: LexErrorKind::LoneSurrogate, msg: "Waiting for low surrogate, got `65`".into(), line: 0, column: 13 }),
+        Ok(LexerToken::String("A".into())),
+        Ok(EndFile),
+    ));
+    test_read_with_options("\"\\ud83d\\u0041\"".as_bytes(), options(SurrogatePolicy::Replace), vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::String("\u{fffd}A".into())),
+        Ok(EndFile),
+    ));
+    test_read_with_options("\"\\ud83d\\ud83d\\ude00\"".as_bytes(), options(SurrogatePolicy::Replace), vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::String("\u{fffd}\u{1f600}".into())),
+        Ok(EndFile),
+    ));
+    test_read_with_options("\"-\\udc00-\\ud83d\\n\"".as_bytes(), options(SurrogatePolicy::Replace), vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::String("-\u{fffd}-\u{fffd}\n".into())),
+        Ok(EndFile),
+    ));
+    test_read_with_options("\"-\\udc00-\\ud83d\"".as_bytes(), options(SurrogatePolicy::PreserveWtf8), vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::Bytes(b"-\xed\xb0\x80-\xed\xa0\xbd".to_vec())),
+        Ok(EndFile),
+    ));
+    test_read_with_options("\"\\ud83d\\ude00\"".as_bytes(), options(SurrogatePolicy::PreserveWtf8), vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::String("\u{1f600}".into())),
+        Ok(EndFile),
+    ));
+}
+
+#[test]
+fn test_raw_strings() {
+    let options = || LexerOptions { raw_strings: true, ..Default::default() };
+    test_read_with_options(r#"{"été": ["a\/b", "😀\n", ""]}"#.as_bytes(), options(), vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::BeginObject),
+        Ok(LexerToken::RawString { decoded: "été".into(), raw: r#""été""#.into() }),
+        Ok(LexerToken::NameSeparator),
+        Ok(BeginArray),
+        Ok(LexerToken::RawString { decoded: "a/b".into(), raw: r#""a\/b""#.into() }),
+        Ok(LexerToken::ValueSeparator),
+        Ok(LexerToken::RawString { decoded: "\u{1f600}\n".into(), raw: r#""😀\n""#.into() }),
+        Ok(LexerToken::ValueSeparator),
+        Ok(LexerToken::RawString { decoded: "".into(), raw: r#""""#.into() }),
+        Ok(EndArray),
+        Ok(LexerToken::EndObject),
+        Ok(EndFile),
+    ));
+    // the high surrogate is pushed back
+    test_read_with_options(r#"'\ud83d-'"#.as_bytes(), LexerOptions { dialect: Dialect::Json5, surrogates: SurrogatePolicy::Replace, ..options() }, vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::RawString { decoded: "\u{fffd}-".into(), raw: r#"'\ud83d-'"#.into() }),
+        Ok(EndFile),
+    ));
+    test_read_with_options(r#""abcdefgh""#.as_bytes(), LexerOptions { string_chunk_size: Some(4), ..options() }, vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::StringStart),
+        Ok(LexerToken::StringChunk(b"abcd".to_vec())),
+        Ok(LexerToken::StringChunk(b"efgh".to_vec())),
+        Ok(LexerToken::StringEnd),
+        Ok(EndFile),
+    ));
+}
+
+#[test]
+fn test_raw_strings_comments_and_minus_zero() {
+    let options = LexerOptions { raw_strings: true, allow_comments: true, emit_comments: true, ..Default::default() };
+    test_read_with_options("[-0 /* a */, 0, -0] // b\r\n".as_bytes(), options, vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(IntValue("-0".into())),
+        Ok(LexerToken::Comment("/* a */".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(IntValue("0".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(IntValue("-0".into())),
+        Ok(EndArray),
+        Ok(LexerToken::Comment("// b".into())),
+        Ok(EndFile),
+    ));
+}
+
+struct SpanConsumer {
+    spans: Vec<(LexerToken, usize, usize)>,
+}
+
+impl JSONLexConsumer for SpanConsumer {
+    fn consume(&mut self, _token: Result<LexerToken, JSONLexError>, _line: usize, _column: usize) -> Result<(), ConsumeError> {
+        panic!("consume_with_span expected")
+    }
+
+    fn consume_with_span(&mut self, token: Result<LexerToken, JSONLexError>, span: Span) -> Result<(), ConsumeError> {
+        self.spans.push((token.unwrap(), span.start, span.end));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_spans() {
+    let json = r#"{"a": [10, -1.5e3, true], "b\"c": null} 7"#;
+    let mut consumer = SpanConsumer { spans: vec!() };
+    let mut lexer = JSONLexer::new(ByteSource::new(json.as_bytes()), false);
+    lexer.lex(&mut consumer).unwrap();
+    assert_eq!(vec!(
+        (BeginFile, 0, 0),
+        (LexerToken::BeginObject, 0, 1),
+        (LexerToken::String("a".into()), 1, 4),
+        (LexerToken::NameSeparator, 4, 5),
+        (BeginArray, 6, 7),
+        (IntValue("10".into()), 7, 9),
+        (LexerToken::ValueSeparator, 9, 10),
+        (FloatValue("-1.5e3".into()), 11, 17),
+        (LexerToken::ValueSeparator, 17, 18),
+        (LexerToken::BooleanValue(true), 19, 23),
+        (EndArray, 23, 24),
+        (LexerToken::ValueSeparator, 24, 25),
+        (LexerToken::String("b\"c".into()), 26, 32),
+        (LexerToken::NameSeparator, 32, 33),
+        (LexerToken::NullValue, 34, 38),
+        (LexerToken::EndObject, 38, 39),
+        (IntValue("7".into()), 40, 41),
+        (EndFile, 41, 41),
+    ), consumer.spans);
+    assert_eq!(&json[26..32], r#""b\"c""#);
+}
+
+#[test]
+fn test_next_token_span() {
+    let mut lexer = JSONLexer::new(ByteSource::new("\n  \"é\"".as_bytes()), false);
+    assert_eq!(Some((Ok(BeginFile), Span { start: 0, end: 0, line: 0, column: 0 })), lexer.next_token());
+    let (token, span) = lexer.next_token().unwrap();
+    assert_eq!(Ok(LexerToken::String("é".into())), token);
+    assert_eq!((3, 7, 1, 7), (span.start, span.end, span.line, span.column));
+    assert_eq!(4, span.len());
+}
+
+#[test]
+fn test_limits() {
+    let limits = |limits: LexerLimits| LexerOptions { limits, ..Default::default() };
+    test_read_with_options(r#"["abc", "abcd"]"#.as_bytes(), limits(LexerLimits { max_string_bytes: Some(3), ..Default::default() }), vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(LexerToken::String("abc".into())),
+        Ok(LexerToken::ValueSeparator),
+        Err(JSONLexError { kind: LexErrorKind::LimitExceeded, msg: "String longer than 3 bytes".into(), line: 0, column: 13 }),
+    ));
+    test_read_with_options("[-1.5, 12345]".as_bytes(), limits(LexerLimits { max_number_bytes: Some(4), ..Default::default() }), vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(FloatValue("-1.5".into())),
+        Ok(LexerToken::ValueSeparator),
+        Err(JSONLexError { kind: LexErrorKind::LimitExceeded, msg: "Number longer than 4 bytes".into(), line: 0, column: 13 }),
+    ));
+    test_read_with_options("[1, 2]".as_bytes(), limits(LexerLimits { max_tokens: Some(3), ..Default::default() }), vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(IntValue("1".into())),
+        Ok(LexerToken::ValueSeparator),
+        Err(JSONLexError { kind: LexErrorKind::LimitExceeded, msg: "More than 3 tokens".into(), line: 0, column: 7 }),
+    ));
+    test_read_with_options("[1, 2]  ".as_bytes(), limits(LexerLimits { max_document_bytes: Some(6), ..Default::default() }), vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(IntValue("1".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(IntValue("2".into())),
+        Ok(EndArray),
+        Err(JSONLexError { kind: LexErrorKind::LimitExceeded, msg: "Document longer than 6 bytes".into(), line: 0, column: 8 }),
+    ));
+}
+
+#[test]
+fn lex_json5() {
+    let json5 = || LexerOptions { dialect: Dialect::Json5, ..Default::default() };
+    test_read_with_options("[+1, .5, -.5, 5., 5.e2, 0x1F, -0XfF, Infinity, -Infinity, NaN]".as_bytes(), json5(), vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(IntValue("1".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(FloatValue("0.5".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(FloatValue("-0.5".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(FloatValue("5.0".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(FloatValue("5.0e2".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(IntValue("31".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(IntValue("-255".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(FloatValue("Infinity".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(FloatValue("-Infinity".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(FloatValue("NaN".into())),
+        Ok(EndArray),
+        Ok(EndFile),
+    ));
+    test_read_with_options("{$a_1: 'it\\'s \"x\\\r\ny', b: true}".as_bytes(), json5(), vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::BeginObject),
+        Ok(LexerToken::String("$a_1".into())),
+        Ok(LexerToken::NameSeparator),
+        Ok(LexerToken::String("it's \"xy".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(LexerToken::String("b".into())),
+        Ok(LexerToken::NameSeparator),
+        Ok(LexerToken::BooleanValue(true)),
+        Ok(LexerToken::EndObject),
+        Ok(EndFile),
+    ));
+    test_read_with_options("[0x, -Inf]".as_bytes(), json5(), vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Err(JSONLexError { kind: LexErrorKind::MissingDigits, msg: "Missing digits `0x`".into(), line: 0, column: 4 }),
+        Ok(LexerToken::ValueSeparator),
+        Err(JSONLexError { kind: LexErrorKind::ExpectedWord("Infinity".into()), msg: "Expected word `Infinity` or `NaN`, got `-Inf`".into(), line: 0, column: 11 }),
+        Ok(EndArray),
+        Ok(EndFile),
+    ));
+    test_read("'a'".as_bytes(), vec!(
+        Ok(BeginFile),
+        Err(JSONLexError { kind: LexErrorKind::UnexpectedChar('\''), msg: "Unexpected char `'`".into(), line: 0, column: 1 }),
+        Err(JSONLexError { kind: LexErrorKind::UnexpectedChar('a'), msg: "Unexpected char `a`".into(), line: 0, column: 2 }),
+        Err(JSONLexError { kind: LexErrorKind::UnexpectedChar('\''), msg: "Unexpected char `'`".into(), line: 0, column: 3 }),
+        Ok(EndFile),
+    ));
+}
+
+#[test]
+fn lex_single_quotes() {
+    let options = LexerOptions { single_quotes: true, ..Default::default() };
+    test_read_with_options(r#"{'a': 'it\'s "b"', "c": "'"}"#.as_bytes(), options, vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::BeginObject),
+        Ok(LexerToken::String("a".into())),
+        Ok(LexerToken::NameSeparator),
+        Ok(LexerToken::String("it's \"b\"".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(LexerToken::String("c".into())),
+        Ok(LexerToken::NameSeparator),
+        Ok(LexerToken::String("'".into())),
+        Ok(LexerToken::EndObject),
+        Ok(EndFile),
+    ));
+}
+
+#[test]
+fn lex_comments() {
+    let comments = |emit_comments| LexerOptions { allow_comments: true, emit_comments, ..Default::default() };
+    let json = "// settings\r\n{\"a\": 1/* one **/, \"b\": [] // end\n}";
+    test_read_with_options(json.as_bytes(), comments(true), vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::Comment(" settings".into())),
+        Ok(LexerToken::BeginObject),
+        Ok(LexerToken::String("a".into())),
+        Ok(LexerToken::NameSeparator),
+        Ok(IntValue("1".into())),
+        Ok(LexerToken::Comment(" one *".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(LexerToken::String("b".into())),
+        Ok(LexerToken::NameSeparator),
+        Ok(BeginArray),
+        Ok(EndArray),
+        Ok(LexerToken::Comment(" end".into())),
+        Ok(LexerToken::EndObject),
+        Ok(EndFile),
+    ));
+    test_read_with_options("[1 /* 2".as_bytes(), comments(false), vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(IntValue("1".into())),
+        Err(JSONLexError { kind: LexErrorKind::UnfinishedComment, msg: "Unfinished comment".into(), line: 0, column: 8 }),
+        Ok(EndFile),
+    ));
+    test_read("/".as_bytes(), vec!(
+        Ok(BeginFile),
+        Err(JSONLexError { kind: LexErrorKind::UnexpectedChar('/'), msg: "Unexpected char `/`".into(), line: 0, column: 1 }),
+        Ok(EndFile),
+    ));
+}
+
+#[test]
+fn lex_string_chunks() {
+    let options = LexerOptions { string_chunk_size: Some(4), ..Default::default() };
+    test_read_with_options("{\"long key\": [\"abc\", \"abcdéfghij\"]}".as_bytes(), options, vec!(
+        Ok(BeginFile),
+        Ok(LexerToken::BeginObject),
+        Ok(LexerToken::StringStart),
+        Ok(LexerToken::StringChunk(b"long".to_vec())),
+        Ok(LexerToken::StringChunk(b" key".to_vec())),
+        Ok(LexerToken::StringEnd),
+        Ok(LexerToken::NameSeparator),
+        Ok(BeginArray),
+        Ok(LexerToken::String("abc".into())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(LexerToken::StringStart),
+        Ok(LexerToken::StringChunk(b"abcd".to_vec())),
+        Ok(LexerToken::StringChunk("éfg".as_bytes().to_vec())),
+        Ok(LexerToken::StringChunk(b"hij".to_vec())),
+        Ok(LexerToken::StringEnd),
+        Ok(EndArray),
+        Ok(LexerToken::EndObject),
+        Ok(EndFile),
+    ));
+}
+
+#[test]
+fn lex_long_strings() {
+    let value = "aé\u{1f600}\t".repeat(20000);
+    let json = format!("[{:?}, \"{}\\\"\"]", value, value.replace('\t', " "));
+    test_read(json.as_bytes(), vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(LexerToken::String(value.clone())),
+        Ok(LexerToken::ValueSeparator),
+        Ok(LexerToken::String(format!("{}\"", value.replace('\t', " ")))),
+        Ok(EndArray),
+        Ok(EndFile),
+    ));
+    let limits = LexerOptions { limits: LexerLimits { max_string_bytes: Some(3), ..Default::default() }, ..Default::default() };
+    test_read_with_options(r#"["abc", "abcdefgh"]"#.as_bytes(), limits, vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(LexerToken::String("abc".into())),
+        Ok(LexerToken::ValueSeparator),
+        Err(JSONLexError { kind: LexErrorKind::LimitExceeded, msg: "String longer than 3 bytes".into(), line: 0, column: 13 }),
+    ));
+}
+
+#[test]
+fn lex_literals_at_end() {
+    test_read("true".as_bytes(), vec!(Ok(BeginFile), Ok(LexerToken::BooleanValue(true)), Ok(EndFile)));
+    test_read("[false,null]".as_bytes(), vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(LexerToken::BooleanValue(false)),
+        Ok(LexerToken::ValueSeparator),
+        Ok(LexerToken::NullValue),
+        Ok(EndArray),
+        Ok(EndFile),
+    ));
+}
 
 fn test_file(path: &str, expected_tokens: Vec<Result<LexerToken, JSONLexError>>) {
     let f = fs::File::open(path).expect("no file found");
@@ -1207,3 +1643,95 @@ fn test_read<R: Read>(read: R, expected_tokens: Vec<Result<LexerToken, JSONLexEr
     assert_eq!(expected_tokens, consumer.tokens);
 }
 
+
+fn test_read_with_options<R: Read>(read: R, options: LexerOptions, expected_tokens: Vec<Result<LexerToken, JSONLexError>>) {
+    let byte_source = ByteSource::new(read);
+    let mut consumer = AssertEqualsConsumer::new();
+    let mut lexer = JSONLexer::new_with_options(byte_source, options);
+    let _ = lexer.lex(&mut consumer);
+    assert_eq!(expected_tokens, consumer.tokens);
+}
+
+fn feed_by_bytes(json: &[u8], chunk_size: usize) -> Vec<Result<LexerToken, JSONLexError>> {
+    let mut consumer = AssertEqualsConsumer::new();
+    let mut lexer = JSONLexer::new_feed(LexerOptions::default());
+    for chunk in json.chunks(chunk_size) {
+        let _ = lexer.feed(chunk, &mut consumer);
+    }
+    let _ = lexer.finish(&mut consumer);
+    consumer.tokens
+}
+
+#[test]
+fn test_feed_same_tokens() {
+    let json = "{\"a\u{e9}\u{1f600}\": [true, false, null, -12.5e3, 1234567890], \"b\\u00e9\\n\": \"x\"}".as_bytes();
+    let mut consumer = AssertEqualsConsumer::new();
+    let _ = JSONLexer::new_with_options(ByteSource::new(json), LexerOptions::default()).lex(&mut consumer);
+    for chunk_size in 1..5 {
+        assert_eq!(consumer.tokens, feed_by_bytes(json, chunk_size));
+    }
+}
+
+#[test]
+fn test_feed_error() {
+    for json in [&b"[tru]"[..], b"[1, \"ab", b"[1.e]"] {
+        let mut consumer = AssertEqualsConsumer::new();
+        let _ = JSONLexer::new_with_options(ByteSource::new(json), LexerOptions::default()).lex(&mut consumer);
+        assert!(consumer.tokens.iter().any(|t| t.is_err()));
+        assert_eq!(consumer.tokens, feed_by_bytes(json, 1));
+    }
+}
+
+#[test]
+fn test_feed_after_finish() {
+    let mut consumer = AssertEqualsConsumer::new();
+    let mut lexer = JSONLexer::new_feed(LexerOptions::default());
+    lexer.feed(b"1", &mut consumer).unwrap();
+    lexer.finish(&mut consumer).unwrap();
+    lexer.feed(b"2", &mut consumer).unwrap();
+    assert_eq!(vec!(Ok(BeginFile), Ok(IntValue("1".into())), Ok(EndFile)), consumer.tokens);
+}
+
+/// Skips the strings and the numbers.
+struct SkippingConsumer {
+    tokens: Vec<Result<LexerToken, JSONLexError>>,
+}
+
+impl JSONLexConsumer for SkippingConsumer {
+    fn consume(&mut self, token: Result<LexerToken, JSONLexError>, _line: usize, _column: usize) -> Result<(), ConsumeError> {
+        self.tokens.push(token);
+        Ok(())
+    }
+
+    fn skip_value(&self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn test_skipped_string() {
+    let mut lexer = JSONLexer::new(ByteSource::new(r#"["a\"é", "", 10]"#.as_bytes()), false);
+    let mut consumer = SkippingConsumer { tokens: vec!() };
+    lexer.lex(&mut consumer).unwrap();
+    assert_eq!(vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(LexerToken::SkippedString),
+        Ok(LexerToken::ValueSeparator),
+        Ok(LexerToken::SkippedString),
+        Ok(LexerToken::ValueSeparator),
+        Ok(IntValue("".into())),
+        Ok(EndArray),
+        Ok(EndFile),
+    ), consumer.tokens);
+}
+
+#[test]
+fn test_pull_skipped_string() {
+    let mut lexer = JSONLexer::new(ByteSource::new(r#"["a", "b"]"#.as_bytes()), false);
+    assert_eq!(Some(Ok(BeginFile)), lexer.next_token().map(|(t, _)| t));
+    assert_eq!(Some(Ok(BeginArray)), lexer.next_token().map(|(t, _)| t));
+    assert_eq!(Some(Ok(LexerToken::SkippedString)), lexer.next_token_skipping(true).map(|(t, _)| t));
+    assert_eq!(Some(Ok(LexerToken::ValueSeparator)), lexer.next_token().map(|(t, _)| t));
+    assert_eq!(Some(Ok(LexerToken::String("b".into()))), lexer.next_token().map(|(t, _)| t));
+}
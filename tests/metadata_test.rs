@@ -0,0 +1,144 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::{ConsumeError, LexerOptions};
+use r_json_event_parser::json_metadata::{JSONMetadataConsumer, JSONPathAwareConsumer, MetadataAdapter, PathAdapter, TokenMetadata};
+use r_json_event_parser::json_parser::{JSONParseError, JSONParser, ParserToken};
+use r_json_event_parser::json_pointer::{PathElement, to_pointer_string};
+use r_json_event_parser::json_parser::ParserToken::{BeginArray, BeginFile, BeginObject, EndArray, EndFile, EndObject, IntValue, Key, NullValue};
+
+struct MetadataCollector {
+    tokens: Vec<(ParserToken, usize, Option<usize>)>,
+}
+
+impl JSONMetadataConsumer for MetadataCollector {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>, metadata: TokenMetadata) -> Result<(), ConsumeError> {
+        self.tokens.push((token.unwrap(), metadata.depth, metadata.index));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_metadata() {
+    let mut collector = MetadataCollector { tokens: vec!() };
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a": [1, null], "b": {"c": 2}}"#.as_bytes()), false);
+    parser.parse(&mut MetadataAdapter::new(&mut collector)).unwrap();
+    assert_eq!(vec!(
+        (BeginFile, 0, None),
+        (BeginObject, 0, None),
+        (Key("a".into()), 1, Some(0)),
+        (BeginArray, 1, Some(0)),
+        (IntValue("1".into()), 2, Some(0)),
+        (NullValue, 2, Some(1)),
+        (EndArray, 1, Some(0)),
+        (Key("b".into()), 1, Some(1)),
+        (BeginObject, 1, Some(1)),
+        (Key("c".into()), 2, Some(0)),
+        (IntValue("2".into()), 2, Some(0)),
+        (EndObject, 1, Some(1)),
+        (EndObject, 0, None),
+        (EndFile, 0, None),
+    ), collector.tokens);
+}
+
+struct RowCollector {
+    rows: Vec<(ParserToken, Option<usize>)>,
+}
+
+impl JSONMetadataConsumer for RowCollector {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>, metadata: TokenMetadata) -> Result<(), ConsumeError> {
+        if metadata.depth == 1 {
+            self.rows.push((token.unwrap(), metadata.array_index()));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_array_index() {
+    let mut collector = RowCollector { rows: vec!() };
+    let mut parser = JSONParser::new(ByteSource::new(r#"[{"a": 1}, 2, [3]]"#.as_bytes()), false);
+    parser.parse(&mut MetadataAdapter::new(&mut collector)).unwrap();
+    assert_eq!(vec!(
+        (BeginObject, Some(0)),
+        (EndObject, Some(0)),
+        (IntValue("2".into()), Some(1)),
+        (BeginArray, Some(2)),
+        (EndArray, Some(2)),
+    ), collector.rows);
+
+    let mut collector = RowCollector { rows: vec!() };
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a": 1}"#.as_bytes()), false);
+    parser.parse(&mut MetadataAdapter::new(&mut collector)).unwrap();
+    assert_eq!(vec!((Key("a".into()), None), (IntValue("1".into()), None)), collector.rows);
+}
+
+struct PathCollector {
+    tokens: Vec<(ParserToken, String)>,
+}
+
+impl JSONPathAwareConsumer for PathCollector {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>, path: &[PathElement]) -> Result<(), ConsumeError> {
+        self.tokens.push((token.unwrap(), to_pointer_string(path)));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_path() {
+    let mut collector = PathCollector { tokens: vec!() };
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a": [1, {"b": null}]}"#.as_bytes()), false);
+    parser.parse(&mut PathAdapter::new(&mut collector)).unwrap();
+    assert_eq!(vec!(
+        (BeginFile, "".into()),
+        (BeginObject, "".into()),
+        (Key("a".into()), "/a".into()),
+        (BeginArray, "/a".into()),
+        (IntValue("1".into()), "/a/0".into()),
+        (BeginObject, "/a/1".into()),
+        (Key("b".into()), "/a/1/b".into()),
+        (NullValue, "/a/1/b".into()),
+        (EndObject, "/a/1".into()),
+        (EndArray, "/a".into()),
+        (EndObject, "".into()),
+        (EndFile, "".into()),
+    ), collector.tokens);
+}
+
+#[test]
+fn test_metadata_chunked_string() {
+    let mut collector = MetadataCollector { tokens: vec!() };
+    let options = LexerOptions { string_chunk_size: Some(4), ..Default::default() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new(r#"["abcdef", 1]"#.as_bytes()), options);
+    parser.parse(&mut MetadataAdapter::new(&mut collector)).unwrap();
+    assert_eq!(vec!(
+        (BeginFile, 0, None),
+        (BeginArray, 0, None),
+        (ParserToken::StringStart, 1, Some(0)),
+        (ParserToken::StringChunk(b"abcd".to_vec()), 1, Some(0)),
+        (ParserToken::StringChunk(b"ef".to_vec()), 1, Some(0)),
+        (ParserToken::StringEnd, 1, Some(0)),
+        (IntValue("1".into()), 1, Some(1)),
+        (EndArray, 0, None),
+        (EndFile, 0, None),
+    ), collector.tokens);
+}
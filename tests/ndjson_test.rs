@@ -0,0 +1,78 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::json_lexer::LexErrorKind;
+use r_json_event_parser::json_parser::{JSONParseError, ParseErrorKind, ParserToken};
+use r_json_event_parser::json_parser::ParserToken::{BeginFile, EndFile, IntValue};
+use r_json_event_parser::ndjson::{NDJSONParser, RecordError, RecordErrorPolicy};
+
+const INPUT: &str = "1\n\n[2\n3\n";
+
+type RecordTokens = Vec<(usize, Result<ParserToken, JSONParseError>)>;
+
+fn parse(policy: RecordErrorPolicy) -> (RecordTokens, Result<Vec<RecordError>, String>) {
+    let mut tokens = vec!();
+    let result = NDJSONParser::new(INPUT.as_bytes(), false).error_policy(policy).parse(&mut tokens);
+    (tokens, result.map(|summary| summary.errors).map_err(|e| e.msg))
+}
+
+#[test]
+fn test_skip() {
+    let (tokens, errors) = parse(RecordErrorPolicy::Skip);
+    assert_eq!(vec!(
+        (0, Ok(BeginFile)), (0, Ok(IntValue("1".into()))), (0, Ok(EndFile)),
+        (2, Ok(BeginFile)), (2, Ok(IntValue("3".into()))), (2, Ok(EndFile)),
+    ), tokens);
+    let errors = errors.unwrap();
+    assert_eq!(1, errors.len());
+    assert_eq!(1, errors[0].record);
+    assert_eq!(2, errors[0].error.line);
+    assert_eq!(ParseErrorKind::UnclosedContainer, errors[0].error.kind);
+}
+
+#[test]
+fn test_emit() {
+    let (tokens, errors) = parse(RecordErrorPolicy::Emit);
+    let records: Vec<(usize, bool)> = tokens.iter().map(|(r, t)| (*r, t.is_ok())).collect();
+    assert_eq!(vec!(
+        (0, true), (0, true), (0, true),
+        (1, true), (1, true), (1, true), (1, false),
+        (2, true), (2, true), (2, true),
+    ), records);
+    assert_eq!(1, errors.unwrap().len());
+}
+
+#[test]
+fn test_abort() {
+    let (tokens, errors) = parse(RecordErrorPolicy::Abort);
+    assert_eq!(7, tokens.len());
+    assert!(tokens[6].1.is_err());
+    assert!(errors.unwrap_err().starts_with("Unexpected token `Ok(EndFile)`"));
+}
+
+#[test]
+fn test_lex_error() {
+    let mut tokens = vec!();
+    let summary = NDJSONParser::new("{\"a\": 1}\n{\"a\": x}\n".as_bytes(), false).parse(&mut tokens).unwrap();
+    assert_eq!(2, summary.records);
+    assert_eq!(ParseErrorKind::Lex(LexErrorKind::UnexpectedChar('x')), summary.errors[0].error.kind);
+    assert_eq!((1, 1), (summary.errors[0].record, summary.errors[0].error.line));
+}
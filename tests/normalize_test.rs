@@ -0,0 +1,82 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#![cfg(feature = "unicode-normalization")]
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_duplicates::{DuplicateKeyConsumer, DuplicateKeyResolution};
+use r_json_event_parser::json_lexer::ConsumeError;
+use r_json_event_parser::json_normalize::{NFCConsumer, NormalizationScope};
+use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use r_json_event_parser::json_parser::ParserToken::{BeginFile, BeginObject, EndFile, EndObject, Key, StringValue};
+
+struct AssertEqualsConsumer {
+    tokens: Vec<Result<ParserToken, JSONParseError>>,
+}
+
+impl AssertEqualsConsumer {
+    fn new() -> Self {
+        AssertEqualsConsumer { tokens: vec!() }
+    }
+}
+
+impl JSONParseConsumer for AssertEqualsConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.tokens.push(token);
+        Ok(())
+    }
+}
+
+// the second key is the NFD form of the first one
+const JSON: &str = "{\"caf\u{e9}\": \"e\u{301}\", \"cafe\u{301}\": \"x\"}";
+
+#[test]
+fn test_normalize_keys() {
+    let mut consumer = AssertEqualsConsumer::new();
+    let mut parser = JSONParser::new(ByteSource::new(JSON.as_bytes()), false);
+    parser.parse(&mut NFCConsumer::new(&mut consumer, NormalizationScope::Keys)).unwrap();
+    assert_eq!(vec!(
+        Ok(BeginFile),
+        Ok(BeginObject),
+        Ok(Key("caf\u{e9}".into())),
+        Ok(StringValue("e\u{301}".into())),
+        Ok(Key("caf\u{e9}".into())),
+        Ok(StringValue("x".into())),
+        Ok(EndObject),
+        Ok(EndFile),
+    ), consumer.tokens);
+}
+
+#[test]
+fn test_normalize_keys_and_strings_then_dedupe() {
+    let mut consumer = AssertEqualsConsumer::new();
+    let mut parser = JSONParser::new(ByteSource::new(JSON.as_bytes()), false);
+    let mut dedupe = DuplicateKeyConsumer::new(&mut consumer, DuplicateKeyResolution::FirstWins);
+    parser.parse(&mut NFCConsumer::new(&mut dedupe, NormalizationScope::KeysAndStrings)).unwrap();
+    assert_eq!(vec!(
+        Ok(BeginFile),
+        Ok(BeginObject),
+        Ok(Key("caf\u{e9}".into())),
+        Ok(StringValue("\u{e9}".into())),
+        Ok(EndObject),
+        Ok(EndFile),
+    ), consumer.tokens);
+}
@@ -0,0 +1,68 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::ConsumeError;
+use r_json_event_parser::json_numbers::{is_exact_float, is_exact_int, PrecisionCheckConsumer, PrecisionLossKind, PrecisionWarning};
+use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+
+struct CountConsumer {
+    count: usize,
+}
+
+impl JSONParseConsumer for CountConsumer {
+    fn consume(&mut self, _token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.count += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_exact_int() {
+    assert!(is_exact_int("-9223372036854775808"));
+    assert!(is_exact_int("18446744073709551615"));
+    assert!(!is_exact_int("18446744073709551616"));
+    assert!(!is_exact_int("-9223372036854775809"));
+}
+
+#[test]
+fn test_exact_float() {
+    assert!(is_exact_float("0.1"));
+    assert!(is_exact_float("-12.50e1"));
+    assert!(is_exact_float("0.0"));
+    assert!(is_exact_float("1e-7"));
+    assert!(!is_exact_float("0.10000000000000000001"));
+    assert!(!is_exact_float("1e400"));
+    assert!(!is_exact_float("9007199254740993.0"));
+}
+
+#[test]
+fn test_precision_check() {
+    let mut consumer = CountConsumer { count: 0 };
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a": [1, 123456789012345678901234567890], "b": 3.14159265358979323846}"#.as_bytes()), false);
+    let mut check = PrecisionCheckConsumer::new(&mut consumer);
+    parser.parse(&mut check).unwrap();
+    assert_eq!(&[
+        PrecisionWarning { kind: PrecisionLossKind::IntegerOverflow, path: "/a/1".into(), lexeme: "123456789012345678901234567890".into() },
+        PrecisionWarning { kind: PrecisionLossKind::InexactFloat, path: "/b".into(), lexeme: "3.14159265358979323846".into() },
+    ], check.warnings());
+    assert_eq!(11, consumer.count);
+}
@@ -0,0 +1,119 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fs;
+use std::io::Write;
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::input::Compression;
+use r_json_event_parser::json_lexer::{ConsumeError, LexerOptions};
+use r_json_event_parser::json_writer::{reformat, WriteStyle};
+use r_json_event_parser::output::{compress, compression_from_extension, replace_file};
+
+#[test]
+fn test_compression_from_extension() {
+    assert_eq!(Compression::Gzip, compression_from_extension("out/a.xml.gz"));
+    assert_eq!(Compression::Zstd, compression_from_extension("a.xml.zst"));
+    assert_eq!(Compression::None, compression_from_extension("a.xml"));
+    assert_eq!(Compression::None, compression_from_extension("-"));
+}
+
+#[test]
+fn test_compress_none() {
+    let mut bytes = vec!();
+    compress(&mut bytes, Compression::None).unwrap().write_all(b"<root/>").unwrap();
+    assert_eq!(b"<root/>".to_vec(), bytes);
+}
+
+#[cfg(not(feature = "gzip"))]
+#[test]
+fn test_compress_gzip_without_feature() {
+    let err = compress(vec!(), Compression::Gzip).err().unwrap();
+    assert_eq!(std::io::ErrorKind::Unsupported, err.kind());
+}
+
+#[cfg(not(feature = "zstd"))]
+#[test]
+fn test_open_output_without_feature() {
+    use r_json_event_parser::output::open_output;
+    let path = std::env::temp_dir().join(format!("r_json_output_test_{}.xml.zst", std::process::id()));
+    let err = open_output(&path.to_string_lossy(), Compression::Auto).err().unwrap();
+    assert_eq!(std::io::ErrorKind::Unsupported, err.kind());
+    assert!(!path.exists());
+}
+
+#[cfg(all(feature = "gzip", feature = "zstd"))]
+#[test]
+fn test_compress_round_trip() {
+    use std::io::Read;
+    use r_json_event_parser::input::decompress;
+    for compression in [Compression::Gzip, Compression::Zstd] {
+        let mut bytes = vec!();
+        {
+            let mut write = compress(&mut bytes, compression).unwrap();
+            write.write_all(b"<root>").unwrap();
+            write.write_all(b"</root>").unwrap();
+        }
+        let mut s = String::new();
+        decompress(bytes.as_slice(), Compression::Auto).unwrap().read_to_string(&mut s).unwrap();
+        assert_eq!("<root></root>", s);
+    }
+}
+
+fn minify_in_place(path: &std::path::Path) -> Result<(), ConsumeError> {
+    let input = fs::read(path).unwrap();
+    replace_file(path, |write| reformat(ByteSource::new(input.as_slice()), LexerOptions::default(), write, WriteStyle::compact()).map(|_| ()))
+}
+
+#[test]
+fn test_replace_file() {
+    let dir = std::env::temp_dir().join(format!("r_json_replace_test_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("a.json");
+    fs::write(&path, "{ \"a\" : [1, 2] }").unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+    }
+    minify_in_place(&path).unwrap();
+    assert_eq!("{\"a\":[1,2]}", fs::read_to_string(&path).unwrap());
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        assert_eq!(0o640, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+    }
+    assert_eq!(1, fs::read_dir(&dir).unwrap().count());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_replace_file_error() {
+    let dir = std::env::temp_dir().join(format!("r_json_replace_error_test_{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("a.json");
+    fs::write(&path, "{ \"a\" : [1, }").unwrap();
+    assert!(minify_in_place(&path).is_err());
+    assert_eq!("{ \"a\" : [1, }", fs::read_to_string(&path).unwrap());
+    // no temporary file left
+    assert_eq!(1, fs::read_dir(&dir).unwrap().count());
+    fs::remove_dir_all(&dir).unwrap();
+}
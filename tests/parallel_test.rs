@@ -0,0 +1,122 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::{ConsumeError, LexerOptions};
+use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use r_json_event_parser::ndjson::{NDJSONParser, RecordErrorPolicy};
+use r_json_event_parser::parallel::{split_lines, ParallelParser};
+
+struct VecConsumer {
+    tokens: Vec<Result<ParserToken, JSONParseError>>,
+}
+
+impl JSONParseConsumer for VecConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.tokens.push(token);
+        Ok(())
+    }
+}
+
+fn ndjson(bad_record: Option<usize>) -> String {
+    (0..200).map(|i| if Some(i) == bad_record {
+        "{\"id\": }\n".to_string()
+    } else if i % 7 == 0 {
+        "\n".to_string()
+    } else {
+        format!("{{\"id\": {}, \"name\": \"n\\\"{}\", \"tags\": [1, [2]]}}\n", i, i)
+    }).collect()
+}
+
+fn array(bad_element: Option<usize>) -> String {
+    let elements: Vec<String> = (0..200).map(|i| if Some(i) == bad_element {
+        "{\"id\": ]".to_string()
+    } else {
+        format!("{{\"id\": {},\n \"s\": \"],[{{\\\\\\\"\", \"a\": [{}, {{}}]}}", i, i)
+    }).collect();
+    format!(" [{}]\n", elements.join(", "))
+}
+
+#[test]
+fn test_split_lines() {
+    assert_eq!(vec!(&b"ab\n"[..], b"cd\n", b"e\nf"), split_lines(b"ab\ncd\ne\nf", 3));
+    assert_eq!(vec!(&b"ab\ncd\n"[..], b"e\nf"), split_lines(b"ab\ncd\ne\nf", 5));
+    assert!(split_lines(b"", 4).is_empty());
+}
+
+#[test]
+fn test_map_chunks() {
+    let parser = ParallelParser::new(LexerOptions::default()).threads(3);
+    let chunks: Vec<&[u8]> = vec!(b"a", b"bb", b"ccc", b"dddd", b"e");
+    assert_eq!(vec!((0, 1), (1, 2), (2, 3), (3, 4), (4, 1)), parser.map_chunks(&chunks, |i, c| (i, c.len())));
+}
+
+#[test]
+fn test_parallel_ndjson() {
+    for bad_record in [None, Some(150)] {
+        let input = ndjson(bad_record);
+        for policy in [RecordErrorPolicy::Skip, RecordErrorPolicy::Emit, RecordErrorPolicy::Abort] {
+            let mut expected = vec!();
+            let expected_result = NDJSONParser::new(input.as_bytes(), false).error_policy(policy).parse(&mut expected);
+            let mut tokens = vec!();
+            let result = ParallelParser::new(LexerOptions::default()).threads(4).chunk_size(300)
+                .error_policy(policy).parse_ndjson(input.as_bytes(), &mut tokens);
+            assert_eq!(expected_result, result);
+            assert_eq!(expected, tokens);
+        }
+    }
+}
+
+#[test]
+fn test_parallel_array() {
+    for bad_element in [None, Some(150)] {
+        let input = array(bad_element);
+        let mut expected = VecConsumer { tokens: vec!() };
+        let expected_result = JSONParser::new(ByteSource::new(input.as_bytes()), false).parse(&mut expected);
+        let mut consumer = VecConsumer { tokens: vec!() };
+        let result = ParallelParser::new(LexerOptions::default()).threads(4).chunk_size(500)
+            .parse_array(input.as_bytes(), &mut consumer);
+        match expected.tokens.iter().position(|t| t.is_err()) {
+            None => {
+                assert_eq!(expected_result, result);
+                assert_eq!(expected.tokens, consumer.tokens);
+            }
+            Some(i) => {
+                // the parallel parser stops at the first error
+                let e = expected.tokens[i].clone().unwrap_err();
+                assert_eq!(Err(ConsumeError::from(e)), result);
+                assert_eq!(expected.tokens[..=i].to_vec(), consumer.tokens);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_parallel_array_fallback() {
+    for input in [&b"{\"a\": [1, 2]}"[..], b"[1, 2, 3] 4", b"[1, 2, 3, ]", b"[1, 2}", b"[1, 2"] {
+        let mut expected = VecConsumer { tokens: vec!() };
+        let expected_result = JSONParser::new(ByteSource::new(input), false).parse(&mut expected);
+        let mut consumer = VecConsumer { tokens: vec!() };
+        let result = ParallelParser::new(LexerOptions::default()).chunk_size(1).parse_array(input, &mut consumer);
+        assert_eq!(expected_result, result);
+        assert_eq!(expected.tokens, consumer.tokens);
+    }
+}
@@ -20,12 +20,16 @@
  */
 
 use std::fs;
-use std::io::Read;
+use std::io::{BufRead, BufReader, Read};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
 
 use r_json_event_parser::byte_source::ByteSource;
-use r_json_event_parser::json_lexer::ConsumeError;
-use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
-use r_json_event_parser::json_parser::ParserToken::{BeginArray, BeginFile, BeginObject, BooleanValue, EndArray, EndFile, EndObject, IntValue, Key, NullValue, StringValue};
+use r_json_event_parser::json_lexer::{ConsumeError, Dialect, Interrupt, InterruptReason, InvalidUtf8Policy, LexErrorKind, LexerLimits, LexerOptions, LexerToken, Progress, ProgressInfo, Span};
+use r_json_event_parser::json_parser::{ContainerKind, DuplicateKeyPolicy, JSONFeedParser, JSONParseConsumer, JSONParseError, JSONParser, JSONPullParser, NumberMode, OpenContainer, ParseErrorKind, ParserToken, ParseStats, Pipeline, PipelineError};
+use r_json_event_parser::json_numbers::PrecisionLossKind;
+use r_json_event_parser::json_parser::ParserToken::{BeginArray, BeginFile, BeginObject, BooleanValue, BytesValue, EndArray, EndFile, EndObject, FloatValue, IntValue, Key, NullValue, StringValue};
 
 struct AssertEqualsConsumer {
     tokens: Vec<Result<ParserToken, JSONParseError>>,
@@ -527,7 +531,7 @@ fn parse_wrong() {
     test_read("-foo".as_bytes(),
               vec!(
                   Ok(BeginFile),
-                  Err(JSONParseError { msg: "Expected a digit `f`".into(), line: 0, column: 2 })
+                  Err(JSONParseError { kind: ParseErrorKind::Lex(LexErrorKind::ExpectedDigit('f')), msg: "Expected a digit `f`".into(), line: 0, column: 2 })
               ),
     );
     test_read("{\"foo\":-,\"bar\":10}".as_bytes(),
@@ -535,7 +539,7 @@ fn parse_wrong() {
                   Ok(BeginFile),
                   Ok(BeginObject),
                   Ok(Key("foo".into())),
-                  Err(JSONParseError { msg: "Expected a digit `,`".into(), line: 0, column: 9 })
+                  Err(JSONParseError { kind: ParseErrorKind::Lex(LexErrorKind::ExpectedDigit(',')), msg: "Expected a digit `,`".into(), line: 0, column: 9 })
               ),
     );
 }
@@ -548,8 +552,748 @@ fn test_unfinished() {
                   Ok(BeginObject),
                   Ok(Key("foo".into())),
                   Ok(IntValue("1".into())),
-                  Err(JSONParseError { msg: "Unexpected token `Ok(EndFile)`".into(),
+                  Err(JSONParseError { kind: ParseErrorKind::UnclosedContainer, msg: "Unexpected token `Ok(EndFile)`: object opened at 0:1 not closed".into(),
                       line: 0, column: 8 }),
               ),
     );
-}
\ No newline at end of file
+}
+#[test]
+fn test_pipeline() {
+    let byte_source = ByteSource::new("{\"foo\":[1, {}]}".as_bytes());
+    let pipeline = Pipeline::new(byte_source, false, AssertEqualsConsumer::new());
+    let (stats, consumer) = pipeline.run().unwrap();
+    assert_eq!(ParseStats { tokens: 10, max_depth: 3 }, stats);
+    assert_eq!(vec!(
+        Ok(BeginFile),
+        Ok(BeginObject),
+        Ok(Key("foo".into())),
+        Ok(BeginArray),
+        Ok(IntValue("1".into())),
+        Ok(BeginObject),
+        Ok(EndObject),
+        Ok(EndArray),
+        Ok(EndObject),
+        Ok(EndFile),
+    ), consumer.tokens);
+}
+
+#[test]
+fn test_pipeline_with_options() {
+    let options = LexerOptions { dialect: Dialect::Json5, ..Default::default() };
+    let pipeline = Pipeline::new_with_options(ByteSource::new("[0x1F,]".as_bytes()), options, AssertEqualsConsumer::new());
+    let (_, consumer) = pipeline.run().unwrap();
+    assert_eq!(vec!(Ok(BeginFile), Ok(BeginArray), Ok(IntValue("31".into())), Ok(EndArray), Ok(EndFile)), consumer.tokens);
+
+    let parser = JSONParser::new(ByteSource::new(r#"{"a": 1, "a": 2}"#.as_bytes()), false).duplicate_key_policy(DuplicateKeyPolicy::Error);
+    let (_, consumer) = Pipeline::from_parser(parser, AssertEqualsConsumer::new()).run().unwrap();
+    assert_eq!(Some(ParseErrorKind::DuplicateKey("a".into())), consumer.tokens.iter().find_map(|t| t.clone().err()).map(|e| e.kind));
+}
+
+/// Fails on the `IntValue` 2.
+struct FailOnTwo(usize);
+
+impl JSONParseConsumer for FailOnTwo {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        if token == Ok(IntValue("2".into())) {
+            return Err(ConsumeError { msg: "two".into(), line: 0, column: 0, io_error: None });
+        }
+        self.0 += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_pipeline_error() {
+    let byte_source = ByteSource::new("[[1], 2, 3]".as_bytes());
+    let pipeline = Pipeline::new(byte_source, false, FailOnTwo(0));
+    let (error, stats, consumer) = match pipeline.run() {
+        Err(PipelineError { error, stats, consumer }) => (error, stats, consumer),
+        Ok(_) => panic!("an error was expected"),
+    };
+    assert_eq!("two", error.msg);
+    assert_eq!(ParseStats { tokens: 6, max_depth: 2 }, stats);
+    assert_eq!(5, consumer.0);
+}
+
+#[test]
+fn test_invalid_utf8_bytes() {
+    let options = LexerOptions { invalid_utf8: InvalidUtf8Policy::Bytes, ..Default::default() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new(&b"{\"k\xff\": \"v\xfe\"}"[..]), options);
+    let mut consumer = AssertEqualsConsumer::new();
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(vec!(
+        Ok(BeginFile),
+        Ok(BeginObject),
+        Ok(Key("k\u{fffd}".into())),
+        Ok(BytesValue(b"v\xfe".to_vec())),
+        Ok(EndObject),
+        Ok(EndFile),
+    ), consumer.tokens);
+}
+
+#[test]
+fn test_raw_strings() {
+    let options = LexerOptions { raw_strings: true, ..Default::default() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new(r#"{"k": "v"}"#.as_bytes()), options);
+    let mut consumer = AssertEqualsConsumer::new();
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(vec!(
+        Ok(BeginFile),
+        Ok(BeginObject),
+        Ok(Key("k".into())),
+        Ok(StringValue("v".into())),
+        Ok(EndObject),
+        Ok(EndFile),
+    ), consumer.tokens);
+}
+
+#[test]
+fn test_interrupt_cancelled() {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let interrupt = Interrupt { every: 3, cancelled: Some(cancelled.clone()), ..Default::default() };
+    let options = LexerOptions { interrupt: Some(interrupt.clone()), ..Default::default() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new(r#"[1, 2]"#.as_bytes()), options);
+    let mut consumer = AssertEqualsConsumer::new();
+    assert_eq!(None, interrupt.check());
+    cancelled.store(true, Ordering::Relaxed);
+    let err = parser.parse(&mut consumer).unwrap_err();
+    assert_eq!("Parsing interrupted: cancelled", err.msg);
+    assert_eq!(Some(InterruptReason::Cancelled), interrupt.check());
+    assert_eq!(vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Err(JSONParseError { kind: ParseErrorKind::Lex(LexErrorKind::Interrupted(InterruptReason::Cancelled)), msg: "Parsing interrupted: cancelled".into(), line: 0, column: 3 }),
+    ), consumer.tokens);
+}
+
+#[test]
+fn test_interrupt_cancelled_feed() {
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let interrupt = Interrupt { cancelled: Some(cancelled.clone()), ..Default::default() };
+    let mut parser = JSONFeedParser::new(LexerOptions { interrupt: Some(interrupt), ..Default::default() });
+    let mut consumer = AssertEqualsConsumer::new();
+    parser.feed(b"[1, ", &mut consumer).unwrap();
+    cancelled.store(true, Ordering::Relaxed);
+    assert_eq!("Parsing interrupted: cancelled", parser.feed(b"2]", &mut consumer).unwrap_err().msg);
+    assert_eq!(Ok(()), parser.finish(&mut consumer));
+    assert_eq!(Some(&ParseErrorKind::Lex(LexErrorKind::Interrupted(InterruptReason::Cancelled))),
+               consumer.tokens.last().unwrap().as_ref().err().map(|e| &e.kind));
+}
+
+#[test]
+fn test_interrupt_deadline() {
+    let interrupt = Interrupt { deadline: Some(Instant::now()), ..Default::default() };
+    let options = LexerOptions { interrupt: Some(interrupt), ..Default::default() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new(r#"[1, 2]"#.as_bytes()), options);
+    let mut consumer = AssertEqualsConsumer::new();
+    assert_eq!(Err(ConsumeError { msg: "Parsing interrupted: deadline exceeded".into(), line: 0, column: 0, io_error: None }),
+               parser.parse(&mut consumer));
+    assert_eq!(vec!(
+        Err(JSONParseError { kind: ParseErrorKind::Lex(LexErrorKind::Interrupted(InterruptReason::DeadlineExceeded)), msg: "Parsing interrupted: deadline exceeded".into(), line: 0, column: 0 }),
+    ), consumer.tokens);
+}
+
+fn progress_recorder(every_bytes: usize) -> (Progress, Arc<Mutex<Vec<ProgressInfo>>>) {
+    let reports = Arc::new(Mutex::new(vec!()));
+    let recorded = reports.clone();
+    (Progress::new(every_bytes, move |info| recorded.lock().unwrap().push(info)), reports)
+}
+
+#[test]
+fn test_progress() {
+    let (progress, reports) = progress_recorder(4);
+    let options = LexerOptions { progress: Some(progress), ..Default::default() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new(r#"[1, 22, 333]"#.as_bytes()), options);
+    parser.parse(&mut AssertEqualsConsumer::new()).unwrap();
+    assert_eq!(vec!(
+        ProgressInfo { bytes: 0, tokens: 1 },
+        ProgressInfo { bytes: 6, tokens: 5 },
+        ProgressInfo { bytes: 11, tokens: 7 },
+        ProgressInfo { bytes: 12, tokens: 8 },
+        ProgressInfo { bytes: 12, tokens: 9 },
+    ), *reports.lock().unwrap());
+}
+
+#[test]
+fn test_progress_feed() {
+    let (progress, reports) = progress_recorder(1000);
+    let mut parser = JSONFeedParser::new(LexerOptions { progress: Some(progress), ..Default::default() });
+    let mut consumer = AssertEqualsConsumer::new();
+    parser.feed(b"[1, 2", &mut consumer).unwrap();
+    parser.feed(b"2, 333]", &mut consumer).unwrap();
+    parser.finish(&mut consumer).unwrap();
+    assert_eq!(vec!(
+        ProgressInfo { bytes: 0, tokens: 1 },
+        ProgressInfo { bytes: 12, tokens: 9 },
+    ), *reports.lock().unwrap());
+}
+
+#[test]
+fn test_limits() {
+    let limits = LexerLimits { max_string_bytes: Some(4), ..Default::default() };
+    let options = LexerOptions { limits, ..Default::default() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new(r#"{"key": "value"}"#.as_bytes()), options);
+    let mut consumer = AssertEqualsConsumer::new();
+    assert_eq!("String longer than 4 bytes", parser.parse(&mut consumer).unwrap_err().msg);
+    assert_eq!(&[Ok(BeginFile), Ok(BeginObject), Ok(Key("key".into()))], &consumer.tokens[..3]);
+}
+
+struct SkippingConsumer {
+    tokens: Vec<Result<ParserToken, JSONParseError>>,
+}
+
+impl JSONParseConsumer for SkippingConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.tokens.push(token);
+        Ok(())
+    }
+
+    fn skip_value(&self) -> bool {
+        true
+    }
+}
+
+#[test]
+fn test_skip_values() {
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"k\"": "v\"é", "n": [0, 12, -2.5e3, true]}"#.as_bytes()), false);
+    let mut consumer = SkippingConsumer { tokens: vec!() };
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(vec!(
+        Ok(BeginFile),
+        Ok(BeginObject),
+        Ok(Key("k\"".into())),
+        Ok(StringValue("".into())),
+        Ok(Key("n".into())),
+        Ok(BeginArray),
+        Ok(IntValue("0".into())),
+        Ok(IntValue("".into())),
+        Ok(FloatValue("".into())),
+        Ok(BooleanValue(true)),
+        Ok(EndArray),
+        Ok(EndObject),
+        Ok(EndFile),
+    ), consumer.tokens);
+}
+
+#[test]
+fn test_skip_unfinished_string() {
+    let mut parser = JSONParser::new(ByteSource::new(r#"["a\""#.as_bytes()), false);
+    let mut consumer = SkippingConsumer { tokens: vec!() };
+    let _ = parser.parse(&mut consumer);
+    assert_eq!(Err(JSONParseError { kind: ParseErrorKind::Lex(LexErrorKind::UnfinishedString), msg: "Unfinished string".into(), line: 0, column: 5 }), consumer.tokens[2]);
+}
+
+#[test]
+fn test_open_containers() {
+    let mut parser = JSONParser::new(ByteSource::new("{\"a\": [1, {}],\n \"b\": [{\"c\": 2".as_bytes()), false);
+    let mut consumer = AssertEqualsConsumer::new();
+    let _ = parser.parse(&mut consumer);
+    assert_eq!(&[
+        OpenContainer { kind: ContainerKind::Object, line: 0, column: 1 },
+        OpenContainer { kind: ContainerKind::Array, line: 1, column: 23 },
+        OpenContainer { kind: ContainerKind::Object, line: 1, column: 24 },
+    ], parser.open_containers());
+    assert_eq!(&Err(JSONParseError { kind: ParseErrorKind::UnclosedContainer, msg: "Unexpected token `Ok(EndFile)`: object opened at 1:24 not closed, array opened at 1:23 not closed, object opened at 0:1 not closed".into(),
+        line: 1, column: 30,
+    }), consumer.tokens.last().unwrap());
+}
+
+#[test]
+fn test_pull_parser_same_tokens() {
+    for i in 1..=5 {
+        let path = format!("tests/files/example{}.json", i);
+        let mut consumer = AssertEqualsConsumer::new();
+        JSONParser::new(ByteSource::new(fs::File::open(&path).unwrap()), false).parse(&mut consumer).unwrap();
+        let pull_parser = JSONPullParser::new(ByteSource::new(fs::File::open(&path).unwrap()), false);
+        assert_eq!(consumer.tokens, pull_parser.collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn test_pull_parser_suspend() {
+    let mut parser = JSONPullParser::new(ByteSource::new(r#"{"a": [1, 2], "b": "c"}"#.as_bytes()), false);
+    while let Some(token) = parser.next_event() {
+        if token == Ok(Key("b".into())) {
+            break;
+        }
+    }
+    assert_eq!(1, parser.open_containers().len());
+    assert_eq!(Some(Ok(StringValue("c".into()))), parser.next_event());
+    assert_eq!(vec!(Ok(EndObject), Ok(EndFile)), parser.collect::<Vec<_>>());
+}
+
+#[test]
+fn test_pull_parser_error() {
+    let parser = JSONPullParser::new(ByteSource::new(r#"[1, }, 2]"#.as_bytes()), false);
+    assert_eq!(vec!(
+        Ok(BeginFile),
+        Ok(BeginArray),
+        Ok(IntValue("1".into())),
+        Err(JSONParseError { kind: ParseErrorKind::UnexpectedToken(LexerToken::EndObject), msg: "Unexpected token `Ok(EndObject)`".into(), line: 0, column: 6 }),
+    ), parser.collect::<Vec<_>>());
+}
+
+struct SliceConsumer<'a> {
+    json: &'a str,
+    slices: Vec<&'a str>,
+}
+
+impl<'a> JSONParseConsumer for SliceConsumer<'a> {
+    fn consume(&mut self, _token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        panic!("consume_with_span expected")
+    }
+
+    fn consume_with_span(&mut self, _token: Result<ParserToken, JSONParseError>, span: Span) -> Result<(), ConsumeError> {
+        self.slices.push(&self.json[span.start..span.end]);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_spans() {
+    let json = r#"{"a": [1.5, "x"], "b": false}"#;
+    let mut consumer = SliceConsumer { json, slices: vec!() };
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(vec!("", "{", r#""a""#, "[", "1.5", r#""x""#, "]", r#""b""#, "false", "}", ""), consumer.slices);
+}
+
+#[test]
+fn test_pull_parser_spans() {
+    let mut parser = JSONPullParser::new(ByteSource::new("[12,\n 3]".as_bytes()), false);
+    let spans: Vec<(usize, usize, usize)> = std::iter::from_fn(|| parser.next_event_with_span())
+        .map(|(_, span)| (span.start, span.end, span.line)).collect();
+    assert_eq!(vec!((0, 0, 0), (0, 1, 0), (1, 3, 0), (6, 7, 1), (7, 8, 1), (8, 8, 1)), spans);
+}
+
+#[test]
+fn test_duplicate_keys_error() {
+    let json = r#"{"a": 1, "b": {"a": 2}, "a": 3}"#;
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false).duplicate_key_policy(DuplicateKeyPolicy::Error);
+    let mut consumer = AssertEqualsConsumer::new();
+    let _ = parser.parse(&mut consumer);
+    assert!(consumer.tokens.contains(&Err(JSONParseError { kind: ParseErrorKind::DuplicateKey("a".into()), msg: "Duplicate key `a`".into(), line: 0, column: 29 })));
+
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+    assert_eq!(Ok(()), parser.parse(&mut AssertEqualsConsumer::new()));
+}
+
+#[test]
+fn test_duplicate_keys_warn() {
+    let json = r#"[{"a": 1, "a": 2}, {"a": 3, "b": 4, "b": 5}]"#;
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false).duplicate_key_policy(DuplicateKeyPolicy::Warn);
+    let mut consumer = AssertEqualsConsumer::new();
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(vec!("Duplicate key `a`", "Duplicate key `b`"), parser.duplicate_keys().iter().map(|e| e.msg.as_str()).collect::<Vec<_>>());
+    assert_eq!(18, consumer.tokens.len());
+
+    let mut pull_parser = JSONPullParser::new(ByteSource::new(json.as_bytes()), false).duplicate_key_policy(DuplicateKeyPolicy::Warn);
+    assert_eq!(18, pull_parser.by_ref().count());
+    assert_eq!(2, pull_parser.duplicate_keys().len());
+}
+
+#[test]
+fn test_validate() {
+    let validate = |json: &str| JSONParser::validate(ByteSource::new(json.as_bytes()));
+    assert_eq!(Ok(()), validate(r#"{"a": [1, 0, -0.5e+3, "x\ty"]}"#));
+    assert_eq!(Ok(()), validate("\n[1,\n2]\n"));
+    assert_eq!(Err(JSONParseError { kind: ParseErrorKind::Lex(LexErrorKind::LeadingZero), msg: "Leading zero in `01`".into(), line: 0, column: 3 }), validate("[01]"));
+    assert_eq!("Unexpected token after the root value `Ok(IntValue(\"2\"))`", validate("1 2").unwrap_err().msg);
+    assert_eq!("Unexpected token after the root value `Ok(BeginArray)`", validate("{} []").unwrap_err().msg);
+    assert_eq!("Unescaped control char `0x0a` in string", validate("\"a\nb\"").unwrap_err().msg);
+    assert!(validate("[1, }").is_err());
+    assert!(validate("[1").is_err());
+}
+
+#[test]
+fn test_error_kinds() {
+    let kind = |json: &str| JSONParser::validate(ByteSource::new(json.as_bytes())).unwrap_err().kind;
+    assert_eq!(ParseErrorKind::ValueAfterRoot, kind("1 2"));
+    assert_eq!(ParseErrorKind::UnclosedContainer, kind("[1"));
+    assert_eq!(ParseErrorKind::UnexpectedToken(LexerToken::EndObject), kind("[1, }"));
+    assert_eq!(ParseErrorKind::Lex(LexErrorKind::ControlChar(b'\n')), kind("\"a\nb\""));
+    assert_eq!(ParseErrorKind::Lex(LexErrorKind::UnknownEscape('x')), kind(r#""\x""#));
+    assert_eq!("Leading zero in `01` at 0:3", JSONParser::validate(ByteSource::new("[01]".as_bytes())).unwrap_err().to_string());
+}
+
+#[test]
+fn test_lenient() {
+    let mut parser = JSONParser::new(ByteSource::new("1 \"a\nb\"".as_bytes()), false);
+    let mut consumer = AssertEqualsConsumer::new();
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(vec!(Ok(BeginFile), Ok(IntValue("1".into())), Ok(StringValue("a\nb".into())), Ok(EndFile)), consumer.tokens);
+}
+
+#[test]
+fn test_number_mode() {
+    let json = "[1.10, -0, 18446744073709551615, 100000000000000000000, 0.1, 1e400]";
+    let parse = |number_mode| {
+        let pull_parser = JSONPullParser::new(ByteSource::new(json.as_bytes()), false).number_mode(number_mode);
+        pull_parser.collect::<Vec<Result<ParserToken, JSONParseError>>>()
+    };
+    assert_eq!(Ok(FloatValue("1.10".into())), parse(NumberMode::PreserveLexeme)[2]);
+    assert_eq!(vec!(
+        Ok(BeginFile), Ok(BeginArray), Ok(FloatValue("1.1".into())), Ok(IntValue("0".into())), Ok(IntValue("18446744073709551615".into())),
+        Ok(FloatValue("1e20".into())), Ok(FloatValue("0.1".into())), Ok(FloatValue("inf".into())), Ok(EndArray), Ok(EndFile),
+    ), parse(NumberMode::ParseLossy));
+    let tokens = parse(NumberMode::ParseStrictOrError);
+    assert_eq!(Ok(FloatValue("1.1".into())), tokens[2]);
+    assert_eq!(6, tokens.len());
+    assert_eq!(ParseErrorKind::PrecisionLoss(PrecisionLossKind::IntegerOverflow), tokens[5].as_ref().unwrap_err().kind);
+}
+
+#[test]
+fn test_json5() {
+    let options = LexerOptions { dialect: Dialect::Json5, ..Default::default() };
+    let pull_parser = JSONPullParser::new_with_options(ByteSource::new("{a: [0x10, 2,], 'b': null,}".as_bytes()), options);
+    let tokens: Vec<Result<ParserToken, JSONParseError>> = pull_parser.collect();
+    assert_eq!(vec!(
+        Ok(BeginFile), Ok(BeginObject), Ok(Key("a".into())), Ok(BeginArray), Ok(IntValue("16".into())), Ok(IntValue("2".into())), Ok(EndArray),
+        Ok(Key("b".into())), Ok(NullValue), Ok(EndObject), Ok(EndFile),
+    ), tokens);
+}
+
+#[test]
+fn test_comments() {
+    let options = LexerOptions { allow_comments: true, emit_comments: true, ..Default::default() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new("/* a */ [1, // b\n 2]".as_bytes()), options);
+    let mut consumer = AssertEqualsConsumer::new();
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(vec!(Ok(BeginFile), Ok(BeginArray), Ok(IntValue("1".into())), Ok(IntValue("2".into())), Ok(EndArray), Ok(EndFile)), consumer.tokens);
+}
+
+#[test]
+fn test_multi_document() {
+    let json = "{\"a\": 1}\n{\"a\": [2]} 3\n";
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false).multi_document(true);
+    let mut consumer = AssertEqualsConsumer::new();
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(vec!(
+        Ok(BeginFile), Ok(BeginObject), Ok(Key("a".into())), Ok(IntValue("1".into())), Ok(EndObject), Ok(EndFile),
+        Ok(BeginFile), Ok(BeginObject), Ok(Key("a".into())), Ok(BeginArray), Ok(IntValue("2".into())), Ok(EndArray), Ok(EndObject), Ok(EndFile),
+        Ok(BeginFile), Ok(IntValue("3".into())), Ok(EndFile),
+    ), consumer.tokens);
+
+    let options = LexerOptions { strict: true, ..Default::default() };
+    let pull_parser = JSONPullParser::new_with_options(ByteSource::new("[] []".as_bytes()), options).multi_document(true);
+    let tokens: Vec<Result<ParserToken, JSONParseError>> = pull_parser.collect();
+    assert_eq!(vec!(
+        Ok(BeginFile), Ok(BeginArray), Ok(EndArray), Ok(EndFile), Ok(BeginFile), Ok(BeginArray), Ok(EndArray), Ok(EndFile),
+    ), tokens);
+}
+
+struct KeyConsumer {
+    keys: Vec<Arc<str>>,
+    tokens: usize,
+}
+
+impl JSONParseConsumer for KeyConsumer {
+    fn consume(&mut self, _token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.tokens += 1;
+        Ok(())
+    }
+
+    fn consume_interned_key(&mut self, key: Arc<str>, _span: Span) -> Result<(), ConsumeError> {
+        self.keys.push(key);
+        Ok(())
+    }
+}
+
+#[test]
+fn test_intern_keys() {
+    let json = r#"[{"servlet-name": "a", "x": 1}, {"servlet-name": "b", "x": {"x": 2}}]"#;
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false).intern_keys(true);
+    let mut consumer = KeyConsumer { keys: vec!(), tokens: 0 };
+    parser.parse(&mut consumer).unwrap();
+    let keys: Vec<&str> = consumer.keys.iter().map(|k| k.as_ref()).collect();
+    assert_eq!(vec!("servlet-name", "x", "servlet-name", "x", "x"), keys);
+    assert!(Arc::ptr_eq(&consumer.keys[0], &consumer.keys[2]));
+    assert!(Arc::ptr_eq(&consumer.keys[1], &consumer.keys[4]));
+    assert_eq!(14, consumer.tokens);
+
+    // by default, the interned keys are sent as keys
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false).intern_keys(true);
+    let mut consumer = AssertEqualsConsumer::new();
+    parser.parse(&mut consumer).unwrap();
+    let mut expected = AssertEqualsConsumer::new();
+    JSONParser::new(ByteSource::new(json.as_bytes()), false).parse(&mut expected).unwrap();
+    assert_eq!(expected.tokens, consumer.tokens);
+}
+
+fn parse_source<R: Read>(byte_source: ByteSource<R>) -> (Vec<Result<ParserToken, JSONParseError>>, usize) {
+    let mut parser = JSONParser::new(byte_source, false);
+    let mut consumer = AssertEqualsConsumer::new();
+    parser.parse(&mut consumer).unwrap();
+    (consumer.tokens, parser.byte_offset())
+}
+
+#[test]
+fn test_byte_source_buffers() {
+    let json = fs::read("tests/files/example4.json").expect("no file found");
+    let expected = parse_source(ByteSource::new(json.as_slice()));
+    assert_eq!(json.len(), expected.1);
+    for capacity in [0, 1, 2, 3, 7, 1 << 20] {
+        assert_eq!(expected, parse_source(ByteSource::with_capacity(json.as_slice(), capacity)));
+        assert_eq!(expected, parse_source(ByteSource::from_buf_read(BufReader::with_capacity(capacity.max(1), json.as_slice()))));
+    }
+    assert_eq!(expected, parse_source(ByteSource::from_buf_read(json.as_slice())));
+    assert_eq!(expected, parse_source(ByteSource::from_slice(&json)));
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_byte_source_mmap() {
+    let json = fs::read("tests/files/example4.json").expect("no file found");
+    let expected = parse_source(ByteSource::from_slice(&json));
+    let f = fs::File::open("tests/files/example4.json").expect("no file found");
+    assert_eq!(expected, parse_source(ByteSource::from_mmap(&f).unwrap()));
+}
+
+#[test]
+fn test_byte_source_peek() {
+    let json = r#"{"a": [true, null]}"#;
+    let expected = parse_source(ByteSource::new(json.as_bytes()));
+    let mut byte_source = ByteSource::with_capacity(json.as_bytes(), 2);
+    assert_eq!(Some(b'{'), byte_source.peek().unwrap());
+    assert_eq!(br#"{"a": ["#, byte_source.peek_n(7).unwrap());
+    assert_eq!(json.as_bytes(), byte_source.peek_n(100).unwrap());
+    assert_eq!(expected, parse_source(byte_source));
+
+    let mut byte_source = ByteSource::with_capacity(&json.as_bytes()[1..], 3);
+    byte_source.unget_byte(b'{');
+    assert_eq!(br#"{"a""#, byte_source.peek_n(4).unwrap());
+    let (tokens, offset) = parse_source(byte_source);
+    assert_eq!(expected.0, tokens);
+    assert_eq!(json.len(), offset);
+}
+
+/// Returns the bytes, then an error; `Interrupted` errors are returned before each read.
+struct FailingRead<'a> {
+    bytes: &'a [u8],
+    interrupted: bool,
+}
+
+impl<'a> Read for FailingRead<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.interrupted = !self.interrupted;
+        if self.interrupted {
+            return Err(std::io::Error::from(std::io::ErrorKind::Interrupted));
+        }
+        if self.bytes.is_empty() {
+            return Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset"));
+        }
+        let n = self.bytes.read(buf)?;
+        Ok(n)
+    }
+}
+
+#[test]
+fn test_read_error() {
+    let source = FailingRead { bytes: b"[1, 2", interrupted: false };
+    let mut parser = JSONParser::new(ByteSource::with_capacity(source, 2), false);
+    let mut consumer = AssertEqualsConsumer::new();
+    let e = parser.parse(&mut consumer).unwrap_err();
+    assert_eq!("read error ConnectionReset at 0:6", e.to_string());
+    assert_eq!(Some(std::io::ErrorKind::ConnectionReset), e.io_error.map(|e| e.kind()));
+    assert_eq!(vec!(
+        Ok(BeginFile), Ok(BeginArray), Ok(IntValue("1".into())),
+        Err(JSONParseError { kind: ParseErrorKind::Lex(LexErrorKind::Io(std::io::ErrorKind::ConnectionReset)), msg: "read error ConnectionReset".into(), line: 0, column: 6 }),
+    ), consumer.tokens);
+
+    let source = FailingRead { bytes: b"[true]", interrupted: false };
+    let tokens: Vec<Result<ParserToken, JSONParseError>> = JSONPullParser::new(ByteSource::new(source), false).collect();
+    assert_eq!(vec!(Ok(BeginFile), Ok(BeginArray), Ok(BooleanValue(true)), Ok(EndArray)), tokens[..4].to_vec());
+    assert_eq!(Some(ParseErrorKind::Lex(LexErrorKind::Io(std::io::ErrorKind::ConnectionReset))), tokens[4].clone().err().map(|e| e.kind));
+    assert_eq!(5, tokens.len());
+}
+
+/// A `BufRead` whose `fill_buf` fails at the `fail_at`-th call.
+struct FailingBufRead<'a> {
+    bytes: &'a [u8],
+    calls: usize,
+    fail_at: usize,
+}
+
+impl<'a> Read for FailingBufRead<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.bytes.read(buf)
+    }
+}
+
+impl<'a> BufRead for FailingBufRead<'a> {
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        self.calls += 1;
+        if self.calls == self.fail_at {
+            return Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "reset"));
+        }
+        Ok(self.bytes)
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.bytes = &self.bytes[amt..];
+    }
+}
+
+#[test]
+fn test_buf_read_error() {
+    let mut source = FailingBufRead { bytes: br#"["abcdef", true]"#, calls: 0, fail_at: 0 };
+    let mut parser = JSONParser::new(ByteSource::from_buf_read(&mut source), false);
+    parser.parse(&mut AssertEqualsConsumer::new()).unwrap();
+    // `fill_buf` is not called for every byte
+    let calls = source.calls;
+    assert!(calls <= 4, "{} calls", calls);
+
+    for fail_at in 1..=calls {
+        let source = FailingBufRead { bytes: br#"["abcdef", true]"#, calls: 0, fail_at };
+        let mut parser = JSONParser::new(ByteSource::from_buf_read(source), false);
+        let mut consumer = AssertEqualsConsumer::new();
+        let e = parser.parse(&mut consumer).unwrap_err();
+        assert_eq!(Some(std::io::ErrorKind::ConnectionReset), e.io_error.map(|e| e.kind()));
+    }
+}
+
+#[test]
+fn test_error_recovery() {
+    let json = r#"[1, }, 2, {"a": x, "b": 3}, [4 5], 6]"#;
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false).error_recovery(true);
+    let mut consumer = AssertEqualsConsumer::new();
+    parser.parse(&mut consumer).unwrap();
+    let errors = consumer.tokens.iter().filter(|t| t.is_err()).count();
+    let tokens: Vec<ParserToken> = consumer.tokens.into_iter().filter_map(|t| t.ok()).collect();
+    assert_eq!(3, errors);
+    assert_eq!(vec!(
+        BeginFile, BeginArray, IntValue("1".into()), IntValue("2".into()),
+        BeginObject, Key("a".into()), Key("b".into()), IntValue("3".into()), EndObject,
+        BeginArray, IntValue("4".into()), EndArray, IntValue("6".into()), EndArray, EndFile,
+    ), tokens);
+}
+
+#[test]
+fn test_pull_parser_error_recovery() {
+    let parser = JSONPullParser::new(ByteSource::new("[1, }, 2]".as_bytes()), false).error_recovery(true);
+    let tokens: Vec<Result<ParserToken, JSONParseError>> = parser.collect();
+    assert_eq!(7, tokens.len());
+    assert!(tokens[3].is_err());
+    assert_eq!(&[Ok(IntValue("2".into())), Ok(EndArray), Ok(EndFile)], &tokens[4..]);
+}
+
+#[test]
+fn test_feed_parser() {
+    let json = "{\"a\u{e9}\": [true, null, -12.5e3, \"\u{1f600}\"], \"b\": {\"c\": 1}}".as_bytes();
+    let mut expected = AssertEqualsConsumer::new();
+    JSONParser::new(ByteSource::new(json), false).parse(&mut expected).unwrap();
+    let mut consumer = AssertEqualsConsumer::new();
+    let mut parser = JSONFeedParser::new(LexerOptions::default());
+    for chunk in json[..json.len() - 1].chunks(1) {
+        parser.feed(chunk, &mut consumer).unwrap();
+    }
+    assert_eq!(1, parser.open_containers().len());
+    parser.feed(&json[json.len() - 1..], &mut consumer).unwrap();
+    parser.finish(&mut consumer).unwrap();
+    assert_eq!(expected.tokens, consumer.tokens);
+    assert_eq!(json.len(), parser.byte_offset());
+}
+
+#[test]
+fn test_feed_parser_unclosed() {
+    let mut consumer = AssertEqualsConsumer::new();
+    let mut parser = JSONFeedParser::new(LexerOptions::default());
+    parser.feed(b"[1, ", &mut consumer).unwrap();
+    let _ = parser.finish(&mut consumer);
+    assert_eq!(Some(ParseErrorKind::UnclosedContainer), consumer.tokens.last().cloned().and_then(|t| t.err()).map(|e| e.kind));
+}
+
+/// Done after `n` tokens.
+struct TakeConsumer {
+    tokens: Vec<ParserToken>,
+    n: usize,
+}
+
+impl JSONParseConsumer for TakeConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.tokens.push(token.unwrap());
+        Ok(())
+    }
+
+    fn is_done(&self) -> bool {
+        self.tokens.len() >= self.n
+    }
+}
+
+#[test]
+fn test_stop_parsing() {
+    let mut consumer = TakeConsumer { tokens: vec!(), n: 3 };
+    let mut parser = JSONParser::new(ByteSource::new(r#"[1, 2, }}}"#.as_bytes()), false);
+    assert!(parser.parse(&mut consumer).is_ok());
+    assert!(parser.stopped());
+    assert_eq!(vec!(BeginFile, BeginArray, IntValue("1".into())), consumer.tokens);
+
+    let mut consumer = TakeConsumer { tokens: vec!(), n: 100 };
+    let mut parser = JSONParser::new(ByteSource::new(r#"[1]"#.as_bytes()), false);
+    parser.parse(&mut consumer).unwrap();
+    assert!(!parser.stopped());
+}
+
+#[test]
+fn test_feed_parser_stop() {
+    let mut consumer = TakeConsumer { tokens: vec!(), n: 4 };
+    let mut parser = JSONFeedParser::new(LexerOptions::default());
+    parser.feed(br#"{"a": 1, "#, &mut consumer).unwrap();
+    assert!(parser.stopped());
+    parser.feed(b"]]", &mut consumer).unwrap();
+    parser.finish(&mut consumer).unwrap();
+    assert_eq!(vec!(BeginFile, BeginObject, Key("a".into()), IntValue("1".into())), consumer.tokens);
+}
+
+#[test]
+fn test_pull_parser_skip_value() {
+    let json = r#"{"a": {"b": [1, "x\ty", {"c": 123456789012345678901234567890}]}, "d": [true], "e": 3}"#;
+    let mut parser = JSONPullParser::new(ByteSource::new(json.as_bytes()), false).number_mode(NumberMode::ParseStrictOrError);
+    assert_eq!(Ok(()), parser.skip_value());
+    assert_eq!(vec!(Ok(BeginFile), Ok(BeginObject), Ok(Key("a".into())), Ok(BeginObject)), (0..4).map(|_| parser.next_event().unwrap()).collect::<Vec<_>>());
+    assert_eq!(Ok(()), parser.skip_value());
+    assert_eq!(Some(Ok(Key("d".into()))), parser.next_event());
+    assert_eq!(Some(Ok(BeginArray)), parser.next_event());
+    assert_eq!(Ok(()), parser.skip_value());
+    assert_eq!(Some(Ok(Key("e".into()))), parser.next_event());
+    assert_eq!(Ok(()), parser.skip_value());
+    assert_eq!(Some(Ok(EndFile)), parser.next_event());
+    assert_eq!(None, parser.next_event());
+}
+
+#[test]
+fn test_pull_parser_skip_value_error() {
+    let mut parser = JSONPullParser::new(ByteSource::new(r#"[[1, "a" 2], 3]"#.as_bytes()), false);
+    parser.next_event();
+    parser.next_event();
+    parser.next_event();
+    assert!(parser.skip_value().is_err());
+    assert_eq!(None, parser.next_event());
+}
+
+/// Fails on the first error.
+struct FailingConsumer;
+
+impl JSONParseConsumer for FailingConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        token?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_chunked_string_at_wrong_place() {
+    let error = |column| Err(JSONParseError { kind: ParseErrorKind::UnexpectedToken(LexerToken::StringStart), msg: "Unexpected token `Ok(StringStart)`".into(), line: 0, column });
+    for (json, expected_tokens) in [
+        (r#"[1 "abcdefghij"]"#, vec!(Ok(BeginFile), Ok(BeginArray), Ok(IntValue("1".into())), error(10), Ok(EndArray), Ok(EndFile))),
+        (r#"{"a" "abcdefghij"}"#, vec!(Ok(BeginFile), Ok(BeginObject), Ok(Key("a".into())), error(11))),
+        (r#"{"a": 1 "abcdefghij"}"#, vec!(Ok(BeginFile), Ok(BeginObject), Ok(Key("a".into())), Ok(IntValue("1".into())), error(15), Ok(EndObject), Ok(EndFile))),
+    ] {
+        let options = || LexerOptions { string_chunk_size: Some(4), ..Default::default() };
+        let mut consumer = AssertEqualsConsumer::new();
+        let mut parser = JSONParser::new_with_options(ByteSource::new(json.as_bytes()), options());
+        let _ = parser.parse(&mut consumer);
+        assert_eq!(expected_tokens, consumer.tokens[..expected_tokens.len()].to_vec());
+        assert!(JSONParser::new_with_options(ByteSource::new(json.as_bytes()), options()).parse(&mut FailingConsumer).is_err());
+    }
+}
@@ -0,0 +1,101 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_patch::{apply_merge_patch, apply_patch, JSONPatch, PatchOperation};
+use r_json_event_parser::json_value::JsonValue;
+use r_json_event_parser::json_writer::JSONWriteConsumer;
+
+fn patch(document: &str, patch: &str) -> Result<String, String> {
+    let patch = JSONPatch::parse(patch).map_err(|e| e.msg)?;
+    let mut consumer = JSONWriteConsumer::new(vec!());
+    apply_patch(ByteSource::new(document.as_bytes()), &patch, &mut consumer).map_err(|e| e.msg)?;
+    Ok(String::from_utf8(consumer.into_inner()).unwrap())
+}
+
+fn merge(document: &str, patch: &str) -> String {
+    let patch = JsonValue::parse(patch).unwrap();
+    let mut consumer = JSONWriteConsumer::new(vec!());
+    apply_merge_patch(ByteSource::new(document.as_bytes()), &patch, &mut consumer).unwrap();
+    String::from_utf8(consumer.into_inner()).unwrap()
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(vec!(
+        PatchOperation::Remove { path: "/a".into() },
+        PatchOperation::Move { from: "/b".into(), path: "/c".into() },
+    ), JSONPatch::parse(r#"[{"op": "remove", "path": "/a"}, {"op": "move", "from": "/b", "path": "/c"}]"#).unwrap().operations);
+    assert_eq!("Unknown operation `delete`", JSONPatch::parse(r#"[{"op": "delete", "path": "/a"}]"#).unwrap_err().msg);
+    assert_eq!("Missing `value`", JSONPatch::parse(r#"[{"op": "add", "path": "/a"}]"#).unwrap_err().msg);
+}
+
+#[test]
+fn test_add() {
+    assert_eq!(Ok(r#"{"a":1,"b":{"c":[true]}}"#.into()), patch(r#"{"a": 1}"#, r#"[{"op": "add", "path": "/b", "value": {"c": [true]}}]"#));
+    assert_eq!(Ok(r#"{"a":2}"#.into()), patch(r#"{"a": [1]}"#, r#"[{"op": "add", "path": "/a", "value": 2}]"#));
+    assert_eq!(Ok(r#"[1,"x",2]"#.into()), patch("[1, 2]", r#"[{"op": "add", "path": "/1", "value": "x"}]"#));
+    assert_eq!(Ok(r#"[1,2,"x","y"]"#.into()), patch("[1, 2]", r#"[{"op": "add", "path": "/-", "value": "x"}, {"op": "add", "path": "/3", "value": "y"}]"#));
+    assert_eq!(Ok("null".into()), patch("[1, 2]", r#"[{"op": "add", "path": "", "value": null}]"#));
+}
+
+#[test]
+fn test_remove_replace() {
+    assert_eq!(Ok(r#"{"b":[1,3]}"#.into()), patch(r#"{"a": 0, "b": [1, 2, 3]}"#, r#"[{"op": "remove", "path": "/a"}, {"op": "remove", "path": "/b/1"}]"#));
+    assert_eq!(Ok(r#"{"a":{"b":[]}}"#.into()), patch(r#"{"a": {"b": {"c": 1}}}"#, r#"[{"op": "replace", "path": "/a/b", "value": []}]"#));
+}
+
+#[test]
+fn test_sequence() {
+    // the second operation sees the result of the first one
+    assert_eq!(Ok(r#"[0,2]"#.into()), patch("[1, 2]", r#"[{"op": "add", "path": "/0", "value": 0}, {"op": "remove", "path": "/1"}]"#));
+}
+
+#[test]
+fn test_test() {
+    assert_eq!(Ok(r#"{"a":{"x":1,"y":2.0}}"#.into()), patch(r#"{"a": {"x": 1, "y": 2.0}}"#, r#"[{"op": "test", "path": "/a", "value": {"y": 2, "x": 1}}]"#));
+    assert_eq!(Err("Operation 0 (test `/a/x`): test failed".into()), patch(r#"{"a": {"x": 1}}"#, r#"[{"op": "test", "path": "/a/x", "value": "1"}]"#));
+}
+
+#[test]
+fn test_move_copy() {
+    assert_eq!(Ok(r#"{"b":2,"c":{"d":1}}"#.into()), patch(r#"{"a": {"d": 1}, "b": 2}"#, r#"[{"op": "move", "from": "/a", "path": "/c"}]"#));
+    // the destination comes before the source
+    assert_eq!(Ok(r#"{"a":[3,1],"b":[2]}"#.into()), patch(r#"{"a": [1], "b": [2, 3]}"#, r#"[{"op": "move", "from": "/b/1", "path": "/a/0"}]"#));
+    assert_eq!(Ok(r#"{"a":{"x":[1]},"b":{"x":[1]}}"#.into()), patch(r#"{"a": {"x": [1]}, "b": 0}"#, r#"[{"op": "copy", "from": "/a", "path": "/b"}]"#));
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(Err("Operation 0 (remove `/b`): path not found".into()), patch(r#"{"a": 1}"#, r#"[{"op": "remove", "path": "/b"}]"#));
+    assert_eq!(Err("Operation 0 (add `/x/y`): path not found".into()), patch(r#"{"a": 1}"#, r#"[{"op": "add", "path": "/x/y", "value": 1}]"#));
+    assert_eq!(Err("Operation 0 (add `/3`): index out of bounds".into()), patch("[1]", r#"[{"op": "add", "path": "/3", "value": 1}]"#));
+    assert_eq!(Err("Operation 0 (move `/a/b`): can't move a value into itself".into()), patch(r#"{"a": {}}"#, r#"[{"op": "move", "from": "/a", "path": "/a/b"}]"#));
+}
+
+#[test]
+fn test_merge_patch() {
+    assert_eq!(r#"{"a":"z","c":{"d":"e"}}"#, merge(r#"{"a": "b", "c": {"d": "e", "f": "g"}}"#, r#"{"a": "z", "c": {"f": null}}"#));
+    assert_eq!(r#"{"a":[1],"b":{"c":1}}"#, merge(r#"{"a": {"x": 1}, "b": 0}"#, r#"{"a": [1], "b": {"c": 1, "d": null}}"#));
+    assert_eq!(r#"{"a":1,"b":{"c":2}}"#, merge(r#"{"a": 1}"#, r#"{"b": {"c": 2, "d": null}}"#));
+    assert_eq!(r#"[3]"#, merge(r#"{"a": 1}"#, "[3]"));
+    assert_eq!(r#"{"b":1}"#, merge(r#"[{"a": 1}]"#, r#"{"b": 1, "c": null}"#));
+}
@@ -0,0 +1,87 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::LexerOptions;
+use r_json_event_parser::json_parser::JSONParser;
+use r_json_event_parser::json_path::{JSONPath, JSONPathConsumer, select};
+use r_json_event_parser::json_pointer::PathElement;
+use r_json_event_parser::json_value::JsonValue;
+
+const STORE: &str = r#"{"store": {
+    "book": [
+        {"author": "Nigel Rees", "title": "Sayings of the Century", "price": 8.95},
+        {"author": "Evelyn Waugh", "title": "Sword of Honour", "price": 12.99}
+    ],
+    "bicycle": {"color": "red", "price": 19.95}
+}}"#;
+
+fn values(expression: &str) -> Vec<JsonValue> {
+    let path = JSONPath::compile(expression).unwrap();
+    select(ByteSource::new(STORE.as_bytes()), &path).unwrap().into_iter().map(|(_, v)| v).collect()
+}
+
+#[test]
+fn test_child_and_wildcard() {
+    assert_eq!(vec!(JsonValue::String("Nigel Rees".into()), JsonValue::String("Evelyn Waugh".into())),
+               values("$.store.book[*].author"));
+    assert_eq!(vec!(JsonValue::String("Sword of Honour".into())), values("$['store'][\"book\"][1].title"));
+    assert_eq!(vec!(JsonValue::String("red".into())), values("$.store.*.color"));
+    assert_eq!(1, values("$").len());
+}
+
+#[test]
+fn test_recursive_descent() {
+    assert_eq!(vec!(JsonValue::Float("8.95".into()), JsonValue::Float("12.99".into()), JsonValue::Float("19.95".into())),
+               values("$..price"));
+    assert_eq!(vec!(JsonValue::String("Evelyn Waugh".into())), values("$..[1].author"));
+}
+
+#[test]
+fn test_nested_matches() {
+    let path = JSONPath::compile("$..a").unwrap();
+    let selected = select(ByteSource::new(r#"{"a": {"a": 1}}"#.as_bytes()), &path).unwrap();
+    assert_eq!(vec!(
+        (vec!(PathElement::Key("a".into()), PathElement::Key("a".into())), JsonValue::Int("1".into())),
+        (vec!(PathElement::Key("a".into())), JsonValue::parse(r#"{"a": 1}"#).unwrap()),
+    ), selected);
+}
+
+#[test]
+fn test_compile_errors() {
+    assert!(JSONPath::compile("store").is_err());
+    assert!(JSONPath::compile("$.store[").is_err());
+    assert!(JSONPath::compile("$.store[x]").is_err());
+    assert!(JSONPath::compile("$.").is_err());
+}
+
+#[test]
+fn test_chunked_strings() {
+    let mut selected = vec!();
+    let mut consumer = JSONPathConsumer::new(JSONPath::compile("$.store.book[*].author").unwrap(), |path, value| selected.push((path.to_vec(), value)));
+    let options = LexerOptions { string_chunk_size: Some(4), ..Default::default() };
+    JSONParser::new_with_options(ByteSource::new(STORE.as_bytes()), options).parse(&mut consumer).unwrap();
+    let book = |i| vec!(PathElement::Key("store".into()), PathElement::Key("book".into()), PathElement::Index(i), PathElement::Key("author".into()));
+    assert_eq!(vec!(
+        (book(0), JsonValue::String("Nigel Rees".into())),
+        (book(1), JsonValue::String("Evelyn Waugh".into())),
+    ), selected);
+}
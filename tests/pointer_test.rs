@@ -0,0 +1,79 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::{ConsumeError, LexerOptions};
+use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use r_json_event_parser::json_pointer::{JSONPointer, PathElement, PathTracker, token_matches};
+
+/// The path after each token.
+struct PathConsumer {
+    tracker: PathTracker,
+    paths: Vec<(ParserToken, Vec<PathElement>)>,
+}
+
+impl JSONParseConsumer for PathConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        let token = token.unwrap();
+        self.tracker.update(&token);
+        self.paths.push((token, self.tracker.path().to_vec()));
+        Ok(())
+    }
+}
+
+#[test]
+fn test_tracker_chunked_string() {
+    let options = LexerOptions { string_chunk_size: Some(4), ..Default::default() };
+    let mut consumer = PathConsumer { tracker: PathTracker::new(), paths: vec!() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new(r#"{"a": ["x", "abcdefghij", 3]}"#.as_bytes()), options);
+    parser.parse(&mut consumer).unwrap();
+    let a = || PathElement::Key("a".into());
+    assert_eq!(vec!(
+        (ParserToken::BeginFile, vec!()),
+        (ParserToken::BeginObject, vec!()),
+        (ParserToken::Key("a".into()), vec!(a())),
+        (ParserToken::BeginArray, vec!(a())),
+        (ParserToken::StringValue("x".into()), vec!(a(), PathElement::Index(0))),
+        (ParserToken::StringStart, vec!(a(), PathElement::Index(1))),
+        (ParserToken::StringChunk(b"abcd".to_vec()), vec!(a(), PathElement::Index(1))),
+        (ParserToken::StringChunk(b"efgh".to_vec()), vec!(a(), PathElement::Index(1))),
+        (ParserToken::StringChunk(b"ij".to_vec()), vec!(a(), PathElement::Index(1))),
+        (ParserToken::StringEnd, vec!(a(), PathElement::Index(1))),
+        (ParserToken::IntValue("3".into()), vec!(a(), PathElement::Index(2))),
+        (ParserToken::EndArray, vec!(a())),
+        (ParserToken::EndObject, vec!()),
+        (ParserToken::EndFile, vec!()),
+    ), consumer.paths);
+}
+
+#[test]
+fn test_pattern() {
+    let pointer = JSONPointer::parse("/a/*/b").unwrap();
+    let path = vec!(PathElement::Key("a".into()), PathElement::Index(2), PathElement::Key("b".into()));
+    assert!(pointer.matches_pattern(&path));
+    assert!(pointer.is_pattern_prefix_of(&path));
+    assert!(!pointer.matches(&path));
+    assert!(!pointer.matches_pattern(&path[..2]));
+    assert!(JSONPointer::parse("/a/*").unwrap().is_pattern_prefix_of(&path));
+    assert!(token_matches("*", &PathElement::Key("x".into())));
+    assert!(token_matches("2", &PathElement::Index(2)));
+    assert!(!token_matches("2", &PathElement::Key("x".into())));
+}
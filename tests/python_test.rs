@@ -0,0 +1,69 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#![cfg(feature = "python")]
+
+use std::ffi::CString;
+
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use r_json_event_parser::json_python::{JSONParseError, PyJSONLexer, PyJSONParser};
+
+fn run(code: &str) -> PyResult<String> {
+    Python::with_gil(|py| {
+        let locals = PyDict::new(py);
+        locals.set_item("JSONLexer", py.get_type::<PyJSONLexer>())?;
+        locals.set_item("JSONParser", py.get_type::<PyJSONParser>())?;
+        locals.set_item("JSONParseError", py.get_type::<JSONParseError>())?;
+        py.run(&CString::new(code).unwrap(), None, Some(&locals))?;
+        locals.get_item("result")?.unwrap().extract()
+    })
+}
+
+#[test]
+fn test_python_parser() {
+    pyo3::prepare_freethreaded_python();
+    assert_eq!(
+        "[('begin_file', None), ('begin_object', None), ('key', 'a'), ('begin_array', None), \
+('int', 1), ('int', 123456789012345678901234567890), ('float', 1.5), ('boolean', True), \
+('null', None), ('string', 'é'), ('end_array', None), ('end_object', None), ('end_file', None)]",
+        run(r#"result = repr(list(JSONParser(b'{"a": [1, 123456789012345678901234567890, 1.5, true, null, "\\u00e9"]}')))"#).unwrap()
+    );
+}
+
+#[test]
+fn test_python_file() {
+    pyo3::prepare_freethreaded_python();
+    assert_eq!(
+        "[('begin_file', None), ('begin_array', None), ('int', 1), ('value_separator', None), ('string', 'x'), ('end_array', None), ('end_file', None)]",
+        run("import io\nresult = repr(list(JSONLexer(io.BytesIO(b'[1, \"x\"]'))))").unwrap()
+    );
+}
+
+#[test]
+fn test_python_error() {
+    pyo3::prepare_freethreaded_python();
+    assert_eq!(
+        "ValueError 3",
+        run("events = []\ntry:\n    for e in JSONParser('[1, }'):\n        events.append(e)\nexcept JSONParseError as e:\n    result = f'{type(e).__base__.__name__} {len(events)}'").unwrap()
+    );
+}
@@ -0,0 +1,91 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_parser::ParserToken::{BeginArray, BeginObject, EndArray, EndObject, IntValue, Key, StringValue};
+use r_json_event_parser::json_pointer::{JSONPointer, JSONPointerError, PathElement};
+use r_json_event_parser::json_query::{find_first, find_first_at, first_value, Found, get_number_at, get_string_at, JsonScalar};
+
+#[test]
+fn test_pointer() {
+    assert_eq!(vec!["a/b".to_string(), "m~n".into(), "".into()], JSONPointer::parse("/a~1b/m~0n/").unwrap().tokens());
+    assert!(JSONPointer::parse("").unwrap().tokens().is_empty());
+    assert_eq!(Err(JSONPointerError { msg: "Pointer should start with a `/`: `a`".into() }), JSONPointer::parse("a"));
+    assert_eq!(Err(JSONPointerError { msg: "Wrong escape sequence in `a~2`".into() }), JSONPointer::parse("/a~2"));
+}
+
+#[test]
+fn test_find_first_scalar() {
+    let pointer = JSONPointer::parse("/a/1").unwrap();
+    let json = r#"{"a": [1, "x", 3], "b": 4}"#;
+    assert_eq!(Ok(Some(Found { tokens: vec!(StringValue("x".into())), offset: 13 })),
+               find_first_at(ByteSource::new(json.as_bytes()), &pointer));
+}
+
+#[test]
+fn test_find_first_container() {
+    let pointer = JSONPointer::parse("/a/2").unwrap();
+    let json = r#"{"a": [1, [2], {"b": [3]}], "c": {}}"#;
+    assert_eq!(Ok(Some(Found {
+        tokens: vec!(BeginObject, Key("b".into()), BeginArray, IntValue("3".into()), EndArray, EndObject),
+        offset: 25,
+    })), find_first_at(ByteSource::new(json.as_bytes()), &pointer));
+}
+
+#[test]
+fn test_find_first_predicate() {
+    let json = r#"[{"id": 1}, {"id": 2, "name": "n"}]"#;
+    assert_eq!(Ok(Some(Found { tokens: vec!(StringValue("n".into())), offset: 33 })),
+               find_first(ByteSource::new(json.as_bytes()), |path| path.last() == Some(&PathElement::Key("name".into()))));
+}
+
+#[test]
+fn test_find_first_not_found() {
+    let pointer = JSONPointer::parse("/z").unwrap();
+    assert_eq!(Ok(None), find_first_at(ByteSource::new(r#"{"a": 1}"#.as_bytes()), &pointer));
+}
+
+#[test]
+fn test_find_first_stops_before_error() {
+    let pointer = JSONPointer::parse("/a").unwrap();
+    assert_eq!(Ok(Some(Found { tokens: vec!(IntValue("1".into())), offset: 8 })),
+               find_first_at(ByteSource::new(r#"{"a": 1, ]]]"#.as_bytes()), &pointer));
+}
+
+#[test]
+fn test_first_value() {
+    let json = r#"{"a": {"b": [1.5e1, "x", true, null, {}]}}"#;
+    assert_eq!(Some(JsonScalar::Number("1.5e1".into())), first_value(json.as_bytes(), "/a/b/0").unwrap());
+    assert_eq!(Some(JsonScalar::Bool(true)), first_value(json.as_bytes(), "/a/b/2").unwrap());
+    assert_eq!(Some(JsonScalar::Null), first_value(json.as_bytes(), "/a/b/3").unwrap());
+    assert_eq!(None, first_value(json.as_bytes(), "/a/b/4").unwrap());
+    assert_eq!(None, first_value(json.as_bytes(), "/a/c").unwrap());
+    assert!(first_value(json.as_bytes(), "a").is_err());
+}
+
+#[test]
+fn test_get_at() {
+    // the parsing stops before the syntax error
+    let json = r#"{"a": {"b": [15, "x"]}, ]"#;
+    assert_eq!(Some(15.0), get_number_at(json.as_bytes(), "/a/b/0").unwrap());
+    assert_eq!(Some("x".to_string()), get_string_at(json.as_bytes(), "/a/b/1").unwrap());
+    assert_eq!(None, get_string_at(json.as_bytes(), "/a/b/0").unwrap());
+}
@@ -0,0 +1,63 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_parser::JSONParser;
+use r_json_event_parser::json_pointer::PathElement;
+use r_json_event_parser::json_redact::{RedactConsumer, RedactPattern};
+use r_json_event_parser::json_writer::JSONWriteConsumer;
+
+fn redact(json: &str, patterns: &[&str]) -> String {
+    let patterns = patterns.iter().map(|p| RedactPattern::parse(p).unwrap()).collect();
+    let mut writer = JSONWriteConsumer::new(vec!());
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+    parser.parse(&mut RedactConsumer::new(&mut writer, patterns)).unwrap();
+    String::from_utf8(writer.into_inner()).unwrap()
+}
+
+#[test]
+fn test_pattern() {
+    let path = vec!(PathElement::Key("users".into()), PathElement::Index(2), PathElement::Key("ssn".into()));
+    assert!(RedactPattern::parse("ssn").unwrap().matches(&path));
+    assert!(RedactPattern::parse("*.ssn").unwrap().matches(&path));
+    assert!(RedactPattern::parse("users.2.ssn").unwrap().matches(&path));
+    assert!(RedactPattern::parse("/users/*/ssn").unwrap().matches(&path));
+    assert!(!RedactPattern::parse("/*/ssn").unwrap().matches(&path));
+    assert!(!RedactPattern::parse("*.*.*.ssn").unwrap().matches(&path));
+    assert!(!RedactPattern::parse("*.token").unwrap().matches(&[PathElement::Key("token".into())]));
+    assert!(RedactPattern::parse("").is_err());
+    assert!(RedactPattern::parse("/a~2").is_err());
+}
+
+#[test]
+fn test_redact() {
+    assert_eq!(r#"{"password":"***","a":{"token":"***","b":[1,"***"]},"token":"t"}"#,
+               redact(r#"{"password": {"x": [1]}, "a": {"token": "s3cr3t", "b": [1, 2]}, "token": "t"}"#, &["password", "*.token", "/a/b/1"]));
+    assert_eq!(r#"[{"ssn":"***"},{"ssn":"***","id":2}]"#, redact(r#"[{"ssn": "1"}, {"ssn": null, "id": 2}]"#, &["/*/ssn"]));
+}
+
+#[test]
+fn test_mask() {
+    let mut writer = JSONWriteConsumer::new(vec!());
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"key": "é"}"#.as_bytes()), false);
+    parser.parse(&mut RedactConsumer::new(&mut writer, vec!(RedactPattern::parse("key").unwrap())).mask("<redacted>")).unwrap();
+    assert_eq!(br#"{"key":"<redacted>"}"#.to_vec(), writer.into_inner());
+}
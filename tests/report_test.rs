@@ -0,0 +1,115 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::collections::BTreeSet;
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::LexerOptions;
+use r_json_event_parser::json_parser::JSONParser;
+use r_json_event_parser::json_pointer::JSONPointer;
+use r_json_event_parser::json_report::{JSONStatsConsumer, KeyUsage, KeyUsageConsumer, ValueFrequencyConsumer, ValueFrequencyReport, ValueType};
+
+#[test]
+fn test_key_usage() {
+    let mut consumer = KeyUsageConsumer::new(2);
+    for record in [r#"{"id": 1, "tags": ["a"], "meta": {"id": "x"}}"#, r#"{"id": 2.5, "tags": null}"#, r#"{"id": 3}"#].iter() {
+        let mut parser = JSONParser::new(ByteSource::new(record.as_bytes()), false);
+        parser.parse(&mut consumer).unwrap();
+    }
+    let report = consumer.into_report();
+    assert_eq!(vec!("id", "meta", "tags"), report.keys.keys().collect::<Vec<&String>>());
+    assert_eq!(&KeyUsage {
+        count: 4,
+        types: [ValueType::String, ValueType::Int, ValueType::Float].iter().cloned().collect::<BTreeSet<ValueType>>(),
+        example_paths: vec!("/id".into(), "/meta/id".into()),
+    }, report.keys.get("id").unwrap());
+    assert_eq!("id\t4\tstring|int|float\t/id /meta/id
+meta\t1\tobject\t/meta
+tags\t2\tarray|null\t/tags
+", report.to_string());
+}
+
+#[test]
+fn test_value_frequency() {
+    let mut consumer = ValueFrequencyConsumer::new(JSONPointer::parse("/level").unwrap());
+    let records = [r#"{"level": "info", "msg": "a"}"#, r#"{"level": "warn"}"#, r#"{"level": "info"}"#,
+        r#"{"msg": {"level": "error"}}"#, r#"{"level": "debug"}"#, r#"{"level": "info"}"#, r#"{"level": "warn"}"#];
+    for record in records.iter() {
+        let mut parser = JSONParser::new(ByteSource::new(record.as_bytes()), false);
+        parser.parse(&mut consumer).unwrap();
+    }
+    let report = consumer.report(2);
+    assert_eq!(ValueFrequencyReport {
+        values: vec!((ValueType::String, "info".into(), 3), (ValueType::String, "warn".into(), 2)),
+        others: 1,
+        total: 6,
+    }, report);
+    assert_eq!("3\tinfo\n2\twarn\n1\t(others)\n", report.to_string());
+}
+
+#[test]
+fn test_stats() {
+    let mut consumer = JSONStatsConsumer::new();
+    let json = r#"{"a": [1, -2.5e1, {"b": "xyz", "c": []}], "b": [true, null, 100, "é"]}"#;
+    JSONParser::new(ByteSource::new(json.as_bytes()), false).parse(&mut consumer).unwrap();
+    let report = consumer.into_report();
+    assert_eq!(2, report.objects);
+    assert_eq!(3, report.arrays);
+    assert_eq!(4, report.max_depth);
+    assert_eq!(5, report.string_bytes);
+    assert_eq!(Some("-2.5e1".to_string()), report.min_number);
+    assert_eq!(Some("100".to_string()), report.max_number);
+    assert_eq!(vec!(("a", 1), ("b", 2), ("c", 1)), report.keys.iter().map(|(k, c)| (k.as_str(), *c)).collect::<Vec<_>>());
+    assert_eq!(Some(&2), report.token_counts.get("IntValue"));
+    assert_eq!(Some(&4), report.token_counts.get("Key"));
+    assert_eq!("objects\t2\narrays\t3\nmax depth\t4\nstring bytes\t5\nmin number\t-2.5e1\nmax number\t100\n",
+        report.to_string().lines().take(6).map(|l| format!("{}\n", l)).collect::<String>());
+}
+
+#[test]
+fn test_stats_error() {
+    let mut consumer = JSONStatsConsumer::new();
+    assert!(JSONParser::new(ByteSource::new("[1, }".as_bytes()), false).parse(&mut consumer).is_err());
+    assert_eq!(1, consumer.report().arrays);
+}
+
+#[test]
+fn test_chunked_strings() {
+    let json = r#"{"level": "information", "tags": ["a long tag", "b"], "n": 1}"#;
+    let options = || LexerOptions { string_chunk_size: Some(4), ..Default::default() };
+    let mut key_usage = KeyUsageConsumer::new(1);
+    JSONParser::new_with_options(ByteSource::new(json.as_bytes()), options()).parse(&mut key_usage).unwrap();
+    let report = key_usage.into_report();
+    assert_eq!(vec!("level", "n", "tags"), report.keys.keys().collect::<Vec<&String>>());
+    assert_eq!(&KeyUsage {
+        count: 1,
+        types: [ValueType::String].iter().cloned().collect::<BTreeSet<ValueType>>(),
+        example_paths: vec!("/level".into()),
+    }, report.keys.get("level").unwrap());
+
+    let mut frequency = ValueFrequencyConsumer::new(JSONPointer::parse("/tags/0").unwrap());
+    JSONParser::new_with_options(ByteSource::new(json.as_bytes()), options()).parse(&mut frequency).unwrap();
+    assert_eq!(ValueFrequencyReport {
+        values: vec!((ValueType::String, "a long tag".into(), 1)),
+        others: 0,
+        total: 1,
+    }, frequency.report(5));
+}
@@ -0,0 +1,107 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#![cfg(feature = "schema")]
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::{LexerOptions, ConsumeError};
+use r_json_event_parser::json_parser::JSONParser;
+use r_json_event_parser::json_schema::{JSONSchema, SchemaError, SchemaValidatorConsumer, SchemaViolation};
+
+const SCHEMA: &str = r#"{
+    "type": "object",
+    "required": ["id", "name"],
+    "properties": {
+        "id": {"type": "integer", "minimum": 1},
+        "name": {"type": "string", "minLength": 2, "maxLength": 5, "pattern": "^[a-z]+$"},
+        "kind": {"enum": ["a", "b", 3]},
+        "score": {"type": ["number", "null"], "exclusiveMaximum": 10},
+        "tags": {"type": "array", "items": {"type": "string"}, "maxItems": 2},
+        "meta": {"type": "object", "additionalProperties": false, "properties": {"x": true}}
+    }
+}"#;
+
+fn validate(json: &str) -> Vec<(String, String)> {
+    let schema = JSONSchema::parse(SCHEMA).unwrap();
+    schema.validate(ByteSource::new(json.as_bytes())).unwrap().into_iter().map(|v| (v.path, v.msg)).collect()
+}
+
+#[test]
+fn test_valid() {
+    assert!(validate(r#"{"id": 1, "name": "ab", "kind": 3.0, "score": null, "tags": ["x"], "meta": {"x": [1]}, "other": {}}"#).is_empty());
+    assert!(validate(r#"{"id": 2.0, "name": "abcde", "kind": "b", "score": 9.5}"#).is_empty());
+}
+
+#[test]
+fn test_violations() {
+    assert_eq!(vec!(
+        ("/id".to_string(), "Expected integer".to_string()),
+        ("/name".into(), "Shorter than 2 chars".into()),
+        ("/name".into(), "Does not match the pattern `^[a-z]+$`".into()),
+        ("/kind".into(), "Value not in the enum".into()),
+        ("/score".into(), "Not less than the exclusive maximum 10".into()),
+        ("/tags/1".into(), "Expected string".into()),
+        ("/tags".into(), "More than 2 items".into()),
+        ("/meta/y".into(), "No value is allowed here".into()),
+    ), validate(r#"{"id": "1", "name": "A", "kind": "c", "score": 10, "tags": ["x", 1, "y"], "meta": {"x": 1, "y": 2}}"#));
+}
+
+#[test]
+fn test_required_and_root_type() {
+    assert_eq!(vec!(
+        ("".to_string(), "Missing required property `id`".to_string()),
+        ("".into(), "Missing required property `name`".into()),
+    ), validate("{}"));
+    assert_eq!(vec!(("".to_string(), "Expected object".to_string())), validate("[1]"));
+}
+
+#[test]
+fn test_position() {
+    let schema = JSONSchema::parse(r#"{"items": {"maximum": 3}}"#).unwrap();
+    let violations = schema.validate(ByteSource::new("[1,\n 5]".as_bytes())).unwrap();
+    assert_eq!(vec!(SchemaViolation { path: "/1".into(), msg: "Greater than the maximum 3".into(), line: 1, column: 8 }), violations);
+}
+
+#[test]
+fn test_chunked_strings() {
+    let schema = JSONSchema::parse(r#"{"items": {"maxLength": 10, "enum": ["abcdefgh"]}}"#).unwrap();
+    let options = LexerOptions { string_chunk_size: Some(3), ..Default::default() };
+    let mut consumer = SchemaValidatorConsumer::new(&schema);
+    JSONParser::new_with_options(ByteSource::new(r#"["abcdefgh", "abcdefghijk"]"#.as_bytes()), options).parse(&mut consumer).unwrap();
+    assert_eq!(vec!("Value not in the enum", "Longer than 10 chars"), consumer.violations().iter().map(|v| v.msg.as_str()).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_fail_fast() {
+    let schema = JSONSchema::parse(r#"{"items": {"type": "integer"}}"#).unwrap();
+    let mut consumer = SchemaValidatorConsumer::new(&schema).fail_fast();
+    let result = JSONParser::new(ByteSource::new("[1, true, null]".as_bytes()), false).parse(&mut consumer);
+    assert_eq!(Err(ConsumeError { msg: "/1: Expected integer".into(), line: 0, column: 9, io_error: None }), result);
+    assert_eq!(1, consumer.violations().len());
+}
+
+#[test]
+fn test_schema_errors() {
+    assert_eq!(Err(SchemaError { msg: "Unknown type `text`".into() }), JSONSchema::parse(r#"{"type": "text"}"#).map(|_| ()));
+    assert_eq!(Err(SchemaError { msg: "`minLength` must be a non-negative integer".into() }), JSONSchema::parse(r#"{"minLength": -1}"#).map(|_| ()));
+    assert!(JSONSchema::parse(r#"{"pattern": "("}"#).is_err());
+    assert!(JSONSchema::parse("[]").is_err());
+}
@@ -0,0 +1,191 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+#![cfg(feature = "serde")]
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::LexerOptions;
+use r_json_event_parser::json_lexer::ConsumeError;
+use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParseError, JSONPullParser, ParseErrorKind, ParserToken};
+use r_json_event_parser::json_parser::ParserToken::*;
+use r_json_event_parser::json_serde::{DeError, DeErrorKind, Deserializer, from_str, to_consumer};
+use r_json_event_parser::json_writer::JSONWriteConsumer;
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Point {
+    x: i32,
+    y: f64,
+    label: Option<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+enum Shape {
+    Empty,
+    Circle(f32),
+    Segment(Point, Point),
+    Rect { w: u8, h: u8 },
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Drawing {
+    name: String,
+    shapes: Vec<Shape>,
+    #[serde(default)]
+    tags: HashMap<u32, bool>,
+    pair: (u64, i128),
+}
+
+#[test]
+fn deserialize_values() {
+    let json = r#"{"name": "d", "ignored": [1, {"a": null}], "shapes": ["Empty", {"Circle": 1.5},
+        {"Segment": [{"x": 1, "y": 2, "label": null}, {"x": -1, "y": 2.5, "label": "b"}]}, {"Rect": {"w": 2, "h": 3}}],
+        "tags": {"1": true, "20": false}, "pair": [18446744073709551615, -170141183460469231731687303715884105728]}"#;
+    assert_eq!(Ok(Drawing {
+        name: "d".into(),
+        shapes: vec!(
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Segment(Point { x: 1, y: 2.0, label: None }, Point { x: -1, y: 2.5, label: Some("b".into()) }),
+            Shape::Rect { w: 2, h: 3 },
+        ),
+        tags: vec!((1, true), (20, false)).into_iter().collect(),
+        pair: (u64::MAX, i128::MIN),
+    }), from_str(json));
+    assert_eq!(Ok(vec!(Some(1u8), None)), from_str("[1, null]"));
+}
+
+#[test]
+fn deserialize_string_chunks() {
+    let options = LexerOptions { string_chunk_size: Some(4), ..Default::default() };
+    let parser = JSONPullParser::new_with_options(ByteSource::new(r#"["a long string", "é€😀"]"#.as_bytes()), options);
+    let mut deserializer = Deserializer::from_pull_parser(parser);
+    let value: Vec<String> = Deserialize::deserialize(&mut deserializer).unwrap();
+    assert_eq!(vec!("a long string".to_string(), "é€😀".to_string()), value);
+    assert_eq!(Ok(()), deserializer.end());
+}
+
+#[test]
+fn deserialize_array_elements() {
+    let json = r#"[{"x": 1, "y": 1, "label": "a"}, {"x": 2, "y": 0.5, "label": null}]"#;
+    let deserializer = Deserializer::new(ByteSource::new(json.as_bytes()));
+    let points: Vec<Result<Point, DeError>> = deserializer.array_elements().collect();
+    assert_eq!(vec!(Ok(Point { x: 1, y: 1.0, label: Some("a".into()) }), Ok(Point { x: 2, y: 0.5, label: None })), points);
+
+    let json = r#"[{"x": 1, "y": 1}, {"x": "2", "y": 0.5}, {"x": 3, "y": 3}]"#;
+    let deserializer = Deserializer::new(ByteSource::new(json.as_bytes()));
+    let points: Vec<Result<Point, DeError>> = deserializer.array_elements().collect();
+    assert_eq!(2, points.len());
+    assert_eq!(Ok(Point { x: 1, y: 1.0, label: None }), points[0]);
+    let err = points[1].as_ref().unwrap_err();
+    assert_eq!(DeErrorKind::Custom, err.kind);
+    assert_eq!("invalid type: string \"2\", expected i32", err.msg);
+
+    let deserializer = Deserializer::new(ByteSource::new("{}".as_bytes()));
+    let elements: Vec<Result<Point, DeError>> = deserializer.array_elements().collect();
+    assert_eq!(DeErrorKind::UnexpectedToken, elements[0].as_ref().unwrap_err().kind);
+}
+
+#[test]
+fn deserialize_errors() {
+    let err = from_str::<Point>(r#"{"x": 1}"#).unwrap_err();
+    assert_eq!((DeErrorKind::Custom, "missing field `y`".to_string(), 0, 9), (err.kind, err.msg, err.line, err.column));
+    let err = from_str::<(u8, u8)>("[1, 2, 3]").unwrap_err();
+    assert_eq!((DeErrorKind::UnexpectedToken, "Too many elements".to_string()), (err.kind, err.msg));
+    let err = from_str::<u8>("1 2").unwrap_err();
+    assert_eq!((DeErrorKind::UnexpectedToken, "Value after the root value".to_string(), 0, 4), (err.kind, err.msg, err.line, err.column));
+    let err = from_str::<Vec<u8>>("[1, ").unwrap_err();
+    assert_eq!((DeErrorKind::Parse(ParseErrorKind::UnclosedContainer), 0, 5), (err.kind, err.line, err.column));
+    let err = from_str::<u8>("300").unwrap_err();
+    assert_eq!(("invalid value: integer `300`, expected u8".to_string(), 0, 3), (err.msg, err.line, err.column));
+}
+
+fn to_json<T: Serialize>(value: &T) -> String {
+    let mut consumer = JSONWriteConsumer::new(vec!());
+    to_consumer(value, &mut consumer).unwrap();
+    String::from_utf8(consumer.into_inner()).unwrap()
+}
+
+#[test]
+fn serialize_values() {
+    let drawing = Drawing {
+        name: "d".into(),
+        shapes: vec!(
+            Shape::Empty,
+            Shape::Circle(1.5),
+            Shape::Segment(Point { x: 1, y: 2.0, label: None }, Point { x: -1, y: 2.5, label: Some("b".into()) }),
+            Shape::Rect { w: 2, h: 3 },
+        ),
+        tags: vec!((1, true)).into_iter().collect(),
+        pair: (u64::MAX, i128::MIN),
+    };
+    let json = to_json(&drawing);
+    assert_eq!(r#"{"name":"d","shapes":["Empty",{"Circle":1.5},{"Segment":[{"x":1,"y":2.0,"label":null},{"x":-1,"y":2.5,"label":"b"}]},{"Rect":{"w":2,"h":3}}],"tags":{"1":true},"pair":[18446744073709551615,-170141183460469231731687303715884105728]}"#, json);
+    assert_eq!(Ok(drawing), from_str(&json));
+    assert_eq!("[null,0.1,\"x\",[1,2]]", to_json(&(f64::NAN, 0.1f32, 'x', serde_bytes_like(&[1, 2]))));
+    #[derive(PartialEq, Eq, PartialOrd, Ord, Serialize)]
+    enum Color { Red }
+    let map: BTreeMap<Color, u8> = vec!((Color::Red, 1)).into_iter().collect();
+    assert_eq!(r#"{"Red":1}"#, to_json(&map));
+}
+
+/// A `&[u8]` serialized as bytes.
+fn serde_bytes_like(bytes: &[u8]) -> impl Serialize + '_ {
+    struct Bytes<'a>(&'a [u8]);
+    impl<'a> Serialize for Bytes<'a> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(self.0)
+        }
+    }
+    Bytes(bytes)
+}
+
+#[test]
+fn serialize_tokens() {
+    let mut tokens = VecDeque::new();
+    to_consumer(&Shape::Circle(2.0), &mut tokens).unwrap();
+    assert_eq!(vec!(Ok(BeginFile), Ok(BeginObject), Ok(Key("Circle".into())), Ok(FloatValue("2.0".into())), Ok(EndObject), Ok(EndFile)),
+               tokens.into_iter().collect::<Vec<_>>());
+}
+
+/// A consumer that fails at the first string.
+struct NoStringConsumer;
+
+impl JSONParseConsumer for NoStringConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        match token {
+            Ok(StringValue(s)) => Err(ConsumeError { msg: format!("string {}", s), line: 0, column: 0, io_error: None }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[test]
+fn serialize_errors() {
+    assert_eq!(Err("string a".into()), to_consumer(&vec!(Some(1), None), &mut NoStringConsumer)
+        .and_then(|_| to_consumer(&("a", 1), &mut NoStringConsumer)).map_err(|e| e.msg));
+    let map: BTreeMap<Vec<u8>, u8> = vec!((vec!(1), 1)).into_iter().collect();
+    let err = to_consumer(&map, &mut VecDeque::<Result<ParserToken, JSONParseError>>::new()).unwrap_err();
+    assert_eq!("A key must be a string, a number, a boolean or a unit variant", err.msg);
+}
@@ -0,0 +1,104 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::borrow::Cow;
+use std::fs;
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::{JSONLexError, JSONLexer, LexErrorKind, LexerToken};
+use r_json_event_parser::json_slice_lexer::{JSONSliceLexer, SliceToken};
+
+fn tokens(json: &str) -> Vec<Result<SliceToken<'_>, JSONLexError>> {
+    JSONSliceLexer::new(json.as_bytes()).map(|(token, _)| token).collect()
+}
+
+#[test]
+fn test_borrowed() {
+    let json = r#"{"a": [1, -2.5e3, true, false, null], "b\"c": "d\u00e9\ud83d\ude00"}"#;
+    let tokens = tokens(json);
+    assert_eq!(vec!(
+        Ok(SliceToken::BeginFile),
+        Ok(SliceToken::BeginObject),
+        Ok(SliceToken::String(Cow::Borrowed("a"))),
+        Ok(SliceToken::NameSeparator),
+        Ok(SliceToken::BeginArray),
+        Ok(SliceToken::IntValue("1")),
+        Ok(SliceToken::ValueSeparator),
+        Ok(SliceToken::FloatValue("-2.5e3")),
+        Ok(SliceToken::ValueSeparator),
+        Ok(SliceToken::BooleanValue(true)),
+        Ok(SliceToken::ValueSeparator),
+        Ok(SliceToken::BooleanValue(false)),
+        Ok(SliceToken::ValueSeparator),
+        Ok(SliceToken::NullValue),
+        Ok(SliceToken::EndArray),
+        Ok(SliceToken::ValueSeparator),
+        Ok(SliceToken::String(Cow::Owned("b\"c".into()))),
+        Ok(SliceToken::NameSeparator),
+        Ok(SliceToken::String(Cow::Owned("dé😀".into()))),
+        Ok(SliceToken::EndObject),
+        Ok(SliceToken::EndFile),
+    ), tokens);
+    assert!(matches!(tokens[2], Ok(SliceToken::String(Cow::Borrowed(_)))));
+    assert!(matches!(tokens[16], Ok(SliceToken::String(Cow::Owned(_)))));
+}
+
+#[test]
+fn test_spans() {
+    let json = "[\n  \"é\", 10]";
+    let spans: Vec<(usize, usize, usize, usize)> = JSONSliceLexer::new(json.as_bytes())
+        .map(|(_, span)| (span.start, span.end, span.line, span.column)).collect();
+    assert_eq!(vec!((0, 0, 0, 0), (0, 1, 0, 1), (4, 8, 1, 6), (8, 9, 1, 7), (10, 12, 1, 10), (12, 13, 1, 11), (13, 13, 1, 11)), spans);
+    assert_eq!(&json[4..8], "\"é\"");
+}
+
+#[test]
+fn test_errors() {
+    let error = |kind, msg: &str, column| Err(JSONLexError { kind, msg: msg.into(), line: 0, column });
+    assert_eq!(vec!(Ok(SliceToken::BeginFile), Ok(SliceToken::BeginArray), error(LexErrorKind::LeadingZero, "Leading zero in `01`", 3), Ok(SliceToken::EndFile)),
+               tokens("[01]"));
+    assert_eq!(vec!(Ok(SliceToken::BeginFile), error(LexErrorKind::MissingDecimals, "Missing decimals `1.`", 2), Ok(SliceToken::EndFile)),
+               tokens("1."));
+    assert_eq!(vec!(Ok(SliceToken::BeginFile), error(LexErrorKind::ExpectedWord("true".into()), "Expected word `true`", 1), Ok(SliceToken::EndFile)),
+               tokens("tru"));
+    assert_eq!(vec!(Ok(SliceToken::BeginFile), error(LexErrorKind::UnfinishedString, "Unfinished string", 3), Ok(SliceToken::EndFile)),
+               tokens("\"ab"));
+    assert_eq!(vec!(Ok(SliceToken::BeginFile), error(LexErrorKind::UnknownEscape('x'), "Unknown escaped char `x`", 3), Ok(SliceToken::EndFile)),
+               tokens("\"\\x\""));
+    assert_eq!(vec!(Ok(SliceToken::BeginFile), error(LexErrorKind::LoneSurrogate, "Waiting for low surrogate, got `65`", 13), Ok(SliceToken::EndFile)),
+               tokens("\"\\ud83d\\u0041\""));
+    assert_eq!(vec!(Ok(SliceToken::BeginFile), error(LexErrorKind::UnexpectedChar('/'), "Unexpected char `/`", 1), Ok(SliceToken::EndFile)),
+               tokens("/"));
+}
+
+#[test]
+fn test_same_tokens_as_lexer() {
+    for i in 1..=5 {
+        let json = fs::read(format!("tests/files/example{}.json", i)).expect("no file found");
+        let mut lexer = JSONLexer::new(ByteSource::new(json.as_slice()), false);
+        let mut expected: Vec<LexerToken> = vec!();
+        while let Some((token, _)) = lexer.next_token() {
+            expected.push(token.unwrap());
+        }
+        let tokens: Vec<LexerToken> = JSONSliceLexer::new(&json).map(|(token, _)| token.unwrap().into_owned()).collect();
+        assert_eq!(expected, tokens);
+    }
+}
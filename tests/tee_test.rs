@@ -0,0 +1,113 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::ConsumeError;
+use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use r_json_event_parser::json_report::JSONStatsConsumer;
+use r_json_event_parser::json_tee::{TeeConsumer, TeeErrorPolicy};
+use r_json_event_parser::json_writer::JSONWriteConsumer;
+
+/// Fails on the n-th token.
+struct FailingConsumer {
+    n: usize,
+}
+
+impl JSONParseConsumer for FailingConsumer {
+    fn consume(&mut self, _token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        if self.n == 0 {
+            return Err(ConsumeError { msg: "failed".into(), line: 0, column: 0, io_error: None });
+        }
+        self.n -= 1;
+        Ok(())
+    }
+}
+
+fn parse(json: &str, tee: &mut TeeConsumer) -> Result<(), ConsumeError> {
+    JSONParser::new(ByteSource::new(json.as_bytes()), false).parse(tee)
+}
+
+#[test]
+fn test_tee() {
+    let mut writer = JSONWriteConsumer::new(vec!());
+    let mut stats = JSONStatsConsumer::new();
+    let mut tee = TeeConsumer::new().branch(&mut writer).branch(&mut stats);
+    parse(r#"{"a": [1, 2], "b": {"c": null}}"#, &mut tee).unwrap();
+    assert_eq!(br#"{"a":[1,2],"b":{"c":null}}"#.to_vec(), writer.into_inner());
+    assert_eq!(2, stats.report().max_depth);
+    assert_eq!(2, stats.report().objects);
+}
+
+#[test]
+fn test_stop_all() {
+    let mut writer = JSONWriteConsumer::new(vec!());
+    let mut failing = FailingConsumer { n: 2 };
+    let mut tee = TeeConsumer::new().branch(&mut failing).branch(&mut writer);
+    assert_eq!("failed", parse("[1, 2, 3]", &mut tee).unwrap_err().msg);
+    assert_eq!(b"[".to_vec(), writer.into_inner());
+}
+
+#[test]
+fn test_continue_others() {
+    let mut writer = JSONWriteConsumer::new(vec!());
+    let mut failing = FailingConsumer { n: 2 };
+    let mut tee = TeeConsumer::new().branch(&mut failing).branch(&mut writer).error_policy(TeeErrorPolicy::ContinueOthers);
+    parse("[1, 2, 3]", &mut tee).unwrap();
+    assert_eq!(vec!((0, "failed".to_string())), tee.errors().iter().map(|(i, e)| (*i, e.msg.clone())).collect::<Vec<_>>());
+    assert_eq!(b"[1,2,3]".to_vec(), writer.into_inner());
+}
+
+#[test]
+fn test_every_branch_failed() {
+    let mut failing1 = FailingConsumer { n: 1 };
+    let mut failing2 = FailingConsumer { n: 3 };
+    let mut tee = TeeConsumer::new().branch(&mut failing1).branch(&mut failing2).error_policy(TeeErrorPolicy::ContinueOthers);
+    assert_eq!("Every branch failed: failed", parse("[1, 2, 3]", &mut tee).unwrap_err().msg);
+    assert_eq!(2, tee.errors().len());
+}
+
+/// Done after `n` tokens.
+struct TakeConsumer {
+    count: usize,
+    n: usize,
+}
+
+impl JSONParseConsumer for TakeConsumer {
+    fn consume(&mut self, _token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.count += 1;
+        Ok(())
+    }
+
+    fn is_done(&self) -> bool {
+        self.count >= self.n
+    }
+}
+
+#[test]
+fn test_done_branches() {
+    let mut take2 = TakeConsumer { count: 0, n: 2 };
+    let mut take4 = TakeConsumer { count: 0, n: 4 };
+    let mut tee = TeeConsumer::new().branch(&mut take2).branch(&mut take4);
+    let mut parser = JSONParser::new(ByteSource::new("[1, 2, 3, 4]".as_bytes()), false);
+    parser.parse(&mut tee).unwrap();
+    assert!(parser.stopped());
+    assert_eq!((2, 4), (take2.count, take4.count));
+}
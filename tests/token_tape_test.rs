@@ -0,0 +1,73 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use std::fs;
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::fmt_write::FmtWriter;
+use r_json_event_parser::json2xml::JSON2XMLConsumer;
+use r_json_event_parser::json_parser::JSONParser;
+use r_json_event_parser::token_tape::{TokenTapeReader, TokenTapeWriter};
+
+fn record(path: &str) -> Vec<u8> {
+    let f = fs::File::open(path).expect("no file found");
+    let mut parser = JSONParser::new(ByteSource::new(f), false);
+    let mut writer = TokenTapeWriter::new(vec!());
+    parser.parse(&mut writer).unwrap();
+    writer.into_inner()
+}
+
+#[test]
+fn test_replay_example4() {
+    let tape = record("tests/files/example4.json");
+    assert_eq!(b"RJT1\x00\x02\x06\x07web-app", &tape[..15]);
+    let expected = fs::read_to_string("tests/files/example4.xml").unwrap();
+    for _ in 0..2 {
+        let mut destination = String::new();
+        let mut consumer = JSON2XMLConsumer::new_formatted_and_typed(FmtWriter::new(&mut destination));
+        TokenTapeReader::new(&tape[..]).replay(&mut consumer).unwrap();
+        assert_eq!(expected, destination);
+    }
+}
+
+#[test]
+fn test_interned_keys() {
+    let mut parser = JSONParser::new(ByteSource::new(r#"[{"k": 1}, {"k": 2}]"#.as_bytes()), false);
+    let mut writer = TokenTapeWriter::new(vec!());
+    parser.parse(&mut writer).unwrap();
+    assert_eq!(b"RJT1\x00\x04\x02\x06\x01k\x0c\x011\x03\x02\x07\x00\x0c\x012\x03\x05\x01".to_vec(), writer.into_inner());
+}
+
+#[test]
+fn test_wrong_tape() {
+    let mut destination = String::new();
+    let mut consumer = JSON2XMLConsumer::new(FmtWriter::new(&mut destination));
+    assert_eq!("Not a token tape", TokenTapeReader::new("[1, 2]".as_bytes()).replay(&mut consumer).unwrap_err().msg);
+}
+
+#[test]
+fn test_truncated_tape() {
+    let mut destination = String::new();
+    let mut consumer = JSON2XMLConsumer::new(FmtWriter::new(&mut destination));
+    // a string of 2^63 - 1 bytes
+    let tape = b"RJT1\x00\x0b\xff\xff\xff\xff\xff\xff\xff\xff\x7f\x61";
+    assert_eq!("Truncated tape: expected 9223372036854775807 bytes, got 1", TokenTapeReader::new(&tape[..]).replay(&mut consumer).unwrap_err().msg);
+}
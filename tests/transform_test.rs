@@ -0,0 +1,158 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_lexer::{ConsumeError, LexerOptions};
+use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParseError, JSONParser, ParserToken};
+use r_json_event_parser::json_parser::ParserToken::{BeginArray, BeginFile, BeginObject, EndArray, EndFile, EndObject, IntValue, Key, StringValue};
+use r_json_event_parser::json_pointer::{JSONPointer, PathElement};
+use r_json_event_parser::json_transform::{chain, drop_nulls, filter_paths, map_keys, rename_key, SortKeysConsumer, SubstituteConsumer, TokenTransformer, TransformConsumer, value_tokens};
+use r_json_event_parser::json_writer::JSONWriteConsumer;
+
+struct TokensConsumer {
+    tokens: Vec<ParserToken>,
+}
+
+impl JSONParseConsumer for TokensConsumer {
+    fn consume(&mut self, token: Result<ParserToken, JSONParseError>) -> Result<(), ConsumeError> {
+        self.tokens.push(token.unwrap());
+        Ok(())
+    }
+}
+
+#[test]
+fn test_value_tokens() {
+    assert_eq!(Ok(vec!(BeginArray, IntValue("1".into()), EndArray)), value_tokens("[1]"));
+    assert!(value_tokens("[1").is_err());
+}
+
+#[test]
+fn test_substitute() {
+    let mut consumer = TokensConsumer { tokens: vec!() };
+    let pointers = vec!(JSONPointer::parse("/a/b").unwrap(), JSONPointer::parse("/c/1").unwrap());
+    let mut seen = vec!();
+    let mut substitute = SubstituteConsumer::new(&mut consumer, pointers, |path, tokens| {
+        seen.push((path.to_vec(), tokens.len()));
+        if path.len() == 2 && path[0] == PathElement::Key("a".into()) {
+            vec!(StringValue("x".into()))
+        } else {
+            vec!(BeginObject, EndObject)
+        }
+    });
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a": {"b": [1, [2]], "d": 3}, "c": [4, 5]}"#.as_bytes()), false);
+    parser.parse(&mut substitute).unwrap();
+    assert_eq!(vec!(
+        BeginFile, BeginObject,
+        Key("a".into()), BeginObject, Key("b".into()), StringValue("x".into()), Key("d".into()), IntValue("3".into()), EndObject,
+        Key("c".into()), BeginArray, IntValue("4".into()), BeginObject, EndObject, EndArray,
+        EndObject, EndFile,
+    ), consumer.tokens);
+    assert_eq!(vec!(
+        (vec!(PathElement::Key("a".into()), PathElement::Key("b".into())), 6),
+        (vec!(PathElement::Key("c".into()), PathElement::Index(1)), 1),
+    ), seen);
+}
+
+#[test]
+fn test_substitute_wildcard() {
+    let mut consumer = TokensConsumer { tokens: vec!() };
+    let pointers = vec!(JSONPointer::parse("/*/id").unwrap());
+    let mut substitute = SubstituteConsumer::new(&mut consumer, pointers, |_, _| vec!(IntValue("0".into())));
+    let mut parser = JSONParser::new(ByteSource::new(r#"[{"id": 1}, {"id": 2, "n": 3}]"#.as_bytes()), false);
+    parser.parse(&mut substitute).unwrap();
+    assert_eq!(vec!(
+        BeginFile, BeginArray,
+        BeginObject, Key("id".into()), IntValue("0".into()), EndObject,
+        BeginObject, Key("id".into()), IntValue("0".into()), Key("n".into()), IntValue("3".into()), EndObject,
+        EndArray, EndFile,
+    ), consumer.tokens);
+}
+
+#[test]
+fn test_substitute_chunked_string() {
+    let mut consumer = TokensConsumer { tokens: vec!() };
+    let mut seen = vec!();
+    let mut substitute = SubstituteConsumer::new(&mut consumer, vec!(JSONPointer::parse("/0").unwrap()), |_, tokens| {
+        seen.push(tokens);
+        vec!(ParserToken::NullValue)
+    });
+    let options = LexerOptions { string_chunk_size: Some(4), ..Default::default() };
+    let mut parser = JSONParser::new_with_options(ByteSource::new(r#"["abcdefgh", 1]"#.as_bytes()), options);
+    parser.parse(&mut substitute).unwrap();
+    assert_eq!(vec!(BeginFile, BeginArray, ParserToken::NullValue, IntValue("1".into()), EndArray, EndFile), consumer.tokens);
+    assert_eq!(vec!(vec!(ParserToken::StringStart, ParserToken::StringChunk(b"abcd".to_vec()),
+                         ParserToken::StringChunk(b"efgh".to_vec()), ParserToken::StringEnd)), seen);
+}
+
+fn sort_keys(json: &str) -> String {
+    let mut consumer = JSONWriteConsumer::new(vec!());
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+    parser.parse(&mut SortKeysConsumer::new(&mut consumer)).unwrap();
+    String::from_utf8(consumer.into_inner()).unwrap()
+}
+
+#[test]
+fn test_sort_keys() {
+    assert_eq!(r#"{"a":[{"x":1,"y":2}],"b":{"c":null,"d":"e"}}"#, sort_keys(r#"{"b": {"d": "e", "c": null}, "a": [{"y": 2, "x": 1}]}"#));
+    assert_eq!(r#"[{"a":1,"b":2,"b":3},4]"#, sort_keys(r#"[{"b": 2, "a": 1, "b": 3}, 4]"#));
+}
+
+#[test]
+fn test_sort_keys_comparator() {
+    let mut consumer = JSONWriteConsumer::new(vec!());
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a": 1, "B": 2, "c": {"D": 3, "b": 4}}"#.as_bytes()), false);
+    parser.parse(&mut SortKeysConsumer::with_comparator(&mut consumer, |a, b| b.to_lowercase().cmp(&a.to_lowercase()))).unwrap();
+    assert_eq!(r#"{"c":{"D":3,"b":4},"B":2,"a":1}"#, String::from_utf8(consumer.into_inner()).unwrap());
+}
+
+fn transform<T: TokenTransformer>(json: &str, transformer: T) -> String {
+    let mut writer = JSONWriteConsumer::new(vec!());
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+    parser.parse(&mut TransformConsumer::new(&mut writer, transformer)).unwrap();
+    String::from_utf8(writer.into_inner()).unwrap()
+}
+
+#[test]
+fn test_map_keys() {
+    assert_eq!(r#"{"A":[{"B":1}]}"#, transform(r#"{"a": [{"b": 1}]}"#, map_keys(|k| k.to_uppercase())));
+    assert_eq!(r#"{"x":{"y":{"x":2}}}"#, transform(r#"{"a": {"y": {"a": 2}}}"#, rename_key("a", "x")));
+}
+
+#[test]
+fn test_filter_paths() {
+    let keep = |path: &[PathElement]| path.last() != Some(&PathElement::Key("secret".into())) && path.last() != Some(&PathElement::Index(1));
+    assert_eq!(r#"{"a":[1,3],"b":{"c":true}}"#, transform(r#"{"secret": [0], "a": [1, {"x": 2}, 3], "b": {"secret": {}, "c": true}}"#, filter_paths(keep)));
+    assert_eq!("[]", transform("[]", filter_paths(|_| false)));
+}
+
+#[test]
+fn test_drop_nulls() {
+    assert_eq!(r#"{"b":[1,{}],"c":2}"#, transform(r#"{"a": null, "b": [null, 1, {"d": null}], "c": 2}"#, drop_nulls()));
+    assert_eq!("null", transform("null", drop_nulls()));
+}
+
+#[test]
+fn test_chain() {
+    let pipeline = chain(rename_key("a", "b"), drop_nulls()).then(filter_paths(|path| path.len() < 2));
+    assert_eq!(r#"{"b":{},"c":[]}"#, transform(r#"{"a": {"x": 1}, "n": null, "c": [1, 2]}"#, pipeline));
+    let boxed: Vec<Box<dyn TokenTransformer>> = vec!(Box::new(drop_nulls()));
+    assert_eq!("[1]", transform("[null, 1]", boxed.into_iter().next().unwrap()));
+}
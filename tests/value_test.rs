@@ -0,0 +1,84 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::json_parser::{JSONParseConsumer, JSONParser, ParserToken};
+use r_json_event_parser::json_value::{JsonValue, SubtreeCaptureConsumer, ValueBuilderConsumer};
+
+#[test]
+fn test_build() {
+    let mut consumer = ValueBuilderConsumer::new();
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a": [1, 2.5, "x", true, null], "b": {}, "a": []}"#.as_bytes()), false);
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(Some(JsonValue::Object(vec!(
+        ("a".into(), JsonValue::Array(vec!(
+            JsonValue::Int("1".into()),
+            JsonValue::Float("2.5".into()),
+            JsonValue::String("x".into()),
+            JsonValue::Bool(true),
+            JsonValue::Null,
+        ))),
+        ("b".into(), JsonValue::Object(vec!())),
+        ("a".into(), JsonValue::Array(vec!())),
+    ))), consumer.into_value());
+}
+
+#[test]
+fn test_accessors() {
+    let value = JsonValue::parse(r#"{"a": {"b": [10, 1.5e1, "s", false, null]}}"#).unwrap();
+    let b = value.get("a").and_then(|a| a.get("b")).unwrap();
+    assert_eq!(Some(10), b.at(0).and_then(|v| v.as_i64()));
+    assert_eq!(Some(15.0), b.at(1).and_then(|v| v.as_f64()));
+    assert_eq!(Some("s"), b.at(2).and_then(|v| v.as_str()));
+    assert_eq!(Some(false), b.at(3).and_then(|v| v.as_bool()));
+    assert!(b.at(4).unwrap().is_null());
+    assert_eq!(None, b.at(5));
+    assert_eq!(None, value.get("c"));
+    assert!(JsonValue::parse("[1, ").is_err());
+}
+
+#[test]
+fn test_scalar_and_tokens() {
+    let mut consumer = ValueBuilderConsumer::new();
+    let tokens = vec!(ParserToken::BeginArray, ParserToken::IntValue("1".into()), ParserToken::BeginObject,
+                      ParserToken::Key("k".into()), ParserToken::NullValue, ParserToken::EndObject, ParserToken::EndArray);
+    for token in tokens.iter() {
+        assert_eq!(None, consumer.value());
+        consumer.consume(Ok(token.clone())).unwrap();
+    }
+    assert_eq!(tokens, consumer.value().unwrap().to_tokens());
+    assert_eq!(Ok(JsonValue::String("é".into())), JsonValue::parse(r#""é""#));
+}
+
+#[test]
+fn test_subtree_capture() {
+    let json = r#"{"items": [{"config": 1}, {"config": {"a": [true]}, "other": 2}], "config": 3}"#;
+    let mut document = ValueBuilderConsumer::new();
+    let mut captured = vec!();
+    {
+        let mut consumer = SubtreeCaptureConsumer::new(&mut document, "/items/1/config", |v| captured.push(v)).unwrap();
+        let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+        parser.parse(&mut consumer).unwrap();
+    }
+    assert_eq!(vec!(JsonValue::parse(r#"{"a": [true]}"#).unwrap()), captured);
+    assert_eq!(JsonValue::parse(json).ok(), document.into_value());
+    assert!(SubtreeCaptureConsumer::new(&mut ValueBuilderConsumer::new(), "items", |_| {}).is_err());
+}
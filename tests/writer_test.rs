@@ -0,0 +1,181 @@
+/*
+ * R-JSON Event Parser - a Rust JSON event based parser.
+ *
+ *    Copyright (C) 2021 J. Férard <https://github.com/jferard>
+ *
+ * This file is part of JSON Event Parser.
+ *
+ * R-JSON Event Parser is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * R-JSON Event Parser is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with this program.  If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use r_json_event_parser::byte_source::ByteSource;
+use r_json_event_parser::fmt_write::FmtWriter;
+use r_json_event_parser::json_lexer::{Dialect, JSONLexer, LexerOptions};
+use r_json_event_parser::json_parser::JSONParser;
+use r_json_event_parser::json_pretty::JSONPrettyPrintConsumer;
+use r_json_event_parser::json_writer::{reformat, JSONMinifyConsumer, JSONWriteConsumer, WriteStyle};
+
+fn rewrite(json: &str, indent: Option<usize>) -> String {
+    let mut destination = String::new();
+    let mut consumer = match indent {
+        Some(indent) => JSONWriteConsumer::pretty(FmtWriter::new(&mut destination), indent),
+        None => JSONWriteConsumer::new(FmtWriter::new(&mut destination)),
+    };
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+    parser.parse(&mut consumer).unwrap();
+    destination
+}
+
+#[test]
+fn test_compact() {
+    assert_eq!(r#"{"a":[1,-2.5e3,true,false,null],"b":{},"c":[],"d":{"e":"f"}}"#,
+               rewrite(r#"{ "a": [1, -2.5e3, true, false, null], "b": {}, "c": [ ], "d": {"e": "f"} }"#, None));
+    assert_eq!("\"x\"", rewrite(" \"x\" ", None));
+}
+
+#[test]
+fn test_pretty() {
+    assert_eq!(r#"{
+  "a": [
+    1,
+    [],
+    {
+      "b": null
+    }
+  ],
+  "c": {}
+}
+"#, rewrite(r#"{"a": [1, [], {"b": null}], "c": {}}"#, Some(2)));
+}
+
+#[test]
+fn test_escape() {
+    assert_eq!(r#"["a\"b\\c\n\u001f\t","é€😀"]"#,
+               rewrite(r#"["a\"b\\c\n\u001F\u0009", "é€😀"]"#, None));
+}
+
+#[test]
+fn test_round_trip() {
+    let json = r#"{"a":[1,2.50,{"b":"\"c\""}],"d":true}"#;
+    assert_eq!(json, rewrite(json, None));
+    assert_eq!(json, rewrite(&rewrite(json, Some(4)), None));
+}
+
+#[test]
+fn test_error() {
+    let mut consumer = JSONWriteConsumer::new(vec!());
+    let mut parser = JSONParser::new(ByteSource::new("[1, }".as_bytes()), false);
+    assert!(parser.parse(&mut consumer).is_err());
+    assert_eq!(b"[1".to_vec(), consumer.into_inner());
+}
+
+#[test]
+fn test_minify() {
+    let options = LexerOptions { raw_strings: true, ..Default::default() };
+    let mut consumer = JSONMinifyConsumer::new(vec!());
+    let mut lexer = JSONLexer::new_with_options(ByteSource::new("{\n  \"a\\u0041\" : [ 1.0E+2 ,\t\"\\u00e9\\/\" ]\n}".as_bytes()), options);
+    lexer.lex(&mut consumer).unwrap();
+    assert_eq!(br#"{"a\u0041":[1.0E+2,"\u00e9\/"]}"#.to_vec(), consumer.into_inner());
+}
+
+#[test]
+fn test_minify_error() {
+    let options = LexerOptions { raw_strings: true, ..Default::default() };
+    let mut consumer = JSONMinifyConsumer::new(vec!());
+    let mut lexer = JSONLexer::new_with_options(ByteSource::new(r#"{"a" 1}"#.as_bytes()), options);
+    assert!(lexer.lex(&mut consumer).is_err());
+    assert_eq!(br#"{"a""#.to_vec(), consumer.into_inner());
+}
+
+#[test]
+fn test_pretty_print_consumer() {
+    let mut consumer = JSONPrettyPrintConsumer::new(vec!()).indent("    ").key_value_separator(" : ");
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a": {"b": []}, "c": [true]}"#.as_bytes()), false);
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!("{\n    \"a\" : {\n        \"b\" : []\n    },\n    \"c\" : [\n        true\n    ]\n}\n",
+               String::from_utf8(consumer.into_inner()).unwrap());
+}
+
+#[test]
+fn test_single_line_style() {
+    let style = WriteStyle { indent: String::new(), key_value_separator: ": ".into(), newline: String::new() };
+    let mut consumer = JSONWriteConsumer::with_style(vec!(), style);
+    let mut parser = JSONParser::new(ByteSource::new(r#"{"a":1,"b":[2]}"#.as_bytes()), false);
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(br#"{"a": 1,"b": [2]}"#.to_vec(), consumer.into_inner());
+}
+
+#[test]
+fn test_string_chunks() {
+    let json = r#"{"a long key":["short","a \"long\" string\n","é€😀é€😀"],"k":"v"}"#;
+    let options = LexerOptions { string_chunk_size: Some(5), ..Default::default() };
+    let mut consumer = JSONWriteConsumer::new(vec!());
+    let mut parser = JSONParser::new_with_options(ByteSource::new(json.as_bytes()), options);
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(json, String::from_utf8(consumer.into_inner()).unwrap());
+}
+
+#[test]
+fn test_ascii_only() {
+    let json = r#"{"clé":["é€😀","a\"b\n"]}"#;
+    let expected = r#"{"cl\u00e9":["\u00e9\u20ac\ud83d\ude00","a\"b\n"]}"#;
+    let mut consumer = JSONWriteConsumer::new(vec!()).ascii_only();
+    let mut parser = JSONParser::new(ByteSource::new(json.as_bytes()), false);
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(expected, String::from_utf8(consumer.into_inner()).unwrap());
+
+    let options = LexerOptions { string_chunk_size: Some(5), ..Default::default() };
+    let mut consumer = JSONWriteConsumer::new(vec!()).ascii_only();
+    let mut parser = JSONParser::new_with_options(ByteSource::new(json.as_bytes()), options);
+    parser.parse(&mut consumer).unwrap();
+    assert_eq!(expected, String::from_utf8(consumer.into_inner()).unwrap());
+}
+
+#[test]
+fn test_reformat() {
+    let json = "{\"b\" : [1.0E+2, -0.50, \"\\u00e9\\/\"], \"a\": {}}";
+    let destination = reformat(ByteSource::new(json.as_bytes()), LexerOptions::default(), vec!(), WriteStyle::pretty(2)).unwrap();
+    assert_eq!("{\n  \"b\": [\n    1.0E+2,\n    -0.50,\n    \"\\u00e9\\/\"\n  ],\n  \"a\": {}\n}\n", String::from_utf8(destination).unwrap());
+    let destination = reformat(ByteSource::new(json.as_bytes()), LexerOptions::default(), vec!(), WriteStyle::compact()).unwrap();
+    assert_eq!("{\"b\":[1.0E+2,-0.50,\"\\u00e9\\/\"],\"a\":{}}", String::from_utf8(destination).unwrap());
+}
+
+#[test]
+fn test_reformat_json5() {
+    let options = LexerOptions { dialect: Dialect::Json5, ..Default::default() };
+    let destination = reformat(ByteSource::new("['a\\'b', \"c\"]".as_bytes()), options, vec!(), WriteStyle::compact()).unwrap();
+    assert_eq!("['a\\'b',\"c\"]", String::from_utf8(destination).unwrap());
+}
+
+#[test]
+fn test_reformat_error() {
+    let result = reformat(ByteSource::new("[1, }".as_bytes()), LexerOptions::default(), vec!(), WriteStyle::compact());
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_reformat_minus_zero() {
+    let destination = reformat(ByteSource::new("[-0, 0, -0.0, -0e1]".as_bytes()), LexerOptions::default(), vec!(), WriteStyle::compact()).unwrap();
+    assert_eq!("[-0,0,-0.0,-0e1]", String::from_utf8(destination).unwrap());
+}
+
+#[test]
+fn test_reformat_comments() {
+    let json = "// settings\r\n{\"a\": 1/* one **/, // two\n\"b\": [ ], \"c\": // three\n3 // end\n}";
+    let options = || LexerOptions { allow_comments: true, ..Default::default() };
+    let destination = reformat(ByteSource::new(json.as_bytes()), options(), vec!(), WriteStyle::compact()).unwrap();
+    assert_eq!("// settings\n{\"a\":1/* one **/,// two\n\"b\":[],\"c\":// three\n3// end\n}", String::from_utf8(destination).unwrap());
+    let destination = reformat(ByteSource::new(json.as_bytes()), options(), vec!(), WriteStyle::pretty(2)).unwrap();
+    assert_eq!("// settings\n{\n  \"a\": 1 /* one **/, // two\n  \"b\": [],\n  \"c\": // three\n  3 // end\n}\n", String::from_utf8(destination).unwrap());
+}